@@ -1,34 +1,763 @@
-use config::{Config, ConfigError, Environment, File};
+use crate::evaluator::Limits;
+use config::{Config, ConfigError, Environment, File, FileFormat, FileSourceFile};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
 use std::path::Path;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+pub mod secrets;
+
+pub const DEFAULT_CONFIG_FILE: &str = "config.toml";
+
+const REDACTED: &str = "***";
+const SECRET_KEY_MARKERS: [&str; 5] = ["key", "secret", "password", "token", "url"];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct AppConfig {
+    #[serde(default)]
+    pub profile: Profile,
     pub http_server: HttpServer,
+    #[serde(default)]
+    pub auth: Auth,
+    #[serde(default)]
+    pub tls: Option<Tls>,
+    #[serde(default)]
+    pub logging: Logging,
+    #[serde(default)]
+    pub sessions: Sessions,
+    #[serde(default)]
+    pub audit: Audit,
+    #[serde(default)]
+    pub quotas: Quotas,
+    #[serde(default)]
+    pub evaluator: Evaluator,
+    #[serde(default)]
+    pub privacy: Privacy,
+    #[serde(default)]
+    pub shadow: Shadow,
+    #[serde(default)]
+    pub idempotency: Idempotency,
+    #[serde(default)]
+    pub documents: Documents,
+    #[serde(default)]
+    pub tenants: Tenants,
+    #[serde(default)]
+    pub warmup: Warmup,
+    #[serde(default)]
+    pub chaos: Chaos,
+    #[serde(default)]
+    pub result_cache: ResultCache,
+    #[serde(default)]
+    pub queue: Queue,
+    #[serde(default)]
+    pub workers: Workers,
+}
+
+/// Presets applied on top of the other settings.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Profile {
+    #[default]
+    Standard,
+    /// A public playground: small expressions, low precision, heavy
+    /// throttling and no sessions. Settings already stricter are kept.
+    Demo,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct HttpServer {
     pub port: u16,
+    /// Requests accepted per second across all clients
+    pub rate_limit_per_sec: u64,
+    pub max_body_bytes: usize,
+}
+
+impl Default for HttpServer {
+    fn default() -> Self {
+        HttpServer {
+            port: 8080,
+            rate_limit_per_sec: 100,
+            max_body_bytes: 4 * 1024 * 1024,
+        }
+    }
+}
+
+/// Log filtering used when `RUST_LOG` is not set. `filter` takes full
+/// `EnvFilter` directives and wins over the plain `level`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Logging {
+    pub level: String,
+    pub filter: Option<String>,
+    /// Also record the raw expression on request spans. Off by default, since
+    /// expressions may carry personal data; their length and shape always are.
+    #[serde(default)]
+    pub record_expressions: bool,
+}
+
+impl Default for Logging {
+    fn default() -> Self {
+        Logging {
+            level: "info".to_string(),
+            filter: None,
+            record_expressions: false,
+        }
+    }
+}
+
+/// Resource caps applied to every evaluation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Evaluator {
+    /// Largest number of significant digits an intermediate or final result may have
+    pub max_result_digits: u64,
+    /// Largest magnitude of a result's decimal exponent (digits after or zeros before the point)
+    pub max_scale: i64,
+    /// Significant digits of inexact results; intermediates carry a few guard digits more
+    pub precision: u64,
+    /// Deepest nesting of parentheses, LaTeX groups or subexpressions
+    pub max_depth: usize,
+    /// Largest estimated evaluation cost accepted before any arithmetic runs
+    pub max_cost: u64,
+    /// Largest number of rows of a matrix accepted by the `matrix` tool
+    pub max_matrix_size: usize,
+    /// Wall-clock budget of one request's evaluation in milliseconds, 0 for none
+    pub max_eval_ms: u64,
+    /// Smallest estimated cost of a subexpression evaluated in parallel with
+    /// its siblings, 0 to never evaluate in parallel
+    pub parallel_min_cost: u64,
+    /// Memory the values of one request's evaluation may take in MiB, 0 for no limit
+    pub max_memory_mb: u64,
+}
+
+impl Evaluator {
+    /// The evaluator limits these settings describe.
+    pub fn limits(&self) -> Limits {
+        Limits {
+            max_digits: self.max_result_digits,
+            max_scale: self.max_scale,
+            precision: self.precision.max(1),
+            max_depth: self.max_depth,
+            max_cost: self.max_cost,
+            max_matrix_size: self.max_matrix_size,
+            max_eval_ms: self.max_eval_ms,
+            parallel_min_cost: self.parallel_min_cost,
+            max_memory_bytes: self.max_memory_mb.saturating_mul(1024 * 1024),
+        }
+    }
+}
+
+impl Default for Evaluator {
+    fn default() -> Self {
+        Evaluator {
+            max_result_digits: 100_000,
+            max_scale: 100_000,
+            precision: 100,
+            max_depth: 256,
+            max_cost: 10_000_000,
+            max_matrix_size: 10,
+            max_eval_ms: 10_000,
+            parallel_min_cost: 100_000,
+            max_memory_mb: 256,
+        }
+    }
+}
+
+/// Limits and storage for stateful sessions (MCP sessions and the REPL).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Sessions {
+    /// Whether MCP clients get a session on `initialize`; without one, every
+    /// call starts from an empty scope
+    pub enabled: bool,
+    pub backend: SessionBackendKind,
+    /// Used by the `redis` backend (`redis-sessions` feature); supports `${ENV_VAR}`
+    pub redis_url: String,
+    /// Used by the `sqlite` backend (`sqlite-sessions` feature)
+    pub sqlite_path: String,
+    pub max_sessions: usize,
+    pub max_variables: usize,
+    /// Largest number of significant digits a stored value may have
+    pub max_value_digits: u64,
+    /// Idle time after which a session is dropped
+    pub ttl_secs: u64,
+    pub sweep_interval_secs: u64,
+    /// Evaluations kept per session; older entries are dropped
+    pub max_history: usize,
+    pub max_saved_expressions: usize,
+}
+
+impl Default for Sessions {
+    fn default() -> Self {
+        Sessions {
+            enabled: true,
+            backend: SessionBackendKind::Memory,
+            redis_url: "redis://127.0.0.1/".to_string(),
+            sqlite_path: "sessions.db".to_string(),
+            max_sessions: 10_000,
+            max_variables: 100,
+            max_value_digits: 10_000,
+            ttl_secs: 3600,
+            sweep_interval_secs: 60,
+            max_history: 1000,
+            max_saved_expressions: 100,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionBackendKind {
+    #[default]
+    Memory,
+    Redis,
+    Sqlite,
+}
+
+/// Append-only record of every `tools/call`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Audit {
+    pub enabled: bool,
+    pub backend: AuditBackend,
+    pub path: String,
+}
+
+impl Default for Audit {
+    fn default() -> Self {
+        Audit {
+            enabled: false,
+            backend: AuditBackend::Jsonl,
+            path: "audit.jsonl".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditBackend {
+    Jsonl,
+    /// Requires the `sqlite-audit` feature
+    Sqlite,
+}
+
+/// How raw expressions appear in request spans and audit records, for
+/// deployments whose inputs may hold sensitive figures.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Privacy {
+    pub expressions: ExpressionRedaction,
+    /// Characters kept by `truncate`
+    pub truncate_to: usize,
+    /// Secret keying the HMAC of `hash` and of audited results; supports
+    /// `${ENV_VAR}`
+    pub hash_key: Option<String>,
+    /// File holding `hash_key` instead
+    pub hash_key_file: Option<String>,
+}
+
+impl Default for Privacy {
+    fn default() -> Self {
+        Privacy {
+            expressions: ExpressionRedaction::Plain,
+            truncate_to: 16,
+            hash_key: None,
+            hash_key_file: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExpressionRedaction {
+    /// Record expressions as given
+    #[default]
+    Plain,
+    /// Record an HMAC-SHA256 under `hash_key`, so repeated inputs can still
+    /// be matched but short ones cannot be guessed from their digest
+    Hash,
+    /// Record the first `truncate_to` characters and the total length
+    Truncate,
+}
+
+/// Dry-run mode for evaluator upgrades: `evaluate` also runs `candidate` and
+/// logs results that differ, without changing responses.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Shadow {
+    pub enabled: bool,
+    pub candidate: ShadowCandidate,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ShadowCandidate {
+    /// Tree-walking evaluation of the parsed expression
+    #[default]
+    Ast,
+}
+
+/// Replay of `tools/call` results for submissions repeated with the same
+/// `Idempotency-Key` within `window_secs`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Idempotency {
+    pub enabled: bool,
+    pub window_secs: u64,
+    /// Most results kept; the oldest is dropped first
+    pub max_entries: usize,
+}
+
+impl Default for Idempotency {
+    fn default() -> Self {
+        Idempotency {
+            enabled: true,
+            window_secs: 300,
+            max_entries: 10_000,
+        }
+    }
+}
+
+/// Parse sessions opened through `/documents` by editor integrations.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Documents {
+    pub max_documents: usize,
+    /// Idle time after which a document is closed
+    pub ttl_secs: u64,
+}
+
+impl Default for Documents {
+    fn default() -> Self {
+        Documents {
+            max_documents: 1000,
+            ttl_secs: 3600,
+        }
+    }
+}
+
+/// Self-test run before the server accepts traffic. Every canary must
+/// evaluate to its expected value and every constant must resolve, or the
+/// server refuses to start. Also fills lazily built tables ahead of the first
+/// request.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Warmup {
+    pub enabled: bool,
+    pub canaries: Vec<Canary>,
+    /// Built-in constants looked up by name
+    pub constants: Vec<String>,
+}
+
+impl Default for Warmup {
+    fn default() -> Self {
+        let canary = |expression: &str, expected: &str| Canary {
+            expression: expression.to_string(),
+            expected: expected.to_string(),
+        };
+        Warmup {
+            enabled: true,
+            canaries: vec![
+                canary("1 + 2 * 3", "7"),
+                canary("sqrt(16) + 2 ^ 10", "1028"),
+                canary("x = 1/4; x * 8", "2"),
+            ],
+            constants: ["pi", "e", "c"].map(str::to_string).to_vec(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Canary {
+    pub expression: String,
+    /// Expected value, itself evaluated, so `1/3` compares as a number
+    pub expected: String,
+}
+
+/// Fault injection for testing clients' retry handling: a share of requests
+/// is delayed and a share answered with an error instead of reaching the
+/// handlers. Never enable it for real traffic.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Chaos {
+    pub enabled: bool,
+    /// Share of requests delayed by `latency_ms`, from 0 to 1
+    pub latency_rate: f64,
+    pub latency_ms: u64,
+    /// Share of requests answered with `error_status`, from 0 to 1
+    pub error_rate: f64,
+    pub error_status: u16,
+    /// Paths never touched, so probes keep working
+    pub exempt_paths: Vec<String>,
+    /// Makes the sequence of faults reproducible
+    pub seed: Option<u64>,
+}
+
+impl Default for Chaos {
+    fn default() -> Self {
+        Chaos {
+            enabled: false,
+            latency_rate: 0.0,
+            latency_ms: 500,
+            error_rate: 0.0,
+            error_status: 503,
+            exempt_paths: ["/health", "/ready", "/metrics"]
+                .map(str::to_string)
+                .to_vec(),
+            seed: None,
+        }
+    }
+}
+
+/// Results of expensive evaluations kept on disk across restarts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResultCache {
+    pub enabled: bool,
+    pub backend: ResultCacheBackend,
+    pub path: String,
+    /// Smallest estimated evaluation cost worth caching
+    pub min_cost: u64,
+    /// Oldest results are dropped beyond this many
+    pub max_entries: usize,
+}
+
+impl Default for ResultCache {
+    fn default() -> Self {
+        ResultCache {
+            enabled: false,
+            backend: ResultCacheBackend::Sqlite,
+            path: "result-cache.db".to_string(),
+            min_cost: 1_000,
+            max_entries: 100_000,
+        }
+    }
+}
+
+/// Admission of HTTP evaluations: at most `workers` run at once and up to
+/// `capacity` more wait, interactive MCP calls ahead of batch work such as
+/// `/compare` and `/evaluate/stream`. When the queue is full the oldest
+/// waiting request of the lowest class is shed with `503`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Queue {
+    pub enabled: bool,
+    /// Concurrent evaluations, 0 for one per CPU
+    pub workers: usize,
+    /// Requests waiting for a worker
+    pub capacity: usize,
+}
+
+impl Default for Queue {
+    fn default() -> Self {
+        Queue {
+            enabled: true,
+            workers: 0,
+            capacity: 256,
+        }
+    }
+}
+
+/// Evaluation in child processes: tool calls outside a session are sent to
+/// a pool of `processes` copies of this binary, so a crash or a runaway
+/// computation takes down a worker rather than the server. A worker still
+/// busy `grace_ms` past `evaluator.max_eval_ms` is killed and replaced.
+/// On Linux the kernel can also enforce hard limits on each worker.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Workers {
+    pub enabled: bool,
+    pub processes: usize,
+    pub grace_ms: u64,
+    /// CPU seconds one call may use before its worker is killed, 0 for none
+    pub cpu_secs: u64,
+    /// Address space of a worker in MiB, 0 for no limit
+    pub memory_mb: u64,
+    /// A cgroup v2 directory workers join, with the operator's controls
+    pub cgroup: Option<String>,
+}
+
+impl Default for Workers {
+    fn default() -> Self {
+        Workers {
+            enabled: false,
+            processes: 2,
+            grace_ms: 1_000,
+            cpu_secs: 0,
+            memory_mb: 0,
+            cgroup: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResultCacheBackend {
+    /// Requires the `sqlite-cache` feature
+    Sqlite,
+}
+
+/// Teams sharing one deployment. A request belongs to the tenant its API key
+/// is assigned in `keys` (by key fingerprint as reported by `/usage`), else to
+/// the one named in `header`, else to `default`. Sessions, quotas, constants
+/// and metrics are kept per tenant.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Tenants {
+    pub enabled: bool,
+    pub header: String,
+    pub keys: HashMap<String, String>,
+    /// Where per-tenant settings are read from
+    pub store: TenantStoreKind,
+    /// Settings by tenant, for the `config` store
+    pub settings: HashMap<String, TenantSettings>,
+    /// JSON file of settings by tenant, for the `file` store
+    pub path: String,
+    /// Used by the `sqlite` store (`sqlite-tenants` feature)
+    pub sqlite_path: String,
+    /// Endpoint answering GET with settings by tenant, for the `http` store;
+    /// supports `${ENV_VAR}`
+    pub url: String,
+    /// How often settings are read again from the store
+    pub refresh_secs: u64,
+}
+
+impl Default for Tenants {
+    fn default() -> Self {
+        Tenants {
+            enabled: false,
+            header: "x-tenant-id".to_string(),
+            keys: HashMap::new(),
+            store: TenantStoreKind::Config,
+            settings: HashMap::new(),
+            path: "tenants.json".to_string(),
+            sqlite_path: "tenants.db".to_string(),
+            url: String::new(),
+            refresh_secs: 60,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TenantStoreKind {
+    /// `tenants.settings` of this configuration
+    #[default]
+    Config,
+    File,
+    Sqlite,
+    Http,
+}
+
+/// What one tenant may do and starts with.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TenantSettings {
+    /// Variables bound for every evaluation; values may be expressions such as `1/3`
+    pub constants: BTreeMap<String, String>,
+    /// Functions the tenant's expressions may call; all of them when unset
+    pub functions: Option<Vec<String>>,
+    /// Replaces the default quota for the tenant
+    pub quotas: Option<QuotaLimits>,
+}
+
+/// Evaluation quotas per API key, or per tenant when tenants are enabled,
+/// counted per UTC day and calendar month. `overrides` is keyed by the caller
+/// as reported by `/usage`: a key fingerprint or `tenant:<name>`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Quotas {
+    pub enabled: bool,
+    pub default: QuotaLimits,
+    pub overrides: HashMap<String, QuotaLimits>,
+}
+
+/// Unset limits are unlimited.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuotaLimits {
+    pub daily: Option<u64>,
+    pub monthly: Option<u64>,
+}
+
+/// API keys accepted by the server. Keys may use `${ENV_VAR}` interpolation,
+/// and `api_keys_file` may point at a file with one key per line.
+/// Authentication is disabled while no keys are configured.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Auth {
+    #[serde(default)]
+    pub api_keys: Vec<String>,
+    pub api_keys_file: Option<String>,
+}
+
+/// PEM certificate chain and private key, given inline or via `*_file` paths.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tls {
+    pub cert: Option<String>,
+    pub cert_file: Option<String>,
+    pub key: Option<String>,
+    pub key_file: Option<String>,
 }
 
 impl AppConfig {
     /// Load configuration from a specific TOML file path.
     /// Environment variables take priority over the file.
     pub fn new_from_file(file_path: &str) -> Result<Self, ConfigError> {
-        let config = Config::builder()
-            .add_source(File::from(Path::new(file_path)))
-            .add_source(
-                Environment::with_prefix("APP")
-                    .separator("__")
-                    .try_parsing(true),
-            )
-            .build()?;
+        Self::load(Some(file_path), &[])
+    }
+
+    /// Resolve the effective configuration with precedence
+    /// defaults < file < environment (`APP__*`) < `overrides`.
+    ///
+    /// When `file_path` is `None`, [`DEFAULT_CONFIG_FILE`] is used if present;
+    /// an explicitly given file must exist.
+    pub fn load(
+        file_path: Option<&str>,
+        overrides: &[(String, String)],
+    ) -> Result<Self, ConfigError> {
+        let file = match file_path {
+            Some(path) => File::from(Path::new(path)),
+            None => File::from(Path::new(DEFAULT_CONFIG_FILE)).required(false),
+        };
+        Self::build(Some(file), overrides)
+    }
+
+    /// Like [`AppConfig::load`] but never reads a file, so the environment and
+    /// `overrides` are the only inputs on top of the defaults.
+    pub fn load_env_only(overrides: &[(String, String)]) -> Result<Self, ConfigError> {
+        Self::build(None, overrides)
+    }
+
+    fn build(
+        file: Option<File<FileSourceFile, FileFormat>>,
+        overrides: &[(String, String)],
+    ) -> Result<Self, ConfigError> {
+        let mut builder = Config::builder().add_source(Config::try_from(&AppConfig::default())?);
+        if let Some(file) = file {
+            builder = builder.add_source(file);
+        }
+        builder = builder.add_source(
+            Environment::with_prefix("APP")
+                .separator("__")
+                .try_parsing(true),
+        );
+        for (key, value) in overrides {
+            builder = builder.set_override(key.as_str(), value.as_str())?;
+        }
+
+        let mut app_config: AppConfig = builder.build()?.try_deserialize()?;
+        app_config.apply_profile();
+        app_config
+            .resolve_secrets()
+            .map_err(|err| ConfigError::Message(format!("{err:#}")))?;
+        Ok(app_config)
+    }
+
+    /// Tighten the settings the profile caps.
+    fn apply_profile(&mut self) {
+        if self.profile != Profile::Demo {
+            return;
+        }
+        let evaluator = &mut self.evaluator;
+        evaluator.max_result_digits = evaluator.max_result_digits.min(1_000);
+        evaluator.max_scale = evaluator.max_scale.min(1_000);
+        evaluator.precision = evaluator.precision.min(20);
+        evaluator.max_depth = evaluator.max_depth.min(16);
+        evaluator.max_cost = evaluator.max_cost.min(10_000);
+        evaluator.max_matrix_size = evaluator.max_matrix_size.min(4);
+        evaluator.max_eval_ms = match evaluator.max_eval_ms {
+            0 => 1_000,
+            ms => ms.min(1_000),
+        };
+        evaluator.max_memory_mb = match evaluator.max_memory_mb {
+            0 => 16,
+            mb => mb.min(16),
+        };
+        let http = &mut self.http_server;
+        http.rate_limit_per_sec = http.rate_limit_per_sec.min(5);
+        http.max_body_bytes = http.max_body_bytes.min(4 * 1024);
+        self.sessions.enabled = false;
+        self.documents.max_documents = 0;
+        self.chaos.enabled = false;
+        self.result_cache.enabled = false;
+        self.queue.enabled = true;
+        self.queue.workers = match self.queue.workers {
+            0 => 2,
+            workers => workers.min(2),
+        };
+        self.queue.capacity = self.queue.capacity.min(16);
+    }
+
+    /// Replace `${ENV_VAR}` references and `*_file` indirections with the secret values.
+    fn resolve_secrets(&mut self) -> anyhow::Result<()> {
+        let mut api_keys = self
+            .auth
+            .api_keys
+            .iter()
+            .map(|key| secrets::interpolate_env(key))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        if let Some(path) = &self.auth.api_keys_file {
+            let content = secrets::read_secret_file(path)?;
+            api_keys.extend(
+                content
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                    .map(str::to_string),
+            );
+        }
+        self.auth.api_keys = api_keys;
+        self.sessions.redis_url = secrets::interpolate_env(&self.sessions.redis_url)?;
+        self.tenants.url = secrets::interpolate_env(&self.tenants.url)?;
+        self.privacy.hash_key = secrets::resolve(
+            "hash_key",
+            self.privacy.hash_key.as_deref(),
+            self.privacy.hash_key_file.as_deref(),
+        )?;
+        self.privacy.hash_key_file = None;
+        if self.privacy.expressions == ExpressionRedaction::Hash && self.privacy.hash_key.is_none()
+        {
+            anyhow::bail!("privacy.expressions = \"hash\" requires privacy.hash_key");
+        }
+
+        if let Some(tls) = &mut self.tls {
+            tls.cert = secrets::resolve("cert", tls.cert.as_deref(), tls.cert_file.as_deref())?;
+            tls.key = secrets::resolve("key", tls.key.as_deref(), tls.key_file.as_deref())?;
+            tls.cert_file = None;
+            tls.key_file = None;
+            if tls.cert.is_none() || tls.key.is_none() {
+                anyhow::bail!("TLS requires both a certificate and a private key");
+            }
+        }
+
+        Ok(())
+    }
 
-        config.try_deserialize()
+    /// The configuration as JSON with every secret-looking value masked,
+    /// safe to print or serve over HTTP.
+    pub fn redacted(&self) -> Value {
+        let mut value = serde_json::to_value(self).unwrap_or(Value::Null);
+        redact(&mut value);
+        value
     }
 }
 
+fn redact(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, entry) in map.iter_mut() {
+                let key = key.to_ascii_lowercase();
+                if SECRET_KEY_MARKERS.iter().any(|marker| key.contains(marker)) && !entry.is_null()
+                {
+                    *entry = Value::String(REDACTED.to_string());
+                } else {
+                    redact(entry);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -101,4 +830,96 @@ mod tests {
             .expect("Failed to load config from config.toml");
         assert_eq!(config2.http_server.port, 5000);
     }
+
+    #[test]
+    fn test_explicit_missing_file_is_error() {
+        assert!(AppConfig::load(Some("does-not-exist.toml"), &[]).is_err());
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_override_beats_env() {
+        let _guard = EnvGuard::new("APP__HTTP_SERVER__PORT", "9090");
+        let overrides = vec![("http_server.port".to_string(), "7070".to_string())];
+
+        let config = AppConfig::load(Some("config.toml"), &overrides)
+            .expect("Failed to load config with overrides");
+
+        assert_eq!(config.http_server.port, 7070);
+    }
+
+    #[test]
+    fn test_redact_masks_secret_keys() {
+        let mut value = serde_json::json!({
+            "auth": { "api_keys": ["abc"], "header": "x-api-key" },
+            "port": 80
+        });
+        redact(&mut value);
+
+        assert_eq!(value["auth"]["api_keys"], "***");
+        assert_eq!(value["auth"]["header"], "x-api-key");
+        assert_eq!(value["port"], 80);
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_api_keys_from_env_and_file() {
+        let _guard = EnvGuard::new("CALC_TEST_API_KEY", "from-env");
+        let path = std::env::temp_dir().join("calculator-mcp-api-keys-test");
+        std::fs::write(&path, "# comment\nfrom-file\n\n").unwrap();
+        let overrides = vec![
+            (
+                "auth.api_keys[0]".to_string(),
+                "${CALC_TEST_API_KEY}".to_string(),
+            ),
+            (
+                "auth.api_keys_file".to_string(),
+                path.to_string_lossy().to_string(),
+            ),
+        ];
+
+        let config =
+            AppConfig::load(Some("config.toml"), &overrides).expect("Failed to load config");
+
+        assert_eq!(config.auth.api_keys, vec!["from-env", "from-file"]);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_demo_profile_tightens_limits() {
+        let overrides = vec![
+            ("profile".to_string(), "demo".to_string()),
+            ("evaluator.max_depth".to_string(), "8".to_string()),
+            ("chaos.enabled".to_string(), "true".to_string()),
+            ("result_cache.enabled".to_string(), "true".to_string()),
+        ];
+        let config = AppConfig::load_env_only(&overrides).expect("Failed to load config");
+
+        assert_eq!(config.evaluator.precision, 20);
+        // Stricter settings survive the profile
+        assert_eq!(config.evaluator.max_depth, 8);
+        assert_eq!(config.http_server.rate_limit_per_sec, 5);
+        assert!(!config.sessions.enabled);
+        assert!(!config.chaos.enabled);
+        assert!(!config.result_cache.enabled);
+        assert_eq!(config.evaluator.max_eval_ms, 1_000);
+        assert_eq!(config.evaluator.max_memory_mb, 16);
+        assert!(config.queue.enabled);
+        assert_eq!((config.queue.workers, config.queue.capacity), (2, 16));
+
+        let standard = AppConfig::load_env_only(&[]).expect("Failed to load config");
+        assert_eq!(standard.profile, Profile::Standard);
+        assert!(standard.sessions.enabled);
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_env_only_ignores_file() {
+        let _guard = EnvGuard::new("APP__LOGGING__LEVEL", "warn");
+
+        let config = AppConfig::load_env_only(&[]).expect("Failed to load env-only config");
+
+        assert_eq!(config.logging.level, "warn");
+        assert_eq!(config.http_server.port, HttpServer::default().port);
+    }
 }
@@ -0,0 +1,97 @@
+use anyhow::{Context, anyhow, bail};
+use std::fs;
+
+/// Expand `${VAR}` references using the process environment.
+/// `$$` yields a literal `$`; referencing an unset variable is an error.
+pub fn interpolate_env(raw: &str) -> anyhow::Result<String> {
+    let mut out = String::with_capacity(raw.len());
+    let mut chars = raw.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            out.push(c);
+            continue;
+        }
+        match chars.peek() {
+            Some('$') => {
+                chars.next();
+                out.push('$');
+            }
+            Some('{') => {
+                chars.next();
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(ch) => name.push(ch),
+                        None => bail!("Unterminated `${{` in configuration value"),
+                    }
+                }
+                let value = std::env::var(&name)
+                    .map_err(|_| anyhow!("Environment variable `{}` is not set", name))?;
+                out.push_str(&value);
+            }
+            _ => out.push('$'),
+        }
+    }
+
+    Ok(out)
+}
+
+/// Resolve a secret given either inline (with `${VAR}` interpolation) or as a file path.
+/// Setting both is rejected so it is always clear which one is in effect.
+pub fn resolve(
+    name: &str,
+    inline: Option<&str>,
+    file: Option<&str>,
+) -> anyhow::Result<Option<String>> {
+    match (inline, file) {
+        (Some(_), Some(_)) => bail!("Both `{name}` and `{name}_file` are set; use only one"),
+        (Some(value), None) => interpolate_env(value).map(Some),
+        (None, Some(path)) => read_secret_file(path).map(Some),
+        (None, None) => Ok(None),
+    }
+}
+
+pub fn read_secret_file(path: &str) -> anyhow::Result<String> {
+    let path = interpolate_env(path)?;
+    let content = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read secret file `{path}`"))?;
+    Ok(content.trim_end_matches(['\r', '\n']).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[serial_test::serial]
+    fn test_interpolate_env() {
+        unsafe {
+            std::env::set_var("CALC_TEST_SECRET", "s3cr3t");
+        }
+        assert_eq!(
+            interpolate_env("key-${CALC_TEST_SECRET}").unwrap(),
+            "key-s3cr3t"
+        );
+        assert_eq!(interpolate_env("cost $$5").unwrap(), "cost $5");
+        assert_eq!(interpolate_env("plain").unwrap(), "plain");
+        assert!(interpolate_env("${CALC_TEST_UNSET_VARIABLE}").is_err());
+        assert!(interpolate_env("${CALC_TEST_SECRET").is_err());
+        unsafe {
+            std::env::remove_var("CALC_TEST_SECRET");
+        }
+    }
+
+    #[test]
+    fn test_resolve_from_file() {
+        let path = std::env::temp_dir().join("calculator-mcp-secret-test");
+        fs::write(&path, "from-file\n").unwrap();
+
+        let resolved = resolve("key", None, path.to_str()).unwrap();
+        assert_eq!(resolved.as_deref(), Some("from-file"));
+        assert!(resolve("key", Some("inline"), path.to_str()).is_err());
+
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,197 @@
+use crate::app_config::{Audit, AuditBackend, ExpressionRedaction, Privacy};
+use anyhow::Context;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::sync::Mutex;
+
+#[cfg(feature = "sqlite-audit")]
+pub mod sqlite;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
+    Ok,
+    Error,
+}
+
+/// One `tools/call`, as written to the audit log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub timestamp_ms: u64,
+    /// API key fingerprint, or `anonymous` when authentication is off
+    pub caller: String,
+    pub session_id: Option<String>,
+    pub tool: String,
+    pub expression: String,
+    /// HMAC-SHA256 of the structured result under `privacy.hash_key`, so
+    /// results can be matched without storing them; absent without a key
+    pub result_hash: Option<String>,
+    pub outcome: Outcome,
+    pub error: Option<String>,
+}
+
+pub trait AuditSink: Send + Sync {
+    fn record(&self, record: &AuditRecord) -> anyhow::Result<()>;
+}
+
+/// Append-only JSON Lines file; each record is flushed before the call returns.
+pub struct JsonlAuditSink {
+    file: Mutex<File>,
+}
+
+impl JsonlAuditSink {
+    pub fn open(path: &str) -> anyhow::Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open audit log `{path}`"))?;
+        Ok(JsonlAuditSink {
+            file: Mutex::new(file),
+        })
+    }
+}
+
+impl AuditSink for JsonlAuditSink {
+    fn record(&self, record: &AuditRecord) -> anyhow::Result<()> {
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+        let mut file = self
+            .file
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        file.write_all(&line)?;
+        file.flush()?;
+        Ok(())
+    }
+}
+
+pub fn from_config(config: &Audit) -> anyhow::Result<Option<Box<dyn AuditSink>>> {
+    if !config.enabled {
+        return Ok(None);
+    }
+    let sink: Box<dyn AuditSink> = match config.backend {
+        AuditBackend::Jsonl => Box::new(JsonlAuditSink::open(&config.path)?),
+        #[cfg(feature = "sqlite-audit")]
+        AuditBackend::Sqlite => Box::new(sqlite::SqliteAuditSink::open(&config.path)?),
+        #[allow(unreachable_patterns)]
+        other => anyhow::bail!(
+            "Audit backend `{:?}` is not compiled in; enable its cargo feature",
+            other
+        ),
+    };
+    Ok(Some(sink))
+}
+
+pub fn sha256_hex(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+/// HMAC-SHA256 of `data` under `key`, hex-encoded. Unlike a plain digest,
+/// it cannot be matched against guessed inputs without the key.
+pub fn hmac_sha256_hex(key: &str, data: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(key.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    format!("{:x}", mac.finalize().into_bytes())
+}
+
+/// An expression as it may be written to logs and audit records under `privacy`.
+pub fn redact_expression(privacy: &Privacy, expression: &str) -> String {
+    match privacy.expressions {
+        ExpressionRedaction::Plain => expression.to_string(),
+        // Configuration loading insists on a key for `hash`
+        ExpressionRedaction::Hash => match &privacy.hash_key {
+            Some(key) => format!(
+                "hmac-sha256:{}",
+                hmac_sha256_hex(key, expression.as_bytes())
+            ),
+            None => format!("[redacted] ({} chars)", expression.chars().count()),
+        },
+        ExpressionRedaction::Truncate => {
+            let length = expression.chars().count();
+            if length <= privacy.truncate_to {
+                return expression.to_string();
+            }
+            let kept: String = expression.chars().take(privacy.truncate_to).collect();
+            format!("{kept}… ({length} chars)")
+        }
+    }
+}
+
+/// The `result_hash` of an audit record for `result`, when `privacy` has a key.
+pub fn result_hash(privacy: &Privacy, result: &serde_json::Value) -> Option<String> {
+    let key = privacy.hash_key.as_ref()?;
+    Some(hmac_sha256_hex(key, result.to_string().as_bytes()))
+}
+
+/// The caller recorded for requests without an API key to identify them.
+pub const ANONYMOUS_CALLER: &str = "anonymous";
+
+/// Short, non-reversible identifier for an API key.
+pub fn key_fingerprint(key: &str) -> String {
+    format!("key:{}", &sha256_hex(key.as_bytes())[..12])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jsonl_sink_appends() {
+        let path = std::env::temp_dir().join(format!("audit-{}.jsonl", uuid::Uuid::new_v4()));
+        let path = path.to_str().unwrap();
+        let record = AuditRecord {
+            timestamp_ms: 1,
+            caller: key_fingerprint("secret"),
+            session_id: None,
+            tool: "evaluate".to_string(),
+            expression: "1 + 1".to_string(),
+            result_hash: Some(sha256_hex(b"2")),
+            outcome: Outcome::Ok,
+            error: None,
+        };
+
+        let sink = JsonlAuditSink::open(path).unwrap();
+        sink.record(&record).unwrap();
+        sink.record(&record).unwrap();
+
+        let content = std::fs::read_to_string(path).unwrap();
+        let lines: Vec<AuditRecord> = content
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines, vec![record.clone(), record]);
+        assert!(!content.contains("secret"));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_redact_expression() {
+        let salary = "salary = 85000 * 1.04";
+        let mut privacy = Privacy::default();
+        assert_eq!(redact_expression(&privacy, salary), salary);
+
+        privacy.expressions = ExpressionRedaction::Hash;
+        assert_eq!(redact_expression(&privacy, salary), "[redacted] (21 chars)");
+        privacy.hash_key = Some("k3y".to_string());
+        let hashed = redact_expression(&privacy, salary);
+        assert_eq!(
+            hashed,
+            format!("hmac-sha256:{}", hmac_sha256_hex("k3y", salary.as_bytes()))
+        );
+        assert!(!hashed.contains("85000"));
+        // Without the key, a guessed input does not reproduce the digest
+        assert!(!hashed.contains(&sha256_hex(salary.as_bytes())));
+        privacy.hash_key = Some("other".to_string());
+        assert_ne!(redact_expression(&privacy, salary), hashed);
+
+        privacy.expressions = ExpressionRedaction::Truncate;
+        privacy.truncate_to = 9;
+        assert_eq!(redact_expression(&privacy, salary), "salary = … (21 chars)");
+        assert_eq!(redact_expression(&privacy, "1 + 1"), "1 + 1");
+    }
+}
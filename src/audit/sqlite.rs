@@ -0,0 +1,54 @@
+use super::{AuditRecord, AuditSink};
+use rusqlite::{Connection, params};
+use std::sync::Mutex;
+
+pub struct SqliteAuditSink {
+    connection: Mutex<Connection>,
+}
+
+impl SqliteAuditSink {
+    pub fn open(path: &str) -> anyhow::Result<Self> {
+        let connection = Connection::open(path)?;
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS audit_log (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                timestamp_ms INTEGER NOT NULL,
+                caller TEXT NOT NULL,
+                session_id TEXT,
+                tool TEXT NOT NULL,
+                expression TEXT NOT NULL,
+                result_hash TEXT,
+                outcome TEXT NOT NULL,
+                error TEXT
+            )",
+        )?;
+        Ok(SqliteAuditSink {
+            connection: Mutex::new(connection),
+        })
+    }
+}
+
+impl AuditSink for SqliteAuditSink {
+    fn record(&self, record: &AuditRecord) -> anyhow::Result<()> {
+        let outcome = serde_json::to_value(&record.outcome)?;
+        self.connection
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .execute(
+                "INSERT INTO audit_log
+                    (timestamp_ms, caller, session_id, tool, expression, result_hash, outcome, error)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    record.timestamp_ms as i64,
+                    record.caller,
+                    record.session_id,
+                    record.tool,
+                    record.expression,
+                    record.result_hash,
+                    outcome.as_str(),
+                    record.error,
+                ],
+            )?;
+        Ok(())
+    }
+}
@@ -0,0 +1,266 @@
+use crate::evaluator::{self, Environment, EvalContext, Limits};
+use anyhow::{Context, bail};
+use clap::{Args, ValueEnum};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+#[derive(Debug, Args)]
+pub struct BatchArgs {
+    /// File with one expression per line, a JSON array, or a CSV whose first column is the expression
+    pub input: PathBuf,
+
+    /// Input format; detected from the file extension by default
+    #[arg(long, value_enum)]
+    pub input_format: Option<InputFormat>,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Csv)]
+    pub format: OutputFormat,
+
+    /// Write results to a file instead of stdout
+    #[arg(short, long, value_name = "FILE")]
+    pub output: Option<PathBuf>,
+
+    /// Evaluate expressions on all available cores
+    #[arg(long)]
+    pub parallel: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum InputFormat {
+    Lines,
+    Json,
+    Csv,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Csv,
+    Json,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchResult {
+    pub index: usize,
+    pub expression: String,
+    pub result: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonEntry {
+    Expression(String),
+    Object { expression: String },
+}
+
+pub fn run(args: &BatchArgs, limits: Limits) -> anyhow::Result<()> {
+    let content = fs::read_to_string(&args.input)
+        .with_context(|| format!("Failed to read {}", args.input.display()))?;
+    let format = args
+        .input_format
+        .unwrap_or_else(|| detect_format(&args.input));
+    let expressions = parse_input(&content, format)?;
+    let results = evaluate_all(&expressions, args.parallel, limits);
+
+    let rendered = match args.format {
+        OutputFormat::Csv => render_csv(&results),
+        OutputFormat::Json => serde_json::to_string_pretty(&results)? + "\n",
+    };
+    match &args.output {
+        Some(path) => fs::write(path, rendered)
+            .with_context(|| format!("Failed to write {}", path.display()))?,
+        None => std::io::stdout().write_all(rendered.as_bytes())?,
+    }
+    Ok(())
+}
+
+fn detect_format(path: &Path) -> InputFormat {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some(ext) if ext.eq_ignore_ascii_case("json") => InputFormat::Json,
+        Some(ext) if ext.eq_ignore_ascii_case("csv") => InputFormat::Csv,
+        _ => InputFormat::Lines,
+    }
+}
+
+pub fn parse_input(content: &str, format: InputFormat) -> anyhow::Result<Vec<String>> {
+    match format {
+        InputFormat::Lines => Ok(content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect()),
+        InputFormat::Json => {
+            let entries: Vec<JsonEntry> = serde_json::from_str(content)
+                .context("Expected a JSON array of strings or {\"expression\": ...} objects")?;
+            Ok(entries
+                .into_iter()
+                .map(|entry| match entry {
+                    JsonEntry::Expression(expression) | JsonEntry::Object { expression } => {
+                        expression
+                    }
+                })
+                .collect())
+        }
+        InputFormat::Csv => {
+            let mut expressions = Vec::new();
+            for (idx, line) in content.lines().enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let first = first_csv_field(line)?;
+                if idx == 0 && first.trim().eq_ignore_ascii_case("expression") {
+                    continue;
+                }
+                expressions.push(first.trim().to_string());
+            }
+            Ok(expressions)
+        }
+    }
+}
+
+fn first_csv_field(line: &str) -> anyhow::Result<String> {
+    let Some(quoted) = line.strip_prefix('"') else {
+        return Ok(line.split(',').next().unwrap_or_default().to_string());
+    };
+
+    let mut field = String::new();
+    let mut chars = quoted.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => return Ok(field),
+            _ => field.push(c),
+        }
+    }
+    bail!("Unterminated quoted field in CSV line: {}", line)
+}
+
+/// Evaluate each expression under its own [`EvalContext`] for `limits`.
+pub fn evaluate_all(expressions: &[String], parallel: bool, limits: Limits) -> Vec<BatchResult> {
+    if !parallel || expressions.len() < 2 {
+        return expressions
+            .iter()
+            .enumerate()
+            .map(|(index, expression)| evaluate_one(index, expression, limits))
+            .collect();
+    }
+
+    let workers = thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk_size = expressions.len().div_ceil(workers);
+    thread::scope(|scope| {
+        let handles: Vec<_> = expressions
+            .chunks(chunk_size)
+            .enumerate()
+            .map(|(chunk_idx, chunk)| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .enumerate()
+                        .map(|(offset, expression)| {
+                            evaluate_one(chunk_idx * chunk_size + offset, expression, limits)
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("batch worker panicked"))
+            .collect()
+    })
+}
+
+fn evaluate_one(index: usize, expression: &str, limits: Limits) -> BatchResult {
+    let ctx = EvalContext::new(limits);
+    let (result, error) = match evaluator::eval_with(expression, &Environment::new(), &ctx) {
+        Ok(value) => (Some(value.to_string()), None),
+        Err(err) => (None, Some(err.to_string())),
+    };
+    BatchResult {
+        index,
+        expression: expression.to_string(),
+        result,
+        error,
+    }
+}
+
+fn render_csv(results: &[BatchResult]) -> String {
+    let mut out = String::from("index,expression,result,error\n");
+    for row in results {
+        out.push_str(&format!(
+            "{},{},{},{}\n",
+            row.index,
+            csv_escape(&row.expression),
+            csv_escape(row.result.as_deref().unwrap_or_default()),
+            csv_escape(row.error.as_deref().unwrap_or_default()),
+        ));
+    }
+    out
+}
+
+fn csv_escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_input_formats() {
+        assert_eq!(
+            parse_input("1 + 1\n\n2 * 3\n", InputFormat::Lines).unwrap(),
+            vec!["1 + 1", "2 * 3"]
+        );
+        assert_eq!(
+            parse_input(r#"["1 + 1", {"expression": "2 * 3"}]"#, InputFormat::Json).unwrap(),
+            vec!["1 + 1", "2 * 3"]
+        );
+        assert_eq!(
+            parse_input(
+                "expression,note\n\"1 + 1\",a\n2 * 3,b\n\"say \"\"hi\"\"\"\n",
+                InputFormat::Csv
+            )
+            .unwrap(),
+            vec!["1 + 1", "2 * 3", "say \"hi\""]
+        );
+    }
+
+    #[test]
+    fn test_parallel_matches_sequential() {
+        let expressions: Vec<String> = (0..50).map(|i| format!("{i} * 2")).collect();
+        let sequential = evaluate_all(&expressions, false, Limits::DEFAULT);
+        let parallel = evaluate_all(&expressions, true, Limits::DEFAULT);
+
+        assert_eq!(parallel.len(), sequential.len());
+        for (a, b) in sequential.iter().zip(&parallel) {
+            assert_eq!(a.index, b.index);
+            assert_eq!(a.result, b.result);
+        }
+    }
+
+    #[test]
+    fn test_render_csv_reports_errors() {
+        let results = evaluate_all(
+            &["1 / 0".to_string(), "2 + 2".to_string()],
+            false,
+            Limits::DEFAULT,
+        );
+        assert_eq!(
+            render_csv(&results),
+            "index,expression,result,error\n0,1 / 0,,Division by zero\n1,2 + 2,4,\n"
+        );
+    }
+}
@@ -0,0 +1,137 @@
+use crate::mcp::client::McpClient;
+use anyhow::{anyhow, bail, ensure};
+use clap::Args;
+use serde_json::{Value, json};
+use std::time::{Duration, Instant};
+
+const SAMPLE_EXPRESSION: &str = "2 + 3 * 4";
+const SAMPLE_RESULT: &str = "14";
+
+#[derive(Debug, Args)]
+pub struct CheckArgs {
+    /// MCP endpoint of the deployment, e.g. `http://localhost:8080/mcp`
+    #[arg(long)]
+    pub url: String,
+
+    /// API key sent as a bearer token
+    #[arg(long)]
+    pub api_key: Option<String>,
+
+    /// Per-request timeout in seconds
+    #[arg(long, default_value_t = 10)]
+    pub timeout: u64,
+}
+
+/// Run initialize, tools/list and a sample tools/call against a deployment and
+/// report each step; fails if any step fails.
+pub async fn run(args: &CheckArgs) -> anyhow::Result<()> {
+    let mut client = McpClient::new(
+        &args.url,
+        args.api_key.clone(),
+        Duration::from_secs(args.timeout),
+    )?;
+    let mut failures = 0;
+
+    let initialized = step("initialize", initialize(&mut client).await);
+    if !initialized {
+        bail!("Cannot continue without a successful initialize");
+    }
+    failures += usize::from(!step("tools/list", list_tools(&mut client).await));
+    failures += usize::from(!step(
+        "tools/call evaluate",
+        call_evaluate(&mut client).await,
+    ));
+
+    if failures > 0 {
+        bail!("{failures} check(s) failed");
+    }
+    println!("All checks passed");
+    Ok(())
+}
+
+fn step(name: &str, outcome: (anyhow::Result<String>, Duration)) -> bool {
+    let (result, elapsed) = outcome;
+    match result {
+        Ok(detail) => {
+            println!("PASS {name} ({} ms): {detail}", elapsed.as_millis());
+            true
+        }
+        Err(err) => {
+            println!("FAIL {name} ({} ms): {err:#}", elapsed.as_millis());
+            false
+        }
+    }
+}
+
+async fn initialize(client: &mut McpClient) -> (anyhow::Result<String>, Duration) {
+    let started = Instant::now();
+    let result = async {
+        let result = client
+            .request(
+                "initialize",
+                Some(json!({
+                    "protocolVersion": crate::mcp::protocol::PROTOCOL_VERSION,
+                    "capabilities": {},
+                    "clientInfo": { "name": "calculator-mcp-check", "version": env!("CARGO_PKG_VERSION") },
+                })),
+            )
+            .await?;
+        client.notify("notifications/initialized").await?;
+        let server = &result["serverInfo"];
+        Ok(format!(
+            "{} {} (protocol {})",
+            server["name"].as_str().unwrap_or("unknown"),
+            server["version"].as_str().unwrap_or("?"),
+            result["protocolVersion"].as_str().unwrap_or("?"),
+        ))
+    }
+    .await;
+    (result, started.elapsed())
+}
+
+async fn list_tools(client: &mut McpClient) -> (anyhow::Result<String>, Duration) {
+    let started = Instant::now();
+    let result = async {
+        let result = client.request("tools/list", None).await?;
+        let names: Vec<&str> = result["tools"]
+            .as_array()
+            .ok_or_else(|| anyhow!("`tools` is not an array"))?
+            .iter()
+            .filter_map(|tool| tool["name"].as_str())
+            .collect();
+        ensure!(
+            names.contains(&"evaluate"),
+            "`evaluate` tool not advertised"
+        );
+        Ok(format!("{} tool(s): {}", names.len(), names.join(", ")))
+    }
+    .await;
+    (result, started.elapsed())
+}
+
+async fn call_evaluate(client: &mut McpClient) -> (anyhow::Result<String>, Duration) {
+    let started = Instant::now();
+    let result = async {
+        let result = client
+            .request(
+                "tools/call",
+                Some(
+                    json!({ "name": "evaluate", "arguments": { "expression": SAMPLE_EXPRESSION } }),
+                ),
+            )
+            .await?;
+        ensure!(
+            result["isError"] != Value::Bool(true),
+            "tool returned an error: {}",
+            result["content"][0]["text"]
+        );
+        let value = &result["structuredContent"]["result"];
+        ensure!(
+            value == SAMPLE_RESULT,
+            "expected {SAMPLE_EXPRESSION} = {SAMPLE_RESULT}, got {value}"
+        );
+        Ok(format!("{SAMPLE_EXPRESSION} = {SAMPLE_RESULT}"))
+    }
+    .await;
+    (result, started.elapsed())
+}
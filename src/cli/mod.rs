@@ -0,0 +1,75 @@
+use clap::builder::FalseyValueParser;
+use clap::{Parser, Subcommand};
+
+use crate::batch::BatchArgs;
+use crate::check::CheckArgs;
+
+#[derive(Debug, Parser)]
+#[command(name = "calculator-mcp", version, about)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Path to the TOML configuration file (defaults to ./config.toml when present)
+    #[arg(short, long, value_name = "FILE", global = true)]
+    pub config: Option<String>,
+
+    /// Ignore configuration files and read settings only from `APP__*` environment
+    /// variables and flags; the resolved configuration is logged at startup
+    #[arg(
+        long,
+        env = "CALCULATOR_ENV_ONLY",
+        value_parser = FalseyValueParser::new(),
+        conflicts_with = "config",
+        global = true
+    )]
+    pub env_only: bool,
+
+    /// Port for the HTTP server
+    #[arg(long)]
+    pub port: Option<u16>,
+
+    /// Override any configuration key, e.g. `--set http_server.port=9000`
+    #[arg(long = "set", value_name = "KEY=VALUE", value_parser = parse_key_value, global = true)]
+    pub overrides: Vec<(String, String)>,
+
+    /// Print the effective configuration (secrets redacted) and exit
+    #[arg(long)]
+    pub print_config: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Run the HTTP server (default)
+    Serve,
+    /// Evaluate expressions from a file and print the results
+    Batch(BatchArgs),
+    /// Evaluate expressions interactively with persistent variables
+    Repl,
+    /// Verify a running deployment end to end as an MCP client
+    Check(CheckArgs),
+    /// Answer evaluations for a server over stdin and stdout
+    #[command(hide = true)]
+    Worker,
+}
+
+impl Cli {
+    /// Command-line overrides in application order; dedicated flags win over `--set`.
+    pub fn config_overrides(&self) -> Vec<(String, String)> {
+        let mut overrides = self.overrides.clone();
+        if let Some(port) = self.port {
+            overrides.push(("http_server.port".to_string(), port.to_string()));
+        }
+        overrides
+    }
+}
+
+fn parse_key_value(raw: &str) -> Result<(String, String), String> {
+    let (key, value) = raw
+        .split_once('=')
+        .ok_or_else(|| format!("expected KEY=VALUE, got `{raw}`"))?;
+    if key.trim().is_empty() {
+        return Err(format!("empty key in `{raw}`"));
+    }
+    Ok((key.trim().to_string(), value.to_string()))
+}
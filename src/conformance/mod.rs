@@ -0,0 +1,118 @@
+//! Machine-readable conformance suite: expressions with the result or error
+//! the server gives for them, for alternative frontends (a WASM build, FFI
+//! consumers) to check they behave the same. The cases ship with the crate in
+//! `conformance/cases.json` and assume the default evaluator limits.
+
+use crate::evaluator::{self, Environment, EvalContext};
+use serde::{Deserialize, Serialize};
+
+/// The suite as shipped, a JSON array of [`Case`]s.
+pub const CASES_JSON: &str = include_str!("../../conformance/cases.json");
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Case {
+    pub name: String,
+    /// Input to the `evaluate` tool, possibly several `;`-separated statements
+    pub expression: String,
+    #[serde(flatten)]
+    pub expected: Expected,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Expected {
+    /// The `result` string, compared exactly
+    Result(String),
+    /// A fragment of the error message
+    Error(String),
+}
+
+impl Expected {
+    pub fn matches(&self, actual: &Result<String, String>) -> bool {
+        match (self, actual) {
+            (Expected::Result(expected), Ok(actual)) => expected == actual,
+            (Expected::Error(expected), Err(actual)) => actual.contains(expected.as_str()),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Failure {
+    pub case: Case,
+    pub actual: Result<String, String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Report {
+    pub passed: usize,
+    pub failures: Vec<Failure>,
+}
+
+impl Report {
+    pub fn is_success(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+pub fn cases() -> anyhow::Result<Vec<Case>> {
+    Ok(serde_json::from_str(CASES_JSON)?)
+}
+
+/// Run every case through `evaluate`, which returns the result string or the
+/// error message for an expression.
+pub fn run(mut evaluate: impl FnMut(&str) -> Result<String, String>) -> anyhow::Result<Report> {
+    let mut report = Report::default();
+    for case in cases()? {
+        let actual = evaluate(&case.expression);
+        if case.expected.matches(&actual) {
+            report.passed += 1;
+        } else {
+            report.failures.push(Failure { case, actual });
+        }
+    }
+    Ok(report)
+}
+
+/// The reference behavior: what the `evaluate` tool answers outside a
+/// session, under default limits.
+pub fn evaluate(expression: &str) -> Result<String, String> {
+    evaluator::eval_statements(expression, &mut Environment::new(), &EvalContext::default())
+        .map(|evaluation| evaluation.value.to_string())
+        .map_err(|err| err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reference_passes_suite() {
+        let report = run(evaluate).unwrap();
+        assert!(report.is_success(), "{:#?}", report.failures);
+        assert_eq!(report.passed, cases().unwrap().len());
+
+        let names: Vec<String> = cases().unwrap().into_iter().map(|case| case.name).collect();
+        let mut unique = names.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(unique.len(), names.len(), "case names must be unique");
+    }
+
+    #[test]
+    fn test_divergent_frontend_is_reported() {
+        let report = run(|expression| match expression {
+            "0.1 + 0.2" => Ok("0.30000000000000004".to_string()),
+            "1 / 0" => Ok("inf".to_string()),
+            _ => evaluate(expression),
+        })
+        .unwrap();
+        let failed: Vec<&str> = report
+            .failures
+            .iter()
+            .map(|failure| failure.case.name.as_str())
+            .collect();
+        assert_eq!(failed, ["decimal_addition", "division_by_zero"]);
+        assert_eq!(report.failures[1].actual, Ok("inf".to_string()));
+    }
+}
@@ -0,0 +1,314 @@
+//! Long-lived parse sessions for editor integrations such as expression
+//! builders and formula bars. A client opens a [`Document`], sends
+//! [`TextEdit`]s and gets diagnostics back; only statements whose text changed
+//! are parsed again.
+
+use crate::app_config::Documents;
+use crate::evaluator::{self, ParseError, Span, limits::limits, strip_comments};
+use crate::session::now_ms;
+use anyhow::{anyhow, bail};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Replace the characters `start..end` (character offsets, end exclusive)
+/// with `text`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct TextEdit {
+    pub start: usize,
+    pub end: usize,
+    #[serde(default)]
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A problem found in the document, at character offsets into its text.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    pub span: Span,
+    pub severity: Severity,
+    pub message: String,
+}
+
+/// Diagnostics after a check, and how many statements had to be parsed again.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Check {
+    pub version: u64,
+    pub diagnostics: Vec<Diagnostic>,
+    pub reparsed: usize,
+}
+
+/// An expression being edited. Diagnostics are cached per statement text,
+/// with spans relative to the statement, so an edit to one statement of a
+/// long script leaves the others untouched.
+#[derive(Debug, Clone)]
+pub struct Document {
+    text: String,
+    version: u64,
+    cache: HashMap<String, Vec<Diagnostic>>,
+}
+
+impl Document {
+    pub fn new(text: impl Into<String>) -> Self {
+        Document {
+            text: text.into(),
+            version: 1,
+            cache: HashMap::new(),
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Apply `edits` in order, each against the text left by the previous one,
+    /// and bump the version. Nothing changes if any edit is out of range.
+    pub fn apply(&mut self, edits: &[TextEdit]) -> anyhow::Result<()> {
+        let mut chars: Vec<char> = self.text.chars().collect();
+        for edit in edits {
+            if edit.start > edit.end || edit.end > chars.len() {
+                bail!(
+                    "Edit {}..{} is outside the document of {} characters",
+                    edit.start,
+                    edit.end,
+                    chars.len()
+                );
+            }
+            chars.splice(edit.start..edit.end, edit.text.chars());
+        }
+        self.text = chars.into_iter().collect();
+        self.version += 1;
+        Ok(())
+    }
+
+    /// Diagnostics for the current text, parsing only statements not seen in
+    /// the previous check.
+    pub fn check(&mut self) -> Check {
+        let mut check = Check {
+            version: self.version,
+            diagnostics: Vec::new(),
+            reparsed: 0,
+        };
+        let stripped = match strip_comments(&self.text) {
+            Ok(stripped) => stripped,
+            Err(err) => {
+                check.diagnostics.push(error(err.span, err.message));
+                return check;
+            }
+        };
+
+        let mut cache = HashMap::new();
+        let mut offset = 0;
+        for statement in stripped.split(';') {
+            let length = statement.chars().count();
+            if !statement.trim().is_empty() {
+                let diagnostics = match self.cache.get(statement).cloned() {
+                    Some(diagnostics) => diagnostics,
+                    None => {
+                        check.reparsed += 1;
+                        check_statement(statement)
+                    }
+                };
+                check
+                    .diagnostics
+                    .extend(diagnostics.iter().cloned().map(|mut diagnostic| {
+                        diagnostic.span.start += offset;
+                        diagnostic.span.end += offset;
+                        diagnostic
+                    }));
+                cache.insert(statement.to_string(), diagnostics);
+            }
+            offset += length + 1;
+        }
+        self.cache = cache;
+        check
+    }
+}
+
+fn error(span: Span, message: impl Into<String>) -> Diagnostic {
+    Diagnostic {
+        span,
+        severity: Severity::Error,
+        message: message.into(),
+    }
+}
+
+/// Diagnostics of one statement, with spans relative to it.
+fn check_statement(statement: &str) -> Vec<Diagnostic> {
+    let length = statement.chars().count();
+    let whole = Span {
+        start: length - statement.trim_start().chars().count(),
+        end: statement.trim_end().chars().count(),
+    };
+    let (_, expression) = evaluator::split_assignment(statement);
+    let offset = length - expression.chars().count();
+    let cost = evaluator::parse(expression).and_then(|_| evaluator::cost_estimate(expression));
+    match cost {
+        Ok(cost) => match limits().check_cost(&cost) {
+            Ok(()) => Vec::new(),
+            Err(exceeded) => vec![Diagnostic {
+                span: whole,
+                severity: Severity::Warning,
+                message: exceeded.to_string(),
+            }],
+        },
+        Err(err) => match err.downcast_ref::<ParseError>() {
+            Some(parse) => vec![error(
+                Span {
+                    start: parse.span.start + offset,
+                    end: parse.span.end + offset,
+                },
+                &parse.message,
+            )],
+            None => vec![error(whole, err.to_string())],
+        },
+    }
+}
+
+struct Open {
+    document: Document,
+    last_used_ms: u64,
+}
+
+/// Open documents by id, dropped after `ttl_secs` without use.
+pub struct DocumentStore {
+    config: Documents,
+    documents: Mutex<HashMap<String, Open>>,
+}
+
+impl DocumentStore {
+    pub fn new(config: Documents) -> Self {
+        DocumentStore {
+            config,
+            documents: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Open a document and check it, returning its id.
+    pub fn open(&self, text: String) -> anyhow::Result<(String, Check)> {
+        self.evict_expired();
+        let mut document = Document::new(text);
+        let check = document.check();
+        let mut documents = self.lock();
+        if documents.len() >= self.config.max_documents {
+            bail!(
+                "Too many open documents (limit {})",
+                self.config.max_documents
+            );
+        }
+        let id = uuid::Uuid::new_v4().to_string();
+        documents.insert(
+            id.clone(),
+            Open {
+                document,
+                last_used_ms: now_ms(),
+            },
+        );
+        Ok((id, check))
+    }
+
+    /// Run `f` on an open document, refreshing its expiry.
+    pub fn with_document<T>(
+        &self,
+        id: &str,
+        f: impl FnOnce(&mut Document) -> T,
+    ) -> anyhow::Result<T> {
+        let mut documents = self.lock();
+        let open = documents
+            .get_mut(id)
+            .filter(|open| !self.expired(open, now_ms()))
+            .ok_or_else(|| anyhow!("Unknown or expired document: {id}"))?;
+        open.last_used_ms = now_ms();
+        Ok(f(&mut open.document))
+    }
+
+    pub fn close(&self, id: &str) -> bool {
+        self.lock().remove(id).is_some()
+    }
+
+    pub fn evict_expired(&self) -> usize {
+        let now = now_ms();
+        let mut documents = self.lock();
+        let before = documents.len();
+        documents.retain(|_, open| !self.expired(open, now));
+        before - documents.len()
+    }
+
+    fn expired(&self, open: &Open, now: u64) -> bool {
+        now.saturating_sub(open.last_used_ms) >= self.config.ttl_secs.saturating_mul(1000)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Open>> {
+        self.documents
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_incremental_check() {
+        let mut document = Document::new("a = 2; b = a * ; a + b");
+        let check = document.check();
+        assert_eq!(check.reparsed, 3);
+        let [diagnostic] = check.diagnostics.as_slice() else {
+            panic!("expected one diagnostic: {:?}", check.diagnostics);
+        };
+        assert_eq!(diagnostic.severity, Severity::Error);
+        assert_eq!(diagnostic.span, Span { start: 7, end: 14 });
+
+        // Fix the second statement: only it is parsed again
+        document
+            .apply(&[TextEdit {
+                start: 15,
+                end: 15,
+                text: "3".to_string(),
+            }])
+            .unwrap();
+        assert_eq!(document.text(), "a = 2; b = a * 3; a + b");
+        let check = document.check();
+        assert_eq!((check.version, check.reparsed), (2, 1));
+        assert!(check.diagnostics.is_empty());
+
+        assert!(
+            document
+                .apply(&[TextEdit {
+                    start: 20,
+                    end: 40,
+                    text: String::new(),
+                }])
+                .is_err()
+        );
+        assert_eq!(document.version(), 2);
+    }
+
+    #[test]
+    fn test_diagnostics_skip_comments() {
+        let mut document = Document::new("1 + # note; not code\n2 @ 3");
+        let check = document.check();
+        let [diagnostic] = check.diagnostics.as_slice() else {
+            panic!("expected one diagnostic: {:?}", check.diagnostics);
+        };
+        // The error points into the second line, not the comment
+        assert!(diagnostic.span.start > 21, "{diagnostic:?}");
+
+        let mut unterminated = Document::new("1 /* open");
+        assert_eq!(
+            unterminated.check().diagnostics[0].message,
+            "Unterminated comment"
+        );
+    }
+}
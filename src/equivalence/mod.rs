@@ -0,0 +1,186 @@
+//! Probabilistic equivalence of two expressions: both are evaluated at
+//! pseudo-random points and compared, catching identities such as
+//! `(x + 1)^2` and `x^2 + 2x + 1` that differ structurally.
+
+use anyhow::bail;
+use bigdecimal::BigDecimal;
+use serde::Serialize;
+use std::str::FromStr;
+
+use crate::evaluator::{AngleMode, Environment, Expr, validate_variable_name};
+
+pub const MAX_SAMPLES: usize = 1000;
+
+/// Where and how closely to compare.
+#[derive(Debug, Clone)]
+pub struct Sampling {
+    pub from: f64,
+    pub to: f64,
+    pub samples: usize,
+    /// Values agree when they differ by at most this, relative to the larger
+    /// magnitude once it exceeds 1
+    pub tolerance: BigDecimal,
+    pub seed: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Verdict {
+    /// Whether the sampled points gave no reason to doubt equivalence
+    pub equivalent: bool,
+    /// Whether the expressions are identical after constant folding
+    pub structurally_equal: bool,
+    /// Points where both sides had equal values
+    pub agreed: usize,
+    /// Points where neither side was defined
+    pub skipped: usize,
+    pub counterexample: Option<Counterexample>,
+}
+
+/// A point where the expressions differ; a side is `None` where it is undefined.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Counterexample {
+    pub point: Vec<(String, String)>,
+    pub left: Option<String>,
+    pub right: Option<String>,
+}
+
+/// Compare `left` and `right` at up to `sampling.samples` points, assigning
+/// each of `variables` a value in `[from, to]`; stops at the first disagreement.
+pub fn check(
+    left: &Expr,
+    right: &Expr,
+    variables: &[String],
+    base_env: &Environment,
+    sampling: &Sampling,
+) -> anyhow::Result<Verdict> {
+    for variable in variables {
+        validate_variable_name(variable)?;
+    }
+    if !sampling.from.is_finite() || !sampling.to.is_finite() || sampling.from > sampling.to {
+        bail!("Range must be finite with from <= to");
+    }
+    if !(1..=MAX_SAMPLES).contains(&sampling.samples) {
+        bail!("samples must be between 1 and {}", MAX_SAMPLES);
+    }
+
+    let structurally_equal =
+        left.optimize(AngleMode::default())? == right.optimize(AngleMode::default())?;
+    let mut verdict = Verdict {
+        equivalent: true,
+        structurally_equal,
+        agreed: 0,
+        skipped: 0,
+        counterexample: None,
+    };
+    // Without variables one evaluation decides
+    let samples = if variables.is_empty() {
+        1
+    } else {
+        sampling.samples
+    };
+    let mut rng = SplitMix64(sampling.seed);
+    let mut env = base_env.clone();
+    for _ in 0..samples {
+        let mut point = Vec::new();
+        for variable in variables {
+            let value = sampling.from + rng.next_unit() * (sampling.to - sampling.from);
+            // Six decimals keep powers of sampled values small and exact
+            let value = BigDecimal::from_str(&format!("{value:.6}"))?.normalized();
+            env.set(variable, value.clone())?;
+            point.push((variable.clone(), value.to_string()));
+        }
+        match (left.eval(&env).ok(), right.eval(&env).ok()) {
+            (None, None) => verdict.skipped += 1,
+            (Some(l), Some(r)) if agree(&l, &r, &sampling.tolerance) => verdict.agreed += 1,
+            (l, r) => {
+                verdict.equivalent = false;
+                verdict.counterexample = Some(Counterexample {
+                    point,
+                    left: l.map(|value| value.to_string()),
+                    right: r.map(|value| value.to_string()),
+                });
+                return Ok(verdict);
+            }
+        }
+    }
+    if verdict.agreed == 0 {
+        bail!("Neither expression is defined at any sampled point; try another range");
+    }
+    Ok(verdict)
+}
+
+fn agree(left: &BigDecimal, right: &BigDecimal, tolerance: &BigDecimal) -> bool {
+    let scale = left.abs().max(right.abs()).max(BigDecimal::from(1));
+    (left - right).abs() <= tolerance * scale
+}
+
+/// Small deterministic generator, so a seed reproduces the same points.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `[0, 1)`.
+    fn next_unit(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::{free_variables, parse};
+
+    fn compare(left: &str, right: &str) -> anyhow::Result<Verdict> {
+        let mut variables = free_variables(left).unwrap();
+        for variable in free_variables(right).unwrap() {
+            if !variables.contains(&variable) {
+                variables.push(variable);
+            }
+        }
+        let sampling = Sampling {
+            from: -10.0,
+            to: 10.0,
+            samples: 50,
+            tolerance: BigDecimal::from_str("1e-9").unwrap(),
+            seed: 7,
+        };
+        check(
+            &parse(left).unwrap(),
+            &parse(right).unwrap(),
+            &variables,
+            &Environment::new(),
+            &sampling,
+        )
+    }
+
+    #[test]
+    fn test_equivalence() {
+        let same = compare("(x + 1) ^ 2", "x ^ 2 + 2 * x + 1").unwrap();
+        assert!(same.equivalent);
+        assert!(!same.structurally_equal);
+        assert_eq!(same.agreed, 50);
+
+        assert!(compare("sin(x) ^ 2 + cos(x) ^ 2", "1").unwrap().equivalent);
+        assert!(compare("a * b - b * a", "0").unwrap().equivalent);
+        assert!(compare("x + 0", "x").unwrap().structurally_equal);
+
+        let different = compare("(x + y) ^ 2", "x ^ 2 + y ^ 2").unwrap();
+        assert!(!different.equivalent);
+        let counterexample = different.counterexample.unwrap();
+        assert_eq!(counterexample.point.len(), 2);
+
+        // Undefined on one side only counts as a difference
+        let domain = compare("sqrt(x) ^ 2", "x").unwrap();
+        assert!(!domain.equivalent);
+        assert_eq!(domain.counterexample.unwrap().left, None);
+
+        assert!(compare("1 / (x - x)", "1 / 0 + x").is_err());
+    }
+}
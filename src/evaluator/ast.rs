@@ -0,0 +1,371 @@
+use anyhow::{anyhow, bail};
+use bigdecimal::{BigDecimal, Signed};
+use std::fmt;
+
+use super::context::{self, checkpoint};
+use super::error::DepthExceeded;
+use super::functions::apply_function;
+use super::limits::{DepthBudget, limits};
+use super::precision;
+use super::{
+    AngleMode, Assoc, Environment, Function, MathConst, Operator, Token, apply_operator,
+    apply_unary_operator,
+};
+
+/// Expression tree rebuilt from the RPN produced by the parser.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Number(BigDecimal),
+    Const(MathConst),
+    Var(String),
+    Unary(Operator, Box<Expr>),
+    Binary(Operator, Box<Expr>, Box<Expr>),
+    Call(Function, Vec<Expr>),
+}
+
+/// Binding strength of atoms, calls and other self-delimiting nodes.
+pub const ATOM_PRECEDENCE: u8 = u8::MAX;
+
+impl Expr {
+    pub fn from_rpn(tokens: &[Token]) -> anyhow::Result<Expr> {
+        let mut stack: Vec<Expr> = Vec::new();
+        for token in tokens {
+            let node = match token {
+                Token::Number(num) => Expr::Number(num.clone()),
+                Token::Ident(math_const) => Expr::Const(*math_const),
+                Token::Var(name) => Expr::Var(name.clone()),
+                Token::Op(op) if op.is_unary() => {
+                    let Some(operand) = stack.pop() else {
+                        bail!("Not enough operands for operator");
+                    };
+                    Expr::Unary(*op, Box::new(operand))
+                }
+                Token::Op(op) => {
+                    let (Some(rhs), Some(lhs)) = (stack.pop(), stack.pop()) else {
+                        bail!("Not enough operands for operator");
+                    };
+                    Expr::Binary(*op, Box::new(lhs), Box::new(rhs))
+                }
+                Token::Call(func, argc) => {
+                    if stack.len() < *argc {
+                        bail!("Not enough arguments for function {}", func);
+                    }
+                    Expr::Call(*func, stack.split_off(stack.len() - argc))
+                }
+                Token::LParenthesis | Token::RParenthesis | Token::Func(_) | Token::Comma => {
+                    bail!("Unexpected token in RPN stream: {}", token)
+                }
+            };
+            stack.push(node);
+        }
+
+        match (stack.pop(), stack.is_empty()) {
+            (Some(expr), true) => Ok(expr),
+            _ => bail!("Invalid RPN expression"),
+        }
+    }
+
+    /// Evaluate the tree directly, reading variables from `env`.
+    pub fn eval(&self, env: &Environment) -> anyhow::Result<BigDecimal> {
+        let result = self.eval_intermediate(env, DepthBudget::from_limits())?;
+        Ok(precision::round_to(result, limits().precision))
+    }
+
+    fn eval_intermediate(
+        &self,
+        env: &Environment,
+        budget: DepthBudget,
+    ) -> anyhow::Result<BigDecimal> {
+        let budget = budget.descend()?;
+        checkpoint()?;
+        match self {
+            Expr::Number(num) => Ok(num.clone()),
+            Expr::Const(math_const) => Ok(BigDecimal::from(*math_const)),
+            Expr::Var(name) => env
+                .get(name)
+                .cloned()
+                .ok_or_else(|| anyhow!("Unknown variable: {}", name)),
+            Expr::Unary(op, operand) => {
+                apply_unary_operator(operand.eval_intermediate(env, budget)?, *op)
+            }
+            Expr::Binary(op, lhs, rhs) => apply_operator(
+                lhs.eval_intermediate(env, budget)?,
+                rhs.eval_intermediate(env, budget)?,
+                *op,
+            ),
+            Expr::Call(func, args) => {
+                let args = args
+                    .iter()
+                    .map(|arg| arg.eval_intermediate(env, budget))
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                apply_function(*func, &args, context::angle_mode(env))
+            }
+        }
+    }
+
+    /// Fold constant subtrees and drop identities such as `x * 1`, `x + 0` and
+    /// `x ^ 1`, so repeated evaluation only does the work that depends on variables.
+    /// Subtrees that fail to evaluate are kept and report their error when evaluated.
+    /// Trigonometric calls are folded with angles in `mode`.
+    pub fn optimize(&self, mode: AngleMode) -> Result<Expr, DepthExceeded> {
+        let mut constants = Environment::new();
+        constants.set_angle_mode(mode);
+        self.optimize_within(&constants, DepthBudget::from_limits())
+    }
+
+    fn optimize_within(
+        &self,
+        constants: &Environment,
+        budget: DepthBudget,
+    ) -> Result<Expr, DepthExceeded> {
+        let budget = budget.descend()?;
+        let expr = match self {
+            Expr::Number(_) | Expr::Const(_) | Expr::Var(_) => return Ok(self.clone()),
+            Expr::Unary(Operator::UnaryAdd, operand) => {
+                return operand.optimize_within(constants, budget);
+            }
+            Expr::Unary(op, operand) => {
+                Expr::Unary(*op, Box::new(operand.optimize_within(constants, budget)?))
+            }
+            Expr::Binary(op, lhs, rhs) => {
+                let (lhs, rhs) = (
+                    lhs.optimize_within(constants, budget)?,
+                    rhs.optimize_within(constants, budget)?,
+                );
+                match op {
+                    Operator::Add if lhs.is_integer(0) => return Ok(rhs),
+                    Operator::Add | Operator::Sub if rhs.is_integer(0) => return Ok(lhs),
+                    Operator::Mul if lhs.is_integer(1) => return Ok(rhs),
+                    Operator::Mul | Operator::Div | Operator::Pow if rhs.is_integer(1) => {
+                        return Ok(lhs);
+                    }
+                    _ => Expr::Binary(*op, Box::new(lhs), Box::new(rhs)),
+                }
+            }
+            Expr::Call(func, args) => Expr::Call(
+                *func,
+                args.iter()
+                    .map(|arg| arg.optimize_within(constants, budget))
+                    .collect::<Result<_, _>>()?,
+            ),
+        };
+        let constant = match &expr {
+            Expr::Unary(_, operand) => operand.is_constant(),
+            Expr::Binary(_, lhs, rhs) => lhs.is_constant() && rhs.is_constant(),
+            Expr::Call(_, args) => args.iter().all(Expr::is_constant),
+            _ => false,
+        };
+        if constant && let Ok(value) = expr.eval_intermediate(constants, budget) {
+            return Ok(Expr::Number(value));
+        }
+        Ok(expr)
+    }
+
+    fn is_constant(&self) -> bool {
+        matches!(self, Expr::Number(_) | Expr::Const(_))
+    }
+
+    fn is_integer(&self, value: i32) -> bool {
+        matches!(self, Expr::Number(num) if *num == BigDecimal::from(value))
+    }
+
+    pub fn precedence(&self) -> u8 {
+        match self {
+            // Unary plus is not printed, so it binds like its operand
+            Expr::Unary(Operator::UnaryAdd, operand) => operand.precedence(),
+            Expr::Unary(op, _) | Expr::Binary(op, _, _) => op.precedence(),
+            Expr::Number(num) if num.is_negative() => Operator::UnarySub.precedence(),
+            Expr::Number(_) | Expr::Const(_) | Expr::Var(_) | Expr::Call(..) => ATOM_PRECEDENCE,
+        }
+    }
+}
+
+/// Whether `child` must be parenthesized as an operand of `parent` written inline.
+pub fn needs_parens(parent: Operator, child: &Expr, right: bool) -> bool {
+    let parent_prec = parent.precedence();
+    let child_prec = child.precedence();
+    child_prec < parent_prec
+        || (child_prec == parent_prec
+            && match parent.associativity() {
+                Assoc::Left => right,
+                Assoc::Right => !right,
+            })
+}
+
+/// Canonical infix: minimal parentheses, binary operators surrounded by single
+/// spaces, no space after unary minus and `, ` between function arguments.
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Number(num) => write!(f, "{}", canonical_number(num)),
+            Expr::Const(math_const) => write!(f, "{}", math_const),
+            Expr::Var(name) => write!(f, "{}", name),
+            Expr::Unary(Operator::UnaryAdd, operand) => write!(f, "{}", operand),
+            Expr::Unary(op, operand) if op.is_postfix() => {
+                write_operand(f, *op, operand, false)?;
+                write!(f, "{}", op.symbol())
+            }
+            Expr::Unary(op, operand) => {
+                write!(f, "-")?;
+                write_operand(f, *op, operand, true)
+            }
+            Expr::Binary(op, lhs, rhs) => {
+                write_operand(f, *op, lhs, false)?;
+                write!(f, " {} ", op)?;
+                write_operand(f, *op, rhs, true)
+            }
+            Expr::Call(func, args) => {
+                write!(f, "{}(", func)?;
+                for (idx, arg) in args.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", arg)?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+fn write_operand(
+    f: &mut fmt::Formatter<'_>,
+    parent: Operator,
+    child: &Expr,
+    right: bool,
+) -> fmt::Result {
+    if needs_parens(parent, child, right) {
+        write!(f, "({})", child)
+    } else {
+        write!(f, "{}", child)
+    }
+}
+
+/// Shortest spelling the tokenizer reads back as the same value: trailing
+/// fractional zeros dropped, large exponents in `1.5e30` form.
+pub fn canonical_number(num: &BigDecimal) -> String {
+    let text = num.to_string();
+    if text.contains(['e', 'E']) {
+        return num.normalized().to_scientific_notation();
+    }
+    if text.contains('.') {
+        return text.trim_end_matches('0').trim_end_matches('.').to_string();
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::evaluator::{AngleMode, Environment, eval, format_expression, parse};
+    use bigdecimal::BigDecimal;
+
+    #[test]
+    fn test_tree_eval_matches_rpn_eval() {
+        for input in [
+            "1 + 2 * 3",
+            "-(2 ^ 3) % 5",
+            "sqrt(abs(-16)) / 8",
+            "2 ^ 3 ^ 2",
+        ] {
+            assert_eq!(
+                parse(input).unwrap().eval(&Environment::new()).unwrap(),
+                eval(input).unwrap()
+            );
+        }
+        let mut env = Environment::new();
+        env.set("x", BigDecimal::from(4)).unwrap();
+        assert_eq!(
+            parse("x ^ 2 - x").unwrap().eval(&env).unwrap(),
+            BigDecimal::from(12)
+        );
+    }
+
+    #[test]
+    fn test_optimize() {
+        let cases = [
+            ("x * 1 + (2 + 3) * y ^ 1", "x + 5 * y"),
+            ("0 + x - 0", "x"),
+            ("1 * (x / 1)", "x"),
+            ("sqrt(16) * x + abs(-2)", "4 * x + 2"),
+            ("2 * 3!", "12"),
+            ("x + 1 / 0", "x + 1 / 0"),
+            ("x * 0", "x * 0"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(
+                parse(input)
+                    .unwrap()
+                    .optimize(AngleMode::Radians)
+                    .unwrap()
+                    .to_string(),
+                expected
+            );
+        }
+
+        let mut env = Environment::new();
+        env.set("x", BigDecimal::from(7)).unwrap();
+        env.set("y", BigDecimal::from(-3)).unwrap();
+        for input in [
+            "x * 1 + (2 + 3) * y ^ 1",
+            "2 * pi * x",
+            "x ^ (4 / 2) + 1 / 3",
+        ] {
+            let expr = parse(input).unwrap();
+            assert_eq!(
+                expr.optimize(AngleMode::Radians)
+                    .unwrap()
+                    .eval(&env)
+                    .unwrap(),
+                expr.eval(&env).unwrap(),
+                "optimizing {input}"
+            );
+        }
+        assert!(
+            parse("x + 1 / 0")
+                .unwrap()
+                .optimize(AngleMode::Radians)
+                .unwrap()
+                .eval(&env)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_canonical_format() {
+        let cases = [
+            ("1+2*3", "1 + 2 * 3"),
+            ("((1+2))*3", "(1 + 2) * 3"),
+            ("1-(2-3)", "1 - (2 - 3)"),
+            ("(1-2)-3", "1 - 2 - 3"),
+            ("2^(3^2)", "2 ^ 3 ^ 2"),
+            ("(2^3)^2", "(2 ^ 3) ^ 2"),
+            ("(-2)^2", "(-2) ^ 2"),
+            ("-(2^2)", "-2 ^ 2"),
+            ("- - 5", "--5"),
+            ("sqrt( 1.50 , )", "sqrt( 1.50 , )"),
+            ("SQRT(1.50)+ABS( -x )", "sqrt(1.5) + abs(-x)"),
+            ("1.2e3 + 6.02E23", "1200 + 6.02e23"),
+            ("y=  PI/2", "y = pi / 2"),
+        ];
+        for (input, expected) in cases {
+            match format_expression(input) {
+                Ok(formatted) => {
+                    assert_eq!(formatted, expected, "formatting {input}");
+                    let (_, expression) = crate::evaluator::split_assignment(&formatted);
+                    let (_, original) = crate::evaluator::split_assignment(input);
+                    assert_eq!(parse(expression).unwrap(), parse(original).unwrap());
+                }
+                Err(_) => assert_eq!(input, expected, "{input} should not parse"),
+            }
+        }
+
+        assert_eq!(format_expression("+5").unwrap(), "5");
+        assert_eq!(format_expression("3 - -+2").unwrap(), "3 - -2");
+        assert_eq!(format_expression("+(1 + 2) * 3").unwrap(), "(1 + 2) * 3");
+        assert_eq!(
+            format_expression("(3)! + -(2)² + 10 %").unwrap(),
+            "3! + -2² + 10%"
+        );
+        assert_eq!(format_expression("(1+2)! % (4)").unwrap(), "(1 + 2)! % 4");
+    }
+}
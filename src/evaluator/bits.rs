@@ -0,0 +1,166 @@
+//! Bit manipulation on integers for programmer-calculator workflows. Rotations
+//! and two's-complement conversions work on a fixed `width`, so their operand
+//! must fit in that many bits.
+
+use anyhow::{anyhow, bail};
+use bigdecimal::BigDecimal;
+use bigdecimal::num_bigint::BigInt;
+use num_traits::{One, Signed, ToPrimitive};
+
+use super::Function;
+
+/// Widest word accepted by the fixed-width functions.
+pub const MAX_BIT_WIDTH: u64 = 4096;
+
+fn integer(func: Function, value: &BigDecimal) -> anyhow::Result<BigInt> {
+    if !value.is_integer() {
+        bail!("{} is only defined for integers", func);
+    }
+    Ok(value.with_scale(0).into_bigint_and_exponent().0)
+}
+
+fn width(func: Function, value: &BigDecimal) -> anyhow::Result<u64> {
+    value
+        .to_u64()
+        .filter(|width| value.is_integer() && (1..=MAX_BIT_WIDTH).contains(width))
+        .ok_or_else(|| {
+            anyhow!(
+                "{} width must be an integer from 1 to {}",
+                func,
+                MAX_BIT_WIDTH
+            )
+        })
+}
+
+/// Number of set bits of a non-negative integer.
+pub fn popcount(value: &BigDecimal) -> anyhow::Result<BigDecimal> {
+    let n = integer(Function::Popcount, value)?;
+    if n.is_negative() {
+        bail!("popcount of a negative number depends on its width; use twos(n, width) first");
+    }
+    Ok(BigDecimal::from(
+        n.to_u64_digits()
+            .1
+            .iter()
+            .map(|limb| u64::from(limb.count_ones()))
+            .sum::<u64>(),
+    ))
+}
+
+/// Bits needed to write `|n|` in binary; 0 for zero.
+pub fn bit_length(value: &BigDecimal) -> anyhow::Result<BigDecimal> {
+    Ok(BigDecimal::from(
+        integer(Function::BitLength, value)?.bits(),
+    ))
+}
+
+/// Rotate the low `width` bits of `n` left by `k`, or right for a negative `k`.
+pub fn rotate(func: Function, args: &[BigDecimal], left: bool) -> anyhow::Result<BigDecimal> {
+    let n = integer(func, &args[0])?;
+    let k = integer(func, &args[1])?;
+    let width = width(func, &args[2])?;
+    if n.is_negative() || n.bits() > width {
+        bail!(
+            "{} operand must be a non-negative integer below 2^{}",
+            func,
+            width
+        );
+    }
+    let k = if left { k } else { -k };
+    let shift = (k % width).to_i64().unwrap_or(0).rem_euclid(width as i64) as u64;
+    if shift == 0 {
+        return Ok(BigDecimal::from(n));
+    }
+    let mask = (BigInt::one() << width) - 1;
+    let rotated: BigInt = ((&n << shift) | (&n >> (width - shift))) & mask;
+    Ok(BigDecimal::from(rotated))
+}
+
+/// The unsigned `width`-bit pattern of `n` in two's complement, so
+/// `twos(-1, 8)` is 255. `n` may be signed or already unsigned.
+pub fn twos(args: &[BigDecimal]) -> anyhow::Result<BigDecimal> {
+    let func = Function::Twos;
+    let n = integer(func, &args[0])?;
+    let width = width(func, &args[1])?;
+    let modulus = BigInt::one() << width;
+    let half = BigInt::one() << (width - 1);
+    if n < -&half || n >= modulus {
+        bail!("{} operand {} does not fit in {} bits", func, n, width);
+    }
+    Ok(BigDecimal::from(if n.is_negative() {
+        n + modulus
+    } else {
+        n
+    }))
+}
+
+/// Reinterpret the unsigned `width`-bit pattern `n` as a signed integer, so
+/// `signed(255, 8)` is -1. The inverse of [`twos`].
+pub fn signed(args: &[BigDecimal]) -> anyhow::Result<BigDecimal> {
+    let func = Function::Signed;
+    let n = integer(func, &args[0])?;
+    let width = width(func, &args[1])?;
+    if n.is_negative() || n.bits() > width {
+        bail!(
+            "{} operand must be a non-negative integer below 2^{}",
+            func,
+            width
+        );
+    }
+    let half = BigInt::one() << (width - 1);
+    Ok(BigDecimal::from(if n >= half {
+        n - (BigInt::one() << width)
+    } else {
+        n
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::evaluator::eval;
+    use bigdecimal::BigDecimal;
+
+    #[test]
+    fn test_bits() {
+        let cases = [
+            ("popcount(255)", 8),
+            ("popcount(2 ^ 100 + 1)", 2),
+            ("popcount(0)", 0),
+            ("bit_length(255)", 8),
+            ("bit_length(256)", 9),
+            ("bit_length(-256)", 9),
+            ("bit_length(0)", 0),
+            ("rotl(1, 1, 8)", 2),
+            ("rotl(128, 1, 8)", 1),
+            ("rotr(1, 1, 8)", 128),
+            ("rotl(1, -1, 8)", 128),
+            ("rotl(3, 17, 8)", 6),
+            ("rotr(2 ^ 63, 63, 64)", 1),
+            ("twos(-1, 8)", 255),
+            ("twos(-128, 8)", 128),
+            ("twos(200, 8)", 200),
+            ("twos(-2, 64) - 2 ^ 64", -2),
+            ("signed(255, 8)", -1),
+            ("signed(127, 8)", 127),
+            ("signed(32768, 16)", -32768),
+            ("signed(twos(-12345, 32), 32)", -12345),
+            ("signed(1, 1)", -1),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(
+                eval(input).unwrap(),
+                BigDecimal::from(expected),
+                "evaluating {input}"
+            );
+        }
+        assert!(eval("popcount(-1)").is_err());
+        assert!(eval("popcount(1.5)").is_err());
+        assert!(eval("rotl(256, 1, 8)").is_err());
+        assert!(eval("rotl(1, 1, 0)").is_err());
+        assert!(eval("rotl(1, 1, 10 ^ 6)").is_err());
+        assert!(eval("twos(-129, 8)").is_err());
+        assert!(eval("twos(256, 8)").is_err());
+        assert!(eval("signed(-1, 8)").is_err());
+        assert!(eval("signed(65536, 16)").is_err());
+    }
+}
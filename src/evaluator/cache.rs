@@ -0,0 +1,194 @@
+//! Results of expensive evaluations kept in a store outside the process, so a
+//! costly expression, e.g. a transcendental function at high precision, is
+//! computed once across requests and restarts. Results are keyed by the
+//! canonical token stream together with everything else that decides the
+//! value: the values of the variables used, the angle unit, the limits and
+//! the engine versions.
+
+use bigdecimal::BigDecimal;
+use sha2::{Digest, Sha256};
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+use super::context::EvalContext;
+use super::provenance::{EVALUATOR_VERSION, PARSER_VERSION};
+use super::{Environment, Token};
+
+/// Persistent key-value storage for results. Both methods may block.
+pub trait ResultStore: Send + Sync {
+    fn get(&self, key: &str) -> anyhow::Result<Option<String>>;
+    fn put(&self, key: &str, value: &str) -> anyhow::Result<()>;
+}
+
+struct Installed {
+    store: Arc<dyn ResultStore>,
+    /// Smallest [`CostEstimate::cost`](super::CostEstimate::cost) worth caching
+    min_cost: u64,
+}
+
+static STORE: RwLock<Option<Installed>> = RwLock::new(None);
+static HITS: AtomicU64 = AtomicU64::new(0);
+static MISSES: AtomicU64 = AtomicU64::new(0);
+
+/// Process-wide lookups answered from the store and those evaluated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+pub fn stats() -> CacheStats {
+    CacheStats {
+        hits: HITS.load(Ordering::Relaxed),
+        misses: MISSES.load(Ordering::Relaxed),
+    }
+}
+
+/// Cache evaluations estimated to cost at least `min_cost` in `store`, or
+/// stop caching with `None`. Typically set once at startup from configuration.
+pub fn set_store(store: Option<Arc<dyn ResultStore>>, min_cost: u64) {
+    *STORE
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner()) =
+        store.map(|store| Installed { store, min_cost });
+}
+
+/// The stored result of `rpn` when its estimated `cost` makes it worth
+/// caching, else `eval`'s, stored for next time. Hits are noted on the active
+/// [`EvalContext`](super::EvalContext). Only successful results are stored; a
+/// failing store is logged and bypassed.
+pub(super) fn get_or_eval(
+    rpn: &[Token],
+    env: &Environment,
+    ctx: &EvalContext,
+    cost: u64,
+    eval: impl FnOnce() -> anyhow::Result<BigDecimal>,
+) -> anyhow::Result<BigDecimal> {
+    let store = STORE
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .as_ref()
+        .filter(|installed| cost >= installed.min_cost)
+        .map(|installed| installed.store.clone());
+    let Some((store, key)) = store.and_then(|store| Some((store, key(rpn, env, ctx)?))) else {
+        return eval();
+    };
+    // A cancelled or late request is not answered, even from the store
+    ctx.check()?;
+    match store.get(&key) {
+        Ok(Some(value)) => match value.parse() {
+            Ok(value) => {
+                HITS.fetch_add(1, Ordering::Relaxed);
+                ctx.cache_hits.record();
+                return Ok(value);
+            }
+            Err(err) => tracing::warn!(key, %err, "Ignoring malformed cached result"),
+        },
+        Ok(None) => {}
+        Err(err) => tracing::warn!(key, %err, "Result cache lookup failed"),
+    }
+    MISSES.fetch_add(1, Ordering::Relaxed);
+    let value = eval()?;
+    if let Err(err) = store.put(&key, &encode(&value)) {
+        tracing::warn!(key, %err, "Failed to store result in cache");
+    }
+    Ok(value)
+}
+
+/// Hex SHA-256 of everything that decides the value of `rpn`; `None` when
+/// a variable is unbound, so the evaluation reports it.
+fn key(rpn: &[Token], env: &Environment, ctx: &EvalContext) -> Option<String> {
+    let limits = &ctx.limits;
+    let mut canonical = format!(
+        "parser {PARSER_VERSION} evaluator {EVALUATOR_VERSION} precision {} digits {} scale {} angles {:?}\n",
+        limits.precision,
+        limits.max_digits,
+        limits.max_scale,
+        ctx.angle_mode_in(env),
+    );
+    for token in rpn {
+        let _ = match token {
+            // Digits and scale, since `2` and `2.0` give results of different scale
+            Token::Number(num) => write!(canonical, "{} ", encode(num)),
+            Token::Var(name) => write!(canonical, "{name}={} ", encode(env.get(name)?)),
+            token => write!(canonical, "{token} "),
+        };
+    }
+    Some(format!("{:x}", Sha256::digest(canonical.as_bytes())))
+}
+
+/// `value` as digits and exponent, which parse back with the same scale.
+fn encode(value: &BigDecimal) -> String {
+    let (digits, scale) = value.as_bigint_and_exponent();
+    format!("{digits}e{}", -scale)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::{cost_estimate, eval, eval_with, shunting_yard, tokenize};
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct MemoryStore(Mutex<HashMap<String, String>>);
+
+    impl ResultStore for MemoryStore {
+        fn get(&self, key: &str) -> anyhow::Result<Option<String>> {
+            Ok(self.0.lock().unwrap().get(key).cloned())
+        }
+
+        fn put(&self, key: &str, value: &str) -> anyhow::Result<()> {
+            self.0
+                .lock()
+                .unwrap()
+                .insert(key.to_string(), value.to_string());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_encoding_keeps_scale() {
+        for value in ["2.0", "-0.00125", "1e5", "123456789012345678901234567890"] {
+            let value: BigDecimal = value.parse().unwrap();
+            let decoded: BigDecimal = encode(&value).parse().unwrap();
+            assert_eq!(
+                decoded.as_bigint_and_exponent(),
+                value.as_bigint_and_exponent()
+            );
+        }
+    }
+
+    #[test]
+    fn test_results_are_served_from_the_store() {
+        let input = "sqrt(7919) * ln(104729)";
+        let store = Arc::new(MemoryStore::default());
+        set_store(Some(store.clone()), cost_estimate(input).unwrap().cost);
+        let first = eval(input).unwrap();
+        let ctx = EvalContext::default();
+        let rpn = shunting_yard(&tokenize(input, &ctx.limits).unwrap(), &ctx.limits).unwrap();
+        let stored = key(&rpn, &Environment::new(), &ctx).unwrap();
+        assert_eq!(store.get(&stored).unwrap(), Some(encode(&first)));
+
+        // A planted value proves the second evaluation reads the store
+        store.put(&stored, "42e0").unwrap();
+        let second = eval_with(input, &Environment::new(), &ctx).unwrap();
+        set_store(None, 0);
+        assert_ne!(first, second);
+        assert_eq!(second, BigDecimal::from(42));
+        assert!(ctx.cache_hits.any());
+        let ctx = EvalContext::default();
+        eval_with(input, &Environment::new(), &ctx).unwrap();
+        assert!(!ctx.cache_hits.any());
+
+        let mut env = Environment::new();
+        env.set("x", BigDecimal::from(2)).unwrap();
+        let rpn = [Token::Var("x".to_string())];
+        let with_two = key(&rpn, &env, &ctx).unwrap();
+        env.set("x", "2.0".parse().unwrap()).unwrap();
+        assert_ne!(key(&rpn, &env, &ctx).unwrap(), with_two);
+        assert!(key(&rpn, &Environment::new(), &ctx).is_none());
+        assert!(eval_with("x", &Environment::new(), &ctx).is_err());
+    }
+}
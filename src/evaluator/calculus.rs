@@ -0,0 +1,764 @@
+//! Symbolic differentiation of expression trees, with Taylor expansion and
+//! limits built on it. Results are simplified as they are built: exact
+//! arithmetic on literals is folded and identities such as `0 * u` and `u ^ 1`
+//! are dropped, while transcendental constants such as `sin(2)` are kept
+//! symbolic. Angles are taken to be in radians.
+
+use anyhow::bail;
+use bigdecimal::{BigDecimal, RoundingMode};
+use num_traits::{One, Signed, Zero};
+use std::fmt;
+
+use super::error::{Interrupted, MemoryExceeded};
+use super::limits::limits;
+use super::precision::{divide, round_to, working_digits};
+use super::{AngleMode, Environment, Expr, Function, Operator};
+
+/// Highest order accepted by [`taylor`].
+pub const MAX_TAYLOR_ORDER: usize = 20;
+
+/// Largest derivative tree [`taylor`] evaluates. Repeated product and
+/// quotient rules grow trees geometrically, so deep expansions of expressions
+/// such as `tan(x) / x` stop here instead of running away.
+pub const MAX_TAYLOR_NODES: usize = 20_000;
+
+/// Order of the series compared by [`limit`] for quotients.
+const SERIES_ORDER: usize = 8;
+
+/// Decimal exponents of the sample offsets in [`limit`]: `10^-5` down to
+/// `10^-30` from a point, `10^5` up to `10^30` towards infinity.
+const SAMPLE_EXPONENTS: [i64; 6] = [5, 10, 15, 20, 25, 30];
+
+/// Taylor polynomial of an expression around a point.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Taylor {
+    /// `sum f⁽ᵏ⁾(a) * (var - a) ^ k / k!`, with the factorials kept symbolic
+    pub polynomial: Expr,
+    /// `f⁽ᵏ⁾(a) / k!` for `k` from 0 to the order
+    pub coefficients: Vec<BigDecimal>,
+}
+
+/// `d expr / d var`.
+pub fn derivative(expr: &Expr, var: &str) -> anyhow::Result<Expr> {
+    if !depends_on(expr, var) {
+        return Ok(number(0));
+    }
+    Ok(match expr {
+        Expr::Var(_) => number(1),
+        Expr::Number(_) | Expr::Const(_) => number(0),
+        Expr::Unary(op, u) => {
+            let du = derivative(u, var)?;
+            match op {
+                Operator::UnaryAdd => du,
+                Operator::UnarySub => neg(du),
+                Operator::Square => mul(mul(number(2), (**u).clone()), du),
+                Operator::Cube => mul(mul(number(3), pow((**u).clone(), number(2))), du),
+                Operator::Percent => div(du, number(100)),
+                _ => bail!("Cannot differentiate {}", op.symbol()),
+            }
+        }
+        Expr::Binary(op, u, v) => {
+            let (u, v) = (&**u, &**v);
+            match op {
+                Operator::Add => add(derivative(u, var)?, derivative(v, var)?),
+                Operator::Sub => sub(derivative(u, var)?, derivative(v, var)?),
+                Operator::Mul => add(
+                    mul(derivative(u, var)?, v.clone()),
+                    mul(u.clone(), derivative(v, var)?),
+                ),
+                Operator::Div if !depends_on(v, var) => div(derivative(u, var)?, v.clone()),
+                Operator::Div => div(
+                    sub(
+                        mul(derivative(u, var)?, v.clone()),
+                        mul(u.clone(), derivative(v, var)?),
+                    ),
+                    pow(v.clone(), number(2)),
+                ),
+                Operator::Pow if !depends_on(v, var) => mul(
+                    mul(v.clone(), pow(u.clone(), sub(v.clone(), number(1)))),
+                    derivative(u, var)?,
+                ),
+                Operator::Pow if !depends_on(u, var) => mul(
+                    mul(expr.clone(), call(Function::Ln, u.clone())),
+                    derivative(v, var)?,
+                ),
+                // d(u^v) = u^v * (v' * ln(u) + v * u' / u)
+                Operator::Pow => mul(
+                    expr.clone(),
+                    add(
+                        mul(derivative(v, var)?, call(Function::Ln, u.clone())),
+                        div(mul(v.clone(), derivative(u, var)?), u.clone()),
+                    ),
+                ),
+                _ => bail!("Cannot differentiate {}", op.symbol()),
+            }
+        }
+        Expr::Call(func, args) => {
+            let [u] = args.as_slice() else {
+                bail!("Cannot differentiate {}", func);
+            };
+            let outer = match func {
+                Function::Sqrt => div(number(1), mul(number(2), expr.clone())),
+                Function::Abs => div(u.clone(), expr.clone()),
+                Function::Sin => call(Function::Cos, u.clone()),
+                Function::Cos => neg(call(Function::Sin, u.clone())),
+                Function::Tan => div(number(1), pow(call(Function::Cos, u.clone()), number(2))),
+                Function::Exp => expr.clone(),
+                Function::Ln => div(number(1), u.clone()),
+                _ => bail!("Cannot differentiate {}", func),
+            };
+            mul(outer, derivative(u, var)?)
+        }
+    })
+}
+
+/// The derivative of `expr` in `var` at `point`, given the `derivative`
+/// formula, evaluated in `env` (in radians). Where the formula is undefined
+/// but `expr` is not, as for `x / abs(x)` from `abs(x)` at 0, the derivative
+/// is the limit of the formula when both sides agree, and the point is
+/// reported as not differentiable otherwise.
+pub fn derivative_at(
+    expr: &Expr,
+    derivative: &Expr,
+    var: &str,
+    point: &BigDecimal,
+    env: &Environment,
+) -> anyhow::Result<BigDecimal> {
+    let mut env = env.clone();
+    env.set(var, point.clone())?;
+    env.set_angle_mode(AngleMode::Radians);
+    let err = match derivative.eval(&env) {
+        Ok(value) => return Ok(value),
+        Err(err) => err,
+    };
+    // Running out of time or memory says nothing about the point
+    if err.is::<Interrupted>() || err.is::<MemoryExceeded>() {
+        return Err(err);
+    }
+    if let Err(undefined) = expr.eval(&env) {
+        bail!("Not differentiable at {} = {}: {}", var, point, undefined);
+    }
+    let sides = limit(derivative, var, &Approach::Point(point.clone()), &env)?;
+    match (sides.left, sides.right) {
+        (Some(Behavior::Finite(left)), Some(Behavior::Finite(right))) if close(&left, &right) => {
+            Ok(left)
+        }
+        (Some(left), Some(right))
+            if left != Behavior::Undefined && right != Behavior::Undefined =>
+        {
+            bail!(
+                "Not differentiable at {} = {}: the derivative is {} from the left and {} from the right",
+                var,
+                point,
+                left,
+                right
+            )
+        }
+        _ => bail!("Not differentiable at {} = {}: {}", var, point, err),
+    }
+}
+
+/// Expand `expr` in powers of `var - around` up to `order`, evaluating the
+/// derivatives in `env` (in radians).
+pub fn taylor(
+    expr: &Expr,
+    var: &str,
+    around: &BigDecimal,
+    order: usize,
+    env: &Environment,
+) -> anyhow::Result<Taylor> {
+    if order > MAX_TAYLOR_ORDER {
+        bail!("Order must be at most {}", MAX_TAYLOR_ORDER);
+    }
+    let mut env = env.clone();
+    env.set(var, around.clone())?;
+    env.set_angle_mode(AngleMode::Radians);
+
+    let offset = sub(Expr::Var(var.to_string()), Expr::Number(around.clone()));
+    let mut polynomial: Option<Expr> = None;
+    let mut coefficients = Vec::with_capacity(order + 1);
+    let mut current = expr.clone();
+    let mut factorial = BigDecimal::one();
+    for k in 0..=order {
+        if k > 0 {
+            current = derivative(&current, var)?;
+            if size(&current) > MAX_TAYLOR_NODES {
+                bail!("Derivative {} is too large to expand; lower the order", k);
+            }
+            factorial *= BigDecimal::from(k as u64);
+        }
+        let value = current.eval(&env).map_err(|err| {
+            anyhow::anyhow!("Derivative {} is undefined at {}: {}", k, around, err)
+        })?;
+        coefficients.push(round_to(
+            divide(&value, &factorial, working_digits()),
+            limits().precision,
+        ));
+        if value.is_zero() {
+            continue;
+        }
+        let magnitude = Expr::Number(value.abs());
+        let term = div(
+            mul(magnitude, pow(offset.clone(), number(k as i32))),
+            Expr::Number(factorial.clone()),
+        );
+        polynomial = Some(match (polynomial, value < BigDecimal::zero()) {
+            (None, negative) if negative => neg(term),
+            (None, _) => term,
+            (Some(sum), true) => sub(sum, term),
+            (Some(sum), false) => add(sum, term),
+        });
+    }
+    Ok(Taylor {
+        polynomial: polynomial.unwrap_or_else(|| number(0)),
+        coefficients,
+    })
+}
+
+/// Where the variable goes in [`limit`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Approach {
+    Point(BigDecimal),
+    PositiveInfinity,
+    NegativeInfinity,
+}
+
+/// Behavior of an expression as the variable approaches its target.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Behavior {
+    Finite(BigDecimal),
+    PositiveInfinity,
+    NegativeInfinity,
+    /// No limit: the values oscillate, or the one-sided limits differ
+    Diverges,
+    /// The expression is not defined on that side
+    Undefined,
+}
+
+impl fmt::Display for Behavior {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Behavior::Finite(value) => write!(f, "{}", value),
+            Behavior::PositiveInfinity => write!(f, "infinity"),
+            Behavior::NegativeInfinity => write!(f, "-infinity"),
+            Behavior::Diverges => write!(f, "diverges"),
+            Behavior::Undefined => write!(f, "undefined"),
+        }
+    }
+}
+
+/// How [`limit`] found its answer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    /// The expression is defined and continuous at the point
+    Direct,
+    /// Leading terms of the Taylor series of a quotient's numerator and denominator
+    Series,
+    /// Samples approaching from each side
+    Numeric,
+}
+
+impl Method {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Method::Direct => "direct",
+            Method::Series => "series",
+            Method::Numeric => "numeric",
+        }
+    }
+}
+
+/// Result of [`limit`]. `left` and `right` are the one-sided limits; only
+/// the side facing the number line is set for an infinite target.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Limit {
+    pub value: Behavior,
+    pub left: Option<Behavior>,
+    pub right: Option<Behavior>,
+    pub method: Method,
+}
+
+/// The limit of `expr` as `var` approaches `to`, evaluated in `env` (in
+/// radians). Quotients that are undefined at a point are resolved from the
+/// leading terms of their series; anything else is sampled on both sides,
+/// reporting divergence when the samples neither settle nor grow steadily.
+pub fn limit(expr: &Expr, var: &str, to: &Approach, env: &Environment) -> anyhow::Result<Limit> {
+    let mut env = env.clone();
+    env.set_angle_mode(AngleMode::Radians);
+    let mut at = |point: BigDecimal| {
+        env.set(var, point)?;
+        expr.eval(&env)
+    };
+
+    let point = match to {
+        Approach::Point(point) => point,
+        Approach::PositiveInfinity => {
+            let left = sample(&mut at, power_of_ten);
+            return Ok(Limit {
+                value: left.clone(),
+                left: Some(left),
+                right: None,
+                method: Method::Numeric,
+            });
+        }
+        Approach::NegativeInfinity => {
+            let right = sample(&mut at, |exponent| -power_of_ten(exponent));
+            return Ok(Limit {
+                value: right.clone(),
+                left: None,
+                right: Some(right),
+                method: Method::Numeric,
+            });
+        }
+    };
+    let direct = at(point.clone()).ok();
+    let left = sample(&mut at, |exponent| point - power_of_ten(-exponent));
+    let right = sample(&mut at, |exponent| point + power_of_ten(-exponent));
+
+    if let Some(value) = direct {
+        let continuous = [&left, &right].iter().all(|side| match side {
+            Behavior::Finite(side) => close(side, &value),
+            _ => false,
+        });
+        if continuous {
+            let value = Behavior::Finite(value);
+            return Ok(Limit {
+                value: value.clone(),
+                left: Some(value.clone()),
+                right: Some(value),
+                method: Method::Direct,
+            });
+        }
+    } else if let Expr::Binary(Operator::Div, numerator, denominator) = expr
+        && let Some((left, right)) = series_quotient(numerator, denominator, var, point, &env)
+    {
+        return Ok(Limit {
+            value: combine(&left, &right),
+            left: Some(left),
+            right: Some(right),
+            method: Method::Series,
+        });
+    }
+    Ok(Limit {
+        value: combine(&left, &right),
+        left: Some(left),
+        right: Some(right),
+        method: Method::Numeric,
+    })
+}
+
+/// One-sided limits of `numerator / denominator` at `point` from the first
+/// non-zero coefficients of their Taylor series, or `None` when either has no
+/// usable series there.
+fn series_quotient(
+    numerator: &Expr,
+    denominator: &Expr,
+    var: &str,
+    point: &BigDecimal,
+    env: &Environment,
+) -> Option<(Behavior, Behavior)> {
+    let leading = |expr: &Expr| {
+        let series = taylor(expr, var, point, SERIES_ORDER, env).ok()?;
+        let negligible = power_of_ten(-(limits().precision as i64) / 2);
+        series
+            .coefficients
+            .into_iter()
+            .enumerate()
+            .find(|(_, coefficient)| coefficient.abs() > negligible)
+    };
+    let (p, a) = leading(numerator)?;
+    let (q, b) = leading(denominator)?;
+    if p >= q {
+        let value = if p > q {
+            BigDecimal::zero()
+        } else {
+            round_to(divide(&a, &b, working_digits()), limits().precision)
+        };
+        return Some((Behavior::Finite(value.clone()), Behavior::Finite(value)));
+    }
+    // a (x - point)^p / (b (x - point)^q) blows up, flipping sign on the left
+    // when q - p is odd
+    let positive = a.is_negative() == b.is_negative();
+    let infinity = |positive: bool| {
+        if positive {
+            Behavior::PositiveInfinity
+        } else {
+            Behavior::NegativeInfinity
+        }
+    };
+    let odd = (q - p) % 2 == 1;
+    Some((infinity(positive != odd), infinity(positive)))
+}
+
+/// Classify the values of `at` over the sample points `offset(exponent)`.
+fn sample(
+    at: &mut impl FnMut(BigDecimal) -> anyhow::Result<BigDecimal>,
+    offset: impl Fn(i64) -> BigDecimal,
+) -> Behavior {
+    let values: Vec<BigDecimal> = SAMPLE_EXPONENTS
+        .iter()
+        .filter_map(|&exponent| at(offset(exponent)).ok())
+        .collect();
+    if values.is_empty() {
+        return Behavior::Undefined;
+    }
+    if values.len() < 3 {
+        return Behavior::Diverges;
+    }
+    let steps: Vec<BigDecimal> = values
+        .windows(2)
+        .map(|pair| (&pair[1] - &pair[0]).abs())
+        .collect();
+    let last = values.last().unwrap();
+    let step = steps.last().unwrap();
+    let settling = steps.windows(2).all(|pair| pair[1] <= pair[0]);
+    if settling && close(last, &(last + step)) {
+        return Behavior::Finite(settle(last, step));
+    }
+    let growing = values.windows(2).all(|pair| pair[1].abs() > pair[0].abs());
+    let same_sign = values
+        .iter()
+        .all(|value| value.is_negative() == last.is_negative());
+    if growing && same_sign && last.abs() > power_of_ten(10) {
+        return if last.is_negative() {
+            Behavior::NegativeInfinity
+        } else {
+            Behavior::PositiveInfinity
+        };
+    }
+    Behavior::Diverges
+}
+
+/// Round `value` to the decimal places still changing by less than `step`.
+fn settle(value: &BigDecimal, step: &BigDecimal) -> BigDecimal {
+    if step.is_zero() {
+        return value.normalized();
+    }
+    let magnitude = step.digits() as i64 - step.fractional_digit_count() - 1;
+    value
+        .with_scale_round(-magnitude - 1, RoundingMode::HalfEven)
+        .normalized()
+}
+
+/// The two-sided limit from the one-sided ones. A side where the expression is
+/// undefined defers to the other, as for `x * ln(x)` at 0.
+fn combine(left: &Behavior, right: &Behavior) -> Behavior {
+    match (left, right) {
+        (Behavior::Undefined, side) | (side, Behavior::Undefined) => side.clone(),
+        (Behavior::Finite(a), Behavior::Finite(b)) if close(a, b) => left.clone(),
+        _ if left == right => left.clone(),
+        _ => Behavior::Diverges,
+    }
+}
+
+/// Agreement to about ten significant digits, or absolutely below 1.
+fn close(a: &BigDecimal, b: &BigDecimal) -> bool {
+    let scale = a.abs().max(b.abs()).max(BigDecimal::one());
+    (a - b).abs() <= scale * power_of_ten(-10)
+}
+
+fn power_of_ten(exponent: i64) -> BigDecimal {
+    BigDecimal::new(1.into(), -exponent)
+}
+
+/// Whether `var` occurs in `expr`.
+pub fn depends_on(expr: &Expr, var: &str) -> bool {
+    match expr {
+        Expr::Var(name) => name == var,
+        Expr::Number(_) | Expr::Const(_) => false,
+        Expr::Unary(_, operand) => depends_on(operand, var),
+        Expr::Binary(_, lhs, rhs) => depends_on(lhs, var) || depends_on(rhs, var),
+        Expr::Call(_, args) => args.iter().any(|arg| depends_on(arg, var)),
+    }
+}
+
+fn size(expr: &Expr) -> usize {
+    match expr {
+        Expr::Number(_) | Expr::Const(_) | Expr::Var(_) => 1,
+        Expr::Unary(_, operand) => 1 + size(operand),
+        Expr::Binary(_, lhs, rhs) => 1 + size(lhs) + size(rhs),
+        Expr::Call(_, args) => 1 + args.iter().map(size).sum::<usize>(),
+    }
+}
+
+fn number(value: i32) -> Expr {
+    Expr::Number(BigDecimal::from(value))
+}
+
+fn literal(expr: &Expr) -> Option<&BigDecimal> {
+    match expr {
+        Expr::Number(num) => Some(num),
+        _ => None,
+    }
+}
+
+fn is(expr: &Expr, value: i32) -> bool {
+    literal(expr).is_some_and(|num| *num == BigDecimal::from(value))
+}
+
+fn call(func: Function, arg: Expr) -> Expr {
+    Expr::Call(func, vec![arg])
+}
+
+fn neg(u: Expr) -> Expr {
+    match u {
+        Expr::Number(num) => Expr::Number(-num),
+        Expr::Unary(Operator::UnarySub, operand) => *operand,
+        u => Expr::Unary(Operator::UnarySub, Box::new(u)),
+    }
+}
+
+fn add(u: Expr, v: Expr) -> Expr {
+    match (literal(&u), literal(&v)) {
+        (Some(a), Some(b)) => Expr::Number(a + b),
+        (Some(a), _) if a.is_zero() => v,
+        (_, Some(b)) if b.is_zero() => u,
+        _ => match v {
+            Expr::Unary(Operator::UnarySub, operand) => sub(u, *operand),
+            Expr::Number(num) if num < BigDecimal::zero() => sub(u, Expr::Number(-num)),
+            v => Expr::Binary(Operator::Add, Box::new(u), Box::new(v)),
+        },
+    }
+}
+
+fn sub(u: Expr, v: Expr) -> Expr {
+    match (literal(&u), literal(&v)) {
+        (Some(a), Some(b)) => Expr::Number(a - b),
+        (Some(a), _) if a.is_zero() => neg(v),
+        (_, Some(b)) if b.is_zero() => u,
+        (_, Some(b)) if *b < BigDecimal::zero() => add(u, Expr::Number(-b)),
+        _ if u == v => number(0),
+        _ => Expr::Binary(Operator::Sub, Box::new(u), Box::new(v)),
+    }
+}
+
+fn mul(u: Expr, v: Expr) -> Expr {
+    match (literal(&u), literal(&v)) {
+        (Some(a), Some(b)) => Expr::Number(a * b),
+        _ if is(&u, 0) || is(&v, 0) => number(0),
+        (Some(a), _) if a.is_one() => v,
+        (_, Some(b)) if b.is_one() => u,
+        _ if is(&u, -1) => neg(v),
+        _ if is(&v, -1) => neg(u),
+        // Keep literal factors in front: `2 * x` rather than `x * 2`
+        (None, Some(_)) => mul(v, u),
+        _ => match (u, v) {
+            (Expr::Unary(Operator::UnarySub, u), v) => neg(mul(*u, v)),
+            (u, Expr::Unary(Operator::UnarySub, v)) => neg(mul(u, *v)),
+            (u, v) => Expr::Binary(Operator::Mul, Box::new(u), Box::new(v)),
+        },
+    }
+}
+
+fn div(u: Expr, v: Expr) -> Expr {
+    if is(&u, 0) {
+        return number(0);
+    }
+    if is(&v, 1) {
+        return u;
+    }
+    match (u, v) {
+        (Expr::Unary(Operator::UnarySub, u), v) => neg(div(*u, v)),
+        (u, v) => Expr::Binary(Operator::Div, Box::new(u), Box::new(v)),
+    }
+}
+
+fn pow(u: Expr, v: Expr) -> Expr {
+    if is(&v, 0) {
+        return number(1);
+    }
+    if is(&v, 1) {
+        return u;
+    }
+    Expr::Binary(Operator::Pow, Box::new(u), Box::new(v))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::{Environment, parse};
+
+    fn d(input: &str) -> String {
+        derivative(&parse(input).unwrap(), "x").unwrap().to_string()
+    }
+
+    #[test]
+    fn test_derivative() {
+        let cases = [
+            ("x ^ 3", "3 * x ^ 2"),
+            ("5 * x ^ 2 + 3 * x - 7", "5 * (2 * x) + 3"),
+            ("y * x", "y"),
+            ("x * sin(x)", "sin(x) + x * cos(x)"),
+            ("sin(2 * x)", "2 * cos(2 * x)"),
+            ("cos(x)", "-sin(x)"),
+            ("exp(x ^ 2)", "exp(x ^ 2) * (2 * x)"),
+            ("ln(x)", "1 / x"),
+            ("1 / x", "-1 / x ^ 2"),
+            ("x / 2", "1 / 2"),
+            ("2 ^ x", "2 ^ x * ln(2)"),
+            ("x ^ x", "x ^ x * (ln(x) + x / x)"),
+            ("sqrt(x)", "1 / (2 * sqrt(x))"),
+            ("(x + 1)²", "2 * (x + 1)"),
+            ("pi * x", "pi"),
+            ("sin(2)", "0"),
+            ("-x", "-1"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(d(input), expected, "differentiating {input}");
+        }
+
+        // Check a messier one numerically: d/dx tan(x) ^ 2 = 2 tan(x) / cos(x)^2
+        let mut env = Environment::new();
+        env.set("x", BigDecimal::from(1)).unwrap();
+        let derived = derivative(&parse("tan(x) ^ 2").unwrap(), "x").unwrap();
+        let expected = parse("2 * tan(x) / cos(x) ^ 2").unwrap();
+        let delta = derived.eval(&env).unwrap() - expected.eval(&env).unwrap();
+        assert!(delta.abs() < "1e-20".parse::<BigDecimal>().unwrap());
+
+        assert!(derivative(&parse("x!").unwrap(), "x").is_err());
+        assert!(derivative(&parse("x % 3").unwrap(), "x").is_err());
+        assert!(derivative(&parse("fib(x)").unwrap(), "x").is_err());
+        assert_eq!(d("fib(3) * x"), "fib(3)");
+    }
+
+    #[test]
+    fn test_derivative_at() {
+        let at = |input: &str, point: &str| {
+            let expr = parse(input).unwrap();
+            let derived = derivative(&expr, "x").unwrap();
+            derivative_at(
+                &expr,
+                &derived,
+                "x",
+                &point.parse().unwrap(),
+                &Environment::new(),
+            )
+            .map(|value| value.to_string())
+            .map_err(|err| err.to_string())
+        };
+        assert_eq!(at("abs(x)", "-2"), Ok("-1".to_string()));
+        assert_eq!(
+            at("abs(x)", "0"),
+            Err("Not differentiable at x = 0: the derivative is -1 from the left and 1 from the right".to_string())
+        );
+        assert_eq!(
+            at("abs(x - 3)", "3"),
+            Err("Not differentiable at x = 3: the derivative is -1 from the left and 1 from the right".to_string())
+        );
+        // The formula is undefined at 0, but the function is smooth there
+        assert_eq!(at("abs(x) ^ 2", "0"), Ok("0".to_string()));
+        assert_eq!(
+            at("sqrt(x)", "0"),
+            Err("Not differentiable at x = 0: Division by zero".to_string())
+        );
+        assert!(
+            at("ln(x)", "0")
+                .unwrap_err()
+                .starts_with("Not differentiable at x = 0: ")
+        );
+    }
+
+    #[test]
+    fn test_limit() {
+        let at = |input: &str, to: Approach| {
+            let expr = parse(input).unwrap();
+            limit(&expr, "x", &to, &Environment::new()).unwrap()
+        };
+        let point = |value: i32| Approach::Point(BigDecimal::from(value));
+        let finite = |value: &str| Behavior::Finite(value.parse().unwrap());
+
+        let sinc = at("sin(x) / x", point(0));
+        assert_eq!((sinc.value, sinc.method), (finite("1"), Method::Series));
+        assert_eq!(at("(1 - cos(x)) / x ^ 2", point(0)).value, finite("0.5"));
+        assert_eq!(at("(x ^ 2 - 1) / (x - 1)", point(1)).value, finite("2"));
+        assert_eq!(at("x ^ 2 / sin(x)", point(0)).value, finite("0"));
+
+        let continuous = at("x ^ 2 + 1", point(3));
+        assert_eq!(
+            (continuous.value, continuous.method),
+            (finite("10"), Method::Direct)
+        );
+
+        let reciprocal = at("1 / x", point(0));
+        assert_eq!(reciprocal.value, Behavior::Diverges);
+        assert_eq!(reciprocal.left, Some(Behavior::NegativeInfinity));
+        assert_eq!(reciprocal.right, Some(Behavior::PositiveInfinity));
+        assert_eq!(at("1 / x ^ 2", point(0)).value, Behavior::PositiveInfinity);
+
+        let sign = at("abs(x) / x", point(0));
+        assert_eq!(sign.value, Behavior::Diverges);
+        assert_eq!(
+            (sign.left, sign.right),
+            (Some(finite("-1")), Some(finite("1")))
+        );
+        assert_eq!(at("x * ln(x)", point(0)).value, finite("0"));
+        assert_eq!(at("sin(1 / x)", point(0)).value, Behavior::Diverges);
+        assert_eq!(at("ln(x)", point(-1)).value, Behavior::Undefined);
+
+        assert_eq!(
+            at("(2 * x + 1) / (x + 3)", Approach::PositiveInfinity).value,
+            finite("2")
+        );
+        assert_eq!(
+            at("sin(x) / x", Approach::PositiveInfinity).value,
+            finite("0")
+        );
+        assert_eq!(
+            at("x ^ 2", Approach::NegativeInfinity).value,
+            Behavior::PositiveInfinity
+        );
+        assert_eq!(at("exp(x)", Approach::NegativeInfinity).value, finite("0"));
+    }
+
+    #[test]
+    fn test_taylor() {
+        let expand = |input: &str, around: i32, order: usize| {
+            let expr = parse(input).unwrap();
+            taylor(
+                &expr,
+                "x",
+                &BigDecimal::from(around),
+                order,
+                &Environment::new(),
+            )
+        };
+
+        let sine = expand("sin(x)", 0, 7).unwrap();
+        assert_eq!(
+            sine.polynomial.to_string(),
+            "x - x ^ 3 / 6 + x ^ 5 / 120 - x ^ 7 / 5040"
+        );
+        assert_eq!(sine.coefficients[0], BigDecimal::zero());
+        let error = &sine.coefficients[5] * BigDecimal::from(120) - BigDecimal::one();
+        assert!(error.abs() < "1e-90".parse::<BigDecimal>().unwrap());
+
+        let cubic = expand("x ^ 3 - 2 * x", 1, 4).unwrap();
+        assert_eq!(
+            cubic.polynomial.to_string(),
+            "-1 + (x - 1) + 6 * (x - 1) ^ 2 / 2 + 6 * (x - 1) ^ 3 / 6"
+        );
+        let coefficients: Vec<String> = cubic.coefficients.iter().map(|c| c.to_string()).collect();
+        assert_eq!(coefficients, ["-1", "1", "3", "1", "0"]);
+        assert_eq!(
+            expand("1 / x", -1, 1).unwrap().polynomial.to_string(),
+            "-1 - (x + 1)"
+        );
+
+        assert!(expand("sqrt(x)", 0, 2).is_err());
+        assert!(expand("x", 0, MAX_TAYLOR_ORDER + 1).is_err());
+        assert_eq!(
+            expand("exp(x)", 0, MAX_TAYLOR_ORDER)
+                .unwrap()
+                .coefficients
+                .len(),
+            21
+        );
+        let error = expand("tan(x) / (1 + x ^ 2)", 0, MAX_TAYLOR_ORDER).unwrap_err();
+        assert!(error.to_string().contains("too large"));
+        let tangent = expand("tan(x)", 0, 5).unwrap();
+        assert_eq!(
+            tangent.polynomial.to_string(),
+            "x + 2 * x ^ 3 / 6 + 16 * x ^ 5 / 120"
+        );
+    }
+}
@@ -0,0 +1,92 @@
+//! Comments in expressions copied from scripts and notebooks: `# ...` to the
+//! end of the line, `// ...` when it starts a line, and `/* ... */` blocks.
+//! A `#` directly after an operand, as in `7#`, is the primorial operator.
+
+use std::borrow::Cow;
+
+use super::error::ParseError;
+
+/// Blank out comments with spaces, keeping newlines, so spans into the result
+/// still point at the original input. A `//` after an operand is floor division.
+pub fn strip_comments(input: &str) -> Result<Cow<'_, str>, ParseError> {
+    if !input.contains(['#', '/']) {
+        return Ok(Cow::Borrowed(input));
+    }
+    let chars: Vec<char> = input.chars().collect();
+    let mut out = String::with_capacity(input.len());
+    let mut line_start = true;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        let primorial =
+            c == '#' && i > 0 && (chars[i - 1].is_ascii_alphanumeric() || chars[i - 1] == ')');
+        let end = if (c == '#' && !primorial) || (c == '/' && next == Some('/') && line_start) {
+            chars[i..]
+                .iter()
+                .position(|&c| c == '\n')
+                .map_or(chars.len(), |offset| i + offset)
+        } else if c == '/' && next == Some('*') {
+            let close = chars[i + 2..]
+                .windows(2)
+                .position(|pair| pair == ['*', '/'])
+                .ok_or_else(|| ParseError::new("Unterminated comment", i..chars.len()))?;
+            i + 2 + close + 2
+        } else {
+            out.push(c);
+            if c == '\n' {
+                line_start = true;
+            } else if !c.is_whitespace() {
+                line_start = false;
+            }
+            i += 1;
+            continue;
+        };
+        for &c in &chars[i..end] {
+            out.push(if c == '\n' { '\n' } else { ' ' });
+        }
+        i = end;
+    }
+    Ok(Cow::Owned(out))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::EvalContext;
+    use crate::evaluator::{Environment, eval, eval_in};
+    use bigdecimal::BigDecimal;
+
+    #[test]
+    fn test_strip_comments() {
+        assert_eq!(strip_comments("1 + 2 # three").unwrap(), "1 + 2        ");
+        assert_eq!(strip_comments("1 /* x */ + 2").unwrap(), "1         + 2");
+        assert_eq!(
+            strip_comments("// note\n7 // 2").unwrap(),
+            "       \n7 // 2"
+        );
+        assert_eq!(
+            strip_comments("7# + (3)# # sum").unwrap(),
+            "7# + (3)#      "
+        );
+        let err = strip_comments("1 + /* open").unwrap_err();
+        assert_eq!((err.span.start, err.span.end), (4, 11));
+    }
+
+    #[test]
+    fn test_eval_with_comments() {
+        assert_eq!(
+            eval("7 // 2 # floor division").unwrap(),
+            BigDecimal::from(3)
+        );
+        assert_eq!(eval("2 /* two */ * 3").unwrap(), BigDecimal::from(6));
+        let mut env = Environment::new();
+        let script = "// setup\nrate = 5; # percent; not a statement\nrate * 2";
+        assert_eq!(
+            eval_in(script, &mut env, &EvalContext::default()).unwrap(),
+            BigDecimal::from(10)
+        );
+        assert!(eval("# only a comment").is_err());
+        assert_eq!(eval("5# # primorial").unwrap(), BigDecimal::from(30));
+    }
+}
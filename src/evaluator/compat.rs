@@ -0,0 +1,174 @@
+//! Tolerant rewriting of syntax copied from other tools into plain infix:
+//! Mathematica's `2*^3` and `Sin[x]`, subscripted logarithms such as
+//! `log_2(8)` or `log_{2}(8)`, Python's `**`, and typographic operators such
+//! as `×`, `÷` and `−`. Only forms that would not parse otherwise are touched,
+//! and LaTeX commands (`\sqrt[3]{x}`) are left for the LaTeX pass.
+
+use super::limits::DepthBudget;
+use anyhow::bail;
+use std::borrow::Cow;
+
+/// `input` with foreign syntax rewritten, borrowed when there is none.
+pub fn normalize(input: &str) -> anyhow::Result<Cow<'_, str>> {
+    let foreign = input.contains(['[', '×', '÷', '−', '·', '⋅', 'π'])
+        || input.contains("**")
+        || input.contains("*^")
+        || input.to_ascii_lowercase().contains("log");
+    if !foreign {
+        return Ok(Cow::Borrowed(input));
+    }
+    let chars: Vec<char> = input.chars().collect();
+    Ok(Cow::Owned(rewrite(&chars, DepthBudget::from_limits())?))
+}
+
+fn rewrite(chars: &[char], budget: DepthBudget) -> anyhow::Result<String> {
+    let mut out = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_ascii_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let mut word: String = chars[start..i].iter().collect();
+            if start > 0 && chars[start - 1] == '\\' {
+                out.push_str(&word);
+                continue;
+            }
+            if word.eq_ignore_ascii_case("log_") && chars.get(i) == Some(&'{') {
+                let Some(close) = chars[i..].iter().position(|&c| c == '}') else {
+                    bail!("Missing `}}` after `{word}{{`");
+                };
+                word.extend(&chars[i + 1..i + close]);
+                i += close + 1;
+            }
+            let mut next = i;
+            while next < chars.len() && chars[next].is_whitespace() {
+                next += 1;
+            }
+            match chars.get(next) {
+                Some(&open @ ('(' | '[')) => {
+                    let (arguments, end) = arguments(chars, next + 1)?;
+                    let budget = budget.descend()?;
+                    let arguments = arguments
+                        .iter()
+                        .map(|argument| rewrite(argument, budget))
+                        .collect::<anyhow::Result<Vec<_>>>()?;
+                    out.push_str(&call(&word, open, &arguments)?);
+                    i = end;
+                }
+                _ => out.push_str(&word),
+            }
+            continue;
+        }
+        match (c, chars.get(i + 1)) {
+            ('*', Some('^')) => {
+                out.push_str("*10^");
+                i += 1;
+            }
+            ('*', Some('*')) => {
+                out.push('^');
+                i += 1;
+            }
+            ('×' | '·' | '⋅', _) => out.push('*'),
+            ('÷', _) => out.push('/'),
+            ('−', _) => out.push('-'),
+            ('π', _) => out.push_str("pi"),
+            _ => out.push(c),
+        }
+        i += 1;
+    }
+    Ok(out)
+}
+
+/// A call of `name` with already rewritten arguments, opened by `open`.
+fn call(name: &str, open: char, arguments: &[String]) -> anyhow::Result<String> {
+    let lower = name.to_ascii_lowercase();
+    let base = match (lower.as_str(), open, arguments) {
+        ("log2", _, [x]) => Some(("2", x)),
+        ("log10", _, [x]) => Some(("10", x)),
+        // Mathematica: Log[x] is natural, Log[b, x] has base b
+        ("log", '[', [x]) => return Ok(format!("ln({x})")),
+        ("log", '[', [b, x]) => Some((b.as_str(), x)),
+        _ => match lower.strip_prefix("log_") {
+            Some(b) if !b.is_empty() => match arguments {
+                [x] => Some((&name[4..], x)),
+                _ => bail!("{name} takes 1 argument, got {}", arguments.len()),
+            },
+            _ => None,
+        },
+    };
+    Ok(match base {
+        Some((b, x)) => format!("(ln({}) / ln({}))", x.trim(), b.trim()),
+        None => format!("{name}({})", arguments.join(",")),
+    })
+}
+
+/// The raw arguments of a call whose opening bracket precedes `start`, and
+/// the position after its closing one. `(` and `[` nest alike.
+fn arguments(chars: &[char], start: usize) -> anyhow::Result<(Vec<Vec<char>>, usize)> {
+    let mut arguments = Vec::new();
+    let mut current = Vec::new();
+    let mut depth = 0;
+    for (i, &c) in chars.iter().enumerate().skip(start) {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' if depth == 0 => {
+                if current.iter().any(|c: &char| !c.is_whitespace()) || !arguments.is_empty() {
+                    arguments.push(current);
+                }
+                return Ok((arguments, i + 1));
+            }
+            ')' | ']' => depth -= 1,
+            ',' if depth == 0 => {
+                arguments.push(std::mem::take(&mut current));
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    bail!("Missing closing bracket in function call")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::eval;
+    use bigdecimal::BigDecimal;
+
+    fn normalized(input: &str) -> String {
+        normalize(input).unwrap().into_owned()
+    }
+
+    #[test]
+    fn test_normalize_foreign_syntax() {
+        assert_eq!(normalized("2*^3"), "2*10^3");
+        assert_eq!(
+            normalized("Sin[x] + Sqrt[Abs[-4]]"),
+            "Sin(x) + Sqrt(Abs(-4))"
+        );
+        assert_eq!(normalized("log_2(8)"), "(ln(8) / ln(2))");
+        assert_eq!(normalized("log_{10}(1000)"), "(ln(1000) / ln(10))");
+        assert_eq!(normalized("Log[2, 8] + Log[E]"), "(ln(8) / ln(2)) + ln(E)");
+        assert_eq!(normalized("2 ** 3 × 4 ÷ 2 − 1"), "2 ^ 3 * 4 / 2 - 1");
+        assert_eq!(normalized("\\sqrt[3]{8}"), "\\sqrt[3]{8}");
+        assert!(matches!(normalize("1 + 2"), Ok(Cow::Borrowed(_))));
+        assert!(normalize("Sin[x").is_err());
+        assert!(normalize("log_2(1, 2)").is_err());
+    }
+
+    #[test]
+    fn test_eval_foreign_syntax() {
+        assert_eq!(eval("2*^3").unwrap(), BigDecimal::from(2000));
+        assert_eq!(eval("5*^-1 * 4").unwrap(), BigDecimal::from(2));
+        assert_eq!(eval("Sqrt[Abs[-16]] + 2**3").unwrap(), BigDecimal::from(12));
+        assert_eq!(
+            eval("round_half_up(log_2(8), 10)").unwrap(),
+            BigDecimal::from(3)
+        );
+        assert_eq!(eval("Cos[0]").unwrap(), BigDecimal::from(1));
+        assert_eq!(eval("\\sqrt{16} \\cdot 2").unwrap(), BigDecimal::from(8));
+    }
+}
@@ -0,0 +1,323 @@
+//! Per-request evaluation budget. Callers build one [`EvalContext`] per request
+//! and pass it to the evaluator's entry points such as
+//! [`eval_with`](super::eval_with), which hand it on to tokenizing, parsing
+//! and evaluation. The numeric kernels below them (operators, functions,
+//! rounding) take no context; the entry points enter it on the calling thread
+//! for them, so [`limits`](super::limits::limits), [`checkpoint`] and
+//! [`charge_memory`] answer for the request being evaluated.
+
+use super::error::{Interrupted, MemoryExceeded};
+use super::limits::Limits;
+use super::{AngleMode, Environment};
+use crate::i18n::Locale;
+use std::cell::RefCell;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Checkpoints between two reads of the clock.
+const CLOCK_INTERVAL: u32 = 64;
+
+/// Shared flag a caller sets to stop an evaluation it no longer waits for.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// A guard cancelling the token when dropped, e.g. with the future of a
+    /// request whose client disconnected.
+    pub fn drop_guard(&self) -> DropGuard {
+        DropGuard(Some(self.clone()))
+    }
+}
+
+pub struct DropGuard(Option<CancellationToken>);
+
+impl DropGuard {
+    /// Keep the token alive after all.
+    pub fn disarm(mut self) {
+        self.0 = None;
+    }
+}
+
+impl Drop for DropGuard {
+    fn drop(&mut self) {
+        if let Some(token) = &self.0 {
+            token.cancel();
+        }
+    }
+}
+
+/// Bytes of the values a request has built, shared by the threads working on
+/// it. Values are counted when built and never released, so the count bounds
+/// what the request holds at once from above.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryMeter(Arc<AtomicU64>);
+
+impl MemoryMeter {
+    pub fn used(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    fn charge(&self, bytes: u64) -> u64 {
+        self.0
+            .fetch_add(bytes, Ordering::Relaxed)
+            .saturating_add(bytes)
+    }
+}
+
+/// Whether any evaluation of a request was answered from the result cache,
+/// shared like [`MemoryMeter`].
+#[derive(Debug, Clone, Default)]
+pub struct CacheHits(Arc<AtomicBool>);
+
+impl CacheHits {
+    pub fn any(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    pub(super) fn record(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EvalContext {
+    pub limits: Limits,
+    pub deadline: Option<Instant>,
+    pub cancellation: CancellationToken,
+    /// Replaces the angle unit of the environments evaluated in
+    pub angle_mode: Option<AngleMode>,
+    /// Language of the request's error messages
+    pub locale: Locale,
+    pub memory: MemoryMeter,
+    pub cache_hits: CacheHits,
+}
+
+impl EvalContext {
+    /// A context for `limits`, due `limits.max_eval_ms` from now.
+    pub fn new(limits: Limits) -> Self {
+        EvalContext {
+            deadline: (limits.max_eval_ms > 0)
+                .then(|| Instant::now() + Duration::from_millis(limits.max_eval_ms)),
+            limits,
+            cancellation: CancellationToken::new(),
+            angle_mode: None,
+            locale: Locale::default(),
+            memory: MemoryMeter::default(),
+            cache_hits: CacheHits::default(),
+        }
+    }
+
+    pub fn with_cancellation(mut self, cancellation: CancellationToken) -> Self {
+        self.cancellation = cancellation;
+        self
+    }
+
+    pub fn with_angle_mode(mut self, angle_mode: AngleMode) -> Self {
+        self.angle_mode = Some(angle_mode);
+        self
+    }
+
+    pub fn with_locale(mut self, locale: Locale) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    /// Angle unit for evaluating in `env`: this context's, else its own.
+    pub fn angle_mode_in(&self, env: &Environment) -> AngleMode {
+        self.angle_mode.unwrap_or(env.angle_mode())
+    }
+
+    /// Run `f` with this context active on the current thread. Contexts nest;
+    /// the previous one is restored when `f` returns or unwinds.
+    pub fn run<T>(&self, f: impl FnOnce() -> T) -> T {
+        struct Restore(Option<Active>);
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                ACTIVE.with(|active| *active.borrow_mut() = self.0.take());
+            }
+        }
+
+        let previous = ACTIVE.with(|active| {
+            active.borrow_mut().replace(Active {
+                context: self.clone(),
+                ticks: 0,
+            })
+        });
+        let _restore = Restore(previous);
+        f()
+    }
+
+    /// The context entered on the current thread, else a default one; for
+    /// conveniences such as [`eval`](super::eval) that take no context.
+    pub fn current() -> EvalContext {
+        ACTIVE
+            .with(|active| {
+                active
+                    .borrow()
+                    .as_ref()
+                    .map(|active| active.context.clone())
+            })
+            .unwrap_or_default()
+    }
+
+    /// Fail if the context was cancelled or is past its deadline.
+    pub fn check(&self) -> Result<(), Interrupted> {
+        if self.cancellation.is_cancelled() {
+            return Err(Interrupted::Cancelled);
+        }
+        if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            return Err(Interrupted::DeadlineExceeded {
+                limit_ms: self.limits.max_eval_ms,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Default for EvalContext {
+    fn default() -> Self {
+        EvalContext::new(Limits::DEFAULT)
+    }
+}
+
+struct Active {
+    context: EvalContext,
+    ticks: u32,
+}
+
+thread_local! {
+    static ACTIVE: RefCell<Option<Active>> = const { RefCell::new(None) };
+}
+
+/// Stop here if the active context is cancelled or out of time. Cheap enough
+/// for inner loops: the clock is read only every few calls. A no-op outside
+/// a context.
+pub fn checkpoint() -> Result<(), Interrupted> {
+    ACTIVE.with(|active| {
+        let mut active = active.borrow_mut();
+        let Some(active) = active.as_mut() else {
+            return Ok(());
+        };
+        if active.context.cancellation.is_cancelled() {
+            return Err(Interrupted::Cancelled);
+        }
+        active.ticks = active.ticks.wrapping_add(1);
+        if active.ticks % CLOCK_INTERVAL == 0 {
+            return active.context.check();
+        }
+        Ok(())
+    })
+}
+
+/// Count `bytes` of new values against the active context's
+/// `max_memory_bytes`, failing once they exceed it. A no-op outside a context.
+pub fn charge_memory(bytes: u64) -> Result<(), MemoryExceeded> {
+    ACTIVE.with(|active| match active.borrow().as_ref() {
+        Some(active) => {
+            let used = active.context.memory.charge(bytes);
+            active.context.limits.check_memory(used)
+        }
+        None => Ok(()),
+    })
+}
+
+pub(super) fn active_limits() -> Option<Limits> {
+    ACTIVE.with(|active| active.borrow().as_ref().map(|active| active.context.limits))
+}
+
+/// Angle unit for evaluating in `env` under the entered context, for the
+/// kernels that take no context.
+pub fn angle_mode(env: &Environment) -> AngleMode {
+    ACTIVE.with(|active| match active.borrow().as_ref() {
+        Some(active) => active.context.angle_mode_in(env),
+        None => env.angle_mode(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::{cost, eval, eval_with};
+    use bigdecimal::BigDecimal;
+
+    fn eval_in_context(input: &str, ctx: &EvalContext) -> anyhow::Result<BigDecimal> {
+        eval_with(input, &Environment::new(), ctx)
+    }
+
+    #[test]
+    fn test_context_scopes_limits() {
+        let tight = Limits {
+            precision: 5,
+            ..Limits::default()
+        };
+        let third = eval_in_context("1/3", &EvalContext::new(tight)).unwrap();
+        assert_eq!(third.to_string(), "0.33333");
+        assert_ne!(eval("1/3").unwrap().to_string(), "0.33333");
+        // Conveniences without a context parameter use the entered one
+        assert_eq!(
+            EvalContext::new(tight).run(|| eval("1/3").unwrap().to_string()),
+            "0.33333"
+        );
+
+        let degrees = EvalContext::new(Limits::default()).with_angle_mode(AngleMode::Degrees);
+        let sin = eval_in_context("sin(90)", &degrees).unwrap();
+        assert_eq!(sin.to_string(), "1");
+    }
+
+    #[test]
+    fn test_memory_is_metered() {
+        let context = EvalContext::new(Limits::default());
+        eval_in_context("1 / 3 + 2 ^ 100", &context).unwrap();
+        assert!(context.memory.used() >= 3 * cost::value_bytes(1));
+
+        // Few values are held at once, but together they are too many
+        let input = vec!["1 / 3"; 40].join(" + ");
+        let context = EvalContext::new(Limits {
+            max_memory_bytes: 2_000,
+            ..Limits::default()
+        });
+        let err = eval_in_context(&input, &context).unwrap_err();
+        assert!(err.downcast_ref::<MemoryExceeded>().is_some(), "{err}");
+        assert!(eval(&input).is_ok());
+    }
+
+    #[test]
+    fn test_deadline_and_cancellation() {
+        let context = EvalContext {
+            deadline: Some(Instant::now()),
+            ..EvalContext::new(Limits::default())
+        };
+        let err = eval_in_context("2 ^ 10", &context).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<Interrupted>(),
+            Some(Interrupted::DeadlineExceeded { .. })
+        ));
+
+        let token = CancellationToken::new();
+        drop(token.drop_guard());
+        let context = EvalContext::new(Limits::default()).with_cancellation(token);
+        let err = eval_in_context("1 + 2", &context).unwrap_err();
+        assert_eq!(err.to_string(), Interrupted::Cancelled.to_string());
+
+        let token = CancellationToken::new();
+        token.drop_guard().disarm();
+        assert!(!token.is_cancelled());
+        assert!(checkpoint().is_ok());
+    }
+}
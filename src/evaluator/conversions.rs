@@ -0,0 +1,133 @@
+//! Conversions between numbers and notations the grammar cannot express as
+//! literals. `from_roman(MCMXCIV)` is rewritten to its value before
+//! tokenizing, since the grammar has no strings.
+
+use anyhow::{anyhow, bail};
+use bigdecimal::BigDecimal;
+use num_traits::ToPrimitive;
+use std::borrow::Cow;
+
+const NUMERALS: [(u32, &str); 13] = [
+    (1000, "M"),
+    (900, "CM"),
+    (500, "D"),
+    (400, "CD"),
+    (100, "C"),
+    (90, "XC"),
+    (50, "L"),
+    (40, "XL"),
+    (10, "X"),
+    (9, "IX"),
+    (5, "V"),
+    (4, "IV"),
+    (1, "I"),
+];
+
+/// Largest number with a standard Roman numeral.
+pub const MAX_ROMAN: u32 = 3999;
+
+pub fn to_roman(value: &BigDecimal) -> anyhow::Result<String> {
+    let mut n = value
+        .to_u32()
+        .filter(|n| value.is_integer() && (1..=MAX_ROMAN).contains(n))
+        .ok_or_else(|| anyhow!("Roman numerals need an integer from 1 to {}", MAX_ROMAN))?;
+    let mut out = String::new();
+    for (size, numeral) in NUMERALS {
+        while n >= size {
+            out.push_str(numeral);
+            n -= size;
+        }
+    }
+    Ok(out)
+}
+
+/// Value of a standard Roman numeral such as `MCMXCIV`, in either case.
+/// Non-canonical forms such as `IIII` or `IC` are rejected.
+pub fn from_roman(numeral: &str) -> anyhow::Result<u32> {
+    let upper = numeral.trim().to_ascii_uppercase();
+    let mut rest = upper.as_str();
+    let mut value = 0;
+    for (size, symbol) in NUMERALS {
+        while let Some(tail) = rest.strip_prefix(symbol) {
+            value += size;
+            rest = tail;
+        }
+    }
+    if !rest.is_empty() || value == 0 || to_roman(&BigDecimal::from(value))? != upper {
+        bail!("Not a Roman numeral: {}", numeral.trim());
+    }
+    Ok(value)
+}
+
+/// Replace each `from_roman(...)` call, with the numeral optionally quoted,
+/// by its value.
+pub fn to_infix(input: &str) -> anyhow::Result<Cow<'_, str>> {
+    const CALL: &str = "from_roman";
+    if !input.contains(CALL) {
+        return Ok(Cow::Borrowed(input));
+    }
+    let mut out = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(at) = rest.find(CALL) {
+        let (before, after) = rest.split_at(at);
+        out.push_str(before);
+        let is_name_start = !before
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_alphanumeric() || c == '_');
+        let call = after[CALL.len()..]
+            .trim_start()
+            .strip_prefix('(')
+            .and_then(|args| args.split_once(')'));
+        match call {
+            Some((numeral, tail)) if is_name_start => {
+                let numeral = numeral.trim().trim_matches(['"', '\'']);
+                out.push_str(&format!("({})", from_roman(numeral)?));
+                rest = tail;
+            }
+            _ => {
+                out.push_str(CALL);
+                rest = &after[CALL.len()..];
+            }
+        }
+    }
+    out.push_str(rest);
+    Ok(Cow::Owned(out))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::eval;
+
+    #[test]
+    fn test_roman_numerals() {
+        for (n, numeral) in [
+            (1994, "MCMXCIV"),
+            (4, "IV"),
+            (3999, "MMMCMXCIX"),
+            (49, "XLIX"),
+        ] {
+            assert_eq!(to_roman(&BigDecimal::from(n)).unwrap(), numeral);
+            assert_eq!(from_roman(numeral).unwrap(), n);
+        }
+        assert_eq!(from_roman("mmxxiv").unwrap(), 2024);
+        for invalid in ["IIII", "IC", "VX", "ABC", ""] {
+            assert!(from_roman(invalid).is_err(), "{invalid}");
+        }
+        assert!(to_roman(&BigDecimal::from(0)).is_err());
+        assert!(to_roman(&BigDecimal::from(4000)).is_err());
+
+        assert_eq!(
+            eval("from_roman(\"MCMXCIV\") + 6").unwrap(),
+            BigDecimal::from(2000)
+        );
+        assert_eq!(
+            eval("from_roman( xlii ) * 2").unwrap(),
+            BigDecimal::from(84)
+        );
+        assert_eq!(eval("roman(1994)").unwrap(), BigDecimal::from(1994));
+        assert!(eval("roman(1.5)").is_err());
+        assert!(eval("from_roman(IIII)").is_err());
+    }
+}
@@ -0,0 +1,347 @@
+//! Static cost estimate of an RPN stream, used to reject expensive input
+//! before any arithmetic runs.
+
+use num_traits::ToPrimitive;
+use serde::Serialize;
+
+use super::bits::MAX_BIT_WIDTH;
+use super::finance::MAX_PERIODS;
+use super::limits::Limits;
+use super::{Function, Operator, Token};
+
+/// Digits per cost unit: an operation is charged once per limb of its result.
+const LIMB_DIGITS: u64 = 100;
+
+/// Bytes of a value besides its digits: the digit vector's header, the sign
+/// and the scale.
+const VALUE_OVERHEAD_BYTES: u64 = 40;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct CostEstimate {
+    pub tokens: usize,
+    pub operations: usize,
+    /// Largest literal exponent or factorial argument
+    pub max_exponent: u64,
+    /// Largest estimated intermediate result, in digits
+    pub max_digits: u64,
+    /// Sum of operation weights scaled by result size, comparable to `max_cost`
+    pub cost: u64,
+    /// Most bytes the values held at once take, comparable to `max_memory_bytes`
+    pub memory_bytes: u64,
+}
+
+/// One stack entry: estimated digits and the value when it is a small integer literal.
+#[derive(Clone, Copy)]
+struct Operand {
+    digits: u64,
+    literal: Option<u64>,
+}
+
+/// Approximate bytes of a value with `digits` decimal digits, stored as 64-bit
+/// limbs of 19 digits each.
+pub fn value_bytes(digits: u64) -> u64 {
+    digits.div_ceil(19) * 8 + VALUE_OVERHEAD_BYTES
+}
+
+/// Estimate the work needed to evaluate `rpn`. Sizes are capped at the digit
+/// limit, since anything larger fails with an overflow error instead.
+pub fn estimate(rpn: &[Token], limits: &Limits) -> CostEstimate {
+    estimate_each(rpn, limits, |_, _| {})
+}
+
+/// Estimated cost of the subtree each token of `rpn` completes: its own
+/// operation plus those of all its operands, by token index.
+pub fn subtree_costs(rpn: &[Token], limits: &Limits) -> Vec<u64> {
+    let mut costs = vec![0; rpn.len()];
+    estimate_each(rpn, limits, |index, cost| costs[index] = cost);
+    let mut stack: Vec<u64> = Vec::new();
+    for (token, cost) in rpn.iter().zip(costs.iter_mut()) {
+        let operands = match token {
+            Token::Op(op) => op.arity(),
+            Token::Call(_, argc) => *argc,
+            Token::Number(_) | Token::Ident(_) | Token::Var(_) => 0,
+            Token::LParenthesis | Token::RParenthesis | Token::Func(_) | Token::Comma => continue,
+        };
+        *cost = stack
+            .split_off(stack.len().saturating_sub(operands))
+            .into_iter()
+            .fold(*cost, u64::saturating_add);
+        stack.push(*cost);
+    }
+    costs
+}
+
+/// [`estimate`], also reporting what each operation is charged by token index.
+fn estimate_each(
+    rpn: &[Token],
+    limits: &Limits,
+    mut charge: impl FnMut(usize, u64),
+) -> CostEstimate {
+    let unknown = Operand {
+        digits: limits.precision,
+        literal: None,
+    };
+    let mut estimate = CostEstimate {
+        tokens: rpn.len(),
+        operations: 0,
+        max_exponent: 0,
+        max_digits: 0,
+        cost: 0,
+        memory_bytes: 0,
+    };
+    let mut stack: Vec<Operand> = Vec::new();
+    // Bytes of the values on the stack, and their sum
+    let mut held: Vec<u64> = Vec::new();
+    let mut live: u64 = 0;
+    for (index, token) in rpn.iter().enumerate() {
+        let (weight, digits) = match token {
+            Token::Number(num) => {
+                let digits = (num.digits() as i64 - num.fractional_digit_count()).max(1) as u64
+                    + num.fractional_digit_count().max(0) as u64;
+                // Converting a literal like `1e999999999` would build all of its
+                // digits, and `is_integer` on `1e-999999999` a power of ten as long
+                let literal = (num.fractional_digit_count() <= 0 && digits <= 20)
+                    .then(|| num.to_u64())
+                    .flatten();
+                stack.push(Operand { digits, literal });
+                let bytes = value_bytes(digits.min(limits.max_digits));
+                held.push(bytes);
+                live = live.saturating_add(bytes);
+                estimate.memory_bytes = estimate.memory_bytes.max(live);
+                continue;
+            }
+            Token::Ident(_) | Token::Var(_) => {
+                stack.push(unknown);
+                held.push(value_bytes(unknown.digits));
+                live = live.saturating_add(value_bytes(unknown.digits));
+                estimate.memory_bytes = estimate.memory_bytes.max(live);
+                continue;
+            }
+            Token::Op(op) if op.is_unary() => {
+                let operand = stack.pop().unwrap_or(unknown);
+                match op {
+                    Operator::Factorial => {
+                        let n = operand.literal.unwrap_or(limits.max_digits);
+                        estimate.max_exponent = estimate.max_exponent.max(n);
+                        (8, factorial_digits(n))
+                    }
+                    Operator::DoubleFactorial => {
+                        let n = operand.literal.unwrap_or(limits.max_digits);
+                        estimate.max_exponent = estimate.max_exponent.max(n);
+                        (8, factorial_digits(n).div_ceil(2))
+                    }
+                    Operator::Primorial => {
+                        let n = operand.literal.unwrap_or(limits.max_digits);
+                        estimate.max_exponent = estimate.max_exponent.max(n);
+                        // ln(n#) is about n, so n# has about n / ln(10) digits
+                        (8, n.saturating_mul(10) / 23 + 1)
+                    }
+                    Operator::Square => (8, operand.digits.saturating_mul(2)),
+                    Operator::Cube => (8, operand.digits.saturating_mul(3)),
+                    Operator::Percent => (2, operand.digits.saturating_add(2)),
+                    _ => (1, operand.digits),
+                }
+            }
+            Token::Op(op) => {
+                let rhs = stack.pop().unwrap_or(unknown);
+                let lhs = stack.pop().unwrap_or(unknown);
+                match op {
+                    Operator::Mul => (4, lhs.digits.saturating_add(rhs.digits)),
+                    Operator::Div | Operator::FloorDiv | Operator::Mod => {
+                        (4, lhs.digits.max(rhs.digits).max(limits.precision))
+                    }
+                    Operator::Pow => {
+                        let exponent = rhs.literal.unwrap_or(limits.max_digits);
+                        estimate.max_exponent = estimate.max_exponent.max(exponent);
+                        (8, lhs.digits.saturating_mul(exponent))
+                    }
+                    _ => (1, lhs.digits.max(rhs.digits).saturating_add(1)),
+                }
+            }
+            Token::Call(func, argc) => {
+                let args = stack.split_off(stack.len().saturating_sub(*argc));
+                let largest = args.iter().map(|arg| arg.digits).max().unwrap_or(0);
+                match func {
+                    Function::Abs | Function::IntPart | Function::FracPart => (1, largest),
+                    Function::Digits | Function::Scale => (1, 1),
+                    Function::Clamp => (1, largest),
+                    Function::Lerp => (4, largest.saturating_mul(2)),
+                    Function::MapRange => (8, largest.max(limits.precision)),
+                    Function::QMul | Function::QConj => (16, largest.saturating_mul(2)),
+                    Function::QRotate => (60, limits.precision),
+                    Function::Polar
+                    | Function::Cartesian
+                    | Function::Spherical
+                    | Function::Cylindrical
+                    | Function::FromSpherical
+                    | Function::FromCylindrical => (40, limits.precision),
+                    Function::Hypot | Function::Norm | Function::Normalize | Function::QNorm => {
+                        (20 + 8 * args.len() as u64, limits.precision)
+                    }
+                    Function::Dot => (4 * args.len() as u64, largest.saturating_mul(2)),
+                    Function::Angle | Function::Proj => {
+                        (40 + 8 * args.len() as u64, limits.precision)
+                    }
+                    Function::Deg2Rad | Function::Rad2Deg => (8, limits.precision),
+                    Function::DivMod => (4, largest),
+                    Function::ApproxEq => (1, largest),
+                    Function::ToFraction | Function::Cfrac => (20, limits.precision),
+                    Function::Sqrt => (20, limits.precision),
+                    Function::Fib | Function::Lucas | Function::Catalan => {
+                        let n = args
+                            .first()
+                            .and_then(|arg| arg.literal)
+                            .unwrap_or(limits.max_digits);
+                        estimate.max_exponent = estimate.max_exponent.max(n);
+                        // F(n) has about 0.21n digits and C(n) about 0.6n
+                        (8, n.saturating_mul(6) / 10 + 1)
+                    }
+                    Function::Triangular => (2, largest.saturating_mul(2)),
+                    Function::Popcount | Function::BitLength => (1, 4),
+                    Function::Twos | Function::Signed => (2, largest),
+                    Function::Roman => (1, 4),
+                    Function::RoundHalfEven | Function::RoundHalfUp => (1, largest),
+                    Function::WithTax | Function::Tip | Function::Discount => {
+                        (6, largest.saturating_mul(2))
+                    }
+                    Function::FutureValue => {
+                        // One multiplication per compounding period
+                        let literal = |index: usize, default: u64| {
+                            args.get(index).map_or(Some(default), |arg| arg.literal)
+                        };
+                        let periods = literal(2, 0)
+                            .zip(literal(4, 12))
+                            .map_or(MAX_PERIODS, |(years, n)| years.saturating_mul(n))
+                            .min(MAX_PERIODS);
+                        (4 * periods.max(1), limits.precision)
+                    }
+                    Function::Rotl | Function::Rotr => {
+                        // The result fits in `width` bits, about 0.3 digits each
+                        let width = args.last().and_then(|arg| arg.literal);
+                        (
+                            4,
+                            width.map_or(largest, |width| width.min(MAX_BIT_WIDTH) * 3 / 10 + 1),
+                        )
+                    }
+                    Function::WMean => (4 * args.len() as u64, limits.precision),
+                    Function::GeoMean => {
+                        let product = args.iter().map(|arg| arg.digits).sum::<u64>();
+                        (20 + 8 * args.len() as u64, product.max(limits.precision))
+                    }
+                    Function::HarMean => (20 * args.len() as u64, limits.precision),
+                    _ => (50, limits.precision),
+                }
+            }
+            Token::LParenthesis | Token::RParenthesis | Token::Func(_) | Token::Comma => continue,
+        };
+        let digits = digits.min(limits.max_digits);
+        estimate.operations += 1;
+        estimate.max_digits = estimate.max_digits.max(digits);
+        let cost = weight * digits.div_ceil(LIMB_DIGITS).max(1);
+        charge(index, cost);
+        estimate.cost = estimate.cost.saturating_add(cost);
+        // The operands are held until the result is built
+        let result = value_bytes(digits);
+        estimate.memory_bytes = estimate.memory_bytes.max(live.saturating_add(result));
+        let operands: u64 = held.split_off(stack.len().min(held.len())).iter().sum();
+        live = live.saturating_sub(operands).saturating_add(result);
+        held.push(result);
+        stack.push(Operand {
+            digits,
+            literal: None,
+        });
+    }
+    estimate
+}
+
+/// Digits of `n!` by Stirling's approximation.
+fn factorial_digits(n: u64) -> u64 {
+    if n < 2 {
+        return 1;
+    }
+    let n = n as f64;
+    let log10 =
+        n * (n / std::f64::consts::E).log10() + (2.0 * std::f64::consts::PI * n).log10() / 2.0;
+    log10.floor() as u64 + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::{cost_estimate, shunting_yard, tokenize};
+
+    #[test]
+    fn test_cost_estimate() {
+        let simple = cost_estimate("2 + 3 * 4").unwrap();
+        assert_eq!((simple.tokens, simple.operations), (5, 2));
+        assert_eq!(simple.cost, 5);
+
+        let power = cost_estimate("2 ^ 1000").unwrap();
+        assert_eq!(power.max_exponent, 1000);
+        assert_eq!(power.max_digits, 1000);
+        assert!(cost_estimate("(9 ^ 9999) * (9 ^ 9999)").unwrap().cost > power.cost);
+        assert_eq!(cost_estimate("100!").unwrap().max_digits, 158);
+        assert!(cost_estimate("(1 + 2").is_err());
+
+        let limits = Limits {
+            max_cost: 1_000,
+            ..Limits::DEFAULT
+        };
+        let chained = cost_estimate(&vec!["9 ^ 9999"; 20].join(" * ")).unwrap();
+        assert_eq!(limits.check_cost(&chained).unwrap_err().limit, 1_000);
+        assert!(limits.check_cost(&simple).is_ok());
+    }
+
+    #[test]
+    fn test_memory_estimate() {
+        // 2 and 3 are held while 3 * 4 is built
+        assert_eq!(
+            cost_estimate("2 + 3 * 4").unwrap().memory_bytes,
+            4 * value_bytes(1)
+        );
+        // Both powers are held while their product is built
+        let product = cost_estimate("(9 ^ 9999) * (9 ^ 9999)").unwrap();
+        assert_eq!(
+            product.memory_bytes,
+            2 * value_bytes(9999) + value_bytes(19998)
+        );
+        let limits = Limits {
+            max_memory_bytes: 10_000,
+            ..Limits::DEFAULT
+        };
+        assert!(limits.check_memory(product.memory_bytes).is_err());
+        assert!(Limits::DEFAULT.check_memory(product.memory_bytes).is_ok());
+    }
+
+    #[test]
+    fn test_tiny_literal_is_estimated_without_its_digits() {
+        use std::str::FromStr;
+        let tiny = bigdecimal::BigDecimal::from_str("1e-100000000").unwrap();
+        let estimate = estimate(&[Token::Number(tiny)], &Limits::DEFAULT);
+        assert_eq!(estimate.max_exponent, 0);
+        assert_eq!(
+            estimate.memory_bytes,
+            value_bytes(Limits::DEFAULT.max_digits)
+        );
+    }
+
+    #[test]
+    fn test_subtree_costs() {
+        let limits = Limits::DEFAULT;
+        let rpn = shunting_yard(
+            &tokenize("2 + 3 * 4", &Limits::DEFAULT).unwrap(),
+            &Limits::DEFAULT,
+        )
+        .unwrap();
+        assert_eq!(subtree_costs(&rpn, &limits), vec![0, 0, 0, 4, 5]);
+
+        let rpn = shunting_yard(
+            &tokenize("sqrt(2) * (9 ^ 999 + 1)", &Limits::DEFAULT).unwrap(),
+            &Limits::DEFAULT,
+        )
+        .unwrap();
+        let costs = subtree_costs(&rpn, &limits);
+        assert_eq!(costs.last(), Some(&estimate(&rpn, &limits).cost));
+        assert!(costs[1] < costs[6]);
+    }
+}
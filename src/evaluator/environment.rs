@@ -0,0 +1,112 @@
+use anyhow::bail;
+use bigdecimal::BigDecimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::{Function, MathConst};
+
+/// Name bound to the result of the most recent evaluation.
+pub const ANS: &str = "ans";
+
+/// Unit of angles taken by trigonometric functions and coordinate converters
+/// and of the angles they return.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AngleMode {
+    #[default]
+    Radians,
+    Degrees,
+}
+
+/// Meaning of the data-size units `KB`, `MB`, `GB`...: powers of 1000 as in
+/// SI, or powers of 1024 as in the traditional binary usage.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DataUnits {
+    #[default]
+    Decimal,
+    Binary,
+}
+
+/// Variable bindings visible to an evaluation.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Environment {
+    vars: HashMap<String, BigDecimal>,
+    /// Not serialized; sessions persist their own modes and apply them here
+    #[serde(skip)]
+    angle_mode: AngleMode,
+    #[serde(skip)]
+    data_units: DataUnits,
+}
+
+impl Environment {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&BigDecimal> {
+        self.vars.get(name)
+    }
+
+    pub fn set(&mut self, name: &str, value: BigDecimal) -> anyhow::Result<()> {
+        validate_variable_name(name)?;
+        self.vars.insert(name.to_string(), value);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<BigDecimal> {
+        self.vars.remove(name)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.vars.contains_key(name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.vars.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vars.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &BigDecimal)> {
+        self.vars.iter()
+    }
+
+    pub fn angle_mode(&self) -> AngleMode {
+        self.angle_mode
+    }
+
+    pub fn set_angle_mode(&mut self, mode: AngleMode) {
+        self.angle_mode = mode;
+    }
+
+    pub fn data_units(&self) -> DataUnits {
+        self.data_units
+    }
+
+    pub fn set_data_units(&mut self, units: DataUnits) {
+        self.data_units = units;
+    }
+}
+
+pub fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+pub fn validate_variable_name(name: &str) -> anyhow::Result<()> {
+    if !is_identifier(name) {
+        bail!("Invalid variable name: {}", name);
+    }
+    if MathConst::try_from(name).is_ok() {
+        bail!("Cannot assign to constant: {}", name);
+    }
+    if Function::try_from(name).is_ok() {
+        bail!("Cannot assign to function: {}", name);
+    }
+    Ok(())
+}
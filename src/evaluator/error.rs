@@ -0,0 +1,152 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::Range;
+
+/// Character offsets into the input, end exclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl From<Range<usize>> for Span {
+    fn from(range: Range<usize>) -> Self {
+        Span {
+            start: range.start,
+            end: range.end,
+        }
+    }
+}
+
+/// A syntax error pinned to the offending part of the input. Returned inside
+/// `anyhow::Error`; callers can recover it with `downcast_ref`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParseError {
+    pub message: String,
+    pub span: Span,
+}
+
+impl ParseError {
+    pub fn new(message: impl Into<String>, span: impl Into<Span>) -> Self {
+        ParseError {
+            message: message.into(),
+            span: span.into(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} at {}..{}",
+            self.message, self.span.start, self.span.end
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// A value would exceed the configured size limits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Overflow {
+    Digits { estimated: i128, limit: u64 },
+    Scale { estimated: i128, limit: i64 },
+}
+
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Overflow::Digits { estimated, limit } => write!(
+                f,
+                "Overflow: result would have about {} digits (limit {})",
+                estimated, limit
+            ),
+            Overflow::Scale { estimated, limit } => write!(
+                f,
+                "Overflow: result would have a decimal exponent of about {} (limit {})",
+                -estimated, limit
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Overflow {}
+
+/// Nesting beyond the configured depth budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct DepthExceeded {
+    pub limit: usize,
+}
+
+impl fmt::Display for DepthExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Expression is nested too deeply (limit {})", self.limit)
+    }
+}
+
+impl std::error::Error for DepthExceeded {}
+
+/// The static cost estimate exceeds the configured budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct CostExceeded {
+    pub estimated: u64,
+    pub limit: u64,
+}
+
+impl fmt::Display for CostExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Expression is too expensive to evaluate: estimated cost {} exceeds the limit of {}",
+            self.estimated, self.limit
+        )
+    }
+}
+
+impl std::error::Error for CostExceeded {}
+
+/// The values of an evaluation would take more memory than the request may
+/// use, by the static estimate or by the values built so far.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct MemoryExceeded {
+    pub estimated_bytes: u64,
+    pub limit_bytes: u64,
+}
+
+impl fmt::Display for MemoryExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Expression needs too much memory: about {} bytes exceeds the limit of {}",
+            self.estimated_bytes, self.limit_bytes
+        )
+    }
+}
+
+impl std::error::Error for MemoryExceeded {}
+
+/// Evaluation stopped before finishing: the request's deadline passed or its
+/// caller went away.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Interrupted {
+    DeadlineExceeded { limit_ms: u64 },
+    Cancelled,
+}
+
+impl fmt::Display for Interrupted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Interrupted::DeadlineExceeded { limit_ms } => write!(
+                f,
+                "Evaluation took too long and was stopped (limit {} ms)",
+                limit_ms
+            ),
+            Interrupted::Cancelled => write!(f, "Evaluation was cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for Interrupted {}
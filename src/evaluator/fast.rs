@@ -0,0 +1,215 @@
+//! Fast path for everyday arithmetic such as `23 * 7 + 4` or `19.99 * 3`:
+//! values are held as an `i128` mantissa and a decimal scale, so no
+//! `BigDecimal` is built until the result. Whenever an operation would
+//! overflow, need rounding or is not supported here, the whole expression is
+//! handed back to the exact paths, so results are identical either way.
+
+use anyhow::bail;
+use bigdecimal::BigDecimal;
+use bigdecimal::num_bigint::BigInt;
+
+use super::limits::Limits;
+use super::{Environment, Operator, Token, precision};
+
+/// Largest scale kept, so aligning two operands cannot overflow on its own.
+const MAX_SCALE: u32 = 18;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Small {
+    mantissa: i128,
+    /// Digits after the decimal point
+    scale: u32,
+}
+
+impl Small {
+    fn from_decimal(value: &BigDecimal) -> Option<Small> {
+        let (int, scale) = value.as_bigint_and_exponent();
+        let scale = u32::try_from(scale)
+            .ok()
+            .filter(|scale| *scale <= MAX_SCALE)?;
+        let mantissa = i128::try_from(int).ok()?;
+        Some(Small { mantissa, scale })
+    }
+
+    fn to_decimal(self) -> BigDecimal {
+        BigDecimal::new(BigInt::from(self.mantissa), i64::from(self.scale))
+    }
+
+    fn digits(self) -> u64 {
+        self.mantissa
+            .unsigned_abs()
+            .checked_ilog10()
+            .map_or(1, |log| u64::from(log) + 1)
+    }
+
+    /// The mantissa at a larger `scale`.
+    fn rescaled(self, scale: u32) -> Option<i128> {
+        self.mantissa
+            .checked_mul(10i128.checked_pow(scale - self.scale)?)
+    }
+
+    fn add(self, rhs: Small) -> Option<Small> {
+        let scale = self.scale.max(rhs.scale);
+        Some(Small {
+            mantissa: self.rescaled(scale)?.checked_add(rhs.rescaled(scale)?)?,
+            scale,
+        })
+    }
+
+    fn mul(self, rhs: Small) -> Option<Small> {
+        let scale = self.scale + rhs.scale;
+        (scale <= MAX_SCALE).then_some(())?;
+        Some(Small {
+            mantissa: self.mantissa.checked_mul(rhs.mantissa)?,
+            scale,
+        })
+    }
+
+    fn neg(self) -> Option<Small> {
+        Some(Small {
+            mantissa: self.mantissa.checked_neg()?,
+            scale: self.scale,
+        })
+    }
+
+    fn integer(self) -> Option<i128> {
+        (self.scale == 0).then_some(self.mantissa)
+    }
+}
+
+/// Evaluate `rpn` when every literal, variable and intermediate is small.
+/// `Ok(None)` hands the expression to the exact paths.
+pub fn try_eval(
+    rpn: &[Token],
+    env: &Environment,
+    limits: &Limits,
+) -> anyhow::Result<Option<BigDecimal>> {
+    // Decimal intermediates are rounded to the working precision on the
+    // exact path; stay below it so there is never anything to round
+    let working_digits = limits.precision + precision::GUARD_DIGITS;
+    let mut stack: Vec<Small> = Vec::with_capacity(rpn.len());
+    for token in rpn {
+        super::checkpoint()?;
+        let value = match token {
+            Token::Number(num) => Small::from_decimal(num),
+            Token::Var(name) => env.get(name).and_then(Small::from_decimal),
+            Token::Op(op) if op.is_unary() => {
+                let Some(value) = stack.pop() else {
+                    return Ok(None);
+                };
+                match op {
+                    Operator::UnarySub => value.neg(),
+                    Operator::UnaryAdd => Some(value),
+                    Operator::Square => value.integer().and(value.mul(value)),
+                    Operator::Cube => value
+                        .integer()
+                        .and(value.mul(value))
+                        .and_then(|square| square.mul(value)),
+                    _ => None,
+                }
+            }
+            Token::Op(op) => {
+                let (Some(rhs), Some(lhs)) = (stack.pop(), stack.pop()) else {
+                    return Ok(None);
+                };
+                match op {
+                    Operator::Add => lhs.add(rhs),
+                    Operator::Sub => rhs.neg().and_then(|rhs| lhs.add(rhs)),
+                    Operator::Mul => lhs.mul(rhs),
+                    Operator::Mod => match (lhs.integer(), rhs.integer()) {
+                        (Some(_), Some(0)) => bail!("Modulo by zero"),
+                        (Some(lhs), Some(rhs)) => lhs
+                            .checked_rem(rhs)
+                            .map(|mantissa| Small { mantissa, scale: 0 }),
+                        _ => None,
+                    },
+                    Operator::Pow => match (lhs.integer(), rhs.integer()) {
+                        (Some(base), Some(exponent)) => u32::try_from(exponent)
+                            .ok()
+                            .and_then(|exponent| base.checked_pow(exponent))
+                            .map(|mantissa| Small { mantissa, scale: 0 }),
+                        _ => None,
+                    },
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        let Some(value) = value else {
+            return Ok(None);
+        };
+        if value.scale > 0 && value.digits() > working_digits {
+            return Ok(None);
+        }
+        limits.check(value.digits() as i128, i128::from(value.scale))?;
+        stack.push(value);
+    }
+    match (stack.pop(), stack.is_empty()) {
+        (Some(result), true) => Ok(Some(precision::round_to(
+            result.to_decimal(),
+            limits.precision,
+        ))),
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::EvalContext;
+    use crate::evaluator::{eval, parse, tokenize_input};
+
+    fn fast(input: &str) -> Option<String> {
+        let rpn = super::super::shunting_yard(
+            &tokenize_input(input, Default::default(), &EvalContext::default()).unwrap(),
+            &Limits::DEFAULT,
+        )
+        .unwrap();
+        try_eval(&rpn, &Environment::new(), &Limits::default())
+            .unwrap()
+            .map(|value| value.to_string())
+    }
+
+    #[test]
+    fn test_fast_path_matches_exact_paths() {
+        let cases = [
+            ("23 * 7 + 4", "165"),
+            ("19.99 * 3", "59.97"),
+            ("1.50 + 1", "2.50"),
+            ("2.0 * 3", "6.0"),
+            ("-7 % 3", "-1"),
+            ("(-2) ^ 63", "-9223372036854775808"),
+            ("12² - 3³", "117"),
+            ("0.1 + 0.2 - 0.3", "0"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(fast(input).as_deref(), Some(expected), "fast {input}");
+            // The tree evaluator never takes the fast path
+            let exact = parse(input).unwrap().eval(&Environment::new()).unwrap();
+            assert_eq!(exact.to_string(), expected, "exact {input}");
+        }
+    }
+
+    #[test]
+    fn test_promotes_what_it_cannot_do_exactly() {
+        for input in [
+            "6 / 4",
+            "2 ^ -2",
+            "2 ^ 127",
+            "99999999999999999999 * 99999999999999999999",
+            "1.5 ^ 2",
+            "1e5 + 1",
+            "pi * 2",
+            "sqrt(4)",
+        ] {
+            assert_eq!(fast(input), None, "{input}");
+        }
+        assert_eq!(
+            eval("99999999999999999999 * 99999999999999999999")
+                .unwrap()
+                .to_string(),
+            "9999999999999999999800000000000000000001"
+        );
+        assert!(eval("7 % 0").is_err());
+    }
+}
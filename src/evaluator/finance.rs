@@ -0,0 +1,119 @@
+//! Compound interest with regular contributions, simulated one compounding
+//! period at a time so any compounding frequency is handled without
+//! fractional powers.
+
+use anyhow::bail;
+use bigdecimal::BigDecimal;
+use num_traits::{ToPrimitive, Zero};
+
+use super::Function;
+use super::functions::round_money;
+use super::precision::{divide, round_to, working_digits};
+
+/// Most compounding periods `future_value` simulates, e.g. daily for 270 years.
+pub const MAX_PERIODS: u64 = 100_000;
+
+const DEFAULT_COMPOUNDING: u64 = 12;
+
+/// `future_value(principal, rate, years, monthly_contribution, compounding)`
+/// as `[future_value, contributions, interest]`. `rate` is the nominal
+/// annual rate in percent, compounded `compounding` times a year (monthly by
+/// default); contributions are paid at the end of each month and earn
+/// interest from the first compounding after they are paid. The future value
+/// is rounded to cents with banker's rounding.
+pub fn future_value(args: &[BigDecimal]) -> anyhow::Result<Vec<BigDecimal>> {
+    let func = Function::FutureValue;
+    let (principal, rate, years) = (&args[0], &args[1], &args[2]);
+    let monthly = args.get(3).cloned().unwrap_or_else(BigDecimal::zero);
+    let compounding = match args.get(4) {
+        Some(n) => n
+            .to_u64()
+            .filter(|count| n.is_integer() && *count >= 1)
+            .ok_or_else(|| anyhow::anyhow!("{} compounding must be a positive integer", func))?,
+        None => DEFAULT_COMPOUNDING,
+    };
+    if rate <= &BigDecimal::from(-100) {
+        bail!("{} rate must be above -100 percent", func);
+    }
+    let periods =
+        whole(&(years * BigDecimal::from(compounding))).filter(|periods| *periods <= MAX_PERIODS);
+    let months = whole(&(years * BigDecimal::from(12)));
+    let (Some(periods), Some(months)) = (periods, months) else {
+        bail!(
+            "{} needs years covering whole months and whole compounding periods, at most {} periods",
+            func,
+            MAX_PERIODS
+        );
+    };
+
+    let digits = working_digits();
+    let growth = BigDecimal::from(1) + divide(rate, &BigDecimal::from(100 * compounding), digits);
+    let mut balance = principal.clone();
+    for period in 1..=periods {
+        super::checkpoint()?;
+        // Month ends falling in this period are paid after its interest
+        let paid = 12 * period / compounding - 12 * (period - 1) / compounding;
+        balance = round_to(&balance * &growth, digits) + &monthly * BigDecimal::from(paid);
+    }
+    let balance = round_money(&balance);
+    let contributions = principal + monthly * BigDecimal::from(months);
+    let interest = &balance - &contributions;
+    Ok(vec![balance, contributions, interest])
+}
+
+/// `value` as a non-negative whole number, if it is one.
+fn whole(value: &BigDecimal) -> Option<u64> {
+    value.is_integer().then(|| value.to_u64()).flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::EvalContext;
+    use crate::evaluator::{Environment, components, eval};
+    use std::str::FromStr;
+
+    fn rounded(input: &str) -> String {
+        eval(input)
+            .unwrap()
+            .with_scale_round(2, bigdecimal::RoundingMode::HalfEven)
+            .to_string()
+    }
+
+    #[test]
+    fn test_future_value() {
+        // 1000 at 5% for 10 years
+        assert_eq!(rounded("future_value(1000, 5, 10, 0, 1)"), "1628.89");
+        assert_eq!(rounded("future_value(1000, 5, 10, 0, 4)"), "1643.62");
+        assert_eq!(rounded("future_value(1000, 5, 10)"), "1647.01");
+        assert_eq!(rounded("future_value(1000, 5, 10, 0, 365)"), "1648.66");
+        // Ordinary annuity of 100 a month: 100 * ((1 + r)^120 - 1) / r
+        assert_eq!(rounded("future_value(0, 6, 10, 100)"), "16387.93");
+        // Yearly compounding credits each year's contributions at its end
+        assert_eq!(rounded("future_value(0, 10, 2, 100, 1)"), "2520.00");
+        assert_eq!(
+            eval("future_value(500, 0, 1.5, 10)").unwrap(),
+            BigDecimal::from(680)
+        );
+
+        let parts = components(
+            "future_value(1000, 12, 1, 100, 12)",
+            &Environment::new(),
+            &EvalContext::default(),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(parts[1], ("contributions", BigDecimal::from(2200)));
+        let (total, interest) = (&parts[0].1, &parts[2].1);
+        assert_eq!(total - interest, BigDecimal::from(2200));
+        assert_eq!(
+            total.with_scale_round(2, bigdecimal::RoundingMode::HalfEven),
+            BigDecimal::from_str("2395.08").unwrap()
+        );
+
+        assert!(eval("future_value(1000, 5, 1.5, 0, 1)").is_err());
+        assert!(eval("future_value(1000, 5, 10, 0, 0)").is_err());
+        assert!(eval("future_value(1000, 5, 1000, 0, 365)").is_err());
+        assert!(eval("future_value(1000, -100, 1)").is_err());
+    }
+}
@@ -0,0 +1,870 @@
+use anyhow::{anyhow, bail};
+use bigdecimal::num_bigint::BigInt;
+use bigdecimal::{BigDecimal, RoundingMode};
+use num_traits::{One, ToPrimitive, Zero};
+use std::str::FromStr;
+
+use super::precision::working_digits;
+use super::quaternion::Quaternion;
+use super::{AngleMode, Apply, Function, MathConst, number_theory, vector};
+
+pub fn apply_function(
+    func: Function,
+    args: &[BigDecimal],
+    mode: AngleMode,
+) -> anyhow::Result<BigDecimal> {
+    if !func.accepts(args.len()) {
+        bail!("Wrong number of arguments for {}: {}", func, args.len());
+    }
+    let info = func.info();
+    let result = match (info.apply, info.value) {
+        (_, Some(value)) | (Apply::Value(value), None) => value(args, mode),
+        (Apply::Components(_, values) | Apply::List(values), None) => values(args, mode)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("{} returned no values", func)),
+    }?;
+    super::limits::check_value(&result)?;
+    let result = super::precision::round_to(result, working_digits());
+    super::context::charge_memory(super::cost::value_bytes(result.digits()))?;
+    Ok(result)
+}
+
+/// All components of a multi-valued function, in [`Function::components`] order.
+pub fn apply_components(
+    func: Function,
+    args: &[BigDecimal],
+    mode: AngleMode,
+) -> anyhow::Result<Vec<BigDecimal>> {
+    if !func.accepts(args.len()) {
+        bail!("Wrong number of arguments for {}: {}", func, args.len());
+    }
+    match func.info().apply {
+        Apply::Components(_, values) => values(args, mode),
+        _ => Ok(vec![apply_function(func, args, mode)?]),
+    }
+}
+
+/// All terms of a list-valued function, see [`Function::is_list`].
+pub fn apply_list(
+    func: Function,
+    args: &[BigDecimal],
+    mode: AngleMode,
+) -> anyhow::Result<Vec<BigDecimal>> {
+    if !func.accepts(args.len()) {
+        bail!("Wrong number of arguments for {}: {}", func, args.len());
+    }
+    match func.info().apply {
+        Apply::List(values) => values(args, mode),
+        _ => Ok(vec![apply_function(func, args, mode)?]),
+    }
+}
+
+pub fn sqrt(x: &BigDecimal) -> anyhow::Result<BigDecimal> {
+    x.sqrt()
+        .map(|root| root.normalized())
+        .ok_or_else(|| anyhow!("Square root of a negative number"))
+}
+
+/// Digits after the decimal point, counted on the value as it would be
+/// returned, without guard digits.
+pub fn scale(x: &BigDecimal) -> BigDecimal {
+    BigDecimal::from(
+        super::precision::round_to(x.clone(), super::limits::limits().precision)
+            .normalized()
+            .fractional_digit_count()
+            .max(0),
+    )
+}
+
+pub fn clamp(args: &[BigDecimal]) -> anyhow::Result<BigDecimal> {
+    let (x, lo, hi) = (&args[0], &args[1], &args[2]);
+    if lo > hi {
+        bail!("clamp requires lo <= hi");
+    }
+    Ok(x.clamp(lo, hi).clone())
+}
+
+/// `map_range(x, a1, a2, b1, b2)`, `x` carried linearly from `[a1, a2]` to `[b1, b2]`.
+pub fn map_range(args: &[BigDecimal]) -> anyhow::Result<BigDecimal> {
+    let (x, a1, a2, b1, b2) = (&args[0], &args[1], &args[2], &args[3], &args[4]);
+    if a1 == a2 {
+        bail!("map_range requires a non-empty source range");
+    }
+    let scaled = (x - a1) * (b2 - b1);
+    Ok(b1 + super::precision::divide(&scaled, &(a2 - a1), working_digits()))
+}
+
+pub fn deg2rad(degrees: &BigDecimal) -> BigDecimal {
+    super::precision::divide(
+        &(degrees * BigDecimal::from(MathConst::Pi)),
+        &BigDecimal::from(180),
+        working_digits(),
+    )
+}
+
+pub fn rad2deg(radians: &BigDecimal) -> BigDecimal {
+    super::precision::divide(
+        &(radians * BigDecimal::from(180)),
+        &BigDecimal::from(MathConst::Pi),
+        working_digits(),
+    )
+}
+
+/// The angle between two vectors listed one after the other, in `mode`.
+pub fn angle(args: &[BigDecimal], mode: AngleMode) -> anyhow::Result<BigDecimal> {
+    let (a, b) = vector::halves(Function::Angle, args)?;
+    via_f64(Function::Angle, &vector::cos_angle(a, b)?, |x| {
+        from_radians(libm::acos(x), mode)
+    })
+}
+
+pub fn geometric_mean(args: &[BigDecimal]) -> anyhow::Result<BigDecimal> {
+    if args.iter().any(|x| x < &BigDecimal::zero()) {
+        bail!("geomean requires non-negative values");
+    }
+    let product = args.iter().try_fold(BigDecimal::one(), |product, x| {
+        let product = product * x;
+        super::limits::check_value(&product).map(|_| product)
+    })?;
+    Ok(super::precision::nth_root(
+        &product,
+        args.len() as u64,
+        working_digits(),
+    ))
+}
+
+pub fn harmonic_mean(args: &[BigDecimal]) -> anyhow::Result<BigDecimal> {
+    if args.iter().any(|x| x <= &BigDecimal::zero()) {
+        bail!("harmean requires positive values");
+    }
+    let digits = working_digits();
+    let reciprocals = args
+        .iter()
+        .map(|x| super::precision::divide(&BigDecimal::one(), x, digits))
+        .fold(BigDecimal::zero(), |sum, r| sum + r);
+    Ok(super::precision::divide(
+        &BigDecimal::from(args.len() as u64),
+        &reciprocals,
+        digits,
+    ))
+}
+
+pub fn fib(n: &BigDecimal) -> anyhow::Result<BigDecimal> {
+    let n = sequence_index(Function::Fib, n, MAX_FIBONACCI_INDEX)?;
+    Ok(BigDecimal::from(number_theory::fibonacci_pair(n).0))
+}
+
+pub fn lucas(n: &BigDecimal) -> anyhow::Result<BigDecimal> {
+    let n = sequence_index(Function::Lucas, n, MAX_FIBONACCI_INDEX)?;
+    Ok(BigDecimal::from(number_theory::lucas(n)))
+}
+
+pub fn catalan(n: &BigDecimal) -> anyhow::Result<BigDecimal> {
+    let n = sequence_index(Function::Catalan, n, MAX_CATALAN_INDEX)?;
+    Ok(BigDecimal::from(number_theory::catalan(n)))
+}
+
+pub fn triangular(n: &BigDecimal) -> anyhow::Result<BigDecimal> {
+    if !n.is_integer() || n < &BigDecimal::zero() {
+        bail!("triangular is only defined for non-negative integers");
+    }
+    let n = n.with_scale(0).into_bigint_and_exponent().0;
+    Ok(BigDecimal::from(&n * (&n + 1) / 2))
+}
+
+pub fn qmul(args: &[BigDecimal]) -> Vec<BigDecimal> {
+    let (a, b) = args.split_at(4);
+    Quaternion::from_slice(a)
+        .mul(&Quaternion::from_slice(b))
+        .into_vec()
+}
+
+pub fn qconj(args: &[BigDecimal]) -> Vec<BigDecimal> {
+    Quaternion::from_slice(args).conjugate().into_vec()
+}
+
+/// `qrotate(q, vx, vy, vz)`, the vector rotated by the quaternion.
+pub fn qrotate(args: &[BigDecimal]) -> anyhow::Result<Vec<BigDecimal>> {
+    let (q, v) = args.split_at(4);
+    Quaternion::from_slice(q).rotate(v)
+}
+
+/// Decimal places of the amounts returned by money functions.
+pub const MONEY_DIGITS: i64 = 2;
+
+/// Round a monetary amount to cents with banker's rounding (half to even),
+/// so ties like `0.125` don't bias totals upward.
+pub fn round_money(amount: &BigDecimal) -> BigDecimal {
+    amount.with_scale_round(MONEY_DIGITS, RoundingMode::HalfEven)
+}
+
+/// Total, base and signed change of `amount` adjusted by `percent`: added for
+/// tax and tips, subtracted for discounts. The change is rounded to cents.
+pub fn percent_change(func: Function, args: &[BigDecimal]) -> anyhow::Result<Vec<BigDecimal>> {
+    let (base, percent) = (&args[0], &args[1]);
+    if percent < &BigDecimal::zero() {
+        bail!("{} requires a non-negative percentage", func);
+    }
+    if func == Function::Discount && percent > &BigDecimal::from(100) {
+        bail!("discount cannot exceed 100 percent");
+    }
+    let change = round_money(&(base * percent / BigDecimal::from(100)));
+    let delta = if func == Function::Discount {
+        -change
+    } else {
+        change
+    };
+    Ok(vec![base + &delta, base.clone(), delta])
+}
+
+/// Largest number of decimal places accepted by the rounding functions.
+const MAX_ROUNDING_PLACES: i64 = 1000;
+
+/// `x` rounded to `digits` decimal places (0 by default; negative values round
+/// to tens, hundreds...), resolving ties with `mode`.
+pub fn round_places(
+    func: Function,
+    args: &[BigDecimal],
+    mode: RoundingMode,
+) -> anyhow::Result<BigDecimal> {
+    let places = match args.get(1) {
+        Some(digits) => digits
+            .to_i64()
+            .filter(|places| digits.is_integer() && places.abs() <= MAX_ROUNDING_PLACES)
+            .ok_or_else(|| {
+                anyhow!(
+                    "{} digits must be an integer from -{} to {}",
+                    func,
+                    MAX_ROUNDING_PLACES,
+                    MAX_ROUNDING_PLACES
+                )
+            })?,
+        None => 0,
+    };
+    let rounded = args[0].with_scale_round(places, mode);
+    Ok(if places < 0 {
+        rounded.with_scale(0)
+    } else {
+        rounded
+    })
+}
+
+const DEFAULT_CFRAC_TERMS: usize = 20;
+const MAX_CFRAC_TERMS: usize = 1000;
+
+/// The continued-fraction coefficients of `cfrac(x, terms)`.
+pub fn cfrac(args: &[BigDecimal]) -> anyhow::Result<Vec<BigDecimal>> {
+    let terms = match args.get(1) {
+        Some(terms) => terms
+            .is_integer()
+            .then(|| terms.to_usize())
+            .flatten()
+            .filter(|terms| (1..=MAX_CFRAC_TERMS).contains(terms))
+            .ok_or_else(|| anyhow!("cfrac takes between 1 and {} terms", MAX_CFRAC_TERMS))?,
+        None => DEFAULT_CFRAC_TERMS,
+    };
+    Ok(super::rational::continued_fraction(&args[0], terms)
+        .into_iter()
+        .map(BigDecimal::from)
+        .collect())
+}
+
+/// Floored division: the quotient rounds toward negative infinity and the
+/// remainder takes the sign of the divisor, so `a == b * q + r`.
+pub fn floor_div(a: &BigDecimal, b: &BigDecimal) -> anyhow::Result<(BigDecimal, BigDecimal)> {
+    if b.is_zero() {
+        bail!("Division by zero");
+    }
+    let mut quotient = (a / b).with_scale_round(0, RoundingMode::Floor);
+    let mut remainder = a - b * &quotient;
+    // The rounded quotient can be off by one when a / b is not representable
+    let zero = BigDecimal::zero();
+    while !remainder.is_zero() && (remainder < zero) != (b < &zero) {
+        quotient -= 1;
+        remainder += b;
+    }
+    while remainder.abs() >= b.abs() {
+        quotient += 1;
+        remainder -= b;
+    }
+    Ok((quotient, remainder))
+}
+
+/// Default `max_denominator` of `to_fraction`.
+const MAX_DENOMINATOR: u64 = 1_000_000;
+
+pub fn to_fraction(args: &[BigDecimal]) -> anyhow::Result<(BigInt, BigInt)> {
+    let max_denominator = match args.get(1) {
+        Some(limit) if !limit.is_integer() => bail!("Maximum denominator must be an integer"),
+        Some(limit) => limit.with_scale(0).into_bigint_and_exponent().0,
+        None => BigInt::from(MAX_DENOMINATOR),
+    };
+    super::rational::best_approximation(&args[0], &max_denominator)
+}
+
+/// The value of the best rational approximation `to_fraction` finds.
+pub fn fraction_value(args: &[BigDecimal]) -> anyhow::Result<BigDecimal> {
+    let (numerator, denominator) = to_fraction(args)?;
+    Ok(super::precision::divide(
+        &BigDecimal::from(numerator),
+        &BigDecimal::from(denominator),
+        working_digits(),
+    ))
+}
+
+/// `1` when `|a - b| <= tolerance` (default 0, i.e. equal at full precision),
+/// otherwise `0`, together with `|a - b|`.
+pub fn approx_eq(args: &[BigDecimal]) -> anyhow::Result<(BigDecimal, BigDecimal)> {
+    let tolerance = args.get(2).cloned().unwrap_or_else(BigDecimal::zero);
+    if tolerance < BigDecimal::zero() {
+        bail!("Tolerance must not be negative");
+    }
+    let delta = (&args[0] - &args[1]).abs();
+    Ok((BigDecimal::from(u8::from(delta <= tolerance)), delta))
+}
+
+/// `wmean(x1, w1, x2, w2, ...)`, the mean of the values weighted by the
+/// weights that follow them.
+pub fn weighted_mean(args: &[BigDecimal]) -> anyhow::Result<BigDecimal> {
+    if !args.len().is_multiple_of(2) {
+        bail!("wmean takes value, weight pairs");
+    }
+    let (sum, weights) = args.chunks(2).fold(
+        (BigDecimal::zero(), BigDecimal::zero()),
+        |(sum, weights), pair| (sum + &pair[0] * &pair[1], weights + &pair[1]),
+    );
+    if weights.is_zero() {
+        bail!("wmean weights sum to zero");
+    }
+    Ok(super::precision::divide(
+        &sum,
+        &weights,
+        super::precision::working_digits(),
+    ))
+}
+
+/// The integer part, truncated toward zero, at scale 0.
+pub fn int_part(value: &BigDecimal) -> BigDecimal {
+    value.with_scale_round(0, RoundingMode::Down)
+}
+
+/// Largest `n` accepted by `n!`, keeping results to a few tens of thousands of digits.
+pub const MAX_FACTORIAL: u64 = 10_000;
+
+pub fn factorial(value: &BigDecimal) -> anyhow::Result<BigDecimal> {
+    let n = factorial_argument("Factorial", value)?;
+    let product = (2..=n).fold(BigInt::one(), |acc, k| acc * k);
+    Ok(BigDecimal::from(product))
+}
+
+/// `n!! = n * (n - 2) * ...` down to 1 or 2.
+pub fn double_factorial(value: &BigDecimal) -> anyhow::Result<BigDecimal> {
+    let n = factorial_argument("Double factorial", value)?;
+    let product = (2..=n)
+        .rev()
+        .step_by(2)
+        .fold(BigInt::one(), |acc, k| acc * k);
+    Ok(BigDecimal::from(product))
+}
+
+/// `n#`, the product of all primes up to `n`.
+pub fn primorial(value: &BigDecimal) -> anyhow::Result<BigDecimal> {
+    let n = factorial_argument("Primorial", value)? as usize;
+    let mut composite = vec![false; n + 1];
+    let mut product = BigInt::one();
+    for k in 2..=n {
+        super::checkpoint()?;
+        if composite[k] {
+            continue;
+        }
+        product *= k;
+        for multiple in (k * k..=n).step_by(k) {
+            composite[multiple] = true;
+        }
+    }
+    Ok(BigDecimal::from(product))
+}
+
+/// Largest index accepted by `fib` and `lucas` (about 20,900 digits).
+pub const MAX_FIBONACCI_INDEX: u64 = 100_000;
+/// Largest index accepted by `catalan` (about 6,000 digits).
+pub const MAX_CATALAN_INDEX: u64 = 10_000;
+
+fn sequence_index(func: Function, value: &BigDecimal, max: u64) -> anyhow::Result<u64> {
+    if !value.is_integer() || value < &BigDecimal::zero() {
+        bail!("{} is only defined for non-negative integers", func);
+    }
+    value
+        .to_u64()
+        .filter(|n| *n <= max)
+        .ok_or_else(|| anyhow!("{} argument exceeds {}", func, max))
+}
+
+/// A non-negative integer argument no larger than [`MAX_FACTORIAL`].
+fn factorial_argument(name: &str, value: &BigDecimal) -> anyhow::Result<u64> {
+    if !value.is_integer() || value < &BigDecimal::from(0) {
+        bail!("{} is only defined for non-negative integers", name);
+    }
+    value
+        .to_u64()
+        .filter(|n| *n <= MAX_FACTORIAL)
+        .ok_or_else(|| anyhow!("{} argument exceeds {}", name, MAX_FACTORIAL))
+}
+
+/// Inverse trigonometric functions and coordinate conversions are computed in
+/// double precision, using the portable `libm` implementations rather than the
+/// platform's, so every target produces the same bits for the same input.
+fn via_f64(func: Function, arg: &BigDecimal, f: impl Fn(f64) -> f64) -> anyhow::Result<BigDecimal> {
+    from_f64(func, f(to_f64(func, arg)?))
+}
+
+fn to_f64(func: Function, arg: &BigDecimal) -> anyhow::Result<f64> {
+    arg.to_f64()
+        .filter(|x| x.is_finite())
+        .ok_or_else(|| anyhow!("Argument out of range for {}", func))
+}
+
+fn from_f64(func: Function, y: f64) -> anyhow::Result<BigDecimal> {
+    if !y.is_finite() {
+        bail!("Result out of range for {}", func);
+    }
+    // Shortest round-trip decimal rather than the exact binary expansion
+    Ok(BigDecimal::from_str(&y.to_string())?)
+}
+
+fn to_radians(angle: f64, mode: AngleMode) -> f64 {
+    match mode {
+        AngleMode::Radians => angle,
+        AngleMode::Degrees => angle.to_radians(),
+    }
+}
+
+fn from_radians(angle: f64, mode: AngleMode) -> f64 {
+    match mode {
+        AngleMode::Radians => angle,
+        AngleMode::Degrees => angle.to_degrees(),
+    }
+}
+
+/// Converted coordinates in [`Function::components`] order. Angles are in
+/// `mode`; spherical coordinates are `(r, theta, phi)` with `theta` measured
+/// from the z axis and `phi` the azimuth in the xy plane.
+pub fn coordinates(
+    func: Function,
+    args: &[BigDecimal],
+    mode: AngleMode,
+) -> anyhow::Result<Vec<BigDecimal>> {
+    // The angle from the positive x axis to the point (x, y)
+    let direction = |x: &BigDecimal, y: &BigDecimal| -> anyhow::Result<BigDecimal> {
+        let angle = libm::atan2(to_f64(func, y)?, to_f64(func, x)?);
+        from_f64(func, from_radians(angle, mode))
+    };
+    // The cosine and sine of an angle argument
+    let trig = |angle: &BigDecimal| -> anyhow::Result<(BigDecimal, BigDecimal)> {
+        let radians = to_radians(to_f64(func, angle)?, mode);
+        Ok((
+            from_f64(func, libm::cos(radians))?,
+            from_f64(func, libm::sin(radians))?,
+        ))
+    };
+    Ok(match func {
+        Function::Polar => {
+            let (x, y) = (&args[0], &args[1]);
+            vec![vector::norm(args)?, direction(x, y)?]
+        }
+        Function::Cartesian => {
+            let (r, (cos, sin)) = (&args[0], trig(&args[1])?);
+            vec![r * cos, r * sin]
+        }
+        Function::Spherical => {
+            let (x, y, z) = (&args[0], &args[1], &args[2]);
+            let rho = vector::norm(&args[..2])?;
+            vec![vector::norm(args)?, direction(z, &rho)?, direction(x, y)?]
+        }
+        Function::Cylindrical => {
+            let (x, y, z) = (&args[0], &args[1], &args[2]);
+            vec![vector::norm(&args[..2])?, direction(x, y)?, z.clone()]
+        }
+        Function::FromSpherical => {
+            let r = &args[0];
+            let ((cos_theta, sin_theta), (cos_phi, sin_phi)) = (trig(&args[1])?, trig(&args[2])?);
+            vec![
+                r * &sin_theta * cos_phi,
+                r * &sin_theta * sin_phi,
+                r * cos_theta,
+            ]
+        }
+        Function::FromCylindrical => {
+            let (rho, (cos, sin), z) = (&args[0], trig(&args[1])?, &args[2]);
+            vec![rho * cos, rho * sin, z.clone()]
+        }
+        _ => bail!("{} is not a coordinate conversion", func),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::{self, Environment, EvalContext, eval};
+
+    fn num(value: &str) -> BigDecimal {
+        BigDecimal::from_str(value).unwrap()
+    }
+
+    fn error(input: &str) -> String {
+        eval(input).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_floor_div() {
+        for (a, b, quotient, remainder) in [
+            ("7", "2", "3", "1"),
+            ("-7", "2", "-4", "1"),
+            ("7", "-2", "-4", "-1"),
+            ("-7", "-2", "3", "-1"),
+            ("5.5", "0.2", "27", "0.1"),
+            ("0", "3", "0", "0"),
+        ] {
+            let (a, b) = (num(a), num(b));
+            let (q, r) = floor_div(&a, &b).unwrap();
+            assert_eq!((&q, &r), (&num(quotient), &num(remainder)), "{a} // {b}");
+            assert_eq!(&b * &q + &r, a);
+        }
+        let components =
+            apply_components(Function::DivMod, &[num("-7"), num("2")], AngleMode::Radians);
+        assert_eq!(components.unwrap(), [num("-4"), num("1")]);
+        assert_eq!(eval("7.5 // -2").unwrap(), num("-4"));
+        // `//` binds like `*`
+        assert_eq!(eval("1 + 9 // 2 * 3").unwrap(), num("13"));
+        assert_eq!(eval("divmod(-7, 2) * 10").unwrap(), num("-40"));
+        let ctx = EvalContext::default();
+        assert_eq!(
+            evaluator::components("q = divmod(-7, 2)", &Environment::new(), &ctx).unwrap(),
+            Some(vec![("quotient", num("-4")), ("remainder", num("1"))])
+        );
+        assert_eq!(
+            evaluator::components("7 // 2", &Environment::new(), &ctx).unwrap(),
+            None
+        );
+
+        assert_eq!(error("divmod(7, 0)"), "Division by zero");
+        assert_eq!(error("7 // 0"), "Division by zero");
+        assert_eq!(
+            error("divmod(7)"),
+            "Wrong number of arguments for divmod: 1"
+        );
+    }
+
+    #[test]
+    fn test_digit_helpers() {
+        assert_eq!(eval("digits(0)").unwrap(), num("1"));
+        assert_eq!(eval("digits(-123.9)").unwrap(), num("3"));
+        assert_eq!(eval("intpart(9.99)").unwrap(), num("9"));
+        assert_eq!(eval("intpart(-0.5)").unwrap(), num("0"));
+        assert_eq!(eval("fracpart(-7.25)").unwrap(), num("-0.25"));
+        assert_eq!(eval("intpart(12.5) + fracpart(12.5)").unwrap(), num("12.5"));
+        assert_eq!(eval("scale(2.500)").unwrap(), num("1"));
+        assert_eq!(eval("scale(1e-3)").unwrap(), num("3"));
+        assert_eq!(eval("scale(1200)").unwrap(), num("0"));
+        assert_eq!(eval("scale(1 / 3)").unwrap(), num("100"));
+        assert_eq!(eval("digits(2 ^ 100)").unwrap(), num("31"));
+
+        assert_eq!(
+            error("digits(1, 2)"),
+            "Wrong number of arguments for digits: 2"
+        );
+        assert_eq!(error("scale()"), "Wrong number of arguments for scale: 0");
+    }
+
+    #[test]
+    fn test_clamp_lerp_and_map_range() {
+        assert_eq!(eval("clamp(5, 0, 10)").unwrap(), num("5"));
+        assert_eq!(eval("clamp(7, 7, 7)").unwrap(), num("7"));
+        assert_eq!(eval("clamp(-0.5, 0, 1)").unwrap(), num("0"));
+        assert_eq!(eval("lerp(10, 20, 0)").unwrap(), num("10"));
+        assert_eq!(eval("lerp(10, 20, 1)").unwrap(), num("20"));
+        // Outside [0, 1] it extrapolates
+        assert_eq!(eval("lerp(10, 20, 1.5)").unwrap(), num("25"));
+        assert_eq!(eval("map_range(25, 0, 100, 1, 0)").unwrap(), num("0.75"));
+        assert_eq!(
+            eval("map_range(-40, -40, 100, -40, 212)").unwrap(),
+            num("-40")
+        );
+
+        assert_eq!(error("clamp(1, 2, 0)"), "clamp requires lo <= hi");
+        assert_eq!(
+            error("map_range(1, 3, 3, 0, 1)"),
+            "map_range requires a non-empty source range"
+        );
+        assert_eq!(error("lerp(1, 2)"), "Wrong number of arguments for lerp: 2");
+    }
+
+    #[test]
+    fn test_hypot_and_angle_conversions() {
+        assert_eq!(eval("hypot(0, 0)").unwrap(), num("0"));
+        assert_eq!(eval("hypot(5, 12)").unwrap(), num("13"));
+        assert_eq!(eval("hypot(1, 2, 2, 4)").unwrap(), num("5"));
+        assert_eq!(eval("hypot(0.3, 0.4)").unwrap(), num("0.5"));
+        // No overflow or underflow at extreme exponents
+        assert_eq!(eval("hypot(3e-60000, 4e-60000)").unwrap(), num("5e-60000"));
+        assert_eq!(eval("hypot(3e90000, 4e90000) / 1e90000").unwrap(), num("5"));
+        assert_eq!(
+            eval("approx_eq(hypot(1, 1), sqrt(2), 1e-99)").unwrap(),
+            num("1")
+        );
+        assert_eq!(eval("deg2rad(-90)").unwrap(), eval("-pi / 2").unwrap());
+        assert_eq!(eval("rad2deg(-pi)").unwrap(), num("-180"));
+        assert_eq!(eval("deg2rad(0) + rad2deg(0)").unwrap(), num("0"));
+
+        assert_eq!(error("hypot()"), "Wrong number of arguments for hypot: 0");
+        assert_eq!(
+            error("deg2rad(1, 2)"),
+            "Wrong number of arguments for deg2rad: 2"
+        );
+    }
+
+    #[test]
+    fn test_approx_eq() {
+        // The tolerance is inclusive
+        assert_eq!(eval("approx_eq(1, 1.5, 0.5)").unwrap(), num("1"));
+        assert_eq!(eval("approx_eq(1, 1.5, 0.49)").unwrap(), num("0"));
+        assert_eq!(eval("approx_eq(-2, -2)").unwrap(), num("1"));
+        assert_eq!(eval("approx_eq(1 / 3, 0.333)").unwrap(), num("0"));
+        let components = apply_components(
+            Function::ApproxEq,
+            &[num("2"), num("2.25"), num("0.1")],
+            AngleMode::Radians,
+        );
+        assert_eq!(components.unwrap(), [num("0"), num("0.25")]);
+
+        assert_eq!(
+            error("approx_eq(1, 1, -0.1)"),
+            "Tolerance must not be negative"
+        );
+        assert_eq!(
+            error("approx_eq(1)"),
+            "Wrong number of arguments for approx_eq: 1"
+        );
+    }
+
+    #[test]
+    fn test_double_factorial_and_primorial() {
+        for (n, expected) in [(0, "1"), (1, "1"), (2, "2"), (6, "48"), (9, "945")] {
+            assert_eq!(
+                double_factorial(&BigDecimal::from(n)).unwrap(),
+                num(expected)
+            );
+        }
+        for (n, expected) in [(0, "1"), (1, "1"), (2, "2"), (4, "6"), (30, "6469693230")] {
+            assert_eq!(primorial(&BigDecimal::from(n)).unwrap(), num(expected));
+        }
+        assert_eq!(eval("5!! * 2#").unwrap(), num("30"));
+        assert_eq!(eval("8!! + 0!!").unwrap(), num("385"));
+        assert_eq!(eval("(0# + 1#) * 2#").unwrap(), num("4"));
+        assert_eq!(eval("3#!").unwrap(), num("720"));
+
+        assert_eq!(
+            error("(-1)!!"),
+            "Double factorial is only defined for non-negative integers"
+        );
+        assert_eq!(
+            error("2.5!!"),
+            "Double factorial is only defined for non-negative integers"
+        );
+        assert_eq!(error("10001!!"), "Double factorial argument exceeds 10000");
+        assert_eq!(
+            error("(-3)#"),
+            "Primorial is only defined for non-negative integers"
+        );
+        assert_eq!(
+            error("1.5#"),
+            "Primorial is only defined for non-negative integers"
+        );
+        assert_eq!(error("20000#"), "Primorial argument exceeds 10000");
+    }
+
+    #[test]
+    fn test_means() {
+        assert_eq!(eval("wmean(3, 1)").unwrap(), num("3"));
+        assert_eq!(eval("wmean(1, 1, 4, 2)").unwrap(), num("3"));
+        // Negative weights are allowed as long as they don't cancel out
+        assert_eq!(eval("wmean(10, 2, 4, -1)").unwrap(), num("16"));
+        assert_eq!(eval("geomean(7)").unwrap(), num("7"));
+        assert_eq!(eval("geomean(1, 3, 9)").unwrap(), num("3"));
+        assert_eq!(eval("geomean(0.5, 0.1, 0.16)").unwrap(), num("0.2"));
+        assert_eq!(eval("geomean(5, 0)").unwrap(), num("0"));
+        assert_eq!(eval("harmean(2, 2, 2)").unwrap(), num("2"));
+        assert_eq!(eval("harmean(3, 6)").unwrap(), num("4"));
+        assert_eq!(eval("harmean(1, 2)").unwrap(), eval("4 / 3").unwrap());
+
+        assert_eq!(error("wmean(1, 2, 3)"), "wmean takes value, weight pairs");
+        assert_eq!(error("wmean(1, 1, 2, -1)"), "wmean weights sum to zero");
+        assert_eq!(
+            error("geomean(4, -1)"),
+            "geomean requires non-negative values"
+        );
+        assert_eq!(error("harmean(1, -2)"), "harmean requires positive values");
+        assert_eq!(error("harmean(0)"), "harmean requires positive values");
+    }
+
+    #[test]
+    fn test_tax_tip_and_discount() {
+        let components = |func: Function, base: &str, percent: &str| {
+            apply_components(func, &[num(base), num(percent)], AngleMode::Radians).unwrap()
+        };
+        assert_eq!(
+            components(Function::WithTax, "100", "8.25"),
+            [num("108.25"), num("100"), num("8.25")]
+        );
+        assert_eq!(
+            components(Function::Discount, "50", "100"),
+            [num("0"), num("50"), num("-50")]
+        );
+        assert_eq!(
+            components(Function::Tip, "42.5", "0"),
+            [num("42.5"), num("42.5"), num("0")]
+        );
+        assert_eq!(eval("tip(80, 20)").unwrap(), num("96"));
+        let sale = evaluator::components(
+            "discount(59.99, 25)",
+            &Environment::new(),
+            &EvalContext::default(),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(sale[2], ("delta", num("-15.00")));
+        assert_eq!(eval("with_tax(0.5, 25)").unwrap(), num("0.62"));
+
+        assert_eq!(
+            error("discount(10, 100.01)"),
+            "discount cannot exceed 100 percent"
+        );
+        assert_eq!(
+            error("with_tax(10, -1)"),
+            "with_tax requires a non-negative percentage"
+        );
+        assert_eq!(
+            error("tip(10, -5)"),
+            "tip requires a non-negative percentage"
+        );
+        assert_eq!(error("tip(10)"), "Wrong number of arguments for tip: 1");
+    }
+
+    #[test]
+    fn test_rounding_modes() {
+        let round = |input: &str| eval(input).unwrap().to_string();
+        for (input, expected) in [
+            ("round_half_even(2.5)", "2"),
+            ("round_half_even(3.5)", "4"),
+            ("round_half_even(-2.5)", "-2"),
+            ("round_half_up(2.5)", "3"),
+            ("round_half_up(-2.5)", "-3"),
+            ("round_half_even(1.005, 2)", "1.00"),
+            ("round_half_up(1.005, 2)", "1.01"),
+            ("round_half_even(2.665, 2)", "2.66"),
+            ("round_half_up(2.665, 2)", "2.67"),
+            ("round_half_even(-2.675, 2)", "-2.68"),
+            ("round_half_even(1250, -2)", "1200"),
+            ("round_half_up(1250, -2)", "1300"),
+        ] {
+            assert_eq!(round(input), expected, "{input}");
+        }
+        assert_eq!(round_money(&num("0.125")), num("0.12"));
+        assert_eq!(round_money(&num("0.135")), num("0.14"));
+        assert_eq!(round_money(&num("-0.125")).to_string(), "-0.12");
+
+        assert_eq!(
+            error("round_half_even(1, 0.5)"),
+            "round_half_even digits must be an integer from -1000 to 1000"
+        );
+        assert_eq!(
+            error("round_half_up(1, 1001)"),
+            "round_half_up digits must be an integer from -1000 to 1000"
+        );
+    }
+
+    #[test]
+    fn test_integer_sequences() {
+        assert_eq!(eval("fib(10) + lucas(10)").unwrap(), num("178"));
+        assert_eq!(eval("digits(fib(10000))").unwrap(), num("2090"));
+        assert_eq!(eval("catalan(10)").unwrap(), num("16796"));
+        assert_eq!(eval("triangular(100)").unwrap(), num("5050"));
+        assert_eq!(
+            eval("triangular(10 ^ 30) - 10 ^ 60 / 2").unwrap(),
+            eval("10 ^ 30 / 2").unwrap()
+        );
+
+        assert!(eval("fib(-1)").is_err());
+        assert!(eval("lucas(2.5)").is_err());
+        assert!(eval("fib(10 ^ 6)").is_err());
+        assert!(eval("catalan(20000)").is_err());
+    }
+
+    #[test]
+    fn test_vector_and_quaternion_functions() {
+        let terms = |input: &str| {
+            let mut env = Environment::new();
+            evaluator::eval_statements(input, &mut env, &EvalContext::default())
+                .unwrap()
+                .terms
+                .unwrap()
+        };
+        assert_eq!(eval("norm(1, 2, 2)").unwrap(), num("3"));
+        assert_eq!(eval("dot(1, 2, 3, 4, 5, 6)").unwrap(), num("32"));
+        assert_eq!(eval("angle(1, 1, 2, 2)").unwrap(), num("0"));
+        assert_eq!(
+            eval("approx_eq(angle(1, 0, 0, 2), pi / 2, 1e-15)").unwrap(),
+            num("1")
+        );
+        assert_eq!(terms("normalize(3, 4)"), [num("0.6"), num("0.8")]);
+        assert_eq!(terms("proj(2, 3, 4, 0)"), [num("2"), num("0")]);
+        // i * j = k, and a quarter turn about z takes x to y
+        assert_eq!(
+            terms("qmul(0, 1, 0, 0, 0, 0, 1, 0)"),
+            ["0", "0", "0", "1"].map(num)
+        );
+        assert_eq!(terms("qconj(1, 2, -3, 4)"), ["1", "-2", "3", "-4"].map(num));
+        assert_eq!(
+            terms("qrotate(1, 0, 0, 1, 1, 0, 0)"),
+            ["0", "1", "0"].map(num)
+        );
+        assert_eq!(eval("qnorm(1, 1, 1, 1)").unwrap(), num("2"));
+
+        assert!(eval("angle(1, 2, 3)").is_err());
+        assert!(eval("angle(0, 0, 1, 1)").is_err());
+        assert!(eval("normalize(0, 0)").is_err());
+        assert!(eval("qrotate(0, 0, 0, 0, 1, 0, 0)").is_err());
+        assert!(eval("qmul(1, 2, 3)").is_err());
+    }
+
+    #[test]
+    fn test_coordinate_conversions() {
+        let mut degrees = Environment::new();
+        degrees.set_angle_mode(AngleMode::Degrees);
+        let components = |input: &str, env: &Environment| {
+            evaluator::components(input, env, &EvalContext::default())
+                .unwrap()
+                .unwrap()
+        };
+        let polar = components("polar(3, 4)", &Environment::new());
+        assert_eq!(polar[0], ("r", num("5")));
+        assert_eq!(eval("polar(-2, 0)").unwrap(), num("2"));
+        let point = components("cartesian(2, 90)", &degrees);
+        assert_eq!(point[1], ("y", num("2")));
+        let sphere: Vec<_> = components("spherical(0, 1, 0)", &degrees)
+            .into_iter()
+            .map(|(_, value)| value)
+            .collect();
+        assert_eq!(sphere, ["1", "90", "90"].map(num));
+        let cylinder = components("cylindrical(1, 1, 5)", &degrees);
+        assert_eq!(cylinder[1], ("phi", num("45")));
+        assert_eq!(cylinder[2], ("z", num("5")));
+        let back = components("from_cylindrical(2, 0, 7)", &degrees);
+        assert_eq!(back[0], ("x", num("2")));
+        assert_eq!(
+            evaluator::eval_in("angle(1, 0, 0, 1)", &mut degrees, &EvalContext::default()).unwrap(),
+            num("90")
+        );
+    }
+}
@@ -0,0 +1,131 @@
+//! Exact evaluation of integer-only expressions with `BigInt`, skipping the
+//! scale bookkeeping and rounding of the decimal path.
+
+use anyhow::bail;
+use bigdecimal::num_bigint::BigInt;
+use num_traits::{Pow, Signed, ToPrimitive, Zero};
+
+use super::error::Overflow;
+use super::limits::Limits;
+use super::{Operator, Token};
+
+/// Evaluate `rpn` when it only combines integer literals with `+ - * % ^`.
+/// `Ok(None)` means decimal arithmetic is needed instead, either from the start
+/// (division, decimal literals, functions) or once a negative exponent turns up.
+pub fn try_eval(rpn: &[Token], limits: &Limits) -> anyhow::Result<Option<BigInt>> {
+    if !rpn.iter().all(is_integer_token) {
+        return Ok(None);
+    }
+    let mut stack: Vec<BigInt> = Vec::new();
+    for token in rpn {
+        super::checkpoint()?;
+        let value = match token {
+            Token::Number(num) => num.as_bigint_and_exponent().0,
+            Token::Op(op) if op.is_unary() => {
+                let Some(value) = stack.pop() else {
+                    return Ok(None);
+                };
+                match op {
+                    Operator::UnarySub => -value,
+                    Operator::Square => pow(value, 2, limits)?,
+                    Operator::Cube => pow(value, 3, limits)?,
+                    _ => value,
+                }
+            }
+            Token::Op(op) => {
+                let (Some(rhs), Some(lhs)) = (stack.pop(), stack.pop()) else {
+                    return Ok(None);
+                };
+                match op {
+                    Operator::Add => lhs + rhs,
+                    Operator::Sub => lhs - rhs,
+                    Operator::Mul => lhs * rhs,
+                    Operator::Mod => {
+                        if rhs.is_zero() {
+                            bail!("Modulo by zero");
+                        }
+                        lhs % rhs
+                    }
+                    _ => match rhs.to_u64() {
+                        Some(exponent) if lhs.magnitude().bits() > 1 => pow(lhs, exponent, limits)?,
+                        // Negative exponents, and huge ones on 0 or ±1, take the decimal path
+                        _ => return Ok(None),
+                    },
+                }
+            }
+            _ => return Ok(None),
+        };
+        limits.check(min_digits(&value), 0)?;
+        stack.push(value);
+    }
+    match (stack.pop(), stack.is_empty()) {
+        (Some(result), true) => Ok(Some(result)),
+        _ => Ok(None),
+    }
+}
+
+fn is_integer_token(token: &Token) -> bool {
+    match token {
+        Token::Number(num) => num.fractional_digit_count() == 0,
+        Token::Op(op) => matches!(
+            op,
+            Operator::Add
+                | Operator::Sub
+                | Operator::Mul
+                | Operator::Mod
+                | Operator::Pow
+                | Operator::UnarySub
+                | Operator::UnaryAdd
+                | Operator::Square
+                | Operator::Cube
+        ),
+        _ => false,
+    }
+}
+
+fn pow(base: BigInt, exponent: u64, limits: &Limits) -> Result<BigInt, Overflow> {
+    if base.magnitude().bits() > 1 {
+        limits.check(min_digits(&base).saturating_mul(i128::from(exponent)), 0)?;
+    }
+    Ok(Pow::pow(base, exponent))
+}
+
+/// A lower bound on the decimal digits of `value`, exact for most inputs.
+fn min_digits(value: &BigInt) -> i128 {
+    let bits = value.abs().bits();
+    if bits == 0 {
+        return 1;
+    }
+    ((bits - 1) as f64 * std::f64::consts::LOG10_2).floor() as i128 + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::evaluator::eval;
+    use bigdecimal::BigDecimal;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_integer_mode_matches_decimal() {
+        let cases = [
+            ("2 + 3 * 4", "14"),
+            ("-7 % 3", "-1"),
+            ("3 ^ 200 - 3 ^ 200 + 1", "1"),
+            ("(-2) ^ 63", "-9223372036854775808"),
+            ("12²", "144"),
+            ("2 ^ -2", "0.25"),
+            ("1 ^ 100000000000", "1"),
+            ("6 / 4", "1.5"),
+            ("2.0 * 3", "6.0"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(
+                eval(input).unwrap(),
+                BigDecimal::from_str(expected).unwrap(),
+                "evaluating {input}"
+            );
+        }
+        assert!(eval("7 % 0").is_err());
+        assert!(eval("10 ^ 200000").is_err());
+    }
+}
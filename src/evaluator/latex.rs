@@ -0,0 +1,195 @@
+//! Translation of a restricted LaTeX subset into plain infix, e.g.
+//! `\frac{1}{2} \cdot \sqrt{2}` becomes `((1) / (2)) * sqrt(2)`.
+
+use anyhow::bail;
+
+use super::limits::DepthBudget;
+
+pub fn is_latex(input: &str) -> bool {
+    input.contains(['\\', '{', '$'])
+}
+
+pub fn to_infix(input: &str) -> anyhow::Result<String> {
+    let trimmed = strip_delimiters(input.trim());
+    let mut parser = Parser {
+        chars: trimmed.chars().collect(),
+        pos: 0,
+        budget: DepthBudget::from_limits(),
+    };
+    let out = parser.sequence(None)?;
+    Ok(out.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+fn strip_delimiters(input: &str) -> &str {
+    for (open, close) in [("$$", "$$"), ("$", "$"), ("\\(", "\\)"), ("\\[", "\\]")] {
+        if let Some(inner) = input
+            .strip_prefix(open)
+            .and_then(|rest| rest.strip_suffix(close))
+        {
+            return inner;
+        }
+    }
+    input
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    budget: DepthBudget,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        Some(c)
+    }
+
+    /// Translate until `end` (consumed) or end of input when `end` is `None`.
+    fn sequence(&mut self, end: Option<char>) -> anyhow::Result<String> {
+        let mut out = String::new();
+        loop {
+            match self.next() {
+                None if end.is_none() => return Ok(out),
+                None => bail!("Unclosed LaTeX group"),
+                Some(c) if Some(c) == end => return Ok(out),
+                Some('}') => bail!("Unmatched closing brace"),
+                Some('{') => {
+                    let inner = self.group()?;
+                    push_operand(&mut out, &format!("({inner})"));
+                }
+                Some('\\') => self.command(&mut out)?,
+                Some(c) => out.push(c),
+            }
+        }
+    }
+
+    /// Run one level deeper into the input, e.g. a braced group or a command argument.
+    fn nested<T>(&mut self, f: impl FnOnce(&mut Self) -> anyhow::Result<T>) -> anyhow::Result<T> {
+        let outer = self.budget;
+        self.budget = outer.descend()?;
+        let result = f(self);
+        self.budget = outer;
+        result
+    }
+
+    /// The rest of a braced group whose `{` was just consumed.
+    fn group(&mut self) -> anyhow::Result<String> {
+        self.nested(|parser| parser.sequence(Some('}')))
+    }
+
+    /// A `\frac` or `\sqrt` argument: a braced group, a command or a single character.
+    fn argument(&mut self) -> anyhow::Result<String> {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+        match self.next() {
+            Some('{') => self.group(),
+            Some('\\') => self.nested(|parser| {
+                let mut out = String::new();
+                parser.command(&mut out)?;
+                Ok(out)
+            }),
+            Some(c) if c.is_ascii_alphanumeric() => Ok(c.to_string()),
+            _ => bail!("Missing LaTeX argument"),
+        }
+    }
+
+    fn command(&mut self, out: &mut String) -> anyhow::Result<()> {
+        let mut name = String::new();
+        while let Some(c) = self.peek().filter(char::is_ascii_alphabetic) {
+            name.push(c);
+            self.pos += 1;
+        }
+        if name.is_empty() {
+            // Escaped symbols like `\,` (spacing) or `\%`
+            return match self.next() {
+                Some(',' | ';' | ':' | '!' | ' ') => Ok(()),
+                Some(c @ ('%' | '{' | '}')) => {
+                    out.push(if c == '%' { '%' } else { ' ' });
+                    Ok(())
+                }
+                Some(c) => bail!("Unsupported LaTeX command: \\{}", c),
+                None => bail!("Dangling backslash"),
+            };
+        }
+
+        match name.as_str() {
+            "frac" | "dfrac" | "tfrac" => {
+                let numerator = self.argument()?;
+                let denominator = self.argument()?;
+                push_operand(out, &format!("(({numerator}) / ({denominator}))"));
+            }
+            "sqrt" => {
+                if self.peek() == Some('[') {
+                    bail!("Only square roots are supported: \\sqrt[n]{{...}}");
+                }
+                let radicand = self.argument()?;
+                push_operand(out, &format!("sqrt({radicand})"));
+            }
+            "cdot" | "times" | "ast" => out.push_str(" * "),
+            "div" => out.push_str(" / "),
+            "bmod" | "mod" => out.push_str(" % "),
+            "pi" | "tau" | "phi" | "varphi" => {
+                let name = if name == "varphi" { "phi" } else { &name };
+                push_operand(out, name);
+            }
+            "left" | "right" | "quad" | "qquad" => {}
+            _ => bail!("Unsupported LaTeX command: \\{}", name),
+        }
+        Ok(())
+    }
+}
+
+/// Append an operand, making juxtaposition such as `2\pi` an explicit product.
+fn push_operand(out: &mut String, operand: &str) {
+    if out
+        .trim_end()
+        .ends_with(|c: char| c.is_ascii_alphanumeric() || c == ')' || c == '.')
+    {
+        out.push_str(" * ");
+    }
+    out.push_str(operand);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::eval;
+    use bigdecimal::BigDecimal;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_latex_to_infix() {
+        assert_eq!(to_infix(r"\frac{1}{2}").unwrap(), "((1) / (2))");
+        assert_eq!(to_infix(r"2^{3+1}").unwrap(), "2^(3+1)");
+        assert_eq!(to_infix(r"$2\pi$").unwrap(), "2 * pi");
+        assert_eq!(to_infix(r"\sqrt{2} \cdot 3").unwrap(), "sqrt(2) * 3");
+        assert_eq!(to_infix(r"\frac12").unwrap(), "((1) / (2))");
+    }
+
+    #[test]
+    fn test_eval_latex() {
+        assert_eq!(
+            eval(r"\frac{1}{2} + \frac{3}{4}").unwrap(),
+            BigDecimal::from_str("1.25").unwrap()
+        );
+        assert_eq!(
+            eval(r"\sqrt{16} \cdot 2^{3}").unwrap(),
+            BigDecimal::from(32)
+        );
+        assert_eq!(
+            eval(r"\left(1 + 2\right) \times 3").unwrap(),
+            BigDecimal::from(9)
+        );
+        assert_eq!(eval(r"\frac{\pi}{\pi}").unwrap(), BigDecimal::from(1));
+
+        assert!(eval(r"\int_0^1 x").is_err());
+        assert!(eval(r"\sqrt[3]{8}").is_err());
+        assert!(eval(r"\frac{1}{2").is_err());
+    }
+}
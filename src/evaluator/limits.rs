@@ -0,0 +1,231 @@
+use bigdecimal::BigDecimal;
+use num_traits::ToPrimitive;
+
+use super::Operator;
+use super::cost::CostEstimate;
+use super::error::{CostExceeded, DepthExceeded, MemoryExceeded, Overflow};
+
+/// Caps on the size of values produced during evaluation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    pub max_digits: u64,
+    pub max_scale: i64,
+    /// Significant digits of inexact results such as `1 / 3`
+    pub precision: u64,
+    /// Deepest nesting of parentheses, groups or subexpressions
+    pub max_depth: usize,
+    /// Largest accepted [`CostEstimate::cost`]
+    pub max_cost: u64,
+    /// Largest number of rows of a matrix operation
+    pub max_matrix_size: usize,
+    /// Wall-clock budget of one request's evaluation in milliseconds, 0 for none
+    pub max_eval_ms: u64,
+    /// Smallest estimated cost of a subexpression worth evaluating on another
+    /// thread, 0 to evaluate on the calling thread only
+    pub parallel_min_cost: u64,
+    /// Bytes the values of one request may take, 0 for no limit
+    pub max_memory_bytes: u64,
+}
+
+impl Limits {
+    pub const DEFAULT: Limits = Limits {
+        max_digits: 100_000,
+        max_scale: 100_000,
+        precision: 100,
+        max_depth: 256,
+        max_cost: 10_000_000,
+        max_matrix_size: 10,
+        max_eval_ms: 10_000,
+        parallel_min_cost: 100_000,
+        max_memory_bytes: 256 * 1024 * 1024,
+    };
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits::DEFAULT
+    }
+}
+
+/// Remaining nesting allowance for a recursive pass. Every pass that descends
+/// into subexpressions takes one and calls [`DepthBudget::descend`] per level,
+/// so all of them reject deep input with the same [`DepthExceeded`] error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DepthBudget {
+    remaining: usize,
+    limit: usize,
+}
+
+impl DepthBudget {
+    pub fn new(limit: usize) -> Self {
+        DepthBudget {
+            remaining: limit,
+            limit,
+        }
+    }
+
+    /// The budget for the `max_depth` of the entered context.
+    pub fn from_limits() -> Self {
+        DepthBudget::new(limits().max_depth)
+    }
+
+    pub fn descend(self) -> Result<DepthBudget, DepthExceeded> {
+        match self.remaining.checked_sub(1) {
+            Some(remaining) => Ok(DepthBudget {
+                remaining,
+                limit: self.limit,
+            }),
+            None => Err(DepthExceeded { limit: self.limit }),
+        }
+    }
+
+    /// Fail if `depth` levels would exceed what is left of the budget.
+    pub fn check(&self, depth: usize) -> Result<(), DepthExceeded> {
+        if depth > self.remaining {
+            return Err(DepthExceeded { limit: self.limit });
+        }
+        Ok(())
+    }
+}
+
+/// The limits of the entered [`EvalContext`](super::EvalContext), else the
+/// defaults.
+pub fn limits() -> Limits {
+    super::context::active_limits().unwrap_or(Limits::DEFAULT)
+}
+
+pub fn check_operands(lhs: &BigDecimal, rhs: &BigDecimal, op: Operator) -> Result<(), Overflow> {
+    limits().check_operands(lhs, rhs, op)
+}
+
+pub fn check_value(value: &BigDecimal) -> Result<(), Overflow> {
+    limits().check_value(value)
+}
+
+impl Limits {
+    /// Reject `lhs op rhs` up front when its exact result would be too large to build.
+    pub fn check_operands(
+        &self,
+        lhs: &BigDecimal,
+        rhs: &BigDecimal,
+        op: Operator,
+    ) -> Result<(), Overflow> {
+        let (da, sa) = (lhs.digits() as i128, lhs.fractional_digit_count() as i128);
+        let (db, sb) = (rhs.digits() as i128, rhs.fractional_digit_count() as i128);
+        let (digits, scale) = match op {
+            Operator::Add | Operator::Sub | Operator::Mod | Operator::FloorDiv => {
+                ((da - sa).max(db - sb) + sa.max(sb) + 1, sa.max(sb))
+            }
+            Operator::Mul => (da + db, sa + sb),
+            Operator::Pow => {
+                let magnitude = lhs.abs();
+                if magnitude == BigDecimal::from(1) || magnitude == BigDecimal::from(0) {
+                    return Ok(());
+                }
+                // Invalid exponents are reported by the operator itself
+                let Some(exponent) = rhs.is_integer().then(|| rhs.abs().to_i128()).flatten() else {
+                    return Ok(());
+                };
+                (da.saturating_mul(exponent), sa.saturating_mul(exponent))
+            }
+            _ => return Ok(()),
+        };
+        self.check(digits, scale)
+    }
+
+    pub fn check_cost(&self, estimate: &CostEstimate) -> Result<(), CostExceeded> {
+        if estimate.cost > self.max_cost {
+            return Err(CostExceeded {
+                estimated: estimate.cost,
+                limit: self.max_cost,
+            });
+        }
+        Ok(())
+    }
+
+    /// Reject an evaluation whose values take more than `max_memory_bytes`.
+    pub fn check_memory(&self, bytes: u64) -> Result<(), MemoryExceeded> {
+        if self.max_memory_bytes > 0 && bytes > self.max_memory_bytes {
+            return Err(MemoryExceeded {
+                estimated_bytes: bytes,
+                limit_bytes: self.max_memory_bytes,
+            });
+        }
+        Ok(())
+    }
+
+    /// Reject a computed value that exceeds the limits.
+    pub fn check_value(&self, value: &BigDecimal) -> Result<(), Overflow> {
+        self.check(
+            value.digits() as i128,
+            value.fractional_digit_count() as i128,
+        )
+    }
+
+    /// Reject a literal that exceeds the limits, judged by its exponent alone
+    /// so that `1e-999999999` is turned away without building its digits.
+    pub fn check_literal(&self, value: &BigDecimal) -> Result<(), Overflow> {
+        let (digits, scale) = (
+            value.digits() as i128,
+            value.fractional_digit_count() as i128,
+        );
+        // `1e9` is one stored digit but ten written ones
+        self.check((digits - scale).max(digits), scale)
+    }
+
+    pub(super) fn check(&self, digits: i128, scale: i128) -> Result<(), Overflow> {
+        if digits > i128::from(self.max_digits) {
+            return Err(Overflow::Digits {
+                estimated: digits,
+                limit: self.max_digits,
+            });
+        }
+        if scale.abs() > i128::from(self.max_scale) {
+            return Err(Overflow::Scale {
+                estimated: scale,
+                limit: self.max_scale,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_custom_limits() {
+        let limits = Limits {
+            max_digits: 10,
+            max_scale: 5,
+            ..Limits::default()
+        };
+        let num = |s: &str| BigDecimal::from_str(s).unwrap();
+        assert!(
+            limits
+                .check_operands(&num("99999"), &num("99999"), Operator::Mul)
+                .is_ok()
+        );
+        assert!(matches!(
+            limits.check_operands(&num("12345678901"), &num("1"), Operator::Add),
+            Err(Overflow::Digits { .. })
+        ));
+        assert!(matches!(
+            limits.check_operands(&num("0.001"), &num("0.001"), Operator::Mul),
+            Err(Overflow::Scale { .. })
+        ));
+        assert!(limits.check_value(&num("1.23456")).is_ok());
+        assert!(limits.check_value(&num("1.234567")).is_err());
+        assert!(limits.check_literal(&num("1e4")).is_ok());
+        assert!(matches!(
+            limits.check_literal(&num("12345e6")),
+            Err(Overflow::Digits { .. })
+        ));
+        assert!(matches!(
+            limits.check_literal(&num("1e-999999999")),
+            Err(Overflow::Scale { .. })
+        ));
+    }
+}
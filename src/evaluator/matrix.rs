@@ -0,0 +1,550 @@
+//! Exact linear algebra on small square matrices. Decimal entries are exact
+//! rationals, so elimination runs on rationals and never rounds; only the
+//! final values are converted back to decimals at the working precision.
+
+use anyhow::{bail, ensure};
+use bigdecimal::num_bigint::BigInt;
+use bigdecimal::{BigDecimal, RoundingMode};
+use num_rational::BigRational;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+use std::cmp::Reverse;
+use std::num::NonZeroU64;
+use std::str::FromStr;
+
+use super::limits::limits;
+use super::{precision, rational, vector};
+
+/// The determinant of a square `matrix`, exact for decimal entries.
+pub fn determinant(matrix: &[Vec<BigDecimal>]) -> anyhow::Result<BigDecimal> {
+    check_square(matrix)?;
+    Ok(to_decimal(&rational_determinant(to_rationals(matrix))))
+}
+
+fn rational_determinant(mut rows: Vec<Vec<BigRational>>) -> BigRational {
+    let n = rows.len();
+    let mut det = BigRational::one();
+    for col in 0..n {
+        let Some(pivot) = (col..n).find(|&row| !rows[row][col].is_zero()) else {
+            return BigRational::zero();
+        };
+        if pivot != col {
+            rows.swap(pivot, col);
+            det = -det;
+        }
+        det *= &rows[col][col];
+        eliminate_below(&mut rows, col);
+    }
+    det
+}
+
+/// The inverse of a square, non-singular `matrix`.
+pub fn inverse(matrix: &[Vec<BigDecimal>]) -> anyhow::Result<Vec<Vec<BigDecimal>>> {
+    let n = check_square(matrix)?;
+    let identity: Vec<Vec<BigDecimal>> = (0..n)
+        .map(|i| (0..n).map(|j| BigDecimal::from(u8::from(i == j))).collect())
+        .collect();
+    gauss_jordan(matrix, &identity)
+}
+
+/// The `x` with `matrix * x = b` for a square, non-singular `matrix`.
+pub fn solve(matrix: &[Vec<BigDecimal>], b: &[BigDecimal]) -> anyhow::Result<Vec<BigDecimal>> {
+    let n = check_square(matrix)?;
+    ensure!(
+        b.len() == n,
+        "Right-hand side has {} entries, expected {}",
+        b.len(),
+        n
+    );
+    let column: Vec<Vec<BigDecimal>> = b.iter().map(|x| vec![x.clone()]).collect();
+    Ok(gauss_jordan(matrix, &column)?
+        .into_iter()
+        .flatten()
+        .collect())
+}
+
+/// Reduce `[matrix | rhs]` until `matrix` is the identity and return what
+/// `rhs` became.
+fn gauss_jordan(
+    matrix: &[Vec<BigDecimal>],
+    rhs: &[Vec<BigDecimal>],
+) -> anyhow::Result<Vec<Vec<BigDecimal>>> {
+    let n = matrix.len();
+    let mut rows: Vec<Vec<BigRational>> = to_rationals(matrix)
+        .into_iter()
+        .zip(to_rationals(rhs))
+        .map(|(mut row, extra)| {
+            row.extend(extra);
+            row
+        })
+        .collect();
+    for col in 0..n {
+        let Some(pivot) = (col..n).find(|&row| !rows[row][col].is_zero()) else {
+            bail!("Matrix is singular");
+        };
+        rows.swap(pivot, col);
+        clear_column(&mut rows, col, col);
+    }
+    Ok(rows
+        .iter()
+        .map(|row| row[n..].iter().map(to_decimal).collect())
+        .collect())
+}
+
+/// Scale row `pivot` so its entry in `col` is 1 and subtract it from every
+/// other row to zero the rest of the column.
+fn clear_column(rows: &mut [Vec<BigRational>], pivot: usize, col: usize) {
+    let scale = rows[pivot][col].recip();
+    rows[pivot].iter_mut().for_each(|x| *x *= &scale);
+    let pivot_row = rows[pivot].clone();
+    for (i, row) in rows.iter_mut().enumerate() {
+        if i == pivot || row[col].is_zero() {
+            continue;
+        }
+        let factor = row[col].clone();
+        for (x, p) in row.iter_mut().zip(&pivot_row).skip(col) {
+            *x -= &factor * p;
+        }
+    }
+}
+
+fn eliminate_below(rows: &mut [Vec<BigRational>], col: usize) {
+    let (top, below) = rows.split_at_mut(col + 1);
+    let pivot_row = &top[col];
+    for row in below {
+        if row[col].is_zero() {
+            continue;
+        }
+        let factor = &row[col] / &pivot_row[col];
+        for k in col..row.len() {
+            row[k] -= &factor * &pivot_row[k];
+        }
+    }
+}
+
+/// An eigenvalue. Complex ones come in conjugate pairs and are reported as
+/// their real and imaginary parts.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Eigenvalue {
+    Real(BigDecimal),
+    Complex { re: BigDecimal, im: BigDecimal },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Eigenpair {
+    pub value: Eigenvalue,
+    /// Scaled so that one component is 1. `None` for complex eigenvalues and
+    /// for repeats of an eigenvalue beyond the dimension of its eigenspace.
+    pub vector: Option<Vec<BigDecimal>>,
+}
+
+/// A root of the characteristic polynomial before rounding.
+enum Root {
+    /// Rational eigenvalues, including every repeated one, are found exactly
+    Exact(BigRational),
+    /// Irrational eigenvalues at the working precision
+    Approx(BigDecimal),
+    Complex(BigDecimal, BigDecimal),
+}
+
+/// Eigenvalues and eigenvectors of a 1x1, 2x2 or 3x3 `matrix` from the closed
+/// form roots of its characteristic polynomial, real ones first in
+/// descending order.
+pub fn eigen(matrix: &[Vec<BigDecimal>]) -> anyhow::Result<Vec<Eigenpair>> {
+    let n = check_square(matrix)?;
+    ensure!(n <= 3, "Eigenvalues are only available up to 3x3 matrices");
+    let a = to_rationals(matrix);
+    let mut roots = match n {
+        1 => vec![Root::Exact(a[0][0].clone())],
+        2 => quadratic_exact(
+            &-(&a[0][0] + &a[1][1]),
+            &(&a[0][0] * &a[1][1] - &a[0][1] * &a[1][0]),
+        ),
+        _ => cubic_roots(&a)?,
+    };
+    roots.sort_by_key(|root| Reverse(sort_key(root)));
+
+    let mut pairs = Vec::with_capacity(n);
+    for (i, root) in roots.iter().enumerate() {
+        let pair = match root {
+            Root::Exact(lambda) => {
+                let repeat = roots[..i]
+                    .iter()
+                    .filter(|earlier| matches!(earlier, Root::Exact(x) if x == lambda))
+                    .count();
+                let shifted = shift_diagonal(a.clone(), lambda);
+                Eigenpair {
+                    value: Eigenvalue::Real(to_decimal(lambda)),
+                    vector: kernel(shifted)
+                        .into_iter()
+                        .nth(repeat)
+                        .map(|v| v.iter().map(to_decimal).collect()),
+                }
+            }
+            Root::Approx(lambda) => Eigenpair {
+                value: Eigenvalue::Real(round(lambda)),
+                vector: approximate_eigenvector(matrix, lambda),
+            },
+            Root::Complex(re, im) => Eigenpair {
+                value: Eigenvalue::Complex {
+                    re: round(re),
+                    im: round(im),
+                },
+                vector: None,
+            },
+        };
+        pairs.push(pair);
+    }
+    Ok(pairs)
+}
+
+/// Real roots by value, then complex ones by imaginary part.
+fn sort_key(root: &Root) -> (bool, BigDecimal) {
+    match root {
+        Root::Exact(x) => (true, to_working(x)),
+        Root::Approx(x) => (true, x.clone()),
+        Root::Complex(_, im) => (false, im.clone()),
+    }
+}
+
+/// Roots of `x^2 + b x + c`, exact when the discriminant is a rational square.
+fn quadratic_exact(b: &BigRational, c: &BigRational) -> Vec<Root> {
+    let disc = b * b - c * BigInt::from(4);
+    if let Some(root) = rational_sqrt(&disc) {
+        let half = -b / BigInt::from(2);
+        let offset = root / BigInt::from(2);
+        return vec![Root::Exact(&half + &offset), Root::Exact(half - offset)];
+    }
+    quadratic_approx(&to_working(b), &to_working(c))
+}
+
+fn quadratic_approx(b: &BigDecimal, c: &BigDecimal) -> Vec<Root> {
+    let digits = precision::working_digits();
+    let disc = b.square() - c * BigDecimal::from(4);
+    let half = precision::divide(&-b, &BigDecimal::from(2), digits);
+    let offset = precision::divide(
+        &precision::nth_root(&disc.abs(), 2, digits),
+        &BigDecimal::from(2),
+        digits,
+    );
+    if disc.is_negative() {
+        vec![
+            Root::Complex(half.clone(), offset.clone()),
+            Root::Complex(half, -offset),
+        ]
+    } else {
+        vec![Root::Approx(&half + &offset), Root::Approx(half - offset)]
+    }
+}
+
+/// Roots of the characteristic polynomial `x^3 - t x^2 + m x - d` of a 3x3
+/// matrix: one real root found in floating point and then made exact or
+/// refined by Newton's method, and the quadratic left after dividing it out.
+fn cubic_roots(a: &[Vec<BigRational>]) -> anyhow::Result<Vec<Root>> {
+    let trace = &a[0][0] + &a[1][1] + &a[2][2];
+    let minor = |i: usize, j: usize| &a[i][i] * &a[j][j] - &a[i][j] * &a[j][i];
+    let minors = minor(0, 1) + minor(0, 2) + minor(1, 2);
+    let det = rational_determinant(a.to_vec());
+
+    let f64_of = |x: &BigRational| x.to_f64().filter(|x| x.is_finite());
+    let (Some(t), Some(m), Some(d)) = (f64_of(&trace), f64_of(&minors), f64_of(&det)) else {
+        bail!("Matrix entries out of range for eigenvalues");
+    };
+    let guess = BigDecimal::from_str(&real_cubic_root(-t, m, -d).to_string())?;
+
+    let polynomial = |x: &BigRational| ((x - &trace) * x + &minors) * x - &det;
+    let (numerator, denominator) = rational::best_approximation(&guess, &BigInt::from(1000))?;
+    let candidate = BigRational::new(numerator, denominator);
+    let roots = if polynomial(&candidate).is_zero() {
+        let b = &candidate - &trace;
+        let c = &minors + &candidate * &b;
+        let mut roots = quadratic_exact(&b, &c);
+        roots.push(Root::Exact(candidate));
+        roots
+    } else {
+        // Without a rational root the cubic is irreducible, so all its roots are simple
+        let (trace, minors, det) = (to_working(&trace), to_working(&minors), to_working(&det));
+        let digits = precision::working_digits();
+        let precision = NonZeroU64::new(digits).unwrap_or(NonZeroU64::MIN);
+        let mut x = guess;
+        for _ in 0..100 {
+            let value = ((&x - &trace) * &x + &minors) * &x - &det;
+            let slope = (BigDecimal::from(3) * &x - BigDecimal::from(2) * &trace) * &x + &minors;
+            if slope.is_zero() {
+                break;
+            }
+            let step = precision::divide(&value, &slope, digits);
+            x = (&x - &step).with_precision_round(precision, RoundingMode::HalfEven);
+            if step.abs() <= x.abs() * BigDecimal::new(BigInt::one(), digits as i64) {
+                break;
+            }
+        }
+        let b = &x - &trace;
+        let c = (&minors + &x * &b).with_precision_round(precision, RoundingMode::HalfEven);
+        let mut roots = quadratic_approx(&b, &c);
+        roots.push(Root::Approx(x));
+        roots
+    };
+    Ok(roots)
+}
+
+/// The largest real root of `x^3 + a x^2 + b x + c` in floating point, by
+/// Cardano's formula or, with three real roots, the trigonometric method.
+fn real_cubic_root(a: f64, b: f64, c: f64) -> f64 {
+    let p = b - a * a / 3.0;
+    let q = 2.0 * a * a * a / 27.0 - a * b / 3.0 + c;
+    let disc = (q / 2.0) * (q / 2.0) + (p / 3.0) * (p / 3.0) * (p / 3.0);
+    let t = if disc > 0.0 {
+        let s = libm::sqrt(disc);
+        libm::cbrt(-q / 2.0 + s) + libm::cbrt(-q / 2.0 - s)
+    } else if p == 0.0 {
+        0.0
+    } else {
+        let cos = (3.0 * q / (2.0 * p) * libm::sqrt(-3.0 / p)).clamp(-1.0, 1.0);
+        2.0 * libm::sqrt(-p / 3.0) * libm::cos(libm::acos(cos) / 3.0)
+    };
+    t - a / 3.0
+}
+
+fn rational_sqrt(x: &BigRational) -> Option<BigRational> {
+    if x.is_negative() {
+        return None;
+    }
+    let (numerator, denominator) = (x.numer().sqrt(), x.denom().sqrt());
+    (&numerator * &numerator == *x.numer() && &denominator * &denominator == *x.denom())
+        .then(|| BigRational::new(numerator, denominator))
+}
+
+fn shift_diagonal(mut rows: Vec<Vec<BigRational>>, lambda: &BigRational) -> Vec<Vec<BigRational>> {
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[i] -= lambda;
+    }
+    rows
+}
+
+/// A basis of the null space of `rows`, one vector per free column of the
+/// reduced row echelon form with that column set to 1.
+fn kernel(mut rows: Vec<Vec<BigRational>>) -> Vec<Vec<BigRational>> {
+    let (n, cols) = (rows.len(), rows[0].len());
+    let mut pivots = Vec::new();
+    for col in 0..cols {
+        let r = pivots.len();
+        let Some(pivot) = (r..n).find(|&row| !rows[row][col].is_zero()) else {
+            continue;
+        };
+        rows.swap(pivot, r);
+        clear_column(&mut rows, r, col);
+        pivots.push(col);
+    }
+    (0..cols)
+        .filter(|col| !pivots.contains(col))
+        .map(|free| {
+            let mut v = vec![BigRational::zero(); cols];
+            v[free] = BigRational::one();
+            for (row, &col) in pivots.iter().enumerate() {
+                v[col] = -rows[row][free].clone();
+            }
+            v
+        })
+        .collect()
+}
+
+/// An eigenvector for a simple irrational eigenvalue: perpendicular to a row
+/// of `matrix - lambda I` in 2D, or the cross product of two of its rows in
+/// 3D, taking the largest candidate to stay clear of rounding noise.
+fn approximate_eigenvector(
+    matrix: &[Vec<BigDecimal>],
+    lambda: &BigDecimal,
+) -> Option<Vec<BigDecimal>> {
+    let mut m = matrix.to_vec();
+    for (i, row) in m.iter_mut().enumerate() {
+        row[i] -= lambda;
+    }
+    let candidates: Vec<Vec<BigDecimal>> = match m.len() {
+        2 => m.iter().map(|row| vec![-&row[1], row[0].clone()]).collect(),
+        _ => [(0, 1), (0, 2), (1, 2)]
+            .into_iter()
+            .map(|(i, j)| {
+                let (u, v) = (&m[i], &m[j]);
+                vec![
+                    &u[1] * &v[2] - &u[2] * &v[1],
+                    &u[2] * &v[0] - &u[0] * &v[2],
+                    &u[0] * &v[1] - &u[1] * &v[0],
+                ]
+            })
+            .collect(),
+    };
+    let best = candidates.into_iter().max_by_key(|v| vector::dot(v, v))?;
+    let scale = best.iter().max_by_key(|x| x.abs())?.clone();
+    if scale.is_zero() {
+        return None;
+    }
+    let digits = precision::working_digits();
+    Some(
+        best.iter()
+            .map(|x| round(&precision::divide(x, &scale, digits)))
+            .collect(),
+    )
+}
+
+/// The size of a non-empty square `matrix` within the configured limit.
+fn check_square(matrix: &[Vec<BigDecimal>]) -> anyhow::Result<usize> {
+    let n = matrix.len();
+    ensure!(n > 0, "Matrix is empty");
+    ensure!(
+        matrix.iter().all(|row| row.len() == n),
+        "Matrix must be square"
+    );
+    let max = limits().max_matrix_size;
+    ensure!(n <= max, "Matrix size {} exceeds {}", n, max);
+    Ok(n)
+}
+
+fn to_rationals(matrix: &[Vec<BigDecimal>]) -> Vec<Vec<BigRational>> {
+    matrix
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|x| {
+                    let (numerator, denominator) = rational::exact(x);
+                    BigRational::new(numerator, denominator)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn to_decimal(x: &BigRational) -> BigDecimal {
+    precision::divide(
+        &BigDecimal::from(x.numer().clone()),
+        &BigDecimal::from(x.denom().clone()),
+        limits().precision,
+    )
+}
+
+fn to_working(x: &BigRational) -> BigDecimal {
+    precision::divide(
+        &BigDecimal::from(x.numer().clone()),
+        &BigDecimal::from(x.denom().clone()),
+        precision::working_digits(),
+    )
+}
+
+/// An intermediate rounded to the configured precision.
+fn round(x: &BigDecimal) -> BigDecimal {
+    x.with_precision_round(
+        NonZeroU64::new(limits().precision).unwrap_or(NonZeroU64::MIN),
+        RoundingMode::HalfEven,
+    )
+    .normalized()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matrix(rows: &[&[&str]]) -> Vec<Vec<BigDecimal>> {
+        rows.iter()
+            .map(|row| {
+                row.iter()
+                    .map(|x| BigDecimal::from_str(x).unwrap())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_linear_algebra() {
+        let a = matrix(&[&["2", "1"], &["1", "3"]]);
+        assert_eq!(determinant(&a).unwrap(), BigDecimal::from(5));
+        assert_eq!(
+            inverse(&a).unwrap(),
+            matrix(&[&["0.6", "-0.2"], &["-0.2", "0.4"]])
+        );
+        let b = [BigDecimal::from(3), BigDecimal::from(5)];
+        assert_eq!(solve(&a, &b).unwrap(), matrix(&[&["0.8", "1.4"]])[0]);
+
+        let swapped = matrix(&[&["0", "1", "2"], &["1", "0", "3"], &["4", "-3", "8"]]);
+        assert_eq!(determinant(&swapped).unwrap(), BigDecimal::from(-2));
+        let thirds = matrix(&[&["3", "0"], &["0", "1"]]);
+        assert_eq!(
+            inverse(&thirds).unwrap()[0][0],
+            precision::divide(
+                &BigDecimal::from(1),
+                &BigDecimal::from(3),
+                limits().precision
+            )
+        );
+
+        let singular = matrix(&[&["1", "2"], &["2", "4"]]);
+        assert_eq!(determinant(&singular).unwrap(), BigDecimal::from(0));
+        assert!(inverse(&singular).is_err());
+        assert!(solve(&a, &b[..1]).is_err());
+        assert!(determinant(&matrix(&[&["1", "2"]])).is_err());
+        assert!(determinant(&[]).is_err());
+    }
+
+    fn real(x: &str) -> Eigenvalue {
+        Eigenvalue::Real(BigDecimal::from_str(x).unwrap())
+    }
+
+    #[test]
+    fn test_eigen() {
+        let symmetric = eigen(&matrix(&[&["2", "1"], &["1", "2"]])).unwrap();
+        assert_eq!(symmetric[0].value, real("3"));
+        assert_eq!(symmetric[0].vector, Some(matrix(&[&["1", "1"]])[0].clone()));
+        assert_eq!(symmetric[1].value, real("1"));
+        assert_eq!(
+            symmetric[1].vector,
+            Some(matrix(&[&["-1", "1"]])[0].clone())
+        );
+
+        let rotation = eigen(&matrix(&[&["0", "-1"], &["1", "0"]])).unwrap();
+        let expected = Eigenvalue::Complex {
+            re: BigDecimal::zero(),
+            im: BigDecimal::one(),
+        };
+        assert_eq!(rotation[0].value, expected);
+        assert_eq!(rotation[0].vector, None);
+
+        let block = matrix(&[&["2", "0", "0"], &["0", "3", "4"], &["0", "4", "9"]]);
+        let values: Vec<_> = eigen(&block)
+            .unwrap()
+            .into_iter()
+            .map(|p| p.value)
+            .collect();
+        assert_eq!(values, [real("11"), real("2"), real("1")]);
+
+        let identity = matrix(&[&["1", "0", "0"], &["0", "1", "0"], &["0", "0", "1"]]);
+        let vectors: Vec<_> = eigen(&identity)
+            .unwrap()
+            .into_iter()
+            .map(|p| p.vector)
+            .collect();
+        assert_eq!(vectors, identity.into_iter().map(Some).collect::<Vec<_>>());
+        let defective = eigen(&matrix(&[&["1", "1"], &["0", "1"]])).unwrap();
+        assert_eq!(defective[0].vector, Some(matrix(&[&["1", "0"]])[0].clone()));
+        assert_eq!(defective[1].vector, None);
+
+        // x^3 - 2: the real cube root of 2 and a complex pair
+        let companion = matrix(&[&["0", "0", "2"], &["1", "0", "0"], &["0", "1", "0"]]);
+        let pairs = eigen(&companion).unwrap();
+        let Eigenvalue::Real(root) = &pairs[0].value else {
+            panic!("expected a real eigenvalue first");
+        };
+        assert!(
+            root.to_string()
+                .starts_with("1.25992104989487316476721060727822835057")
+        );
+        let Eigenvalue::Complex { re, im } = &pairs[1].value else {
+            panic!("expected a complex pair");
+        };
+        let tolerance = BigDecimal::from_str("1e-90").unwrap();
+        assert!((re * BigDecimal::from(-2) - root).abs() < tolerance);
+        assert!(im.is_positive());
+        let vector = pairs[0].vector.as_ref().unwrap();
+        assert!((&vector[1] * root - &vector[0]).abs() < tolerance);
+
+        let row: &[&str] = &["1", "0", "0", "0"];
+        assert!(eigen(&matrix(&[row; 4])).is_err());
+    }
+}
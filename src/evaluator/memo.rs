@@ -0,0 +1,221 @@
+//! Evaluation of repeated subexpressions once: identical subtrees of the RPN
+//! stream are hash-consed into one node, so `(a + b) ^ 2 + (a + b) ^ 3`
+//! computes `a + b` a single time. Nodes are evaluated in the order of their
+//! first appearance, which is the order the stack evaluator would reach
+//! them, so the first error is the same.
+
+use anyhow::anyhow;
+use bigdecimal::BigDecimal;
+use bigdecimal::num_bigint::BigInt;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use super::context::{EvalContext, checkpoint};
+use super::functions::apply_function;
+use super::{
+    Environment, Function, MathConst, Operator, Token, apply_operator, apply_unary_operator,
+};
+
+static SUBEXPRESSIONS: AtomicU64 = AtomicU64::new(0);
+static DEDUPLICATED: AtomicU64 = AtomicU64::new(0);
+
+/// Process-wide counts of operations and function calls seen by the
+/// evaluator, and of those served from an identical subtree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DedupStats {
+    pub subexpressions: u64,
+    pub deduplicated: u64,
+}
+
+pub fn stats() -> DedupStats {
+    DedupStats {
+        subexpressions: SUBEXPRESSIONS.load(Ordering::Relaxed),
+        deduplicated: DEDUPLICATED.load(Ordering::Relaxed),
+    }
+}
+
+/// What makes two tokens the same node. Numbers compare by digits and scale,
+/// since `2` and `2.0` give results of different scale.
+#[derive(PartialEq, Eq, Hash)]
+enum Key<'a> {
+    Number(BigInt, i64),
+    Const(MathConst),
+    Var(&'a str),
+    Op(Operator, Vec<usize>),
+    Call(Function, Vec<usize>),
+}
+
+struct Node<'a> {
+    token: &'a Token,
+    operands: Vec<usize>,
+}
+
+/// The distinct subtrees of `rpn`, children before parents, and how many
+/// parents use each. `None` when `rpn` is malformed.
+fn dag(rpn: &[Token]) -> Option<(Vec<Node<'_>>, Vec<usize>)> {
+    let mut ids: HashMap<Key, usize> = HashMap::new();
+    let mut nodes: Vec<Node> = Vec::new();
+    let mut uses: Vec<usize> = Vec::new();
+    let mut stack: Vec<usize> = Vec::new();
+    for token in rpn {
+        let (key, operands) = match token {
+            Token::Number(num) => {
+                let (digits, scale) = num.as_bigint_and_exponent();
+                (Key::Number(digits, scale), Vec::new())
+            }
+            Token::Ident(math_const) => (Key::Const(*math_const), Vec::new()),
+            Token::Var(name) => (Key::Var(name), Vec::new()),
+            Token::Op(op) => {
+                let operands = stack.split_off(stack.len().checked_sub(op.arity())?);
+                (Key::Op(*op, operands.clone()), operands)
+            }
+            Token::Call(func, argc) => {
+                let operands = stack.split_off(stack.len().checked_sub(*argc)?);
+                (Key::Call(*func, operands.clone()), operands)
+            }
+            Token::LParenthesis | Token::RParenthesis | Token::Func(_) | Token::Comma => {
+                return None;
+            }
+        };
+        let id = *ids.entry(key).or_insert_with(|| {
+            for operand in &operands {
+                uses[*operand] += 1;
+            }
+            nodes.push(Node { token, operands });
+            uses.push(0);
+            nodes.len() - 1
+        });
+        stack.push(id);
+    }
+    match stack.as_slice() {
+        [root] => {
+            uses[*root] += 1;
+            Some((nodes, uses))
+        }
+        _ => None,
+    }
+}
+
+/// Evaluate `rpn` computing each distinct subtree once. `Ok(None)` when no
+/// subtree repeats, or when it is malformed, leaving it to the stack
+/// evaluator, which then also reports why.
+pub(super) fn try_eval(
+    rpn: &[Token],
+    env: &Environment,
+    ctx: &EvalContext,
+) -> anyhow::Result<Option<BigDecimal>> {
+    let Some((nodes, mut uses)) = dag(rpn) else {
+        return Ok(None);
+    };
+    let is_operation = |token: &Token| matches!(token, Token::Op(_) | Token::Call(..));
+    let subexpressions = rpn.iter().filter(|token| is_operation(token)).count() as u64;
+    let distinct = nodes.iter().filter(|node| is_operation(node.token)).count() as u64;
+    SUBEXPRESSIONS.fetch_add(subexpressions, Ordering::Relaxed);
+    DEDUPLICATED.fetch_add(subexpressions - distinct, Ordering::Relaxed);
+    if subexpressions == distinct {
+        return Ok(None);
+    }
+
+    let mut values: Vec<Option<BigDecimal>> = Vec::with_capacity(nodes.len());
+    for node in &nodes {
+        checkpoint()?;
+        // The last use of a value takes it instead of cloning
+        let operands = node.operands.iter().map(|&operand| {
+            uses[operand] -= 1;
+            let value = &mut values[operand];
+            if uses[operand] == 0 {
+                value.take()
+            } else {
+                value.clone()
+            }
+            .expect("operands are evaluated first and kept until their last use")
+        });
+        let mut operands = operands.collect::<Vec<_>>().into_iter();
+        let value = match node.token {
+            Token::Number(num) => num.clone(),
+            Token::Ident(math_const) => BigDecimal::from(*math_const),
+            Token::Var(name) => env
+                .get(name)
+                .cloned()
+                .ok_or_else(|| anyhow!("Unknown variable: {}", name))?,
+            Token::Op(op) if op.is_unary() => {
+                let value = operands.next().expect("unary operator has an operand");
+                apply_unary_operator(value, *op)?
+            }
+            Token::Op(op) => {
+                let lhs = operands.next().expect("binary operator has two operands");
+                let rhs = operands.next().expect("binary operator has two operands");
+                apply_operator(lhs, rhs, *op)?
+            }
+            Token::Call(func, _) => {
+                let args: Vec<BigDecimal> = operands.collect();
+                apply_function(*func, &args, ctx.angle_mode_in(env))?
+            }
+            Token::LParenthesis | Token::RParenthesis | Token::Func(_) | Token::Comma => {
+                unreachable!("not part of a tree")
+            }
+        };
+        values.push(Some(value));
+    }
+    Ok(values.pop().flatten())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::Limits;
+    use crate::evaluator::{eval_with, shunting_yard, tokenize};
+
+    fn nodes(input: &str) -> usize {
+        let rpn = shunting_yard(
+            &tokenize(input, &Limits::DEFAULT).unwrap(),
+            &Limits::DEFAULT,
+        )
+        .unwrap();
+        dag(&rpn).unwrap().0.len()
+    }
+
+    #[test]
+    fn test_identical_subtrees_share_a_node() {
+        // a, b, a + b, 2, (a + b) ^ 2, 3, (a + b) ^ 3, sum
+        assert_eq!(nodes("(a + b) ^ 2 + (a + b) ^ 3"), 8);
+        assert_eq!(nodes("sqrt(2) * sqrt(2) + sqrt(2)"), 4);
+        // Equal values written at different scales stay apart
+        assert_eq!(nodes("x * 2 + x * 2.0"), 6);
+        assert!(dag(&[Token::Number(1.into()), Token::Number(2.into())]).is_none());
+    }
+
+    #[test]
+    fn test_memoized_evaluation() {
+        let mut env = Environment::new();
+        env.set("a", "1.5".parse().unwrap()).unwrap();
+        env.set("b", "0.25".parse().unwrap()).unwrap();
+        let ctx = EvalContext::default();
+        let before = stats();
+        let value = eval_with("(a + b) ^ 2 + (a + b) ^ 3 + (a + b) / 7", &env, &ctx).unwrap();
+        let after = stats();
+        assert_eq!(value, "8.671875".parse::<BigDecimal>().unwrap());
+        assert!(after.deduplicated - before.deduplicated >= 2);
+
+        // Nothing to share: the stack evaluator takes it
+        let rpn = shunting_yard(
+            &tokenize("(a + b) * 2 - a / b", &ctx.limits).unwrap(),
+            &ctx.limits,
+        )
+        .unwrap();
+        assert!(try_eval(&rpn, &env, &ctx).unwrap().is_none());
+        let rpn = shunting_yard(
+            &tokenize("a * b + a * b", &ctx.limits).unwrap(),
+            &ctx.limits,
+        )
+        .unwrap();
+        assert!(try_eval(&rpn, &env, &ctx).unwrap().is_some());
+
+        assert_eq!(
+            eval_with("(a / 0) + (a / 0) * b", &env, &ctx)
+                .unwrap_err()
+                .to_string(),
+            "Division by zero"
+        );
+    }
+}
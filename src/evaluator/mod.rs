@@ -1,65 +1,105 @@
+pub mod ast;
+mod bits;
+pub mod cache;
+pub mod calculus;
+mod comments;
+mod compat;
+pub mod context;
+pub mod conversions;
+pub mod cost;
+pub mod environment;
+pub mod error;
+mod fast;
+mod finance;
+mod functions;
+mod integer;
+pub mod latex;
+pub mod limits;
+pub mod matrix;
+pub mod memo;
 pub mod models;
+pub mod number_theory;
+mod parallel;
+pub mod precision;
+pub mod provenance;
+mod quaternion;
+pub mod rational;
+pub mod shadow;
+pub mod solver;
+pub mod spreadsheet;
+pub mod stream;
+mod transcendental;
+mod uncertainty;
+pub mod units;
+mod vector;
 use anyhow::{anyhow, bail};
+pub use ast::Expr;
 use bigdecimal::BigDecimal;
+pub use comments::strip_comments;
+pub use context::{CancellationToken, EvalContext, checkpoint};
+pub use cost::CostEstimate;
+pub use environment::*;
+pub use error::{
+    CostExceeded, DepthExceeded, Interrupted, MemoryExceeded, Overflow, ParseError, Span,
+};
+use functions::apply_function;
+pub use limits::{DepthBudget, Limits};
 pub use models::*;
-use num_traits::{ToPrimitive, Zero};
+use std::borrow::Cow;
 use std::convert::TryFrom;
+use std::fmt;
 
-fn tokenize(input: &str) -> anyhow::Result<Vec<Token>> {
+fn tokenize(input: &str, limits: &Limits) -> anyhow::Result<Vec<Token>> {
+    let chars: Vec<char> = input.chars().collect();
     let mut tokens = Vec::new();
-    let mut chars = input.chars().peekable();
+    let mut pos = 0;
 
-    while let Some(c) = chars.next() {
+    while let Some(&c) = chars.get(pos) {
+        let start = pos;
+        pos += 1;
         match c {
-            c if is_paren(c) => tokens.push(to_paren(c)),
+            _ if let Some(paren) = to_paren(c) => tokens.push(paren),
             c if c.is_whitespace() => {}
-            c if is_op(c) => tokens.push(Token::Op(c.into())),
-            c if c.is_ascii_digit() => {
-                // normal number, decimals, scientific notation
-                let mut num_str = String::new();
-                num_str.push(c);
-
-                // Consume the rest of the numbers
-                while let Some(&next_char) = chars.peek() {
-                    if next_char.is_ascii_digit()
-                        || next_char == '.'
-                        // Scientific notation
-                        || (next_char.eq_ignore_ascii_case(&'e') && !num_str.contains(|c: char| c.eq_ignore_ascii_case(&'e')))
-                    {
-                        num_str.push(next_char);
-                        chars.next(); // Consume the character
-
-                        // Handle sign for scientific notation
-                        if next_char.eq_ignore_ascii_case(&'e')
-                            && let Some(&sign) = chars.peek()
-                            && (sign == '+' || sign == '-')
-                        {
-                            num_str.push(sign);
-                            chars.next();
-                        }
-                    } else {
-                        break;
-                    }
-                }
-                let num = num_str.parse()?;
+            ',' => tokens.push(Token::Comma),
+            '%' if is_percent_sign(&chars[pos..]) => tokens.push(Token::Op(Operator::Percent)),
+            _ if let Some((op, len)) = Operator::lex(&chars[start..]) => {
+                pos = start + len;
+                tokens.push(Token::Op(op));
+            }
+            c if c.is_ascii_digit()
+                || (c == '.' && chars.get(pos).is_some_and(char::is_ascii_digit)) =>
+            {
+                pos = scan_number(&chars, start)?;
+                let literal: String = chars[start..pos].iter().collect();
+                let num = literal
+                    .parse()
+                    .map_err(|_| ParseError::new("Malformed number", start..pos))?;
+                limits.check_literal(&num)?;
                 tokens.push(Token::Number(num));
             }
-            _ if c.is_ascii_alphabetic() => {
-                let mut ident = String::new();
-                ident.push(c);
-                while let Some(&next) = chars.peek() {
-                    if next.is_alphanumeric() {
-                        ident.push(next);
-                        chars.next();
-                    } else {
-                        break;
-                    }
+            _ if c.is_ascii_alphabetic() || c == '_' => {
+                while chars
+                    .get(pos)
+                    .is_some_and(|next| next.is_alphanumeric() || *next == '_')
+                {
+                    pos += 1;
+                }
+                let ident: String = chars[start..pos].iter().collect();
+                let is_call = chars[pos..].iter().find(|c| !c.is_whitespace()) == Some(&'(');
+                if let Ok(math_const) = MathConst::try_from(ident.as_str()) {
+                    tokens.push(Token::Ident(math_const));
+                } else if let Ok(func) = Function::try_from(ident.as_str())
+                    && is_call
+                {
+                    tokens.push(Token::Func(func));
+                } else {
+                    tokens.push(Token::Var(ident));
                 }
-                let math_const = MathConst::try_from(ident.as_str())?;
-                tokens.push(Token::Ident(math_const));
             }
             _ => {
-                bail!("Unexpected character: {}", c);
+                return Err(
+                    ParseError::new(format!("Unexpected character: {}", c), start..pos).into(),
+                );
             }
         }
     }
@@ -67,28 +107,86 @@ fn tokenize(input: &str) -> anyhow::Result<Vec<Token>> {
     Ok(tokens)
 }
 
-fn shunting_yard(tokens: &[Token]) -> anyhow::Result<Vec<Token>> {
+/// End of the numeric literal starting at `start`: `12`, `1.5`, `.5`, `5.`,
+/// optionally followed by an exponent such as `e10`, `E-3` or `e+2`.
+fn scan_number(chars: &[char], start: usize) -> Result<usize, ParseError> {
+    let digits_from = |mut pos: usize| {
+        while chars.get(pos).is_some_and(char::is_ascii_digit) {
+            pos += 1;
+        }
+        pos
+    };
+
+    let mut pos = digits_from(start);
+    if chars.get(pos) == Some(&'.') {
+        pos = digits_from(pos + 1);
+    }
+    if chars.get(pos).is_some_and(|c| c.eq_ignore_ascii_case(&'e')) {
+        let mut exponent = pos + 1;
+        if matches!(chars.get(exponent), Some('+' | '-')) {
+            exponent += 1;
+        }
+        let end = digits_from(exponent);
+        if end == exponent {
+            return Err(ParseError::new("Missing exponent digits", start..end));
+        }
+        pos = end;
+    }
+    if chars.get(pos) == Some(&'.') {
+        let end = digits_from(pos + 1);
+        return Err(ParseError::new("Malformed number", start..end.max(pos + 1)));
+    }
+    Ok(pos)
+}
+
+/// `%` is a postfix percent unless an operand follows, in which case it is modulo.
+/// A following `+`/`-` counts as binary (percent) only when it is spaced: `50% - 10`.
+fn is_percent_sign(rest: &[char]) -> bool {
+    let mut rest = rest.iter().copied().skip_while(|c| c.is_whitespace());
+    match rest.next() {
+        None | Some(')' | ',' | '*' | '/' | '^' | '!' | '#' | '%' | '²' | '³') => true,
+        Some('+' | '-') => rest.next().is_some_and(char::is_whitespace),
+        Some(_) => false,
+    }
+}
+
+fn shunting_yard(tokens: &[Token], limits: &Limits) -> anyhow::Result<Vec<Token>> {
+    let budget = DepthBudget::new(limits.max_depth);
     let mut output = Vec::new();
     let mut stack: Vec<Token> = Vec::new();
+    // One entry per open parenthesis: the argument count so far for function calls
+    let mut frames: Vec<Option<usize>> = Vec::new();
     let mut expect_operand = true;
+    let mut prev: Option<&Token> = None;
 
     for token in tokens {
         match token {
-            Token::Number(_) | Token::Ident(_) => {
+            Token::Number(_) | Token::Ident(_) | Token::Var(_) => {
                 output.push(token.clone());
                 expect_operand = false;
             }
+            Token::Op(op) if op.is_postfix() => {
+                if expect_operand {
+                    bail!("Postfix operator {} without operand", op.symbol());
+                }
+                while let Some(Token::Op(stack_op)) = stack.last()
+                    && should_pop_operator(*stack_op, *op)
+                {
+                    output.extend(stack.pop());
+                }
+                // Postfix operators apply to the operand already in the output
+                output.push(token.clone());
+            }
             Token::Op(op) => {
                 let mut current_op = *op;
                 if expect_operand {
-                    if current_op == Operator::Sub {
-                        current_op = Operator::UnarySub;
-                    } else {
-                        bail!("Unexpected operator placement");
-                    }
+                    current_op = current_op
+                        .to_unary()
+                        .ok_or_else(|| anyhow!("Unexpected operator placement"))?;
                 }
 
-                while let Some(stack_top) = stack.last() {
+                // A prefix operator has no left operand, so nothing on the stack is complete yet
+                while let Some(stack_top) = stack.last().filter(|_| !current_op.is_unary()) {
                     let should_pop = match stack_top {
                         Token::Op(stack_op) => should_pop_operator(*stack_op, current_op),
                         Token::LParenthesis => false,
@@ -106,11 +204,33 @@ fn shunting_yard(tokens: &[Token]) -> anyhow::Result<Vec<Token>> {
                 stack.push(Token::Op(current_op));
                 expect_operand = true;
             }
+            Token::Func(_) => {
+                stack.push(token.clone());
+                expect_operand = true;
+            }
             Token::LParenthesis => {
+                budget.check(frames.len() + 1)?;
+                frames.push(matches!(prev, Some(Token::Func(_))).then_some(1));
                 stack.push(Token::LParenthesis);
                 expect_operand = true;
             }
+            Token::Comma => {
+                if expect_operand {
+                    bail!("Missing function argument");
+                }
+                while let Some(Token::Op(_)) = stack.last() {
+                    output.extend(stack.pop());
+                }
+                match frames.last_mut() {
+                    Some(Some(argc)) => *argc += 1,
+                    _ => bail!("Unexpected comma outside function call"),
+                }
+                expect_operand = true;
+            }
             Token::RParenthesis => {
+                if expect_operand && !matches!(prev, Some(Token::LParenthesis)) {
+                    bail!("Missing operand before closing parenthesis");
+                }
                 let mut found_left = false;
                 while let Some(popped) = stack.pop() {
                     match popped {
@@ -125,29 +245,121 @@ fn shunting_yard(tokens: &[Token]) -> anyhow::Result<Vec<Token>> {
                 if !found_left {
                     bail!("Mismatched parentheses");
                 }
+                match frames.pop().flatten() {
+                    Some(argc) => {
+                        let argc = if matches!(prev, Some(Token::LParenthesis)) {
+                            0
+                        } else {
+                            argc
+                        };
+                        let Some(Token::Func(func)) = stack.pop() else {
+                            bail!("Function call without function");
+                        };
+                        if !func.accepts(argc) {
+                            bail!("Wrong number of arguments for {}: {}", func, argc);
+                        }
+                        output.push(Token::Call(func, argc));
+                    }
+                    None if matches!(prev, Some(Token::LParenthesis)) => {
+                        bail!("Empty parentheses")
+                    }
+                    None => {}
+                }
                 expect_operand = false;
             }
+            Token::Call(..) => bail!("Unexpected function call token"),
         }
+        prev = Some(token);
     }
 
     while let Some(token) = stack.pop() {
         match token {
             Token::LParenthesis | Token::RParenthesis => bail!("Mismatched parentheses"),
+            Token::Func(func) => bail!("Missing arguments for function {}", func),
             _ => output.push(token),
         }
     }
 
+    budget.check(rpn_depth(&output))?;
     Ok(output)
 }
 
-fn eval_rpn(tokens: &[Token]) -> anyhow::Result<BigDecimal> {
+/// Height of the expression tree the RPN stream describes, without building it.
+fn rpn_depth(rpn: &[Token]) -> usize {
+    let mut depths: Vec<usize> = Vec::new();
+    for token in rpn {
+        let operands = match token {
+            Token::Op(op) => op.arity(),
+            Token::Call(_, argc) => *argc,
+            _ => 0,
+        };
+        let children = depths.split_off(depths.len().saturating_sub(operands));
+        depths.push(children.into_iter().max().unwrap_or(0) + 1);
+    }
+    depths.into_iter().max().unwrap_or(0)
+}
+
+/// Rewrites each percentage that is a factor of a product, `a * b%`, as
+/// `a * b / 100`, so `50% * 8` is `4` rather than `4.0`.
+fn percent_products(tokens: &[Token]) -> Cow<'_, [Token]> {
+    let mut parents = vec![None; tokens.len()];
+    let mut operands = Vec::new();
+    for (index, token) in tokens.iter().enumerate() {
+        let arity = match token {
+            Token::Op(op) => op.arity(),
+            Token::Call(_, argc) => *argc,
+            _ => 0,
+        };
+        for child in operands.split_off(operands.len().saturating_sub(arity)) {
+            parents[child] = Some(index);
+        }
+        operands.push(index);
+    }
+    let is_factor = |index: usize| {
+        tokens[index] == Token::Op(Operator::Percent)
+            && parents[index].is_some_and(|parent| tokens[parent] == Token::Op(Operator::Mul))
+    };
+    if !(0..tokens.len()).any(is_factor) {
+        return Cow::Borrowed(tokens);
+    }
+
+    let mut rewritten = Vec::with_capacity(tokens.len() + 2);
+    for (index, token) in tokens.iter().enumerate() {
+        if is_factor(index) {
+            continue;
+        }
+        rewritten.push(token.clone());
+        for _ in (0..index).filter(|&child| is_factor(child) && parents[child] == Some(index)) {
+            rewritten.push(Token::Number(BigDecimal::from(100)));
+            rewritten.push(Token::Op(Operator::Div));
+        }
+    }
+    Cow::Owned(rewritten)
+}
+
+fn eval_rpn(tokens: &[Token], env: &Environment, ctx: &EvalContext) -> anyhow::Result<BigDecimal> {
+    let tokens = &*percent_products(tokens);
+    let limits = ctx.limits;
+    if let Some(result) = fast::try_eval(tokens, env, &limits)? {
+        return Ok(result);
+    }
+    if let Some(result) = integer::try_eval(tokens, &limits)? {
+        return Ok(BigDecimal::from(result));
+    }
+    if let Some(result) = parallel::try_eval(tokens, env, ctx)? {
+        return Ok(result);
+    }
+    if let Some(result) = memo::try_eval(tokens, env, ctx)? {
+        return Ok(precision::round_to(result, limits.precision));
+    }
     let mut stack: Vec<BigDecimal> = Vec::new();
 
     for token in tokens {
+        checkpoint()?;
         match token {
             Token::Number(num) => stack.push(num.clone()),
             Token::Op(op) => {
-                if op.is_unary_sub() {
+                if op.is_unary() {
                     let value = stack
                         .pop()
                         .ok_or_else(|| anyhow!("Not enough operands for operator"))?;
@@ -165,8 +377,21 @@ fn eval_rpn(tokens: &[Token]) -> anyhow::Result<BigDecimal> {
                 }
             }
             Token::Ident(math_const) => stack.push(BigDecimal::from(*math_const)),
-            Token::LParenthesis | Token::RParenthesis => {
-                bail!("Parenthesis encountered in RPN stream")
+            Token::Var(name) => {
+                let value = env
+                    .get(name)
+                    .ok_or_else(|| anyhow!("Unknown variable: {}", name))?;
+                stack.push(value.clone());
+            }
+            Token::Call(func, argc) => {
+                if stack.len() < *argc {
+                    bail!("Not enough arguments for function {}", func);
+                }
+                let args = stack.split_off(stack.len() - argc);
+                stack.push(apply_function(*func, &args, ctx.angle_mode_in(env))?);
+            }
+            Token::LParenthesis | Token::RParenthesis | Token::Func(_) | Token::Comma => {
+                bail!("Unexpected token in RPN stream: {}", token)
             }
         }
     }
@@ -175,52 +400,321 @@ fn eval_rpn(tokens: &[Token]) -> anyhow::Result<BigDecimal> {
         bail!("Invalid RPN expression");
     }
 
-    Ok(stack.pop().expect("stack length already validated"))
+    let result = stack.pop().expect("stack length already validated");
+    Ok(precision::round_to(result, limits.precision))
 }
 
 fn apply_operator(lhs: BigDecimal, rhs: BigDecimal, op: Operator) -> anyhow::Result<BigDecimal> {
-    let result = match op {
-        Operator::Add => lhs + rhs,
-        Operator::Sub => lhs - rhs,
-        Operator::Mul => lhs * rhs,
-        Operator::Div => {
-            if rhs.is_zero() {
-                bail!("Division by zero");
-            }
-            lhs / rhs
-        }
-        Operator::Mod => {
-            if rhs.is_zero() {
-                bail!("Modulo by zero");
-            }
-            lhs % rhs
-        }
-        Operator::Pow => {
-            if !rhs.is_integer() {
-                bail!("Exponent must be an integer for power operation");
-            }
-            let exponent = rhs
-                .to_i64()
-                .ok_or_else(|| anyhow!("Exponent is out of range for power operation"))?;
-            lhs.powi(exponent)
-        }
-        Operator::UnarySub => bail!("Unary operator cannot be applied in binary context"),
+    let Eval::Binary(eval) = op.info().eval else {
+        bail!("Unary operator cannot be applied in binary context");
     };
-
+    limits::check_operands(&lhs, &rhs, op)?;
+    let result = eval(lhs, rhs)?;
+    limits::check_value(&result)?;
+    let result = precision::round_to(result, precision::working_digits());
+    context::charge_memory(cost::value_bytes(result.digits()))?;
     Ok(result)
 }
 
 fn apply_unary_operator(value: BigDecimal, op: Operator) -> anyhow::Result<BigDecimal> {
-    match op {
-        Operator::UnarySub => Ok(-value),
-        _ => bail!("Unsupported unary operator"),
-    }
+    let Eval::Unary(eval) = op.info().eval else {
+        bail!("Unsupported unary operator");
+    };
+    let result = eval(value)?;
+    limits::check_value(&result)?;
+    let result = precision::round_to(result, precision::working_digits());
+    context::charge_memory(cost::value_bytes(result.digits()))?;
+    Ok(result)
 }
 
+/// Evaluate `input` under the entered [`EvalContext`], else a default one.
 pub fn eval(input: &str) -> anyhow::Result<BigDecimal> {
-    let tokens = tokenize(input)?;
-    let rpn = shunting_yard(&tokens)?;
-    eval_rpn(&rpn)
+    eval_with(input, &Environment::default(), &EvalContext::current())
+}
+
+/// Result of evaluating one or more `;`-separated statements.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Evaluation {
+    /// Value of the last statement
+    pub value: BigDecimal,
+    /// Every `name = expr` binding made, in statement order
+    pub bindings: Vec<(String, BigDecimal)>,
+    /// Named components when the last statement calls a multi-valued function
+    pub components: Option<Vec<(&'static str, BigDecimal)>>,
+    /// All terms when the last statement calls a list-valued function
+    pub terms: Option<Vec<BigDecimal>>,
+    /// The last statement's expression and the variables it was evaluated with
+    expression: String,
+    scope: Environment,
+}
+
+impl Evaluation {
+    /// Standard uncertainty of the value propagated from the CODATA uncertainties
+    /// of the constants it uses; `None` when the value is exact.
+    pub fn uncertainty(&self, ctx: &EvalContext) -> anyhow::Result<Option<BigDecimal>> {
+        let expr = parse_in(&self.expression, &self.scope, ctx)?;
+        ctx.run(|| uncertainty::propagate(&expr, &self.scope))
+    }
+}
+
+impl fmt::Display for Evaluation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
+
+/// Evaluate `input` against `env`. The input may be an assignment `name = expr`
+/// or several statements separated by `;`, e.g. `a = 2; b = 3; a ^ b`; the value
+/// of the last one is returned.
+pub fn eval_in(
+    input: &str,
+    env: &mut Environment,
+    ctx: &EvalContext,
+) -> anyhow::Result<BigDecimal> {
+    Ok(eval_statements(input, env, ctx)?.value)
+}
+
+/// Evaluate `;`-separated statements left to right in `env`, stopping at the first error.
+pub fn eval_statements(
+    input: &str,
+    env: &mut Environment,
+    ctx: &EvalContext,
+) -> anyhow::Result<Evaluation> {
+    let input = strip_comments(input)?;
+    let statements = split_statements(&input);
+    let Some((last, leading)) = statements.split_last() else {
+        bail!("Empty expression");
+    };
+    let mut bindings = Vec::new();
+    for statement in leading {
+        eval_statement(statement, env, &mut bindings, ctx)?;
+    }
+    let scope = env.clone();
+    let value = eval_statement(last, env, &mut bindings, ctx)?;
+    let (_, expression) = split_assignment(last);
+    Ok(Evaluation {
+        value,
+        bindings,
+        components: components(last, &scope, ctx)?,
+        terms: terms(last, &scope, ctx)?,
+        expression: expression.to_string(),
+        scope,
+    })
+}
+
+/// The non-empty statements of `input`, so a trailing `;` is allowed. Strip
+/// comments first, since they may contain `;`.
+pub fn split_statements(input: &str) -> Vec<&str> {
+    input
+        .split(';')
+        .filter(|statement| !statement.trim().is_empty())
+        .collect()
+}
+
+/// Evaluate one statement, binding the result to its target (if any) and to [`ANS`].
+fn eval_statement(
+    input: &str,
+    env: &mut Environment,
+    bindings: &mut Vec<(String, BigDecimal)>,
+    ctx: &EvalContext,
+) -> anyhow::Result<BigDecimal> {
+    let (target, expression) = split_assignment(input);
+    if let Some(target) = target {
+        validate_variable_name(target)?;
+    }
+
+    let value = eval_with(expression, env, ctx)?;
+    if let Some(target) = target {
+        env.set(target, value.clone())?;
+        bindings.push((target.to_string(), value.clone()));
+    }
+    env.set(ANS, value.clone())?;
+    Ok(value)
+}
+
+/// Evaluate `input` under `ctx`, reading variables from `env` without modifying it.
+pub fn eval_with(input: &str, env: &Environment, ctx: &EvalContext) -> anyhow::Result<BigDecimal> {
+    eval_tokens(&tokenize_input(input, env.data_units(), ctx)?, env, ctx)
+}
+
+/// Evaluate already tokenized input, e.g. from a [`stream::StreamTokenizer`],
+/// under `ctx`, reading variables from `env`.
+pub fn eval_tokens(
+    tokens: &[Token],
+    env: &Environment,
+    ctx: &EvalContext,
+) -> anyhow::Result<BigDecimal> {
+    let limits = &ctx.limits;
+    let rpn = shunting_yard(tokens, limits)?;
+    let estimate = cost::estimate(&rpn, limits);
+    limits.check_cost(&estimate)?;
+    limits.check_memory(estimate.memory_bytes)?;
+    ctx.run(|| cache::get_or_eval(&rpn, env, ctx, estimate.cost, || eval_rpn(&rpn, env, ctx)))
+}
+
+/// Estimate the cost of evaluating `input` (without assignment) without evaluating it.
+pub fn cost_estimate(input: &str) -> anyhow::Result<CostEstimate> {
+    let ctx = EvalContext::current();
+    let rpn = shunting_yard(
+        &tokenize_input(input, DataUnits::default(), &ctx)?,
+        &ctx.limits,
+    )?;
+    Ok(cost::estimate(&rpn, &ctx.limits))
+}
+
+/// Parse `input` (without assignment) into an expression tree under the
+/// entered [`EvalContext`], else a default one.
+pub fn parse(input: &str) -> anyhow::Result<Expr> {
+    parse_in(input, &Environment::default(), &EvalContext::current())
+}
+
+/// Parse `input` under `ctx` with the data-size units of `env`, for trees
+/// evaluated in it.
+pub fn parse_in(input: &str, env: &Environment, ctx: &EvalContext) -> anyhow::Result<Expr> {
+    let rpn = shunting_yard(&tokenize_input(input, env.data_units(), ctx)?, &ctx.limits)?;
+    ctx.run(|| Expr::from_rpn(&rpn))
+}
+
+/// Named components when `input` (after any assignment) is a call to a
+/// multi-valued function such as `divmod`, otherwise `None`.
+pub fn components(
+    input: &str,
+    env: &Environment,
+    ctx: &EvalContext,
+) -> anyhow::Result<Option<Vec<(&'static str, BigDecimal)>>> {
+    let wanted = |func: &Function| func.components().is_some();
+    let Some((func, args)) = evaluated_call(input, env, ctx, wanted)? else {
+        return Ok(None);
+    };
+    let names = func.components().unwrap_or_default();
+    let values = ctx.run(|| {
+        let values = functions::apply_components(func, &args, ctx.angle_mode_in(env))?;
+        charge_memory(&values)?;
+        anyhow::Ok(values)
+    })?;
+    Ok(Some(names.iter().copied().zip(values).collect()))
+}
+
+/// All terms when `input` (after any assignment) is a call to a list-valued
+/// function such as `cfrac`, otherwise `None`.
+pub fn terms(
+    input: &str,
+    env: &Environment,
+    ctx: &EvalContext,
+) -> anyhow::Result<Option<Vec<BigDecimal>>> {
+    let Some((func, args)) = evaluated_call(input, env, ctx, Function::is_list)? else {
+        return Ok(None);
+    };
+    ctx.run(|| {
+        let terms = functions::apply_list(func, &args, ctx.angle_mode_in(env))?;
+        charge_memory(&terms)?;
+        Ok(Some(terms))
+    })
+}
+
+/// Count `values` against the entered request's memory limit.
+fn charge_memory(values: &[BigDecimal]) -> Result<(), MemoryExceeded> {
+    let bytes = values
+        .iter()
+        .map(|value| cost::value_bytes(value.digits()))
+        .fold(0, u64::saturating_add);
+    context::charge_memory(bytes)
+}
+
+/// The function and argument values when `input` is a call to a function
+/// selected by `wanted`; arguments are only evaluated for those.
+fn evaluated_call(
+    input: &str,
+    env: &Environment,
+    ctx: &EvalContext,
+    wanted: impl Fn(&Function) -> bool,
+) -> anyhow::Result<Option<(Function, Vec<BigDecimal>)>> {
+    let (_, expression) = split_assignment(input);
+    let Expr::Call(func, args) = parse_in(expression, env, ctx)? else {
+        return Ok(None);
+    };
+    if !wanted(&func) {
+        return Ok(None);
+    }
+    let args = ctx.run(|| {
+        args.iter()
+            .map(|arg| arg.eval(env))
+            .collect::<anyhow::Result<Vec<_>>>()
+    })?;
+    Ok(Some((func, args)))
+}
+
+/// Normalize `input`, including an optional `name = ` prefix, to canonical infix.
+pub fn format_expression(input: &str) -> anyhow::Result<String> {
+    let (target, expression) = split_assignment(input);
+    let expr = parse(expression)?;
+    Ok(match target {
+        Some(target) => format!("{} = {}", target, expr),
+        None => expr.to_string(),
+    })
+}
+
+/// Tokenize plain infix or, when it looks like LaTeX, its infix translation,
+/// after rewriting foreign syntax and quantities with units.
+fn tokenize_input(
+    input: &str,
+    data_units: DataUnits,
+    ctx: &EvalContext,
+) -> anyhow::Result<Vec<Token>> {
+    ctx.check()?;
+    // The rewriting passes bound their recursion by the entered limits
+    let input = ctx.run(|| {
+        let input = strip_comments(input)?;
+        let input = compat::normalize(&input)?;
+        let input = if latex::is_latex(&input) {
+            Cow::Owned(latex::to_infix(&input)?)
+        } else {
+            input
+        };
+        let input = conversions::to_infix(&input)?;
+        anyhow::Ok(units::to_infix(&input, data_units)?.0)
+    })?;
+    tokenize(&input, &ctx.limits)
+}
+
+/// Check that `input` parses, returning its free variables in order of first use.
+pub fn free_variables(input: &str) -> anyhow::Result<Vec<String>> {
+    let ctx = EvalContext::current();
+    let tokens = tokenize_input(input, DataUnits::default(), &ctx)?;
+    shunting_yard(&tokens, &ctx.limits)?;
+
+    let mut names: Vec<String> = Vec::new();
+    for token in tokens {
+        if let Token::Var(name) = token
+            && !names.contains(&name)
+        {
+            names.push(name);
+        }
+    }
+    Ok(names)
+}
+
+/// Functions called by `input`, in order of first use.
+pub fn functions_used(input: &str) -> anyhow::Result<Vec<Function>> {
+    let mut functions: Vec<Function> = Vec::new();
+    for token in tokenize_input(input, DataUnits::default(), &EvalContext::current())? {
+        if let Token::Func(func) = token
+            && !functions.contains(&func)
+        {
+            functions.push(func);
+        }
+    }
+    Ok(functions)
+}
+
+/// Split `name = expr` into its target and expression; other input is returned unchanged.
+pub fn split_assignment(input: &str) -> (Option<&str>, &str) {
+    match input.split_once('=') {
+        Some((target, expression)) if is_identifier(target.trim()) => {
+            (Some(target.trim()), expression)
+        }
+        _ => (None, input),
+    }
 }
 
 #[cfg(test)]
@@ -253,6 +747,33 @@ mod tests {
         assert_eq!(eval("10 % 3 * 2").unwrap(), BigDecimal::from(2));
     }
 
+    #[test]
+    fn test_unary_plus_and_sign_chains() {
+        assert_eq!(eval("+-+-2").unwrap(), BigDecimal::from(2));
+        assert_eq!(eval("- - -2").unwrap(), BigDecimal::from(-2));
+        assert_eq!(eval("1 + +1").unwrap(), BigDecimal::from(2));
+        // Signs bind looser than `^`, on either side of it
+        assert_eq!(eval("-+2 ^ 2").unwrap(), BigDecimal::from(-4));
+        assert_eq!(
+            eval("2 ^ -+2").unwrap(),
+            BigDecimal::from_str("0.25").unwrap()
+        );
+        assert_eq!(
+            shunting_yard(&tokenize("+x", &Limits::DEFAULT).unwrap(), &Limits::DEFAULT).unwrap(),
+            [Token::Var("x".to_string()), Token::Op(Operator::UnaryAdd)]
+        );
+
+        assert_eq!(eval("3 * +2").unwrap(), BigDecimal::from(6));
+        assert_eq!(eval("3 - -+2").unwrap(), BigDecimal::from(5));
+        assert_eq!(eval("-+-+(1 + 1)").unwrap(), BigDecimal::from(2));
+
+        assert!(eval("+").is_err());
+        assert!(eval("3 * / 2").is_err());
+        assert!(eval("2 +").is_err());
+        assert!(eval("2 * +").is_err());
+        assert!(eval("(+)").is_err());
+    }
+
     #[test]
     fn test_eval_float() {
         assert_eq!(eval("3 / 4").unwrap(), BigDecimal::from_f64(0.75).unwrap());
@@ -281,16 +802,214 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_number_literals() {
+        assert_eq!(
+            eval(".5 + 5.").unwrap(),
+            BigDecimal::from_str("5.5").unwrap()
+        );
+        assert_eq!(eval("1e10").unwrap(), BigDecimal::from(10_000_000_000u64));
+        assert_eq!(eval("2E+2 - .5e1").unwrap(), BigDecimal::from(195));
+
+        let span_of = |input: &str| {
+            let err = eval(input).unwrap_err();
+            let parse = err
+                .downcast_ref::<ParseError>()
+                .expect("span-carrying error");
+            (parse.message.clone(), parse.span)
+        };
+        assert_eq!(
+            span_of("1 + 1e"),
+            (
+                "Missing exponent digits".to_string(),
+                Span { start: 4, end: 6 }
+            )
+        );
+        assert_eq!(
+            span_of("2 * 1.2.3"),
+            ("Malformed number".to_string(), Span { start: 4, end: 9 })
+        );
+        assert_eq!(span_of("1e+ 2").1, Span { start: 0, end: 3 });
+        assert_eq!(span_of("2 @ 3").1, Span { start: 2, end: 3 });
+    }
+
+    #[test]
+    fn test_overflow_limits() {
+        let overflow = |input: &str| {
+            eval(input)
+                .unwrap_err()
+                .downcast_ref::<Overflow>()
+                .copied()
+                .expect("overflow error")
+        };
+        assert!(matches!(overflow("9 ^ 999999"), Overflow::Digits { .. }));
+        assert!(matches!(
+            overflow("1e999999999 + 1"),
+            Overflow::Digits { .. }
+        ));
+        assert!(matches!(overflow("0.5 ^ 999999"), Overflow::Digits { .. }));
+        assert!(matches!(
+            overflow("1e-60000 * 1e-60000"),
+            Overflow::Scale { .. }
+        ));
+        assert_eq!(eval("1 ^ 99999999999").unwrap(), BigDecimal::from(1));
+        assert_eq!(eval("(-1) ^ 99999999999").unwrap(), BigDecimal::from(-1));
+    }
+
+    #[test]
+    fn test_oversized_literals_are_rejected() {
+        let overflow = |input: &str| {
+            tokenize(input, &Limits::DEFAULT)
+                .unwrap_err()
+                .downcast_ref::<Overflow>()
+                .copied()
+                .expect("overflow error")
+        };
+        assert!(matches!(overflow("1e-100001"), Overflow::Scale { .. }));
+        assert!(matches!(overflow("1e-100000000"), Overflow::Scale { .. }));
+        assert!(matches!(overflow("2 * 1e100001"), Overflow::Digits { .. }));
+        assert!(tokenize("1e-100000", &Limits::DEFAULT).is_ok());
+        assert!(cost_estimate("1e-100000000").is_err());
+    }
+
+    #[test]
+    fn test_working_precision() {
+        let third = eval("1 / 3").unwrap();
+        assert_eq!(third.digits(), 100);
+        assert_eq!(eval("3 / 4").unwrap().to_string(), "0.75");
+        assert_eq!(eval("10 / 4").unwrap().to_string(), "2.5");
+        assert_eq!(eval("100 / 5").unwrap().to_string(), "20");
+        assert_eq!(eval("2 ^ -2").unwrap().to_string(), "0.25");
+        assert_eq!(eval("(1 / 3) * 3").unwrap().round(90), BigDecimal::from(1));
+        assert_eq!(eval("2 / 3").unwrap().to_string().chars().last(), Some('7'));
+        assert_eq!(eval("-2 / 3").unwrap(), -eval("2 / 3").unwrap());
+        assert!(eval("0 ^ -1").is_err());
+
+        let chained = (0..200).map(|_| "/ 7").collect::<Vec<_>>().join(" ");
+        let result = eval(&format!("1 {chained} * 7 ^ 200")).unwrap();
+        assert!(result.digits() <= 100);
+        assert_eq!(result.round(80), BigDecimal::from(1));
+
+        assert_eq!(eval("2 ^ 400").unwrap().digits(), 121);
+
+        // Leading fractional zeros do not count against the precision
+        for tiny in ["2 ^ -1000", "1 / 3 ^ 1000", "1e-200 / 3", "1e-50 / 7"] {
+            let result = eval(tiny).unwrap();
+            assert_ne!(result, BigDecimal::from(0), "{tiny}");
+            assert_eq!(result.digits(), 100, "{tiny}");
+        }
+        assert_eq!(
+            eval("2 ^ -1000 * 2 ^ 1000").unwrap().round(90),
+            BigDecimal::from(1)
+        );
+    }
+
+    /// Exact result strings; any platform-dependent arithmetic shows up as a diff here.
+    const GOLDEN_RESULTS: &[(&str, &str)] = &[
+        ("0.1 + 0.2", "0.3"),
+        (
+            "1 / 7",
+            "0.1428571428571428571428571428571428571428571428571428571428571428571428571428571428571428571428571429",
+        ),
+        (
+            "sqrt(2)",
+            "1.414213562373095048801688724209698078569671875376948073176679737990732478462107038850387534327641573",
+        ),
+        (
+            "pi * e",
+            "8.539734222673567065463550869546574495034888535765114961879601130179228611157330807572563869710473944",
+        ),
+        ("2 ^ 100", "1267650600228229401496703205376"),
+        ("20!", "2432902008176640000"),
+        ("10 // 3", "3"),
+        ("17.5 % 4", "1.5"),
+        ("25%", "0.25"),
+        (
+            "sin(1)",
+            "0.8414709848078965066525023216302989996225630607983710656727517099919104043912396689486397435430526959",
+        ),
+        (
+            "cos(1)",
+            "0.5403023058681397174009366074429766037323104206179222276700972553811003947744717645179518560871830893",
+        ),
+        (
+            "tan(1)",
+            "1.557407724654902230506974807458360173087250772381520038383946605698861397151727289555099965202242984",
+        ),
+        ("sin(pi / 6)", "0.5"),
+        ("ln(e)", "1"),
+        (
+            "exp(1)",
+            "2.718281828459045235360287471352662497757247093699959574966967627724076630353547594571382178525166427",
+        ),
+        (
+            "exp(-10)",
+            "0.00004539992976248485153559151556055061023791808886656496925907130565099942161430228165252500454594778232",
+        ),
+        (
+            "ln(2)",
+            "0.6931471805599453094172321214581765680755001343602552541206800094933936219696947156058633269964186875",
+        ),
+        (
+            "ln(10) * 1e5",
+            "230258.5092994045684017991454684364207601101488628772976033327900967572609677352480235997205089598298",
+        ),
+    ];
+
+    #[test]
+    fn test_golden_results() {
+        for (input, expected) in GOLDEN_RESULTS {
+            assert_eq!(
+                eval(input).unwrap().to_string(),
+                *expected,
+                "evaluating {input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_depth_budget() {
+        let limit = Limits::DEFAULT.max_depth;
+        let nested = |depth: usize| format!("{}1{}", "(".repeat(depth), ")".repeat(depth));
+        assert_eq!(eval(&nested(limit)).unwrap(), BigDecimal::from(1));
+
+        let deep = nested(100_000);
+        for err in [eval(&deep).unwrap_err(), parse(&deep).unwrap_err()] {
+            assert_eq!(err.downcast_ref(), Some(&DepthExceeded { limit }));
+        }
+
+        let negations = format!("{}1", "- ".repeat(10_000));
+        assert!(eval(&negations).unwrap_err().is::<DepthExceeded>());
+        let braces = format!("{}1{}", "{".repeat(10_000), "}".repeat(10_000));
+        assert!(eval(&braces).unwrap_err().is::<DepthExceeded>());
+        assert!(
+            eval(&format!("{}4", r"\sqrt".repeat(10_000)))
+                .unwrap_err()
+                .is::<DepthExceeded>()
+        );
+    }
+
     #[test]
     fn test_eval_math_const() {
-        assert_eq!(eval("pi").unwrap(), BigDecimal::from(MathConst::Pi));
+        // Computed to the working precision, then rounded like any result
+        assert_eq!(
+            eval("pi").unwrap().to_string(),
+            "3.141592653589793238462643383279502884197169399375105820974944592307816406286208998628034825342117068"
+        );
+        assert_eq!(
+            eval("tau").unwrap().to_string(),
+            "6.283185307179586476925286766559005768394338798750211641949889184615632812572417997256069650684234136"
+        );
+        assert_eq!(
+            eval("e").unwrap().to_string(),
+            "2.718281828459045235360287471352662497757247093699959574966967627724076630353547594571382178525166427"
+        );
         assert_eq!(
-            eval("pi * 2").unwrap(),
-            BigDecimal::from(MathConst::Pi) * BigDecimal::from(2)
+            eval("phi").unwrap().to_string(),
+            "1.618033988749894848204586834365638117720309179805762862135448622705260462818902449707207204189391137"
         );
-        assert_eq!(eval("tau").unwrap(), BigDecimal::from(MathConst::Tau));
-        assert_eq!(eval("e").unwrap(), BigDecimal::from(MathConst::E));
-        assert_eq!(eval("phi").unwrap(), BigDecimal::from(MathConst::Phi));
+        assert_eq!(eval("pi * 2").unwrap(), eval("tau").unwrap());
+        assert_eq!(BigDecimal::from(MathConst::Pi).digits(), 110);
         assert_eq!(eval("c").unwrap(), BigDecimal::from(MathConst::C));
         assert_eq!(eval("h").unwrap(), BigDecimal::from(MathConst::H));
         assert_eq!(eval("g").unwrap(), BigDecimal::from(MathConst::G));
@@ -300,4 +1019,112 @@ mod tests {
         assert_eq!(eval("ec").unwrap(), BigDecimal::from(MathConst::Ec));
         assert_eq!(eval("tau / pi").unwrap(), BigDecimal::from(2));
     }
+
+    #[test]
+    fn test_eval_in_environment() {
+        let mut env = Environment::new();
+        assert_eq!(
+            eval_in("x = 3", &mut env, &EvalContext::default()).unwrap(),
+            BigDecimal::from(3)
+        );
+        assert_eq!(
+            eval_in("my_var = x * 2", &mut env, &EvalContext::default()).unwrap(),
+            BigDecimal::from(6)
+        );
+        assert_eq!(
+            eval_in("ans + x", &mut env, &EvalContext::default()).unwrap(),
+            BigDecimal::from(9)
+        );
+        assert_eq!(env.get(ANS), Some(&BigDecimal::from(9)));
+
+        assert!(eval_in("pi = 3", &mut env, &EvalContext::default()).is_err());
+        assert!(eval_in("y + 1", &mut env, &EvalContext::default()).is_err());
+        assert!(eval("x").is_err());
+    }
+
+    #[test]
+    fn test_eval_statements() {
+        let mut env = Environment::new();
+        let evaluation = eval_statements(
+            "a = 2; b = 3; a ^ b + 1;",
+            &mut env,
+            &EvalContext::default(),
+        )
+        .unwrap();
+        assert_eq!(evaluation.value, BigDecimal::from(9));
+        assert_eq!(
+            evaluation.bindings,
+            vec![
+                ("a".to_string(), BigDecimal::from(2)),
+                ("b".to_string(), BigDecimal::from(3)),
+            ]
+        );
+        assert_eq!(env.get(ANS), Some(&BigDecimal::from(9)));
+
+        let check = eval_statements(
+            "approx_eq(3.14159, pi, 1e-5)",
+            &mut env,
+            &EvalContext::default(),
+        )
+        .unwrap();
+        assert_eq!(check.value, BigDecimal::from(1));
+        let components = check.components.unwrap();
+        assert_eq!(components[0].0, "equal");
+        assert!(components[1].1 > BigDecimal::from(0));
+        assert_eq!(
+            eval("approx_eq(0.1 + 0.2, 0.3)").unwrap(),
+            BigDecimal::from(1)
+        );
+        assert_eq!(
+            eval("approx_eq(pi, 3.14, 0.001)").unwrap(),
+            BigDecimal::from(0)
+        );
+        assert!(eval("approx_eq(1, 1, -1)").is_err());
+
+        let expansion = eval_statements("cfrac(3.245)", &mut env, &EvalContext::default()).unwrap();
+        assert_eq!(expansion.value, BigDecimal::from(3));
+        let terms: Vec<_> = [3, 4, 12, 4].into_iter().map(BigDecimal::from).collect();
+        assert_eq!(expansion.terms, Some(terms));
+        assert!(expansion.components.is_none());
+
+        let split =
+            eval_statements("x = 17; divmod(x, 5)", &mut env, &EvalContext::default()).unwrap();
+        let components = split.components.unwrap();
+        assert_eq!(components[1], ("remainder", BigDecimal::from(2)));
+
+        assert_eq!(
+            eval_in("2; ans * 5", &mut env, &EvalContext::default()).unwrap(),
+            BigDecimal::from(10)
+        );
+        assert!(eval_in("k = 1; 1 / 0; m = 2", &mut env, &EvalContext::default()).is_err());
+        assert_eq!(env.get("k"), Some(&BigDecimal::from(1)));
+        assert!(env.get("m").is_none());
+        assert!(eval_in(" ; ", &mut env, &EvalContext::default()).is_err());
+    }
+
+    #[test]
+    fn test_eval_functions() {
+        assert_eq!(eval("sqrt(16) + 1").unwrap(), BigDecimal::from(5));
+        assert_eq!(eval("abs(-3 * 2)").unwrap(), BigDecimal::from(6));
+        assert_eq!(eval("-sqrt(4) ^ 2").unwrap(), BigDecimal::from(-4));
+        assert_eq!(eval("sqrt(abs(-9))").unwrap(), BigDecimal::from(3));
+        assert_eq!(eval("sin(0) + cos(0)").unwrap(), BigDecimal::from(1));
+        assert_eq!(eval("ln(exp(2))").unwrap(), BigDecimal::from(2));
+        assert!(eval("ln(0)").is_err());
+
+        assert!(eval("sqrt(-1)").is_err());
+        assert!(eval("sqrt()").is_err());
+        assert!(eval("sqrt(1, 2)").is_err());
+        assert!(eval("1, 2").is_err());
+        assert!(eval("sqrt").is_err());
+    }
+
+    #[test]
+    fn test_free_variables() {
+        assert_eq!(
+            free_variables("a * x ^ 2 + b * x + pi").unwrap(),
+            vec!["a", "x", "b"]
+        );
+        assert!(free_variables("a * (x").is_err());
+    }
 }
@@ -0,0 +1,474 @@
+use anyhow::{Error, anyhow};
+use bigdecimal::{BigDecimal, RoundingMode};
+use std::convert::TryFrom;
+use std::fmt;
+
+use crate::evaluator::precision::working_digits;
+use crate::evaluator::{AngleMode, bits, conversions, finance, functions, transcendental, vector};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Function {
+    Sqrt,
+    Abs,
+    Sin,
+    Cos,
+    Tan,
+    Exp,
+    Ln,
+    DivMod,
+    Digits,
+    IntPart,
+    FracPart,
+    Scale,
+    Clamp,
+    Lerp,
+    MapRange,
+    Hypot,
+    Deg2Rad,
+    Rad2Deg,
+    ApproxEq,
+    ToFraction,
+    Cfrac,
+    Fib,
+    Lucas,
+    Catalan,
+    Triangular,
+    WMean,
+    GeoMean,
+    HarMean,
+    Norm,
+    Normalize,
+    Dot,
+    Angle,
+    Proj,
+    QMul,
+    QConj,
+    QNorm,
+    QRotate,
+    Polar,
+    Cartesian,
+    Spherical,
+    Cylindrical,
+    FromSpherical,
+    FromCylindrical,
+    Popcount,
+    BitLength,
+    Rotl,
+    Rotr,
+    Twos,
+    Signed,
+    Roman,
+    WithTax,
+    Tip,
+    Discount,
+    FutureValue,
+    RoundHalfEven,
+    RoundHalfUp,
+}
+
+type ValueFn = fn(&[BigDecimal], AngleMode) -> anyhow::Result<BigDecimal>;
+type ValuesFn = fn(&[BigDecimal], AngleMode) -> anyhow::Result<Vec<BigDecimal>>;
+
+/// How a function computes its result from arguments whose count was
+/// checked, before the size checks and rounding every value goes through.
+#[derive(Clone, Copy)]
+pub enum Apply {
+    Value(ValueFn),
+    /// Named components, e.g. the quotient and remainder of `divmod`
+    Components(&'static [&'static str], ValuesFn),
+    /// A variable-length list of terms
+    List(ValuesFn),
+}
+
+/// Everything the tokenizer, evaluator and tools know about a function.
+/// Adding a function means adding a variant and a row to [`FUNCTIONS`].
+pub struct FunctionInfo {
+    pub func: Function,
+    /// The name as written in an expression, in lower case
+    pub name: &'static str,
+    /// Minimum and maximum argument count; no maximum means variadic
+    pub arity: (usize, Option<usize>),
+    pub apply: Apply,
+    /// What a multi-valued function stands for inside a larger expression,
+    /// when not its first value
+    pub value: Option<ValueFn>,
+}
+
+/// Indexed by `Function as usize`.
+pub static FUNCTIONS: [FunctionInfo; 56] = [
+    value(Function::Sqrt, "sqrt", (1, Some(1)), |args, _| {
+        functions::sqrt(&args[0])
+    }),
+    value(Function::Abs, "abs", (1, Some(1)), |args, _| {
+        Ok(args[0].abs())
+    }),
+    value(Function::Sin, "sin", (1, Some(1)), |args, mode| {
+        Ok(transcendental::sin_cos(&args[0], mode, working_digits()).0)
+    }),
+    value(Function::Cos, "cos", (1, Some(1)), |args, mode| {
+        Ok(transcendental::sin_cos(&args[0], mode, working_digits()).1)
+    }),
+    value(Function::Tan, "tan", (1, Some(1)), |args, mode| {
+        transcendental::tan(&args[0], mode, working_digits())
+    }),
+    value(Function::Exp, "exp", (1, Some(1)), |args, _| {
+        transcendental::exp(&args[0], working_digits())
+    }),
+    value(Function::Ln, "ln", (1, Some(1)), |args, _| {
+        transcendental::ln(&args[0], working_digits())
+    }),
+    components(
+        Function::DivMod,
+        "divmod",
+        (2, Some(2)),
+        &["quotient", "remainder"],
+        |args, _| {
+            let (quotient, remainder) = functions::floor_div(&args[0], &args[1])?;
+            Ok(vec![quotient, remainder])
+        },
+    ),
+    value(Function::Digits, "digits", (1, Some(1)), |args, _| {
+        Ok(BigDecimal::from(functions::int_part(&args[0]).digits()))
+    }),
+    value(Function::IntPart, "intpart", (1, Some(1)), |args, _| {
+        Ok(functions::int_part(&args[0]))
+    }),
+    value(Function::FracPart, "fracpart", (1, Some(1)), |args, _| {
+        Ok(&args[0] - functions::int_part(&args[0]))
+    }),
+    value(Function::Scale, "scale", (1, Some(1)), |args, _| {
+        Ok(functions::scale(&args[0]))
+    }),
+    value(Function::Clamp, "clamp", (3, Some(3)), |args, _| {
+        functions::clamp(args)
+    }),
+    value(Function::Lerp, "lerp", (3, Some(3)), |args, _| {
+        let (a, b, t) = (&args[0], &args[1], &args[2]);
+        Ok(a + (b - a) * t)
+    }),
+    value(Function::MapRange, "map_range", (5, Some(5)), |args, _| {
+        functions::map_range(args)
+    }),
+    value(Function::Hypot, "hypot", (1, None), |args, _| {
+        vector::norm(args)
+    }),
+    value(Function::Deg2Rad, "deg2rad", (1, Some(1)), |args, _| {
+        Ok(functions::deg2rad(&args[0]))
+    }),
+    value(Function::Rad2Deg, "rad2deg", (1, Some(1)), |args, _| {
+        Ok(functions::rad2deg(&args[0]))
+    }),
+    components(
+        Function::ApproxEq,
+        "approx_eq",
+        (2, Some(3)),
+        &["equal", "delta"],
+        |args, _| {
+            let (equal, delta) = functions::approx_eq(args)?;
+            Ok(vec![equal, delta])
+        },
+    ),
+    components(
+        Function::ToFraction,
+        "to_fraction",
+        (1, Some(2)),
+        &["numerator", "denominator"],
+        |args, _| {
+            let (numerator, denominator) = functions::to_fraction(args)?;
+            Ok(vec![
+                BigDecimal::from(numerator),
+                BigDecimal::from(denominator),
+            ])
+        },
+    )
+    .with_value(|args, _| functions::fraction_value(args)),
+    list(Function::Cfrac, "cfrac", (1, Some(2)), |args, _| {
+        functions::cfrac(args)
+    }),
+    value(Function::Fib, "fib", (1, Some(1)), |args, _| {
+        functions::fib(&args[0])
+    }),
+    value(Function::Lucas, "lucas", (1, Some(1)), |args, _| {
+        functions::lucas(&args[0])
+    }),
+    value(Function::Catalan, "catalan", (1, Some(1)), |args, _| {
+        functions::catalan(&args[0])
+    }),
+    value(
+        Function::Triangular,
+        "triangular",
+        (1, Some(1)),
+        |args, _| functions::triangular(&args[0]),
+    ),
+    value(Function::WMean, "wmean", (2, None), |args, _| {
+        functions::weighted_mean(args)
+    }),
+    value(Function::GeoMean, "geomean", (1, None), |args, _| {
+        functions::geometric_mean(args)
+    }),
+    value(Function::HarMean, "harmean", (1, None), |args, _| {
+        functions::harmonic_mean(args)
+    }),
+    value(Function::Norm, "norm", (1, None), |args, _| {
+        vector::norm(args)
+    }),
+    list(Function::Normalize, "normalize", (1, None), |args, _| {
+        vector::normalize(args)
+    }),
+    value(Function::Dot, "dot", (2, None), |args, _| {
+        let (a, b) = vector::halves(Function::Dot, args)?;
+        Ok(vector::dot(a, b))
+    }),
+    value(Function::Angle, "angle", (2, None), functions::angle),
+    list(Function::Proj, "proj", (2, None), |args, _| {
+        let (a, b) = vector::halves(Function::Proj, args)?;
+        vector::project(a, b)
+    }),
+    list(Function::QMul, "qmul", (8, Some(8)), |args, _| {
+        Ok(functions::qmul(args))
+    }),
+    list(Function::QConj, "qconj", (4, Some(4)), |args, _| {
+        Ok(functions::qconj(args))
+    }),
+    value(Function::QNorm, "qnorm", (4, Some(4)), |args, _| {
+        vector::norm(args)
+    }),
+    list(Function::QRotate, "qrotate", (7, Some(7)), |args, _| {
+        functions::qrotate(args)
+    }),
+    components(
+        Function::Polar,
+        "polar",
+        (2, Some(2)),
+        &["r", "theta"],
+        |args, mode| functions::coordinates(Function::Polar, args, mode),
+    ),
+    components(
+        Function::Cartesian,
+        "cartesian",
+        (2, Some(2)),
+        &["x", "y"],
+        |args, mode| functions::coordinates(Function::Cartesian, args, mode),
+    ),
+    components(
+        Function::Spherical,
+        "spherical",
+        (3, Some(3)),
+        &["r", "theta", "phi"],
+        |args, mode| functions::coordinates(Function::Spherical, args, mode),
+    ),
+    components(
+        Function::Cylindrical,
+        "cylindrical",
+        (3, Some(3)),
+        &["rho", "phi", "z"],
+        |args, mode| functions::coordinates(Function::Cylindrical, args, mode),
+    ),
+    components(
+        Function::FromSpherical,
+        "from_spherical",
+        (3, Some(3)),
+        &["x", "y", "z"],
+        |args, mode| functions::coordinates(Function::FromSpherical, args, mode),
+    ),
+    components(
+        Function::FromCylindrical,
+        "from_cylindrical",
+        (3, Some(3)),
+        &["x", "y", "z"],
+        |args, mode| functions::coordinates(Function::FromCylindrical, args, mode),
+    ),
+    value(Function::Popcount, "popcount", (1, Some(1)), |args, _| {
+        bits::popcount(&args[0])
+    }),
+    value(
+        Function::BitLength,
+        "bit_length",
+        (1, Some(1)),
+        |args, _| bits::bit_length(&args[0]),
+    ),
+    value(Function::Rotl, "rotl", (3, Some(3)), |args, _| {
+        bits::rotate(Function::Rotl, args, true)
+    }),
+    value(Function::Rotr, "rotr", (3, Some(3)), |args, _| {
+        bits::rotate(Function::Rotr, args, false)
+    }),
+    value(Function::Twos, "twos", (2, Some(2)), |args, _| {
+        bits::twos(args)
+    }),
+    value(Function::Signed, "signed", (2, Some(2)), |args, _| {
+        bits::signed(args)
+    }),
+    value(Function::Roman, "roman", (1, Some(1)), |args, _| {
+        conversions::to_roman(&args[0]).map(|_| args[0].clone())
+    }),
+    components(
+        Function::WithTax,
+        "with_tax",
+        (2, Some(2)),
+        &["total", "base", "delta"],
+        |args, _| functions::percent_change(Function::WithTax, args),
+    ),
+    components(
+        Function::Tip,
+        "tip",
+        (2, Some(2)),
+        &["total", "base", "delta"],
+        |args, _| functions::percent_change(Function::Tip, args),
+    ),
+    components(
+        Function::Discount,
+        "discount",
+        (2, Some(2)),
+        &["total", "base", "delta"],
+        |args, _| functions::percent_change(Function::Discount, args),
+    ),
+    components(
+        Function::FutureValue,
+        "future_value",
+        (3, Some(5)),
+        &["future_value", "contributions", "interest"],
+        |args, _| finance::future_value(args),
+    ),
+    value(
+        Function::RoundHalfEven,
+        "round_half_even",
+        (1, Some(2)),
+        |args, _| functions::round_places(Function::RoundHalfEven, args, RoundingMode::HalfEven),
+    ),
+    value(
+        Function::RoundHalfUp,
+        "round_half_up",
+        (1, Some(2)),
+        |args, _| functions::round_places(Function::RoundHalfUp, args, RoundingMode::HalfUp),
+    ),
+];
+
+const fn value(
+    func: Function,
+    name: &'static str,
+    arity: (usize, Option<usize>),
+    apply: ValueFn,
+) -> FunctionInfo {
+    FunctionInfo {
+        func,
+        name,
+        arity,
+        apply: Apply::Value(apply),
+        value: None,
+    }
+}
+
+/// Inside a larger expression such a function evaluates to its first component.
+const fn components(
+    func: Function,
+    name: &'static str,
+    arity: (usize, Option<usize>),
+    names: &'static [&'static str],
+    apply: ValuesFn,
+) -> FunctionInfo {
+    FunctionInfo {
+        func,
+        name,
+        arity,
+        apply: Apply::Components(names, apply),
+        value: None,
+    }
+}
+
+/// Inside a larger expression such a function evaluates to its first term.
+const fn list(
+    func: Function,
+    name: &'static str,
+    arity: (usize, Option<usize>),
+    apply: ValuesFn,
+) -> FunctionInfo {
+    FunctionInfo {
+        func,
+        name,
+        arity,
+        apply: Apply::List(apply),
+        value: None,
+    }
+}
+
+impl FunctionInfo {
+    const fn with_value(mut self, value: ValueFn) -> Self {
+        self.value = Some(value);
+        self
+    }
+}
+
+impl Function {
+    pub fn info(self) -> &'static FunctionInfo {
+        &FUNCTIONS[self as usize]
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        self.info().name
+    }
+
+    /// Minimum and maximum argument count; `None` means variadic.
+    pub fn arity(&self) -> (usize, Option<usize>) {
+        self.info().arity
+    }
+
+    /// Names of the components of a multi-valued function. Inside a larger
+    /// expression such a function evaluates to its first component.
+    pub fn components(&self) -> Option<&'static [&'static str]> {
+        match self.info().apply {
+            Apply::Components(names, _) => Some(names),
+            _ => None,
+        }
+    }
+
+    /// Whether the function yields a variable-length list of terms. Inside a
+    /// larger expression it evaluates to the first term.
+    pub fn is_list(&self) -> bool {
+        matches!(self.info().apply, Apply::List(_))
+    }
+
+    pub fn accepts(&self, argc: usize) -> bool {
+        let (min, max) = self.arity();
+        argc >= min && max.is_none_or(|max| argc <= max)
+    }
+}
+
+impl fmt::Display for Function {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl TryFrom<&str> for Function {
+    type Error = Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let name = value.to_ascii_lowercase();
+        FUNCTIONS
+            .iter()
+            .find(|info| info.name == name)
+            .map(|info| info.func)
+            .ok_or_else(|| anyhow!("Unknown function: {}", value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_is_indexed_by_function() {
+        for (index, info) in FUNCTIONS.iter().enumerate() {
+            assert_eq!(info.func as usize, index, "{}", info.name);
+            assert_eq!(Function::try_from(info.name).unwrap(), info.func);
+            let (min, max) = info.arity;
+            assert!(max.is_none_or(|max| min <= max), "{}", info.name);
+        }
+        assert_eq!(Function::try_from("SQRT").unwrap(), Function::Sqrt);
+        assert_eq!(
+            Function::try_from("nope").unwrap_err().to_string(),
+            "Unknown function: nope"
+        );
+    }
+}
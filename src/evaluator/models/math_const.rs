@@ -4,7 +4,9 @@ use std::convert::TryFrom;
 use std::fmt;
 use std::str::FromStr;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+use crate::evaluator::{precision, transcendental};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum MathConst {
     Pi,
     Tau,
@@ -20,6 +22,20 @@ pub enum MathConst {
 }
 
 impl MathConst {
+    pub const ALL: [MathConst; 11] = [
+        Self::Pi,
+        Self::Tau,
+        Self::E,
+        Self::Phi,
+        Self::C,
+        Self::H,
+        Self::G,
+        Self::R,
+        Self::Na,
+        Self::Kb,
+        Self::Ec,
+    ];
+
     pub fn as_str(&self) -> &'static str {
         match self {
             Self::Pi => "pi",
@@ -35,6 +51,15 @@ impl MathConst {
             Self::Ec => "ec",
         }
     }
+
+    /// CODATA 2018 standard uncertainty; `None` for exact values, which include
+    /// the SI defining constants and mathematical constants.
+    pub fn uncertainty(&self) -> Option<BigDecimal> {
+        match self {
+            Self::G => Some(BigDecimal::from_str("0.00015e-11").unwrap()),
+            _ => None,
+        }
+    }
 }
 
 impl fmt::Display for MathConst {
@@ -43,21 +68,14 @@ impl fmt::Display for MathConst {
     }
 }
 
+/// The value at working precision; the mathematical constants are computed
+/// to as many digits as intermediates carry.
 impl From<MathConst> for BigDecimal {
     fn from(value: MathConst) -> Self {
+        if let Some(computed) = transcendental::math_constant(value, precision::working_digits()) {
+            return computed;
+        }
         match value {
-            MathConst::Pi => {
-                BigDecimal::from_str("3.1415926535897932384626433832795028841971").unwrap()
-            }
-            MathConst::Tau => {
-                BigDecimal::from_str("6.2831853071795864769252867665590057683942").unwrap()
-            }
-            MathConst::E => {
-                BigDecimal::from_str("2.7182818284590452353602874713526624977572").unwrap()
-            }
-            MathConst::Phi => {
-                BigDecimal::from_str("1.6180339887498948482045868343656381177203").unwrap()
-            }
             MathConst::C => BigDecimal::from_str("299792458").unwrap(),
             MathConst::H => BigDecimal::from_str("6.62607015e-34").unwrap(),
             MathConst::G => BigDecimal::from_str("6.67430e-11").unwrap(),
@@ -65,6 +83,9 @@ impl From<MathConst> for BigDecimal {
             MathConst::Na => BigDecimal::from_str("6.02214076e23").unwrap(),
             MathConst::Kb => BigDecimal::from_str("1.380649e-23").unwrap(),
             MathConst::Ec => BigDecimal::from_str("1.602176634e-19").unwrap(),
+            MathConst::Pi | MathConst::Tau | MathConst::E | MathConst::Phi => {
+                unreachable!("computed above")
+            }
         }
     }
 }
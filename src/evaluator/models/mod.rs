@@ -1,9 +1,11 @@
 pub mod assoc;
+pub mod function;
 pub mod math_const;
 pub mod operator;
 pub mod token;
 
 pub use assoc::*;
+pub use function::*;
 pub use math_const::*;
 pub use operator::*;
 pub use token::*;
@@ -1,81 +1,348 @@
+use anyhow::{anyhow, bail};
+use bigdecimal::{BigDecimal, ToPrimitive, Zero};
 use std::fmt;
 use variantly::Variantly;
 
-use crate::evaluator::Assoc;
+use crate::evaluator::{Assoc, apply_operator, functions, precision};
 
-#[derive(Debug, Clone, PartialEq, Copy, Variantly)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Copy, Variantly)]
 pub enum Operator {
     Add,
     Sub,
     Mul,
     Div,
+    FloorDiv,
     Mod,
     Pow,
     UnarySub,
+    UnaryAdd,
+    Factorial,
+    DoubleFactorial,
+    Primorial,
+    Percent,
+    Square,
+    Cube,
 }
 
-impl From<char> for Operator {
-    fn from(c: char) -> Self {
-        match c {
-            '+' => Operator::Add,
-            '-' => Operator::Sub,
-            '*' => Operator::Mul,
-            '/' => Operator::Div,
-            '%' => Operator::Mod,
-            '^' => Operator::Pow,
-            _ => panic!("Invalid character for operator: {}", c),
-        }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fixity {
+    Prefix,
+    Infix,
+    Postfix,
+}
+
+/// How an operator computes its value, before the size checks and rounding
+/// every operator goes through.
+#[derive(Clone, Copy)]
+pub enum Eval {
+    Unary(fn(BigDecimal) -> anyhow::Result<BigDecimal>),
+    Binary(fn(BigDecimal, BigDecimal) -> anyhow::Result<BigDecimal>),
+}
+
+/// Everything the tokenizer, parser, evaluator and formatters know about an
+/// operator. Adding an operator means adding a variant and a row to
+/// [`OPERATORS`].
+pub struct OperatorInfo {
+    pub op: Operator,
+    /// The symbol as written in an expression
+    pub symbol: &'static str,
+    /// What the tokenizer matches, `None` for operators it derives from
+    /// context: prefix signs and the percent sign
+    pub lexeme: Option<&'static str>,
+    /// Name in RPN listings, where prefix and infix forms must differ
+    pub name: &'static str,
+    pub precedence: u8,
+    pub assoc: Assoc,
+    pub fixity: Fixity,
+    /// The prefix operator the symbol means where an operand is expected
+    pub prefix: Option<Operator>,
+    pub eval: Eval,
+}
+
+/// Indexed by `Operator as usize`.
+pub static OPERATORS: [OperatorInfo; 15] = [
+    infix(Operator::Add, "+", 1, Assoc::Left, |lhs, rhs| Ok(lhs + rhs))
+        .with_prefix(Operator::UnaryAdd),
+    infix(Operator::Sub, "-", 1, Assoc::Left, |lhs, rhs| Ok(lhs - rhs))
+        .with_prefix(Operator::UnarySub),
+    infix(Operator::Mul, "*", 2, Assoc::Left, |lhs, rhs| Ok(lhs * rhs)),
+    infix(Operator::Div, "/", 2, Assoc::Left, div),
+    infix(Operator::FloorDiv, "//", 2, Assoc::Left, |lhs, rhs| {
+        Ok(functions::floor_div(&lhs, &rhs)?.0)
+    }),
+    infix(Operator::Mod, "%", 2, Assoc::Left, rem),
+    infix(Operator::Pow, "^", 4, Assoc::Right, pow),
+    prefix(Operator::UnarySub, "-", "u-", |value| Ok(-value)),
+    prefix(Operator::UnaryAdd, "+", "u+", Ok),
+    postfix(Operator::Factorial, "!", |value| {
+        functions::factorial(&value)
+    }),
+    postfix(Operator::DoubleFactorial, "!!", |value| {
+        functions::double_factorial(&value)
+    }),
+    postfix(Operator::Primorial, "#", |value| {
+        functions::primorial(&value)
+    }),
+    postfix(Operator::Percent, "%", |value| {
+        let (digits, scale) = value.into_bigint_and_exponent();
+        Ok(BigDecimal::new(digits, scale + 2).normalized())
+    })
+    .derived("p%"),
+    postfix(Operator::Square, "²", |value| {
+        apply_operator(value, BigDecimal::from(2), Operator::Pow)
+    }),
+    postfix(Operator::Cube, "³", |value| {
+        apply_operator(value, BigDecimal::from(3), Operator::Pow)
+    }),
+];
+
+const fn infix(
+    op: Operator,
+    symbol: &'static str,
+    precedence: u8,
+    assoc: Assoc,
+    eval: fn(BigDecimal, BigDecimal) -> anyhow::Result<BigDecimal>,
+) -> OperatorInfo {
+    OperatorInfo {
+        op,
+        symbol,
+        lexeme: Some(symbol),
+        name: symbol,
+        precedence,
+        assoc,
+        fixity: Fixity::Infix,
+        prefix: None,
+        eval: Eval::Binary(eval),
     }
 }
 
-pub fn is_op(ch: char) -> bool {
-    matches!(ch, '+' | '-' | '*' | '/' | '%' | '^')
+/// Prefix signs bind tighter than `*` but looser than `^`, so `-2 ^ 2` is -4.
+const fn prefix(
+    op: Operator,
+    symbol: &'static str,
+    name: &'static str,
+    eval: fn(BigDecimal) -> anyhow::Result<BigDecimal>,
+) -> OperatorInfo {
+    OperatorInfo {
+        op,
+        symbol,
+        lexeme: None,
+        name,
+        precedence: 3,
+        assoc: Assoc::Right,
+        fixity: Fixity::Prefix,
+        prefix: None,
+        eval: Eval::Unary(eval),
+    }
 }
 
-impl fmt::Display for Operator {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let symbol = match self {
-            Operator::Add => "+",
-            Operator::Sub => "-",
-            Operator::Mul => "*",
-            Operator::Div => "/",
-            Operator::Mod => "%",
-            Operator::Pow => "^",
-            Operator::UnarySub => "u-",
-        };
-        write!(f, "{symbol}")
+const fn postfix(
+    op: Operator,
+    symbol: &'static str,
+    eval: fn(BigDecimal) -> anyhow::Result<BigDecimal>,
+) -> OperatorInfo {
+    OperatorInfo {
+        op,
+        symbol,
+        lexeme: Some(symbol),
+        name: symbol,
+        precedence: 5,
+        assoc: Assoc::Left,
+        fixity: Fixity::Postfix,
+        prefix: None,
+        eval: Eval::Unary(eval),
     }
 }
 
-pub fn operator_precedence(op: Operator) -> u8 {
-    match op {
-        Operator::Add | Operator::Sub => 1,
-        Operator::Mul | Operator::Div | Operator::Mod => 2,
-        Operator::UnarySub => 3,
-        Operator::Pow => 4,
+impl OperatorInfo {
+    const fn with_prefix(mut self, prefix: Operator) -> Self {
+        self.prefix = Some(prefix);
+        self
+    }
+
+    /// Told apart from another operator by context rather than by symbol.
+    const fn derived(mut self, name: &'static str) -> Self {
+        self.lexeme = None;
+        self.name = name;
+        self
     }
 }
 
-pub fn operator_associativity(op: Operator) -> Assoc {
-    match op {
-        Operator::Pow | Operator::UnarySub => Assoc::Right,
-        Operator::Add | Operator::Sub | Operator::Mul | Operator::Div | Operator::Mod => {
-            Assoc::Left
-        }
+fn div(lhs: BigDecimal, rhs: BigDecimal) -> anyhow::Result<BigDecimal> {
+    if rhs.is_zero() {
+        bail!("Division by zero");
+    }
+    Ok(precision::divide(&lhs, &rhs, precision::working_digits()))
+}
+
+fn rem(lhs: BigDecimal, rhs: BigDecimal) -> anyhow::Result<BigDecimal> {
+    if rhs.is_zero() {
+        bail!("Modulo by zero");
+    }
+    Ok(lhs % rhs)
+}
+
+fn pow(lhs: BigDecimal, rhs: BigDecimal) -> anyhow::Result<BigDecimal> {
+    if !rhs.is_integer() {
+        bail!("Exponent must be an integer for power operation");
+    }
+    let exponent = rhs
+        .to_i64()
+        .ok_or_else(|| anyhow!("Exponent is out of range for power operation"))?;
+    if exponent >= 0 {
+        return Ok(precision::pow_exact(&lhs, exponent as u64));
+    }
+    if lhs.is_zero() {
+        bail!("Division by zero");
+    }
+    let denominator = precision::pow_exact(&lhs, exponent.unsigned_abs());
+    Ok(precision::divide(
+        &BigDecimal::from(1),
+        &denominator,
+        precision::working_digits(),
+    ))
+}
+
+impl Operator {
+    pub fn info(self) -> &'static OperatorInfo {
+        &OPERATORS[self as usize]
+    }
+
+    /// The operator written at the start of `input`, longest symbol first,
+    /// with the number of characters it spans.
+    pub fn lex(input: &[char]) -> Option<(Operator, usize)> {
+        OPERATORS
+            .iter()
+            .filter_map(|info| {
+                let lexeme = info.lexeme?;
+                let len = lexeme.chars().count();
+                (input.len() >= len && lexeme.chars().eq(input[..len].iter().copied()))
+                    .then_some((info.op, len))
+            })
+            .max_by_key(|(_, len)| *len)
+    }
+
+    pub fn fixity(&self) -> Fixity {
+        self.info().fixity
+    }
+
+    /// Prefix and postfix operators take a single operand.
+    pub fn is_unary(&self) -> bool {
+        self.fixity() != Fixity::Infix
+    }
+
+    pub fn is_postfix(&self) -> bool {
+        self.fixity() == Fixity::Postfix
+    }
+
+    pub fn arity(&self) -> usize {
+        if self.is_unary() { 1 } else { 2 }
+    }
+
+    pub fn precedence(&self) -> u8 {
+        self.info().precedence
+    }
+
+    pub fn associativity(&self) -> Assoc {
+        self.info().assoc
+    }
+
+    /// The symbol as written in an expression.
+    pub fn symbol(&self) -> &'static str {
+        self.info().symbol
+    }
+
+    /// The prefix form of a binary operator written where an operand is expected.
+    pub fn to_unary(self) -> Option<Operator> {
+        self.info().prefix
+    }
+}
+
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.info().name)
     }
 }
 
 pub fn should_pop_operator(stack_op: Operator, incoming: Operator) -> bool {
-    let stack_prec = operator_precedence(stack_op);
-    let incoming_prec = operator_precedence(incoming);
+    let stack_prec = stack_op.precedence();
+    let incoming_prec = incoming.precedence();
 
     if stack_prec > incoming_prec {
         return true;
     }
 
     if stack_prec == incoming_prec {
-        return matches!(operator_associativity(incoming), Assoc::Left);
+        return matches!(incoming.associativity(), Assoc::Left);
     }
 
     false
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_is_indexed_by_operator() {
+        for (index, info) in OPERATORS.iter().enumerate() {
+            assert_eq!(info.op as usize, index, "{}", info.name);
+            assert_eq!(
+                matches!(info.eval, Eval::Binary(_)),
+                info.fixity == Fixity::Infix,
+                "{}",
+                info.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_lex_prefers_longest_symbol() {
+        let lex = |input: &str| Operator::lex(&input.chars().collect::<Vec<_>>());
+        assert_eq!(lex("//2"), Some((Operator::FloorDiv, 2)));
+        assert_eq!(lex("/2"), Some((Operator::Div, 1)));
+        assert_eq!(lex("!!"), Some((Operator::DoubleFactorial, 2)));
+        assert_eq!(lex("% 3"), Some((Operator::Mod, 1)));
+        assert_eq!(lex("²"), Some((Operator::Square, 1)));
+        assert_eq!(lex("a"), None);
+        assert_eq!(lex(""), None);
+    }
+
+    #[test]
+    fn test_postfix_operators() {
+        let eval = |input: &str| crate::evaluator::eval(input).map(|value| value.to_string());
+        assert_eq!(eval("0!").unwrap(), "1");
+        assert_eq!(eval("5!").unwrap(), "120");
+        assert_eq!(eval("-3! + 2^3!").unwrap(), "58");
+        assert_eq!(eval("(1 + 2)!²").unwrap(), "36");
+        assert_eq!(eval("3³").unwrap(), "27");
+        // Postfix operators apply left to right and before a prefix sign
+        assert_eq!(eval("2³!").unwrap(), "40320");
+        assert_eq!(eval("-2²").unwrap(), "-4");
+        assert_eq!(eval("(-2)²").unwrap(), "4");
+        assert_eq!(eval("2.5²").unwrap(), "6.25");
+        assert_eq!(eval("50%").unwrap(), "0.5");
+        assert_eq!(eval("50% * 8").unwrap(), "4");
+        assert_eq!(eval("200 * 15%").unwrap(), "30");
+        assert_eq!(eval("50% * 50%").unwrap(), "0.25");
+        assert_eq!(eval("12.5% * 8 + 1").unwrap(), "2");
+        assert_eq!(eval("2 * 10%^2").unwrap(), "0.02");
+        assert_eq!(eval("1e-100%").unwrap(), "1E-102");
+        assert_eq!(eval("50% - 10").unwrap(), "-9.5");
+        // `%` followed by an operand is still the modulo operator
+        assert_eq!(eval("7 % -3").unwrap(), "1");
+
+        assert_eq!(
+            eval("(-1)!").unwrap_err().to_string(),
+            "Factorial is only defined for non-negative integers"
+        );
+        assert_eq!(
+            eval("10001!").unwrap_err().to_string(),
+            "Factorial argument exceeds 10000"
+        );
+        assert!(eval("2.5!").is_err());
+        assert!(eval("!").is_err());
+        assert!(eval("!3").is_err());
+        assert!(eval("²3").is_err());
+    }
+}
@@ -1,13 +1,18 @@
 use bigdecimal::BigDecimal;
 use std::fmt;
 
-use super::{math_const::MathConst, operator::Operator};
+use super::{function::Function, math_const::MathConst, operator::Operator};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Token {
     Number(BigDecimal),
     Ident(MathConst),
+    Var(String),
     Op(Operator),
+    Func(Function),
+    /// Function application with its argument count, only present in RPN
+    Call(Function, usize),
+    Comma,
     LParenthesis,
     RParenthesis,
 }
@@ -26,15 +31,12 @@ impl<'a> From<&'a Vec<Token>> for TokenList<'a> {
     }
 }
 
-pub fn is_paren(ch: char) -> bool {
-    matches!(ch, '(' | ')')
-}
-
-pub fn to_paren(ch: char) -> Token {
+/// The parenthesis token `ch` stands for, if it is one.
+pub fn to_paren(ch: char) -> Option<Token> {
     match ch {
-        '(' => Token::LParenthesis,
-        ')' => Token::RParenthesis,
-        _ => panic!("Invalid character for parenthesis: {}", ch),
+        '(' => Some(Token::LParenthesis),
+        ')' => Some(Token::RParenthesis),
+        _ => None,
     }
 }
 
@@ -43,7 +45,11 @@ impl fmt::Display for Token {
         match self {
             Token::Number(num) => write!(f, "{}", num),
             Token::Ident(name) => write!(f, "{}", name),
+            Token::Var(name) => write!(f, "{}", name),
             Token::Op(op) => write!(f, "{}", op),
+            Token::Func(func) => write!(f, "{}", func),
+            Token::Call(func, argc) => write!(f, "{}/{}", func, argc),
+            Token::Comma => write!(f, ","),
             Token::LParenthesis => write!(f, "("),
             Token::RParenthesis => write!(f, ")"),
         }
@@ -0,0 +1,180 @@
+//! Integer helpers for exact representations and number-theoretic functions.
+
+use bigdecimal::num_bigint::BigInt;
+use num_integer::Integer;
+use num_traits::{One, Zero};
+
+/// Deterministic Miller-Rabin for all `u64`.
+pub fn is_prime(n: u64) -> bool {
+    if n < 2 {
+        return false;
+    }
+    for p in [2u64, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37] {
+        if n.is_multiple_of(p) {
+            return n == p;
+        }
+    }
+    let (mut d, mut s) = (n - 1, 0);
+    while d % 2 == 0 {
+        d /= 2;
+        s += 1;
+    }
+    'witness: for a in [2u64, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37] {
+        let mut x = pow_mod(a, d, n);
+        if x == 1 || x == n - 1 {
+            continue;
+        }
+        for _ in 1..s {
+            x = mul_mod(x, x, n);
+            if x == n - 1 {
+                continue 'witness;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+/// Prime factors of `n` with multiplicity, ascending as `(prime, exponent)`.
+pub fn factorize(n: u64) -> Vec<(u64, u32)> {
+    let mut primes = Vec::new();
+    let mut n = n;
+    for p in [2u64, 3, 5] {
+        while n > 1 && n.is_multiple_of(p) {
+            primes.push(p);
+            n /= p;
+        }
+    }
+    split_factors(n, &mut primes);
+    primes.sort_unstable();
+
+    let mut factors: Vec<(u64, u32)> = Vec::new();
+    for p in primes {
+        match factors.last_mut() {
+            Some((last, exponent)) if *last == p => *exponent += 1,
+            _ => factors.push((p, 1)),
+        }
+    }
+    factors
+}
+
+fn split_factors(n: u64, primes: &mut Vec<u64>) {
+    if n == 1 {
+        return;
+    }
+    if is_prime(n) {
+        primes.push(n);
+        return;
+    }
+    let divisor = pollard_rho(n);
+    split_factors(divisor, primes);
+    split_factors(n / divisor, primes);
+}
+
+/// A non-trivial divisor of the odd composite `n` (Brent's variant).
+fn pollard_rho(n: u64) -> u64 {
+    let mut c = 1;
+    loop {
+        let f = |x: u64| (mul_mod(x, x, n) + c) % n;
+        let (mut x, mut y, mut d) = (2u64, 2u64, 1u64);
+        while d == 1 {
+            x = f(x);
+            y = f(f(y));
+            d = x.abs_diff(y).gcd(&n);
+        }
+        if d != n {
+            return d;
+        }
+        c += 1;
+    }
+}
+
+/// `(F(n), F(n + 1))`, by squaring the matrix `[[1, 1], [1, 0]]`.
+pub fn fibonacci_pair(n: u64) -> (BigInt, BigInt) {
+    // [[a, b], [b, c]] is symmetric throughout, so three entries suffice
+    let (mut a, mut b, mut c) = (BigInt::one(), BigInt::zero(), BigInt::one());
+    let (mut pa, mut pb, mut pc) = (BigInt::one(), BigInt::one(), BigInt::zero());
+    let mut n = n;
+    while n > 0 {
+        if n & 1 == 1 {
+            (a, b, c) = (
+                &a * &pa + &b * &pb,
+                &a * &pb + &b * &pc,
+                &b * &pb + &c * &pc,
+            );
+        }
+        (pa, pb, pc) = (
+            &pa * &pa + &pb * &pb,
+            &pb * (&pa + &pc),
+            &pb * &pb + &pc * &pc,
+        );
+        n >>= 1;
+    }
+    // a = F(n + 1), b = F(n)
+    (b, a)
+}
+
+/// The Lucas number `L(n) = 2 F(n + 1) - F(n)`.
+pub fn lucas(n: u64) -> BigInt {
+    let (f, next) = fibonacci_pair(n);
+    next * 2 - f
+}
+
+/// The Catalan number `C(n) = binomial(2n, n) / (n + 1)`.
+pub fn catalan(n: u64) -> BigInt {
+    let mut c = BigInt::one();
+    for k in 0..n {
+        c = c * (2 * (2 * k + 1)) / (k + 2);
+    }
+    c
+}
+
+fn mul_mod(a: u64, b: u64, m: u64) -> u64 {
+    ((a as u128 * b as u128) % m as u128) as u64
+}
+
+fn pow_mod(mut base: u64, mut exp: u64, m: u64) -> u64 {
+    let mut result = 1;
+    base %= m;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mul_mod(result, base, m);
+        }
+        base = mul_mod(base, base, m);
+        exp >>= 1;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_factorize() {
+        assert_eq!(factorize(1), vec![]);
+        assert_eq!(factorize(360), vec![(2, 3), (3, 2), (5, 1)]);
+        assert_eq!(
+            factorize(600851475143),
+            vec![(71, 1), (839, 1), (1471, 1), (6857, 1)]
+        );
+        assert_eq!(
+            factorize(18446744073709551557),
+            vec![(18446744073709551557, 1)]
+        );
+        assert_eq!(factorize(4294967297), vec![(641, 1), (6700417, 1)]);
+        assert!(is_prime(2_147_483_647));
+        assert!(!is_prime(3_215_031_751));
+    }
+
+    #[test]
+    fn test_sequences() {
+        let fib: Vec<BigInt> = (0..10).map(|n| fibonacci_pair(n).0).collect();
+        assert_eq!(fib, [0, 1, 1, 2, 3, 5, 8, 13, 21, 34].map(BigInt::from));
+        assert_eq!(fibonacci_pair(100).0.to_string(), "354224848179261915075");
+        let lucas: Vec<BigInt> = (0..6).map(lucas).collect();
+        assert_eq!(lucas, [2, 1, 3, 4, 7, 11].map(BigInt::from));
+        let catalan: Vec<BigInt> = (0..7).map(catalan).collect();
+        assert_eq!(catalan, [1, 1, 2, 5, 14, 42, 132].map(BigInt::from));
+    }
+}
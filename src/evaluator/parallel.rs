@@ -0,0 +1,195 @@
+//! Parallel evaluation of large machine-generated expressions: operands that
+//! do not depend on each other, such as the two sides of `a * b` or the
+//! arguments of a call, are evaluated on the rayon pool once each is
+//! estimated to cost at least `limits.parallel_min_cost`. Operands are still
+//! combined in order, so results and the error reported are the same as on
+//! a single thread.
+
+use anyhow::anyhow;
+use bigdecimal::BigDecimal;
+use rayon::prelude::*;
+
+use super::context::{EvalContext, checkpoint};
+use super::functions::apply_function;
+use super::limits::DepthBudget;
+use super::{Environment, Token, apply_operator, apply_unary_operator, cost, precision};
+
+/// A token of the RPN stream with the subtrees of its operands.
+struct Node<'a> {
+    token: &'a Token,
+    cost: u64,
+    operands: Vec<Node<'a>>,
+}
+
+/// What every worker needs besides the subtree it evaluates.
+struct Scope<'a> {
+    env: &'a Environment,
+    /// Entered again on each worker, since the kernels read it per thread
+    context: &'a EvalContext,
+    min_cost: u64,
+}
+
+impl Scope<'_> {
+    fn enter<T>(&self, f: impl FnOnce() -> T) -> T {
+        self.context.run(f)
+    }
+}
+
+/// Evaluate `rpn` in parallel when it is expensive enough to be worth it.
+/// `Ok(None)` leaves it to the sequential paths.
+pub fn try_eval(
+    rpn: &[Token],
+    env: &Environment,
+    ctx: &EvalContext,
+) -> anyhow::Result<Option<BigDecimal>> {
+    let limits = &ctx.limits;
+    if limits.parallel_min_cost == 0 {
+        return Ok(None);
+    }
+    let costs = cost::subtree_costs(rpn, limits);
+    // Worth it only when the root has two operands over the threshold
+    if costs
+        .last()
+        .is_none_or(|total| *total < limits.parallel_min_cost * 2)
+    {
+        return Ok(None);
+    }
+    let Some(root) = tree(rpn, &costs) else {
+        return Ok(None);
+    };
+    let scope = Scope {
+        env,
+        context: ctx,
+        min_cost: limits.parallel_min_cost,
+    };
+    let result = root.eval(&scope, DepthBudget::new(limits.max_depth))?;
+    Ok(Some(precision::round_to(result, limits.precision)))
+}
+
+/// The tree of `rpn`, `None` when it is malformed; the sequential paths then
+/// report why.
+fn tree<'a>(rpn: &'a [Token], costs: &[u64]) -> Option<Node<'a>> {
+    let mut stack: Vec<Node> = Vec::new();
+    for (token, cost) in rpn.iter().zip(costs) {
+        let arity = match token {
+            Token::Op(op) => op.arity(),
+            Token::Call(_, argc) => *argc,
+            Token::Number(_) | Token::Ident(_) | Token::Var(_) => 0,
+            Token::LParenthesis | Token::RParenthesis | Token::Func(_) | Token::Comma => {
+                return None;
+            }
+        };
+        let operands = stack.split_off(stack.len().checked_sub(arity)?);
+        stack.push(Node {
+            token,
+            cost: *cost,
+            operands,
+        });
+    }
+    let root = stack.pop()?;
+    stack.is_empty().then_some(root)
+}
+
+impl Node<'_> {
+    fn eval(&self, scope: &Scope, budget: DepthBudget) -> anyhow::Result<BigDecimal> {
+        let budget = budget.descend()?;
+        checkpoint()?;
+        let mut operands = self.eval_operands(scope, budget)?;
+        match self.token {
+            Token::Number(num) => Ok(num.clone()),
+            Token::Ident(math_const) => Ok(BigDecimal::from(*math_const)),
+            Token::Var(name) => scope
+                .env
+                .get(name)
+                .cloned()
+                .ok_or_else(|| anyhow!("Unknown variable: {}", name)),
+            Token::Op(op) if op.is_unary() => apply_unary_operator(operands.remove(0), *op),
+            Token::Op(op) => {
+                let rhs = operands.pop().expect("binary operator has two operands");
+                let lhs = operands.pop().expect("binary operator has two operands");
+                apply_operator(lhs, rhs, *op)
+            }
+            Token::Call(func, _) => {
+                apply_function(*func, &operands, scope.context.angle_mode_in(scope.env))
+            }
+            Token::LParenthesis | Token::RParenthesis | Token::Func(_) | Token::Comma => {
+                unreachable!("not part of a tree")
+            }
+        }
+    }
+
+    /// The operands' values in order, computed on the pool when at least two
+    /// are expensive. The first error in operand order wins either way.
+    fn eval_operands(&self, scope: &Scope, budget: DepthBudget) -> anyhow::Result<Vec<BigDecimal>> {
+        let expensive = self
+            .operands
+            .iter()
+            .filter(|operand| operand.cost >= scope.min_cost)
+            .count();
+        let eval = |operand: &Node| scope.enter(|| operand.eval(scope, budget));
+        match self.operands.as_slice() {
+            [lhs, rhs] if expensive == 2 => {
+                let (lhs, rhs) = rayon::join(|| eval(lhs), || eval(rhs));
+                Ok(vec![lhs?, rhs?])
+            }
+            operands if expensive >= 2 => operands
+                .par_iter()
+                .map(eval)
+                .collect::<Vec<_>>()
+                .into_iter()
+                .collect(),
+            operands => operands
+                .iter()
+                .map(|operand| operand.eval(scope, budget))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::{Limits, eval};
+
+    fn in_parallel<T>(f: impl FnOnce() -> T) -> T {
+        EvalContext::new(Limits {
+            parallel_min_cost: 1,
+            ..Limits::DEFAULT
+        })
+        .run(f)
+    }
+
+    fn sequential<T>(f: impl FnOnce() -> T) -> T {
+        EvalContext::new(Limits {
+            parallel_min_cost: 0,
+            ..Limits::DEFAULT
+        })
+        .run(f)
+    }
+
+    #[test]
+    fn test_parallel_matches_sequential() {
+        let inputs = [
+            "sqrt(2) * 3 ^ 200 + 7 ^ 150 / sqrt(3)",
+            "hypot(sqrt(5) * 2 ^ 90, 11 ^ 40 / 3, 1.5 * 5 ^ 60) - abs(-2)",
+            "(1 / 7) ^ 12 * (2 / 3) ^ 9",
+        ];
+        for input in inputs {
+            assert_eq!(
+                in_parallel(|| eval(input).unwrap()),
+                sequential(|| eval(input).unwrap()),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_first_error_wins() {
+        let input = "sqrt(2) * (1 / 0) + sqrt(3) * (5 % 0)";
+        assert_eq!(
+            in_parallel(|| eval(input).unwrap_err().to_string()),
+            "Division by zero"
+        );
+        assert!(in_parallel(|| eval("sqrt(2) * nope + sqrt(3)")).is_err());
+    }
+}
@@ -0,0 +1,153 @@
+//! Working precision for inexact operations. Intermediates carry
+//! [`GUARD_DIGITS`] extra significant digits, final results are rounded to the
+//! configured precision, and exact values are never rounded.
+
+use bigdecimal::num_bigint::BigInt;
+use bigdecimal::{BigDecimal, RoundingMode};
+use num_traits::{Signed, ToPrimitive, Zero};
+use std::num::NonZeroU64;
+use std::str::FromStr;
+
+use super::limits::limits;
+
+pub const GUARD_DIGITS: u64 = 10;
+
+/// Significant digits kept for intermediates.
+pub fn working_digits() -> u64 {
+    limits().precision + GUARD_DIGITS
+}
+
+/// `a / b` correctly rounded (half-even) to `digits` significant digits;
+/// exact quotients such as `3 / 4` keep their short form.
+pub fn divide(a: &BigDecimal, b: &BigDecimal, digits: u64) -> BigDecimal {
+    let (a_int, a_scale) = a.as_bigint_and_exponent();
+    let (b_int, b_scale) = b.as_bigint_and_exponent();
+    if a_int.is_zero() {
+        return BigDecimal::zero();
+    }
+
+    // Two extra digits plus a sticky digit make the final rounding exact
+    let shift = (digits as i64 + 2 + b.digits() as i64 - a.digits() as i64).max(0) as u32;
+    let numerator = &a_int * BigInt::from(10).pow(shift);
+    let (quotient, remainder) = (&numerator / &b_int, &numerator % &b_int);
+    let scale = a_scale - b_scale + i64::from(shift);
+
+    if remainder.is_zero() {
+        let exact = BigDecimal::new(quotient, scale).normalized();
+        return if exact.fractional_digit_count() < 0 {
+            exact.with_scale(0)
+        } else {
+            exact
+        };
+    }
+    let sticky = if a_int.is_negative() == b_int.is_negative() {
+        1
+    } else {
+        -1
+    };
+    BigDecimal::new(quotient * 10 + sticky, scale + 1).with_precision_round(
+        NonZeroU64::new(digits).unwrap_or(NonZeroU64::MIN),
+        RoundingMode::HalfEven,
+    )
+}
+
+/// The positive `n`th root of a positive `value` to `digits` significant
+/// digits, by Newton's method from a floating-point first guess. Roots that
+/// are exact at the configured precision, such as the cube root of `0.008`,
+/// come out exact.
+pub fn nth_root(value: &BigDecimal, n: u64, digits: u64) -> BigDecimal {
+    if n == 1 || value.is_zero() {
+        return value.clone();
+    }
+    let exponent = value.digits() as i64 - value.fractional_digit_count();
+    let (int, scale) = value.as_bigint_and_exponent();
+    let mantissa = BigDecimal::new(int, scale + exponent)
+        .to_f64()
+        .unwrap_or(1.0);
+    let log = (mantissa.log10() + exponent as f64) / n as f64;
+    let magnitude = log.floor();
+    let mut root = BigDecimal::from_str(&format!("{}e{}", 10f64.powf(log - magnitude), magnitude))
+        .unwrap_or_else(|_| BigDecimal::from(1));
+    let tolerance = BigDecimal::new(BigInt::from(1), digits as i64 - magnitude as i64);
+
+    let precision = NonZeroU64::new(digits + 2).unwrap_or(NonZeroU64::MIN);
+    let n_big = BigDecimal::from(n);
+    for _ in 0..100 {
+        let power = pow_rounded(&root, n - 1, precision);
+        let next = divide(
+            &(&root * BigDecimal::from(n - 1) + divide(value, &power, digits + 2)),
+            &n_big,
+            digits + 2,
+        );
+        let converged = (&next - &root).abs() <= tolerance;
+        root = next;
+        if converged {
+            break;
+        }
+    }
+
+    let candidate = root
+        .with_precision_round(
+            NonZeroU64::new(limits().precision).unwrap_or(NonZeroU64::MIN),
+            RoundingMode::HalfEven,
+        )
+        .normalized();
+    if (candidate.digits() - 1).saturating_mul(n) < value.digits()
+        && pow_exact(&candidate, n) == *value
+    {
+        return candidate;
+    }
+    root.with_precision_round(
+        NonZeroU64::new(digits).unwrap_or(NonZeroU64::MIN),
+        RoundingMode::HalfEven,
+    )
+}
+
+/// `base ^ exponent` by squaring, rounding every product to `precision`.
+fn pow_rounded(base: &BigDecimal, exponent: u64, precision: NonZeroU64) -> BigDecimal {
+    let mut result = BigDecimal::from(1);
+    let mut square = base.clone();
+    let mut exponent = exponent;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = (&result * &square).with_precision_round(precision, RoundingMode::HalfEven);
+        }
+        square = square
+            .square()
+            .with_precision_round(precision, RoundingMode::HalfEven);
+        exponent >>= 1;
+    }
+    result
+}
+
+/// `base ^ exponent` computed exactly; callers bound the size beforehand.
+pub fn pow_exact(base: &BigDecimal, exponent: u64) -> BigDecimal {
+    let (int, scale) = base.as_bigint_and_exponent();
+    match u32::try_from(exponent) {
+        Ok(exponent) => BigDecimal::new(int.pow(exponent), scale * i64::from(exponent)),
+        // Only 0 and ±1 get past the size limits with such exponents
+        Err(_) if base.abs() == BigDecimal::from(1) && exponent % 2 == 1 => base.clone(),
+        Err(_) if base.abs() == BigDecimal::from(1) => BigDecimal::from(1),
+        Err(_) => BigDecimal::zero(),
+    }
+}
+
+/// Round away fractional digits beyond `digits` significant digits. Integers
+/// and values already within the budget are returned unchanged.
+pub fn round_to(value: BigDecimal, digits: u64) -> BigDecimal {
+    let scale = value.fractional_digit_count();
+    if scale <= 0 || value.digits() <= digits {
+        return value;
+    }
+    // Negative for values below 0.1, whose leading fractional zeros are not significant
+    let integer_digits = value.digits() as i64 - scale;
+    let keep = (digits as i64 - integer_digits).max(0);
+    let keep = keep.min(scale);
+    let rounded = value.with_scale_round(keep, RoundingMode::HalfEven);
+    // Rounding 0.999… up carries into a power of ten, written without zeros
+    if rounded.digits() > digits {
+        let trimmed = rounded.normalized();
+        return trimmed.with_scale(trimmed.fractional_digit_count().max(0));
+    }
+    rounded
+}
@@ -0,0 +1,44 @@
+//! What produced a result, so callers caching answers can tell when the
+//! engine changed underneath them.
+
+use super::limits::limits;
+use super::precision::GUARD_DIGITS;
+use serde::Serialize;
+
+/// Bumped whenever the same input may parse differently, e.g. a new operator
+/// or syntax rewrite.
+pub const PARSER_VERSION: u32 = 1;
+/// Bumped whenever a parsed expression may evaluate to a different value,
+/// e.g. a change to rounding or to a function's algorithm.
+pub const EVALUATOR_VERSION: u32 = 2;
+/// Source of the values and uncertainties of the physical constants.
+pub const CONSTANTS_VERSION: &str = "CODATA 2018";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Provenance {
+    pub engine_version: &'static str,
+    pub parser_version: u32,
+    pub evaluator_version: u32,
+    /// Significant digits of inexact results
+    pub precision: u64,
+    pub guard_digits: u64,
+    pub constants_version: &'static str,
+    /// Whether the result was served from a cache rather than evaluated
+    pub cache_hit: bool,
+}
+
+impl Provenance {
+    /// Provenance of a result produced now with the current limits.
+    pub fn current(cache_hit: bool) -> Self {
+        Provenance {
+            engine_version: env!("CARGO_PKG_VERSION"),
+            parser_version: PARSER_VERSION,
+            evaluator_version: EVALUATOR_VERSION,
+            precision: limits().precision,
+            guard_digits: GUARD_DIGITS,
+            constants_version: CONSTANTS_VERSION,
+            cache_hit,
+        }
+    }
+}
@@ -0,0 +1,132 @@
+//! Quaternions `w + xi + yj + zk`, passed to functions as their four
+//! components in that order.
+
+use bigdecimal::BigDecimal;
+use num_traits::Zero;
+
+use super::precision::{divide, working_digits};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quaternion {
+    pub w: BigDecimal,
+    pub x: BigDecimal,
+    pub y: BigDecimal,
+    pub z: BigDecimal,
+}
+
+impl Quaternion {
+    /// The quaternion in the four components of `args`.
+    pub fn from_slice(args: &[BigDecimal]) -> Self {
+        Quaternion {
+            w: args[0].clone(),
+            x: args[1].clone(),
+            y: args[2].clone(),
+            z: args[3].clone(),
+        }
+    }
+
+    /// A pure quaternion holding a 3D vector.
+    pub fn pure(v: &[BigDecimal]) -> Self {
+        Quaternion {
+            w: BigDecimal::zero(),
+            x: v[0].clone(),
+            y: v[1].clone(),
+            z: v[2].clone(),
+        }
+    }
+
+    pub fn into_vec(self) -> Vec<BigDecimal> {
+        vec![self.w, self.x, self.y, self.z]
+    }
+
+    /// The Hamilton product `self * other`.
+    pub fn mul(&self, other: &Quaternion) -> Quaternion {
+        let (a, b) = (self, other);
+        Quaternion {
+            w: &a.w * &b.w - &a.x * &b.x - &a.y * &b.y - &a.z * &b.z,
+            x: &a.w * &b.x + &a.x * &b.w + &a.y * &b.z - &a.z * &b.y,
+            y: &a.w * &b.y - &a.x * &b.z + &a.y * &b.w + &a.z * &b.x,
+            z: &a.w * &b.z + &a.x * &b.y - &a.y * &b.x + &a.z * &b.w,
+        }
+    }
+
+    pub fn conjugate(&self) -> Quaternion {
+        Quaternion {
+            w: self.w.clone(),
+            x: -&self.x,
+            y: -&self.y,
+            z: -&self.z,
+        }
+    }
+
+    pub fn norm_squared(&self) -> BigDecimal {
+        self.w.square() + self.x.square() + self.y.square() + self.z.square()
+    }
+
+    /// The 3D vector `v` rotated by this quaternion, which need not be a unit
+    /// quaternion: `q v q* / |q|^2` only depends on its direction.
+    pub fn rotate(&self, v: &[BigDecimal]) -> anyhow::Result<Vec<BigDecimal>> {
+        let norm_squared = self.norm_squared();
+        if norm_squared.is_zero() {
+            anyhow::bail!("Cannot rotate by the zero quaternion");
+        }
+        let rotated = self.mul(&Quaternion::pure(v)).mul(&self.conjugate());
+        Ok([rotated.x, rotated.y, rotated.z]
+            .iter()
+            .map(|c| divide(c, &norm_squared, working_digits()))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn quaternion(w: i32, x: i32, y: i32, z: i32) -> Quaternion {
+        Quaternion::from_slice(&[w, x, y, z].map(BigDecimal::from))
+    }
+
+    #[test]
+    fn test_quaternion_arithmetic() {
+        let (one, i, j, k) = (
+            quaternion(1, 0, 0, 0),
+            quaternion(0, 1, 0, 0),
+            quaternion(0, 0, 1, 0),
+            quaternion(0, 0, 0, 1),
+        );
+        // Hamilton's rules: ij = k, ji = -k, i² = -1
+        assert_eq!(i.mul(&j), k);
+        assert_eq!(j.mul(&i), quaternion(0, 0, 0, -1));
+        assert_eq!(i.mul(&i), quaternion(-1, 0, 0, 0));
+        assert_eq!(one.mul(&k), k);
+
+        let q = quaternion(1, 2, 3, 4);
+        assert_eq!(q.conjugate(), quaternion(1, -2, -3, -4));
+        assert_eq!(q.norm_squared(), BigDecimal::from(30));
+        assert_eq!(q.mul(&q.conjugate()), quaternion(30, 0, 0, 0));
+
+        // Half a turn about z, scaled: only the direction of the quaternion counts
+        let v = [1, 2, 3].map(BigDecimal::from);
+        let rotated = quaternion(0, 0, 0, 5).rotate(&v).unwrap();
+        assert_eq!(rotated, [-1, -2, 3].map(BigDecimal::from));
+        let quarter = Quaternion::from_slice(
+            &["0.5", "0.5", "0.5", "0.5"].map(|c| BigDecimal::from_str(c).unwrap()),
+        );
+        assert_eq!(
+            quarter.rotate(&[1, 0, 0].map(BigDecimal::from)).unwrap(),
+            [0, 1, 0].map(BigDecimal::from)
+        );
+
+        assert_eq!(
+            quaternion(0, 0, 0, 0).rotate(&v).unwrap_err().to_string(),
+            "Cannot rotate by the zero quaternion"
+        );
+        assert_eq!(
+            crate::evaluator::eval("qconj(1, 2, 3)")
+                .unwrap_err()
+                .to_string(),
+            "Wrong number of arguments for qconj: 3"
+        );
+    }
+}
@@ -0,0 +1,129 @@
+//! Exact rationals behind decimal values and their continued-fraction approximations.
+
+use anyhow::bail;
+use bigdecimal::BigDecimal;
+use bigdecimal::num_bigint::BigInt;
+use num_integer::Integer;
+use num_traits::{One, Signed, Zero};
+
+/// `value` as a lowest-terms fraction with a positive denominator.
+pub fn exact(value: &BigDecimal) -> (BigInt, BigInt) {
+    let (mut numerator, scale) = value.normalized().into_bigint_and_exponent();
+    let mut denominator = BigInt::one();
+    if scale > 0 {
+        denominator = BigInt::from(10).pow(scale as u32);
+    } else {
+        numerator *= BigInt::from(10).pow(scale.unsigned_abs() as u32);
+    }
+    let divisor = numerator.gcd(&denominator);
+    (numerator / &divisor, denominator / divisor)
+}
+
+/// The fraction closest to `value` whose denominator is at most `max_denominator`,
+/// found by walking the continued-fraction convergents and the best semiconvergent.
+pub fn best_approximation(
+    value: &BigDecimal,
+    max_denominator: &BigInt,
+) -> anyhow::Result<(BigInt, BigInt)> {
+    if max_denominator < &BigInt::one() {
+        bail!("Maximum denominator must be at least 1");
+    }
+    let (numerator, denominator) = exact(value);
+    if &denominator <= max_denominator {
+        return Ok((numerator, denominator));
+    }
+
+    let negative = numerator.is_negative();
+    let (mut n, mut d) = (numerator.abs(), denominator.clone());
+    let (mut p0, mut q0) = (BigInt::zero(), BigInt::one());
+    let (mut p1, mut q1) = (BigInt::one(), BigInt::zero());
+    loop {
+        let a = n.div_floor(&d);
+        let q2 = &q0 + &a * &q1;
+        if &q2 > max_denominator {
+            break;
+        }
+        let p2 = &p0 + &a * &p1;
+        (p0, q0) = (p1, q1);
+        (p1, q1) = (p2, q2);
+        let remainder = &n - &a * &d;
+        (n, d) = (d, remainder);
+    }
+
+    // The last convergent or the best semiconvergent, whichever is closer:
+    // |p/q - n/d| = |p*d - n*q| / (q*d), and the common `d` cancels
+    let k = (max_denominator - &q0).div_floor(&q1);
+    let (sp, sq) = (&p0 + &k * &p1, &q0 + &k * &q1);
+    let (target_n, target_d) = (numerator.abs(), denominator);
+    let gap = |p: &BigInt, q: &BigInt| (p * &target_d - &target_n * q).abs();
+    let (p, q) = if gap(&p1, &q1) * &sq <= gap(&sp, &sq) * &q1 {
+        (p1, q1)
+    } else {
+        (sp, sq)
+    };
+    Ok((if negative { -p } else { p }, q))
+}
+
+/// Up to `max_terms` coefficients `[a0; a1, a2, ...]` of the continued fraction
+/// of `value`. Decimals are rational, so the expansion ends once it is exact.
+pub fn continued_fraction(value: &BigDecimal, max_terms: usize) -> Vec<BigInt> {
+    let (mut n, mut d) = exact(value);
+    let mut terms = Vec::new();
+    while terms.len() < max_terms && !d.is_zero() {
+        let (a, remainder) = n.div_mod_floor(&d);
+        terms.push(a);
+        (n, d) = (d, remainder);
+    }
+    terms
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::EvalContext;
+    use crate::evaluator::{Environment, eval, eval_statements};
+    use std::str::FromStr;
+
+    fn approximate(value: &str, max_denominator: i64) -> (i64, i64) {
+        let value = BigDecimal::from_str(value).unwrap();
+        let (p, q) = best_approximation(&value, &BigInt::from(max_denominator)).unwrap();
+        (p.try_into().unwrap(), q.try_into().unwrap())
+    }
+
+    #[test]
+    fn test_best_approximation() {
+        assert_eq!(approximate("0.75", 100), (3, 4));
+        assert_eq!(approximate("3.14159265358979", 10), (22, 7));
+        assert_eq!(approximate("3.14159265358979", 1000), (355, 113));
+        assert_eq!(approximate("-0.3333333333", 10), (-1, 3));
+        assert_eq!(approximate("0.1", 1), (0, 1));
+        assert_eq!(approximate("2.5", 1), (2, 1));
+
+        let mut env = Environment::new();
+        let third =
+            eval_statements("to_fraction(1 / 3)", &mut env, &EvalContext::default()).unwrap();
+        let components = third.components.unwrap();
+        assert_eq!(components[0], ("numerator", BigDecimal::from(1)));
+        assert_eq!(components[1], ("denominator", BigDecimal::from(3)));
+        assert_eq!(third.value, eval("1 / 3").unwrap());
+        assert!(eval("to_fraction(0.5, 0)").is_err());
+        assert!(eval("to_fraction(0.5, 2.5)").is_err());
+    }
+
+    #[test]
+    fn test_continued_fraction() {
+        let expand = |value: &str, terms| {
+            continued_fraction(&BigDecimal::from_str(value).unwrap(), terms)
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        assert_eq!(expand("3.245", 10), "3,4,12,4");
+        assert_eq!(expand("-0.75", 10), "-1,4");
+        assert_eq!(expand("3.14159265358979", 4), "3,7,15,1");
+        assert_eq!(expand("7", 10), "7");
+        assert!(eval("cfrac(2, 0)").is_err());
+        assert!(eval("cfrac(2, 1.5)").is_err());
+    }
+}
@@ -0,0 +1,132 @@
+//! Dry runs of a candidate evaluator next to the current one. The candidate
+//! re-evaluates the final statement of each evaluation in the same scope, and
+//! disagreements are logged; responses always carry the primary result.
+
+use super::{Environment, EvalContext, Evaluation, parse_in};
+use bigdecimal::BigDecimal;
+use std::panic::{self, AssertUnwindSafe};
+use std::time::Instant;
+
+/// An alternative evaluator implementation under evaluation.
+pub trait Candidate: Send + Sync {
+    fn name(&self) -> &'static str;
+    /// Evaluate one expression (no assignment) under `ctx` with the variables
+    /// of `scope`.
+    fn eval(
+        &self,
+        expression: &str,
+        scope: &Environment,
+        ctx: &EvalContext,
+    ) -> anyhow::Result<BigDecimal>;
+}
+
+/// Walks the parsed [`Expr`](super::Expr) tree instead of the RPN stream.
+pub struct AstCandidate;
+
+impl Candidate for AstCandidate {
+    fn name(&self) -> &'static str {
+        "ast"
+    }
+
+    fn eval(
+        &self,
+        expression: &str,
+        scope: &Environment,
+        ctx: &EvalContext,
+    ) -> anyhow::Result<BigDecimal> {
+        let expr = parse_in(expression, scope, ctx)?;
+        ctx.run(|| expr.eval(scope))
+    }
+}
+
+/// A candidate result that differs from the primary one. `shadow` holds the
+/// candidate's error message when it failed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Discrepancy {
+    pub candidate: &'static str,
+    pub primary: BigDecimal,
+    pub shadow: Result<BigDecimal, String>,
+}
+
+/// Run `candidate` under `ctx` on the final statement of `evaluation`,
+/// logging and returning any disagreement. A panicking candidate counts as a
+/// failure.
+pub fn compare(
+    candidate: &dyn Candidate,
+    evaluation: &Evaluation,
+    ctx: &EvalContext,
+) -> Option<Discrepancy> {
+    let started = Instant::now();
+    let shadow = panic::catch_unwind(AssertUnwindSafe(|| {
+        candidate.eval(&evaluation.expression, &evaluation.scope, ctx)
+    }))
+    .unwrap_or_else(|_| Err(anyhow::anyhow!("Candidate panicked")))
+    .map_err(|err| err.to_string());
+    let elapsed_us = started.elapsed().as_micros() as u64;
+
+    if shadow.as_ref() == Ok(&evaluation.value) {
+        tracing::debug!(
+            candidate = candidate.name(),
+            elapsed_us,
+            "Shadow evaluation agreed"
+        );
+        return None;
+    }
+    let discrepancy = Discrepancy {
+        candidate: candidate.name(),
+        primary: evaluation.value.clone(),
+        shadow,
+    };
+    tracing::warn!(
+        candidate = discrepancy.candidate,
+        primary = %discrepancy.primary,
+        shadow = ?discrepancy.shadow,
+        elapsed_us,
+        "Shadow evaluation disagreed"
+    );
+    Some(discrepancy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::eval_statements;
+    use std::str::FromStr;
+
+    struct OffByOne;
+
+    impl Candidate for OffByOne {
+        fn name(&self) -> &'static str {
+            "off-by-one"
+        }
+
+        fn eval(
+            &self,
+            expression: &str,
+            scope: &Environment,
+            ctx: &EvalContext,
+        ) -> anyhow::Result<BigDecimal> {
+            if expression.contains("panic") {
+                panic!("unsupported");
+            }
+            Ok(AstCandidate.eval(expression, scope, ctx)? + 1)
+        }
+    }
+
+    #[test]
+    fn test_shadow_compare() {
+        let ctx = EvalContext::default();
+        let mut env = Environment::new();
+        let evaluation =
+            eval_statements("a = 2; b = a * 3; (a + b) ^ 2 / 4", &mut env, &ctx).unwrap();
+        assert_eq!(compare(&AstCandidate, &evaluation, &ctx), None);
+
+        let discrepancy = compare(&OffByOne, &evaluation, &ctx).unwrap();
+        assert_eq!(discrepancy.primary, BigDecimal::from(16));
+        assert_eq!(discrepancy.shadow, Ok(BigDecimal::from_str("17").unwrap()));
+
+        let evaluation = eval_statements("panic = 1; panic", &mut env, &ctx).unwrap();
+        let discrepancy = compare(&OffByOne, &evaluation, &ctx).unwrap();
+        assert_eq!(discrepancy.shadow, Err("Candidate panicked".to_string()));
+    }
+}
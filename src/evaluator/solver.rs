@@ -0,0 +1,734 @@
+//! Linear and quadratic equations and inequalities in one variable, and
+//! systems of linear equations. In one variable both sides are reduced to a
+//! polynomial `lhs - rhs` with exact coefficients, whose real roots split the
+//! number line into regions tested one by one. Systems are reduced to rational
+//! coefficient rows and handed to the exact [`matrix::solve`].
+//!
+//! Equations may write coefficients next to their variables, as in `2x + y`.
+
+use anyhow::{anyhow, bail, ensure};
+use bigdecimal::BigDecimal;
+use bigdecimal::num_bigint::BigInt;
+use num_integer::Integer;
+use num_rational::BigRational;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+use std::fmt;
+
+use super::calculus::depends_on;
+use super::limits::limits;
+use super::precision::{divide, round_to, working_digits};
+use super::{Environment, EvalContext, Expr, Operator, matrix, parse_in, rational};
+
+/// Highest degree accepted once both sides are combined.
+pub const MAX_DEGREE: usize = 2;
+
+/// Highest degree of an intermediate product, as in `(x + 1) ^ 3 - x ^ 3`.
+const MAX_INTERMEDIATE_DEGREE: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Relation {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Relation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Relation::Eq => "=",
+            Relation::Ne => "!=",
+            Relation::Lt => "<",
+            Relation::Le => "<=",
+            Relation::Gt => ">",
+            Relation::Ge => ">=",
+        }
+    }
+
+    /// Whether `value relation 0` holds.
+    fn holds(&self, value: &BigDecimal) -> bool {
+        match self {
+            Relation::Eq => value.is_zero(),
+            Relation::Ne => !value.is_zero(),
+            Relation::Lt => value.is_negative(),
+            Relation::Le => !value.is_positive(),
+            Relation::Gt => value.is_positive(),
+            Relation::Ge => !value.is_negative(),
+        }
+    }
+}
+
+/// A connected set of reals; a missing bound is infinite.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Interval {
+    pub lower: Option<BigDecimal>,
+    pub upper: Option<BigDecimal>,
+    pub lower_closed: bool,
+    pub upper_closed: bool,
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let (Some(lower), Some(upper)) = (&self.lower, &self.upper)
+            && lower == upper
+        {
+            return write!(f, "{{{}}}", lower);
+        }
+        let lower = self
+            .lower
+            .as_ref()
+            .map_or("-inf".to_string(), ToString::to_string);
+        let upper = self
+            .upper
+            .as_ref()
+            .map_or("inf".to_string(), ToString::to_string);
+        let open = if self.lower_closed { '[' } else { '(' };
+        let close = if self.upper_closed { ']' } else { ')' };
+        write!(f, "{}{}, {}{}", open, lower, upper, close)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Solution {
+    pub variable: String,
+    pub relation: Relation,
+    /// Coefficients of `lhs - rhs`, constant term first
+    pub coefficients: Vec<BigDecimal>,
+    /// Real roots of `lhs - rhs` in increasing order
+    pub roots: Vec<BigDecimal>,
+    /// Values satisfying the relation, as disjoint intervals in increasing order
+    pub intervals: Vec<Interval>,
+}
+
+impl Solution {
+    /// Interval notation, e.g. `(-inf, -2) ∪ (2, inf)`, with `∅` for no
+    /// solution and a set such as `{-2, 2}` when only isolated points solve it.
+    pub fn notation(&self) -> String {
+        if self.intervals.is_empty() {
+            return "∅".to_string();
+        }
+        let points: Option<Vec<String>> = self
+            .intervals
+            .iter()
+            .map(|interval| match (&interval.lower, &interval.upper) {
+                (Some(lower), Some(upper)) if lower == upper => Some(lower.to_string()),
+                _ => None,
+            })
+            .collect();
+        if let Some(points) = points {
+            return format!("{{{}}}", points.join(", "));
+        }
+        self.intervals
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(" ∪ ")
+    }
+}
+
+/// Solve `input`, such as `x^2 - 4 > 0` or `2x + 1 = 7`, for `variable`,
+/// which defaults to the only variable not bound in `env`, under `ctx`.
+pub fn solve(
+    input: &str,
+    variable: Option<&str>,
+    env: &Environment,
+    ctx: &EvalContext,
+) -> anyhow::Result<Solution> {
+    let input = implicit_products(input);
+    let (relation, lhs, rhs) = split_relation(&input)?;
+    let variable = match variable {
+        Some(variable) => variable.to_string(),
+        None => {
+            let unbound = unknowns(&[lhs, rhs], env)?;
+            match unbound.as_slice() {
+                [variable] => variable.clone(),
+                [] => bail!("No variable to solve for"),
+                _ => bail!(
+                    "Several unknowns ({}); choose a variable",
+                    unbound.join(", ")
+                ),
+            }
+        }
+    };
+
+    let (lhs, rhs) = (parse_in(lhs, env, ctx)?, parse_in(rhs, env, ctx)?);
+    let lhs = ctx.run(|| polynomial(&lhs, &variable, env))?;
+    let rhs = ctx.run(|| polynomial(&rhs, &variable, env))?;
+    let coefficients = trim(combine(&lhs, &rhs, |a, b| a - b));
+    if coefficients.len() > MAX_DEGREE + 1 {
+        bail!(
+            "Only linear and quadratic relations are supported, this one has degree {}",
+            coefficients.len() - 1
+        );
+    }
+    let roots = roots(&coefficients);
+    let intervals = intervals(&coefficients, &roots, relation);
+    Ok(Solution {
+        variable,
+        relation,
+        coefficients,
+        roots,
+        intervals,
+    })
+}
+
+/// Solve linear `equations` such as `2x + y = 5` and `x - y = 1` together for
+/// `variables`, which default to the unknowns not bound in `env` in order of
+/// appearance. There must be as many equations as unknowns and exactly one
+/// solution; values are exact up to the final conversion to decimals.
+pub fn solve_system(
+    equations: &[String],
+    variables: Option<&[String]>,
+    env: &Environment,
+    ctx: &EvalContext,
+) -> anyhow::Result<Vec<(String, BigDecimal)>> {
+    ensure!(!equations.is_empty(), "No equations given");
+    let mut sides = Vec::with_capacity(equations.len());
+    for equation in equations {
+        let equation = implicit_products(equation);
+        let (relation, lhs, rhs) = split_relation(&equation)?;
+        ensure!(
+            relation == Relation::Eq,
+            "Systems take equations only, found `{}`",
+            relation.as_str()
+        );
+        sides.push((lhs.to_string(), rhs.to_string()));
+    }
+    let variables = match variables {
+        Some(variables) => variables.to_vec(),
+        None => {
+            let texts: Vec<&str> = sides
+                .iter()
+                .flat_map(|(lhs, rhs)| [lhs.as_str(), rhs.as_str()])
+                .collect();
+            unknowns(&texts, env)?
+        }
+    };
+    ensure!(!variables.is_empty(), "No variables to solve for");
+    ensure!(
+        variables.len() == equations.len(),
+        "{} equations for {} unknowns ({}); a unique solution needs as many equations as unknowns",
+        equations.len(),
+        variables.len(),
+        variables.join(", ")
+    );
+
+    let mut coefficients = Vec::with_capacity(sides.len());
+    let mut constants = Vec::with_capacity(sides.len());
+    for (lhs, rhs) in &sides {
+        let (lhs, rhs) = (parse_in(lhs, env, ctx)?, parse_in(rhs, env, ctx)?);
+        let lhs = ctx.run(|| affine(&lhs, &variables, env))?;
+        let rhs = ctx.run(|| affine(&rhs, &variables, env))?;
+        // Clear denominators so the row is exact in decimals
+        let mut row: Vec<BigRational> = lhs.iter().zip(&rhs).map(|(a, b)| a - b).collect();
+        let lcm = row.iter().fold(BigInt::one(), |lcm, x| lcm.lcm(x.denom()));
+        let integer = |x: &BigRational| BigDecimal::from((x * &lcm).to_integer());
+        let constant = row.pop().unwrap();
+        coefficients.push(row.iter().map(integer).collect::<Vec<_>>());
+        constants.push(integer(&-constant));
+    }
+    let values = matrix::solve(&coefficients, &constants).map_err(|err| {
+        if err.to_string() == "Matrix is singular" {
+            anyhow!("The system has no unique solution")
+        } else {
+            err
+        }
+    })?;
+    Ok(variables
+        .into_iter()
+        .zip(values.into_iter().map(|value| value.normalized()))
+        .collect())
+}
+
+/// Names in `texts` that are not bound in `env`, in order of appearance.
+fn unknowns(texts: &[&str], env: &Environment) -> anyhow::Result<Vec<String>> {
+    let mut unbound: Vec<String> = Vec::new();
+    for text in texts {
+        for name in super::free_variables(text)? {
+            if !unbound.contains(&name) && !env.contains(&name) {
+                unbound.push(name);
+            }
+        }
+    }
+    Ok(unbound)
+}
+
+/// Make coefficients written against a name or parenthesis explicit
+/// products: `2x + 3(y - 1)` becomes `2*x + 3*(y - 1)`.
+fn implicit_products(input: &str) -> String {
+    let chars: Vec<char> = input.chars().collect();
+    let mut output = String::with_capacity(input.len());
+    let mut pos = 0;
+    while let Some(&c) = chars.get(pos) {
+        let in_name = pos > 0 && (chars[pos - 1].is_alphanumeric() || chars[pos - 1] == '_');
+        if !c.is_ascii_digit() || in_name {
+            output.push(c);
+            pos += 1;
+            continue;
+        }
+        let start = pos;
+        let digits_from = |mut pos: usize| {
+            while chars.get(pos).is_some_and(char::is_ascii_digit) {
+                pos += 1;
+            }
+            pos
+        };
+        pos = digits_from(pos);
+        if chars.get(pos) == Some(&'.') {
+            pos = digits_from(pos + 1);
+        }
+        // An exponent only when digits follow, so `2e` stays a product with e
+        if chars.get(pos).is_some_and(|c| c.eq_ignore_ascii_case(&'e')) {
+            let sign = usize::from(matches!(chars.get(pos + 1), Some('+' | '-')));
+            if chars.get(pos + 1 + sign).is_some_and(char::is_ascii_digit) {
+                pos = digits_from(pos + 1 + sign);
+            }
+        }
+        output.extend(&chars[start..pos]);
+        if chars
+            .get(pos)
+            .is_some_and(|&next| next.is_alphabetic() || next == '_' || next == '(')
+        {
+            output.push('*');
+        }
+    }
+    output
+}
+
+/// `expr` as `a1 * v1 + ... + an * vn + c` over `variables`, returned as
+/// `[a1, ..., an, c]`. Subexpressions free of the variables are evaluated in `env`.
+fn affine(
+    expr: &Expr,
+    variables: &[String],
+    env: &Environment,
+) -> anyhow::Result<Vec<BigRational>> {
+    let n = variables.len();
+    let constant = |value: BigRational| {
+        let mut row = vec![BigRational::zero(); n + 1];
+        row[n] = value;
+        row
+    };
+    let is_constant = |row: &[BigRational]| row[..n].iter().all(Zero::is_zero);
+    let scaled = |row: Vec<BigRational>, factor: &BigRational| -> Vec<BigRational> {
+        row.into_iter().map(|x| x * factor).collect()
+    };
+    let nonlinear = || anyhow!("{} is not linear in {}", expr, variables.join(", "));
+
+    if !variables.iter().any(|var| depends_on(expr, var)) {
+        let (numerator, denominator) = rational::exact(&expr.eval(env)?);
+        return Ok(constant(BigRational::new(numerator, denominator)));
+    }
+    Ok(match expr {
+        Expr::Var(name) => {
+            let mut row = constant(BigRational::zero());
+            let index = variables
+                .iter()
+                .position(|var| var == name)
+                .ok_or_else(nonlinear)?;
+            row[index] = BigRational::one();
+            row
+        }
+        Expr::Unary(Operator::UnaryAdd, operand) => affine(operand, variables, env)?,
+        Expr::Unary(Operator::UnarySub, operand) => {
+            scaled(affine(operand, variables, env)?, &-BigRational::one())
+        }
+        Expr::Unary(Operator::Percent, operand) => scaled(
+            affine(operand, variables, env)?,
+            &BigRational::new(BigInt::one(), BigInt::from(100)),
+        ),
+        Expr::Binary(op @ (Operator::Add | Operator::Sub), lhs, rhs) => {
+            let lhs = affine(lhs, variables, env)?;
+            let rhs = affine(rhs, variables, env)?;
+            lhs.into_iter()
+                .zip(rhs)
+                .map(|(a, b)| if *op == Operator::Add { a + b } else { a - b })
+                .collect()
+        }
+        Expr::Binary(Operator::Mul, lhs, rhs) => {
+            let lhs = affine(lhs, variables, env)?;
+            let rhs = affine(rhs, variables, env)?;
+            if is_constant(&lhs) {
+                scaled(rhs, &lhs[n])
+            } else if is_constant(&rhs) {
+                scaled(lhs, &rhs[n])
+            } else {
+                return Err(nonlinear());
+            }
+        }
+        Expr::Binary(Operator::Div, lhs, rhs) => {
+            let rhs = affine(rhs, variables, env)?;
+            if !is_constant(&rhs) {
+                return Err(nonlinear());
+            }
+            if rhs[n].is_zero() {
+                bail!("Division by zero");
+            }
+            scaled(affine(lhs, variables, env)?, &rhs[n].recip())
+        }
+        _ => return Err(nonlinear()),
+    })
+}
+
+/// Split at the single relation operator.
+fn split_relation(input: &str) -> anyhow::Result<(Relation, &str, &str)> {
+    const OPERATORS: [(&str, Relation); 10] = [
+        ("<=", Relation::Le),
+        (">=", Relation::Ge),
+        ("!=", Relation::Ne),
+        ("==", Relation::Eq),
+        ("≤", Relation::Le),
+        ("≥", Relation::Ge),
+        ("≠", Relation::Ne),
+        ("<", Relation::Lt),
+        (">", Relation::Gt),
+        ("=", Relation::Eq),
+    ];
+    let mut found = None;
+    let mut index = 0;
+    while index < input.len() {
+        let rest = &input[index..];
+        match OPERATORS
+            .iter()
+            .find(|(symbol, _)| rest.starts_with(symbol))
+        {
+            Some((symbol, relation)) => {
+                if found.is_some() {
+                    bail!("Expected a single relation such as `=` or `<`");
+                }
+                found = Some((*relation, index, symbol.len()));
+                index += symbol.len();
+            }
+            None => index += rest.chars().next().map_or(1, char::len_utf8),
+        }
+    }
+    let (relation, at, len) =
+        found.ok_or_else(|| anyhow!("Expected a relation such as `=`, `<` or `>=`"))?;
+    Ok((relation, &input[..at], &input[at + len..]))
+}
+
+/// Coefficients of `expr` as a polynomial in `var`, constant term first.
+/// Subexpressions free of `var` are evaluated in `env`.
+fn polynomial(expr: &Expr, var: &str, env: &Environment) -> anyhow::Result<Vec<BigDecimal>> {
+    if !depends_on(expr, var) {
+        return Ok(vec![expr.eval(env)?]);
+    }
+    let unsupported = || anyhow!("{} is not a polynomial in {}", expr, var);
+    let poly = match expr {
+        Expr::Var(_) => vec![BigDecimal::zero(), BigDecimal::one()],
+        Expr::Unary(op, operand) => {
+            let p = polynomial(operand, var, env)?;
+            match op {
+                Operator::UnaryAdd => p,
+                Operator::UnarySub => p.iter().map(|c| -c).collect(),
+                Operator::Square => multiply(&p, &p)?,
+                Operator::Cube => multiply(&multiply(&p, &p)?, &p)?,
+                Operator::Percent => scale(&p, &BigDecimal::from(100)),
+                _ => return Err(unsupported()),
+            }
+        }
+        Expr::Binary(op, lhs, rhs) => match op {
+            Operator::Add => combine(
+                &polynomial(lhs, var, env)?,
+                &polynomial(rhs, var, env)?,
+                |a, b| a + b,
+            ),
+            Operator::Sub => combine(
+                &polynomial(lhs, var, env)?,
+                &polynomial(rhs, var, env)?,
+                |a, b| a - b,
+            ),
+            Operator::Mul => multiply(&polynomial(lhs, var, env)?, &polynomial(rhs, var, env)?)?,
+            Operator::Div if !depends_on(rhs, var) => {
+                let divisor = rhs.eval(env)?;
+                if divisor.is_zero() {
+                    bail!("Division by zero");
+                }
+                scale(&polynomial(lhs, var, env)?, &divisor)
+            }
+            Operator::Pow if !depends_on(rhs, var) => {
+                let exponent = rhs.eval(env)?;
+                let exponent = exponent
+                    .is_integer()
+                    .then(|| exponent.to_usize())
+                    .flatten()
+                    .filter(|&n| n <= MAX_INTERMEDIATE_DEGREE)
+                    .ok_or_else(unsupported)?;
+                let base = polynomial(lhs, var, env)?;
+                let mut power = vec![BigDecimal::one()];
+                for _ in 0..exponent {
+                    power = multiply(&power, &base)?;
+                }
+                power
+            }
+            _ => return Err(unsupported()),
+        },
+        _ => return Err(unsupported()),
+    };
+    Ok(trim(poly))
+}
+
+fn combine(
+    a: &[BigDecimal],
+    b: &[BigDecimal],
+    op: impl Fn(&BigDecimal, &BigDecimal) -> BigDecimal,
+) -> Vec<BigDecimal> {
+    let zero = BigDecimal::zero();
+    (0..a.len().max(b.len()))
+        .map(|i| op(a.get(i).unwrap_or(&zero), b.get(i).unwrap_or(&zero)))
+        .collect()
+}
+
+fn multiply(a: &[BigDecimal], b: &[BigDecimal]) -> anyhow::Result<Vec<BigDecimal>> {
+    let (a, b) = (trim(a.to_vec()), trim(b.to_vec()));
+    let degree = a.len() + b.len() - 2;
+    if degree > MAX_INTERMEDIATE_DEGREE {
+        bail!("Only linear and quadratic relations are supported");
+    }
+    let mut product = vec![BigDecimal::zero(); degree + 1];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            product[i + j] += x * y;
+        }
+    }
+    Ok(product)
+}
+
+/// Divide every coefficient by `divisor`.
+fn scale(p: &[BigDecimal], divisor: &BigDecimal) -> Vec<BigDecimal> {
+    p.iter()
+        .map(|c| divide(c, divisor, working_digits()))
+        .collect()
+}
+
+/// Drop zero leading coefficients, keeping at least the constant term.
+fn trim(mut p: Vec<BigDecimal>) -> Vec<BigDecimal> {
+    while p.len() > 1 && p.last().is_some_and(Zero::is_zero) {
+        p.pop();
+    }
+    if p.is_empty() {
+        p.push(BigDecimal::zero());
+    }
+    p
+}
+
+fn evaluate(p: &[BigDecimal], x: &BigDecimal) -> BigDecimal {
+    p.iter()
+        .rev()
+        .fold(BigDecimal::zero(), |acc, c| acc * x + c)
+}
+
+/// Real roots in increasing order, rounded to the configured precision.
+fn roots(p: &[BigDecimal]) -> Vec<BigDecimal> {
+    let digits = working_digits();
+    let finish = |root: BigDecimal| round_to(root, limits().precision).normalized();
+    match p {
+        [c, b, a] => {
+            let discriminant = b * b - BigDecimal::from(4) * a * c;
+            let twice = a * BigDecimal::from(2);
+            if discriminant.is_negative() {
+                return Vec::new();
+            }
+            if discriminant.is_zero() {
+                return vec![finish(divide(&-b, &twice, digits))];
+            }
+            let root = super::precision::nth_root(&discriminant, 2, digits);
+            let mut roots = vec![
+                finish(divide(&(-b - &root), &twice, digits)),
+                finish(divide(&(-b + &root), &twice, digits)),
+            ];
+            roots.sort();
+            roots
+        }
+        [c, b] => vec![finish(divide(&-c, b, digits))],
+        _ => Vec::new(),
+    }
+}
+
+/// Where `p relation 0` holds, from the sign of `p` between and at its roots.
+fn intervals(p: &[BigDecimal], roots: &[BigDecimal], relation: Relation) -> Vec<Interval> {
+    // Regions between the roots, each with a point inside to test
+    let one = BigDecimal::one();
+    let region_holds = |index: usize| {
+        let probe = match (index.checked_sub(1).map(|i| &roots[i]), roots.get(index)) {
+            (None, None) => BigDecimal::zero(),
+            (None, Some(upper)) => upper - &one,
+            (Some(lower), None) => lower + &one,
+            (Some(lower), Some(upper)) => (lower + upper) / BigDecimal::from(2),
+        };
+        relation.holds(&evaluate(p, &probe))
+    };
+    let root_holds = relation.holds(&BigDecimal::zero());
+
+    let mut intervals: Vec<Interval> = Vec::new();
+    let mut open: Option<(Option<BigDecimal>, bool)> = None;
+    for index in 0..=roots.len() {
+        let lower = index.checked_sub(1).map(|i| roots[i].clone());
+        if region_holds(index) {
+            open.get_or_insert((lower, false));
+        } else if let Some((start, start_closed)) = open.take() {
+            // The run ends at the root opening this region, kept only if it holds
+            intervals.push(Interval {
+                lower: start,
+                upper: lower,
+                lower_closed: start_closed,
+                upper_closed: root_holds,
+            });
+        }
+        let Some(root) = roots.get(index) else {
+            break;
+        };
+        match (&open, root_holds) {
+            (Some(_), true) => {}
+            (Some(_), false) => {
+                let (start, start_closed) = open.take().unwrap();
+                intervals.push(Interval {
+                    lower: start,
+                    upper: Some(root.clone()),
+                    lower_closed: start_closed,
+                    upper_closed: false,
+                });
+            }
+            (None, true) => open = Some((Some(root.clone()), true)),
+            (None, false) => {}
+        }
+    }
+    if let Some((start, start_closed)) = open {
+        intervals.push(Interval {
+            lower: start,
+            upper: None,
+            lower_closed: start_closed,
+            upper_closed: false,
+        });
+    }
+    intervals
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notation(input: &str) -> String {
+        solve(input, None, &Environment::new(), &EvalContext::default())
+            .unwrap()
+            .notation()
+    }
+
+    #[test]
+    fn test_solve() {
+        let cases = [
+            ("x^2 - 4 > 0", "(-inf, -2) ∪ (2, inf)"),
+            ("x^2 - 4 <= 0", "[-2, 2]"),
+            ("x^2 - 4 = 0", "{-2, 2}"),
+            ("x^2 - 4 != 0", "(-inf, -2) ∪ (-2, 2) ∪ (2, inf)"),
+            ("2 * x + 1 = 7", "{3}"),
+            ("3 - x >= 2 * x", "(-inf, 1]"),
+            ("x / 4 < 1.5", "(-inf, 6)"),
+            ("x^2 >= 0", "(-inf, inf)"),
+            ("x^2 > 0", "(-inf, 0) ∪ (0, inf)"),
+            ("x^2 <= 0", "{0}"),
+            ("x^2 + 1 < 0", "∅"),
+            ("(x - 1) * (x - 3) ≤ 0", "[1, 3]"),
+            ("(x + 1)^3 - x^3 = 1", "{-1, 0}"),
+            ("x + 1 > x", "(-inf, inf)"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(notation(input), expected, "solving {input}");
+        }
+
+        let irrational = solve(
+            "x^2 = 2",
+            None,
+            &Environment::new(),
+            &EvalContext::default(),
+        )
+        .unwrap();
+        assert_eq!(irrational.roots[1].to_string()[..8], *"1.414213");
+        assert_eq!(irrational.roots[0], -irrational.roots[1].clone());
+
+        let mut env = Environment::new();
+        env.set("k", BigDecimal::from(9)).unwrap();
+        assert_eq!(
+            solve("x^2 < k", None, &env, &EvalContext::default())
+                .unwrap()
+                .notation(),
+            "(-3, 3)"
+        );
+        assert_eq!(
+            solve("a * 2 = 8", Some("a"), &env, &EvalContext::default())
+                .unwrap()
+                .roots,
+            vec![BigDecimal::from(4)]
+        );
+
+        let solve = |input: &str| solve(input, None, &Environment::new(), &EvalContext::default());
+        assert!(
+            solve("x^3 > 1")
+                .unwrap_err()
+                .to_string()
+                .contains("degree 3")
+        );
+        assert!(solve("sin(x) = 0").is_err());
+        assert!(solve("1 / x > 2").is_err());
+        assert!(solve("x + y = 2").is_err());
+        assert!(solve("x < 1 < 2").is_err());
+        assert!(solve("x + 1").is_err());
+        assert_eq!(notation("3x - 2(x + 1) > 0"), "(2, inf)");
+        assert_eq!(notation("2e1x = 40"), "{2}");
+    }
+
+    #[test]
+    fn test_solve_system() {
+        let system = |equations: &[&str]| {
+            let equations: Vec<String> = equations.iter().map(|e| e.to_string()).collect();
+            solve_system(
+                &equations,
+                None,
+                &Environment::new(),
+                &EvalContext::default(),
+            )
+            .map(|solution| {
+                solution
+                    .into_iter()
+                    .map(|(name, value)| format!("{name}={value}"))
+                    .collect::<Vec<_>>()
+            })
+        };
+        assert_eq!(
+            system(&["2x + y = 5", "x - y = 1"]).unwrap(),
+            ["x=2", "y=1"]
+        );
+        assert_eq!(
+            system(&["x + y + z = 6", "2y + 5z = -4", "2x + 5y - z = 27"]).unwrap(),
+            ["x=5", "y=3", "z=-2"]
+        );
+        // Exact despite thirds in the coefficients
+        assert_eq!(
+            system(&["x / 3 + y / 3 = 1", "x - y = 1"]).unwrap(),
+            ["x=2", "y=1"]
+        );
+        assert_eq!(system(&["3x = 1"]).unwrap()[0][..8], *"x=0.3333");
+        assert_eq!(
+            system(&["0.1a + 0.2b = 0.3", "a = b"]).unwrap(),
+            ["a=1", "b=1"]
+        );
+
+        let mut env = Environment::new();
+        env.set("k", BigDecimal::from(4)).unwrap();
+        let equations = ["x + y = k".to_string(), "x - y = 2".to_string()];
+        assert_eq!(
+            solve_system(&equations, None, &env, &EvalContext::default()).unwrap(),
+            [
+                ("x".to_string(), BigDecimal::from(3)),
+                ("y".to_string(), BigDecimal::from(1))
+            ]
+        );
+
+        let error = |equations: &[&str]| system(equations).unwrap_err().to_string();
+        assert!(error(&["x + y = 1", "2x + 2y = 2"]).contains("no unique solution"));
+        assert!(error(&["x + y = 1"]).contains("as many equations"));
+        assert!(error(&["x * y = 1", "x = 2"]).contains("not linear"));
+        assert!(error(&["x < 1", "y = 2"]).contains("equations only"));
+    }
+}
@@ -0,0 +1,321 @@
+//! Spreadsheet formulas such as `=SUM(A1:A3) * 2` or `=ROUND(B2, 2)`,
+//! rewritten into calculator syntax. Cell references become variables named
+//! after the cell (`$A$1` reads `A1`), and functions without a calculator
+//! counterpart (`MIN`, `MAX`, `MOD`, `INT`) are evaluated on the spot.
+
+use super::{Environment, EvalContext, eval_with};
+use anyhow::{anyhow, bail};
+use bigdecimal::{BigDecimal, RoundingMode};
+use num_traits::{Signed, Zero};
+
+/// Largest number of cells a range such as `A1:C100` may cover.
+const MAX_RANGE_CELLS: usize = 10_000;
+
+/// The canonical name of a cell reference: column letters upper-cased and
+/// `$` anchors dropped, or `None` if `reference` is not a cell.
+pub fn cell_name(reference: &str) -> Option<String> {
+    let (column, row) = split_cell(reference)?;
+    Some(format!("{}{row}", column_label(column)))
+}
+
+/// Rewrite `formula` into a calculator expression. `cells` holds the cell
+/// values: blanks in ranges are skipped as spreadsheets do, and eagerly
+/// evaluated functions read their arguments from it, under `ctx`.
+pub fn translate(formula: &str, cells: &Environment, ctx: &EvalContext) -> anyhow::Result<String> {
+    let formula = formula.trim();
+    let formula = formula.strip_prefix('=').unwrap_or(formula);
+    Translator { cells, ctx }.expression(formula)
+}
+
+struct Translator<'a> {
+    cells: &'a Environment,
+    ctx: &'a EvalContext,
+}
+
+impl Translator<'_> {
+    fn expression(&self, input: &str) -> anyhow::Result<String> {
+        let chars: Vec<char> = input.chars().collect();
+        let mut out = String::new();
+        let mut i = 0;
+        while i < chars.len() {
+            let c = chars[i];
+            if c.is_ascii_alphabetic() || c == '_' || c == '$' {
+                let start = i;
+                while i < chars.len()
+                    && (chars[i].is_ascii_alphanumeric() || matches!(chars[i], '_' | '$' | '.'))
+                {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                let mut next = i;
+                while next < chars.len() && chars[next].is_whitespace() {
+                    next += 1;
+                }
+                if chars.get(next) == Some(&'(') {
+                    let (arguments, end) = arguments(&chars, next + 1)?;
+                    out.push_str(&self.call(&word, &arguments)?);
+                    i = end;
+                } else if chars.get(i) == Some(&':') {
+                    bail!("Ranges such as `{word}:...` are only allowed as function arguments");
+                } else if let Some(cell) = cell_name(&word) {
+                    out.push_str(&cell);
+                } else {
+                    out.push_str(match word.to_ascii_uppercase().as_str() {
+                        "TRUE" => "1",
+                        "FALSE" => "0",
+                        _ => &word,
+                    });
+                }
+                continue;
+            }
+            match c {
+                '"' => bail!("Text values are not supported"),
+                '&' => bail!("Text concatenation (`&`) is not supported"),
+                '<' | '>' | '=' => bail!("Comparisons are not supported"),
+                _ => out.push(c),
+            }
+            i += 1;
+        }
+        Ok(out)
+    }
+
+    /// Translated arguments, with ranges expanded into their non-blank cells.
+    fn values(&self, arguments: &[String]) -> anyhow::Result<Vec<String>> {
+        let mut values = Vec::new();
+        for argument in arguments {
+            match range(argument.trim())? {
+                Some(cells) => {
+                    values.extend(cells.into_iter().filter(|cell| self.cells.contains(cell)))
+                }
+                None => values.push(format!("({})", self.expression(argument.trim())?)),
+            }
+        }
+        Ok(values)
+    }
+
+    fn evaluate(&self, values: &[String]) -> anyhow::Result<Vec<BigDecimal>> {
+        values
+            .iter()
+            .map(|value| eval_with(value, self.cells, self.ctx))
+            .collect()
+    }
+
+    fn call(&self, name: &str, arguments: &[String]) -> anyhow::Result<String> {
+        let upper = name.to_ascii_uppercase();
+        let values = self.values(arguments)?;
+        let arity = |expected: &[usize]| {
+            if expected.contains(&values.len()) {
+                Ok(())
+            } else {
+                Err(anyhow!(
+                    "{upper} takes {} argument(s), got {}",
+                    expected
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join(" or "),
+                    values.len()
+                ))
+            }
+        };
+        Ok(match upper.as_str() {
+            "SUM" if values.is_empty() => "0".to_string(),
+            "SUM" => format!("({})", values.join(" + ")),
+            "PRODUCT" if values.is_empty() => "0".to_string(),
+            "PRODUCT" => format!("({})", values.join(" * ")),
+            "AVERAGE" if values.is_empty() => bail!("AVERAGE of no values"),
+            "AVERAGE" => format!("(({}) / {})", values.join(" + "), values.len()),
+            "COUNT" => values.len().to_string(),
+            "MIN" | "MAX" => {
+                let evaluated = self.evaluate(&values)?;
+                let extreme = if upper == "MIN" {
+                    evaluated.into_iter().min()
+                } else {
+                    evaluated.into_iter().max()
+                };
+                literal(&extreme.unwrap_or_default())
+            }
+            "ROUND" => {
+                arity(&[1, 2])?;
+                format!("round_half_up({})", values.join(", "))
+            }
+            "POWER" => {
+                arity(&[2])?;
+                format!("({} ^ {})", values[0], values[1])
+            }
+            "MOD" => {
+                arity(&[2])?;
+                let [n, d] = <[BigDecimal; 2]>::try_from(self.evaluate(&values)?)
+                    .map_err(|_| anyhow!("MOD takes 2 arguments"))?;
+                if d.is_zero() {
+                    bail!("MOD by zero");
+                }
+                // The remainder takes the sign of the divisor
+                let mut remainder = &n % &d;
+                if !remainder.is_zero() && remainder.is_negative() != d.is_negative() {
+                    remainder += &d;
+                }
+                literal(&remainder)
+            }
+            "INT" => {
+                arity(&[1])?;
+                let value = self.evaluate(&values)?.remove(0);
+                literal(&value.with_scale_round(0, RoundingMode::Floor))
+            }
+            "PI" => {
+                arity(&[0])?;
+                "pi".to_string()
+            }
+            "ABS" | "SQRT" | "EXP" | "LN" | "SIN" | "COS" | "TAN" => {
+                arity(&[1])?;
+                format!("{}{}", upper.to_ascii_lowercase(), values[0])
+            }
+            _ => bail!("Unsupported spreadsheet function: {name}"),
+        })
+    }
+}
+
+/// A number as calculator input, parenthesized so a negative value stays one operand.
+fn literal(value: &BigDecimal) -> String {
+    format!("({value})")
+}
+
+/// The raw arguments of a call whose `(` precedes `start`, and the position
+/// after its `)`.
+fn arguments(chars: &[char], start: usize) -> anyhow::Result<(Vec<String>, usize)> {
+    let mut arguments = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0;
+    for (i, &c) in chars.iter().enumerate().skip(start) {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => {
+                if !current.trim().is_empty() || !arguments.is_empty() {
+                    arguments.push(current);
+                }
+                return Ok((arguments, i + 1));
+            }
+            ')' => depth -= 1,
+            ',' | ';' if depth == 0 => {
+                arguments.push(std::mem::take(&mut current));
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    bail!("Missing `)` in function call")
+}
+
+/// The cells of `A1:B2` in row-major order, or `None` if `input` is not a range.
+fn range(input: &str) -> anyhow::Result<Option<Vec<String>>> {
+    let Some(((c1, r1), (c2, r2))) = input
+        .split_once(':')
+        .and_then(|(from, to)| split_cell(from.trim()).zip(split_cell(to.trim())))
+    else {
+        return Ok(None);
+    };
+    let (columns, rows) = (c1.min(c2)..=c1.max(c2), r1.min(r2)..=r1.max(r2));
+    if columns.clone().count() * rows.clone().count() > MAX_RANGE_CELLS {
+        bail!("Range `{input}` covers more than {MAX_RANGE_CELLS} cells");
+    }
+    Ok(Some(
+        rows.flat_map(|row| {
+            columns
+                .clone()
+                .map(move |column| format!("{}{row}", column_label(column)))
+        })
+        .collect(),
+    ))
+}
+
+/// Column number (1 for `A`) and row of a cell reference such as `$B$12`.
+fn split_cell(reference: &str) -> Option<(u32, u32)> {
+    let reference = reference.strip_prefix('$').unwrap_or(reference);
+    let letters = reference
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(reference.len());
+    let (column, row) = reference.split_at(letters);
+    let row = row.strip_prefix('$').unwrap_or(row);
+    if column.is_empty()
+        || column.len() > 3
+        || row.is_empty()
+        || row.starts_with('0')
+        || !row.chars().all(|c| c.is_ascii_digit())
+    {
+        return None;
+    }
+    let column = column.chars().fold(0, |n, c| {
+        n * 26 + (c.to_ascii_uppercase() as u32 - 'A' as u32 + 1)
+    });
+    Some((column, row.parse().ok()?))
+}
+
+fn column_label(mut column: u32) -> String {
+    let mut label = Vec::new();
+    while column > 0 {
+        let digit = (column - 1) % 26;
+        label.push((b'A' + digit as u8) as char);
+        column = (column - 1) / 26;
+    }
+    label.iter().rev().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::eval_with;
+    use std::str::FromStr;
+
+    fn cells(values: &[(&str, &str)]) -> Environment {
+        let mut env = Environment::new();
+        for (name, value) in values {
+            env.set(name, BigDecimal::from_str(value).unwrap()).unwrap();
+        }
+        env
+    }
+
+    fn eval(formula: &str, env: &Environment) -> String {
+        eval_with(
+            &translate(formula, env, &EvalContext::default()).unwrap(),
+            env,
+            &EvalContext::default(),
+        )
+        .unwrap()
+        .to_string()
+    }
+
+    #[test]
+    fn test_cell_references() {
+        assert_eq!(cell_name("$b$12").as_deref(), Some("B12"));
+        assert_eq!(cell_name("AA1").as_deref(), Some("AA1"));
+        assert_eq!(cell_name("A0"), None);
+        assert_eq!(cell_name("sin"), None);
+        assert_eq!(column_label(28), "AB");
+        assert_eq!(
+            range("A1:B2").unwrap().unwrap(),
+            vec!["A1", "B1", "A2", "B2"]
+        );
+    }
+
+    #[test]
+    fn test_translate_formulas() {
+        let env = cells(&[("A1", "2"), ("A2", "3.5"), ("A4", "-4"), ("B1", "1.005")]);
+        assert_eq!(eval("=SUM(1,2,3)", &env), "6");
+        assert_eq!(eval("=SUM(A1:A4) * 2", &env), "3.0");
+        assert_eq!(eval("=AVERAGE(A1:A4)", &env), "0.5");
+        assert_eq!(eval("=COUNT(A1:A4)", &env), "3");
+        assert_eq!(eval("=MAX(A1:A4) - MIN(A1:A4)", &env), "7.5");
+        assert_eq!(eval("=ROUND(B1, 2)", &env), "1.01");
+        assert_eq!(eval("=POWER($A$1, 10) + ABS(A4)", &env), "1028");
+        assert_eq!(eval("=MOD(A4, 3)", &env), "2");
+        assert_eq!(eval("=INT(-A2)", &env), "-4");
+        assert_eq!(eval("=sum(a1, a2; 10%)", &env), "5.6");
+
+        assert!(translate("=A1:A2", &env, &EvalContext::default()).is_err());
+        assert!(translate("=IF(A1 > 1, 1, 0)", &env, &EvalContext::default()).is_err());
+        assert!(translate("=VLOOKUP(A1, B1:C3, 2)", &env, &EvalContext::default()).is_err());
+        assert!(translate("=SUM(A1", &env, &EvalContext::default()).is_err());
+        assert!(translate("=SUM(A1:B)", &env, &EvalContext::default()).is_err());
+    }
+}
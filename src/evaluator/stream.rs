@@ -0,0 +1,206 @@
+//! Incremental tokenizing of input that arrives in chunks, such as a chunked
+//! HTTP body, without holding the whole text. Only plain infix syntax is
+//! accepted: the rewriting passes (comments, LaTeX, compatibility syntax,
+//! units and conversions) need the complete input.
+
+use super::error::ParseError;
+use super::{Limits, Token, tokenize};
+use anyhow::bail;
+
+/// Longest stretch of input without a point the tokenizer can cut at, e.g. a
+/// single huge number literal.
+pub const MAX_PENDING_CHARS: usize = 64 * 1024;
+
+#[derive(Debug, Default)]
+pub struct StreamTokenizer {
+    /// Text not tokenized yet because a token may continue in the next chunk
+    pending: String,
+    /// Characters in `pending`
+    pending_chars: usize,
+    /// The last point `pending` can be cut at, in bytes and in characters
+    cut: (usize, usize),
+    /// Last non-whitespace character of `pending` and its character index,
+    /// which decides whether whitespace after it is a cut point
+    last_significant: Option<(usize, char)>,
+    /// Bytes of a UTF-8 sequence split across chunks
+    partial: Vec<u8>,
+    /// Characters tokenized so far, to report errors at their offset in the
+    /// whole input
+    offset: usize,
+    tokens: Vec<Token>,
+    /// Bounds the number literals
+    limits: Limits,
+}
+
+impl StreamTokenizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Tokenize what `chunk` completes, keeping back a possibly unfinished
+    /// last token. Each character is looked at once here, however the input
+    /// is split.
+    pub fn feed(&mut self, chunk: &[u8]) -> anyhow::Result<()> {
+        self.partial.extend_from_slice(chunk);
+        let valid = match std::str::from_utf8(&self.partial) {
+            Ok(text) => text.len(),
+            Err(err) if err.error_len().is_none() => err.valid_up_to(),
+            Err(_) => bail!(ParseError::new(
+                "Input is not valid UTF-8",
+                self.offset..self.offset + self.pending_chars
+            )),
+        };
+        let rest = self.partial.split_off(valid);
+        let text = String::from_utf8(std::mem::replace(&mut self.partial, rest))?;
+
+        for c in text.chars() {
+            self.pending.push(c);
+            self.pending_chars += 1;
+            if is_cut_after(c, self.last_significant.map(|(_, c)| c)) {
+                self.cut = (self.pending.len(), self.pending_chars);
+            } else if self.pending_chars - self.cut.1 > MAX_PENDING_CHARS {
+                bail!(ParseError::new(
+                    "Token is too long to stream",
+                    self.offset + self.cut.1..self.offset + self.pending_chars
+                ));
+            }
+            if !c.is_whitespace() {
+                self.last_significant = Some((self.pending_chars - 1, c));
+            }
+        }
+
+        let (bytes, chars) = std::mem::take(&mut self.cut);
+        if chars == 0 {
+            return Ok(());
+        }
+        let tail = self.pending.split_off(bytes);
+        let head = std::mem::replace(&mut self.pending, tail);
+        self.tokenize(&head, chars)?;
+        self.pending_chars -= chars;
+        self.last_significant = self
+            .last_significant
+            .and_then(|(index, c)| Some((index.checked_sub(chars)?, c)));
+        Ok(())
+    }
+
+    /// Tokens produced so far, in input order.
+    pub fn tokens(&self) -> &[Token] {
+        &self.tokens
+    }
+
+    /// Tokenize the rest once the input has ended.
+    pub fn finish(mut self) -> anyhow::Result<Vec<Token>> {
+        if !self.partial.is_empty() {
+            bail!(ParseError::new(
+                "Input ends inside a UTF-8 sequence",
+                self.offset..self.offset + 1
+            ));
+        }
+        let rest = std::mem::take(&mut self.pending);
+        self.tokenize(&rest, self.pending_chars)?;
+        Ok(self.tokens)
+    }
+
+    /// Tokenize `text` of `chars` characters, which follows what was
+    /// tokenized before.
+    fn tokenize(&mut self, text: &str, chars: usize) -> anyhow::Result<()> {
+        let offset = self.offset;
+        let tokens =
+            tokenize(text, &self.limits).map_err(|err| match err.downcast::<ParseError>() {
+                Ok(parse_error) => ParseError::new(
+                    parse_error.message,
+                    parse_error.span.start + offset..parse_error.span.end + offset,
+                )
+                .into(),
+                Err(err) => err,
+            })?;
+        self.tokens.extend(tokens);
+        self.offset += chars;
+        Ok(())
+    }
+}
+
+/// Whether the input can be cut after `c`, whose closest non-whitespace
+/// predecessor since the last cut is `before`: what comes before the cut must
+/// tokenize the same on its own as followed by the rest, so nothing before it
+/// may look past it. Identifiers look ahead across whitespace for a `(`, a
+/// `%` for what follows it, numbers and `/` or `!` at the next character.
+fn is_cut_after(c: char, before: Option<char>) -> bool {
+    match c {
+        '(' | ')' | ',' | '*' | '^' | '#' | '²' | '³' => true,
+        c if c.is_whitespace() => {
+            before.is_none_or(|c| !(c.is_alphanumeric() || matches!(c, '_' | '.' | '%')))
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn streamed(input: &str, chunk_size: usize) -> anyhow::Result<Vec<Token>> {
+        let mut tokenizer = StreamTokenizer::new();
+        for chunk in input.as_bytes().chunks(chunk_size) {
+            tokenizer.feed(chunk)?;
+        }
+        tokenizer.finish()
+    }
+
+    #[test]
+    fn test_chunking_does_not_change_tokens() {
+        let inputs = [
+            "sin (2) + 12.5e-3 * x1 - 50 % + 1",
+            "max(1, 2, 3) // 2 + 5!! - 7 % 3 + 2² * 3#",
+            "(1 + 2) * 3 ^ 2 + pi - value_2",
+        ];
+        for input in inputs {
+            let whole = tokenize(input, &Limits::DEFAULT);
+            for chunk_size in 1..=8 {
+                match (&whole, streamed(input, chunk_size)) {
+                    (Ok(whole), Ok(streamed)) => assert_eq!(whole, &streamed, "{input}"),
+                    (Err(whole), Err(streamed)) => {
+                        assert_eq!(whole.to_string(), streamed.to_string(), "{input}")
+                    }
+                    (whole, streamed) => panic!("{input}: {whole:?} vs {streamed:?}"),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_errors_keep_their_offset() {
+        let err = streamed("1 + 2 + $", 2).unwrap_err();
+        let span = err.downcast_ref::<ParseError>().unwrap().span;
+        assert_eq!((span.start, span.end), (8, 9));
+
+        assert!(streamed(&"9".repeat(MAX_PENDING_CHARS + 1), 4096).is_err());
+        assert!(StreamTokenizer::new().feed(&[0xff]).is_err());
+
+        let mut tokenizer = StreamTokenizer::new();
+        tokenizer
+            .feed("2 ×".as_bytes().split_last().unwrap().1)
+            .unwrap();
+        assert!(tokenizer.finish().is_err());
+    }
+
+    #[test]
+    fn test_long_uncut_stretches_stay_linear() {
+        // Whitespace after a number is no cut point, so all of it stays pending
+        let spaced = format!("1{}+ 2", " ".repeat(MAX_PENDING_CHARS - 2));
+        assert_eq!(
+            streamed(&spaced, 1).unwrap(),
+            tokenize("1 + 2", &Limits::DEFAULT).unwrap()
+        );
+        let err = streamed(&format!("1{}", " ".repeat(MAX_PENDING_CHARS + 1)), 1).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<ParseError>().unwrap().message,
+            "Token is too long to stream"
+        );
+    }
+}
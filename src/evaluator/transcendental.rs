@@ -0,0 +1,545 @@
+//! Sine, cosine, tangent, exponential and natural logarithm to any number of
+//! significant digits. Each function reduces its argument to a small range
+//! where a series converges quickly, and sums it in fixed-point integer
+//! arithmetic with enough extra digits to absorb every rounding error:
+//!
+//! - `exp(x) = exp(r) * 10^q` with `x = q ln(10) + r`, and `exp(r)` is
+//!   `exp(r / 2^8)` squared eight times
+//! - `ln(x) = 2 atanh((y - 1) / (y + 1)) - j ln(2) + e ln(10)` with
+//!   `x = m * 10^e` and `y = m * 2^j` within `[0.7, 1.42]`
+//! - `sin` and `cos` of `r = x - q pi/2` with `|r| <= pi/4`, swapped and
+//!   negated by the quadrant `q`. Degrees are reduced by multiples of 90
+//!   exactly, so `sin(180)` is 0 rather than a tiny residue.
+//!
+//! Every series term and every product is truncated, losing less than one
+//! unit of the last fixed-point digit. A series has fewer terms than the
+//! digits it is summed to, so [`working_scale`] carries the digit count of the
+//! target on top of [`GUARD_DIGITS`], which covers the reduction steps. Where
+//! the reduction cancels leading digits, as in `ln(1.000001)` or `sin(355)`,
+//! those digits are computed too. The tests check the results against values
+//! from MPFR at several precisions.
+
+use anyhow::bail;
+use bigdecimal::num_bigint::BigInt;
+use bigdecimal::{BigDecimal, RoundingMode};
+use num_integer::Integer;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+use std::num::NonZeroU64;
+use std::sync::Mutex;
+
+use super::limits::limits;
+use super::precision::{GUARD_DIGITS, divide};
+use super::{AngleMode, MathConst};
+
+/// `exp` sums its series at `r / 2^EXP_HALVINGS`, which then takes that
+/// many squarings; each doubles the relative error, so three digits more.
+const EXP_HALVINGS: u32 = 8;
+const EXP_HALVINGS_DIGITS: u64 = 3;
+
+/// A constant at the largest scale computed so far.
+type Cached = Mutex<Option<(u64, BigInt)>>;
+
+static PI: Cached = Mutex::new(None);
+static E: Cached = Mutex::new(None);
+static LN_2: Cached = Mutex::new(None);
+static LN_10: Cached = Mutex::new(None);
+
+/// `pi`, `tau`, `e` or `phi` to `digits` significant digits; `None` for the
+/// physical constants, whose values are exact or measured.
+pub fn math_constant(constant: MathConst, digits: u64) -> Option<BigDecimal> {
+    let fixed = Fixed::new(working_scale(digits));
+    let value = match constant {
+        MathConst::Pi => fixed.pi(),
+        MathConst::Tau => fixed.pi() * 2,
+        MathConst::E => fixed.e(),
+        // (1 + sqrt(5)) / 2, the root truncated like the series
+        MathConst::Phi => (&fixed.one + (&fixed.one * &fixed.one * 5u8).sqrt()) / 2,
+        _ => return None,
+    };
+    Some(significant(fixed.to_decimal(value), digits))
+}
+
+/// `e^x` to `digits` significant digits. Results too small for the scale
+/// limit lose their trailing digits, down to 0.
+pub fn exp(x: &BigDecimal, digits: u64) -> anyhow::Result<BigDecimal> {
+    if x.is_zero() {
+        return Ok(BigDecimal::one());
+    }
+    let limits = limits();
+    // Decimal exponent of the result
+    let exponent = match x.to_f64() {
+        Some(x) if x.is_finite() => x / std::f64::consts::LN_10,
+        _ if x.is_negative() => f64::NEG_INFINITY,
+        _ => f64::INFINITY,
+    };
+    if exponent > limits.max_digits as f64 {
+        bail!("Result out of range for exp");
+    }
+    if exponent < -(limits.max_scale as f64) - 1.0 {
+        return Ok(BigDecimal::zero());
+    }
+
+    let quotient = exponent.round() as i64;
+    let fixed = Fixed::new(
+        working_scale(digits) + digit_count(quotient.unsigned_abs()) + EXP_HALVINGS_DIGITS,
+    );
+    let reduced = fixed.decimal_to_fixed(x) - fixed.ln_10() * quotient;
+    let mut result = fixed.exp_series(&(reduced >> EXP_HALVINGS));
+    for _ in 0..EXP_HALVINGS {
+        result = fixed.mul(&result, &result);
+    }
+    let result = significant(
+        BigDecimal::new(result, fixed.scale as i64 - quotient),
+        digits,
+    );
+    if result.fractional_digit_count() > limits.max_scale {
+        return Ok(result.with_scale_round(limits.max_scale, RoundingMode::HalfEven));
+    }
+    Ok(result)
+}
+
+/// `ln(x)` to `digits` significant digits.
+pub fn ln(x: &BigDecimal, digits: u64) -> anyhow::Result<BigDecimal> {
+    if !x.is_positive() {
+        bail!("Logarithm of a non-positive number");
+    }
+    if x.is_one() {
+        return Ok(BigDecimal::zero());
+    }
+    let exponent = magnitude(x);
+    let (int, scale) = x.as_bigint_and_exponent();
+    let mantissa = BigDecimal::new(int, scale + exponent);
+    let doublings = (-mantissa.to_f64().unwrap_or(0.5).log2()).round() as u32;
+    // Leading digits of the result cancel out when x is close to 1
+    let cancelled = (1 - magnitude(&(x - BigDecimal::one()))).max(0) as u64;
+
+    let fixed =
+        Fixed::new(working_scale(digits) + cancelled + digit_count(exponent.unsigned_abs()));
+    let y = fixed.decimal_to_fixed(&mantissa) << doublings;
+    let z = fixed.div(&(&y - &fixed.one), &(&y + &fixed.one));
+    let result = fixed.atanh_series(&z) * 2 - fixed.ln_2() * doublings + fixed.ln_10() * exponent;
+    Ok(significant(fixed.to_decimal(result), digits))
+}
+
+/// `(sin(x), cos(x))` with `x` in `mode`, each to `digits` significant digits.
+pub fn sin_cos(x: &BigDecimal, mode: AngleMode, digits: u64) -> (BigDecimal, BigDecimal) {
+    let (sin, cos) = match mode {
+        AngleMode::Radians => reduce_radians(x, digits),
+        AngleMode::Degrees => reduce_degrees(x, digits),
+    };
+    (significant(sin, digits), significant(cos, digits))
+}
+
+/// `tan(x)` with `x` in `mode` to `digits` significant digits.
+pub fn tan(x: &BigDecimal, mode: AngleMode, digits: u64) -> anyhow::Result<BigDecimal> {
+    let (sin, cos) = sin_cos(x, mode, digits + 2);
+    if cos.is_zero() {
+        bail!("Tangent of an odd multiple of a right angle");
+    }
+    Ok(divide(&sin, &cos, digits))
+}
+
+/// The sine and cosine of `x` radians, by those of its remainder after
+/// multiples of pi/2, with at least `digits` significant digits.
+fn reduce_radians(x: &BigDecimal, digits: u64) -> (BigDecimal, BigDecimal) {
+    if x.is_zero() {
+        return (BigDecimal::zero(), BigDecimal::one());
+    }
+    // Each integer digit of x costs a digit of pi/2 in the reduction
+    let integer_digits = magnitude(x).max(0) as u64;
+    let mut cancelled = magnitude(x).min(0).unsigned_abs();
+    loop {
+        let fixed = Fixed::new(working_scale(digits) + integer_digits + cancelled);
+        let half_pi = fixed.pi() / 2;
+        let x = fixed.decimal_to_fixed(x);
+        let shifted: BigInt = &x * 2 + &half_pi;
+        let quadrant = shifted.div_floor(&(&half_pi * 2));
+        let reduced = x - &half_pi * &quadrant;
+        // Leading zeros of the remainder, recomputed at a larger scale
+        let zeros = fixed.scale.saturating_sub(digit_count_big(&reduced));
+        if zeros > cancelled {
+            cancelled = zeros + 1;
+            continue;
+        }
+        let (sin, cos) = rotate(&quadrant, fixed.sin_cos_series(&reduced));
+        return (fixed.to_decimal(sin), fixed.to_decimal(cos));
+    }
+}
+
+/// Like [`reduce_radians`] for `x` degrees, reduced by multiples of 90
+/// exactly, so the results at multiples of 90 are exact.
+fn reduce_degrees(x: &BigDecimal, digits: u64) -> (BigDecimal, BigDecimal) {
+    let right = BigDecimal::from(90);
+    let quadrant = floor_div(&(x + BigDecimal::from(45)), &right);
+    let reduced = x - &right * BigDecimal::from(quadrant.clone());
+    if reduced.is_zero() {
+        let (sin, cos) = rotate(&quadrant, (BigInt::zero(), BigInt::one()));
+        return (BigDecimal::from(sin), BigDecimal::from(cos));
+    }
+    let cancelled = magnitude(&reduced).min(0).unsigned_abs();
+    let fixed = Fixed::new(working_scale(digits) + cancelled);
+    let radians = fixed.decimal_to_fixed(&reduced) * fixed.pi() / (&fixed.one * 180);
+    let (sin, cos) = rotate(&quadrant, fixed.sin_cos_series(&radians));
+    (fixed.to_decimal(sin), fixed.to_decimal(cos))
+}
+
+/// The sine and cosine of `angle + quadrant * pi/2` from those of `angle`.
+fn rotate(quadrant: &BigInt, (sin, cos): (BigInt, BigInt)) -> (BigInt, BigInt) {
+    match quadrant.mod_floor(&BigInt::from(4)).to_u8() {
+        Some(0) => (sin, cos),
+        Some(1) => (cos, -sin),
+        Some(2) => (-sin, -cos),
+        _ => (-cos, sin),
+    }
+}
+
+/// Fixed-point numbers `n / 10^scale`, as plain integers `n`.
+struct Fixed {
+    scale: u64,
+    one: BigInt,
+}
+
+impl Fixed {
+    fn new(scale: u64) -> Self {
+        Self {
+            scale,
+            one: power_of_ten(scale),
+        }
+    }
+
+    /// `value` truncated to this scale.
+    fn decimal_to_fixed(&self, value: &BigDecimal) -> BigInt {
+        let (int, scale) = value.as_bigint_and_exponent();
+        let shift = self.scale as i64 - scale;
+        if shift >= 0 {
+            int * power_of_ten(shift as u64)
+        } else {
+            int / power_of_ten(shift.unsigned_abs())
+        }
+    }
+
+    fn to_decimal(&self, value: BigInt) -> BigDecimal {
+        BigDecimal::new(value, self.scale as i64)
+    }
+
+    fn mul(&self, a: &BigInt, b: &BigInt) -> BigInt {
+        a * b / &self.one
+    }
+
+    fn div(&self, a: &BigInt, b: &BigInt) -> BigInt {
+        a * &self.one / b
+    }
+
+    /// `exp(r)` for `|r| <= 1`.
+    fn exp_series(&self, r: &BigInt) -> BigInt {
+        let mut sum = self.one.clone();
+        let mut term = self.one.clone();
+        for k in 1u64.. {
+            term = self.mul(&term, r) / k;
+            if term.is_zero() {
+                break;
+            }
+            sum += &term;
+        }
+        sum
+    }
+
+    /// `(sin(r), cos(r))` for `|r| <= 1`.
+    fn sin_cos_series(&self, r: &BigInt) -> (BigInt, BigInt) {
+        let square = self.mul(r, r);
+        let (mut sin, mut sin_term) = (r.clone(), r.clone());
+        let (mut cos, mut cos_term) = (self.one.clone(), self.one.clone());
+        for k in 1u64.. {
+            sin_term = -self.mul(&sin_term, &square) / (2 * k * (2 * k + 1));
+            cos_term = -self.mul(&cos_term, &square) / ((2 * k - 1) * 2 * k);
+            if sin_term.is_zero() && cos_term.is_zero() {
+                break;
+            }
+            sin += &sin_term;
+            cos += &cos_term;
+        }
+        (sin, cos)
+    }
+
+    /// `atanh(z)` for `|z| <= 1/2`.
+    fn atanh_series(&self, z: &BigInt) -> BigInt {
+        let square = self.mul(z, z);
+        let mut power = z.clone();
+        let mut sum = z.clone();
+        for k in 1u64.. {
+            power = self.mul(&power, &square);
+            let term = &power / (2 * k + 1);
+            if term.is_zero() {
+                break;
+            }
+            sum += term;
+        }
+        sum
+    }
+
+    /// `atan(1/n)`, or `atanh(1/n)` when `hyperbolic`, for an integer `n > 1`.
+    fn arc_of_inverse(&self, n: u64, hyperbolic: bool) -> BigInt {
+        let square = BigInt::from(n) * n;
+        let mut power = &self.one / n;
+        let mut sum = power.clone();
+        for k in 1u64.. {
+            power /= &square;
+            let term = &power / (2 * k + 1);
+            if term.is_zero() {
+                break;
+            }
+            if hyperbolic || k % 2 == 0 {
+                sum += term;
+            } else {
+                sum -= term;
+            }
+        }
+        sum
+    }
+
+    /// Machin's formula, `pi = 16 atan(1/5) - 4 atan(1/239)`.
+    fn pi(&self) -> BigInt {
+        self.constant(&PI, |fixed| {
+            fixed.arc_of_inverse(5, false) * 16 - fixed.arc_of_inverse(239, false) * 4
+        })
+    }
+
+    /// `e = exp(1)`, summed directly.
+    fn e(&self) -> BigInt {
+        self.constant(&E, |fixed| fixed.exp_series(&fixed.one))
+    }
+
+    /// `ln(2) = 2 atanh(1/3)`.
+    fn ln_2(&self) -> BigInt {
+        self.constant(&LN_2, |fixed| fixed.arc_of_inverse(3, true) * 2)
+    }
+
+    /// `ln(10) = 3 ln(2) + ln(1.25)`, with `ln(1.25) = 2 atanh(1/9)`.
+    fn ln_10(&self) -> BigInt {
+        self.constant(&LN_10, |fixed| {
+            fixed.ln_2() * 3 + fixed.arc_of_inverse(9, true) * 2
+        })
+    }
+
+    /// The constant in `cache` at this scale, computed when only smaller
+    /// scales are cached.
+    fn constant(&self, cache: &Cached, compute: fn(&Fixed) -> BigInt) -> BigInt {
+        let mut cached = cache
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        match &*cached {
+            Some((scale, value)) if *scale >= self.scale => {
+                value / power_of_ten(scale - self.scale)
+            }
+            _ => {
+                let value = compute(self);
+                *cached = Some((self.scale, value.clone()));
+                value
+            }
+        }
+    }
+}
+
+/// Fractional digits that leave `digits` significant digits of a result of
+/// magnitude around 1 exact after the truncations in the series.
+fn working_scale(digits: u64) -> u64 {
+    digits + GUARD_DIGITS + digit_count(digits)
+}
+
+/// `e` with `value = m * 10^e` and `m` in `[0.1, 1)`.
+fn magnitude(value: &BigDecimal) -> i64 {
+    value.digits() as i64 - value.fractional_digit_count()
+}
+
+/// `value` rounded to `digits` significant digits, without trailing zeros
+/// after the point, so `sin(30)` in degrees is `0.5`.
+fn significant(value: BigDecimal, digits: u64) -> BigDecimal {
+    let rounded = if value.digits() > digits {
+        value.with_precision_round(
+            NonZeroU64::new(digits).unwrap_or(NonZeroU64::MIN),
+            RoundingMode::HalfEven,
+        )
+    } else {
+        value
+    };
+    let trimmed = rounded.normalized();
+    if trimmed.fractional_digit_count() < 0 && rounded.fractional_digit_count() >= 0 {
+        trimmed.with_scale(0)
+    } else {
+        trimmed
+    }
+}
+
+/// `floor(a / b)`, exactly at any magnitude.
+fn floor_div(a: &BigDecimal, b: &BigDecimal) -> BigInt {
+    let scale = a
+        .fractional_digit_count()
+        .max(b.fractional_digit_count())
+        .max(0);
+    let a = a.with_scale(scale).into_bigint_and_exponent().0;
+    let b = b.with_scale(scale).into_bigint_and_exponent().0;
+    a.div_floor(&b)
+}
+
+fn power_of_ten(exponent: u64) -> BigInt {
+    BigInt::from(10).pow(exponent as u32)
+}
+
+fn digit_count(n: u64) -> u64 {
+    n.checked_ilog10().map_or(1, |log| u64::from(log) + 1)
+}
+
+fn digit_count_big(n: &BigInt) -> u64 {
+    if n.is_zero() {
+        0
+    } else {
+        n.magnitude().to_string().len() as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::context::EvalContext;
+    use crate::evaluator::limits::Limits;
+    use crate::evaluator::{Environment, eval_with};
+
+    /// Values from MPFR (via mpmath) to 250 significant digits.
+    const REFERENCE: &[(&str, &str)] = &[
+        (
+            "sin(1)",
+            "0.8414709848078965066525023216302989996225630607983710656727517099919104043912396689486397435430526958543490379079206742932591189209918988811934103277292124094807919558267666069999077640119784087827325663474848028702986561570179624553948935729246701271",
+        ),
+        (
+            "cos(1)",
+            "0.5403023058681397174009366074429766037323104206179222276700972553811003947744717645179518560871830893435717311600300890978606337600216634564065122654173185847179711644744794942331179245513932543359435177567028925963757361543275496417544917751151312227",
+        ),
+        (
+            "tan(1)",
+            "1.557407724654902230506974807458360173087250772381520038383946605698861397151727289555099965202242983804633821411748166613323554618124558937606071684548904439293586043167147908036824613274706955597341640610775535247302506796850507041352385144917621482",
+        ),
+        (
+            "sin(-2.5)",
+            "-0.5984721441039564940518547021861622717035971715772235733026270326387442721927370750402114715138763507463332429731509553187935196988082350579412146448587293375894417940948481354409631495172643810065307590682955312015616888425585389656904821203655635404",
+        ),
+        (
+            "cos(355)",
+            "-0.9999999995456589801659358416927540811238249514999282447715512037283576368545459210802018721130394813237892038756374369604378085015366776989481906145490269067775885574091404156713655880984729951529051525590944030775206703866871099605179339257996367047",
+        ),
+        (
+            "sin(355)",
+            "-0.00003014435335948844921433028000865009959025580706632464910578984824067353836547271283531023670003403842808718948744012873078051689943218289095172994230636551951420900884690755357542589932894711826156227339094025459429892267370971102358089719669197030574",
+        ),
+        (
+            "sin(1000000)",
+            "-0.3499935021712929521176524867807714690614066053287162738570590546446412263954505050656668976688940081127331690567910649695709417662806315762409153772132640085410170441537335840691884387032676070160827547129533103896988566823626311123279069463271829161",
+        ),
+        (
+            "sin(1e-20)",
+            "0.000000000000000000009999999999999999999999999999999999999999833333333333333333333333333333333333333334166666666666666666666666666666666666666664682539682539682539682539682539682539682542438271604938271604938271604938271604938269099727433060766394099727433060766394099729",
+        ),
+        (
+            "tan(1.5707963267948966)",
+            "51998506188720270.66019474166122686847581154498651544960157915775355859630025894065786027476209868733496949071079822992287493159768214169323532212920888365013257968370184934942240939570135283447410416083894546283384352480201426316974934791266301339677",
+        ),
+        (
+            "exp(1)",
+            "2.718281828459045235360287471352662497757247093699959574966967627724076630353547594571382178525166427427466391932003059921817413596629043572900334295260595630738132328627943490763233829880753195251019011573834187930702154089149934884167509244761460668",
+        ),
+        (
+            "exp(-10)",
+            "0.00004539992976248485153559151556055061023791808886656496925907130565099942161430228165252500454594778232170805508968602849294519911724452038883718334770941456756099090921700736397018105950178390076296851778703090882436517154844872229365233241602050116826",
+        ),
+        (
+            "exp(100.5)",
+            "44319559098458954160107061979564816895899481.87063064907487691185492512593297152837165767697499127241305920052502900117259497630644679205548708424496754214874127638103094537020435523723653177526276035170378919790310566602031306613781182904134583855017",
+        ),
+        (
+            "exp(-0.001)",
+            "0.9990004998333749916680553571676559747023559023600820590520285111960868025895123627778154446029536485400381704730936503123941830603318018792512009786200202263130256288850111601648117460985499811016686547941670436417001744159712586433537274643251237535",
+        ),
+        (
+            "ln(2)",
+            "0.6931471805599453094172321214581765680755001343602552541206800094933936219696947156058633269964186875420014810205706857336855202357581305570326707516350759619307275708283714351903070386238916734711233501153644979552391204751726815749320651555247341395",
+        ),
+        (
+            "ln(10)",
+            "2.302585092994045684017991454684364207601101488628772976033327900967572609677352480235997205089598298341967784042286248633409525465082806756666287369098781689482907208325554680843799894826233198528393505308965377732628846163366222287698219886746543667",
+        ),
+        (
+            "ln(0.5)",
+            "-0.6931471805599453094172321214581765680755001343602552541206800094933936219696947156058633269964186875420014810205706857336855202357581305570326707516350759619307275708283714351903070386238916734711233501153644979552391204751726815749320651555247341395",
+        ),
+        (
+            "ln(1.000001)",
+            "0.0000009999995000003333330833335333331666668095236845239206348206350115439282107551336837052503717878719091953242281121537061166520328202006547005022518469865915161098426151164195210379068022896077685634383425952867313723524677970578004951613317925350455277",
+        ),
+        (
+            "ln(123456789)",
+            "18.63140176616801803319393334796320420971368184102040197518508994509221746725635177765611642019030269547847652876528432042028394803577154220196027349624727297820779768812069020236716388417634763202138009248987673241293797470738919660313394848878707352",
+        ),
+        (
+            "ln(1e-30)",
+            "-69.07755278982137052053974364053092622803304465886318928099983702902717829032057440707991615268794895025903352126858745900228576395248420269998862107296345068448721624976664042531399684478699595585180515926896133197886538490098666863094659660239631002",
+        ),
+    ];
+
+    fn at_precision(precision: u64) -> EvalContext {
+        EvalContext::new(Limits {
+            precision,
+            ..Limits::DEFAULT
+        })
+    }
+
+    #[test]
+    fn test_matches_mpfr_at_several_precisions() {
+        for precision in [20, 50, 100, 200] {
+            for (input, expected) in REFERENCE {
+                let expected: BigDecimal = expected.parse().unwrap();
+                let value = eval_with(input, &Environment::new(), &at_precision(precision))
+                    .unwrap_or_else(|err| panic!("{input}: {err}"));
+                // Every digit shown is correctly rounded, and at least
+                // `precision` places are shown
+                assert_eq!(
+                    value,
+                    expected
+                        .with_scale_round(value.fractional_digit_count(), RoundingMode::HalfEven),
+                    "{input} at {precision} digits"
+                );
+                assert!(
+                    value.digits() as i64 >= precision as i64 + magnitude(&value).min(0),
+                    "{input} at {precision} digits: {value}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_degrees_are_reduced_exactly() {
+        let mut env = Environment::new();
+        env.set_angle_mode(AngleMode::Degrees);
+        let eval = |input: &str| eval_with(input, &env, &EvalContext::default()).unwrap();
+        assert_eq!(eval("sin(180)"), BigDecimal::zero());
+        assert_eq!(eval("cos(-270)"), BigDecimal::zero());
+        assert_eq!(eval("sin(450)"), BigDecimal::one());
+        assert_eq!(eval("cos(1e30 * 90)"), BigDecimal::one());
+        assert_eq!(eval("sin(30)"), "0.5".parse::<BigDecimal>().unwrap());
+        // MPFR's cos(pi / 180)
+        let expected: BigDecimal = "0.99984769515639123915701155881391485169274031058318593965832071451153918110333721539729939528811034549948248371".parse().unwrap();
+        assert_eq!(significant(eval("cos(1)"), 100), significant(expected, 100));
+        assert!(eval_with("tan(90)", &env, &EvalContext::default()).is_err());
+    }
+
+    #[test]
+    fn test_exp_range() {
+        assert!(eval_with("exp(1e6)", &Environment::new(), &EvalContext::default()).is_err());
+        assert_eq!(
+            eval_with("exp(-1e6)", &Environment::new(), &EvalContext::default()).unwrap(),
+            BigDecimal::zero()
+        );
+        assert_eq!(
+            eval_with("ln(exp(25))", &Environment::new(), &EvalContext::default()).unwrap(),
+            BigDecimal::from(25)
+        );
+    }
+}
@@ -0,0 +1,121 @@
+//! First-order propagation of constant uncertainties through an expression.
+
+use bigdecimal::{BigDecimal, RoundingMode};
+use num_traits::Zero;
+use std::num::NonZeroU64;
+
+use super::error::DepthExceeded;
+use super::limits::DepthBudget;
+use super::{Environment, Expr, MathConst};
+
+/// Significant digits kept in a propagated uncertainty.
+const UNCERTAINTY_DIGITS: u64 = 2;
+
+/// Standard uncertainty of `expr` from the uncertain constants it uses, treating
+/// constants as independent; `None` when the result is exact. Each constant is
+/// shifted by its uncertainty in turn and the changes are added in quadrature.
+pub fn propagate(expr: &Expr, env: &Environment) -> anyhow::Result<Option<BigDecimal>> {
+    let mut constants = Vec::new();
+    collect(expr, &mut constants, DepthBudget::from_limits())?;
+    if constants.is_empty() {
+        return Ok(None);
+    }
+    let value = expr.eval(env)?;
+    let mut variance = BigDecimal::zero();
+    for constant in constants {
+        let sigma = constant.uncertainty().unwrap_or_default();
+        let shifted = BigDecimal::from(constant) + sigma;
+        let delta =
+            substitute(expr, constant, &shifted, DepthBudget::from_limits())?.eval(env)? - &value;
+        variance += &delta * &delta;
+    }
+    let sigma = variance.sqrt().unwrap_or_default();
+    Ok(Some(sigma.with_precision_round(
+        NonZeroU64::new(UNCERTAINTY_DIGITS).unwrap(),
+        RoundingMode::HalfUp,
+    )))
+}
+
+fn collect(
+    expr: &Expr,
+    out: &mut Vec<MathConst>,
+    budget: DepthBudget,
+) -> Result<(), DepthExceeded> {
+    let budget = budget.descend()?;
+    match expr {
+        Expr::Const(constant) if constant.uncertainty().is_some() && !out.contains(constant) => {
+            out.push(*constant)
+        }
+        Expr::Number(_) | Expr::Const(_) | Expr::Var(_) => {}
+        Expr::Unary(_, operand) => collect(operand, out, budget)?,
+        Expr::Binary(_, lhs, rhs) => {
+            collect(lhs, out, budget)?;
+            collect(rhs, out, budget)?;
+        }
+        Expr::Call(_, args) => {
+            for arg in args {
+                collect(arg, out, budget)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn substitute(
+    expr: &Expr,
+    constant: MathConst,
+    value: &BigDecimal,
+    budget: DepthBudget,
+) -> Result<Expr, DepthExceeded> {
+    let budget = budget.descend()?;
+    Ok(match expr {
+        Expr::Const(c) if *c == constant => Expr::Number(value.clone()),
+        Expr::Number(_) | Expr::Const(_) | Expr::Var(_) => expr.clone(),
+        Expr::Unary(op, operand) => {
+            Expr::Unary(*op, Box::new(substitute(operand, constant, value, budget)?))
+        }
+        Expr::Binary(op, lhs, rhs) => Expr::Binary(
+            *op,
+            Box::new(substitute(lhs, constant, value, budget)?),
+            Box::new(substitute(rhs, constant, value, budget)?),
+        ),
+        Expr::Call(func, args) => Expr::Call(
+            *func,
+            args.iter()
+                .map(|arg| substitute(arg, constant, value, budget))
+                .collect::<Result<_, _>>()?,
+        ),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::EvalContext;
+    use crate::evaluator::{eval_statements, parse};
+    use std::str::FromStr;
+
+    fn uncertainty(input: &str) -> Option<String> {
+        propagate(&parse(input).unwrap(), &Environment::new())
+            .unwrap()
+            .map(|sigma| sigma.to_scientific_notation())
+    }
+
+    #[test]
+    fn test_propagate() {
+        assert_eq!(uncertainty("kb"), None);
+        assert_eq!(uncertainty("h * c / ec"), None);
+        assert_eq!(uncertainty("g").as_deref(), Some("1.5e-15"));
+        assert_eq!(uncertainty("g * 1e11").as_deref(), Some("1.5e-4"));
+        // Both factors move together, doubling the relative uncertainty
+        assert_eq!(uncertainty("g * g").as_deref(), Some("2.0e-25"));
+
+        let mut env = Environment::new();
+        let evaluation =
+            eval_statements("m = 5.972e24; g * m", &mut env, &EvalContext::default()).unwrap();
+        assert_eq!(
+            evaluation.uncertainty(&EvalContext::default()).unwrap(),
+            Some(BigDecimal::from_str("9.0e9").unwrap())
+        );
+    }
+}
@@ -0,0 +1,272 @@
+//! Quantities written with units, such as `1.5 GiB + 300 MB` or `2h 45m + 90s`,
+//! rewritten to plain arithmetic in base units (bytes or seconds) before
+//! tokenizing. Adjacent quantities add up, `h:mm:ss` is a duration, and a
+//! trailing `in <unit>` or `to <unit>` converts the result, e.g. `2 GiB in MB`.
+
+use anyhow::bail;
+use bigdecimal::BigDecimal;
+use bigdecimal::num_bigint::BigInt;
+use num_traits::Pow;
+
+use super::{DataUnits, scan_number};
+
+const PREFIXES: [char; 6] = ['K', 'M', 'G', 'T', 'P', 'E'];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dimension {
+    /// Bytes
+    Data,
+    /// Seconds
+    Time,
+}
+
+/// Dimension and size of `unit` in base units. `KB`, `MB`... follow
+/// `data_units`, while `kB` is always 1000 bytes and the IEC units `KiB`,
+/// `MiB`... always powers of 1024.
+pub fn factor(unit: &str, data_units: DataUnits) -> Option<(Dimension, BigDecimal)> {
+    let seconds = |s: u32| Some((Dimension::Time, BigDecimal::from(s)));
+    let bytes =
+        |b: i64, scale: i64| Some((Dimension::Data, BigDecimal::new(BigInt::from(b), scale)));
+    match unit {
+        "B" | "byte" | "bytes" => return bytes(1, 0),
+        "bit" | "bits" => return bytes(125, 3),
+        "kB" => return bytes(1000, 0),
+        "ms" => return Some((Dimension::Time, BigDecimal::new(BigInt::from(1), 3))),
+        "s" | "sec" | "secs" | "second" | "seconds" => return seconds(1),
+        "m" | "min" | "mins" | "minute" | "minutes" => return seconds(60),
+        "h" | "hr" | "hrs" | "hour" | "hours" => return seconds(3600),
+        "d" | "day" | "days" => return seconds(86_400),
+        "w" | "week" | "weeks" => return seconds(604_800),
+        _ => {}
+    }
+    let mut chars = unit.chars();
+    let first = chars.next()?;
+    let power = PREFIXES.iter().position(|prefix| *prefix == first)? + 1;
+    let base = match (chars.as_str(), data_units) {
+        ("iB", _) | ("B", DataUnits::Binary) => 1024,
+        ("B", DataUnits::Decimal) => 1000,
+        _ => return None,
+    };
+    Some((
+        Dimension::Data,
+        BigDecimal::from(Pow::pow(BigInt::from(base), power)),
+    ))
+}
+
+/// The rewritten input and the dimension of its quantities, if it has any.
+/// A conversion leaves the result in the target unit, so it has no dimension.
+pub fn to_infix(input: &str, data_units: DataUnits) -> anyhow::Result<(String, Option<Dimension>)> {
+    let mut words = input.trim_end().rsplitn(3, char::is_whitespace);
+    if let (Some(unit), Some("in" | "to"), Some(rest)) = (words.next(), words.next(), words.next())
+        && let Some((target, factor)) = factor(unit, data_units)
+    {
+        let (rewritten, dimension) = quantities(rest, data_units)?;
+        if dimension.is_some_and(|dimension| dimension != target) {
+            bail!(
+                "Cannot convert to {}: the quantities have a different dimension",
+                unit
+            );
+        }
+        return Ok((format!("({}) / {}", rewritten, factor), None));
+    }
+    quantities(input, data_units)
+}
+
+/// Dimension of the result of `input` when it computes with quantities and
+/// does not convert them, e.g. [`Dimension::Time`] for `2h 45m * 2`.
+pub fn dimension(input: &str) -> Option<Dimension> {
+    to_infix(input, DataUnits::default()).ok()?.1
+}
+
+fn quantities(input: &str, data_units: DataUnits) -> anyhow::Result<(String, Option<Dimension>)> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut out = String::with_capacity(input.len());
+    let mut dimension = None;
+    let mut pos = 0;
+    while let Some(&c) = chars.get(pos) {
+        let start = pos;
+        if c.is_ascii_alphabetic() || c == '_' {
+            pos = identifier_end(&chars, pos);
+        } else if is_number_start(&chars, pos) {
+            let mut terms = Vec::new();
+            let mut end = pos;
+            while let Some((term, term_dimension, term_end)) = quantity(&chars, end, data_units) {
+                if dimension.is_some_and(|d| d != term_dimension) {
+                    bail!("Cannot combine data sizes and durations");
+                }
+                dimension = Some(term_dimension);
+                terms.push(term);
+                end = skip_blanks(&chars, term_end);
+                if !is_number_start(&chars, end) {
+                    end = term_end;
+                    break;
+                }
+            }
+            if terms.is_empty() {
+                // Malformed numbers are copied for the tokenizer to report
+                pos = scan_number(&chars, pos).unwrap_or(chars.len());
+            } else {
+                out.push_str(&match terms.as_slice() {
+                    [term] => term.clone(),
+                    _ => format!("({})", terms.join(" + ")),
+                });
+                pos = end;
+                continue;
+            }
+        } else {
+            pos += 1;
+        }
+        out.extend(&chars[start..pos]);
+    }
+    Ok((out, dimension))
+}
+
+/// A number followed by a unit, or an `h:mm:ss` duration, starting at `pos`:
+/// its rewritten form, dimension and end.
+fn quantity(
+    chars: &[char],
+    pos: usize,
+    data_units: DataUnits,
+) -> Option<(String, Dimension, usize)> {
+    let end = scan_number(chars, pos).ok()?;
+    let number: String = chars[pos..end].iter().collect();
+    if chars.get(end) == Some(&':') {
+        return clock(chars, pos);
+    }
+    let unit_start = skip_blanks(chars, end);
+    if !chars.get(unit_start).is_some_and(char::is_ascii_alphabetic) {
+        return None;
+    }
+    let unit_end = identifier_end(chars, unit_start);
+    let unit: String = chars[unit_start..unit_end].iter().collect();
+    let (dimension, factor) = factor(&unit, data_units)?;
+    Some((format!("({} * {})", number, factor), dimension, unit_end))
+}
+
+/// `h:mm:ss` with optional fractional seconds, in seconds.
+fn clock(chars: &[char], pos: usize) -> Option<(String, Dimension, usize)> {
+    let digits_end = |from: usize| {
+        let mut end = from;
+        while chars.get(end).is_some_and(char::is_ascii_digit) {
+            end += 1;
+        }
+        end
+    };
+    let hours_end = digits_end(pos);
+    let minutes_end = digits_end(hours_end + 1);
+    if minutes_end - hours_end != 3 || chars.get(minutes_end) != Some(&':') {
+        return None;
+    }
+    let seconds_end = scan_number(chars, minutes_end + 1).ok()?;
+    let seconds: String = chars[minutes_end + 1..seconds_end].iter().collect();
+    if digits_end(minutes_end + 1) - minutes_end != 3 || seconds.contains(['e', 'E']) {
+        return None;
+    }
+    let field = |from: usize, to: usize| chars[from..to].iter().collect::<String>();
+    let rewritten = format!(
+        "({} * 3600 + {} * 60 + {})",
+        field(pos, hours_end),
+        field(hours_end + 1, minutes_end),
+        seconds
+    );
+    Some((rewritten, Dimension::Time, seconds_end))
+}
+
+fn is_number_start(chars: &[char], pos: usize) -> bool {
+    match chars.get(pos) {
+        Some(c) if c.is_ascii_digit() => true,
+        Some('.') => chars.get(pos + 1).is_some_and(char::is_ascii_digit),
+        _ => false,
+    }
+}
+
+fn skip_blanks(chars: &[char], mut pos: usize) -> usize {
+    while chars.get(pos).is_some_and(|c| *c == ' ' || *c == '\t') {
+        pos += 1;
+    }
+    pos
+}
+
+fn identifier_end(chars: &[char], mut pos: usize) -> usize {
+    while chars
+        .get(pos)
+        .is_some_and(|c| c.is_alphanumeric() || *c == '_')
+    {
+        pos += 1;
+    }
+    pos
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::EvalContext;
+    use crate::evaluator::{Environment, eval, eval_with};
+    use std::str::FromStr;
+
+    #[test]
+    fn test_data_sizes() {
+        let cases = [
+            ("1.5 GiB + 300 MB", "1910612736"),
+            ("2 KiB", "2048"),
+            ("2 KB + 1 kB", "3000"),
+            ("1 GB in MiB", "953.67431640625"),
+            ("1 GiB to MiB", "1024"),
+            ("8 bits + 1 byte", "2"),
+            ("3 * (2 MB + 500 kB) in MB", "7.5"),
+            ("1e3B", "1000"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(
+                eval(input).unwrap(),
+                BigDecimal::from_str(expected).unwrap(),
+                "evaluating {input}"
+            );
+        }
+
+        let mut binary = Environment::new();
+        binary.set_data_units(DataUnits::Binary);
+        assert_eq!(
+            eval_with("1 MB + 1 kB", &binary, &EvalContext::default()).unwrap(),
+            BigDecimal::from(1_049_576)
+        );
+        assert_eq!(
+            eval_with("1 MiB in MB", &binary, &EvalContext::default()).unwrap(),
+            BigDecimal::from(1)
+        );
+
+        let decimal = DataUnits::Decimal;
+        assert_eq!(to_infix("x2 B + log2", decimal).unwrap().0, "x2 B + log2");
+        assert_eq!(to_infix("2 pi", decimal).unwrap().0, "2 pi");
+        assert!(eval("2 XB").is_err());
+    }
+
+    #[test]
+    fn test_durations() {
+        let cases = [
+            ("2h 45m + 90s", "9990"),
+            ("1:30:00 * 2", "10800"),
+            ("2h 45m * 2", "19800"),
+            ("0:00:01.5 + 500 ms", "2"),
+            ("1 day - 1 hour in min", "1380"),
+            ("90 min to h", "1.5"),
+            ("1w in d", "7"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(
+                eval(input).unwrap(),
+                BigDecimal::from_str(expected).unwrap(),
+                "evaluating {input}"
+            );
+        }
+
+        let decimal = DataUnits::Decimal;
+        assert_eq!(dimension("2h + 1"), Some(Dimension::Time));
+        assert_eq!(dimension("2h in min"), None);
+        assert_eq!(dimension("2 + 1"), None);
+        assert!(to_infix("1 GB + 1 h", decimal).is_err());
+        assert!(to_infix("1 h in GB", decimal).is_err());
+        assert!(eval("1:3:00").is_err());
+        assert!(eval("1:30").is_err());
+    }
+}
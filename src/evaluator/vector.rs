@@ -0,0 +1,147 @@
+//! Vectors passed as flat argument lists: `norm(x, y, z)` takes one vector and
+//! `angle(x1, y1, x2, y2)` takes two of equal length, split down the middle.
+
+use anyhow::{anyhow, bail};
+use bigdecimal::BigDecimal;
+use num_traits::Zero;
+
+use super::Function;
+use super::precision::{divide, working_digits};
+
+/// The two vectors in the arguments of a binary vector function.
+pub fn halves(
+    func: Function,
+    args: &[BigDecimal],
+) -> anyhow::Result<(&[BigDecimal], &[BigDecimal])> {
+    if !args.len().is_multiple_of(2) {
+        bail!("{} takes two vectors of the same length", func);
+    }
+    Ok(args.split_at(args.len() / 2))
+}
+
+/// Euclidean norm of `v`. Values are shifted by the decimal exponent of the
+/// largest one before squaring, so tiny or huge inputs don't square past the
+/// scale limits; the shift is exact, keeping results like `hypot(3, 4)` exact.
+pub fn norm(v: &[BigDecimal]) -> anyhow::Result<BigDecimal> {
+    let Some(largest) = v.iter().map(BigDecimal::abs).max().filter(|m| !m.is_zero()) else {
+        return Ok(BigDecimal::zero());
+    };
+    let exponent = largest.digits() as i64 - largest.fractional_digit_count();
+    let shift = |value: &BigDecimal, by: i64| {
+        let (digits, scale) = value.as_bigint_and_exponent();
+        BigDecimal::new(digits, scale + by)
+    };
+    let sum_of_squares = v
+        .iter()
+        .map(|x| shift(x, exponent).square())
+        .fold(BigDecimal::zero(), |sum, square| sum + square);
+    let root = sum_of_squares
+        .sqrt()
+        .ok_or_else(|| anyhow!("Square root of a negative number"))?;
+    Ok(shift(&root.normalized(), -exponent))
+}
+
+pub fn dot(a: &[BigDecimal], b: &[BigDecimal]) -> BigDecimal {
+    a.iter()
+        .zip(b)
+        .fold(BigDecimal::zero(), |sum, (x, y)| sum + x * y)
+}
+
+/// `v` scaled to unit length.
+pub fn normalize(v: &[BigDecimal]) -> anyhow::Result<Vec<BigDecimal>> {
+    let length = norm(v)?;
+    if length.is_zero() {
+        bail!("Cannot normalize the zero vector");
+    }
+    Ok(v.iter()
+        .map(|x| divide(x, &length, working_digits()))
+        .collect())
+}
+
+/// The vector projection of `a` onto `b`.
+pub fn project(a: &[BigDecimal], b: &[BigDecimal]) -> anyhow::Result<Vec<BigDecimal>> {
+    let length_squared = dot(b, b);
+    if length_squared.is_zero() {
+        bail!("Cannot project onto the zero vector");
+    }
+    let factor = divide(&dot(a, b), &length_squared, working_digits());
+    Ok(b.iter().map(|x| x * &factor).collect())
+}
+
+/// The cosine of the angle between `a` and `b`, clamped to `[-1, 1]` against
+/// rounding in the norms.
+pub fn cos_angle(a: &[BigDecimal], b: &[BigDecimal]) -> anyhow::Result<BigDecimal> {
+    let lengths = norm(a)? * norm(b)?;
+    if lengths.is_zero() {
+        bail!("The angle with a zero vector is undefined");
+    }
+    let one = BigDecimal::from(1);
+    Ok(divide(&dot(a, b), &lengths, working_digits()).clamp(-one.clone(), one))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn vector(values: &[&str]) -> Vec<BigDecimal> {
+        values
+            .iter()
+            .map(|value| BigDecimal::from_str(value).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_vector_operations() {
+        assert_eq!(norm(&vector(&["0", "0"])).unwrap(), BigDecimal::zero());
+        assert_eq!(norm(&vector(&["-6", "8"])).unwrap(), BigDecimal::from(10));
+        assert_eq!(
+            norm(&vector(&["3e-70000", "4e-70000"])).unwrap(),
+            vector(&["5e-70000"])[0]
+        );
+        assert_eq!(
+            dot(&vector(&["1", "-2"]), &vector(&["2", "1"])),
+            BigDecimal::zero()
+        );
+        assert_eq!(
+            normalize(&vector(&["0", "-4"])).unwrap(),
+            vector(&["0", "-1"])
+        );
+        assert_eq!(
+            project(&vector(&["2", "3"]), &vector(&["4", "0"])).unwrap(),
+            vector(&["2", "0"])
+        );
+        assert_eq!(
+            cos_angle(&vector(&["1", "0"]), &vector(&["-3", "0"])).unwrap(),
+            BigDecimal::from(-1)
+        );
+        let both = vector(&["1", "2", "3", "4"]);
+        let (a, b) = halves(Function::Dot, &both).unwrap();
+        assert_eq!((a, b), (&vector(&["1", "2"])[..], &vector(&["3", "4"])[..]));
+
+        assert_eq!(
+            halves(Function::Dot, &vector(&["1", "2", "3"]))
+                .unwrap_err()
+                .to_string(),
+            "dot takes two vectors of the same length"
+        );
+        assert_eq!(
+            normalize(&vector(&["0", "0", "0"]))
+                .unwrap_err()
+                .to_string(),
+            "Cannot normalize the zero vector"
+        );
+        assert_eq!(
+            project(&vector(&["1", "1"]), &vector(&["0", "0"]))
+                .unwrap_err()
+                .to_string(),
+            "Cannot project onto the zero vector"
+        );
+        assert_eq!(
+            cos_angle(&vector(&["0", "0"]), &vector(&["1", "1"]))
+                .unwrap_err()
+                .to_string(),
+            "The angle with a zero vector is undefined"
+        );
+    }
+}
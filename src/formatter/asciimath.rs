@@ -0,0 +1,79 @@
+use bigdecimal::BigDecimal;
+
+use super::split_exponent;
+use crate::evaluator::ast::needs_parens;
+use crate::evaluator::{Expr, Operator};
+
+pub fn equation(expr: &Expr, result: &BigDecimal) -> String {
+    format!("{} = {}", expression(expr), number(result))
+}
+
+pub fn number(value: &BigDecimal) -> String {
+    match split_exponent(value) {
+        (mantissa, Some(exponent)) => format!("{mantissa} xx 10^({exponent})"),
+        (mantissa, None) => mantissa,
+    }
+}
+
+pub fn expression(expr: &Expr) -> String {
+    match expr {
+        Expr::Number(num) => number(num),
+        Expr::Const(math_const) => math_const.as_str().to_string(),
+        Expr::Var(name) => name.clone(),
+        Expr::Unary(Operator::UnaryAdd, operand) => expression(operand),
+        Expr::Unary(Operator::Square, base) => {
+            format!("{}^2", operand_text(Operator::Square, base, false))
+        }
+        Expr::Unary(Operator::Cube, base) => {
+            format!("{}^3", operand_text(Operator::Cube, base, false))
+        }
+        Expr::Unary(op, operand) if op.is_postfix() => {
+            format!("{}{}", operand_text(*op, operand, false), op.symbol())
+        }
+        Expr::Unary(op, operand) => format!("-{}", operand_text(*op, operand, true)),
+        Expr::Binary(Operator::Div, lhs, rhs) => {
+            format!("{}/{}", fraction_part(lhs), fraction_part(rhs))
+        }
+        Expr::Binary(Operator::FloorDiv, lhs, rhs) => {
+            format!("floor({}/{})", fraction_part(lhs), fraction_part(rhs))
+        }
+        Expr::Binary(Operator::Pow, base, exponent) => format!(
+            "{}^{}",
+            operand_text(Operator::Pow, base, false),
+            fraction_part(exponent)
+        ),
+        Expr::Binary(op, lhs, rhs) => {
+            let symbol = match op {
+                Operator::Mod => "mod".to_string(),
+                _ => op.to_string(),
+            };
+            format!(
+                "{} {symbol} {}",
+                operand_text(*op, lhs, false),
+                operand_text(*op, rhs, true)
+            )
+        }
+        Expr::Call(func, args) => {
+            let args: Vec<String> = args.iter().map(expression).collect();
+            format!("{}({})", func, args.join(", "))
+        }
+    }
+}
+
+fn operand_text(parent: Operator, child: &Expr, right: bool) -> String {
+    if needs_parens(parent, child, right) {
+        format!("({})", expression(child))
+    } else {
+        expression(child)
+    }
+}
+
+/// AsciiMath binds `/` and `^` to the adjacent simple term, so anything
+/// compound is bracketed; the brackets are dropped when rendered.
+fn fraction_part(expr: &Expr) -> String {
+    match expr {
+        Expr::Const(_) | Expr::Var(_) | Expr::Call(..) => expression(expr),
+        Expr::Number(num) if !num.to_string().contains(['-', 'e', 'E']) => expression(expr),
+        _ => format!("({})", expression(expr)),
+    }
+}
@@ -0,0 +1,165 @@
+use bigdecimal::BigDecimal;
+
+use super::split_exponent;
+use crate::evaluator::ast::needs_parens;
+use crate::evaluator::{Expr, Function, MathConst, Operator};
+
+const MATH_OPEN: &str = r#"<math xmlns="http://www.w3.org/1998/Math/MathML">"#;
+const MINUS: &str = "<mo>&#x2212;</mo>";
+
+pub fn equation(expr: &Expr, result: &BigDecimal) -> String {
+    let mut out = String::from(MATH_OPEN);
+    out.push_str("<mrow>");
+    write_expr(&mut out, expr);
+    out.push_str("<mo>=</mo>");
+    write_number(&mut out, result);
+    out.push_str("</mrow></math>");
+    out
+}
+
+pub fn number(value: &BigDecimal) -> String {
+    let mut out = String::from(MATH_OPEN);
+    write_number(&mut out, value);
+    out.push_str("</math>");
+    out
+}
+
+fn write_number(out: &mut String, value: &BigDecimal) {
+    let (mantissa, exponent) = split_exponent(value);
+    let mantissa = match mantissa.strip_prefix('-') {
+        Some(digits) => {
+            out.push_str(MINUS);
+            digits.to_string()
+        }
+        None => mantissa,
+    };
+    match exponent {
+        Some(exponent) => {
+            out.push_str(&format!(
+                "<mn>{mantissa}</mn><mo>&#x00D7;</mo><msup><mn>10</mn><mn>{exponent}</mn></msup>"
+            ));
+        }
+        None => out.push_str(&format!("<mn>{mantissa}</mn>")),
+    }
+}
+
+fn write_expr(out: &mut String, expr: &Expr) {
+    match expr {
+        Expr::Number(num) => write_number(out, num),
+        Expr::Const(math_const) => out.push_str(&format!("<mi>{}</mi>", const_symbol(*math_const))),
+        Expr::Var(name) => out.push_str(&format!("<mi>{name}</mi>")),
+        Expr::Unary(Operator::UnaryAdd, operand) => write_expr(out, operand),
+        Expr::Unary(op @ (Operator::Square | Operator::Cube), base) => {
+            out.push_str("<msup>");
+            if needs_parens(*op, base, false) {
+                write_parenthesized(out, base);
+            } else {
+                write_grouped(out, base);
+            }
+            let exponent = if *op == Operator::Square { 2 } else { 3 };
+            out.push_str(&format!("<mn>{exponent}</mn></msup>"));
+        }
+        Expr::Unary(op, operand) if op.is_postfix() => {
+            write_operand(out, *op, operand, false);
+            out.push_str(&format!("<mo>{}</mo>", op.symbol()));
+        }
+        Expr::Unary(op, operand) => {
+            out.push_str(MINUS);
+            write_operand(out, *op, operand, true);
+        }
+        Expr::Binary(Operator::Div, lhs, rhs) => {
+            out.push_str("<mfrac>");
+            write_grouped(out, lhs);
+            write_grouped(out, rhs);
+            out.push_str("</mfrac>");
+        }
+        Expr::Binary(Operator::FloorDiv, lhs, rhs) => {
+            out.push_str("<mrow><mo>&#x230A;</mo><mfrac>");
+            write_grouped(out, lhs);
+            write_grouped(out, rhs);
+            out.push_str("</mfrac><mo>&#x230B;</mo></mrow>");
+        }
+        Expr::Binary(Operator::Pow, base, exponent) => {
+            out.push_str("<msup>");
+            if needs_parens(Operator::Pow, base, false) {
+                write_parenthesized(out, base);
+            } else {
+                write_grouped(out, base);
+            }
+            write_grouped(out, exponent);
+            out.push_str("</msup>");
+        }
+        Expr::Binary(op, lhs, rhs) => {
+            write_operand(out, *op, lhs, false);
+            out.push_str(match op {
+                Operator::Add => "<mo>+</mo>",
+                Operator::Sub => MINUS,
+                Operator::Mul => "<mo>&#x00D7;</mo>",
+                _ => "<mo>mod</mo>",
+            });
+            write_operand(out, *op, rhs, true);
+        }
+        Expr::Call(Function::Sqrt, args) => {
+            out.push_str("<msqrt>");
+            write_args(out, args);
+            out.push_str("</msqrt>");
+        }
+        Expr::Call(Function::Abs, args) => {
+            out.push_str("<mrow><mo>|</mo>");
+            write_args(out, args);
+            out.push_str("<mo>|</mo></mrow>");
+        }
+        Expr::Call(func, args) => {
+            out.push_str(&format!(
+                "<mrow><mi>{func}</mi><mo>&#x2061;</mo><mrow><mo>(</mo>"
+            ));
+            write_args(out, args);
+            out.push_str("<mo>)</mo></mrow></mrow>");
+        }
+    }
+}
+
+fn write_operand(out: &mut String, parent: Operator, child: &Expr, right: bool) {
+    if needs_parens(parent, child, right) {
+        write_parenthesized(out, child);
+    } else {
+        write_expr(out, child);
+    }
+}
+
+fn write_parenthesized(out: &mut String, expr: &Expr) {
+    out.push_str("<mrow><mo>(</mo>");
+    write_expr(out, expr);
+    out.push_str("<mo>)</mo></mrow>");
+}
+
+/// Layout elements such as `mfrac` take exactly one child per slot.
+fn write_grouped(out: &mut String, expr: &Expr) {
+    match expr {
+        Expr::Number(num) if !num.to_string().contains(['-', 'e', 'E']) => write_number(out, num),
+        Expr::Const(_) | Expr::Var(_) => write_expr(out, expr),
+        _ => {
+            out.push_str("<mrow>");
+            write_expr(out, expr);
+            out.push_str("</mrow>");
+        }
+    }
+}
+
+fn write_args(out: &mut String, args: &[Expr]) {
+    for (idx, arg) in args.iter().enumerate() {
+        if idx > 0 {
+            out.push_str("<mo>,</mo>");
+        }
+        write_expr(out, arg);
+    }
+}
+
+fn const_symbol(math_const: MathConst) -> &'static str {
+    match math_const {
+        MathConst::Pi => "&#x03C0;",
+        MathConst::Tau => "&#x03C4;",
+        MathConst::Phi => "&#x03C6;",
+        other => other.as_str(),
+    }
+}
@@ -0,0 +1,96 @@
+use bigdecimal::BigDecimal;
+use serde::{Deserialize, Serialize};
+
+use crate::evaluator::Expr;
+
+pub mod asciimath;
+pub mod mathml;
+pub mod number;
+pub mod representations;
+
+/// Output representation selected by the `format` option.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    #[default]
+    Plain,
+    MathMl,
+    AsciiMath,
+}
+
+/// Render the evaluated expression and its result, e.g. `1/2 = 0.5`.
+/// Plain output is the bare result.
+pub fn render(expr: &Expr, result: &BigDecimal, format: Format) -> String {
+    match format {
+        Format::Plain => result.to_string(),
+        Format::MathMl => mathml::equation(expr, result),
+        Format::AsciiMath => asciimath::equation(expr, result),
+    }
+}
+
+/// Split a number into its mantissa and optional power-of-ten exponent.
+fn split_exponent(num: &BigDecimal) -> (String, Option<String>) {
+    let text = num.to_string();
+    if !text.contains(['e', 'E']) {
+        return (text, None);
+    }
+    let text = num.to_scientific_notation();
+    match text.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (
+            mantissa.to_string(),
+            Some(exponent.trim_start_matches('+').to_string()),
+        ),
+        None => (text, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::{eval, parse};
+
+    fn render_input(input: &str, format: Format) -> String {
+        render(&parse(input).unwrap(), &eval(input).unwrap(), format)
+    }
+
+    #[test]
+    fn test_render_mathml() {
+        assert_eq!(
+            render_input("1 / 2 + sqrt(4)", Format::MathMl),
+            concat!(
+                r#"<math xmlns="http://www.w3.org/1998/Math/MathML">"#,
+                "<mrow><mfrac><mn>1</mn><mn>2</mn></mfrac><mo>+</mo><msqrt><mn>4</mn></msqrt>",
+                "<mo>=</mo><mn>2.5</mn></mrow></math>"
+            )
+        );
+        assert_eq!(
+            render_input("(-2) ^ 2 * pi", Format::MathMl),
+            concat!(
+                r#"<math xmlns="http://www.w3.org/1998/Math/MathML">"#,
+                "<mrow><msup><mrow><mo>(</mo><mo>&#x2212;</mo><mn>2</mn><mo>)</mo></mrow><mn>2</mn></msup>",
+                "<mo>&#x00D7;</mo><mi>&#x03C0;</mi><mo>=</mo><mn>12.56637061435917295385057353311801153678867759750042328389977836923126562514483599451213930136846827</mn></mrow></math>"
+            )
+        );
+    }
+
+    #[test]
+    fn test_render_asciimath() {
+        assert_eq!(
+            render_input("(1 + 2) / 4 - abs(-3)", Format::AsciiMath),
+            "(1 + 2)/4 - abs(-3) = -2.25"
+        );
+        assert_eq!(
+            render_input("2 ^ (1 + 2) % 5", Format::AsciiMath),
+            "2^(1 + 2) mod 5 = 3"
+        );
+        assert_eq!(
+            render_input("6.02e23 * 2", Format::AsciiMath),
+            "6.02 xx 10^(23) * 2 = 1.204 xx 10^(24)"
+        );
+    }
+
+    #[test]
+    fn test_render_plain_is_result() {
+        assert_eq!(render_input("1 + 1", Format::Plain), "2");
+    }
+}
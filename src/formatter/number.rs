@@ -0,0 +1,236 @@
+//! Reformatting of already computed numbers: significant figures, notation,
+//! locale-specific separators and integer bases.
+
+use anyhow::{bail, ensure};
+use bigdecimal::num_bigint::BigInt;
+use bigdecimal::{BigDecimal, RoundingMode};
+use num_traits::{Signed, Zero};
+use serde::{Deserialize, Serialize};
+use std::num::NonZeroU64;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Notation {
+    #[default]
+    Plain,
+    Scientific,
+    Engineering,
+}
+
+/// Digit grouping and decimal separator conventions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    /// No grouping, `.` as the decimal separator
+    #[default]
+    None,
+    /// `1,234,567.89`
+    En,
+    /// `1.234.567,89`
+    De,
+    /// `1 234 567,89` with narrow no-break spaces
+    Fr,
+    /// `1'234'567.89`
+    Ch,
+    /// `12,34,567.89`
+    In,
+}
+
+impl Locale {
+    /// The group and decimal separators.
+    fn separators(self) -> (&'static str, &'static str) {
+        match self {
+            Locale::None => ("", "."),
+            Locale::En | Locale::In => (",", "."),
+            Locale::De => (".", ","),
+            Locale::Fr => ("\u{202f}", ","),
+            Locale::Ch => ("'", "."),
+        }
+    }
+
+    /// Insert group separators into a run of integer digits.
+    fn group(self, digits: &str) -> String {
+        let (separator, _) = self.separators();
+        if separator.is_empty() {
+            return digits.to_string();
+        }
+        // Indian grouping sets off the last three digits, then pairs
+        let (head, tail) = match self {
+            Locale::In if digits.len() > 3 => digits.split_at(digits.len() - 3),
+            _ => ("", digits),
+        };
+        let group_size = if head.is_empty() { 3 } else { 2 };
+        let grouped = |digits: &str, size: usize| {
+            let first = digits.len() % size;
+            let mut groups = Vec::new();
+            if first > 0 {
+                groups.push(&digits[..first]);
+            }
+            groups.extend(
+                digits.as_bytes()[first..]
+                    .chunks(size)
+                    .map(|chunk| std::str::from_utf8(chunk).unwrap_or_default()),
+            );
+            groups.join(separator)
+        };
+        if head.is_empty() {
+            grouped(tail, group_size)
+        } else {
+            format!("{}{}{}", grouped(head, group_size), separator, tail)
+        }
+    }
+}
+
+/// Options of the `format_number` tool.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NumberFormat {
+    /// Round to this many significant figures, padding with zeros if needed
+    pub sig_figs: Option<u64>,
+    pub notation: Notation,
+    pub locale: Locale,
+    /// Write an integer in base 2 to 36; 2, 8 and 16 get a `0b`, `0o` or `0x` prefix
+    pub base: Option<u32>,
+}
+
+pub fn format_number(value: &BigDecimal, format: &NumberFormat) -> anyhow::Result<String> {
+    if let Some(base) = format.base.filter(|base| *base != 10) {
+        ensure!((2..=36).contains(&base), "base must be between 2 and 36");
+        ensure!(
+            format.sig_figs.is_none() && format.notation == Notation::Plain,
+            "Significant figures and notations only apply to base 10"
+        );
+        if !value.is_integer() {
+            bail!("Only integers can be written in base {}", base);
+        }
+        return Ok(radix(
+            &value.with_scale(0).into_bigint_and_exponent().0,
+            base,
+        ));
+    }
+
+    let value = match format.sig_figs {
+        Some(0) => bail!("sig_figs must be at least 1"),
+        Some(figures) => significant(value, figures),
+        None => value.clone(),
+    };
+    let text = match format.notation {
+        Notation::Plain => value.to_plain_string(),
+        Notation::Scientific => value.to_scientific_notation(),
+        Notation::Engineering => value.to_engineering_notation(),
+    };
+    let (mantissa, exponent) = match text.split_once('e') {
+        Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+        None => (text.as_str(), None),
+    };
+    let (sign, unsigned) = match mantissa.strip_prefix('-') {
+        Some(unsigned) => ("-", unsigned),
+        None => ("", mantissa),
+    };
+    let (integer, fraction) = match unsigned.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (unsigned, None),
+    };
+
+    let mut out = format!("{sign}{}", format.locale.group(integer));
+    if let Some(fraction) = fraction {
+        out.push_str(format.locale.separators().1);
+        out.push_str(fraction);
+    }
+    if let Some(exponent) = exponent {
+        out.push('e');
+        out.push_str(exponent);
+    }
+    Ok(out)
+}
+
+/// `value` rounded to `figures` significant figures, keeping trailing zeros
+/// so that e.g. `2.5` to three figures reads `2.50`.
+fn significant(value: &BigDecimal, figures: u64) -> BigDecimal {
+    if value.is_zero() {
+        return BigDecimal::new(BigInt::zero(), figures as i64 - 1);
+    }
+    let rounded = value.with_precision_round(
+        NonZeroU64::new(figures).unwrap_or(NonZeroU64::MIN),
+        RoundingMode::HalfEven,
+    );
+    let missing = figures.saturating_sub(rounded.digits()) as i64;
+    rounded.with_scale(rounded.fractional_digit_count() + missing)
+}
+
+fn radix(n: &BigInt, base: u32) -> String {
+    let prefix = match base {
+        2 => "0b",
+        8 => "0o",
+        16 => "0x",
+        _ => "",
+    };
+    let sign = if n.is_negative() { "-" } else { "" };
+    format!("{sign}{prefix}{}", n.abs().to_str_radix(base))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn format(value: &str, format: NumberFormat) -> String {
+        format_number(&BigDecimal::from_str(value).unwrap(), &format).unwrap()
+    }
+
+    #[test]
+    fn test_format_number() {
+        let figures = |sig_figs| NumberFormat {
+            sig_figs: Some(sig_figs),
+            ..Default::default()
+        };
+        assert_eq!(format("3.14159", figures(3)), "3.14");
+        assert_eq!(format("2.5", figures(3)), "2.50");
+        assert_eq!(format("123456", figures(2)), "120000");
+        assert_eq!(format("0", figures(3)), "0.00");
+
+        let scientific = NumberFormat {
+            sig_figs: Some(3),
+            notation: Notation::Scientific,
+            ..Default::default()
+        };
+        assert_eq!(format("123456", scientific.clone()), "1.23e5");
+        let engineering = NumberFormat {
+            notation: Notation::Engineering,
+            ..Default::default()
+        };
+        assert_eq!(format("123456", engineering), "123.456e3");
+
+        let locale = |locale| NumberFormat {
+            locale,
+            ..Default::default()
+        };
+        assert_eq!(format("-1234567.891", locale(Locale::En)), "-1,234,567.891");
+        assert_eq!(format("1234567.891", locale(Locale::De)), "1.234.567,891");
+        assert_eq!(
+            format("1234567", locale(Locale::Fr)),
+            "1\u{202f}234\u{202f}567"
+        );
+        assert_eq!(format("1234567.5", locale(Locale::In)), "12,34,567.5");
+        assert_eq!(format("123", locale(Locale::In)), "123");
+        assert_eq!(format("1234.5", locale(Locale::None)), "1234.5");
+        let german_scientific = NumberFormat {
+            locale: Locale::De,
+            ..scientific
+        };
+        assert_eq!(format("0.00012345", german_scientific), "1,23e-4");
+
+        let base = |base| NumberFormat {
+            base: Some(base),
+            ..Default::default()
+        };
+        assert_eq!(format("255", base(16)), "0xff");
+        assert_eq!(format("-5", base(2)), "-0b101");
+        assert_eq!(format("35", base(36)), "z");
+        assert_eq!(format("1e3", base(8)), "0o1750");
+        let decimal = BigDecimal::from_str("2.5").unwrap();
+        assert!(format_number(&decimal, &base(2)).is_err());
+        assert!(format_number(&decimal, &base(37)).is_err());
+        assert!(format_number(&decimal, &figures(0)).is_err());
+    }
+}
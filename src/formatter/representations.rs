@@ -0,0 +1,166 @@
+use bigdecimal::num_bigint::BigInt;
+use bigdecimal::{BigDecimal, RoundingMode};
+use num_integer::Integer;
+use num_traits::{One, Signed, ToPrimitive};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+use crate::evaluator::conversions;
+use crate::evaluator::limits::limits;
+use crate::evaluator::number_theory::factorize;
+use crate::evaluator::rational;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Representation {
+    Decimal,
+    Scientific,
+    Engineering,
+    Fraction,
+    Hex,
+    Factors,
+    Duration,
+    Roman,
+}
+
+/// The requested representations of `value`, keyed by name. Representations
+/// that do not apply (a fraction of a rounded value, hex of a non-integer)
+/// are `null`.
+pub fn represent(value: &BigDecimal, kinds: &[Representation]) -> Map<String, Value> {
+    let mut out = Map::new();
+    for kind in kinds {
+        let rendered = match kind {
+            Representation::Decimal => json!(value.to_plain_string()),
+            Representation::Scientific => json!(value.normalized().to_scientific_notation()),
+            Representation::Engineering => json!(value.normalized().to_engineering_notation()),
+            Representation::Fraction => json!(fraction(value)),
+            Representation::Hex => json!(integer(value).map(|n| hex(&n))),
+            Representation::Factors => factors(value),
+            Representation::Duration => json!(duration(value)),
+            Representation::Roman => json!(conversions::to_roman(value).ok()),
+        };
+        let key = serde_json::to_value(kind)
+            .ok()
+            .and_then(|key| key.as_str().map(str::to_string))
+            .unwrap_or_default();
+        out.insert(key, rendered);
+    }
+    out
+}
+
+fn integer(value: &BigDecimal) -> Option<BigInt> {
+    value
+        .is_integer()
+        .then(|| value.with_scale(0).into_bigint_and_exponent().0)
+}
+
+/// Lowest-terms `numerator/denominator`, or `numerator` for integers. Values
+/// using the full working precision are assumed to be rounded (e.g. `1/3`),
+/// so no fraction is reported for them.
+pub fn fraction(value: &BigDecimal) -> Option<String> {
+    let value = value.normalized();
+    if value.fractional_digit_count() > 0 && value.digits() >= limits().precision {
+        return None;
+    }
+    let (numerator, denominator) = rational::exact(&value);
+    Some(if denominator.is_one() {
+        numerator.to_string()
+    } else {
+        format!("{numerator}/{denominator}")
+    })
+}
+
+/// Seconds as `h:mm:ss`, keeping any fraction of a second: `9990` is `2:46:30`.
+pub fn duration(seconds: &BigDecimal) -> String {
+    let whole = seconds.abs().with_scale_round(0, RoundingMode::Down);
+    let fraction = (seconds.abs() - &whole).normalized().to_plain_string();
+    let (minutes, secs) = whole
+        .into_bigint_and_exponent()
+        .0
+        .div_rem(&BigInt::from(60));
+    let (hours, minutes) = minutes.div_rem(&BigInt::from(60));
+    format!(
+        "{}{}:{:02}:{:02}{}",
+        if seconds.is_negative() { "-" } else { "" },
+        hours,
+        minutes,
+        secs,
+        fraction.strip_prefix('0').unwrap_or_default()
+    )
+}
+
+fn hex(n: &BigInt) -> String {
+    let digits = n.abs().to_str_radix(16);
+    if n.is_negative() {
+        format!("-0x{digits}")
+    } else {
+        format!("0x{digits}")
+    }
+}
+
+fn factors(value: &BigDecimal) -> Value {
+    let Some(n) = integer(value).and_then(|n| n.to_u64()).filter(|n| *n >= 2) else {
+        return Value::Null;
+    };
+    let factors = factorize(n);
+    let text = factors
+        .iter()
+        .map(|(prime, exponent)| match exponent {
+            1 => prime.to_string(),
+            _ => format!("{prime}^{exponent}"),
+        })
+        .collect::<Vec<_>>()
+        .join(" * ");
+    let factors: Vec<Value> = factors
+        .iter()
+        .map(|(prime, exponent)| json!({ "prime": prime, "exponent": exponent }))
+        .collect();
+    json!({ "factors": factors, "text": text })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::eval;
+
+    #[test]
+    fn test_representations() {
+        use Representation::*;
+        let all = [
+            Decimal,
+            Scientific,
+            Engineering,
+            Fraction,
+            Hex,
+            Factors,
+            Duration,
+            Roman,
+        ];
+
+        let out = represent(&eval("360").unwrap(), &all);
+        assert_eq!(out["decimal"], "360");
+        assert_eq!(out["scientific"], "3.6e2");
+        assert_eq!(out["engineering"], "360e0");
+        assert_eq!(out["fraction"], "360");
+        assert_eq!(out["hex"], "0x168");
+        assert_eq!(out["factors"]["text"], "2^3 * 3^2 * 5");
+        assert_eq!(out["duration"], "0:06:00");
+        assert_eq!(out["roman"], "CCCLX");
+
+        let out = represent(&eval("-0.375").unwrap(), &all);
+        assert_eq!(out["fraction"], "-3/8");
+        assert_eq!(out["hex"], Value::Null);
+        assert_eq!(out["factors"], Value::Null);
+        assert_eq!(out["roman"], Value::Null);
+
+        let out = represent(&eval("1 / 3").unwrap(), &[Fraction]);
+        assert_eq!(out["fraction"], Value::Null);
+        assert_eq!(represent(&eval("-255").unwrap(), &[Hex])["hex"], "-0xff");
+        assert_eq!(
+            represent(&eval("1.5e3").unwrap(), &[Fraction])["fraction"],
+            "1500"
+        );
+        assert_eq!(duration(&eval("2h 45m + 90s").unwrap()), "2:46:30");
+        assert_eq!(duration(&eval("-(100h + 1.25s)").unwrap()), "-100:00:01.25");
+    }
+}
@@ -0,0 +1,121 @@
+use crate::app_config::Auth;
+use crate::http_server::AppState;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use tracing::info;
+
+#[derive(Debug, Deserialize)]
+pub struct SetLogLevel {
+    /// Level (`debug`) or full filter directives (`calculator_mcp=trace,info`)
+    pub filter: String,
+}
+
+/// Operator endpoints. They are only mounted when API keys are configured,
+/// since without keys anyone who can reach the server could use them.
+pub fn router(auth: &Auth) -> Router<AppState> {
+    if auth.api_keys.is_empty() {
+        return Router::new();
+    }
+    Router::new().route("/admin/log-level", get(get_log_level).put(set_log_level))
+}
+
+async fn get_log_level(State(state): State<AppState>) -> Result<Json<Value>, (StatusCode, String)> {
+    let filter = state
+        .log_level
+        .current()
+        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+    Ok(Json(json!({ "filter": filter })))
+}
+
+async fn set_log_level(
+    State(state): State<AppState>,
+    Json(request): Json<SetLogLevel>,
+) -> Result<Json<Value>, (StatusCode, String)> {
+    state
+        .log_level
+        .set(&request.filter)
+        .map_err(|err| (StatusCode::BAD_REQUEST, err.to_string()))?;
+    info!("Log filter changed to `{}`", request.filter);
+    Ok(Json(json!({ "filter": request.filter })))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::app_config::{AppConfig, Logging};
+    use crate::http_server::HttpServer;
+    use crate::http_server::auth::API_KEY_HEADER;
+    use crate::logging;
+    use axum::body::{Body, to_bytes};
+    use axum::http::{Request, StatusCode, header};
+    use serde_json::{Value, json};
+    use serial_test::serial;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    fn router(api_keys: &[&str]) -> axum::Router {
+        let mut config = AppConfig::default();
+        config.auth.api_keys = api_keys.iter().map(|key| key.to_string()).collect();
+        let log_level = logging::detached(&Logging::default()).unwrap();
+        HttpServer::new(Arc::new(config), log_level)
+            .unwrap()
+            .router()
+    }
+
+    fn get_level() -> Request<Body> {
+        Request::get("/admin/log-level")
+            .header(API_KEY_HEADER, "secret")
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    async fn filter(response: axum::response::Response) -> Value {
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice::<Value>(&body).unwrap()["filter"].clone()
+    }
+
+    // Serial with tests setting RUST_LOG, which decides the initial filter
+    #[tokio::test]
+    #[serial]
+    async fn test_log_level_is_read_and_replaced() {
+        let router = router(&["secret"]);
+        let response = router.clone().oneshot(get_level()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(filter(response).await, "info");
+
+        let set = |filter: &str| {
+            Request::put("/admin/log-level")
+                .header(API_KEY_HEADER, "secret")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(json!({ "filter": filter }).to_string()))
+                .unwrap()
+        };
+        let response = router
+            .clone()
+            .oneshot(set("calculator_mcp=trace,warn"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = router.clone().oneshot(get_level()).await.unwrap();
+        assert_eq!(filter(response).await, "calculator_mcp=trace,warn");
+
+        let response = router.clone().oneshot(set("=[")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let anonymous = Request::put("/admin/log-level")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(json!({ "filter": "trace" }).to_string()))
+            .unwrap();
+        let response = router.oneshot(anonymous).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_not_mounted_without_api_keys() {
+        let response = router(&[]).oneshot(get_level()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,47 @@
+use crate::http_server::AppState;
+use axum::extract::{Request, State};
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Reject requests without a configured API key, given either as
+/// `Authorization: Bearer <key>` or `X-API-Key: <key>`. The liveness and
+/// readiness probes stay open to orchestrators.
+pub async fn require_api_key(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let keys = &state.config.auth.api_keys;
+    if keys.is_empty() || matches!(request.uri().path(), "/health" | "/ready") {
+        return next.run(request).await;
+    }
+
+    match presented_key(request.headers()) {
+        Some(presented) if keys.iter().any(|key| constant_time_eq(key, presented)) => {
+            next.run(request).await
+        }
+        _ => (StatusCode::UNAUTHORIZED, "Missing or invalid API key").into_response(),
+    }
+}
+
+pub fn presented_key(headers: &HeaderMap) -> Option<&str> {
+    if let Some(value) = headers.get(API_KEY_HEADER) {
+        return value.to_str().ok();
+    }
+    headers
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+}
+
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+            == 0
+}
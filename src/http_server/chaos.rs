@@ -0,0 +1,208 @@
+use crate::app_config::Chaos;
+use axum::body::Body;
+use axum::http::{HeaderValue, Request, Response, StatusCode, header};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::task::{Context, Poll};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tower::{Layer, Service};
+
+/// Marks responses the layer interfered with: `latency` or `error`.
+pub const FAULT_HEADER: &str = "x-chaos-fault";
+
+/// Delays or fails a configured share of requests before they reach the
+/// handlers, so integrators can exercise their clients' retries. Passes every
+/// request through untouched unless `chaos.enabled` is set.
+#[derive(Clone, Default)]
+pub struct ChaosLayer {
+    faults: Option<Arc<Faults>>,
+}
+
+struct Faults {
+    config: Chaos,
+    status: StatusCode,
+    /// xorshift64* state; quality is irrelevant, reproducibility is not
+    rng: AtomicU64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Fault {
+    Latency,
+    Error,
+}
+
+impl ChaosLayer {
+    pub fn new(config: &Chaos) -> anyhow::Result<Self> {
+        if !config.enabled {
+            return Ok(ChaosLayer::default());
+        }
+        for (name, rate) in [
+            ("latency_rate", config.latency_rate),
+            ("error_rate", config.error_rate),
+        ] {
+            if !(0.0..=1.0).contains(&rate) {
+                anyhow::bail!("chaos.{name} must be between 0 and 1, got {rate}");
+            }
+        }
+        let status = StatusCode::from_u16(config.error_status)?;
+        let seed = config.seed.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_nanos() as u64)
+                .unwrap_or_default()
+        });
+        Ok(ChaosLayer {
+            faults: Some(Arc::new(Faults {
+                config: config.clone(),
+                status,
+                // xorshift never leaves 0
+                rng: AtomicU64::new(seed | 1),
+            })),
+        })
+    }
+}
+
+impl Faults {
+    fn next_unit(&self) -> f64 {
+        let step = |mut x: u64| {
+            x ^= x >> 12;
+            x ^= x << 25;
+            x ^= x >> 27;
+            x
+        };
+        let previous = self
+            .rng
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |x| Some(step(x)))
+            .unwrap_or_else(|x| x);
+        let value = step(previous).wrapping_mul(0x2545_F491_4F6C_DD1D);
+        (value >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// The faults to inject into a request for `path`, latency first.
+    fn pick(&self, path: &str) -> (Option<Fault>, Option<Fault>) {
+        if self.config.exempt_paths.iter().any(|exempt| exempt == path) {
+            return (None, None);
+        }
+        let latency = (self.next_unit() < self.config.latency_rate).then_some(Fault::Latency);
+        let error = (self.next_unit() < self.config.error_rate).then_some(Fault::Error);
+        (latency, error)
+    }
+
+    fn error_response(&self) -> Response<Body> {
+        let mut response = Response::new(Body::from("Injected fault"));
+        *response.status_mut() = self.status;
+        let headers = response.headers_mut();
+        headers.insert(FAULT_HEADER, HeaderValue::from_static("error"));
+        if matches!(
+            self.status,
+            StatusCode::SERVICE_UNAVAILABLE | StatusCode::TOO_MANY_REQUESTS
+        ) {
+            headers.insert(header::RETRY_AFTER, HeaderValue::from_static("1"));
+        }
+        response
+    }
+}
+
+impl<S> Layer<S> for ChaosLayer {
+    type Service = ChaosService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ChaosService {
+            inner,
+            faults: self.faults.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ChaosService<S> {
+    inner: S,
+    faults: Option<Arc<Faults>>,
+}
+
+impl<S, B> Service<Request<B>> for ChaosService<S>
+where
+    S: Service<Request<B>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send,
+    B: Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<B>) -> Self::Future {
+        let Some(faults) = self.faults.clone() else {
+            return Box::pin(self.inner.call(request));
+        };
+        let (latency, error) = faults.pick(request.uri().path());
+        // The ready service must serve this request; keep a fresh clone for the next one
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        Box::pin(async move {
+            if latency.is_some() {
+                tokio::time::sleep(Duration::from_millis(faults.config.latency_ms)).await;
+            }
+            if error.is_some() {
+                return Ok(faults.error_response());
+            }
+            let mut response = inner.call(request).await?;
+            if latency.is_some() {
+                response
+                    .headers_mut()
+                    .insert(FAULT_HEADER, HeaderValue::from_static("latency"));
+            }
+            Ok(response)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn faults(config: Chaos) -> Arc<Faults> {
+        ChaosLayer::new(&config).unwrap().faults.unwrap()
+    }
+
+    #[test]
+    fn test_rates_and_exempt_paths() {
+        let config = Chaos {
+            enabled: true,
+            latency_rate: 0.25,
+            error_rate: 0.5,
+            seed: Some(7),
+            ..Default::default()
+        };
+        let faults = faults(config.clone());
+        let picks: Vec<_> = (0..4000).map(|_| faults.pick("/mcp")).collect();
+        let errors = picks.iter().filter(|(_, error)| error.is_some()).count();
+        let delays = picks
+            .iter()
+            .filter(|(latency, _)| latency.is_some())
+            .count();
+        assert!((1800..2200).contains(&errors), "{errors}");
+        assert!((800..1200).contains(&delays), "{delays}");
+        assert_eq!(faults.pick("/health"), (None, None));
+
+        // The same seed gives the same faults
+        let again = self::faults(config);
+        assert_eq!(
+            picks[..50],
+            (0..50).map(|_| again.pick("/mcp")).collect::<Vec<_>>()
+        );
+
+        let invalid = Chaos {
+            enabled: true,
+            error_rate: 1.5,
+            ..Default::default()
+        };
+        assert!(ChaosLayer::new(&invalid).is_err());
+        assert!(ChaosLayer::new(&Chaos::default()).unwrap().faults.is_none());
+    }
+}
@@ -0,0 +1,154 @@
+use crate::evaluator::{self, CancellationToken, Environment, EvalContext};
+use crate::http_server::AppState;
+use crate::http_server::queue::Priority;
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::post;
+use axum::{Json, Router};
+use bigdecimal::BigDecimal;
+use num_traits::{Signed, Zero};
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+#[derive(Debug, Deserialize)]
+pub struct CompareRequest {
+    pub expression: String,
+    /// A number or an expression such as `1/3`, as a string or JSON number
+    pub expected: Value,
+    /// Largest accepted absolute difference, 0 by default
+    #[serde(default)]
+    pub tolerance: Option<Value>,
+    /// Largest accepted difference relative to `expected`
+    #[serde(default)]
+    pub relative_tolerance: Option<Value>,
+}
+
+/// Regression checks for CI: evaluate an expression and report whether it
+/// lands within tolerance of an expected value. An expression that fails to
+/// evaluate is a failed check, not a failed request.
+pub fn router() -> Router<AppState> {
+    Router::new().route("/compare", post(compare))
+}
+
+async fn compare(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<CompareRequest>,
+) -> Result<Json<Value>, (StatusCode, String)> {
+    let arguments = json!([
+        request.expression,
+        request.expected,
+        request.tolerance,
+        request.relative_tolerance,
+    ]);
+    let (_, constants) = state.admit_evaluation(&headers, &arguments)?;
+    let permit = state.admit(Priority::Batch).await?;
+    // Cancel the evaluation if the client disconnects and this future is dropped
+    let cancellation = CancellationToken::new();
+    let guard = cancellation.drop_guard();
+    let ctx = EvalContext::new(state.limits).with_cancellation(cancellation);
+    let result = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        check(&request, &constants, &ctx)
+    })
+    .await
+    .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+    guard.disarm();
+    result.map(Json)
+}
+
+/// Run one comparison under `ctx`, with the tenant's `constants` bound in
+/// every expression. `expected` and the tolerances are expressions too, held
+/// to the same budget.
+fn check(
+    request: &CompareRequest,
+    constants: &Environment,
+    ctx: &EvalContext,
+) -> Result<Value, (StatusCode, String)> {
+    let invalid = |field: &str, err: anyhow::Error| {
+        (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!("Invalid {field}: {err}"),
+        )
+    };
+    let expected =
+        number(&request.expected, constants, ctx).map_err(|err| invalid("expected", err))?;
+    let tolerance = match &request.tolerance {
+        Some(tolerance) => {
+            number(tolerance, constants, ctx).map_err(|err| invalid("tolerance", err))?
+        }
+        None => BigDecimal::zero(),
+    };
+    let relative_tolerance = match &request.relative_tolerance {
+        Some(relative) => Some(
+            number(relative, constants, ctx).map_err(|err| invalid("relative_tolerance", err))?,
+        ),
+        None => None,
+    };
+    if tolerance.is_negative() || relative_tolerance.as_ref().is_some_and(Signed::is_negative) {
+        return Err((
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "Tolerances must not be negative".to_string(),
+        ));
+    }
+
+    let mut env = constants.clone();
+    let actual = match evaluator::eval_statements(&request.expression, &mut env, ctx) {
+        Ok(evaluation) => evaluation.value,
+        Err(err) => {
+            return Ok(json!({
+                "pass": false,
+                "expected": expected.to_string(),
+                "error": err.to_string(),
+            }));
+        }
+    };
+    let delta = (&actual - &expected).abs();
+    let allowed = match &relative_tolerance {
+        Some(relative) => tolerance.clone().max(relative * expected.abs()),
+        None => tolerance.clone(),
+    };
+    let mut body = json!({
+        "pass": delta <= allowed,
+        "actual": actual.to_string(),
+        "expected": expected.to_string(),
+        "delta": delta.to_string(),
+        "tolerance": tolerance.to_string(),
+    });
+    if let Some(relative) = relative_tolerance {
+        body["relative_tolerance"] = json!(relative.to_string());
+    }
+    Ok(body)
+}
+
+fn number(value: &Value, constants: &Environment, ctx: &EvalContext) -> anyhow::Result<BigDecimal> {
+    match value {
+        Value::String(expression) => evaluator::eval_with(expression, constants, ctx),
+        Value::Number(number) => Ok(number.to_string().parse()?),
+        _ => anyhow::bail!("expected a number or an expression string"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::Limits;
+
+    #[test]
+    fn test_expected_value_is_held_to_request_limits() {
+        let request = CompareRequest {
+            expression: "1".to_string(),
+            expected: json!("9 ^ 9999"),
+            tolerance: None,
+            relative_tolerance: None,
+        };
+        let limits = Limits {
+            max_memory_bytes: 1_000,
+            ..Limits::DEFAULT
+        };
+        let (status, message) =
+            check(&request, &Environment::new(), &EvalContext::new(limits)).unwrap_err();
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(message.starts_with("Invalid expected: Expression needs too much memory"));
+    }
+}
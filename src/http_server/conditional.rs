@@ -0,0 +1,60 @@
+use crate::audit::sha256_hex;
+use axum::Json;
+use axum::http::{HeaderMap, HeaderValue, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use serde_json::Value;
+
+/// Strong validator for a JSON body: a digest of its serialization, so equal
+/// data always gets the same tag.
+pub fn etag(body: &Value) -> String {
+    format!("\"{}\"", &sha256_hex(body.to_string().as_bytes())[..32])
+}
+
+/// Whether `If-None-Match` lists `etag` (or `*`). Comparison is weak, as
+/// RFC 9110 requires for this header, so `W/` prefixes are ignored.
+pub fn matches(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|tag| tag.trim())
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+/// `body` with its `ETag`, or `304 Not Modified` when the client already has it.
+pub fn json(headers: &HeaderMap, body: Value) -> Response {
+    let etag = etag(&body);
+    let mut response = if matches(headers, &etag) {
+        StatusCode::NOT_MODIFIED.into_response()
+    } else {
+        Json(body).into_response()
+    };
+    if let Ok(value) = HeaderValue::from_str(&etag) {
+        response.headers_mut().insert(header::ETAG, value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_if_none_match() {
+        let tag = etag(&json!({ "pi": "3.14" }));
+        assert_eq!(tag, etag(&json!({ "pi": "3.14" })));
+        assert_ne!(tag, etag(&json!({ "pi": "3.1416" })));
+
+        let mut headers = HeaderMap::new();
+        assert!(!matches(&headers, &tag));
+        headers.insert(
+            header::IF_NONE_MATCH,
+            HeaderValue::from_str(&format!("\"other\", W/{tag}")).unwrap(),
+        );
+        assert!(matches(&headers, &tag));
+        headers.insert(header::IF_NONE_MATCH, HeaderValue::from_static("*"));
+        assert!(matches(&headers, &tag));
+    }
+}
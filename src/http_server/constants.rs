@@ -0,0 +1,34 @@
+use crate::evaluator::MathConst;
+use crate::http_server::{AppState, conditional};
+use axum::Router;
+use axum::http::HeaderMap;
+use axum::response::Response;
+use axum::routing::get;
+use bigdecimal::BigDecimal;
+use serde_json::{Value, json};
+
+/// The built-in constants registry, with an `ETag` for conditional polling.
+pub fn router() -> Router<AppState> {
+    Router::new().route("/constants", get(constants))
+}
+
+async fn constants(headers: HeaderMap) -> Response {
+    conditional::json(&headers, registry())
+}
+
+fn registry() -> Value {
+    let constants: Vec<Value> = MathConst::ALL
+        .iter()
+        .map(|constant| {
+            json!({
+                "name": constant.as_str(),
+                "value": BigDecimal::from(*constant).to_string(),
+                "uncertainty": match constant.uncertainty() {
+                    Some(sigma) => sigma.to_scientific_notation(),
+                    None => "exact".to_string(),
+                },
+            })
+        })
+        .collect();
+    json!({ "constants": constants })
+}
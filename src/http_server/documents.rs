@@ -0,0 +1,89 @@
+use crate::editor::TextEdit;
+use crate::http_server::AppState;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+#[derive(Debug, Deserialize)]
+pub struct OpenDocument {
+    #[serde(default)]
+    pub text: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EditDocument {
+    /// The version the edits were made against
+    pub version: u64,
+    pub edits: Vec<TextEdit>,
+}
+
+/// Incremental parse sessions: open a document, send edits against its
+/// current version and get updated diagnostics back.
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/documents", post(open))
+        .route("/documents/{id}", get(show).patch(edit).delete(close))
+}
+
+async fn open(
+    State(state): State<AppState>,
+    Json(request): Json<OpenDocument>,
+) -> Result<(StatusCode, Json<Value>), (StatusCode, String)> {
+    let (id, check) = state
+        .documents
+        .open(request.text)
+        .map_err(|err| (StatusCode::SERVICE_UNAVAILABLE, err.to_string()))?;
+    Ok((
+        StatusCode::CREATED,
+        Json(json!({ "id": id, "check": check })),
+    ))
+}
+
+async fn show(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Value>, (StatusCode, String)> {
+    state
+        .documents
+        .with_document(&id, |document| {
+            Json(json!({ "id": id, "text": document.text(), "check": document.check() }))
+        })
+        .map_err(|err| (StatusCode::NOT_FOUND, err.to_string()))
+}
+
+async fn edit(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(request): Json<EditDocument>,
+) -> Result<Json<Value>, (StatusCode, String)> {
+    state
+        .documents
+        .with_document(&id, |document| {
+            if request.version != document.version() {
+                return Err((
+                    StatusCode::CONFLICT,
+                    format!(
+                        "Edits were made against version {}, the document is at {}",
+                        request.version,
+                        document.version()
+                    ),
+                ));
+            }
+            document
+                .apply(&request.edits)
+                .map_err(|err| (StatusCode::UNPROCESSABLE_ENTITY, err.to_string()))?;
+            Ok(Json(json!({ "id": id, "check": document.check() })))
+        })
+        .map_err(|err| (StatusCode::NOT_FOUND, err.to_string()))?
+}
+
+async fn close(State(state): State<AppState>, Path(id): Path<String>) -> StatusCode {
+    if state.documents.close(&id) {
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
@@ -0,0 +1,127 @@
+use crate::evaluator::CancellationToken;
+use crate::http_server::AppState;
+use crate::http_server::queue::Priority;
+use crate::i18n::{ACCEPT_LANGUAGE_HEADER, Locale};
+use crate::mcp::RequestMeta;
+use crate::mcp::protocol::{
+    INVALID_REQUEST, JsonRpcError, JsonRpcRequest, JsonRpcResponse, PARSE_ERROR, SESSION_ID_HEADER,
+};
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::{HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Json, Router};
+use serde_json::Value;
+
+/// Streamable HTTP transport in JSON response mode: every POST carries one
+/// JSON-RPC message and gets either a JSON response or `202 Accepted`.
+/// Sessions are announced on `initialize` via `Mcp-Session-Id` and ended by `DELETE`.
+pub fn router() -> Router<AppState> {
+    Router::new().route("/mcp", post(handle_mcp).delete(end_session))
+}
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+/// Set to `true` on responses replayed for a repeated idempotency key
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "idempotent-replayed";
+
+fn session_header(headers: &HeaderMap) -> Option<&str> {
+    headers.get(SESSION_ID_HEADER)?.to_str().ok()
+}
+
+async fn handle_mcp(State(state): State<AppState>, headers: HeaderMap, body: Bytes) -> Response {
+    let mcp = &state.mcp;
+    let body: Value = match serde_json::from_slice(&body) {
+        Ok(body) => body,
+        Err(err) => {
+            let error = JsonRpcError::new(PARSE_ERROR, format!("Parse error: {err}"));
+            return Json(JsonRpcResponse::failure(Value::Null, error)).into_response();
+        }
+    };
+    let request: JsonRpcRequest = match serde_json::from_value(body) {
+        Ok(request) => request,
+        Err(err) => {
+            let error = JsonRpcError::new(INVALID_REQUEST, format!("Invalid request: {err}"));
+            return Json(JsonRpcResponse::failure(Value::Null, error)).into_response();
+        }
+    };
+
+    let tenant = match state.tenant(&headers) {
+        Ok(tenant) => tenant,
+        Err(err) => return (StatusCode::BAD_REQUEST, err.to_string()).into_response(),
+    };
+    let session_id = session_header(&headers);
+    if let Some(id) = session_id
+        && !mcp.sessions().belongs_to(id, tenant.as_deref())
+    {
+        return (StatusCode::NOT_FOUND, "Unknown or expired session").into_response();
+    }
+
+    // Tool calls evaluate and wait their turn; the rest answer at once
+    let permit = if request.method == "tools/call" {
+        match state.admit(Priority::Interactive).await {
+            Ok(permit) => permit,
+            Err(err) => return (StatusCode::SERVICE_UNAVAILABLE, err.to_string()).into_response(),
+        }
+    } else {
+        None
+    };
+    let meta = RequestMeta {
+        session_id: session_id.map(str::to_string),
+        caller: state.caller(&headers),
+        idempotency_key: headers
+            .get(IDEMPOTENCY_KEY_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string),
+        tenant,
+        cancellation: CancellationToken::new(),
+        locale: headers
+            .get(ACCEPT_LANGUAGE_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(Locale::negotiate)
+            .unwrap_or_default(),
+    };
+    // Evaluation blocks; run it off the async workers and stop it if the
+    // client disconnects and this future is dropped
+    let guard = meta.cancellation.drop_guard();
+    let handler = mcp.clone();
+    let reply = match tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        handler.handle(request, &meta)
+    })
+    .await
+    {
+        Ok(reply) => reply,
+        Err(err) => return (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+    };
+    guard.disarm();
+    let mut response = match reply.response {
+        Some(response) => Json(response).into_response(),
+        None => StatusCode::ACCEPTED.into_response(),
+    };
+    if let Some(new_session) = reply.session_id
+        && let Ok(value) = HeaderValue::from_str(&new_session)
+    {
+        response.headers_mut().insert(SESSION_ID_HEADER, value);
+    }
+    if reply.replayed {
+        response
+            .headers_mut()
+            .insert(IDEMPOTENT_REPLAYED_HEADER, HeaderValue::from_static("true"));
+    }
+    response
+}
+
+async fn end_session(State(state): State<AppState>, headers: HeaderMap) -> StatusCode {
+    let Ok(tenant) = state.tenant(&headers) else {
+        return StatusCode::BAD_REQUEST;
+    };
+    let sessions = state.mcp.sessions();
+    match session_header(&headers) {
+        Some(id) if sessions.belongs_to(id, tenant.as_deref()) && sessions.remove(id) => {
+            StatusCode::NO_CONTENT
+        }
+        Some(_) => StatusCode::NOT_FOUND,
+        None => StatusCode::BAD_REQUEST,
+    }
+}
@@ -0,0 +1,146 @@
+use crate::evaluator::{cache, memo};
+use crate::http_server::AppState;
+use crate::http_server::queue::{QueueMetrics, WAIT_BUCKETS};
+use axum::Router;
+use axum::extract::State;
+use axum::routing::get;
+use std::fmt::Write;
+
+/// Prometheus text exposition of server counters.
+pub fn router() -> Router<AppState> {
+    Router::new().route("/metrics", get(metrics))
+}
+
+async fn metrics(State(state): State<AppState>) -> String {
+    let snapshot = state.sessions.metrics();
+    let mut out = String::new();
+    let _ = writeln!(out, "# TYPE calculator_sessions_active gauge");
+    let _ = writeln!(out, "calculator_sessions_active {}", snapshot.active);
+    let _ = writeln!(out, "# TYPE calculator_sessions_created_total counter");
+    let _ = writeln!(
+        out,
+        "calculator_sessions_created_total {}",
+        snapshot.created
+    );
+    let _ = writeln!(out, "# TYPE calculator_sessions_expired_total counter");
+    let _ = writeln!(
+        out,
+        "calculator_sessions_expired_total {}",
+        snapshot.expired
+    );
+    let _ = writeln!(out, "# TYPE calculator_sessions_rejected_total counter");
+    let _ = writeln!(
+        out,
+        "calculator_sessions_rejected_total {}",
+        snapshot.rejected
+    );
+    let results = cache::stats();
+    let _ = writeln!(out, "# TYPE calculator_result_cache_hits_total counter");
+    let _ = writeln!(out, "calculator_result_cache_hits_total {}", results.hits);
+    let _ = writeln!(out, "# TYPE calculator_result_cache_misses_total counter");
+    let _ = writeln!(
+        out,
+        "calculator_result_cache_misses_total {}",
+        results.misses
+    );
+    let dedup = memo::stats();
+    let _ = writeln!(out, "# TYPE calculator_eval_subexpressions_total counter");
+    let _ = writeln!(
+        out,
+        "calculator_eval_subexpressions_total {}",
+        dedup.subexpressions
+    );
+    let _ = writeln!(
+        out,
+        "# TYPE calculator_eval_subexpressions_deduplicated_total counter"
+    );
+    let _ = writeln!(
+        out,
+        "calculator_eval_subexpressions_deduplicated_total {}",
+        dedup.deduplicated
+    );
+    if let Some(queue) = &state.queue {
+        write_queue_metrics(&mut out, &queue.metrics());
+    }
+    if let Some(workers) = &state.workers {
+        let _ = writeln!(out, "# TYPE calculator_workers_replaced_total counter");
+        let _ = writeln!(
+            out,
+            "calculator_workers_replaced_total {}",
+            workers.replaced()
+        );
+    }
+    if let Some(tenancy) = &state.tenancy {
+        let metrics = tenancy.metrics();
+        let _ = writeln!(out, "# TYPE calculator_tenant_tool_calls_total counter");
+        for (tenant, counters) in &metrics {
+            let _ = writeln!(
+                out,
+                "calculator_tenant_tool_calls_total{{tenant=\"{tenant}\"}} {}",
+                counters.tool_calls
+            );
+        }
+        let _ = writeln!(
+            out,
+            "# TYPE calculator_tenant_sessions_created_total counter"
+        );
+        for (tenant, counters) in &metrics {
+            let _ = writeln!(
+                out,
+                "calculator_tenant_sessions_created_total{{tenant=\"{tenant}\"}} {}",
+                counters.sessions_created
+            );
+        }
+    }
+    out
+}
+
+fn write_queue_metrics(out: &mut String, metrics: &QueueMetrics) {
+    let _ = writeln!(out, "# TYPE calculator_queue_workers gauge");
+    let _ = writeln!(out, "calculator_queue_workers {}", metrics.workers);
+    let _ = writeln!(out, "# TYPE calculator_queue_running gauge");
+    let _ = writeln!(out, "calculator_queue_running {}", metrics.running);
+    let _ = writeln!(out, "# TYPE calculator_queue_waiting gauge");
+    for class in &metrics.classes {
+        let _ = writeln!(
+            out,
+            "calculator_queue_waiting{{priority=\"{}\"}} {}",
+            class.priority.label(),
+            class.waiting
+        );
+    }
+    let _ = writeln!(out, "# TYPE calculator_queue_shed_total counter");
+    for class in &metrics.classes {
+        let _ = writeln!(
+            out,
+            "calculator_queue_shed_total{{priority=\"{}\"}} {}",
+            class.priority.label(),
+            class.shed
+        );
+    }
+    let _ = writeln!(out, "# TYPE calculator_queue_wait_seconds histogram");
+    for class in &metrics.classes {
+        let priority = class.priority.label();
+        for (bound, count) in WAIT_BUCKETS.iter().zip(class.wait.buckets) {
+            let _ = writeln!(
+                out,
+                "calculator_queue_wait_seconds_bucket{{priority=\"{priority}\",le=\"{bound}\"}} {count}"
+            );
+        }
+        let _ = writeln!(
+            out,
+            "calculator_queue_wait_seconds_bucket{{priority=\"{priority}\",le=\"+Inf\"}} {}",
+            class.wait.count
+        );
+        let _ = writeln!(
+            out,
+            "calculator_queue_wait_seconds_sum{{priority=\"{priority}\"}} {}",
+            class.wait.sum_secs
+        );
+        let _ = writeln!(
+            out,
+            "calculator_queue_wait_seconds_count{{priority=\"{priority}\"}} {}",
+            class.wait.count
+        );
+    }
+}
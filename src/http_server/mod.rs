@@ -1,8 +1,16 @@
-use crate::app_config::AppConfig;
+use crate::app_config::{AppConfig, Tls};
+use crate::logging::LogLevelHandle;
+use crate::logging::spans::ExpressionSpan;
+use crate::warmup;
+use anyhow::Context;
 use axum::BoxError;
 use axum::error_handling::HandleErrorLayer;
+use axum::extract::State;
 use axum::http::StatusCode;
-use axum::{Router, routing::get};
+use axum::response::{IntoResponse, Response};
+use axum::{Json, Router, middleware, routing::get};
+use axum_server::tls_rustls::RustlsConfig;
+use serde_json::{Value, json};
 use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::Duration;
@@ -16,48 +24,207 @@ use tower_http::catch_panic::CatchPanicLayer;
 use tower_http::cors::CorsLayer;
 use tower_http::limit::RequestBodyLimitLayer;
 use tower_http::request_id::MakeRequestUuid;
-use tower_http::trace::{DefaultMakeSpan, DefaultOnResponse, TraceLayer};
-use tracing::{Level, info};
+use tower_http::trace::{DefaultOnResponse, TraceLayer};
+use tracing::{Level, debug, info, warn};
+
+pub mod admin;
+pub mod auth;
+pub mod chaos;
+pub mod compare;
+pub mod conditional;
+pub mod constants;
+pub mod documents;
+pub mod mcp;
+pub mod metrics;
+pub mod openai;
+pub mod queue;
+pub mod sessions;
+pub mod state;
+pub mod stream;
+pub mod usage;
+
+pub use state::AppState;
+
+/// The MCP endpoint alone, at `/mcp`, guarded by the configured API keys and
+/// reporting quotas in `X-RateLimit-*` headers. Other axum services can mount
+/// it under a prefix of their own, e.g.
+/// `Router::new().nest("/calculator", mcp_router(state))` to serve
+/// `/calculator/mcp`. Expired sessions are only swept by [`HttpServer::start`];
+/// embedders call `state.sessions.evict_expired()` periodically themselves.
+pub fn mcp_router(state: AppState) -> Router {
+    mcp::router()
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            usage::rate_limit_headers,
+        ))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth::require_api_key,
+        ))
+        .with_state(state)
+}
 
 pub struct HttpServer {
-    config: Arc<AppConfig>,
+    state: AppState,
 }
 
 impl HttpServer {
-    pub fn new(config: Arc<AppConfig>) -> Self {
-        HttpServer { config }
+    pub fn new(config: Arc<AppConfig>, log_level: LogLevelHandle) -> anyhow::Result<Self> {
+        Ok(HttpServer {
+            state: AppState::new(config)?.with_log_level(log_level),
+        })
     }
 
-    pub async fn start(&self) -> anyhow::Result<()> {
-        let app = Router::new().route("/health", get(health_check)).layer(
-            ServiceBuilder::new()
-                .set_x_request_id(MakeRequestUuid)
-                .layer(
-                    TraceLayer::new_for_http()
-                        .make_span_with(DefaultMakeSpan::new().level(Level::INFO))
-                        .on_request(())
-                        .on_response(
-                            DefaultOnResponse::new()
-                                .level(Level::INFO)
-                                .include_headers(true),
-                        ),
-                )
-                .propagate_x_request_id()
-                .layer(HandleErrorLayer::new(|err: BoxError| async move {
-                    (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        format!("Unhandled error: {}", err),
-                    )
-                }))
-                .layer(TimeoutLayer::new(Duration::from_secs(30)))
-                .layer(BufferLayer::new(1024))
-                .layer(RateLimitLayer::new(100, Duration::from_secs(1)))
-                .layer(RequestBodyLimitLayer::new(4 * 1024 * 1024))
-                .layer(CatchPanicLayer::new())
-                .layer(CorsLayer::permissive()),
+    pub fn state(&self) -> &AppState {
+        &self.state
+    }
+
+    /// Run the warm-up self-test off the async workers and mark the server
+    /// ready if it passes.
+    pub async fn warm_up(&self) -> anyhow::Result<()> {
+        let config = self.state.config.warmup.clone();
+        let limits = self.state.limits;
+        let report = tokio::task::spawn_blocking(move || warmup::run(&config, limits))
+            .await?
+            .context("Refusing to start")?;
+        info!(
+            "Warm-up passed: {} canaries, {} constants in {} ms",
+            report.canaries, report.constants, report.elapsed_ms
         );
+        let _ = self.state.readiness.set(report);
+        Ok(())
+    }
+
+    fn spawn_session_sweeper(&self) {
+        let sessions = self.state.sessions.clone();
+        let documents = self.state.documents.clone();
+        let period = Duration::from_secs(self.state.config.sessions.sweep_interval_secs.max(1));
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                let evicted = sessions.evict_expired();
+                if evicted > 0 {
+                    debug!("Evicted {} expired session(s)", evicted);
+                }
+                let closed = documents.evict_expired();
+                if closed > 0 {
+                    debug!("Closed {} idle document(s)", closed);
+                }
+            }
+        });
+    }
+
+    fn spawn_tenant_refresher(&self) {
+        if self.state.tenancy.is_none() {
+            return;
+        }
+        let state = self.state.clone();
+        let period = Duration::from_secs(self.state.config.tenants.refresh_secs.max(1));
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                let state = state.clone();
+                match tokio::task::spawn_blocking(move || state.reload_tenants()).await {
+                    Ok(Ok(count)) => debug!("Reloaded settings of {} tenant(s)", count),
+                    Ok(Err(err)) => warn!("Keeping tenant settings: {:#}", err),
+                    Err(err) => warn!("Tenant settings reload failed: {}", err),
+                }
+            }
+        });
+    }
+
+    pub fn log_config(&self) {
+        info!(config = %self.state.config.redacted(), "Resolved configuration");
+    }
+
+    /// Every route with its middleware, without binding a socket: serve it,
+    /// merge it into another axum app, or drive it in-process with
+    /// `tower::ServiceExt::oneshot`.
+    pub fn router(&self) -> Router {
+        Router::new()
+            .route("/health", get(health_check))
+            .route("/ready", get(ready))
+            .route("/info", get(info))
+            .merge(admin::router(&self.state.config.auth))
+            .merge(compare::router())
+            .merge(constants::router())
+            .merge(documents::router())
+            .merge(mcp::router())
+            .merge(metrics::router())
+            .merge(openai::router())
+            .merge(sessions::router())
+            .merge(stream::router())
+            .merge(usage::router())
+            .route_layer(middleware::from_fn_with_state(
+                self.state.clone(),
+                usage::rate_limit_headers,
+            ))
+            .route_layer(middleware::from_fn_with_state(
+                self.state.clone(),
+                auth::require_api_key,
+            ))
+            .with_state(self.state.clone())
+            .layer(
+                ServiceBuilder::new()
+                    .set_x_request_id(MakeRequestUuid)
+                    .layer(
+                        TraceLayer::new_for_http()
+                            .make_span_with(ExpressionSpan {
+                                record_expressions: self.state.config.logging.record_expressions,
+                            })
+                            .on_request(())
+                            .on_response(
+                                DefaultOnResponse::new()
+                                    .level(Level::INFO)
+                                    .include_headers(true),
+                            ),
+                    )
+                    .propagate_x_request_id()
+                    .layer(HandleErrorLayer::new(|err: BoxError| async move {
+                        (
+                            StatusCode::INTERNAL_SERVER_ERROR,
+                            format!("Unhandled error: {}", err),
+                        )
+                    }))
+                    .layer(TimeoutLayer::new(Duration::from_secs(30)))
+                    .layer(BufferLayer::new(1024))
+                    .layer(RateLimitLayer::new(
+                        self.state.config.http_server.rate_limit_per_sec.max(1),
+                        Duration::from_secs(1),
+                    ))
+                    .layer(RequestBodyLimitLayer::new(
+                        self.state.config.http_server.max_body_bytes,
+                    ))
+                    .layer(CatchPanicLayer::new())
+                    .layer(CorsLayer::permissive())
+                    .layer(self.state.chaos.clone()),
+            )
+    }
+
+    pub async fn start(&self) -> anyhow::Result<()> {
+        let app = self.router();
+
+        self.warm_up().await?;
+        if self.state.config.chaos.enabled {
+            warn!("Chaos fault injection is enabled; do not serve real traffic");
+        }
+        self.spawn_session_sweeper();
+        self.spawn_tenant_refresher();
+
+        let addr = SocketAddr::from(([0, 0, 0, 0], self.state.config.http_server.port));
+
+        if let Some(tls) = &self.state.config.tls {
+            let rustls_config = rustls_config(tls).await?;
+            info!("Server running on https://{}", addr);
+            axum_server::bind_rustls(addr, rustls_config)
+                .serve(app.into_make_service())
+                .await?;
+            return Ok(());
+        }
 
-        let addr = SocketAddr::from(([0, 0, 0, 0], self.config.http_server.port));
         let listener = TcpListener::bind(&addr).await?;
 
         info!("Server running on http://{}", addr);
@@ -67,6 +234,792 @@ impl HttpServer {
     }
 }
 
+async fn rustls_config(tls: &Tls) -> anyhow::Result<RustlsConfig> {
+    // Several crates may enable a rustls provider; only the first install wins.
+    let _ = rustls::crypto::ring::default_provider().install_default();
+
+    let cert = tls.cert.clone().unwrap_or_default().into_bytes();
+    let key = tls.key.clone().unwrap_or_default().into_bytes();
+    Ok(RustlsConfig::from_pem(cert, key).await?)
+}
+
 async fn health_check() -> &'static str {
     "OK"
 }
+
+/// 200 with the warm-up report once the self-test passed, else 503.
+async fn ready(State(state): State<AppState>) -> Response {
+    match state.readiness.get() {
+        Some(report) => Json(json!({ "ready": true, "warmup": report })).into_response(),
+        None => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({ "ready": false })),
+        )
+            .into_response(),
+    }
+}
+
+async fn info(State(state): State<AppState>) -> Json<Value> {
+    Json(json!({
+        "name": env!("CARGO_PKG_NAME"),
+        "version": env!("CARGO_PKG_VERSION"),
+        "config": state.config.redacted(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_config::{Logging, TenantSettings};
+    use crate::audit::{ANONYMOUS_CALLER, key_fingerprint};
+    use crate::logging;
+    use crate::mcp::protocol::{QUOTA_EXCEEDED, SESSION_ID_HEADER};
+    use axum::body::{Body, to_bytes};
+    use axum::http::{Request, header};
+    use tower::ServiceExt;
+
+    fn server(config: AppConfig) -> HttpServer {
+        let log_level = logging::detached(&Logging::default()).unwrap();
+        HttpServer::new(Arc::new(config), log_level).unwrap()
+    }
+
+    fn mcp_request(body: Value, session_id: Option<&str>) -> Request<Body> {
+        let mut request = Request::post("/mcp").header(header::CONTENT_TYPE, "application/json");
+        if let Some(id) = session_id {
+            request = request.header(SESSION_ID_HEADER, id);
+        }
+        request.body(Body::from(body.to_string())).unwrap()
+    }
+
+    async fn json_body(response: axum::response::Response) -> Value {
+        serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_router_serves_in_process() {
+        let router = server(AppConfig::default()).router();
+
+        let health = router
+            .clone()
+            .oneshot(Request::get("/health").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(health.status(), StatusCode::OK);
+        assert!(health.headers().contains_key("x-request-id"));
+
+        let initialized = router
+            .clone()
+            .oneshot(mcp_request(
+                json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize" }),
+                None,
+            ))
+            .await
+            .unwrap();
+        let session_id = initialized.headers()[SESSION_ID_HEADER]
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        let call = |expression: &str| {
+            mcp_request(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 2,
+                    "method": "tools/call",
+                    "params": { "name": "evaluate", "arguments": { "expression": expression } },
+                }),
+                Some(&session_id),
+            )
+        };
+        router.clone().oneshot(call("x = 6")).await.unwrap();
+        let response = router.clone().oneshot(call("x * 7")).await.unwrap();
+        assert_eq!(
+            json_body(response).await["result"]["structuredContent"]["result"],
+            "42"
+        );
+
+        let expired = router
+            .oneshot(mcp_request(
+                json!({ "jsonrpc": "2.0", "id": 3, "method": "ping" }),
+                Some("no-such-session"),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(expired.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_mcp_router_mounts_under_prefix() {
+        let mut config = AppConfig::default();
+        config.auth.api_keys = vec!["secret".to_string()];
+        let state = AppState::new(Arc::new(config)).unwrap();
+        let app = Router::new()
+            .route("/", get(|| async { "host app" }))
+            .nest("/calculator", mcp_router(state.clone()));
+
+        let initialize = json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize" });
+        let denied = app
+            .clone()
+            .oneshot(
+                Request::post("/calculator/mcp")
+                    .body(Body::from(initialize.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(denied.status(), StatusCode::UNAUTHORIZED);
+
+        let initialized = app
+            .clone()
+            .oneshot(
+                Request::post("/calculator/mcp")
+                    .header(auth::API_KEY_HEADER, "secret")
+                    .body(Body::from(initialize.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(initialized.status(), StatusCode::OK);
+        let session_id = initialized.headers()[SESSION_ID_HEADER].to_str().unwrap();
+        assert!(state.sessions.contains(session_id));
+
+        let host = app
+            .oneshot(Request::get("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(host.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_handlers_share_app_state() {
+        let mut config = AppConfig::default();
+        config.evaluator.precision = 0;
+        let server = server(config);
+        assert_eq!(server.state().limits.precision, 1);
+
+        let router = server.router();
+        let initialized = router
+            .clone()
+            .oneshot(mcp_request(
+                json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize" }),
+                None,
+            ))
+            .await
+            .unwrap();
+        let session_id = initialized.headers()[SESSION_ID_HEADER].to_str().unwrap();
+        assert!(server.state().sessions.contains(session_id));
+
+        let metrics = router
+            .clone()
+            .oneshot(Request::get("/metrics").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let text = to_bytes(metrics.into_body(), usize::MAX).await.unwrap();
+        let text = String::from_utf8_lossy(&text);
+        assert!(text.contains("calculator_sessions_created_total 1"));
+        assert!(text.contains("calculator_eval_subexpressions_deduplicated_total "));
+        assert!(text.contains("calculator_queue_running 0"));
+        assert!(text.contains("calculator_queue_shed_total{priority=\"batch\"} 0"));
+
+        // Admin routes need API keys
+        let level = router
+            .oneshot(
+                Request::get("/admin/log-level")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(level.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_constants_honor_if_none_match() {
+        let router = server(AppConfig::default()).router();
+        let constants = |etag: Option<&str>| {
+            let mut request = Request::get("/constants");
+            if let Some(etag) = etag {
+                request = request.header(header::IF_NONE_MATCH, etag);
+            }
+            request.body(Body::empty()).unwrap()
+        };
+
+        let listed = router.clone().oneshot(constants(None)).await.unwrap();
+        assert_eq!(listed.status(), StatusCode::OK);
+        let etag = listed.headers()[header::ETAG].to_str().unwrap().to_string();
+        let body = json_body(listed).await;
+        assert_eq!(body["constants"][0]["name"], "pi");
+        assert_eq!(body["constants"][6]["uncertainty"], "1.5e-15");
+
+        let unchanged = router
+            .clone()
+            .oneshot(constants(Some(&etag)))
+            .await
+            .unwrap();
+        assert_eq!(unchanged.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(unchanged.headers()[header::ETAG], etag.as_str());
+        let stale = router.oneshot(constants(Some("\"stale\""))).await.unwrap();
+        assert_eq!(stale.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_openai_tool_export() {
+        let router = server(AppConfig::default()).router();
+        let response = router
+            .clone()
+            .oneshot(Request::get("/openai/tools").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert!(response.headers().contains_key(header::ETAG));
+        let tools = json_body(response).await;
+
+        let listed = router
+            .oneshot(mcp_request(
+                json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/list" }),
+                None,
+            ))
+            .await
+            .unwrap();
+        let listed = json_body(listed).await["result"]["tools"].clone();
+        assert_eq!(
+            tools.as_array().unwrap().len(),
+            listed.as_array().unwrap().len()
+        );
+
+        let evaluate = &tools[0];
+        assert_eq!(evaluate["type"], "function");
+        assert_eq!(evaluate["function"]["name"], "evaluate");
+        assert_eq!(evaluate["function"]["parameters"], listed[0]["inputSchema"]);
+    }
+
+    #[tokio::test]
+    async fn test_document_edits() {
+        let router = server(AppConfig::default()).router();
+        let send = |method: &str, uri: &str, body: Value| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        let opened = router
+            .clone()
+            .oneshot(send("POST", "/documents", json!({ "text": "x = 2 *" })))
+            .await
+            .unwrap();
+        assert_eq!(opened.status(), StatusCode::CREATED);
+        let opened = json_body(opened).await;
+        assert_eq!(opened["check"]["diagnostics"][0]["severity"], "error");
+        let uri = format!("/documents/{}", opened["id"].as_str().unwrap());
+
+        let edit = json!({ "version": 1, "edits": [{ "start": 7, "end": 7, "text": " 3" }] });
+        let edited = router
+            .clone()
+            .oneshot(send("PATCH", &uri, edit.clone()))
+            .await
+            .unwrap();
+        let edited = json_body(edited).await;
+        assert_eq!(edited["check"]["version"], 2);
+        assert_eq!(edited["check"]["diagnostics"], json!([]));
+
+        let stale = router
+            .clone()
+            .oneshot(send("PATCH", &uri, edit))
+            .await
+            .unwrap();
+        assert_eq!(stale.status(), StatusCode::CONFLICT);
+
+        let shown = router
+            .clone()
+            .oneshot(Request::get(&uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(json_body(shown).await["text"], "x = 2 * 3");
+
+        let closed = router
+            .clone()
+            .oneshot(send("DELETE", &uri, json!({})))
+            .await
+            .unwrap();
+        assert_eq!(closed.status(), StatusCode::NO_CONTENT);
+        let gone = router
+            .oneshot(Request::get(&uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(gone.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_ready_after_warm_up() {
+        let server = server(AppConfig::default());
+        let ready = || Request::get("/ready").body(Body::empty()).unwrap();
+        let response = server.router().oneshot(ready()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        server.warm_up().await.unwrap();
+        let response = server.router().oneshot(ready()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: Value =
+            serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap())
+                .unwrap();
+        assert_eq!(body["warmup"]["canaries"], 3);
+
+        let mut config = AppConfig::default();
+        config.warmup.canaries[0].expected = "8".to_string();
+        let broken = HttpServer::new(Arc::new(config), server.state().log_level.clone()).unwrap();
+        assert!(broken.warm_up().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_chaos_injects_errors() {
+        let mut config = AppConfig::default();
+        config.chaos.enabled = true;
+        config.chaos.error_rate = 1.0;
+        let router = server(config).router();
+
+        let response = router
+            .clone()
+            .oneshot(mcp_request(
+                json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/list" }),
+                None,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()[chaos::FAULT_HEADER], "error");
+        assert_eq!(response.headers()[header::RETRY_AFTER], "1");
+
+        let health = router
+            .oneshot(Request::get("/health").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(health.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_tenants_are_kept_apart() {
+        let mut config = AppConfig::default();
+        config.tenants.enabled = true;
+        config.tenants.settings.insert(
+            "analytics".to_string(),
+            TenantSettings {
+                constants: [("vat".to_string(), "0.2".to_string())].into(),
+                ..Default::default()
+            },
+        );
+        let router = server(config).router();
+        let request = |tenant: &str, session_id: Option<&str>, body: Value| {
+            let mut request = mcp_request(body, session_id);
+            request
+                .headers_mut()
+                .insert("x-tenant-id", tenant.parse().unwrap());
+            request
+        };
+        let evaluate = |expression: &str| {
+            json!({
+                "jsonrpc": "2.0",
+                "id": 2,
+                "method": "tools/call",
+                "params": { "name": "evaluate", "arguments": { "expression": expression } },
+            })
+        };
+
+        let initialized = router
+            .clone()
+            .oneshot(request(
+                "analytics",
+                None,
+                json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize" }),
+            ))
+            .await
+            .unwrap();
+        let session_id = initialized.headers()[SESSION_ID_HEADER]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let own = router
+            .clone()
+            .oneshot(request(
+                "analytics",
+                Some(&session_id),
+                evaluate("100 * vat"),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(
+            json_body(own).await["result"]["structuredContent"]["result"],
+            "20.0"
+        );
+
+        let foreign = router
+            .clone()
+            .oneshot(request("billing", Some(&session_id), evaluate("1")))
+            .await
+            .unwrap();
+        assert_eq!(foreign.status(), StatusCode::NOT_FOUND);
+        let unscoped = router
+            .clone()
+            .oneshot(request("billing", None, evaluate("vat")))
+            .await
+            .unwrap();
+        assert_eq!(json_body(unscoped).await["result"]["isError"], true);
+        let invalid = router
+            .clone()
+            .oneshot(request("no spaces", None, evaluate("1")))
+            .await
+            .unwrap();
+        assert_eq!(invalid.status(), StatusCode::BAD_REQUEST);
+
+        let metrics = router
+            .oneshot(Request::get("/metrics").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let metrics = String::from_utf8(
+            to_bytes(metrics.into_body(), usize::MAX)
+                .await
+                .unwrap()
+                .to_vec(),
+        )
+        .unwrap();
+        assert!(metrics.contains("calculator_tenant_tool_calls_total{tenant=\"analytics\"} 1"));
+        assert!(
+            metrics.contains("calculator_tenant_sessions_created_total{tenant=\"analytics\"} 1")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_compare_results() {
+        let router = server(AppConfig::default()).router();
+        let compare = |body: Value| {
+            Request::post("/compare")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        let exact = router
+            .clone()
+            .oneshot(compare(
+                json!({ "expression": "0.1 + 0.2", "expected": "0.3" }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(exact.status(), StatusCode::OK);
+        let exact = json_body(exact).await;
+        assert_eq!(exact["pass"], true);
+        assert_eq!(exact["delta"], "0");
+
+        let within = json_body(
+            router
+                .clone()
+                .oneshot(compare(json!({
+                    "expression": "sqrt(2)",
+                    "expected": 1.414,
+                    "tolerance": "0.001",
+                })))
+                .await
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(within["pass"], true);
+
+        let relative = json_body(
+            router
+                .clone()
+                .oneshot(compare(json!({
+                    "expression": "101",
+                    "expected": "100",
+                    "relative_tolerance": "0.005",
+                })))
+                .await
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(
+            (&relative["pass"], &relative["delta"]),
+            (&json!(false), &json!("1"))
+        );
+
+        let failed = json_body(
+            router
+                .clone()
+                .oneshot(compare(json!({ "expression": "1 / 0", "expected": "1" })))
+                .await
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(failed["pass"], false);
+        assert!(failed["error"].is_string());
+
+        let invalid = router
+            .oneshot(compare(
+                json!({ "expression": "1", "expected": "1", "tolerance": "-1" }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(invalid.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn test_compare_is_held_to_quotas_and_tenants() {
+        let mut config = AppConfig::default();
+        config.auth.api_keys = vec!["secret".to_string()];
+        config.quotas.enabled = true;
+        config.quotas.default.daily = Some(1);
+        config.tenants.enabled = true;
+        config
+            .tenants
+            .keys
+            .insert(key_fingerprint("secret"), "analytics".to_string());
+        config.tenants.settings.insert(
+            "analytics".to_string(),
+            TenantSettings {
+                constants: [("vat".to_string(), "0.2".to_string())].into(),
+                functions: Some(vec!["sqrt".to_string()]),
+                ..Default::default()
+            },
+        );
+        let router = server(config).router();
+        let compare = |expression: &str| {
+            Request::post("/compare")
+                .header(header::CONTENT_TYPE, "application/json")
+                .header(auth::API_KEY_HEADER, "secret")
+                .body(Body::from(
+                    json!({ "expression": expression, "expected": "20" }).to_string(),
+                ))
+                .unwrap()
+        };
+
+        let denied = router.clone().oneshot(compare("sin(0)")).await.unwrap();
+        assert_eq!(denied.status(), StatusCode::FORBIDDEN);
+        let own = router.clone().oneshot(compare("100 * vat")).await.unwrap();
+        assert_eq!(own.status(), StatusCode::OK);
+        assert_eq!(json_body(own).await["pass"], true);
+
+        let over_quota = router.oneshot(compare("100 * vat")).await.unwrap();
+        assert_eq!(over_quota.status(), StatusCode::TOO_MANY_REQUESTS);
+        let message = to_bytes(over_quota.into_body(), usize::MAX).await.unwrap();
+        assert!(
+            String::from_utf8_lossy(&message).starts_with("daily quota of 1 evaluations exceeded")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_streamed_body() {
+        let mut config = AppConfig::default();
+        config.http_server.max_body_bytes = 1024;
+        let router = server(config).router();
+        let streamed = |chunks: Vec<String>| {
+            let (mut sender, body) = http_body_util::channel::Channel::<
+                axum::body::Bytes,
+                axum::Error,
+            >::new(chunks.len().max(1));
+            tokio::spawn(async move {
+                for chunk in chunks {
+                    sender.send_data(chunk.into()).await.unwrap();
+                }
+            });
+            Request::post("/evaluate/stream")
+                .body(Body::new(body))
+                .unwrap()
+        };
+
+        let sum = ["1 + ", "2", "3 * (4", " - 1) + 1", "0 ^ 2"].map(String::from);
+        let response = router
+            .clone()
+            .oneshot(streamed(sum.to_vec()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(json_body(response).await["result"], "170");
+
+        let invalid = router
+            .clone()
+            .oneshot(streamed(vec!["1 + ".into(), "$".into()]))
+            .await
+            .unwrap();
+        assert_eq!(invalid.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let too_large = router
+            .oneshot(streamed(vec!["1 + ".repeat(200); 2]))
+            .await
+            .unwrap();
+        assert_eq!(too_large.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_stream_is_held_to_quotas_and_tenants() {
+        let mut config = AppConfig::default();
+        config.auth.api_keys = vec!["secret".to_string()];
+        config.quotas.enabled = true;
+        config.quotas.default.daily = Some(2);
+        config.tenants.enabled = true;
+        config
+            .tenants
+            .keys
+            .insert(key_fingerprint("secret"), "analytics".to_string());
+        config.tenants.settings.insert(
+            "analytics".to_string(),
+            TenantSettings {
+                constants: [("vat".to_string(), "0.2".to_string())].into(),
+                functions: Some(vec!["sqrt".to_string()]),
+                ..Default::default()
+            },
+        );
+        let router = server(config).router();
+        let stream = |body: &'static str| {
+            Request::post("/evaluate/stream")
+                .header(auth::API_KEY_HEADER, "secret")
+                .body(Body::from(body))
+                .unwrap()
+        };
+
+        let own = router
+            .clone()
+            .oneshot(stream("sqrt(16) * vat"))
+            .await
+            .unwrap();
+        assert_eq!(own.status(), StatusCode::OK);
+        assert_eq!(json_body(own).await["result"], "0.8");
+        let denied = router.clone().oneshot(stream("1 + sin(0)")).await.unwrap();
+        assert_eq!(denied.status(), StatusCode::FORBIDDEN);
+
+        let over_quota = router.oneshot(stream("1")).await.unwrap();
+        assert_eq!(over_quota.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_headers() {
+        let mut config = AppConfig::default();
+        config.auth.api_keys = vec!["secret".to_string()];
+        config.quotas.enabled = true;
+        config.quotas.default.daily = Some(2);
+        let router = server(config).router();
+
+        let evaluate = || {
+            let mut request = mcp_request(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "method": "tools/call",
+                    "params": { "name": "evaluate", "arguments": { "expression": "1 + 1" } },
+                }),
+                None,
+            );
+            request
+                .headers_mut()
+                .insert(auth::API_KEY_HEADER, "secret".parse().unwrap());
+            request
+        };
+        for remaining in ["1", "0"] {
+            let response = router.clone().oneshot(evaluate()).await.unwrap();
+            let headers = response.headers();
+            assert_eq!(headers[usage::RATE_LIMIT_LIMIT_HEADER], "2");
+            assert_eq!(headers[usage::RATE_LIMIT_REMAINING_HEADER], remaining);
+            assert!(headers.contains_key(usage::RATE_LIMIT_RESET_HEADER));
+            let body = json_body(response).await;
+            assert_eq!(
+                body["result"]["_meta"]["rateLimit"]["remaining"].to_string(),
+                remaining
+            );
+        }
+
+        let exhausted = router.clone().oneshot(evaluate()).await.unwrap();
+        assert_eq!(exhausted.headers()[usage::RATE_LIMIT_REMAINING_HEADER], "0");
+
+        let anonymous = router
+            .oneshot(Request::get("/health").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert!(
+            !anonymous
+                .headers()
+                .contains_key(usage::RATE_LIMIT_LIMIT_HEADER)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_quotas_without_auth_share_one_anonymous_caller() {
+        let mut config = AppConfig::default();
+        config.quotas.enabled = true;
+        config.quotas.default.daily = Some(2);
+        let router = server(config).router();
+
+        let evaluate = |key: &str| {
+            let mut request = mcp_request(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "method": "tools/call",
+                    "params": { "name": "evaluate", "arguments": { "expression": "1 + 1" } },
+                }),
+                None,
+            );
+            request
+                .headers_mut()
+                .insert(auth::API_KEY_HEADER, key.parse().unwrap());
+            request
+        };
+        // Unchecked keys are not identities: every caller draws on one allowance
+        for (key, remaining) in [("first", "1"), ("second", "0")] {
+            let response = router.clone().oneshot(evaluate(key)).await.unwrap();
+            assert_eq!(
+                response.headers()[usage::RATE_LIMIT_REMAINING_HEADER],
+                remaining
+            );
+        }
+        let body = json_body(router.clone().oneshot(evaluate("third")).await.unwrap()).await;
+        assert_eq!(body["error"]["code"], QUOTA_EXCEEDED);
+
+        let usage = router
+            .oneshot(Request::get("/usage").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let usage = json_body(usage).await;
+        assert_eq!(usage["caller"], ANONYMOUS_CALLER);
+        assert_eq!(usage["periods"][0]["used"], 2);
+    }
+
+    #[tokio::test]
+    async fn test_router_requires_api_key() {
+        let mut config = AppConfig::default();
+        config.auth.api_keys = vec!["secret".to_string()];
+        let router = server(config).router();
+
+        let info = |key: Option<&str>| {
+            let mut request = Request::get("/info");
+            if let Some(key) = key {
+                request = request.header(auth::API_KEY_HEADER, key);
+            }
+            request.body(Body::empty()).unwrap()
+        };
+        let denied = router.clone().oneshot(info(None)).await.unwrap();
+        assert_eq!(denied.status(), StatusCode::UNAUTHORIZED);
+        let allowed = router.clone().oneshot(info(Some("secret"))).await.unwrap();
+        assert_eq!(allowed.status(), StatusCode::OK);
+        assert_eq!(json_body(allowed).await["name"], env!("CARGO_PKG_NAME"));
+
+        let health = router
+            .oneshot(Request::get("/health").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(health.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_ready_is_open_with_api_keys() {
+        let mut config = AppConfig::default();
+        config.auth.api_keys = vec!["secret".to_string()];
+        let server = server(config);
+        server.warm_up().await.unwrap();
+
+        let ready = server
+            .router()
+            .oneshot(Request::get("/ready").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(ready.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,16 @@
+use crate::http_server::{AppState, conditional};
+use axum::Router;
+use axum::extract::State;
+use axum::http::HeaderMap;
+use axum::response::Response;
+use axum::routing::get;
+
+/// The MCP tools as OpenAI function definitions, so orchestrators without MCP
+/// support can register them and forward calls to `tools/call`.
+pub fn router() -> Router<AppState> {
+    Router::new().route("/openai/tools", get(tools))
+}
+
+async fn tools(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    conditional::json(&headers, state.mcp.openai_tools())
+}
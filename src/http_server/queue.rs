@@ -0,0 +1,327 @@
+//! Admission of evaluations under load. A fixed number of workers evaluate
+//! at once; further requests wait in a bounded queue per priority class and
+//! are admitted interactive first, oldest first within a class. A request
+//! arriving at a full queue sheds the oldest waiting request of the lowest
+//! class present, which is answered `503` so its client backs off instead of
+//! timing out.
+
+use crate::app_config;
+use axum::http::StatusCode;
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+    /// MCP tool calls, with a client waiting on the answer
+    Interactive,
+    /// Regression checks and streamed machine-generated expressions
+    Batch,
+}
+
+impl Priority {
+    /// Admission order
+    pub const ALL: [Priority; 2] = [Priority::Interactive, Priority::Batch];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Priority::Interactive => "interactive",
+            Priority::Batch => "batch",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Upper bounds of the queue-time histogram buckets, in seconds.
+pub const WAIT_BUCKETS: [f64; 8] = [0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0];
+
+/// Time spent waiting for a worker, as a Prometheus histogram.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WaitHistogram {
+    /// Admissions within each of [`WAIT_BUCKETS`], cumulative
+    pub buckets: [u64; WAIT_BUCKETS.len()],
+    pub sum_secs: f64,
+    pub count: u64,
+}
+
+impl WaitHistogram {
+    fn record(&mut self, wait: Duration) {
+        let secs = wait.as_secs_f64();
+        for (bucket, bound) in self.buckets.iter_mut().zip(WAIT_BUCKETS) {
+            if secs <= bound {
+                *bucket += 1;
+            }
+        }
+        self.sum_secs += secs;
+        self.count += 1;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassMetrics {
+    pub priority: Priority,
+    /// Requests currently waiting
+    pub waiting: usize,
+    /// Requests dropped from the queue to make room
+    pub shed: u64,
+    pub wait: WaitHistogram,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueueMetrics {
+    pub workers: usize,
+    /// Evaluations currently holding a worker
+    pub running: usize,
+    pub classes: Vec<ClassMetrics>,
+}
+
+/// The request was shed, or the queue had no room for it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Overloaded;
+
+impl fmt::Display for Overloaded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Server overloaded, retry later")
+    }
+}
+
+impl std::error::Error for Overloaded {}
+
+impl From<Overloaded> for (StatusCode, String) {
+    fn from(err: Overloaded) -> Self {
+        (StatusCode::SERVICE_UNAVAILABLE, err.to_string())
+    }
+}
+
+#[derive(Default)]
+struct Class {
+    /// Oldest first; a waiter whose request was dropped leaves a closed sender
+    waiting: VecDeque<oneshot::Sender<Permit>>,
+    shed: u64,
+    wait: WaitHistogram,
+}
+
+#[derive(Default)]
+struct State {
+    running: usize,
+    /// Indexed by [`Priority`], in admission order
+    classes: [Class; 2],
+}
+
+struct Shared {
+    workers: usize,
+    capacity: usize,
+    state: Mutex<State>,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Hand a finished evaluation's worker to the next waiter, else free it.
+    fn release(self: &Arc<Self>) {
+        let mut state = self.lock();
+        for class in state.classes.iter_mut() {
+            while let Some(waiter) = class.waiting.pop_front() {
+                let permit = Permit {
+                    shared: Some(self.clone()),
+                };
+                match waiter.send(permit) {
+                    Ok(()) => return,
+                    // Its request is gone; the permit must not release again
+                    Err(mut permit) => permit.shared = None,
+                }
+            }
+        }
+        state.running -= 1;
+    }
+}
+
+/// A worker held until dropped.
+pub struct Permit {
+    shared: Option<Arc<Shared>>,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        if let Some(shared) = self.shared.take() {
+            shared.release();
+        }
+    }
+}
+
+pub struct EvalQueue {
+    shared: Arc<Shared>,
+}
+
+impl EvalQueue {
+    pub fn new(config: &app_config::Queue) -> Self {
+        let workers = match config.workers {
+            0 => thread::available_parallelism().map_or(1, |cpus| cpus.get()),
+            workers => workers,
+        };
+        EvalQueue {
+            shared: Arc::new(Shared {
+                workers,
+                capacity: config.capacity,
+                state: Mutex::new(State::default()),
+            }),
+        }
+    }
+
+    /// Wait for a worker. Fails when the request is shed to make room for
+    /// later ones; dropping the future gives up its place.
+    pub async fn acquire(&self, priority: Priority) -> Result<Permit, Overloaded> {
+        let enqueued = Instant::now();
+        let receiver = {
+            let mut state = self.shared.lock();
+            if state.running < self.shared.workers {
+                state.running += 1;
+                state.classes[priority.index()].wait.record(Duration::ZERO);
+                return Ok(Permit {
+                    shared: Some(self.shared.clone()),
+                });
+            }
+            let (sender, receiver) = oneshot::channel();
+            state.classes[priority.index()].waiting.push_back(sender);
+            shed_over_capacity(&mut state.classes, self.shared.capacity);
+            receiver
+        };
+        let permit = receiver.await.map_err(|_| Overloaded)?;
+        let wait = enqueued.elapsed();
+        self.shared.lock().classes[priority.index()]
+            .wait
+            .record(wait);
+        tracing::debug!(
+            priority = priority.label(),
+            wait_ms = wait.as_millis() as u64,
+            "Admitted queued evaluation"
+        );
+        Ok(permit)
+    }
+
+    pub fn metrics(&self) -> QueueMetrics {
+        let state = self.shared.lock();
+        QueueMetrics {
+            workers: self.shared.workers,
+            running: state.running,
+            classes: Priority::ALL
+                .into_iter()
+                .map(|priority| {
+                    let class = &state.classes[priority.index()];
+                    ClassMetrics {
+                        priority,
+                        waiting: class.waiting.iter().filter(|w| !w.is_closed()).count(),
+                        shed: class.shed,
+                        wait: class.wait.clone(),
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Drop abandoned waiters, then the oldest of the lowest class until at most
+/// `capacity` wait. A dropped sender fails its request with [`Overloaded`].
+fn shed_over_capacity(classes: &mut [Class; 2], capacity: usize) {
+    for class in classes.iter_mut() {
+        class.waiting.retain(|waiter| !waiter.is_closed());
+    }
+    while classes
+        .iter()
+        .map(|class| class.waiting.len())
+        .sum::<usize>()
+        > capacity
+    {
+        let Some(class) = classes
+            .iter_mut()
+            .rev()
+            .find(|class| !class.waiting.is_empty())
+        else {
+            return;
+        };
+        class.waiting.pop_front();
+        class.shed += 1;
+        tracing::warn!("Shed a queued evaluation under overload");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::task::{Context, Poll, Waker};
+
+    fn queue(workers: usize, capacity: usize) -> EvalQueue {
+        EvalQueue::new(&app_config::Queue {
+            enabled: true,
+            workers,
+            capacity,
+        })
+    }
+
+    /// Poll once so the request takes its place in the queue.
+    fn enqueue<F: Future + Unpin>(future: &mut F) -> Poll<F::Output> {
+        Pin::new(future).poll(&mut Context::from_waker(Waker::noop()))
+    }
+
+    #[tokio::test]
+    async fn test_interactive_requests_are_admitted_first() {
+        let queue = queue(1, 8);
+        let running = queue.acquire(Priority::Batch).await.unwrap();
+        let mut batch = Box::pin(queue.acquire(Priority::Batch));
+        let mut interactive = Box::pin(queue.acquire(Priority::Interactive));
+        assert!(enqueue(&mut batch).is_pending());
+        assert!(enqueue(&mut interactive).is_pending());
+        let metrics = queue.metrics();
+        assert_eq!(metrics.running, 1);
+        assert_eq!(metrics.classes[0].waiting, 1);
+        assert_eq!(metrics.classes[1].waiting, 1);
+
+        drop(running);
+        assert!(enqueue(&mut batch).is_pending());
+        let permit = interactive.await.unwrap();
+        drop(permit);
+        drop(batch.await.unwrap());
+
+        let metrics = queue.metrics();
+        assert_eq!(metrics.running, 0);
+        assert_eq!(metrics.classes[0].wait.count, 1);
+        assert_eq!(metrics.classes[1].wait.count, 2);
+        assert!(metrics.classes[1].wait.buckets[0] > 0);
+    }
+
+    #[tokio::test]
+    async fn test_full_queue_sheds_oldest_of_lowest_class() {
+        let queue = queue(1, 2);
+        let _running = queue.acquire(Priority::Interactive).await.unwrap();
+        let mut oldest = Box::pin(queue.acquire(Priority::Batch));
+        let mut newer = Box::pin(queue.acquire(Priority::Batch));
+        assert!(enqueue(&mut oldest).is_pending());
+        assert!(enqueue(&mut newer).is_pending());
+
+        let mut interactive = Box::pin(queue.acquire(Priority::Interactive));
+        assert!(enqueue(&mut interactive).is_pending());
+        assert_eq!(oldest.await.err(), Some(Overloaded));
+        assert!(enqueue(&mut newer).is_pending());
+        let metrics = queue.metrics();
+        assert_eq!(metrics.classes[1].shed, 1);
+        assert_eq!(metrics.classes[0].shed, 0);
+
+        // Abandoned requests give up their place instead of being shed
+        drop(newer);
+        let mut another = Box::pin(queue.acquire(Priority::Interactive));
+        assert!(enqueue(&mut another).is_pending());
+        assert_eq!(queue.metrics().classes[1].shed, 1);
+    }
+}
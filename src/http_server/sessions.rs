@@ -0,0 +1,55 @@
+use crate::http_server::AppState;
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde_json::{Value, json};
+
+/// REST access to session state, addressed by the MCP session id.
+pub fn router() -> Router<AppState> {
+    Router::new().route(
+        "/sessions/{id}/history",
+        get(list_history).delete(clear_history),
+    )
+}
+
+async fn list_history(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<Value>, (StatusCode, String)> {
+    check_tenant(&state, &headers, &id)?;
+    let entries = state
+        .sessions
+        .history(&id)
+        .map_err(|err| (StatusCode::NOT_FOUND, err.to_string()))?;
+    Ok(Json(json!({ "entries": entries })))
+}
+
+async fn clear_history(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<Value>, (StatusCode, String)> {
+    check_tenant(&state, &headers, &id)?;
+    let removed = state
+        .sessions
+        .clear_history(&id)
+        .map_err(|err| (StatusCode::NOT_FOUND, err.to_string()))?;
+    Ok(Json(json!({ "removed": removed })))
+}
+
+/// Sessions of other tenants are reported as unknown.
+fn check_tenant(
+    state: &AppState,
+    headers: &HeaderMap,
+    id: &str,
+) -> Result<(), (StatusCode, String)> {
+    let tenant = state
+        .tenant(headers)
+        .map_err(|err| (StatusCode::BAD_REQUEST, err.to_string()))?;
+    if state.tenancy.is_some() && !state.sessions.belongs_to(id, tenant.as_deref()) {
+        return Err((StatusCode::NOT_FOUND, format!("Unknown session: {id}")));
+    }
+    Ok(())
+}
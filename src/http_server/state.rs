@@ -0,0 +1,179 @@
+use crate::app_config::AppConfig;
+use crate::audit::{self, key_fingerprint};
+use crate::editor::DocumentStore;
+use crate::evaluator::{Environment, Limits};
+use crate::http_server::auth::presented_key;
+use crate::http_server::chaos::ChaosLayer;
+use crate::http_server::queue::{EvalQueue, Overloaded, Permit, Priority};
+use crate::logging::{self, LogLevelHandle};
+use crate::mcp::McpServer;
+use crate::mcp::idempotency::IdempotencyCache;
+use crate::quota::QuotaTracker;
+use crate::session::{SessionStore, now_ms};
+use crate::tenant::{Tenancy, quota_subject};
+use crate::warmup;
+use crate::worker::WorkerPool;
+use axum::http::{HeaderMap, StatusCode};
+use serde_json::Value;
+use std::sync::{Arc, OnceLock};
+
+/// Services shared by the HTTP handlers, built once from the configuration and
+/// handed to every route through axum's `State`.
+#[derive(Clone)]
+pub struct AppState {
+    pub config: Arc<AppConfig>,
+    /// Evaluator limits resolved from `config.evaluator`
+    pub limits: Limits,
+    /// Session store; its counters back `/metrics`
+    pub sessions: Arc<SessionStore>,
+    /// Per-caller quotas, when enabled
+    pub quotas: Option<Arc<QuotaTracker>>,
+    pub mcp: Arc<McpServer>,
+    /// Parse sessions behind `/documents`
+    pub documents: Arc<DocumentStore>,
+    /// Runtime log filter behind `/admin/log-level`
+    pub log_level: LogLevelHandle,
+    /// Tenant resolution and per-tenant counters, when tenants are enabled
+    pub tenancy: Option<Arc<Tenancy>>,
+    /// Set once the warm-up self-test has passed; `/ready` answers 503 until then
+    pub readiness: Arc<OnceLock<warmup::Report>>,
+    /// Fault injection, a pass-through unless `chaos.enabled`
+    pub chaos: ChaosLayer,
+    /// Evaluation admission, when `queue.enabled`
+    pub queue: Option<Arc<EvalQueue>>,
+    /// Worker processes evaluating sessionless calls, when `workers.enabled`
+    pub workers: Option<Arc<WorkerPool>>,
+}
+
+impl AppState {
+    /// Open the session store and audit sink described by `config` and set up
+    /// an MCP server with the default tools on top of them. The log filter is
+    /// detached from the global subscriber until [`AppState::with_log_level`]
+    /// hands in the installed one.
+    pub fn new(config: Arc<AppConfig>) -> anyhow::Result<Self> {
+        let sessions = Arc::new(SessionStore::from_config(config.sessions.clone())?);
+        let quotas = config
+            .quotas
+            .enabled
+            .then(|| Arc::new(QuotaTracker::new(config.quotas.clone())));
+        let tenancy = Tenancy::from_config(&config.tenants)?.map(Arc::new);
+        let workers = WorkerPool::from_config(&config)?.map(Arc::new);
+        let mcp = Arc::new(
+            McpServer::with_default_tools(sessions.clone())
+                .with_audit(audit::from_config(&config.audit)?)
+                .with_quotas(quotas.clone())
+                .with_privacy(config.privacy.clone())
+                .with_shadow(&config.shadow)
+                .with_idempotency(IdempotencyCache::from_config(&config.idempotency))
+                .with_tenancy(tenancy.clone())
+                .with_workers(workers.clone())
+                .with_limits(config.evaluator.limits()),
+        );
+        if let (Some(tenancy), Some(quotas)) = (&tenancy, &quotas) {
+            quotas.set_overrides(tenancy.quota_overrides());
+        }
+        Ok(AppState {
+            limits: config.evaluator.limits(),
+            documents: Arc::new(DocumentStore::new(config.documents.clone())),
+            log_level: logging::detached(&config.logging)?,
+            chaos: ChaosLayer::new(&config.chaos)?,
+            queue: config
+                .queue
+                .enabled
+                .then(|| Arc::new(EvalQueue::new(&config.queue))),
+            config,
+            sessions,
+            quotas,
+            mcp,
+            tenancy,
+            readiness: Arc::new(OnceLock::new()),
+            workers,
+        })
+    }
+
+    /// Who a request is charged and audited as: its API key's fingerprint,
+    /// or one shared anonymous caller while authentication is off, when an
+    /// unchecked key would open a new quota entry on every request.
+    pub fn caller(&self, headers: &HeaderMap) -> Option<String> {
+        if self.config.auth.api_keys.is_empty() {
+            return Some(audit::ANONYMOUS_CALLER.to_string());
+        }
+        presented_key(headers).map(key_fingerprint)
+    }
+
+    /// The tenant a request belongs to, or `None` when tenants are disabled.
+    pub fn tenant(&self, headers: &HeaderMap) -> anyhow::Result<Option<String>> {
+        let Some(tenancy) = &self.tenancy else {
+            return Ok(None);
+        };
+        let caller = self.caller(headers);
+        let requested = headers
+            .get(tenancy.header())
+            .map(|value| value.to_str())
+            .transpose()?;
+        let trust_header = self.config.auth.api_keys.is_empty();
+        tenancy
+            .resolve(caller.as_deref(), requested, trust_header)
+            .map(Some)
+    }
+
+    /// The checks `tools/call` makes, for evaluations served outside MCP:
+    /// resolve the tenant, hold `arguments` to its function allowlist and
+    /// charge the caller's quota. Returns the tenant, if any, and the
+    /// constants to evaluate with.
+    pub fn admit_evaluation(
+        &self,
+        headers: &HeaderMap,
+        arguments: &Value,
+    ) -> Result<(Option<String>, Environment), (StatusCode, String)> {
+        let tenant = self
+            .tenant(headers)
+            .map_err(|err| (StatusCode::BAD_REQUEST, err.to_string()))?;
+        if let (Some(tenancy), Some(tenant)) = (&self.tenancy, &tenant) {
+            tenancy.record_tool_call(tenant);
+            tenancy
+                .check_functions(tenant, arguments)
+                .map_err(|err| (StatusCode::FORBIDDEN, err.to_string()))?;
+        }
+        let caller = self.caller(headers);
+        if let (Some(quotas), Some(subject)) = (
+            &self.quotas,
+            quota_subject(self.tenancy.as_deref(), caller.as_deref()),
+        ) {
+            quotas
+                .try_consume(&subject, now_ms() / 1000)
+                .map_err(|exceeded| (StatusCode::TOO_MANY_REQUESTS, exceeded.to_string()))?;
+        }
+        let constants = match (&self.tenancy, &tenant) {
+            (Some(tenancy), Some(tenant)) => tenancy.constants(tenant),
+            _ => None,
+        };
+        Ok((tenant, constants.unwrap_or_default()))
+    }
+
+    /// Read tenant settings from their store again and apply their quotas.
+    /// Returns how many tenants have settings; blocks on the store.
+    pub fn reload_tenants(&self) -> anyhow::Result<usize> {
+        let Some(tenancy) = &self.tenancy else {
+            return Ok(0);
+        };
+        let count = tenancy.reload()?;
+        if let Some(quotas) = &self.quotas {
+            quotas.set_overrides(tenancy.quota_overrides());
+        }
+        Ok(count)
+    }
+
+    /// Wait for a worker to evaluate on; `None` when queueing is disabled.
+    pub async fn admit(&self, priority: Priority) -> Result<Option<Permit>, Overloaded> {
+        match &self.queue {
+            Some(queue) => queue.acquire(priority).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    pub fn with_log_level(mut self, log_level: LogLevelHandle) -> Self {
+        self.log_level = log_level;
+        self
+    }
+}
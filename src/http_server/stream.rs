@@ -0,0 +1,109 @@
+use crate::evaluator::stream::StreamTokenizer;
+use crate::evaluator::{self, CancellationToken, EvalContext, Token};
+use crate::http_server::AppState;
+use crate::http_server::queue::Priority;
+use crate::tenant::Tenancy;
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::post;
+use axum::{Json, Router};
+use http_body_util::{BodyExt, LengthLimitError};
+use serde_json::{Value, json};
+use std::error::Error;
+use std::sync::Arc;
+
+/// Evaluate a machine-generated expression too large to buffer comfortably.
+/// The request body is the plain infix expression itself, usually sent
+/// chunked; it is tokenized as it arrives, so only the tokens are held. Up to
+/// `http_server.max_body_bytes` are accepted.
+pub fn router() -> Router<AppState> {
+    Router::new().route("/evaluate/stream", post(evaluate_stream))
+}
+
+async fn evaluate_stream(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<Json<Value>, (StatusCode, String)> {
+    let unprocessable = |err: anyhow::Error| (StatusCode::UNPROCESSABLE_ENTITY, err.to_string());
+    // Admitted before a byte of the body is read; the allowlist is checked as
+    // functions arrive
+    let (tenant, constants) = state.admit_evaluation(&headers, &Value::Null)?;
+    let allowlist = state.tenancy.clone().zip(tenant);
+    let mut body = body;
+    let mut tokenizer = StreamTokenizer::new().with_limits(state.limits);
+    while let Some(frame) = body.frame().await {
+        let frame = frame.map_err(|err| {
+            if is_length_limit(&err) {
+                (StatusCode::PAYLOAD_TOO_LARGE, err.to_string())
+            } else {
+                (
+                    StatusCode::BAD_REQUEST,
+                    format!("Failed to read body: {err}"),
+                )
+            }
+        })?;
+        if let Ok(data) = frame.into_data() {
+            let checked = tokenizer.tokens().len();
+            // Tokenizing is linear in the chunk but chunks can be large
+            tokenizer =
+                tokio::task::spawn_blocking(move || tokenizer.feed(&data).map(|()| tokenizer))
+                    .await
+                    .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?
+                    .map_err(unprocessable)?;
+            check_functions(allowlist.as_ref(), &tokenizer.tokens()[checked..])?;
+        }
+    }
+    let checked = tokenizer.tokens().len();
+    let tokens = tokio::task::spawn_blocking(move || tokenizer.finish())
+        .await
+        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?
+        .map_err(unprocessable)?;
+    check_functions(allowlist.as_ref(), &tokens[checked..])?;
+
+    let permit = state.admit(Priority::Batch).await?;
+    // Cancel the evaluation if the client disconnects and this future is dropped
+    let cancellation = CancellationToken::new();
+    let guard = cancellation.drop_guard();
+    let ctx = EvalContext::new(state.limits).with_cancellation(cancellation);
+    let result = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        evaluator::eval_tokens(&tokens, &constants, &ctx)
+    })
+    .await
+    .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+    guard.disarm();
+    let value = result.map_err(unprocessable)?;
+    Ok(Json(json!({ "result": value.to_string() })))
+}
+
+/// Reject the first function in `tokens` outside the allowlist of the
+/// request's tenant, if it has one.
+fn check_functions(
+    allowlist: Option<&(Arc<Tenancy>, String)>,
+    tokens: &[Token],
+) -> Result<(), (StatusCode, String)> {
+    let Some((tenancy, tenant)) = allowlist else {
+        return Ok(());
+    };
+    for token in tokens {
+        if let Token::Func(function) = token {
+            tenancy
+                .check_function(tenant, *function)
+                .map_err(|err| (StatusCode::FORBIDDEN, err.to_string()))?;
+        }
+    }
+    Ok(())
+}
+
+fn is_length_limit(err: &axum::Error) -> bool {
+    let mut source: Option<&(dyn Error + 'static)> = Some(err);
+    while let Some(err) = source {
+        if err.is::<LengthLimitError>() {
+            return true;
+        }
+        source = err.source();
+    }
+    false
+}
@@ -0,0 +1,71 @@
+use crate::http_server::AppState;
+use crate::session::now_ms;
+use crate::tenant::quota_subject;
+use axum::extract::{Request, State};
+use axum::http::{HeaderMap, HeaderValue, StatusCode};
+use axum::middleware::Next;
+use axum::response::Response;
+use axum::routing::get;
+use axum::{Json, Router};
+use serde_json::{Value, json};
+
+pub const RATE_LIMIT_LIMIT_HEADER: &str = "x-ratelimit-limit";
+pub const RATE_LIMIT_REMAINING_HEADER: &str = "x-ratelimit-remaining";
+pub const RATE_LIMIT_RESET_HEADER: &str = "x-ratelimit-reset";
+
+/// Current quota usage for the API key presented with the request, or for its
+/// tenant when tenants are enabled.
+pub fn router() -> Router<AppState> {
+    Router::new().route("/usage", get(usage))
+}
+
+async fn usage(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, (StatusCode, &'static str)> {
+    let quotas = state
+        .quotas
+        .as_ref()
+        .ok_or((StatusCode::NOT_FOUND, "Quotas are not enabled"))?;
+    state
+        .tenant(&headers)
+        .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid tenant"))?;
+    let caller = quota_subject(state.tenancy.as_deref(), state.caller(&headers).as_deref())
+        .ok_or((StatusCode::UNAUTHORIZED, "Usage is tracked per API key"))?;
+    let periods = quotas.usage(&caller, now_ms() / 1000);
+    Ok(Json(json!({ "caller": caller, "periods": periods })))
+}
+
+/// Report the tightest quota of the presented API key as `X-RateLimit-Limit`,
+/// `X-RateLimit-Remaining` and `X-RateLimit-Reset` (Unix seconds), counted
+/// after the request. Unlimited callers, and probes sent without a key, get no
+/// headers.
+pub async fn rate_limit_headers(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let caller = quota_subject(
+        state.tenancy.as_deref(),
+        state.caller(request.headers()).as_deref(),
+    );
+    let mut response = next.run(request).await;
+    let (Some(quotas), Some(caller)) = (&state.quotas, caller) else {
+        return response;
+    };
+    let Some(tightest) = quotas.tightest(&caller, now_ms() / 1000) else {
+        return response;
+    };
+    let headers = response.headers_mut();
+    for (name, value) in [
+        (RATE_LIMIT_LIMIT_HEADER, tightest.limit.unwrap_or_default()),
+        (
+            RATE_LIMIT_REMAINING_HEADER,
+            tightest.remaining().unwrap_or_default(),
+        ),
+        (RATE_LIMIT_RESET_HEADER, tightest.reset_at),
+    ] {
+        headers.insert(name, HeaderValue::from(value));
+    }
+    response
+}
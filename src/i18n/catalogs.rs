@@ -0,0 +1,259 @@
+//! English message templates and their translations, most specific first
+//! where one template could match another's messages.
+
+type Catalog = &'static [(&'static str, &'static str)];
+
+pub const GERMAN: Catalog = &[
+    ("Division by zero", "Division durch null"),
+    ("Modulo by zero", "Modulo durch null"),
+    ("Unknown variable: {}", "Unbekannte Variable: {}"),
+    ("Unknown function: {}", "Unbekannte Funktion: {}"),
+    (
+        "Unknown math constant: {}",
+        "Unbekannte mathematische Konstante: {}",
+    ),
+    (
+        "Not enough operands for operator",
+        "Zu wenige Operanden für den Operator",
+    ),
+    ("Mismatched parentheses", "Klammern passen nicht zusammen"),
+    (
+        "Wrong number of arguments for {}: {}",
+        "Falsche Anzahl von Argumenten für {}: {}",
+    ),
+    (
+        "Square root of a negative number",
+        "Quadratwurzel einer negativen Zahl",
+    ),
+    (
+        "Logarithm of a non-positive number",
+        "Logarithmus einer nicht positiven Zahl",
+    ),
+    (
+        "Tangent of an odd multiple of a right angle",
+        "Tangens eines ungeraden Vielfachen eines rechten Winkels",
+    ),
+    (
+        "{} is only defined for non-negative integers",
+        "{} ist nur für nichtnegative ganze Zahlen definiert",
+    ),
+    (
+        "{} is only defined for integers",
+        "{} ist nur für ganze Zahlen definiert",
+    ),
+    (
+        "Result out of range for {}",
+        "Ergebnis außerhalb des Wertebereichs für {}",
+    ),
+    (
+        "Overflow: result would have about {} digits (limit {})",
+        "Überlauf: Das Ergebnis hätte etwa {} Stellen (Grenze {})",
+    ),
+    (
+        "Overflow: result would have a decimal exponent of about {} (limit {})",
+        "Überlauf: Das Ergebnis hätte einen Dezimalexponenten von etwa {} (Grenze {})",
+    ),
+    (
+        "Expression is nested too deeply (limit {})",
+        "Ausdruck ist zu tief verschachtelt (Grenze {})",
+    ),
+    (
+        "Expression is too expensive to evaluate: estimated cost {} exceeds the limit of {}",
+        "Ausdruck ist zu aufwendig: Die geschätzten Kosten {} überschreiten die Grenze von {}",
+    ),
+    (
+        "Expression needs too much memory: about {} bytes exceeds the limit of {}",
+        "Ausdruck benötigt zu viel Speicher: Etwa {} Bytes überschreiten die Grenze von {}",
+    ),
+    (
+        "Evaluation took too long and was stopped (limit {} ms)",
+        "Auswertung dauerte zu lange und wurde angehalten (Grenze {} ms)",
+    ),
+    ("Evaluation was cancelled", "Auswertung wurde abgebrochen"),
+    (
+        "Unexpected character: {} at {}..{}",
+        "Unerwartetes Zeichen: {} bei {}..{}",
+    ),
+    ("Malformed number at {}..{}", "Ungültige Zahl bei {}..{}"),
+    (
+        "Missing exponent digits at {}..{}",
+        "Fehlende Ziffern im Exponenten bei {}..{}",
+    ),
+    (
+        "Unterminated comment at {}..{}",
+        "Nicht abgeschlossener Kommentar bei {}..{}",
+    ),
+    ("Unknown tool: {}", "Unbekanntes Werkzeug: {}"),
+    ("Missing tool name", "Werkzeugname fehlt"),
+    ("Method not found: {}", "Methode nicht gefunden: {}"),
+];
+
+pub const FRENCH: Catalog = &[
+    ("Division by zero", "Division par zéro"),
+    ("Modulo by zero", "Modulo par zéro"),
+    ("Unknown variable: {}", "Variable inconnue : {}"),
+    ("Unknown function: {}", "Fonction inconnue : {}"),
+    (
+        "Unknown math constant: {}",
+        "Constante mathématique inconnue : {}",
+    ),
+    (
+        "Not enough operands for operator",
+        "Pas assez d'opérandes pour l'opérateur",
+    ),
+    ("Mismatched parentheses", "Parenthèses mal appariées"),
+    (
+        "Wrong number of arguments for {}: {}",
+        "Nombre d'arguments incorrect pour {} : {}",
+    ),
+    (
+        "Square root of a negative number",
+        "Racine carrée d'un nombre négatif",
+    ),
+    (
+        "Logarithm of a non-positive number",
+        "Logarithme d'un nombre non positif",
+    ),
+    (
+        "Tangent of an odd multiple of a right angle",
+        "Tangente d'un multiple impair d'un angle droit",
+    ),
+    (
+        "{} is only defined for non-negative integers",
+        "{} n'est défini que pour les entiers positifs ou nuls",
+    ),
+    (
+        "{} is only defined for integers",
+        "{} n'est défini que pour les entiers",
+    ),
+    (
+        "Result out of range for {}",
+        "Résultat hors limites pour {}",
+    ),
+    (
+        "Overflow: result would have about {} digits (limit {})",
+        "Dépassement : le résultat aurait environ {} chiffres (limite {})",
+    ),
+    (
+        "Overflow: result would have a decimal exponent of about {} (limit {})",
+        "Dépassement : le résultat aurait un exposant décimal d'environ {} (limite {})",
+    ),
+    (
+        "Expression is nested too deeply (limit {})",
+        "Expression trop profondément imbriquée (limite {})",
+    ),
+    (
+        "Expression is too expensive to evaluate: estimated cost {} exceeds the limit of {}",
+        "Expression trop coûteuse à évaluer : le coût estimé {} dépasse la limite de {}",
+    ),
+    (
+        "Expression needs too much memory: about {} bytes exceeds the limit of {}",
+        "Expression trop gourmande en mémoire : environ {} octets dépassent la limite de {}",
+    ),
+    (
+        "Evaluation took too long and was stopped (limit {} ms)",
+        "L'évaluation a pris trop de temps et a été interrompue (limite {} ms)",
+    ),
+    ("Evaluation was cancelled", "L'évaluation a été annulée"),
+    (
+        "Unexpected character: {} at {}..{}",
+        "Caractère inattendu : {} en {}..{}",
+    ),
+    ("Malformed number at {}..{}", "Nombre mal formé en {}..{}"),
+    (
+        "Missing exponent digits at {}..{}",
+        "Chiffres de l'exposant manquants en {}..{}",
+    ),
+    (
+        "Unterminated comment at {}..{}",
+        "Commentaire non terminé en {}..{}",
+    ),
+    ("Unknown tool: {}", "Outil inconnu : {}"),
+    ("Missing tool name", "Nom d'outil manquant"),
+    ("Method not found: {}", "Méthode introuvable : {}"),
+];
+
+pub const SPANISH: Catalog = &[
+    ("Division by zero", "División por cero"),
+    ("Modulo by zero", "Módulo por cero"),
+    ("Unknown variable: {}", "Variable desconocida: {}"),
+    ("Unknown function: {}", "Función desconocida: {}"),
+    (
+        "Unknown math constant: {}",
+        "Constante matemática desconocida: {}",
+    ),
+    (
+        "Not enough operands for operator",
+        "Faltan operandos para el operador",
+    ),
+    ("Mismatched parentheses", "Paréntesis desparejados"),
+    (
+        "Wrong number of arguments for {}: {}",
+        "Número incorrecto de argumentos para {}: {}",
+    ),
+    (
+        "Square root of a negative number",
+        "Raíz cuadrada de un número negativo",
+    ),
+    (
+        "Logarithm of a non-positive number",
+        "Logaritmo de un número no positivo",
+    ),
+    (
+        "Tangent of an odd multiple of a right angle",
+        "Tangente de un múltiplo impar de un ángulo recto",
+    ),
+    (
+        "{} is only defined for non-negative integers",
+        "{} solo está definido para enteros no negativos",
+    ),
+    (
+        "{} is only defined for integers",
+        "{} solo está definido para enteros",
+    ),
+    (
+        "Result out of range for {}",
+        "Resultado fuera de rango para {}",
+    ),
+    (
+        "Overflow: result would have about {} digits (limit {})",
+        "Desbordamiento: el resultado tendría unos {} dígitos (límite {})",
+    ),
+    (
+        "Overflow: result would have a decimal exponent of about {} (limit {})",
+        "Desbordamiento: el resultado tendría un exponente decimal de unos {} (límite {})",
+    ),
+    (
+        "Expression is nested too deeply (limit {})",
+        "La expresión está anidada a demasiada profundidad (límite {})",
+    ),
+    (
+        "Expression is too expensive to evaluate: estimated cost {} exceeds the limit of {}",
+        "La expresión es demasiado costosa de evaluar: el coste estimado {} supera el límite de {}",
+    ),
+    (
+        "Expression needs too much memory: about {} bytes exceeds the limit of {}",
+        "La expresión necesita demasiada memoria: unos {} bytes superan el límite de {}",
+    ),
+    (
+        "Evaluation took too long and was stopped (limit {} ms)",
+        "La evaluación tardó demasiado y se detuvo (límite {} ms)",
+    ),
+    ("Evaluation was cancelled", "La evaluación fue cancelada"),
+    (
+        "Unexpected character: {} at {}..{}",
+        "Carácter inesperado: {} en {}..{}",
+    ),
+    ("Malformed number at {}..{}", "Número mal formado en {}..{}"),
+    (
+        "Missing exponent digits at {}..{}",
+        "Faltan los dígitos del exponente en {}..{}",
+    ),
+    (
+        "Unterminated comment at {}..{}",
+        "Comentario sin cerrar en {}..{}",
+    ),
+    ("Unknown tool: {}", "Herramienta desconocida: {}"),
+    ("Missing tool name", "Falta el nombre de la herramienta"),
+    ("Method not found: {}", "Método no encontrado: {}"),
+];
@@ -0,0 +1,169 @@
+//! Error messages in the caller's language. Messages are written in English
+//! throughout; a catalog per language maps each English template, with `{}`
+//! standing for the parts that vary, to its translation. Only the text meant
+//! for people changes: JSON-RPC error codes and structured error data stay as
+//! they are, so programs can keep matching on them. Messages missing from a
+//! catalog are left in English.
+
+use std::borrow::Cow;
+
+mod catalogs;
+
+/// Header carrying the languages an HTTP client prefers.
+pub const ACCEPT_LANGUAGE_HEADER: &str = "accept-language";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Locale {
+    #[default]
+    English,
+    German,
+    French,
+    Spanish,
+}
+
+impl Locale {
+    /// The language of a BCP 47 tag such as `de` or `fr-CA`, by its primary
+    /// subtag; `None` when there is no catalog for it.
+    pub fn from_tag(tag: &str) -> Option<Locale> {
+        let primary = tag.trim().split(['-', '_']).next()?;
+        match primary.to_ascii_lowercase().as_str() {
+            "en" => Some(Locale::English),
+            "de" => Some(Locale::German),
+            "fr" => Some(Locale::French),
+            "es" => Some(Locale::Spanish),
+            _ => None,
+        }
+    }
+
+    /// The supported language an `Accept-Language` value ranks highest, e.g.
+    /// German for `fr-CH;q=0.5, de;q=0.8`; `None` when none is supported.
+    pub fn negotiate(accept_language: &str) -> Option<Locale> {
+        let mut ranked: Vec<(f32, Locale)> = accept_language
+            .split(',')
+            .filter_map(|entry| {
+                let mut parts = entry.split(';');
+                let locale = Locale::from_tag(parts.next()?)?;
+                let quality = parts
+                    .find_map(|param| param.trim().strip_prefix("q="))
+                    .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok())?;
+                (quality > 0.0).then_some((quality, locale))
+            })
+            .collect();
+        // Stable, so equally ranked languages keep the client's order
+        ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+        ranked.first().map(|(_, locale)| *locale)
+    }
+
+    /// `message` in this language, or unchanged when the catalog lacks it.
+    pub fn translate(self, message: &str) -> Cow<'_, str> {
+        let catalog = match self {
+            Locale::English => return Cow::Borrowed(message),
+            Locale::German => catalogs::GERMAN,
+            Locale::French => catalogs::FRENCH,
+            Locale::Spanish => catalogs::SPANISH,
+        };
+        for (english, translated) in catalog {
+            if let Some(args) = capture(english, message) {
+                return Cow::Owned(fill(translated, &args));
+            }
+        }
+        Cow::Borrowed(message)
+    }
+}
+
+/// The parts of `message` standing in for the `{}` of `template`, or `None`
+/// when it does not match. Each part ends at the first occurrence of the
+/// text after it.
+fn capture<'a>(template: &str, message: &'a str) -> Option<Vec<&'a str>> {
+    let mut literals = template.split("{}");
+    let mut rest = message.strip_prefix(literals.next().unwrap_or_default())?;
+    let literals: Vec<&str> = literals.collect();
+    let Some((last, middle)) = literals.split_last() else {
+        return rest.is_empty().then(Vec::new);
+    };
+    let mut args = Vec::with_capacity(literals.len());
+    for literal in middle {
+        let end = rest.find(literal)?;
+        args.push(&rest[..end]);
+        rest = &rest[end + literal.len()..];
+    }
+    args.push(rest.strip_suffix(last)?);
+    Some(args)
+}
+
+fn fill(template: &str, args: &[&str]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut args = args.iter();
+    let mut literals = template.split("{}").peekable();
+    while let Some(literal) = literals.next() {
+        out.push_str(literal);
+        if literals.peek().is_some() {
+            out.push_str(args.next().copied().unwrap_or_default());
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiate() {
+        assert_eq!(
+            Locale::negotiate("de-DE,de;q=0.9,en;q=0.8"),
+            Some(Locale::German)
+        );
+        assert_eq!(
+            Locale::negotiate("fr-CH;q=0.5, es;q=0.8"),
+            Some(Locale::Spanish)
+        );
+        assert_eq!(Locale::negotiate("ja, fr"), Some(Locale::French));
+        assert_eq!(Locale::negotiate("de;q=0, *"), None);
+        assert_eq!(Locale::negotiate("en-US"), Some(Locale::English));
+        assert_eq!(Locale::from_tag("pt_BR"), None);
+    }
+
+    #[test]
+    fn test_translate() {
+        assert_eq!(
+            Locale::German.translate("Division by zero"),
+            "Division durch null"
+        );
+        assert_eq!(
+            Locale::French.translate("Unknown variable: x_1"),
+            "Variable inconnue : x_1"
+        );
+        assert_eq!(
+            Locale::Spanish.translate("Unexpected character: @ at 2..3"),
+            "Carácter inesperado: @ en 2..3"
+        );
+        assert_eq!(
+            Locale::German.translate("Expression is nested too deeply (limit 16)"),
+            "Ausdruck ist zu tief verschachtelt (Grenze 16)"
+        );
+        assert_eq!(Locale::German.translate("Something new"), "Something new");
+        assert_eq!(
+            Locale::English.translate("Division by zero"),
+            "Division by zero"
+        );
+        assert_eq!(
+            Locale::German.translate("Division by zero!"),
+            "Division by zero!"
+        );
+    }
+
+    #[test]
+    fn test_catalogs_keep_placeholders() {
+        for catalog in [catalogs::GERMAN, catalogs::FRENCH, catalogs::SPANISH] {
+            for (english, translated) in catalog {
+                assert_eq!(
+                    english.matches("{}").count(),
+                    translated.matches("{}").count(),
+                    "{english}"
+                );
+                assert!(!english.contains("{}{}"), "{english}");
+            }
+        }
+    }
+}
@@ -1,29 +1,73 @@
 use std::sync::Arc;
 
-use tracing_subscriber::{EnvFilter, fmt::time::UtcTime};
-
-use crate::{app_config::AppConfig, http_server::HttpServer};
+use crate::{
+    app_config::AppConfig,
+    cli::{Cli, Command},
+    http_server::HttpServer,
+};
 
 pub mod app_config;
+pub mod audit;
+pub mod batch;
+pub mod check;
+pub mod cli;
+// Always built for tests, so `cargo test` checks the shipped cases
+#[cfg(any(feature = "conformance", test))]
+pub mod conformance;
+pub mod editor;
+pub mod equivalence;
 pub mod evaluator;
+pub mod formatter;
 pub mod http_server;
+pub mod i18n;
+pub mod logging;
+pub mod mcp;
+pub mod plot;
+pub mod quota;
+pub mod repl;
+pub mod result_cache;
+pub mod session;
+pub mod tenant;
+pub mod warmup;
+pub mod worker;
+
+pub async fn run(cli: Cli) -> anyhow::Result<()> {
+    // Workers are configured by the server that starts them
+    if let Some(Command::Worker) = cli.command {
+        return worker::serve();
+    }
+
+    let app_config = if cli.env_only {
+        AppConfig::load_env_only(&cli.config_overrides())?
+    } else {
+        AppConfig::load(cli.config.as_deref(), &cli.config_overrides())?
+    };
 
-pub fn init() -> anyhow::Result<HttpServer> {
-    init_tracing();
+    result_cache::install(&app_config.result_cache)?;
 
-    let app_config = Arc::new(AppConfig::new_from_file("config.toml")?);
-    let http_server = HttpServer::new(app_config.clone());
-    Ok(http_server)
+    if cli.print_config {
+        println!("{}", serde_json::to_string_pretty(&app_config.redacted())?);
+        return Ok(());
+    }
+
+    match &cli.command {
+        Some(Command::Batch(args)) => return batch::run(args, app_config.evaluator.limits()),
+        Some(Command::Check(args)) => return check::run(args).await,
+        Some(Command::Repl) => return repl::run(&app_config),
+        Some(Command::Worker) => unreachable!("workers return before loading settings"),
+        Some(Command::Serve) | None => {}
+    }
+
+    let env_only = cli.env_only;
+    let http_server = init(app_config)?;
+    if env_only {
+        http_server.log_config();
+    }
+    http_server.start().await
 }
 
-fn init_tracing() {
-    tracing_subscriber::fmt()
-        .with_env_filter(EnvFilter::from_default_env())
-        .with_timer(UtcTime::rfc_3339())
-        .with_target(true)
-        .with_level(true)
-        .with_file(true)
-        .with_line_number(true)
-        .with_ansi(true)
-        .init();
+pub fn init(app_config: AppConfig) -> anyhow::Result<HttpServer> {
+    let log_level = logging::init(&app_config.logging)?;
+
+    HttpServer::new(Arc::new(app_config), log_level)
 }
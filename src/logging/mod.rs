@@ -0,0 +1,124 @@
+use crate::app_config::Logging;
+use anyhow::anyhow;
+use std::sync::Arc;
+use tracing::Subscriber;
+use tracing_subscriber::{
+    EnvFilter, Registry, fmt::time::UtcTime, layer::SubscriberExt, reload, util::SubscriberInitExt,
+};
+
+pub mod spans;
+
+/// Handle for swapping the active log filter while the server is running.
+#[derive(Clone)]
+pub struct LogLevelHandle {
+    inner: Arc<reload::Handle<EnvFilter, Registry>>,
+    /// Keeps the filter alive for a handle not installed as the global subscriber
+    _subscriber: Option<Arc<dyn Subscriber + Send + Sync>>,
+}
+
+impl LogLevelHandle {
+    pub fn current(&self) -> anyhow::Result<String> {
+        self.inner
+            .with_current(|filter| filter.to_string())
+            .map_err(|err| anyhow!("Failed to read log filter: {err}"))
+    }
+
+    /// Replace the active filter with a new directive string such as `debug` or
+    /// `calculator_mcp=trace,tower_http=info`.
+    pub fn set(&self, directives: &str) -> anyhow::Result<()> {
+        let filter = EnvFilter::try_new(directives)?;
+        self.inner
+            .reload(filter)
+            .map_err(|err| anyhow!("Failed to apply log filter: {err}"))
+    }
+}
+
+/// `RUST_LOG` wins when set; otherwise `logging.filter`, falling back to `logging.level`.
+pub fn initial_directives(config: &Logging) -> String {
+    if let Ok(env) = std::env::var(EnvFilter::DEFAULT_ENV)
+        && !env.trim().is_empty()
+    {
+        return env;
+    }
+    config
+        .filter
+        .clone()
+        .unwrap_or_else(|| config.level.clone())
+}
+
+pub fn init(config: &Logging) -> anyhow::Result<LogLevelHandle> {
+    let filter = EnvFilter::try_new(initial_directives(config))?;
+    let (filter_layer, handle) = reload::Layer::new(filter);
+
+    tracing_subscriber::registry()
+        .with(filter_layer)
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_timer(UtcTime::rfc_3339())
+                .with_target(true)
+                .with_level(true)
+                .with_file(true)
+                .with_line_number(true)
+                .with_ansi(true),
+        )
+        .try_init()?;
+
+    Ok(LogLevelHandle {
+        inner: Arc::new(handle),
+        _subscriber: None,
+    })
+}
+
+/// Log plain lines to stderr under `directives`, for processes whose stdout
+/// carries data, such as workers. Keeps a subscriber that is already installed.
+pub fn init_stderr(directives: &str) -> anyhow::Result<()> {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::try_new(directives)?)
+        .with_writer(std::io::stderr)
+        .with_ansi(false)
+        .try_init();
+    Ok(())
+}
+
+/// A handle on a private subscriber instead of the global one, for servers
+/// embedded in an app with its own tracing setup and for tests. The filter
+/// can be read and replaced but affects no output.
+pub fn detached(config: &Logging) -> anyhow::Result<LogLevelHandle> {
+    let filter = EnvFilter::try_new(initial_directives(config))?;
+    let (filter_layer, handle) = reload::Layer::new(filter);
+    Ok(LogLevelHandle {
+        inner: Arc::new(handle),
+        _subscriber: Some(Arc::new(tracing_subscriber::registry().with(filter_layer))),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+
+    #[test]
+    #[serial]
+    fn test_initial_directives_precedence() {
+        let previous = std::env::var(EnvFilter::DEFAULT_ENV).ok();
+        let mut config = Logging {
+            level: "warn".to_string(),
+            filter: Some("calculator_mcp=debug,info".to_string()),
+            ..Logging::default()
+        };
+
+        unsafe { std::env::set_var(EnvFilter::DEFAULT_ENV, "trace") };
+        assert_eq!(initial_directives(&config), "trace");
+        // A blank RUST_LOG counts as unset
+        unsafe { std::env::set_var(EnvFilter::DEFAULT_ENV, " ") };
+        assert_eq!(initial_directives(&config), "calculator_mcp=debug,info");
+        unsafe { std::env::remove_var(EnvFilter::DEFAULT_ENV) };
+        assert_eq!(initial_directives(&config), "calculator_mcp=debug,info");
+        config.filter = None;
+        assert_eq!(initial_directives(&config), "warn");
+
+        if let Some(previous) = previous {
+            unsafe { std::env::set_var(EnvFilter::DEFAULT_ENV, previous) };
+        }
+    }
+}
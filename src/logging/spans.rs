@@ -0,0 +1,156 @@
+//! Expression metadata on HTTP request spans. [`ExpressionSpan`] opens each
+//! request span with empty expression fields, and the evaluation code fills
+//! them in through [`record_expression`], [`record_expression_text`],
+//! [`record_result`] and [`record_memory`] while it runs inside that span.
+
+use crate::evaluator;
+use axum::http::Request;
+use bigdecimal::BigDecimal;
+use tower_http::trace::MakeSpan;
+use tracing::Span;
+use tracing::field::Empty;
+
+/// Builds the request span for `tower_http::trace::TraceLayer`. The raw
+/// `expression` field is only declared when `record_expressions` is set;
+/// recording an undeclared field is a no-op, so the text never reaches a
+/// subscriber otherwise.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExpressionSpan {
+    pub record_expressions: bool,
+}
+
+impl<B> MakeSpan<B> for ExpressionSpan {
+    fn make_span(&mut self, request: &Request<B>) -> Span {
+        if self.record_expressions {
+            tracing::info_span!(
+                "request",
+                method = %request.method(),
+                uri = %request.uri(),
+                version = ?request.version(),
+                expression.length = Empty,
+                expression.tokens = Empty,
+                result.scale = Empty,
+                cache.hit = Empty,
+                eval.memory_bytes = Empty,
+                expression = Empty,
+            )
+        } else {
+            tracing::info_span!(
+                "request",
+                method = %request.method(),
+                uri = %request.uri(),
+                version = ?request.version(),
+                expression.length = Empty,
+                expression.tokens = Empty,
+                result.scale = Empty,
+                cache.hit = Empty,
+                eval.memory_bytes = Empty,
+            )
+        }
+    }
+}
+
+/// Record the length in characters and the token count of `input` on the
+/// current span. Statements that fail to parse count no tokens.
+pub fn record_expression(input: &str) {
+    let span = Span::current();
+    if span.is_disabled() {
+        return;
+    }
+    let tokens: usize = evaluator::split_statements(input)
+        .into_iter()
+        .filter_map(|statement| {
+            let (_, expression) = evaluator::split_assignment(statement);
+            evaluator::cost_estimate(expression).ok()
+        })
+        .map(|estimate| estimate.tokens)
+        .sum();
+    span.record("expression.length", input.chars().count());
+    span.record("expression.tokens", tokens);
+}
+
+/// Record the expression itself on the current span, already passed through
+/// `audit::redact_expression`. Only spans opened with `record_expressions`
+/// keep it.
+pub fn record_expression_text(text: &str) {
+    Span::current().record("expression", text);
+}
+
+/// Record the scale (digits after the decimal point) of a result on the
+/// current span, and whether it came from a cache.
+pub fn record_result(value: &BigDecimal, cache_hit: bool) {
+    let span = Span::current();
+    span.record("result.scale", value.fractional_digit_count());
+    span.record("cache.hit", cache_hit);
+}
+
+/// Record the bytes an evaluation's values took, by its memory meter.
+pub fn record_memory(bytes: u64) {
+    Span::current().record("eval.memory_bytes", bytes);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::fmt;
+    use std::str::FromStr;
+    use std::sync::{Arc, Mutex};
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::subscriber::with_default;
+    use tracing_subscriber::Layer;
+    use tracing_subscriber::layer::{Context, SubscriberExt};
+
+    /// Collects every recorded span field by name.
+    #[derive(Clone, Default)]
+    struct Fields(Arc<Mutex<HashMap<String, String>>>);
+
+    impl Visit for Fields {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            self.0
+                .lock()
+                .unwrap()
+                .insert(field.name().to_string(), format!("{value:?}"));
+        }
+    }
+
+    impl<S: tracing::Subscriber> Layer<S> for Fields {
+        fn on_new_span(&self, attrs: &Attributes<'_>, _: &Id, _: Context<'_, S>) {
+            attrs.record(&mut self.clone());
+        }
+
+        fn on_record(&self, _: &Id, values: &Record<'_>, _: Context<'_, S>) {
+            values.record(&mut self.clone());
+        }
+    }
+
+    fn recorded(record_expressions: bool) -> HashMap<String, String> {
+        let fields = Fields::default();
+        let subscriber = tracing_subscriber::registry().with(fields.clone());
+        with_default(subscriber, || {
+            let request = Request::post("/mcp").body(()).unwrap();
+            let span = ExpressionSpan { record_expressions }.make_span(&request);
+            span.in_scope(|| {
+                record_expression("x = 2; x * 1.25");
+                record_expression_text("x = 2; x * 1.25");
+                record_result(&BigDecimal::from_str("2.50").unwrap(), false);
+                record_memory(96);
+            });
+        });
+        fields.0.lock().unwrap().clone()
+    }
+
+    #[test]
+    fn test_expression_span_fields() {
+        let fields = recorded(false);
+        assert_eq!(fields["expression.length"], "15");
+        assert_eq!(fields["expression.tokens"], "4");
+        assert_eq!(fields["result.scale"], "2");
+        assert_eq!(fields["cache.hit"], "false");
+        assert_eq!(fields["eval.memory_bytes"], "96");
+        assert!(!fields.contains_key("expression"));
+
+        assert_eq!(recorded(true)["expression"], "\"x = 2; x * 1.25\"");
+    }
+}
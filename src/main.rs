@@ -1,5 +1,7 @@
+use calculator_mcp::cli::Cli;
+use clap::Parser;
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    let http_server = calculator_mcp::init()?;
-    http_server.start().await
+    calculator_mcp::run(Cli::parse()).await
 }
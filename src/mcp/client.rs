@@ -0,0 +1,82 @@
+use crate::mcp::protocol::*;
+use anyhow::{Context, anyhow, bail};
+use reqwest::header::{ACCEPT, AUTHORIZATION};
+use serde_json::Value;
+use std::time::Duration;
+
+/// Minimal MCP client for the streamable HTTP transport in JSON response mode.
+pub struct McpClient {
+    http: reqwest::Client,
+    url: String,
+    api_key: Option<String>,
+    session_id: Option<String>,
+    next_id: u64,
+}
+
+impl McpClient {
+    pub fn new(url: &str, api_key: Option<String>, timeout: Duration) -> anyhow::Result<Self> {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let http = reqwest::Client::builder().timeout(timeout).build()?;
+        Ok(McpClient {
+            http,
+            url: url.to_string(),
+            api_key,
+            session_id: None,
+            next_id: 1,
+        })
+    }
+
+    /// Send a request and return its `result`, turning JSON-RPC errors into `Err`.
+    pub async fn request(&mut self, method: &str, params: Option<Value>) -> anyhow::Result<Value> {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let response = self
+            .post(&JsonRpcRequest::new(id, method, params))
+            .await?
+            .ok_or_else(|| anyhow!("Server sent no response to `{method}`"))?;
+        if let Some(error) = response.error {
+            bail!("{} (code {})", error.message, error.code);
+        }
+        response
+            .result
+            .ok_or_else(|| anyhow!("Response to `{method}` has neither result nor error"))
+    }
+
+    pub async fn notify(&mut self, method: &str) -> anyhow::Result<()> {
+        self.post(&JsonRpcRequest::notification(method)).await?;
+        Ok(())
+    }
+
+    async fn post(&mut self, message: &JsonRpcRequest) -> anyhow::Result<Option<JsonRpcResponse>> {
+        let mut request = self
+            .http
+            .post(&self.url)
+            .header(ACCEPT, "application/json, text/event-stream")
+            .json(message);
+        if let Some(key) = &self.api_key {
+            request = request.header(AUTHORIZATION, format!("Bearer {key}"));
+        }
+        if let Some(session_id) = &self.session_id {
+            request = request.header(SESSION_ID_HEADER, session_id);
+        }
+
+        let response = request
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach {}", self.url))?;
+        if let Some(session_id) = response.headers().get(SESSION_ID_HEADER) {
+            self.session_id = Some(session_id.to_str()?.to_string());
+        }
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            bail!("HTTP {status}: {body}");
+        }
+        if message.id.is_none() {
+            return Ok(None);
+        }
+        Ok(Some(response.json().await?))
+    }
+}
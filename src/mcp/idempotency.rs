@@ -0,0 +1,124 @@
+use crate::app_config::Idempotency;
+use crate::audit::sha256_hex;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Longest accepted idempotency key.
+pub const MAX_KEY_LENGTH: usize = 255;
+
+/// What a cache lookup found for an idempotency key.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Lookup {
+    Miss,
+    /// The stored result of the same submission
+    Replay(Value),
+    /// The key was last used with a different submission
+    Conflict,
+}
+
+struct Entry {
+    fingerprint: String,
+    result: Value,
+    stored_at_ms: u64,
+}
+
+/// `tools/call` results by caller and idempotency key, kept for a window so
+/// retried submissions are answered without evaluating again. Two identical
+/// submissions racing each other may both run; the later result is kept.
+pub struct IdempotencyCache {
+    window_ms: u64,
+    max_entries: usize,
+    entries: Mutex<HashMap<String, Entry>>,
+}
+
+impl IdempotencyCache {
+    pub fn new(config: &Idempotency) -> Self {
+        IdempotencyCache {
+            window_ms: config.window_secs.saturating_mul(1000),
+            max_entries: config.max_entries.max(1),
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn from_config(config: &Idempotency) -> Option<Self> {
+        config.enabled.then(|| IdempotencyCache::new(config))
+    }
+
+    /// Identifies a submission by its tool and arguments.
+    pub fn fingerprint(tool: &str, arguments: &Value) -> String {
+        sha256_hex(format!("{tool}\n{arguments}").as_bytes())
+    }
+
+    pub fn lookup(&self, key: &str, fingerprint: &str, now_ms: u64) -> Lookup {
+        let entries = self.lock();
+        match entries.get(key) {
+            Some(entry) if now_ms.saturating_sub(entry.stored_at_ms) >= self.window_ms => {
+                Lookup::Miss
+            }
+            Some(entry) if entry.fingerprint == fingerprint => Lookup::Replay(entry.result.clone()),
+            Some(_) => Lookup::Conflict,
+            None => Lookup::Miss,
+        }
+    }
+
+    /// Remember `result`, dropping expired entries and, when still full, the oldest.
+    pub fn store(&self, key: &str, fingerprint: String, result: Value, now_ms: u64) {
+        let mut entries = self.lock();
+        entries.retain(|_, entry| now_ms.saturating_sub(entry.stored_at_ms) < self.window_ms);
+        if entries.len() >= self.max_entries
+            && !entries.contains_key(key)
+            && let Some(oldest) = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.stored_at_ms)
+                .map(|(key, _)| key.clone())
+        {
+            entries.remove(&oldest);
+        }
+        entries.insert(
+            key.to_string(),
+            Entry {
+                fingerprint,
+                result,
+                stored_at_ms: now_ms,
+            },
+        );
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Entry>> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_replay_within_window() {
+        let cache = IdempotencyCache::new(&Idempotency {
+            enabled: true,
+            window_secs: 60,
+            max_entries: 2,
+        });
+        let fingerprint = IdempotencyCache::fingerprint("evaluate", &json!({ "expression": "1" }));
+        assert_eq!(cache.lookup("a", &fingerprint, 0), Lookup::Miss);
+
+        cache.store("a", fingerprint.clone(), json!(1), 0);
+        assert_eq!(
+            cache.lookup("a", &fingerprint, 59_999),
+            Lookup::Replay(json!(1))
+        );
+        let other = IdempotencyCache::fingerprint("evaluate", &json!({ "expression": "2" }));
+        assert_eq!(cache.lookup("a", &other, 1), Lookup::Conflict);
+        assert_eq!(cache.lookup("a", &fingerprint, 60_000), Lookup::Miss);
+
+        cache.store("b", other.clone(), json!(2), 10);
+        cache.store("c", other.clone(), json!(2), 20);
+        assert_eq!(cache.lookup("a", &fingerprint, 30), Lookup::Miss);
+        assert_eq!(cache.lookup("b", &other, 30), Lookup::Replay(json!(2)));
+    }
+}
@@ -0,0 +1,1036 @@
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+use crate::app_config::{Privacy, Shadow, ShadowCandidate};
+use crate::audit::{self, AuditRecord, AuditSink, Outcome};
+use crate::evaluator::provenance::Provenance;
+use crate::evaluator::shadow::{AstCandidate, Candidate};
+use crate::evaluator::{CancellationToken, Environment, EvalContext, Limits, ParseError};
+use crate::i18n::Locale;
+use crate::logging::spans;
+use crate::mcp::idempotency::{IdempotencyCache, Lookup, MAX_KEY_LENGTH};
+use crate::mcp::protocol::*;
+use crate::mcp::tools::{Tool, ToolContext};
+use crate::quota::QuotaTracker;
+use crate::session::{SessionStore, now_ms};
+use crate::tenant::{Tenancy, quota_subject};
+use crate::worker::WorkerPool;
+
+pub mod client;
+pub mod idempotency;
+pub mod protocol;
+pub mod tools;
+
+/// The candidate evaluator `shadow` configures, if enabled.
+pub fn shadow_candidate(shadow: &Shadow) -> Option<Box<dyn Candidate>> {
+    shadow.enabled.then(|| match shadow.candidate {
+        ShadowCandidate::Ast => Box::new(AstCandidate) as Box<dyn Candidate>,
+    })
+}
+
+/// Transport-independent MCP request handling.
+pub struct McpServer {
+    tools: Vec<Box<dyn Tool>>,
+    sessions: Arc<SessionStore>,
+    audit: Option<Box<dyn AuditSink>>,
+    quotas: Option<Arc<QuotaTracker>>,
+    privacy: Privacy,
+    shadow: Option<Box<dyn Candidate>>,
+    idempotency: Option<IdempotencyCache>,
+    tenancy: Option<Arc<Tenancy>>,
+    workers: Option<Arc<WorkerPool>>,
+    limits: Limits,
+}
+
+/// Transport-level facts about the caller of one message.
+#[derive(Debug, Clone, Default)]
+pub struct RequestMeta {
+    pub session_id: Option<String>,
+    /// Stable, non-secret caller identity such as an API key fingerprint
+    pub caller: Option<String>,
+    /// Client-chosen key marking retries of the same `tools/call`
+    pub idempotency_key: Option<String>,
+    /// Tenant the message is attributed to, when tenants are enabled
+    pub tenant: Option<String>,
+    /// Set by the transport when the caller stops waiting for the reply
+    pub cancellation: CancellationToken,
+    /// Language of error messages, e.g. from `Accept-Language`;
+    /// `params._meta.locale` of the message takes precedence
+    pub locale: Locale,
+}
+
+/// Outcome of handling one message: the response (none for notifications)
+/// and the session created by `initialize`, if any.
+pub struct McpReply {
+    pub response: Option<JsonRpcResponse>,
+    pub session_id: Option<String>,
+    /// Whether the response is a stored result replayed for an idempotency key
+    pub replayed: bool,
+}
+
+impl McpServer {
+    pub fn new(tools: Vec<Box<dyn Tool>>, sessions: Arc<SessionStore>) -> Self {
+        McpServer {
+            tools,
+            sessions,
+            audit: None,
+            quotas: None,
+            privacy: Privacy::default(),
+            shadow: None,
+            idempotency: None,
+            tenancy: None,
+            workers: None,
+            limits: Limits::DEFAULT,
+        }
+    }
+
+    pub fn with_audit(mut self, audit: Option<Box<dyn AuditSink>>) -> Self {
+        self.audit = audit;
+        self
+    }
+
+    /// Count `tools/call` against per-caller quotas; callers without an identity
+    /// are not limited.
+    pub fn with_quotas(mut self, quotas: Option<Arc<QuotaTracker>>) -> Self {
+        self.quotas = quotas;
+        self
+    }
+
+    /// Redact expressions in audit records and request spans.
+    pub fn with_privacy(mut self, privacy: Privacy) -> Self {
+        self.privacy = privacy;
+        self
+    }
+
+    /// Dry-run the configured candidate evaluator on every evaluation.
+    pub fn with_shadow(mut self, shadow: &Shadow) -> Self {
+        self.shadow = shadow_candidate(shadow);
+        self
+    }
+
+    /// Replay `tools/call` results for repeated idempotency keys.
+    pub fn with_idempotency(mut self, idempotency: Option<IdempotencyCache>) -> Self {
+        self.idempotency = idempotency;
+        self
+    }
+
+    /// Give sessions, quotas and constants a tenant scope.
+    pub fn with_tenancy(mut self, tenancy: Option<Arc<Tenancy>>) -> Self {
+        self.tenancy = tenancy;
+        self
+    }
+
+    /// Evaluate calls outside sessions in worker processes. Sessions and
+    /// tenant constants live in this process, so calls using them stay here.
+    pub fn with_workers(mut self, workers: Option<Arc<WorkerPool>>) -> Self {
+        self.workers = workers;
+        self
+    }
+
+    /// Evaluate each `tools/call` under its own [`EvalContext`] for `limits`.
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn with_default_tools(sessions: Arc<SessionStore>) -> Self {
+        McpServer::new(tools::default_tools(), sessions)
+    }
+
+    pub fn sessions(&self) -> &SessionStore {
+        &self.sessions
+    }
+
+    /// Handle one JSON-RPC message within an optional session.
+    pub fn handle(&self, request: JsonRpcRequest, meta: &RequestMeta) -> McpReply {
+        let mut reply = McpReply {
+            response: None,
+            session_id: None,
+            replayed: false,
+        };
+        let Some(id) = request.id.clone() else {
+            return reply;
+        };
+        if request.jsonrpc != JSONRPC_VERSION {
+            reply.response = Some(JsonRpcResponse::failure(
+                id,
+                JsonRpcError::new(INVALID_REQUEST, "Unsupported JSON-RPC version"),
+            ));
+            return reply;
+        }
+
+        let params = request.params.unwrap_or(Value::Null);
+        let locale = params
+            .pointer("/_meta/locale")
+            .and_then(Value::as_str)
+            .and_then(Locale::from_tag)
+            .unwrap_or(meta.locale);
+        let meta = &RequestMeta {
+            locale,
+            ..meta.clone()
+        };
+        let outcome = match request.method.as_str() {
+            "initialize" => self.initialize(meta).map(|(result, new_session)| {
+                reply.session_id = new_session;
+                result
+            }),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(self.list_tools()),
+            "tools/call" => self.call_tool_once(params, meta).map(|(result, replayed)| {
+                reply.replayed = replayed;
+                result
+            }),
+            method => Err(JsonRpcError::new(
+                METHOD_NOT_FOUND,
+                format!("Method not found: {method}"),
+            )),
+        };
+
+        reply.response = Some(match outcome {
+            Ok(mut result) => {
+                localize_tool_error(&mut result, locale);
+                JsonRpcResponse::success(id, result)
+            }
+            Err(mut error) => {
+                error.message = locale.translate(&error.message).into_owned();
+                JsonRpcResponse::failure(id, error)
+            }
+        });
+        reply
+    }
+
+    /// The server's capabilities, and a new session unless sessions are off.
+    fn initialize(&self, meta: &RequestMeta) -> Result<(Value, Option<String>), JsonRpcError> {
+        let tenant = meta.tenant.as_deref();
+        let session_id = self
+            .sessions
+            .enabled()
+            .then(|| {
+                let constants = self.constants(meta).unwrap_or_default();
+                self.sessions.create_in(tenant, constants)
+            })
+            .transpose()
+            .map_err(|err| JsonRpcError::new(INTERNAL_ERROR, err.to_string()))?;
+        if let (Some(tenancy), Some(tenant), Some(_)) = (&self.tenancy, tenant, &session_id) {
+            tenancy.record_session(tenant);
+        }
+        let result = json!({
+            "protocolVersion": PROTOCOL_VERSION,
+            "capabilities": { "tools": { "listChanged": false } },
+            "serverInfo": {
+                "name": env!("CARGO_PKG_NAME"),
+                "version": env!("CARGO_PKG_VERSION"),
+            },
+        });
+        Ok((result, session_id))
+    }
+
+    /// Every tool in OpenAI function-calling format, as the `tools` array of a
+    /// chat completion request expects them.
+    pub fn openai_tools(&self) -> Value {
+        self.tools
+            .iter()
+            .map(|tool| tool.openai_definition())
+            .collect()
+    }
+
+    fn list_tools(&self) -> Value {
+        let tools: Vec<Value> = self.tools.iter().map(|tool| tool.definition()).collect();
+        json!({ "tools": tools })
+    }
+
+    fn call_tool(&self, params: Value, meta: &RequestMeta) -> Result<Value, JsonRpcError> {
+        let name = params
+            .get("name")
+            .and_then(Value::as_str)
+            .ok_or_else(|| JsonRpcError::new(INVALID_PARAMS, "Missing tool name"))?;
+        let tool = self
+            .tools
+            .iter()
+            .find(|tool| tool.name() == name)
+            .ok_or_else(|| JsonRpcError::new(INVALID_PARAMS, format!("Unknown tool: {name}")))?;
+        let arguments = params
+            .get("arguments")
+            .cloned()
+            .unwrap_or_else(|| json!({}));
+
+        if let (Some(tenancy), Some(tenant)) = (&self.tenancy, &meta.tenant) {
+            tenancy.record_tool_call(tenant);
+        }
+        if let (Some(tenancy), Some(tenant)) = (&self.tenancy, &meta.tenant) {
+            tenancy
+                .check_functions(tenant, &arguments)
+                .map_err(|err| JsonRpcError::new(INVALID_PARAMS, err.to_string()))?;
+        }
+        if let (Some(quotas), Some(subject)) = (&self.quotas, self.quota_subject(meta)) {
+            quotas
+                .try_consume(&subject, now_ms() / 1000)
+                .map_err(|exceeded| {
+                    JsonRpcError::new(QUOTA_EXCEEDED, exceeded.to_string())
+                        .with_data(json!(exceeded))
+                })?;
+        }
+
+        if let Some(expression) = arguments.get("expression").and_then(Value::as_str) {
+            spans::record_expression_text(&audit::redact_expression(&self.privacy, expression));
+        }
+
+        let constants = self.constants(meta);
+        let context = EvalContext::new(self.limits)
+            .with_cancellation(meta.cancellation.clone())
+            .with_locale(meta.locale);
+        let ctx = ToolContext {
+            sessions: &self.sessions,
+            session_id: meta.session_id.as_deref(),
+            shadow: self.shadow.as_deref(),
+            constants: constants.as_ref(),
+            eval: &context,
+        };
+        // Workers have no result cache installed, so their answers are always evaluated
+        let (result, cache_hit) = match &self.workers {
+            Some(workers) if ctx.session_id.is_none() && ctx.constants.is_none() => {
+                (workers.call(name, &arguments, &meta.cancellation), false)
+            }
+            _ => {
+                let result = context.run(|| tool.call(&ctx, arguments.clone()));
+                spans::record_memory(context.memory.used());
+                (result, context.cache_hits.any())
+            }
+        };
+        self.audit(name, &arguments, &result, meta);
+
+        let mut result = match result {
+            Ok(structured) => json!({
+                "content": [{ "type": "text", "text": structured.to_string() }],
+                "structuredContent": structured,
+                "isError": false,
+                "_meta": { "provenance": Provenance::current(cache_hit) },
+            }),
+            Err(err) => {
+                let mut result = json!({
+                    "content": [{ "type": "text", "text": err.to_string() }],
+                    "isError": true,
+                });
+                if let Some(parse) = err.downcast_ref::<ParseError>() {
+                    result["structuredContent"] = json!({ "error": parse });
+                }
+                result
+            }
+        };
+        if let Some(rate_limit) = self.rate_limit(meta) {
+            result["_meta"]["rateLimit"] = rate_limit;
+        }
+        Ok(result)
+    }
+}
+
+impl McpServer {
+    /// `tools/call`, answered from the idempotency cache when the key (from
+    /// `meta` or `params._meta.idempotencyKey`) was seen with the same tool and
+    /// arguments. Keys are scoped to the caller and session; a replay does not
+    /// count against quotas. Only successful results are stored, so a retry
+    /// after a cancelled or failed call evaluates again.
+    fn call_tool_once(
+        &self,
+        params: Value,
+        meta: &RequestMeta,
+    ) -> Result<(Value, bool), JsonRpcError> {
+        let key = meta.idempotency_key.clone().or_else(|| {
+            params
+                .pointer("/_meta/idempotencyKey")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        });
+        let (Some(cache), Some(key)) = (&self.idempotency, key) else {
+            return self.call_tool(params, meta).map(|result| (result, false));
+        };
+        if key.is_empty() || key.len() > MAX_KEY_LENGTH {
+            return Err(JsonRpcError::new(
+                INVALID_PARAMS,
+                format!("Idempotency key must be 1 to {MAX_KEY_LENGTH} characters"),
+            ));
+        }
+
+        let scope = format!(
+            "{}\n{}\n{}\n{key}",
+            meta.tenant.as_deref().unwrap_or_default(),
+            meta.caller.as_deref().unwrap_or(audit::ANONYMOUS_CALLER),
+            meta.session_id.as_deref().unwrap_or_default()
+        );
+        let fingerprint = IdempotencyCache::fingerprint(
+            params
+                .get("name")
+                .and_then(Value::as_str)
+                .unwrap_or_default(),
+            params.get("arguments").unwrap_or(&Value::Null),
+        );
+        match cache.lookup(&scope, &fingerprint, now_ms()) {
+            Lookup::Replay(mut result) => {
+                if let Some(cache_hit) = result.pointer_mut("/_meta/provenance/cacheHit") {
+                    *cache_hit = json!(true);
+                }
+                if let Some(rate_limit) = self.rate_limit(meta) {
+                    result["_meta"]["rateLimit"] = rate_limit;
+                }
+                return Ok((result, true));
+            }
+            Lookup::Conflict => {
+                return Err(JsonRpcError::new(
+                    INVALID_PARAMS,
+                    "Idempotency key was already used with different arguments",
+                ));
+            }
+            Lookup::Miss => {}
+        }
+        let result = self.call_tool(params, meta)?;
+        if result["isError"] != true {
+            cache.store(&scope, fingerprint, result.clone(), now_ms());
+        }
+        Ok((result, false))
+    }
+
+    fn quota_subject(&self, meta: &RequestMeta) -> Option<String> {
+        quota_subject(self.tenancy.as_deref(), meta.caller.as_deref())
+    }
+
+    /// Constants of the message's tenant.
+    fn constants(&self, meta: &RequestMeta) -> Option<Environment> {
+        self.tenancy.as_ref()?.constants(meta.tenant.as_deref()?)
+    }
+
+    /// The MCP counterpart of the `X-RateLimit-*` headers: the caller's tightest
+    /// quota after this call.
+    fn rate_limit(&self, meta: &RequestMeta) -> Option<Value> {
+        let tightest = self
+            .quotas
+            .as_ref()?
+            .tightest(&self.quota_subject(meta)?, now_ms() / 1000)?;
+        Some(json!({
+            "limit": tightest.limit,
+            "remaining": tightest.remaining(),
+            "reset": tightest.reset_at,
+        }))
+    }
+
+    fn audit(
+        &self,
+        tool: &str,
+        arguments: &Value,
+        result: &anyhow::Result<Value>,
+        meta: &RequestMeta,
+    ) {
+        let Some(sink) = &self.audit else {
+            return;
+        };
+        let expression = match arguments.get("expression").and_then(Value::as_str) {
+            Some(expression) => expression.to_string(),
+            None => arguments.to_string(),
+        };
+        let record = AuditRecord {
+            timestamp_ms: now_ms(),
+            caller: meta
+                .caller
+                .clone()
+                .unwrap_or_else(|| audit::ANONYMOUS_CALLER.to_string()),
+            session_id: meta.session_id.clone(),
+            tool: tool.to_string(),
+            expression: audit::redact_expression(&self.privacy, &expression),
+            result_hash: result
+                .as_ref()
+                .ok()
+                .and_then(|value| audit::result_hash(&self.privacy, value)),
+            outcome: if result.is_ok() {
+                Outcome::Ok
+            } else {
+                Outcome::Error
+            },
+            error: result.as_ref().err().map(ToString::to_string),
+        };
+        if let Err(err) = sink.record(&record) {
+            tracing::warn!("Failed to write audit record: {err:#}");
+        }
+    }
+}
+
+/// Translate the text of a failed tool call; its structured content is left
+/// as is for programs.
+fn localize_tool_error(result: &mut Value, locale: Locale) {
+    if locale == Locale::English || result["isError"] != json!(true) {
+        return;
+    }
+    let Some(content) = result.get_mut("content").and_then(Value::as_array_mut) else {
+        return;
+    };
+    for text in content.iter_mut().filter_map(|item| item.get_mut("text")) {
+        if let Some(message) = text.as_str() {
+            *text = json!(locale.translate(message));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_config::Sessions;
+    use crate::evaluator::provenance::PARSER_VERSION;
+
+    fn server() -> McpServer {
+        McpServer::with_default_tools(Arc::new(SessionStore::new(Default::default())))
+    }
+
+    fn call(server: &McpServer, method: &str, params: Value) -> JsonRpcResponse {
+        call_in(server, None, method, params)
+    }
+
+    fn call_in(
+        server: &McpServer,
+        session_id: Option<&str>,
+        method: &str,
+        params: Value,
+    ) -> JsonRpcResponse {
+        let meta = RequestMeta {
+            session_id: session_id.map(str::to_string),
+            caller: None,
+            idempotency_key: None,
+            tenant: None,
+            ..RequestMeta::default()
+        };
+        server
+            .handle(JsonRpcRequest::new(1, method, Some(params)), &meta)
+            .response
+            .expect("request with id must be answered")
+    }
+
+    #[test]
+    fn test_notifications_are_not_answered() {
+        let server = server();
+        assert!(
+            server
+                .handle(
+                    JsonRpcRequest::notification("notifications/initialized"),
+                    &RequestMeta::default()
+                )
+                .response
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_initialize_opens_session_with_variables() {
+        let server = server();
+        let reply = server.handle(
+            JsonRpcRequest::new(1, "initialize", None),
+            &RequestMeta::default(),
+        );
+        let session_id = reply.session_id.expect("initialize creates a session");
+
+        let evaluate = |expression: &str| {
+            call_in(
+                &server,
+                Some(&session_id),
+                "tools/call",
+                json!({ "name": "evaluate", "arguments": { "expression": expression } }),
+            )
+            .result
+            .unwrap()
+        };
+        evaluate("x = 6");
+        assert_eq!(evaluate("x * 7")["structuredContent"]["result"], "42");
+    }
+
+    #[test]
+    fn test_initialize_without_sessions() {
+        let server = McpServer::with_default_tools(Arc::new(SessionStore::new(Sessions {
+            enabled: false,
+            ..Default::default()
+        })));
+        let reply = server.handle(
+            JsonRpcRequest::new(1, "initialize", None),
+            &RequestMeta::default(),
+        );
+        assert!(reply.response.unwrap().result.is_some());
+        assert_eq!(reply.session_id, None);
+        assert_eq!(server.sessions().metrics().active, 0);
+    }
+
+    #[test]
+    fn test_tools_list_and_call() {
+        let server = server();
+
+        let list = call(&server, "tools/list", json!({}));
+        assert_eq!(list.result.unwrap()["tools"][0]["name"], "evaluate");
+
+        let ok = call(
+            &server,
+            "tools/call",
+            json!({ "name": "evaluate", "arguments": { "expression": "2 + 3 * 4" } }),
+        );
+        let result = ok.result.unwrap();
+        assert_eq!(result["isError"], false);
+        assert_eq!(result["structuredContent"]["result"], "14");
+
+        let formatted = call(
+            &server,
+            "tools/call",
+            json!({ "name": "evaluate", "arguments": { "expression": "x / 2", "format": "asciimath" } }),
+        );
+        assert_eq!(formatted.result.unwrap()["isError"], true);
+        let formatted = call(
+            &server,
+            "tools/call",
+            json!({ "name": "evaluate", "arguments": { "expression": "1 / 2", "format": "asciimath" } }),
+        );
+        assert_eq!(
+            formatted.result.unwrap()["structuredContent"]["formatted"],
+            "1/2 = 0.5"
+        );
+
+        let statements = call(
+            &server,
+            "tools/call",
+            json!({ "name": "evaluate", "arguments": { "expression": "a = 2; b = 3; a ^ b + 1" } }),
+        )
+        .result
+        .unwrap();
+        assert_eq!(statements["structuredContent"]["result"], "9");
+        assert_eq!(
+            statements["structuredContent"]["bindings"],
+            json!([{ "name": "a", "value": "2" }, { "name": "b", "value": "3" }])
+        );
+
+        let duration = call(
+            &server,
+            "tools/call",
+            json!({ "name": "evaluate", "arguments": { "expression": "2h 45m + 90s" } }),
+        )
+        .result
+        .unwrap();
+        assert_eq!(duration["structuredContent"]["result"], "9990");
+        assert_eq!(duration["structuredContent"]["duration"], "2:46:30");
+
+        let derivative = call(
+            &server,
+            "tools/call",
+            json!({ "name": "derivative", "arguments": { "expression": "x^2 * sin(x)", "at": 0 } }),
+        )
+        .result
+        .unwrap();
+        assert_eq!(
+            derivative["structuredContent"]["derivative"],
+            "2 * x * sin(x) + x ^ 2 * cos(x)"
+        );
+        assert_eq!(derivative["structuredContent"]["value"], "0");
+
+        let taylor = call(
+            &server,
+            "tools/call",
+            json!({ "name": "taylor", "arguments": { "expression": "exp(x)", "order": 3 } }),
+        )
+        .result
+        .unwrap();
+        assert_eq!(
+            taylor["structuredContent"]["polynomial"],
+            "1 + x + x ^ 2 / 2 + x ^ 3 / 6"
+        );
+        assert_eq!(taylor["structuredContent"]["coefficients"][2], "0.5");
+
+        let limit = call(
+            &server,
+            "tools/call",
+            json!({ "name": "limit", "arguments": { "expression": "1 / x", "to": "-inf" } }),
+        )
+        .result
+        .unwrap();
+        assert_eq!(limit["structuredContent"]["limit"], "0");
+        assert_eq!(limit["structuredContent"]["left"], Value::Null);
+        assert_eq!(limit["structuredContent"]["method"], "numeric");
+
+        let solved = call(
+            &server,
+            "tools/call",
+            json!({ "name": "solve", "arguments": { "equation": "x^2 - 4 > 0" } }),
+        )
+        .result
+        .unwrap();
+        assert_eq!(
+            solved["structuredContent"]["notation"],
+            "(-inf, -2) ∪ (2, inf)"
+        );
+        assert_eq!(
+            solved["structuredContent"]["intervals"][0],
+            json!({ "lower": null, "upper": "-2", "lower_closed": false, "upper_closed": false })
+        );
+
+        let system = call(
+            &server,
+            "tools/call",
+            json!({ "name": "solve", "arguments": { "equations": ["2x + y = 5", "x - y = 1"] } }),
+        )
+        .result
+        .unwrap();
+        assert_eq!(
+            system["structuredContent"]["solution"],
+            json!({ "x": "2", "y": "1" })
+        );
+
+        let failed = call(
+            &server,
+            "tools/call",
+            json!({ "name": "evaluate", "arguments": { "expression": "1 / 0" } }),
+        );
+        assert_eq!(failed.result.unwrap()["isError"], true);
+
+        let malformed = call(
+            &server,
+            "tools/call",
+            json!({ "name": "evaluate", "arguments": { "expression": "1.2.3" } }),
+        )
+        .result
+        .unwrap();
+        assert_eq!(malformed["isError"], true);
+        assert_eq!(
+            malformed["structuredContent"]["error"]["span"],
+            json!({ "start": 0, "end": 5 })
+        );
+    }
+
+    #[test]
+    fn test_error_messages_are_localized() {
+        let server = server();
+        let failed = call(
+            &server,
+            "tools/call",
+            json!({
+                "name": "evaluate",
+                "arguments": { "expression": "1 / 0" },
+                "_meta": { "locale": "de-AT" },
+            }),
+        )
+        .result
+        .unwrap();
+        assert_eq!(failed["content"][0]["text"], "Division durch null");
+
+        // The transport's language applies unless the message names one
+        let meta = RequestMeta {
+            locale: Locale::French,
+            ..RequestMeta::default()
+        };
+        let params = json!({ "name": "nope", "arguments": {} });
+        let unknown = server
+            .handle(JsonRpcRequest::new(1, "tools/call", Some(params)), &meta)
+            .response
+            .unwrap()
+            .error
+            .unwrap();
+        assert_eq!(unknown.code, INVALID_PARAMS);
+        assert_eq!(unknown.message, "Outil inconnu : nope");
+
+        let malformed = json!({
+            "name": "evaluate",
+            "arguments": { "expression": "1.2.3" },
+            "_meta": { "locale": "es" },
+        });
+        let malformed = server
+            .handle(JsonRpcRequest::new(2, "tools/call", Some(malformed)), &meta)
+            .response
+            .unwrap()
+            .result
+            .unwrap();
+        assert_eq!(
+            malformed["content"][0]["text"],
+            "Número mal formado en 0..5"
+        );
+        assert_eq!(
+            malformed["structuredContent"]["error"]["message"],
+            "Malformed number"
+        );
+    }
+
+    #[test]
+    fn test_validate_reports_cost_and_errors() {
+        let server = server();
+        let validate = |expression: &str| {
+            call(
+                &server,
+                "tools/call",
+                json!({ "name": "validate", "arguments": { "expression": expression } }),
+            )
+            .result
+            .unwrap()["structuredContent"]
+                .clone()
+        };
+
+        let valid = validate("y = 2 ^ 10 * x");
+        assert_eq!(valid["valid"], true);
+        assert_eq!(valid["variables"], json!(["x"]));
+        assert_eq!(valid["cost"]["max_exponent"], 10);
+
+        let invalid = validate("1.2.3");
+        assert_eq!(invalid["valid"], false);
+        assert_eq!(invalid["span"], json!({ "start": 0, "end": 5 }));
+
+        let mut product = "9 ^ 99999".to_string();
+        for _ in 0..11 {
+            product = format!("({product}) * ({product})");
+        }
+        let expensive = validate(&product);
+        assert_eq!(expensive["valid"], false);
+        assert!(expensive["cost"]["cost"].as_u64().unwrap() > 10_000_000);
+    }
+
+    #[test]
+    fn test_matrix_tool() {
+        let server = server();
+        let matrix = |arguments: Value| {
+            call(
+                &server,
+                "tools/call",
+                json!({ "name": "matrix", "arguments": arguments }),
+            )
+            .result
+            .unwrap()
+        };
+
+        let solved = matrix(json!({
+            "operation": "solve",
+            "matrix": [[2, 1], [1, "1 + 2"]],
+            "b": [3, 5]
+        }));
+        assert_eq!(
+            solved["structuredContent"]["solution"],
+            json!(["0.8", "1.4"])
+        );
+        let det = matrix(json!({ "operation": "determinant", "matrix": [[0.5, 2], [1, 3]] }));
+        assert_eq!(det["structuredContent"]["determinant"], "-0.5");
+        let singular = matrix(json!({ "operation": "inverse", "matrix": [[1, 2], [2, 4]] }));
+        assert_eq!(singular["isError"], true);
+        let eigen = matrix(json!({ "operation": "eigen", "matrix": [[0, -1], [1, 0]] }));
+        assert_eq!(
+            eigen["structuredContent"]["eigenvalues"],
+            json!([{ "re": "0", "im": "1" }, { "re": "0", "im": "-1" }])
+        );
+        assert_eq!(
+            eigen["structuredContent"]["eigenvectors"],
+            json!([null, null])
+        );
+    }
+
+    #[test]
+    fn test_quota_rejection_carries_reset_time() {
+        let quotas = QuotaTracker::new(crate::app_config::Quotas {
+            enabled: true,
+            default: crate::app_config::QuotaLimits {
+                daily: Some(1),
+                monthly: None,
+            },
+            ..Default::default()
+        });
+        let server = server().with_quotas(Some(Arc::new(quotas)));
+        let meta = RequestMeta {
+            session_id: None,
+            caller: Some("key:abc".to_string()),
+            idempotency_key: None,
+            tenant: None,
+            ..RequestMeta::default()
+        };
+        let evaluate = || {
+            server
+                .handle(
+                    JsonRpcRequest::new(
+                        1,
+                        "tools/call",
+                        Some(json!({ "name": "evaluate", "arguments": { "expression": "1" } })),
+                    ),
+                    &meta,
+                )
+                .response
+                .unwrap()
+        };
+
+        assert!(evaluate().result.is_some());
+        let error = evaluate().error.unwrap();
+        assert_eq!(error.code, QUOTA_EXCEEDED);
+        let data = error.data.unwrap();
+        assert_eq!(data["period"], "day");
+        assert_eq!(data["limit"], 1);
+        assert!(data["reset_at"].as_u64().unwrap() > now_ms() / 1000);
+    }
+
+    #[test]
+    fn test_unknown_method_and_tool() {
+        let server = server();
+        assert_eq!(
+            call(&server, "nope", json!({})).error.unwrap().code,
+            METHOD_NOT_FOUND
+        );
+        assert_eq!(
+            call(&server, "tools/call", json!({ "name": "nope" }))
+                .error
+                .unwrap()
+                .code,
+            INVALID_PARAMS
+        );
+    }
+
+    #[test]
+    fn test_audit_redacts_expressions() {
+        use crate::app_config::ExpressionRedaction;
+        use std::sync::Mutex;
+
+        #[derive(Clone, Default)]
+        struct Records(Arc<Mutex<Vec<AuditRecord>>>);
+
+        impl AuditSink for Records {
+            fn record(&self, record: &AuditRecord) -> anyhow::Result<()> {
+                self.0.lock().unwrap().push(record.clone());
+                Ok(())
+            }
+        }
+
+        let records = Records::default();
+        let server = server()
+            .with_audit(Some(Box::new(records.clone())))
+            .with_privacy(Privacy {
+                expressions: ExpressionRedaction::Hash,
+                hash_key: Some("k3y".to_string()),
+                ..Privacy::default()
+            });
+        let response = call(
+            &server,
+            "tools/call",
+            json!({ "name": "evaluate", "arguments": { "expression": "85000 * 1.04" } }),
+        );
+        assert_eq!(
+            response.result.unwrap()["structuredContent"]["result"],
+            "88400.00"
+        );
+
+        let records = records.0.lock().unwrap();
+        assert_eq!(
+            records[0].expression,
+            format!(
+                "hmac-sha256:{}",
+                audit::hmac_sha256_hex("k3y", b"85000 * 1.04")
+            )
+        );
+        let result_hash = records[0].result_hash.as_ref().unwrap();
+        assert_eq!(result_hash.len(), 64);
+    }
+
+    #[test]
+    fn test_shadow_mode_keeps_responses() {
+        let server = server().with_shadow(&Shadow {
+            enabled: true,
+            ..Shadow::default()
+        });
+        let response = call(
+            &server,
+            "tools/call",
+            json!({ "name": "evaluate", "arguments": { "expression": "2h + 30min in min" } }),
+        );
+        assert_eq!(
+            response.result.unwrap()["structuredContent"]["result"],
+            "150"
+        );
+    }
+
+    #[test]
+    fn test_idempotency_key_replays_result() {
+        let server = server().with_idempotency(IdempotencyCache::from_config(&Default::default()));
+        let session = server.sessions().create().unwrap();
+        let increment = |key: &str, expression: &str| {
+            let meta = RequestMeta {
+                session_id: Some(session.clone()),
+                caller: None,
+                idempotency_key: Some(key.to_string()),
+                tenant: None,
+                ..RequestMeta::default()
+            };
+            let params = json!({ "name": "evaluate", "arguments": { "expression": expression } });
+            server.handle(JsonRpcRequest::new(1, "tools/call", Some(params)), &meta)
+        };
+
+        increment("first", "n = 1");
+        let reply = increment("second", "n = n + 1");
+        assert!(!reply.replayed);
+        let provenance = &reply.response.unwrap().result.unwrap()["_meta"]["provenance"];
+        assert_eq!(provenance["cacheHit"], false);
+        assert_eq!(provenance["parserVersion"], PARSER_VERSION);
+        let replay = increment("second", "n = n + 1");
+        assert!(replay.replayed);
+        let result = replay.response.unwrap().result.unwrap();
+        assert_eq!(result["structuredContent"]["result"], "2");
+        assert_eq!(result["_meta"]["provenance"]["cacheHit"], true);
+        assert_eq!(server.sessions().history(&session).unwrap().len(), 2);
+
+        let conflict = increment("second", "n = n + 2").response.unwrap();
+        assert_eq!(conflict.error.unwrap().code, INVALID_PARAMS);
+
+        // The key may also travel in the request metadata
+        let params = json!({
+            "name": "evaluate",
+            "arguments": { "expression": "n = n + 1" },
+            "_meta": { "idempotencyKey": "second" },
+        });
+        let meta = RequestMeta {
+            session_id: Some(session.clone()),
+            ..RequestMeta::default()
+        };
+        let reply = server.handle(JsonRpcRequest::new(2, "tools/call", Some(params)), &meta);
+        assert!(reply.replayed);
+    }
+
+    #[test]
+    fn test_idempotency_key_retries_cancelled_call() {
+        let server = server().with_idempotency(IdempotencyCache::from_config(&Default::default()));
+        let session = server.sessions().create().unwrap();
+        let increment = |cancellation: CancellationToken| {
+            let meta = RequestMeta {
+                session_id: Some(session.clone()),
+                idempotency_key: Some("retried".to_string()),
+                cancellation,
+                ..RequestMeta::default()
+            };
+            let params = json!({ "name": "evaluate", "arguments": { "expression": "n = 1" } });
+            server.handle(JsonRpcRequest::new(1, "tools/call", Some(params)), &meta)
+        };
+
+        let cancelled = CancellationToken::new();
+        cancelled.cancel();
+        let reply = increment(cancelled);
+        assert_eq!(reply.response.unwrap().result.unwrap()["isError"], true);
+
+        let retry = increment(CancellationToken::new());
+        assert!(!retry.replayed);
+        let result = retry.response.unwrap().result.unwrap();
+        assert_eq!(result["structuredContent"]["result"], "1");
+        assert!(increment(CancellationToken::new()).replayed);
+    }
+
+    #[test]
+    fn test_spreadsheet_formula_tool() {
+        let server = server();
+        let formula = |arguments: Value| {
+            call(
+                &server,
+                "tools/call",
+                json!({ "name": "spreadsheet_formula", "arguments": arguments }),
+            )
+            .result
+            .unwrap()
+        };
+
+        let result = formula(json!({
+            "formula": "=ROUND(SUM(A1:A3) / $B$1, 2)",
+            "cells": { "A1": 10, "A3": "2 ^ 3", "B1": 3 },
+        }));
+        assert_eq!(result["structuredContent"]["result"], "6.00", "{result}");
+        assert_eq!(
+            result["structuredContent"]["expression"],
+            "round_half_up(((A1 + A3) / B1), (2))"
+        );
+
+        let invalid = formula(json!({ "formula": "=A1", "cells": { "A-1": 1 } }));
+        assert_eq!(invalid["isError"], true);
+    }
+}
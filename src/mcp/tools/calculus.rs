@@ -0,0 +1,171 @@
+use super::{Entry, Tool, ToolContext, parse_arguments, strings};
+use crate::evaluator;
+use crate::evaluator::calculus::{self, Approach, Behavior};
+use bigdecimal::BigDecimal;
+use num_traits::Zero;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+pub struct Derivative;
+
+pub struct Taylor;
+
+pub struct Limit;
+
+#[derive(Deserialize)]
+struct DerivativeArgs {
+    expression: String,
+    #[serde(default = "default_variable")]
+    variable: String,
+    at: Option<Entry>,
+}
+
+#[derive(Deserialize)]
+struct TaylorArgs {
+    expression: String,
+    #[serde(default = "default_variable")]
+    variable: String,
+    around: Option<Entry>,
+    #[serde(default = "default_order")]
+    order: usize,
+}
+
+#[derive(Deserialize)]
+struct LimitArgs {
+    expression: String,
+    #[serde(default = "default_variable")]
+    variable: String,
+    to: Entry,
+}
+
+fn default_variable() -> String {
+    "x".to_string()
+}
+
+fn default_order() -> usize {
+    5
+}
+
+impl Tool for Derivative {
+    fn name(&self) -> &'static str {
+        "derivative"
+    }
+
+    fn description(&self) -> &'static str {
+        "Differentiate an expression symbolically with respect to `variable` (x by default), e.g. `x^2 * sin(x)` gives `2 * x * sin(x) + x ^ 2 * cos(x)`. Handles + - * / ^, ² and ³, and the functions sqrt, abs, sin, cos, tan, exp and ln through the chain rule; other variables are treated as constants. Trigonometric functions are taken in radians. With `at`, the derivative is also evaluated there and returned in `value`; at a point where it does not exist, such as `abs(x)` at 0, the call fails with the one-sided derivatives."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "expression": { "type": "string" },
+                "variable": { "type": "string", "default": "x" },
+                "at": {
+                    "type": ["number", "string"],
+                    "description": "Point at which to evaluate the derivative"
+                }
+            },
+            "required": ["expression"]
+        })
+    }
+
+    fn call(&self, ctx: &ToolContext, arguments: Value) -> anyhow::Result<Value> {
+        let args: DerivativeArgs = parse_arguments(arguments)?;
+        let env = ctx.environment()?;
+        let expr = evaluator::parse_in(&args.expression, &env, ctx.eval)?;
+        let derivative = calculus::derivative(&expr, &args.variable)?;
+        let mut output = json!({ "derivative": derivative.to_string() });
+        if let Some(at) = args.at {
+            let point = at.value(&env, ctx.eval)?;
+            let value = calculus::derivative_at(&expr, &derivative, &args.variable, &point, &env)?;
+            output["value"] = json!(value.to_string());
+        }
+        Ok(output)
+    }
+}
+
+impl Tool for Taylor {
+    fn name(&self) -> &'static str {
+        "taylor"
+    }
+
+    fn description(&self) -> &'static str {
+        "Expand an expression as a Taylor polynomial in `variable` (x by default) around the point `around` (0 by default) up to `order` (5 by default, at most 20), e.g. `sin(x)` gives `x - x ^ 3 / 6 + x ^ 5 / 120`. Returns the `polynomial` as an expression, with each term written as f⁽ᵏ⁾(a) * (x - a) ^ k / k!, and the `coefficients` f⁽ᵏ⁾(a) / k! from the constant term up. Supports the same functions as `derivative`, in radians; fails when a derivative is undefined at the point."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "expression": { "type": "string" },
+                "variable": { "type": "string", "default": "x" },
+                "around": { "type": ["number", "string"], "default": 0 },
+                "order": { "type": "integer", "minimum": 0, "maximum": calculus::MAX_TAYLOR_ORDER, "default": 5 }
+            },
+            "required": ["expression"]
+        })
+    }
+
+    fn call(&self, ctx: &ToolContext, arguments: Value) -> anyhow::Result<Value> {
+        let args: TaylorArgs = parse_arguments(arguments)?;
+        let env = ctx.environment()?;
+        let expr = evaluator::parse_in(&args.expression, &env, ctx.eval)?;
+        let around = match args.around {
+            Some(around) => around.value(&env, ctx.eval)?,
+            None => BigDecimal::zero(),
+        };
+        let taylor = calculus::taylor(&expr, &args.variable, &around, args.order, &env)?;
+        Ok(json!({
+            "polynomial": taylor.polynomial.to_string(),
+            "coefficients": strings(&taylor.coefficients),
+        }))
+    }
+}
+
+impl Tool for Limit {
+    fn name(&self) -> &'static str {
+        "limit"
+    }
+
+    fn description(&self) -> &'static str {
+        "Find the limit of an expression as `variable` (x by default) approaches `to`, a number, an expression such as `pi / 2`, or `inf` / `-inf`, e.g. `sin(x) / x` at 0 gives 1. Returns `limit` (a number, `infinity`, `-infinity`, `diverges` or `undefined`), whether it `exists` as a finite number, the one-sided limits `left` and `right` (only the reachable side for an infinite target), and the `method`: `direct` for a continuous point, `series` for a quotient resolved from the leading terms of its Taylor series, or `numeric` for samples approaching from each side. A side where the expression is undefined defers to the other. Numeric results are estimates, in radians."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "expression": { "type": "string" },
+                "variable": { "type": "string", "default": "x" },
+                "to": {
+                    "type": ["number", "string"],
+                    "description": "Target point, or `inf` / `-inf`"
+                }
+            },
+            "required": ["expression", "to"]
+        })
+    }
+
+    fn call(&self, ctx: &ToolContext, arguments: Value) -> anyhow::Result<Value> {
+        let args: LimitArgs = parse_arguments(arguments)?;
+        let env = ctx.environment()?;
+        let expr = evaluator::parse_in(&args.expression, &env, ctx.eval)?;
+        let to = match &args.to {
+            Entry::Expression(target) => match target.trim().to_ascii_lowercase().as_str() {
+                "inf" | "+inf" | "infinity" | "+infinity" | "∞" => Approach::PositiveInfinity,
+                "-inf" | "-infinity" | "-∞" => Approach::NegativeInfinity,
+                _ => Approach::Point(args.to.value(&env, ctx.eval)?),
+            },
+            Entry::Number(_) => Approach::Point(args.to.value(&env, ctx.eval)?),
+        };
+        let limit = calculus::limit(&expr, &args.variable, &to, &env)?;
+        Ok(json!({
+            "limit": limit.value.to_string(),
+            "exists": matches!(limit.value, Behavior::Finite(_)),
+            "left": limit.left.map(|side| side.to_string()),
+            "right": limit.right.map(|side| side.to_string()),
+            "method": limit.method.as_str(),
+        }))
+    }
+}
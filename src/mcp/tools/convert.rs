@@ -0,0 +1,131 @@
+use super::{Tool, ToolContext, parse_arguments};
+use crate::evaluator::conversions;
+use crate::formatter::number::{NumberFormat, format_number};
+use anyhow::anyhow;
+use bigdecimal::BigDecimal;
+use bigdecimal::num_bigint::BigInt;
+use num_traits::Num;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::str::FromStr;
+
+pub struct Convert;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Notation {
+    Decimal,
+    Roman,
+    Binary,
+    Octal,
+    Hex,
+}
+
+impl Notation {
+    fn radix(self) -> Option<u32> {
+        match self {
+            Notation::Binary => Some(2),
+            Notation::Octal => Some(8),
+            Notation::Hex => Some(16),
+            Notation::Decimal | Notation::Roman => None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ConvertArgs {
+    value: String,
+    from: Notation,
+    to: Notation,
+}
+
+impl Tool for Convert {
+    fn name(&self) -> &'static str {
+        "convert"
+    }
+
+    fn description(&self) -> &'static str {
+        "Convert a number between notations: `decimal`, `roman` (1 to 3999, e.g. MCMXCIV), `binary`, `octal` and `hex` (integers, with or without a `0b`, `0o` or `0x` prefix). The result is in `result`, with the decimal value in `decimal`."
+    }
+
+    fn input_schema(&self) -> Value {
+        let notation = json!({
+            "type": "string",
+            "enum": ["decimal", "roman", "binary", "octal", "hex"]
+        });
+        json!({
+            "type": "object",
+            "properties": {
+                "value": { "type": "string", "description": "Number written in the `from` notation" },
+                "from": notation,
+                "to": notation
+            },
+            "required": ["value", "from", "to"]
+        })
+    }
+
+    fn call(&self, _ctx: &ToolContext, arguments: Value) -> anyhow::Result<Value> {
+        let args: ConvertArgs = parse_arguments(arguments)?;
+        let value = read(args.value.trim(), args.from)?;
+        let result = match args.to {
+            Notation::Roman => conversions::to_roman(&value)?,
+            Notation::Decimal => value.to_string(),
+            to => format_number(
+                &value,
+                &NumberFormat {
+                    base: to.radix(),
+                    ..Default::default()
+                },
+            )?,
+        };
+        Ok(json!({ "result": result, "decimal": value.to_string() }))
+    }
+}
+
+fn read(text: &str, notation: Notation) -> anyhow::Result<BigDecimal> {
+    let invalid = || anyhow!("Not a {:?} number: {}", notation, text);
+    match notation {
+        Notation::Decimal => BigDecimal::from_str(text).map_err(|_| invalid()),
+        Notation::Roman => Ok(BigDecimal::from(conversions::from_roman(text)?)),
+        _ => {
+            let (negative, digits) = match text.strip_prefix('-') {
+                Some(digits) => (true, digits),
+                None => (false, text),
+            };
+            let digits = ["0b", "0o", "0x", "0B", "0O", "0X"]
+                .iter()
+                .find_map(|prefix| digits.strip_prefix(prefix))
+                .unwrap_or(digits)
+                .replace('_', "");
+            let radix = notation.radix().unwrap_or(10);
+            let n = BigInt::from_str_radix(&digits, radix).map_err(|_| invalid())?;
+            Ok(BigDecimal::from(if negative { -n } else { n }))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_notations() {
+        assert_eq!(
+            read("MCMXCIV", Notation::Roman).unwrap(),
+            BigDecimal::from(1994)
+        );
+        assert_eq!(read("0xff", Notation::Hex).unwrap(), BigDecimal::from(255));
+        assert_eq!(
+            read("-0b1010", Notation::Binary).unwrap(),
+            BigDecimal::from(-10)
+        );
+        assert_eq!(
+            read("1111_0000", Notation::Binary).unwrap(),
+            BigDecimal::from(240)
+        );
+        assert_eq!(read("777", Notation::Octal).unwrap(), BigDecimal::from(511));
+        assert!(read("0x1g", Notation::Hex).is_err());
+        assert!(read("12", Notation::Binary).is_err());
+        assert!(read("1.5.2", Notation::Decimal).is_err());
+    }
+}
@@ -0,0 +1,107 @@
+use super::{Tool, ToolContext, parse_arguments};
+use crate::equivalence::{self, Sampling};
+use crate::evaluator::{self};
+use bigdecimal::BigDecimal;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::str::FromStr;
+
+pub struct CheckEquivalence;
+
+#[derive(Deserialize)]
+struct EquivalenceArgs {
+    left: String,
+    right: String,
+    #[serde(default = "default_from")]
+    from: f64,
+    #[serde(default = "default_to")]
+    to: f64,
+    #[serde(default = "default_samples")]
+    samples: usize,
+    #[serde(default = "default_tolerance")]
+    tolerance: String,
+    #[serde(default)]
+    seed: u64,
+}
+
+fn default_from() -> f64 {
+    -10.0
+}
+
+fn default_to() -> f64 {
+    10.0
+}
+
+fn default_samples() -> usize {
+    50
+}
+
+fn default_tolerance() -> String {
+    "1e-9".to_string()
+}
+
+impl Tool for CheckEquivalence {
+    fn name(&self) -> &'static str {
+        "check_equivalence"
+    }
+
+    fn description(&self) -> &'static str {
+        "Check whether two expressions are equal for all values of their variables by evaluating both at pseudo-random points in `[from, to]`, e.g. `(x + 1)^2` and `x^2 + 2*x + 1`. Reports `equivalent`, `structurally_equal` (identical after constant folding), how many points `agreed` or were `skipped` (undefined on both sides), and a `counterexample` with both values (null where undefined) when they differ. Agreement is strong evidence, not proof. Within an MCP session, session variables are treated as constants."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "left": { "type": "string" },
+                "right": { "type": "string" },
+                "from": { "type": "number", "default": -10 },
+                "to": { "type": "number", "default": 10 },
+                "samples": { "type": "integer", "minimum": 1, "maximum": equivalence::MAX_SAMPLES, "default": 50 },
+                "tolerance": {
+                    "type": "string",
+                    "default": "1e-9",
+                    "description": "Largest difference accepted, relative to magnitudes above 1"
+                },
+                "seed": { "type": "integer", "minimum": 0, "default": 0, "description": "Seed of the sample points, for reproducible runs" }
+            },
+            "required": ["left", "right"]
+        })
+    }
+
+    fn call(&self, ctx: &ToolContext, arguments: Value) -> anyhow::Result<Value> {
+        let args: EquivalenceArgs = parse_arguments(arguments)?;
+        let env = ctx.environment()?;
+        let left = evaluator::parse_in(&args.left, &env, ctx.eval)?;
+        let right = evaluator::parse_in(&args.right, &env, ctx.eval)?;
+        let mut variables = evaluator::free_variables(&args.left)?;
+        for variable in evaluator::free_variables(&args.right)? {
+            if !variables.contains(&variable) {
+                variables.push(variable);
+            }
+        }
+        variables.retain(|variable| !env.contains(variable));
+
+        let tolerance = BigDecimal::from_str(&args.tolerance)
+            .map_err(|_| anyhow::anyhow!("Invalid tolerance: {}", args.tolerance))?;
+        let sampling = Sampling {
+            from: args.from,
+            to: args.to,
+            samples: args.samples,
+            tolerance,
+            seed: args.seed,
+        };
+        let verdict = equivalence::check(&left, &right, &variables, &env, &sampling)?;
+        let mut output = serde_json::to_value(&verdict)?;
+        output["variables"] = json!(variables);
+        if let Some(counterexample) = verdict.counterexample {
+            output["counterexample"]["point"] = counterexample
+                .point
+                .into_iter()
+                .map(|(name, value)| (name, json!(value)))
+                .collect::<serde_json::Map<_, _>>()
+                .into();
+        }
+        Ok(output)
+    }
+}
@@ -0,0 +1,156 @@
+use super::{Tool, ToolContext, parse_arguments};
+use crate::evaluator::shadow;
+use crate::evaluator::units::Dimension;
+use crate::evaluator::{self, AngleMode, DataUnits, Environment};
+use crate::evaluator::{Expr, Function, conversions};
+use crate::formatter::representations::{Representation, duration, represent};
+use crate::formatter::{self, Format};
+use crate::logging::spans;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+pub struct Evaluate;
+
+#[derive(Deserialize)]
+struct EvaluateArgs {
+    expression: String,
+    #[serde(default)]
+    format: Format,
+    #[serde(default)]
+    representations: Vec<Representation>,
+    #[serde(default)]
+    uncertainty: bool,
+    #[serde(default)]
+    angle_mode: Option<AngleMode>,
+    #[serde(default)]
+    data_units: Option<DataUnits>,
+}
+
+impl Tool for Evaluate {
+    fn name(&self) -> &'static str {
+        "evaluate"
+    }
+
+    fn description(&self) -> &'static str {
+        "Evaluate an arithmetic expression with arbitrary precision. Supports + - * / % (modulo) ^, postfix ! (factorial), !! (double factorial), # (primorial), % (percent), ² and ³, parentheses, scientific notation, `//` (floor division), functions sqrt, abs, sin, cos, tan, exp, ln, divmod (quotient and remainder in `components`), digits (integer-part digit count), intpart, fracpart, scale (digits after the decimal point), clamp(x, lo, hi), lerp(a, b, t), map_range(x, a1, a2, b1, b2), hypot (any number of arguments), deg2rad, rad2deg, fib, lucas, catalan, triangular (exact integers, non-negative index), popcount and bit_length of integers, rotl(n, k, width) and rotr(n, k, width) rotating n within a word of `width` bits, roman(n) (the Roman numeral of an integer from 1 to 3999 in `roman`) and from_roman(MCMXCIV) (its value), twos(n, width) (the unsigned two's-complement pattern, e.g. twos(-1, 8) = 255) and signed(n, width) (its inverse, e.g. signed(255, 8) = -1), wmean(x1, w1, x2, w2, ...) (weighted mean), geomean and harmean (any number of arguments), vector functions taking components as arguments (norm(x, y, ...), normalize(x, y, ...) with the unit vector in `terms`, and for two vectors of equal length listed one after the other dot, angle in radians, and proj with the projection of the first onto the second in `terms`, e.g. `angle(1, 0, 0, 1)`), quaternions as their w, x, y, z components (qmul(q1, q2) Hamilton product, qconj(q), qnorm(q), and qrotate(q, vx, vy, vz) rotating a 3D vector, results in `terms`), coordinate conversions returning `components` (polar(x, y) to r, theta; cartesian(r, theta); spherical(x, y, z) to r, theta from the z axis, phi azimuth; cylindrical(x, y, z) to rho, phi, z; from_spherical(r, theta, phi) and from_cylindrical(rho, phi, z) to x, y, z; angles follow `angle_mode`) round_half_even(x, digits) (banker's rounding) and round_half_up(x, digits) (ties away from zero) to `digits` decimal places, 0 by default, consumer math returning `total`, `base` and the signed change `delta` in `components` (with_tax(amount, rate), tip(amount, pct), discount(price, pct), with percentages as plain numbers, e.g. tip(80, 18) = 94.40; money amounts are rounded to cents with banker's rounding), future_value(principal, rate, years, monthly_contribution, compounding) (annual rate in percent, compounded `compounding` times a year, 12 by default, with contributions at each month end, rounded to cents; `future_value`, `contributions` and `interest` in `components`) and approx_eq(a, b, tolerance) (1 or 0, with `equal` and `delta` in `components`) and to_fraction(x, max_denominator) (best rational approximation, default denominator limit 1000000, with `numerator` and `denominator` in `components`) and cfrac(x, terms) (continued-fraction coefficients in `terms`, 20 by default), and constants such as pi, e, tau, phi, c, h, g, r, na, kb, ec. Numbers may carry data-size units, giving bytes: B, bit, kB (1000), KiB, MiB, GiB, TiB, PiB, EiB (powers of 1024) and KB, MB, GB, TB, PB, EB (powers of 1000, or of 1024 with `data_units` set to `binary`); a trailing `in <unit>` converts, e.g. `1.5 GiB + 300 MB in MB`. Durations use the units ms, s, min (or m), h, d, w, or `h:mm:ss`, with adjacent quantities adding up, e.g. `2h 45m + 90s` or `1:30:00 * 2`; the result is in seconds, with the normalized `h:mm:ss` in `duration` (unless converted, e.g. `... in min`). LaTeX input such as `\\frac{1}{2} \\cdot \\sqrt{2}` is also accepted. Syntax copied from other tools is normalized: `2*^3` (2×10³), `Sin[x]`, `Log[b, x]`, `log_2(8)` or `log_{2}(8)`, `**` for powers, and `×`, `÷`, `−`, `π`. Statements separated by `;` are evaluated left to right, e.g. `a = 2; b = 3; a ^ b + 1`; `name = expr` binds a variable (returned in `bindings`) and `ans` holds the previous result. Within an MCP session, bindings persist across calls. Comments are ignored: `# ...` to the end of the line (a `#` directly after an operand is the primorial), `/* ... */` blocks, and `// ...` at the start of a line."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "expression": {
+                    "type": "string",
+                    "description": "Expression or `;`-separated statements to evaluate, e.g. `2 * (3 + 4) ^ 2`"
+                },
+                "format": {
+                    "type": "string",
+                    "enum": ["plain", "mathml", "asciimath"],
+                    "default": "plain",
+                    "description": "Also render `expression = result` as MathML or AsciiMath in `formatted`"
+                },
+                "representations": {
+                    "type": "array",
+                    "items": {
+                        "type": "string",
+                        "enum": ["decimal", "scientific", "engineering", "fraction", "hex", "factors", "duration", "roman"]
+                    },
+                    "description": "Additional forms of the result returned in `representations`; inapplicable ones are null"
+                },
+                "angle_mode": {
+                    "type": "string",
+                    "enum": ["radians", "degrees"],
+                    "description": "Unit of angles for trigonometric functions, angle and coordinate conversions (radians by default). Within an MCP session the mode is remembered for later calls"
+                },
+                "data_units": {
+                    "type": "string",
+                    "enum": ["decimal", "binary"],
+                    "description": "Whether KB, MB, GB... mean powers of 1000 (decimal, the default) or 1024 (binary); KiB, MiB... are always 1024 and kB always 1000. Within an MCP session the setting is remembered for later calls"
+                },
+                "uncertainty": {
+                    "type": "boolean",
+                    "default": false,
+                    "description": "Propagate CODATA uncertainties of physical constants (e.g. g) into `uncertainty`: `exact` or the standard uncertainty of the result"
+                }
+            },
+            "required": ["expression"]
+        })
+    }
+
+    fn call(&self, ctx: &ToolContext, arguments: Value) -> anyhow::Result<Value> {
+        let args: EvaluateArgs = parse_arguments(arguments)?;
+        spans::record_expression(&args.expression);
+        let evaluation = match ctx.session_id {
+            Some(id) => {
+                if let Some(mode) = args.angle_mode {
+                    ctx.sessions.set_angle_mode(id, mode)?;
+                }
+                if let Some(units) = args.data_units {
+                    ctx.sessions.set_data_units(id, units)?;
+                }
+                ctx.sessions
+                    .evaluate_statements(id, &args.expression, ctx.eval)?
+            }
+            None => {
+                let mut env = ctx.environment()?;
+                env.set_angle_mode(args.angle_mode.unwrap_or_default());
+                env.set_data_units(args.data_units.unwrap_or_default());
+                evaluator::eval_statements(&args.expression, &mut env, ctx.eval)?
+            }
+        };
+        if let Some(candidate) = ctx.shadow {
+            shadow::compare(candidate, &evaluation, ctx.eval);
+        }
+        let uncertainty = if args.uncertainty {
+            Some(evaluation.uncertainty(ctx.eval)?)
+        } else {
+            None
+        };
+        let result = evaluation.value;
+        spans::record_result(&result, ctx.eval.cache_hits.any());
+        let mut output = json!({ "result": result.to_string() });
+        if let Some(uncertainty) = uncertainty {
+            output["uncertainty"] = json!(match uncertainty {
+                Some(sigma) => sigma.to_scientific_notation(),
+                None => "exact".to_string(),
+            });
+        }
+        if !evaluation.bindings.is_empty() {
+            output["bindings"] = evaluation
+                .bindings
+                .into_iter()
+                .map(|(name, value)| json!({ "name": name, "value": value.to_string() }))
+                .collect();
+        }
+        if let Some(terms) = evaluation.terms {
+            output["terms"] = terms.iter().map(|term| json!(term.to_string())).collect();
+        }
+        if let Some(components) = evaluation.components {
+            output["components"] = components
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), json!(value.to_string())))
+                .collect::<serde_json::Map<_, _>>()
+                .into();
+        }
+        let input = evaluator::strip_comments(&args.expression)?;
+        let statements = evaluator::split_statements(&input);
+        let last = statements.last().copied().unwrap_or_default();
+        let (_, expression) = evaluator::split_assignment(last);
+        if evaluator::units::dimension(expression) == Some(Dimension::Time) {
+            output["duration"] = json!(duration(&result));
+        }
+        if let Ok(Expr::Call(Function::Roman, _)) =
+            evaluator::parse_in(expression, &Environment::new(), ctx.eval)
+        {
+            output["roman"] = json!(conversions::to_roman(&result)?);
+        }
+        if args.format != Format::Plain {
+            let expr = evaluator::parse_in(expression, &Environment::new(), ctx.eval)?;
+            output["formatted"] = json!(formatter::render(&expr, &result, args.format));
+        }
+        if !args.representations.is_empty() {
+            output["representations"] = Value::Object(represent(&result, &args.representations));
+        }
+        Ok(output)
+    }
+}
@@ -0,0 +1,104 @@
+use super::{Tool, ToolContext, parse_arguments};
+use crate::evaluator;
+use crate::formatter::number::{NumberFormat, format_number};
+use bigdecimal::BigDecimal;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::str::FromStr;
+
+pub struct FormatExpression;
+
+pub struct FormatNumber;
+
+#[derive(Deserialize)]
+struct FormatArgs {
+    expression: String,
+}
+
+#[derive(Deserialize)]
+struct FormatNumberArgs {
+    number: String,
+    #[serde(flatten)]
+    format: NumberFormat,
+}
+
+impl Tool for FormatExpression {
+    fn name(&self) -> &'static str {
+        "format_expression"
+    }
+
+    fn description(&self) -> &'static str {
+        "Rewrite an expression in canonical form without evaluating it: minimal parentheses, single spaces around binary operators, lowercase constant and function names, and normalized numbers. Inputs that differ only in such details produce the same output."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "expression": {
+                    "type": "string",
+                    "description": "Expression to normalize, e.g. `((1+2))*X`"
+                }
+            },
+            "required": ["expression"]
+        })
+    }
+
+    fn call(&self, _ctx: &ToolContext, arguments: Value) -> anyhow::Result<Value> {
+        let args: FormatArgs = parse_arguments(arguments)?;
+        Ok(json!({ "formatted": evaluator::format_expression(&args.expression)? }))
+    }
+}
+
+impl Tool for FormatNumber {
+    fn name(&self) -> &'static str {
+        "format_number"
+    }
+
+    fn description(&self) -> &'static str {
+        "Reformat a number, such as a previous `evaluate` result, without evaluating anything: round to `sig_figs` significant figures, write it in `plain`, `scientific` or `engineering` notation, group digits and pick the decimal separator by `locale`, or write an integer in another `base`."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "number": {
+                    "type": "string",
+                    "description": "Decimal number, e.g. `1234567.891` or `6.02214076e23`"
+                },
+                "sig_figs": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": "Significant figures to round to, keeping trailing zeros"
+                },
+                "notation": {
+                    "type": "string",
+                    "enum": ["plain", "scientific", "engineering"],
+                    "default": "plain"
+                },
+                "locale": {
+                    "type": "string",
+                    "enum": ["none", "en", "de", "fr", "ch", "in"],
+                    "default": "none",
+                    "description": "Digit grouping and decimal separator: en `1,234.5`, de `1.234,5`, fr `1 234,5`, ch `1'234.5`, in `12,34,567.5`, none `1234.5`"
+                },
+                "base": {
+                    "type": "integer",
+                    "minimum": 2,
+                    "maximum": 36,
+                    "default": 10,
+                    "description": "Write an integer in this base; 2, 8 and 16 get a `0b`, `0o` or `0x` prefix"
+                }
+            },
+            "required": ["number"]
+        })
+    }
+
+    fn call(&self, _ctx: &ToolContext, arguments: Value) -> anyhow::Result<Value> {
+        let args: FormatNumberArgs = parse_arguments(arguments)?;
+        let number = BigDecimal::from_str(args.number.trim())
+            .map_err(|_| anyhow::anyhow!("Not a decimal number: {}", args.number))?;
+        Ok(json!({ "formatted": format_number(&number, &args.format)? }))
+    }
+}
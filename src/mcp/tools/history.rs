@@ -0,0 +1,63 @@
+use super::{Tool, ToolContext, parse_arguments, require_session};
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+pub struct HistoryList;
+pub struct HistoryClear;
+
+#[derive(Deserialize)]
+struct HistoryListArgs {
+    /// Only return the most recent `last` entries
+    last: Option<usize>,
+}
+
+impl Tool for HistoryList {
+    fn name(&self) -> &'static str {
+        "history_list"
+    }
+
+    fn description(&self) -> &'static str {
+        "List evaluations made in this session with their index, expression, result or error, timestamp and duration. Indexes are stable for the lifetime of the session."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "last": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": "Only return the most recent N entries"
+                }
+            }
+        })
+    }
+
+    fn call(&self, ctx: &ToolContext, arguments: Value) -> anyhow::Result<Value> {
+        let args: HistoryListArgs = parse_arguments(arguments)?;
+        let mut entries = ctx.sessions.history(require_session(ctx)?)?;
+        if let Some(last) = args.last {
+            entries = entries.split_off(entries.len().saturating_sub(last));
+        }
+        Ok(json!({ "entries": entries }))
+    }
+}
+
+impl Tool for HistoryClear {
+    fn name(&self) -> &'static str {
+        "history_clear"
+    }
+
+    fn description(&self) -> &'static str {
+        "Delete the evaluation history of this session. Variables are kept."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({ "type": "object", "properties": {} })
+    }
+
+    fn call(&self, ctx: &ToolContext, _arguments: Value) -> anyhow::Result<Value> {
+        let removed = ctx.sessions.clear_history(require_session(ctx)?)?;
+        Ok(json!({ "removed": removed }))
+    }
+}
@@ -0,0 +1,100 @@
+use super::{Entry, Tool, ToolContext, parse_arguments, strings, values};
+use crate::evaluator::matrix::{self, Eigenvalue};
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+pub struct Matrix;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Operation {
+    Determinant,
+    Inverse,
+    Solve,
+    Eigen,
+}
+
+#[derive(Deserialize)]
+struct MatrixArgs {
+    operation: Operation,
+    matrix: Vec<Vec<Entry>>,
+    #[serde(default)]
+    b: Option<Vec<Entry>>,
+}
+
+impl Tool for Matrix {
+    fn name(&self) -> &'static str {
+        "matrix"
+    }
+
+    fn description(&self) -> &'static str {
+        "Exact linear algebra on a small square matrix: `determinant`, `inverse`, `solve` (linsolve: the `x` with `matrix * x = b`), or `eigen` (eigenvalues and eigenvectors of a matrix up to 3x3; complex eigenvalues are `{re, im}` objects with a null eigenvector, and eigenvectors are scaled so one component is 1). Entries are numbers or expressions such as `1/3` or `sqrt(2)`; within an MCP session, session variables are available. Elimination runs on exact rationals, so results are rounded only once, to the configured precision. Singular matrices are reported as errors."
+    }
+
+    fn input_schema(&self) -> Value {
+        let entry = json!({ "type": ["number", "string"] });
+        json!({
+            "type": "object",
+            "properties": {
+                "operation": {
+                    "type": "string",
+                    "enum": ["determinant", "inverse", "solve", "eigen"]
+                },
+                "matrix": {
+                    "type": "array",
+                    "items": { "type": "array", "items": entry },
+                    "description": "Rows of a square matrix, e.g. `[[2, 1], [1, 3]]`"
+                },
+                "b": {
+                    "type": "array",
+                    "items": entry,
+                    "description": "Right-hand side for `solve`, one entry per row"
+                }
+            },
+            "required": ["operation", "matrix"]
+        })
+    }
+
+    fn call(&self, ctx: &ToolContext, arguments: Value) -> anyhow::Result<Value> {
+        let args: MatrixArgs = parse_arguments(arguments)?;
+        let env = ctx.environment()?;
+        let rows = args
+            .matrix
+            .iter()
+            .map(|row| values(row, &env, ctx.eval))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(match args.operation {
+            Operation::Determinant => {
+                json!({ "determinant": matrix::determinant(&rows)?.to_string() })
+            }
+            Operation::Inverse => {
+                let inverse = matrix::inverse(&rows)?;
+                json!({ "inverse": inverse.iter().map(|row| strings(row)).collect::<Vec<_>>() })
+            }
+            Operation::Solve => {
+                let b = args
+                    .b
+                    .as_deref()
+                    .ok_or_else(|| anyhow::anyhow!("`solve` requires `b`"))?;
+                json!({ "solution": strings(&matrix::solve(&rows, &values(b, &env, ctx.eval)?)?) })
+            }
+            Operation::Eigen => {
+                let pairs = matrix::eigen(&rows)?;
+                let eigenvalues: Vec<Value> = pairs
+                    .iter()
+                    .map(|pair| match &pair.value {
+                        Eigenvalue::Real(value) => json!(value.to_string()),
+                        Eigenvalue::Complex { re, im } => {
+                            json!({ "re": re.to_string(), "im": im.to_string() })
+                        }
+                    })
+                    .collect();
+                let eigenvectors: Vec<Value> = pairs
+                    .iter()
+                    .map(|pair| pair.vector.as_deref().map_or(Value::Null, strings))
+                    .collect();
+                json!({ "eigenvalues": eigenvalues, "eigenvectors": eigenvectors })
+            }
+        })
+    }
+}
@@ -0,0 +1,150 @@
+use crate::evaluator::shadow::Candidate;
+use crate::evaluator::{self, Environment, EvalContext};
+use crate::session::SessionStore;
+use bigdecimal::BigDecimal;
+use serde::Deserialize;
+use serde_json::{Number, Value, json};
+use std::str::FromStr;
+
+pub mod calculus;
+#[cfg(feature = "checksums")]
+pub mod checksum;
+pub mod convert;
+pub mod equivalence;
+pub mod evaluate;
+pub mod format;
+pub mod history;
+pub mod matrix;
+pub mod plot;
+pub mod proportion;
+pub mod saved;
+pub mod solve;
+pub mod spreadsheet;
+pub mod time;
+pub mod validate;
+
+/// Per-call state available to tools.
+pub struct ToolContext<'a> {
+    pub sessions: &'a SessionStore,
+    /// Present when the client negotiated a session via `Mcp-Session-Id`.
+    pub session_id: Option<&'a str>,
+    /// Candidate evaluator to dry-run next to the primary one, if shadowing is on.
+    pub shadow: Option<&'a dyn Candidate>,
+    /// Constants of the caller's tenant, when tenants are enabled.
+    pub constants: Option<&'a Environment>,
+    /// Budget, cancellation and locale of the call, for the evaluator's entry points.
+    pub eval: &'a EvalContext,
+}
+
+impl ToolContext<'_> {
+    /// Variables in scope for the call: the session's, or else the tenant's
+    /// constants.
+    pub fn environment(&self) -> anyhow::Result<Environment> {
+        match self.session_id {
+            Some(id) => self
+                .sessions
+                .with_session(id, |session| session.env.clone()),
+            None => Ok(self.constants.cloned().unwrap_or_default()),
+        }
+    }
+}
+
+/// An MCP tool. `call` returns the structured result; the server wraps it into
+/// `content`/`structuredContent`, and an `Err` becomes an `isError` tool result.
+pub trait Tool: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn description(&self) -> &'static str;
+    fn input_schema(&self) -> Value;
+    fn call(&self, ctx: &ToolContext, arguments: Value) -> anyhow::Result<Value>;
+
+    fn definition(&self) -> Value {
+        json!({
+            "name": self.name(),
+            "description": self.description(),
+            "inputSchema": self.input_schema(),
+        })
+    }
+
+    /// The tool in OpenAI function-calling format, for orchestrators without MCP.
+    fn openai_definition(&self) -> Value {
+        json!({
+            "type": "function",
+            "function": {
+                "name": self.name(),
+                "description": self.description(),
+                "parameters": self.input_schema(),
+            },
+        })
+    }
+}
+
+pub fn default_tools() -> Vec<Box<dyn Tool>> {
+    #[allow(unused_mut)]
+    let mut tools: Vec<Box<dyn Tool>> = vec![
+        Box::new(evaluate::Evaluate),
+        Box::new(format::FormatExpression),
+        Box::new(format::FormatNumber),
+        Box::new(convert::Convert),
+        Box::new(validate::Validate),
+        Box::new(spreadsheet::SpreadsheetFormula),
+        Box::new(plot::PlotData),
+        Box::new(equivalence::CheckEquivalence),
+        Box::new(calculus::Derivative),
+        Box::new(calculus::Taylor),
+        Box::new(calculus::Limit),
+        Box::new(solve::Solve),
+        Box::new(matrix::Matrix),
+        Box::new(time::TimeBetween),
+        Box::new(proportion::Proportion),
+        Box::new(history::HistoryList),
+        Box::new(history::HistoryClear),
+        Box::new(saved::SaveExpression),
+        Box::new(saved::RunSaved),
+    ];
+    #[cfg(feature = "checksums")]
+    tools.push(Box::new(checksum::Checksum));
+    tools
+}
+
+/// The session id for tools that only make sense within an MCP session.
+pub fn require_session<'a>(ctx: &'a ToolContext) -> anyhow::Result<&'a str> {
+    ctx.session_id
+        .ok_or_else(|| anyhow::anyhow!("This tool requires an MCP session (Mcp-Session-Id)"))
+}
+
+/// Deserialize tool arguments, reporting schema mismatches as tool errors.
+pub fn parse_arguments<T: serde::de::DeserializeOwned>(arguments: Value) -> anyhow::Result<T> {
+    serde_json::from_value(arguments).map_err(|err| anyhow::anyhow!("Invalid arguments: {err}"))
+}
+
+/// A numeric argument given either as a JSON number or as an expression.
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum Entry {
+    Number(Number),
+    Expression(String),
+}
+
+impl Entry {
+    pub fn value(&self, env: &Environment, ctx: &EvalContext) -> anyhow::Result<BigDecimal> {
+        match self {
+            Entry::Number(number) => Ok(BigDecimal::from_str(&number.to_string())?),
+            Entry::Expression(expression) => evaluator::eval_with(expression, env, ctx),
+        }
+    }
+}
+
+pub fn values(
+    entries: &[Entry],
+    env: &Environment,
+    ctx: &EvalContext,
+) -> anyhow::Result<Vec<BigDecimal>> {
+    entries.iter().map(|entry| entry.value(env, ctx)).collect()
+}
+
+pub fn strings(values: &[BigDecimal]) -> Value {
+    values
+        .iter()
+        .map(|value| json!(value.to_string()))
+        .collect()
+}
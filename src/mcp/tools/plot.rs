@@ -0,0 +1,99 @@
+use super::{Tool, ToolContext, parse_arguments};
+use crate::evaluator::{self};
+use crate::plot;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+pub struct PlotData;
+
+#[derive(Deserialize)]
+struct PlotArgs {
+    expression: String,
+    #[serde(default = "default_variable")]
+    variable: String,
+    from: f64,
+    to: f64,
+    #[serde(default = "default_samples")]
+    samples: usize,
+    #[serde(default)]
+    exact: bool,
+    #[serde(default)]
+    svg: bool,
+    #[serde(default = "default_width")]
+    width: u32,
+    #[serde(default = "default_height")]
+    height: u32,
+}
+
+fn default_variable() -> String {
+    "x".to_string()
+}
+
+fn default_samples() -> usize {
+    101
+}
+
+fn default_width() -> u32 {
+    480
+}
+
+fn default_height() -> u32 {
+    320
+}
+
+impl Tool for PlotData {
+    fn name(&self) -> &'static str {
+        "plot_data"
+    }
+
+    fn description(&self) -> &'static str {
+        "Sample an expression of one variable over a range and return the points, optionally with an SVG line chart. Points where the expression is undefined have `y: null`. Within an MCP session, other session variables are available."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "expression": { "type": "string", "description": "Expression to plot, e.g. `sin(x) / x`" },
+                "variable": { "type": "string", "default": "x" },
+                "from": { "type": "number" },
+                "to": { "type": "number" },
+                "samples": { "type": "integer", "minimum": 2, "maximum": plot::MAX_SAMPLES, "default": 101 },
+                "exact": { "type": "boolean", "default": false, "description": "Evaluate every point with exact decimal arithmetic instead of the faster floating-point path" },
+                "svg": { "type": "boolean", "default": false, "description": "Include an SVG rendering in `svg`" },
+                "width": { "type": "integer", "minimum": 64, "maximum": 4096, "default": 480 },
+                "height": { "type": "integer", "minimum": 64, "maximum": 4096, "default": 320 }
+            },
+            "required": ["expression", "from", "to"]
+        })
+    }
+
+    fn call(&self, ctx: &ToolContext, arguments: Value) -> anyhow::Result<Value> {
+        let args: PlotArgs = parse_arguments(arguments)?;
+        let env = ctx.environment()?;
+        let expr = evaluator::parse_in(&args.expression, &env, ctx.eval)?;
+        let points = plot::sample(
+            &expr,
+            &env,
+            &args.variable,
+            args.from,
+            args.to,
+            args.samples,
+            args.exact,
+        )?;
+
+        let mut result = json!({
+            "expression": expr.to_string(),
+            "variable": args.variable,
+            "points": points,
+        });
+        if args.svg {
+            result["svg"] = json!(plot::render_svg(
+                &points,
+                args.width.clamp(64, 4096),
+                args.height.clamp(64, 4096),
+            ));
+        }
+        Ok(result)
+    }
+}
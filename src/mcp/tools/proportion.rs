@@ -0,0 +1,222 @@
+use super::{Entry, Tool, ToolContext, parse_arguments, strings, values};
+use crate::evaluator::limits::limits;
+use crate::evaluator::precision::divide;
+use anyhow::{anyhow, bail};
+use bigdecimal::BigDecimal;
+use num_traits::Zero;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+pub struct Proportion;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Operation {
+    Solve,
+    Scale,
+    Split,
+}
+
+#[derive(Deserialize)]
+struct Item {
+    name: String,
+    amount: Entry,
+}
+
+#[derive(Deserialize)]
+struct ProportionArgs {
+    operation: Operation,
+    #[serde(default)]
+    a: Option<Entry>,
+    #[serde(default)]
+    b: Option<Entry>,
+    #[serde(default)]
+    c: Option<Entry>,
+    #[serde(default)]
+    d: Option<Entry>,
+    #[serde(default)]
+    items: Vec<Item>,
+    #[serde(default)]
+    factor: Option<Entry>,
+    #[serde(default)]
+    from: Option<Entry>,
+    #[serde(default)]
+    to: Option<Entry>,
+    #[serde(default)]
+    ratio: Vec<Entry>,
+    #[serde(default)]
+    total: Option<Entry>,
+}
+
+impl Tool for Proportion {
+    fn name(&self) -> &'static str {
+        "proportion"
+    }
+
+    fn description(&self) -> &'static str {
+        "Ratio and proportion problems. `solve`: give three of `a`, `b`, `c`, `d` in `a / b = c / d` and get the missing one, e.g. 3 eggs for 4 people, how many for 10. `scale`: multiply every amount in `items` (e.g. a recipe) by `factor`, or by `to / from` such as servings 4 to 6. `split`: divide `total` into parts in the given `ratio`, e.g. a 2:3 mixture of 500 ml. Values are numbers or expressions; within an MCP session, session variables are available."
+    }
+
+    fn input_schema(&self) -> Value {
+        let entry = json!({ "type": ["number", "string"] });
+        json!({
+            "type": "object",
+            "properties": {
+                "operation": { "type": "string", "enum": ["solve", "scale", "split"] },
+                "a": entry,
+                "b": entry,
+                "c": entry,
+                "d": entry,
+                "items": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": { "name": { "type": "string" }, "amount": entry },
+                        "required": ["name", "amount"]
+                    },
+                    "description": "Amounts to scale, e.g. `[{\"name\": \"flour\", \"amount\": 200}]`"
+                },
+                "factor": entry,
+                "from": entry,
+                "to": entry,
+                "ratio": { "type": "array", "items": entry, "description": "Parts of the mixture, e.g. `[2, 3]`" },
+                "total": entry
+            },
+            "required": ["operation"]
+        })
+    }
+
+    fn call(&self, ctx: &ToolContext, arguments: Value) -> anyhow::Result<Value> {
+        let args: ProportionArgs = parse_arguments(arguments)?;
+        let env = ctx.environment()?;
+        let value = |entry: &Option<Entry>| {
+            entry
+                .as_ref()
+                .map(|entry| entry.value(&env, ctx.eval))
+                .transpose()
+        };
+        Ok(match args.operation {
+            Operation::Solve => {
+                let terms = [
+                    value(&args.a)?,
+                    value(&args.b)?,
+                    value(&args.c)?,
+                    value(&args.d)?,
+                ];
+                let (unknown, solved) = solve(terms)?;
+                json!({ "unknown": unknown, "value": solved.to_string() })
+            }
+            Operation::Scale => {
+                let (numerator, denominator) =
+                    match (value(&args.factor)?, value(&args.from)?, value(&args.to)?) {
+                        (Some(factor), None, None) => (factor, BigDecimal::from(1)),
+                        (None, Some(from), Some(to)) => (to, from),
+                        _ => bail!("`scale` requires either `factor` or both `from` and `to`"),
+                    };
+                if denominator.is_zero() {
+                    bail!("Cannot scale from zero");
+                }
+                let items = args
+                    .items
+                    .iter()
+                    .map(|item| {
+                        let amount = divide(
+                            &(item.amount.value(&env, ctx.eval)? * &numerator),
+                            &denominator,
+                            limits().precision,
+                        );
+                        Ok(json!({ "name": item.name, "amount": amount.to_string() }))
+                    })
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                let factor = divide(&numerator, &denominator, limits().precision);
+                json!({ "factor": factor.to_string(), "items": items })
+            }
+            Operation::Split => {
+                let total =
+                    value(&args.total)?.ok_or_else(|| anyhow!("`split` requires `total`"))?;
+                json!({ "parts": strings(&split(&values(&args.ratio, &env, ctx.eval)?, &total)?) })
+            }
+        })
+    }
+}
+
+/// The name and value of the single missing term of `a / b = c / d`.
+fn solve(terms: [Option<BigDecimal>; 4]) -> anyhow::Result<(&'static str, BigDecimal)> {
+    let digits = limits().precision;
+    let (unknown, solved) = match terms {
+        [None, Some(b), Some(c), Some(d)] => ("a", (b * c, d)),
+        [Some(a), None, Some(c), Some(d)] => ("b", (a * d, c)),
+        [Some(a), Some(b), None, Some(d)] => ("c", (a * d, b)),
+        [Some(a), Some(b), Some(c), None] => ("d", (b * c, a)),
+        _ => bail!("`solve` requires exactly three of `a`, `b`, `c` and `d`"),
+    };
+    let (numerator, denominator) = solved;
+    if denominator.is_zero() {
+        bail!("The proportion has no solution: it would divide by zero");
+    }
+    Ok((unknown, divide(&numerator, &denominator, digits)))
+}
+
+/// `total` divided in proportion to `ratio`.
+fn split(ratio: &[BigDecimal], total: &BigDecimal) -> anyhow::Result<Vec<BigDecimal>> {
+    if ratio.len() < 2 {
+        bail!("`split` requires a ratio of at least two parts");
+    }
+    if ratio.iter().any(|part| part < &BigDecimal::zero()) {
+        bail!("Ratio parts must not be negative");
+    }
+    let sum: BigDecimal = ratio.iter().sum();
+    if sum.is_zero() {
+        bail!("Ratio parts must not all be zero");
+    }
+    Ok(ratio
+        .iter()
+        .map(|part| divide(&(part * total), &sum, limits().precision))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn number(n: i64) -> Option<BigDecimal> {
+        Some(BigDecimal::from(n))
+    }
+
+    #[test]
+    fn test_proportions() {
+        assert_eq!(
+            solve([number(3), number(4), None, number(10)]).unwrap(),
+            ("c", BigDecimal::from(30) / BigDecimal::from(4))
+        );
+        assert_eq!(
+            solve([number(2), number(5), number(6), None]).unwrap(),
+            ("d", BigDecimal::from(15))
+        );
+        assert_eq!(
+            solve([None, number(3), number(1), number(9)]).unwrap().1,
+            divide(
+                &BigDecimal::from(1),
+                &BigDecimal::from(3),
+                limits().precision
+            )
+        );
+        assert!(solve([number(1), number(2), number(3), number(4)]).is_err());
+        assert!(solve([number(0), number(2), number(3), None]).is_err());
+
+        let parts = split(
+            &[BigDecimal::from(2), BigDecimal::from(3)],
+            &BigDecimal::from(500),
+        )
+        .unwrap();
+        assert_eq!(parts, [BigDecimal::from(200), BigDecimal::from(300)]);
+        assert!(split(&[BigDecimal::from(1)], &BigDecimal::from(5)).is_err());
+        assert!(
+            split(
+                &[BigDecimal::from(0), BigDecimal::from(0)],
+                &BigDecimal::from(5)
+            )
+            .is_err()
+        );
+    }
+}
@@ -0,0 +1,99 @@
+use super::{Tool, ToolContext, parse_arguments, require_session};
+use crate::session::SavedExpression;
+use anyhow::anyhow;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+pub struct SaveExpression;
+pub struct RunSaved;
+
+#[derive(Deserialize)]
+struct SaveExpressionArgs {
+    name: String,
+    expression: String,
+    params: Option<Vec<String>>,
+}
+
+#[derive(Deserialize)]
+struct RunSavedArgs {
+    name: String,
+    #[serde(default)]
+    args: Vec<Value>,
+}
+
+impl Tool for SaveExpression {
+    fn name(&self) -> &'static str {
+        "save_expression"
+    }
+
+    fn description(&self) -> &'static str {
+        "Save a parameterized expression under a name for reuse with run_saved. Parameters default to the expression's free variables in order of first use; other session variables are read at run time."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "name": { "type": "string", "description": "Identifier to save the expression under" },
+                "expression": { "type": "string", "description": "Expression, e.g. `p * (1 + r) ^ n`" },
+                "params": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Parameter names in positional order"
+                }
+            },
+            "required": ["name", "expression"]
+        })
+    }
+
+    fn call(&self, ctx: &ToolContext, arguments: Value) -> anyhow::Result<Value> {
+        let args: SaveExpressionArgs = parse_arguments(arguments)?;
+        let saved = SavedExpression::new(&args.expression, args.params)?;
+        let params = saved.params.clone();
+        ctx.sessions
+            .save_expression(require_session(ctx)?, &args.name, saved)?;
+        Ok(json!({ "name": args.name, "params": params }))
+    }
+}
+
+impl Tool for RunSaved {
+    fn name(&self) -> &'static str {
+        "run_saved"
+    }
+
+    fn description(&self) -> &'static str {
+        "Run an expression stored with save_expression, passing positional arguments (numbers or expressions). The result becomes `ans`."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "name": { "type": "string" },
+                "args": {
+                    "type": "array",
+                    "items": { "type": ["string", "number"] },
+                    "description": "Values for the parameters, in order"
+                }
+            },
+            "required": ["name"]
+        })
+    }
+
+    fn call(&self, ctx: &ToolContext, arguments: Value) -> anyhow::Result<Value> {
+        let args: RunSavedArgs = parse_arguments(arguments)?;
+        let values = args
+            .args
+            .iter()
+            .map(|value| match value {
+                Value::String(expression) => Ok(expression.clone()),
+                Value::Number(number) => Ok(number.to_string()),
+                other => Err(anyhow!("Unsupported argument: {}", other)),
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let result =
+            ctx.sessions
+                .run_saved(require_session(ctx)?, &args.name, &values, ctx.eval)?;
+        Ok(json!({ "result": result.to_string() }))
+    }
+}
@@ -0,0 +1,81 @@
+use super::{Tool, ToolContext, parse_arguments, strings};
+use crate::evaluator::solver;
+use anyhow::bail;
+use serde::Deserialize;
+use serde_json::{Map, Value, json};
+
+pub struct Solve;
+
+#[derive(Deserialize)]
+struct SolveArgs {
+    equation: Option<String>,
+    variable: Option<String>,
+    #[serde(default)]
+    equations: Vec<String>,
+    variables: Option<Vec<String>>,
+}
+
+impl Tool for Solve {
+    fn name(&self) -> &'static str {
+        "solve"
+    }
+
+    fn description(&self) -> &'static str {
+        "Solve a linear or quadratic equation or inequality in one variable, e.g. `2 * x + 1 = 7` or `x^2 - 4 > 0`. Relations are =, !=, <, <=, >, >= (or ≠, ≤, ≥). `variable` defaults to the only unknown. Returns the real `roots` of left side minus right side, the solution set as `intervals` (each with `lower` and `upper`, null when infinite, and whether each end is `closed`), and its interval `notation`, e.g. `(-inf, -2) ∪ (2, inf)`, `{3}` or `∅`. Pass `equations` instead for a system of linear equations, e.g. [`2x + y = 5`, `x - y = 1`], solved exactly for `variables` (by default every unknown, in order of appearance) and returned as a `solution` map such as {\"x\": \"2\", \"y\": \"1\"}; it needs as many equations as unknowns and a unique solution. Coefficients may be written next to variables, as in `2x`. Within an MCP session, session variables are treated as constants."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "equation": { "type": "string" },
+                "variable": { "type": "string" },
+                "equations": { "type": "array", "items": { "type": "string" } },
+                "variables": { "type": "array", "items": { "type": "string" } }
+            }
+        })
+    }
+
+    fn call(&self, ctx: &ToolContext, arguments: Value) -> anyhow::Result<Value> {
+        let args: SolveArgs = parse_arguments(arguments)?;
+        let env = ctx.environment()?;
+        let equation = match (args.equation, args.equations.is_empty()) {
+            (Some(equation), true) => equation,
+            (None, false) => {
+                let solution = solver::solve_system(
+                    &args.equations,
+                    args.variables.as_deref(),
+                    &env,
+                    ctx.eval,
+                )?;
+                let variables: Vec<&str> = solution.iter().map(|(name, _)| name.as_str()).collect();
+                let values: Map<String, Value> = solution
+                    .iter()
+                    .map(|(name, value)| (name.clone(), json!(value.to_string())))
+                    .collect();
+                return Ok(json!({ "variables": variables, "solution": values }));
+            }
+            _ => bail!("Pass either `equation` or `equations`"),
+        };
+        let solution = solver::solve(&equation, args.variable.as_deref(), &env, ctx.eval)?;
+        let intervals: Vec<Value> = solution
+            .intervals
+            .iter()
+            .map(|interval| {
+                json!({
+                    "lower": interval.lower.as_ref().map(ToString::to_string),
+                    "upper": interval.upper.as_ref().map(ToString::to_string),
+                    "lower_closed": interval.lower_closed,
+                    "upper_closed": interval.upper_closed,
+                })
+            })
+            .collect();
+        Ok(json!({
+            "variable": solution.variable,
+            "relation": solution.relation.as_str(),
+            "roots": strings(&solution.roots),
+            "intervals": intervals,
+            "notation": solution.notation(),
+        }))
+    }
+}
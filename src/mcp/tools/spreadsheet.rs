@@ -0,0 +1,57 @@
+use super::{Entry, Tool, ToolContext, parse_arguments};
+use crate::evaluator::{self, spreadsheet};
+use anyhow::anyhow;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+
+pub struct SpreadsheetFormula;
+
+#[derive(Deserialize)]
+struct SpreadsheetFormulaArgs {
+    formula: String,
+    #[serde(default)]
+    cells: BTreeMap<String, Entry>,
+}
+
+impl Tool for SpreadsheetFormula {
+    fn name(&self) -> &'static str {
+        "spreadsheet_formula"
+    }
+
+    fn description(&self) -> &'static str {
+        "Evaluate a spreadsheet formula as written, e.g. `=SUM(A1:A3) * 2` or `=ROUND(B2 / C2, 2)`, with cell values given in `cells` (e.g. `{\"A1\": 10, \"$B$2\": \"1/3\"}`). Supports SUM, PRODUCT, AVERAGE, COUNT, MIN, MAX, ROUND, POWER, MOD, INT, ABS, SQRT, EXP, LN, SIN, COS, TAN and PI, ranges as function arguments (blank cells are skipped), `;` or `,` between arguments, and TRUE/FALSE as 1/0. Returns the `result` and the equivalent calculator `expression`. Text, comparisons and IF are not supported. Within an MCP session, session variables are available."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "formula": {
+                    "type": "string",
+                    "description": "Formula with or without the leading `=`"
+                },
+                "cells": {
+                    "type": "object",
+                    "additionalProperties": { "type": ["number", "string"] },
+                    "description": "Cell values by reference, as numbers or expressions"
+                }
+            },
+            "required": ["formula"]
+        })
+    }
+
+    fn call(&self, ctx: &ToolContext, arguments: Value) -> anyhow::Result<Value> {
+        let args: SpreadsheetFormulaArgs = parse_arguments(arguments)?;
+        let mut env = ctx.environment()?;
+        for (reference, entry) in &args.cells {
+            let name = spreadsheet::cell_name(reference)
+                .ok_or_else(|| anyhow!("Invalid cell reference: {reference}"))?;
+            let value = entry.value(&env, ctx.eval)?;
+            env.set(&name, value)?;
+        }
+        let expression = spreadsheet::translate(&args.formula, &env, ctx.eval)?;
+        let result = evaluator::eval_with(&expression, &env, ctx.eval)?;
+        Ok(json!({ "result": result.to_string(), "expression": expression }))
+    }
+}
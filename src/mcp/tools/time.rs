@@ -0,0 +1,110 @@
+use super::{Tool, ToolContext, parse_arguments};
+use crate::evaluator::limits::limits;
+use crate::evaluator::precision::divide;
+use crate::formatter::representations::duration;
+use anyhow::Context;
+use bigdecimal::BigDecimal;
+use bigdecimal::num_bigint::BigInt;
+use chrono::{DateTime, FixedOffset};
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+pub struct TimeBetween;
+
+#[derive(Deserialize)]
+struct TimeBetweenArgs {
+    start: String,
+    end: String,
+}
+
+/// Seconds per unit reported by `time_between`.
+const UNITS: [(&str, u32); 4] = [
+    ("minutes", 60),
+    ("hours", 3600),
+    ("days", 86_400),
+    ("weeks", 604_800),
+];
+
+impl Tool for TimeBetween {
+    fn name(&self) -> &'static str {
+        "time_between"
+    }
+
+    fn description(&self) -> &'static str {
+        "Exact time elapsed between two ISO-8601 timestamps with zone offsets, e.g. `2024-03-30T22:00:00+01:00` and `2024-03-31T09:30:00Z`. Returns the signed difference `end - start` in seconds, minutes, hours, days and weeks, plus `duration` as `h:mm:ss`. Offsets are applied exactly, so daylight-saving changes are accounted for when each timestamp carries its own offset."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "start": {
+                    "type": "string",
+                    "description": "ISO-8601 timestamp with `Z` or an offset such as `+05:30`, e.g. `2024-01-01T00:00:00Z`"
+                },
+                "end": {
+                    "type": "string",
+                    "description": "ISO-8601 timestamp with `Z` or an offset; may be before `start`, giving negative values"
+                }
+            },
+            "required": ["start", "end"]
+        })
+    }
+
+    fn call(&self, _ctx: &ToolContext, arguments: Value) -> anyhow::Result<Value> {
+        let args: TimeBetweenArgs = parse_arguments(arguments)?;
+        let seconds = seconds_between(&timestamp(&args.start)?, &timestamp(&args.end)?);
+        let mut output = json!({
+            "seconds": seconds.to_string(),
+            "duration": duration(&seconds),
+        });
+        for (unit, size) in UNITS {
+            let value = divide(&seconds, &BigDecimal::from(size), limits().precision);
+            output[unit] = json!(value.to_string());
+        }
+        Ok(output)
+    }
+}
+
+fn timestamp(text: &str) -> anyhow::Result<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc3339(text.trim()).with_context(|| {
+        format!("Invalid timestamp {text:?}: expected e.g. 2024-01-01T12:00:00Z or 2024-01-01T12:00:00+02:00")
+    })
+}
+
+/// `end - start` in seconds, exact to the nanosecond.
+fn seconds_between(start: &DateTime<FixedOffset>, end: &DateTime<FixedOffset>) -> BigDecimal {
+    let delta = end.signed_duration_since(start);
+    let nanos = BigInt::from(delta.num_seconds()) * 1_000_000_000 + delta.subsec_nanos();
+    BigDecimal::new(nanos, 9).normalized()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seconds_between() {
+        let between = |start: &str, end: &str| {
+            seconds_between(&timestamp(start).unwrap(), &timestamp(end).unwrap()).to_string()
+        };
+        assert_eq!(
+            between("2024-03-30T22:00:00+01:00", "2024-03-31T09:30:00+02:00"),
+            "37800"
+        );
+        assert_eq!(
+            between("2024-01-01T00:00:00Z", "2023-12-31T23:59:59.25Z"),
+            "-0.75"
+        );
+        assert_eq!(
+            between("2024-02-28T00:00:00Z", "2024-03-01T00:00:00Z"),
+            "172800"
+        );
+        assert_eq!(
+            between("1900-01-01T00:00:00Z", "2400-01-01T00:00:00Z"),
+            "15778454400"
+        );
+        assert!(timestamp("2024-01-01T00:00:00").is_err());
+        assert!(timestamp("yesterday").is_err());
+    }
+}
@@ -0,0 +1,58 @@
+use super::{Tool, ToolContext, parse_arguments};
+use crate::evaluator::{self, ParseError, limits::limits};
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+pub struct Validate;
+
+#[derive(Deserialize)]
+struct ValidateArgs {
+    expression: String,
+}
+
+impl Tool for Validate {
+    fn name(&self) -> &'static str {
+        "validate"
+    }
+
+    fn description(&self) -> &'static str {
+        "Check an expression without evaluating it. Returns `valid`, its free `variables` and a `cost` estimate (token count, weighted operations, largest exponent and intermediate size); expressions whose cost exceeds the server budget are reported as invalid. Syntax errors come back as `error` with a character `span`."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "expression": {
+                    "type": "string",
+                    "description": "Expression to check, e.g. `2 ^ 1000 * x`"
+                }
+            },
+            "required": ["expression"]
+        })
+    }
+
+    fn call(&self, _ctx: &ToolContext, arguments: Value) -> anyhow::Result<Value> {
+        let args: ValidateArgs = parse_arguments(arguments)?;
+        let (_, expression) = evaluator::split_assignment(&args.expression);
+        let checked = evaluator::free_variables(expression)
+            .and_then(|variables| Ok((variables, evaluator::cost_estimate(expression)?)));
+        Ok(match checked {
+            Ok((variables, cost)) => {
+                let mut output = json!({ "valid": true, "variables": variables, "cost": cost });
+                if let Err(exceeded) = limits().check_cost(&cost) {
+                    output["valid"] = json!(false);
+                    output["error"] = json!(exceeded.to_string());
+                }
+                output
+            }
+            Err(err) => {
+                let mut output = json!({ "valid": false, "error": err.to_string() });
+                if let Some(parse) = err.downcast_ref::<ParseError>() {
+                    output["span"] = json!(parse.span);
+                }
+                output
+            }
+        })
+    }
+}
@@ -0,0 +1,211 @@
+use anyhow::bail;
+use bigdecimal::BigDecimal;
+use num_traits::ToPrimitive;
+use serde::Serialize;
+use std::fmt::Write;
+use std::str::FromStr;
+
+use crate::evaluator::{Environment, Expr, validate_variable_name};
+
+mod simd;
+
+pub const MAX_SAMPLES: usize = 2000;
+const MARGIN: f64 = 10.0;
+
+/// One sample; `y` is `None` where the expression is undefined (e.g. division by zero).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Point {
+    pub x: f64,
+    pub y: Option<f64>,
+}
+
+/// Evaluate `expr` at `samples` evenly spaced values of `variable` in `[from, to]`.
+/// Unless `exact` is set, points are computed four at a time in `f64` when
+/// the range and the expression allow it; points that come out undefined or
+/// infinite are evaluated exactly, to tell which they are.
+pub fn sample(
+    expr: &Expr,
+    base_env: &Environment,
+    variable: &str,
+    from: f64,
+    to: f64,
+    samples: usize,
+    exact: bool,
+) -> anyhow::Result<Vec<Point>> {
+    validate_variable_name(variable)?;
+    if !from.is_finite() || !to.is_finite() || from >= to {
+        bail!("Range must be finite with from < to");
+    }
+    if !(2..=MAX_SAMPLES).contains(&samples) {
+        bail!("samples must be between 2 and {}", MAX_SAMPLES);
+    }
+
+    let expr = expr.optimize(base_env.angle_mode())?;
+    let mut env = base_env.clone();
+    let step = (to - from) / (samples - 1) as f64;
+    let xs: Vec<f64> = (0..samples)
+        .map(|i| {
+            if i == samples - 1 {
+                to
+            } else {
+                from + step * i as f64
+            }
+        })
+        .collect();
+    let vectorized = (!exact && from.abs().max(to.abs()) <= simd::SAFE_MAGNITUDE)
+        .then(|| simd::Program::compile(&expr, base_env, variable))
+        .flatten()
+        .map(|program| program.eval(&xs));
+    xs.iter()
+        .enumerate()
+        .map(|(i, &x)| {
+            if let Some(y) = vectorized
+                .as_ref()
+                .map(|ys| ys[i])
+                .filter(|y| y.is_finite())
+            {
+                return Ok(Point { x, y: Some(y) });
+            }
+            env.set(variable, BigDecimal::from_str(&x.to_string())?)?;
+            let y = expr
+                .eval(&env)
+                .ok()
+                .and_then(|y| y.to_f64())
+                .filter(|y| y.is_finite());
+            Ok(Point { x, y })
+        })
+        .collect()
+}
+
+/// Line chart of `points` with axes where zero is in range; gaps where `y` is undefined.
+pub fn render_svg(points: &[Point], width: u32, height: u32) -> String {
+    let (width, height) = (f64::from(width), f64::from(height));
+    let x_min = points.first().map_or(0.0, |p| p.x);
+    let x_max = points.last().map_or(1.0, |p| p.x);
+    let (mut y_min, mut y_max) = points
+        .iter()
+        .filter_map(|p| p.y)
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), y| {
+            (lo.min(y), hi.max(y))
+        });
+    if y_min > y_max {
+        (y_min, y_max) = (-1.0, 1.0);
+    } else if y_min == y_max {
+        (y_min, y_max) = (y_min - 1.0, y_max + 1.0);
+    }
+
+    let plot_w = width - 2.0 * MARGIN;
+    let plot_h = height - 2.0 * MARGIN;
+    let sx = |x: f64| MARGIN + (x - x_min) / (x_max - x_min) * plot_w;
+    let sy = |y: f64| MARGIN + (y_max - y) / (y_max - y_min) * plot_h;
+
+    let mut svg = String::new();
+    let _ = write!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+    );
+    let _ = write!(
+        svg,
+        r##"<rect width="100%" height="100%" fill="#ffffff"/>"##
+    );
+    if x_min <= 0.0 && 0.0 <= x_max {
+        let x = sx(0.0);
+        let _ = write!(
+            svg,
+            r##"<line x1="{x:.2}" y1="{MARGIN}" x2="{x:.2}" y2="{:.2}" stroke="#999999"/>"##,
+            height - MARGIN
+        );
+    }
+    if y_min <= 0.0 && 0.0 <= y_max {
+        let y = sy(0.0);
+        let _ = write!(
+            svg,
+            r##"<line x1="{MARGIN}" y1="{y:.2}" x2="{:.2}" y2="{y:.2}" stroke="#999999"/>"##,
+            width - MARGIN
+        );
+    }
+
+    let mut path = String::new();
+    let mut pen_down = false;
+    for point in points {
+        match point.y {
+            Some(y) => {
+                let command = if pen_down { 'L' } else { 'M' };
+                let _ = write!(path, "{command}{:.2},{:.2} ", sx(point.x), sy(y));
+                pen_down = true;
+            }
+            None => pen_down = false,
+        }
+    }
+    let _ = write!(
+        svg,
+        r##"<path d="{}" fill="none" stroke="#1f77b4" stroke-width="1.5"/>"##,
+        path.trim_end()
+    );
+    svg.push_str("</svg>");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::parse;
+
+    #[test]
+    fn test_sample_marks_undefined_points() {
+        let expr = parse("sin(x) / x").unwrap();
+        for exact in [false, true] {
+            let points = sample(&expr, &Environment::new(), "x", -1.0, 1.0, 5, exact).unwrap();
+            let xs: Vec<f64> = points.iter().map(|p| p.x).collect();
+            assert_eq!(xs, vec![-1.0, -0.5, 0.0, 0.5, 1.0]);
+            assert_eq!(points[2].y, None);
+            assert!((points[4].y.unwrap() - 1f64.sin()).abs() < 1e-12);
+        }
+
+        assert!(sample(&expr, &Environment::new(), "x", 1.0, 1.0, 5, false).is_err());
+        assert!(sample(&expr, &Environment::new(), "pi", 0.0, 1.0, 5, false).is_err());
+        assert!(
+            sample(
+                &expr,
+                &Environment::new(),
+                "x",
+                0.0,
+                1.0,
+                MAX_SAMPLES + 1,
+                false
+            )
+            .is_err()
+        );
+
+        // Too large for the vector path, so undefined points stay exact
+        let points = sample(
+            &parse("1 / (x - 1e20)").unwrap(),
+            &Environment::new(),
+            "x",
+            0.0,
+            1e20,
+            2,
+            false,
+        )
+        .unwrap();
+        assert_eq!(points[1].y, None);
+    }
+
+    #[test]
+    fn test_render_svg_breaks_path_at_gaps() {
+        let points = [
+            Point {
+                x: -1.0,
+                y: Some(-1.0),
+            },
+            Point { x: 0.0, y: None },
+            Point {
+                x: 1.0,
+                y: Some(1.0),
+            },
+        ];
+        let svg = render_svg(&points, 120, 120);
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains(r#"d="M10.00,110.00 M110.00,10.00""#));
+    }
+}
@@ -0,0 +1,297 @@
+//! Vectorized sampling: an optimized expression of the sampled variable is
+//! compiled to a flat stack program over `f64x4` lanes, so each instruction
+//! evaluates four points at once. Only arithmetic, constant integer powers
+//! and the elementary functions compile; anything else leaves the expression
+//! to the exact evaluator. Values agree with the exact ones to the rounding
+//! error of `f64`, which is all a point carries anyway, as long as the
+//! expression does not cancel large terms.
+
+use bigdecimal::BigDecimal;
+use num_traits::ToPrimitive;
+use wide::f64x4;
+
+use crate::evaluator::{AngleMode, Environment, Expr, Function, MathConst, Operator};
+
+/// Largest magnitude of a literal, variable or range bound the program takes:
+/// integers up to it are exact in `f64`.
+pub const SAFE_MAGNITUDE: f64 = 9_007_199_254_740_992.0;
+
+/// Largest constant exponent compiled to repeated multiplication.
+const MAX_POWER: i32 = 64;
+
+const LANES: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Instr {
+    /// The sampled variable
+    X,
+    Const(f64),
+    Neg,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Powi(i32),
+    Sqrt,
+    Abs,
+    Sin,
+    Cos,
+    Tan,
+    Exp,
+    Ln,
+}
+
+#[derive(Debug)]
+pub struct Program {
+    code: Vec<Instr>,
+    /// Deepest the stack gets
+    depth: usize,
+}
+
+impl Program {
+    /// `expr` as a function of `variable`, with the other variables of `env`
+    /// as constants. `None` when any part of it has no vector form.
+    pub fn compile(expr: &Expr, env: &Environment, variable: &str) -> Option<Program> {
+        let mut compiler = Compiler {
+            env,
+            variable,
+            code: Vec::new(),
+            depth: 0,
+            max_depth: 0,
+        };
+        compiler.compile(expr)?;
+        Some(Program {
+            code: compiler.code,
+            depth: compiler.max_depth,
+        })
+    }
+
+    /// The value at each of `xs`; NaN or infinite where `f64` cannot tell,
+    /// such as at `0 / 0`.
+    pub fn eval(&self, xs: &[f64]) -> Vec<f64> {
+        let mut stack: Vec<f64x4> = Vec::with_capacity(self.depth);
+        let mut ys = Vec::with_capacity(xs.len());
+        for chunk in xs.chunks(LANES) {
+            let mut lanes = [chunk[chunk.len() - 1]; LANES];
+            lanes[..chunk.len()].copy_from_slice(chunk);
+            let x = f64x4::from(lanes);
+            stack.clear();
+            for instr in &self.code {
+                let value = match *instr {
+                    Instr::X => x,
+                    Instr::Const(value) => f64x4::splat(value),
+                    Instr::Neg => -pop(&mut stack),
+                    Instr::Add | Instr::Sub | Instr::Mul | Instr::Div => {
+                        let rhs = pop(&mut stack);
+                        let lhs = pop(&mut stack);
+                        match instr {
+                            Instr::Add => lhs + rhs,
+                            Instr::Sub => lhs - rhs,
+                            Instr::Mul => lhs * rhs,
+                            _ => lhs / rhs,
+                        }
+                    }
+                    Instr::Powi(exponent) => powi(pop(&mut stack), exponent),
+                    Instr::Sqrt => pop(&mut stack).sqrt(),
+                    Instr::Abs => pop(&mut stack).abs(),
+                    Instr::Sin => pop(&mut stack).sin_cos().0,
+                    Instr::Cos => pop(&mut stack).sin_cos().1,
+                    Instr::Tan => {
+                        let (sin, cos) = pop(&mut stack).sin_cos();
+                        sin / cos
+                    }
+                    Instr::Exp => pop(&mut stack).exp(),
+                    Instr::Ln => pop(&mut stack).ln(),
+                };
+                stack.push(value);
+            }
+            let lanes = pop(&mut stack).to_array();
+            ys.extend_from_slice(&lanes[..chunk.len()]);
+        }
+        ys
+    }
+}
+
+fn pop(stack: &mut Vec<f64x4>) -> f64x4 {
+    stack.pop().expect("compiled programs are balanced")
+}
+
+/// `base ^ exponent` by squaring; `x ^ 0` is 1 as on the exact path.
+fn powi(base: f64x4, exponent: i32) -> f64x4 {
+    let mut result = f64x4::splat(1.0);
+    let mut square = base;
+    let mut remaining = exponent.unsigned_abs();
+    while remaining > 0 {
+        if remaining & 1 == 1 {
+            result *= square;
+        }
+        square *= square;
+        remaining >>= 1;
+    }
+    if exponent < 0 {
+        f64x4::splat(1.0) / result
+    } else {
+        result
+    }
+}
+
+struct Compiler<'a> {
+    env: &'a Environment,
+    variable: &'a str,
+    code: Vec<Instr>,
+    depth: usize,
+    max_depth: usize,
+}
+
+impl Compiler<'_> {
+    fn emit(&mut self, instr: Instr) {
+        // Every instruction pushes one value after popping its operands
+        let pops = match instr {
+            Instr::X | Instr::Const(_) => 0,
+            Instr::Add | Instr::Sub | Instr::Mul | Instr::Div => 2,
+            _ => 1,
+        };
+        self.depth = self.depth - pops + 1;
+        self.max_depth = self.max_depth.max(self.depth);
+        self.code.push(instr);
+    }
+
+    fn constant(&mut self, value: &BigDecimal) -> Option<()> {
+        let value = value
+            .to_f64()
+            .filter(|value| value.abs() <= SAFE_MAGNITUDE)?;
+        self.emit(Instr::Const(value));
+        Some(())
+    }
+
+    fn compile(&mut self, expr: &Expr) -> Option<()> {
+        match expr {
+            Expr::Number(value) => self.constant(value)?,
+            Expr::Const(math_const) => self.constant(&BigDecimal::from(*math_const))?,
+            Expr::Var(name) if name == self.variable => self.emit(Instr::X),
+            Expr::Var(name) => self.constant(self.env.get(name)?)?,
+            Expr::Unary(op, operand) => {
+                self.compile(operand)?;
+                match op {
+                    Operator::UnarySub => self.emit(Instr::Neg),
+                    Operator::UnaryAdd => {}
+                    Operator::Square => self.emit(Instr::Powi(2)),
+                    Operator::Cube => self.emit(Instr::Powi(3)),
+                    _ => return None,
+                }
+            }
+            Expr::Binary(Operator::Pow, base, exponent) => {
+                let Expr::Number(exponent) = exponent.as_ref() else {
+                    return None;
+                };
+                let exponent = exponent
+                    .is_integer()
+                    .then(|| exponent.to_i32())
+                    .flatten()
+                    .filter(|exponent| exponent.abs() <= MAX_POWER)?;
+                self.compile(base)?;
+                self.emit(Instr::Powi(exponent));
+            }
+            Expr::Binary(op, lhs, rhs) => {
+                let instr = match op {
+                    Operator::Add => Instr::Add,
+                    Operator::Sub => Instr::Sub,
+                    Operator::Mul => Instr::Mul,
+                    Operator::Div => Instr::Div,
+                    _ => return None,
+                };
+                self.compile(lhs)?;
+                self.compile(rhs)?;
+                self.emit(instr);
+            }
+            Expr::Call(func, args) => {
+                let [arg] = args.as_slice() else {
+                    return None;
+                };
+                let instr = match func {
+                    Function::Sqrt => Instr::Sqrt,
+                    Function::Abs => Instr::Abs,
+                    Function::Sin => Instr::Sin,
+                    Function::Cos => Instr::Cos,
+                    Function::Tan => Instr::Tan,
+                    Function::Exp => Instr::Exp,
+                    Function::Ln => Instr::Ln,
+                    _ => return None,
+                };
+                self.compile(arg)?;
+                if matches!(instr, Instr::Sin | Instr::Cos | Instr::Tan)
+                    && self.env.angle_mode() == AngleMode::Degrees
+                {
+                    self.constant(&BigDecimal::from(MathConst::Pi))?;
+                    self.emit(Instr::Mul);
+                    self.emit(Instr::Const(180.0));
+                    self.emit(Instr::Div);
+                }
+                self.emit(instr);
+            }
+        }
+        Some(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::parse;
+
+    fn program(input: &str, env: &Environment) -> Option<Program> {
+        let expr = parse(input).unwrap().optimize(env.angle_mode()).unwrap();
+        Program::compile(&expr, env, "x")
+    }
+
+    #[test]
+    fn test_vector_values_match_exact_ones() {
+        let mut env = Environment::new();
+        env.set("a", "0.75".parse().unwrap()).unwrap();
+        let xs: Vec<f64> = (0..37).map(|i| -4.5 + 0.25 * f64::from(i)).collect();
+        for input in [
+            "3 * x ^ 2 - 2 * x + 1",
+            "sin(x) * cos(a * x) + tan(x / 3)",
+            "exp(-x ^ 2) / sqrt(abs(x) + 1)",
+            "ln(x ^ 2 + 1) - x ^ -2 + pi",
+        ] {
+            let ys = program(input, &env).unwrap().eval(&xs);
+            let expr = parse(input).unwrap();
+            for (x, y) in xs.iter().zip(ys) {
+                let mut env = env.clone();
+                env.set("x", x.to_string().parse().unwrap()).unwrap();
+                let Ok(exact) = expr.eval(&env) else {
+                    assert!(!y.is_finite(), "{input} at {x}: {y}");
+                    continue;
+                };
+                let exact = exact.to_f64().unwrap();
+                assert!(
+                    (y - exact).abs() <= 1e-12 * exact.abs().max(1.0),
+                    "{input} at {x}: {y} vs {exact}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_compiles_only_what_it_can_vectorize() {
+        let env = Environment::new();
+        for input in [
+            "x % 2",
+            "x!",
+            "x ^ x",
+            "x ^ 0.5",
+            "hypot(x, 1)",
+            "x * 1e300",
+            "y + x",
+        ] {
+            assert!(program(input, &env).is_none(), "{input}");
+        }
+
+        let mut degrees = Environment::new();
+        degrees.set_angle_mode(AngleMode::Degrees);
+        let ys = program("sin(x)", &degrees).unwrap().eval(&[30.0, 90.0]);
+        assert!((ys[0] - 0.5).abs() < 1e-15 && (ys[1] - 1.0).abs() < 1e-15);
+        assert!(program("1 / x", &env).unwrap().eval(&[0.0])[0].is_infinite());
+    }
+}
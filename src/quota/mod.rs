@@ -0,0 +1,349 @@
+use crate::app_config::{QuotaLimits, Quotas};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Mutex, RwLock};
+
+const SECS_PER_DAY: u64 = 86_400;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Period {
+    Day,
+    Month,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PeriodUsage {
+    pub period: Period,
+    pub used: u64,
+    /// `None` when the period is unlimited
+    pub limit: Option<u64>,
+    /// Unix seconds at which the counter resets (UTC midnight / first of the month)
+    pub reset_at: u64,
+}
+
+impl PeriodUsage {
+    /// Calls left in the period, `None` when it is unlimited.
+    pub fn remaining(&self) -> Option<u64> {
+        self.limit.map(|limit| limit.saturating_sub(self.used))
+    }
+}
+
+/// Returned when a call would exceed a quota.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct QuotaExceeded {
+    pub caller: String,
+    #[serde(flatten)]
+    pub usage: PeriodUsage,
+}
+
+impl std::fmt::Display for Period {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Period::Day => "daily",
+            Period::Month => "monthly",
+        })
+    }
+}
+
+impl std::fmt::Display for QuotaExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} quota of {} evaluations exceeded; resets at {}",
+            self.usage.period,
+            self.usage.limit.unwrap_or_default(),
+            self.usage.reset_at
+        )
+    }
+}
+
+impl std::error::Error for QuotaExceeded {}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Counter {
+    /// Day number or month number the count belongs to
+    bucket: u64,
+    count: u64,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Usage {
+    day: Counter,
+    month: Counter,
+}
+
+/// In-memory evaluation counters per caller, reset on UTC calendar boundaries.
+pub struct QuotaTracker {
+    config: Quotas,
+    /// Limits set at runtime, e.g. by a tenant store; they win over `config`
+    overrides: RwLock<HashMap<String, QuotaLimits>>,
+    usage: Mutex<HashMap<String, Usage>>,
+}
+
+impl QuotaTracker {
+    pub fn new(config: Quotas) -> Self {
+        QuotaTracker {
+            config,
+            overrides: RwLock::new(HashMap::new()),
+            usage: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Replace the runtime overrides, leaving counters untouched.
+    pub fn set_overrides(&self, overrides: HashMap<String, QuotaLimits>) {
+        *self
+            .overrides
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = overrides;
+    }
+
+    fn limits_for(&self, caller: &str) -> QuotaLimits {
+        let overrides = self
+            .overrides
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        overrides
+            .get(caller)
+            .or_else(|| self.config.overrides.get(caller))
+            .copied()
+            .unwrap_or(self.config.default)
+    }
+
+    /// Count one evaluation for `caller` at `now` (Unix seconds), or reject it
+    /// without counting if any period is exhausted.
+    pub fn try_consume(&self, caller: &str, now: u64) -> Result<(), QuotaExceeded> {
+        let limits = self.limits_for(caller);
+        let mut usage = self.lock();
+        let entry = usage.entry(caller.to_string()).or_default();
+        roll(entry, now);
+
+        for (period, counter, limit) in [
+            (Period::Day, entry.day, limits.daily),
+            (Period::Month, entry.month, limits.monthly),
+        ] {
+            if let Some(limit) = limit
+                && counter.count >= limit
+            {
+                return Err(QuotaExceeded {
+                    caller: caller.to_string(),
+                    usage: PeriodUsage {
+                        period,
+                        used: counter.count,
+                        limit: Some(limit),
+                        reset_at: reset_at(period, now),
+                    },
+                });
+            }
+        }
+
+        entry.day.count += 1;
+        entry.month.count += 1;
+        Ok(())
+    }
+
+    /// Calls counted for `caller` in the current day and month. Only
+    /// [`QuotaTracker::try_consume`] records callers; looking one up does not.
+    pub fn usage(&self, caller: &str, now: u64) -> Vec<PeriodUsage> {
+        let limits = self.limits_for(caller);
+        let mut entry = self.lock().get(caller).copied().unwrap_or_default();
+        roll(&mut entry, now);
+
+        vec![
+            PeriodUsage {
+                period: Period::Day,
+                used: entry.day.count,
+                limit: limits.daily,
+                reset_at: reset_at(Period::Day, now),
+            },
+            PeriodUsage {
+                period: Period::Month,
+                used: entry.month.count,
+                limit: limits.monthly,
+                reset_at: reset_at(Period::Month, now),
+            },
+        ]
+    }
+
+    /// The limited period with the fewest calls left, which rate-limit headers
+    /// report; `None` when `caller` is unlimited.
+    pub fn tightest(&self, caller: &str, now: u64) -> Option<PeriodUsage> {
+        self.usage(caller, now)
+            .into_iter()
+            .filter_map(|usage| Some((usage.remaining()?, usage)))
+            .min_by_key(|(remaining, _)| *remaining)
+            .map(|(_, usage)| usage)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Usage>> {
+        self.usage
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn roll(usage: &mut Usage, now: u64) {
+    let day = now / SECS_PER_DAY;
+    let month = month_index(day);
+    if usage.day.bucket != day {
+        usage.day = Counter {
+            bucket: day,
+            count: 0,
+        };
+    }
+    if usage.month.bucket != month {
+        usage.month = Counter {
+            bucket: month,
+            count: 0,
+        };
+    }
+}
+
+fn reset_at(period: Period, now: u64) -> u64 {
+    let day = now / SECS_PER_DAY;
+    match period {
+        Period::Day => (day + 1) * SECS_PER_DAY,
+        Period::Month => {
+            let (year, month, _) = civil_from_days(day as i64);
+            let (year, month) = if month == 12 {
+                (year + 1, 1)
+            } else {
+                (year, month + 1)
+            };
+            days_from_civil(year, month, 1) as u64 * SECS_PER_DAY
+        }
+    }
+}
+
+fn month_index(day: u64) -> u64 {
+    let (year, month, _) = civil_from_days(day as i64);
+    (year * 12 + month as i64 - 1) as u64
+}
+
+/// Gregorian date for a day count since 1970-01-01 (Howard Hinnant's algorithm).
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+pub fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let month = i64::from(month);
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2026-01-31T12:00:00Z
+    const NOW: u64 = 1_769_860_800;
+
+    fn tracker(daily: Option<u64>, monthly: Option<u64>) -> QuotaTracker {
+        QuotaTracker::new(Quotas {
+            enabled: true,
+            default: QuotaLimits { daily, monthly },
+            overrides: HashMap::from([(
+                "key:vip".to_string(),
+                QuotaLimits {
+                    daily: None,
+                    monthly: None,
+                },
+            )]),
+        })
+    }
+
+    #[test]
+    fn test_calendar_round_trip() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days((NOW / SECS_PER_DAY) as i64), (2026, 1, 31));
+        assert_eq!(days_from_civil(2026, 1, 31), (NOW / SECS_PER_DAY) as i64);
+        assert_eq!(
+            days_from_civil(2024, 3, 1) - days_from_civil(2024, 2, 28),
+            2
+        );
+    }
+
+    #[test]
+    fn test_daily_quota_resets_at_midnight() {
+        let quotas = tracker(Some(2), None);
+        quotas.try_consume("key:a", NOW).unwrap();
+        quotas.try_consume("key:a", NOW).unwrap();
+
+        let err = quotas.try_consume("key:a", NOW).unwrap_err();
+        assert_eq!(err.usage.period, Period::Day);
+        assert_eq!(
+            err.usage.reset_at,
+            days_from_civil(2026, 2, 1) as u64 * SECS_PER_DAY
+        );
+
+        quotas.try_consume("key:b", NOW).unwrap();
+        quotas.try_consume("key:a", err.usage.reset_at).unwrap();
+    }
+
+    #[test]
+    fn test_monthly_quota_and_overrides() {
+        let quotas = tracker(None, Some(1));
+        quotas.try_consume("key:a", NOW).unwrap();
+        let err = quotas
+            .try_consume("key:a", NOW + SECS_PER_DAY / 4)
+            .unwrap_err();
+        assert_eq!(err.usage.period, Period::Month);
+        assert_eq!(
+            err.usage.reset_at,
+            days_from_civil(2026, 2, 1) as u64 * SECS_PER_DAY
+        );
+
+        for _ in 0..5 {
+            quotas.try_consume("key:vip", NOW).unwrap();
+        }
+        assert_eq!(quotas.usage("key:vip", NOW)[1].used, 5);
+    }
+
+    #[test]
+    fn test_tightest_period() {
+        let quotas = tracker(Some(3), Some(10));
+        for _ in 0..2 {
+            quotas.try_consume("key:a", NOW).unwrap();
+        }
+        let tightest = quotas.tightest("key:a", NOW).unwrap();
+        assert_eq!(tightest.period, Period::Day);
+        assert_eq!(tightest.remaining(), Some(1));
+
+        let quotas = tracker(Some(30), Some(2));
+        quotas.try_consume("key:a", NOW).unwrap();
+        let tightest = quotas.tightest("key:a", NOW).unwrap();
+        assert_eq!(
+            (tightest.period, tightest.remaining()),
+            (Period::Month, Some(1))
+        );
+
+        assert_eq!(quotas.tightest("key:vip", NOW), None);
+    }
+
+    #[test]
+    fn test_lookups_do_not_record_callers() {
+        let quotas = tracker(Some(3), None);
+        assert_eq!(quotas.usage("key:new", NOW)[0].used, 0);
+        assert_eq!(
+            quotas.tightest("key:other", NOW).unwrap().remaining(),
+            Some(3)
+        );
+        assert!(quotas.lock().is_empty());
+
+        quotas.try_consume("key:a", NOW).unwrap();
+        assert_eq!(quotas.lock().len(), 1);
+    }
+}
@@ -0,0 +1,46 @@
+use crate::app_config::AppConfig;
+use crate::evaluator::{AngleMode, EvalContext};
+use crate::session::SessionStore;
+use std::io::{self, BufRead, Write};
+
+const HELP: &str = "Enter an expression, `name = expr` to store a variable, `:vars` to list variables, `:deg` or `:rad` to set the angle unit, `:quit` to exit";
+
+/// Interactive read-eval-print loop backed by a single session, so the same
+/// variable limits and storage backend apply as for MCP sessions.
+pub fn run(config: &AppConfig) -> anyhow::Result<()> {
+    let store = SessionStore::from_config(config.sessions.clone())?;
+    let session_id = store.create()?;
+    let limits = config.evaluator.limits();
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+
+    println!("{HELP}");
+    loop {
+        write!(stdout, "> ")?;
+        stdout.flush()?;
+
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line)? == 0 {
+            break;
+        }
+        match line.trim() {
+            "" => continue,
+            ":quit" | ":q" => break,
+            ":help" => println!("{HELP}"),
+            ":deg" => store.set_angle_mode(&session_id, AngleMode::Degrees)?,
+            ":rad" => store.set_angle_mode(&session_id, AngleMode::Radians)?,
+            ":vars" => store.with_session(&session_id, |session| {
+                let mut vars: Vec<_> = session.env.iter().collect();
+                vars.sort_by(|a, b| a.0.cmp(b.0));
+                for (name, value) in vars {
+                    println!("{name} = {value}");
+                }
+            })?,
+            input => match store.evaluate(&session_id, input, &EvalContext::new(limits)) {
+                Ok(value) => println!("{value}"),
+                Err(err) => println!("error: {err}"),
+            },
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,30 @@
+use crate::app_config::{ResultCache, ResultCacheBackend};
+use crate::evaluator::cache::{self, ResultStore};
+use std::sync::Arc;
+
+#[cfg(feature = "sqlite-cache")]
+pub mod sqlite;
+
+/// The store selected by `result_cache`, `None` when caching is off.
+pub fn from_config(config: &ResultCache) -> anyhow::Result<Option<Arc<dyn ResultStore>>> {
+    if !config.enabled {
+        return Ok(None);
+    }
+    match config.backend {
+        #[cfg(feature = "sqlite-cache")]
+        ResultCacheBackend::Sqlite => Ok(Some(Arc::new(sqlite::SqliteResultStore::open(
+            &config.path,
+            config.max_entries,
+        )?))),
+        #[cfg(not(feature = "sqlite-cache"))]
+        ResultCacheBackend::Sqlite => anyhow::bail!(
+            "Result cache backend `Sqlite` is not compiled in; enable its cargo feature"
+        ),
+    }
+}
+
+/// Open the configured store and have the evaluator use it.
+pub fn install(config: &ResultCache) -> anyhow::Result<()> {
+    cache::set_store(from_config(config)?, config.min_cost);
+    Ok(())
+}
@@ -0,0 +1,86 @@
+use crate::evaluator::cache::ResultStore;
+use rusqlite::{Connection, OptionalExtension, params};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub struct SqliteResultStore {
+    connection: Mutex<Connection>,
+    max_entries: usize,
+}
+
+impl SqliteResultStore {
+    pub fn open(path: &str, max_entries: usize) -> anyhow::Result<Self> {
+        let connection = Connection::open(path)?;
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS results (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL,
+                stored_ms INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS results_stored_ms ON results (stored_ms)",
+        )?;
+        Ok(SqliteResultStore {
+            connection: Mutex::new(connection),
+            max_entries,
+        })
+    }
+
+    fn connection(&self) -> std::sync::MutexGuard<'_, Connection> {
+        self.connection
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl ResultStore for SqliteResultStore {
+    fn get(&self, key: &str) -> anyhow::Result<Option<String>> {
+        Ok(self
+            .connection()
+            .query_row(
+                "SELECT value FROM results WHERE key = ?1",
+                params![key],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    fn put(&self, key: &str, value: &str) -> anyhow::Result<()> {
+        let stored_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as i64;
+        let connection = self.connection();
+        connection.execute(
+            "INSERT OR REPLACE INTO results (key, value, stored_ms) VALUES (?1, ?2, ?3)",
+            params![key, value, stored_ms],
+        )?;
+        connection.execute(
+            "DELETE FROM results WHERE key NOT IN
+                (SELECT key FROM results ORDER BY stored_ms DESC, rowid DESC LIMIT ?1)",
+            params![self.max_entries as i64],
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_results_survive_reopening() {
+        let path = std::env::temp_dir().join(format!("results-{}.db", uuid::Uuid::new_v4()));
+        let path = path.to_string_lossy();
+        let store = SqliteResultStore::open(&path, 2).unwrap();
+        assert_eq!(store.get("a").unwrap(), None);
+        store.put("a", "1e0").unwrap();
+        store.put("b", "2e0").unwrap();
+        store.put("c", "3e0").unwrap();
+        drop(store);
+
+        let store = SqliteResultStore::open(&path, 2).unwrap();
+        assert_eq!(store.get("a").unwrap(), None);
+        assert_eq!(store.get("c").unwrap(), Some("3e0".to_string()));
+        std::fs::remove_file(path.as_ref()).unwrap();
+    }
+}
@@ -0,0 +1,54 @@
+use super::Session;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Storage for session state. Implementations must be safe to share between
+/// request handlers; updates are load-modify-save, so concurrent writes to the
+/// same session resolve as last-writer-wins.
+pub trait SessionBackend: Send + Sync {
+    fn load(&self, id: &str) -> anyhow::Result<Option<Session>>;
+    fn save(&self, id: &str, session: &Session) -> anyhow::Result<()>;
+    fn remove(&self, id: &str) -> anyhow::Result<bool>;
+    fn count(&self) -> anyhow::Result<usize>;
+    /// Drop sessions last accessed before `cutoff_ms` (Unix milliseconds).
+    fn evict_before(&self, cutoff_ms: u64) -> anyhow::Result<usize>;
+}
+
+#[derive(Debug, Default)]
+pub struct MemoryBackend {
+    sessions: Mutex<HashMap<String, Session>>,
+}
+
+impl MemoryBackend {
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Session>> {
+        self.sessions
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl SessionBackend for MemoryBackend {
+    fn load(&self, id: &str) -> anyhow::Result<Option<Session>> {
+        Ok(self.lock().get(id).cloned())
+    }
+
+    fn save(&self, id: &str, session: &Session) -> anyhow::Result<()> {
+        self.lock().insert(id.to_string(), session.clone());
+        Ok(())
+    }
+
+    fn remove(&self, id: &str) -> anyhow::Result<bool> {
+        Ok(self.lock().remove(id).is_some())
+    }
+
+    fn count(&self) -> anyhow::Result<usize> {
+        Ok(self.lock().len())
+    }
+
+    fn evict_before(&self, cutoff_ms: u64) -> anyhow::Result<usize> {
+        let mut sessions = self.lock();
+        let before = sessions.len();
+        sessions.retain(|_, session| session.last_access_ms >= cutoff_ms);
+        Ok(before - sessions.len())
+    }
+}
@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// Stable position in the session, kept when older entries are dropped
+    pub index: u64,
+    pub expression: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub timestamp_ms: u64,
+    pub duration_us: u64,
+}
+
+/// Bounded log of evaluations; the oldest entries are dropped past `capacity`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct History {
+    entries: VecDeque<HistoryEntry>,
+    next_index: u64,
+}
+
+impl History {
+    pub fn record(&mut self, mut entry: HistoryEntry, capacity: usize) -> u64 {
+        entry.index = self.next_index;
+        self.next_index += 1;
+        self.entries.push_back(entry);
+        while self.entries.len() > capacity {
+            self.entries.pop_front();
+        }
+        self.next_index - 1
+    }
+
+    pub fn get(&self, index: u64) -> Option<&HistoryEntry> {
+        let first = self.entries.front()?.index;
+        self.entries.get(index.checked_sub(first)? as usize)
+    }
+
+    pub fn entries(&self) -> impl DoubleEndedIterator<Item = &HistoryEntry> {
+        self.entries.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Remove all entries; indexes keep counting so earlier references stay unambiguous.
+    pub fn clear(&mut self) -> usize {
+        let removed = self.entries.len();
+        self.entries.clear();
+        removed
+    }
+}
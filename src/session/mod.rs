@@ -0,0 +1,547 @@
+use crate::app_config::{SessionBackendKind, Sessions};
+use crate::evaluator::{
+    self, ANS, AngleMode, DataUnits, Environment, EvalContext, Evaluation, validate_variable_name,
+};
+use anyhow::{anyhow, bail};
+use bigdecimal::BigDecimal;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+pub mod backend;
+pub mod history;
+#[cfg(feature = "redis-sessions")]
+pub mod redis;
+pub mod saved;
+#[cfg(feature = "sqlite-sessions")]
+pub mod sqlite;
+
+pub use backend::{MemoryBackend, SessionBackend};
+pub use history::{History, HistoryEntry};
+pub use saved::SavedExpression;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    pub env: Environment,
+    /// Unix milliseconds, so persisted sessions keep their age across restarts
+    pub last_access_ms: u64,
+    #[serde(default)]
+    pub history: History,
+    #[serde(default)]
+    pub saved: BTreeMap<String, SavedExpression>,
+    /// Unit of angles for trigonometric functions and coordinate converters
+    #[serde(default)]
+    pub angle_mode: AngleMode,
+    /// Whether `KB`, `MB`... are powers of 1000 or 1024
+    #[serde(default)]
+    pub data_units: DataUnits,
+    /// Tenant that opened the session, when tenants are enabled
+    #[serde(default)]
+    pub tenant: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct SessionMetrics {
+    pub created: AtomicU64,
+    pub expired: AtomicU64,
+    pub rejected: AtomicU64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionMetricsSnapshot {
+    pub active: u64,
+    pub created: u64,
+    pub expired: u64,
+    pub rejected: u64,
+}
+
+/// Per-session variable environments with TTL eviction and size limits,
+/// shared by MCP sessions and the REPL.
+pub struct SessionStore {
+    limits: Sessions,
+    backend: Box<dyn SessionBackend>,
+    metrics: SessionMetrics,
+}
+
+pub fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as u64)
+}
+
+impl SessionStore {
+    /// In-memory store, independent of the configured backend.
+    pub fn new(limits: Sessions) -> Self {
+        Self::with_backend(limits, Box::new(MemoryBackend::default()))
+    }
+
+    pub fn with_backend(limits: Sessions, backend: Box<dyn SessionBackend>) -> Self {
+        SessionStore {
+            limits,
+            backend,
+            metrics: SessionMetrics::default(),
+        }
+    }
+
+    /// Store using the backend selected by `sessions.backend`.
+    pub fn from_config(limits: Sessions) -> anyhow::Result<Self> {
+        let backend: Box<dyn SessionBackend> = match limits.backend {
+            SessionBackendKind::Memory => Box::new(MemoryBackend::default()),
+            #[cfg(feature = "redis-sessions")]
+            SessionBackendKind::Redis => Box::new(redis::RedisBackend::connect(
+                &limits.redis_url,
+                limits.ttl_secs,
+            )?),
+            #[cfg(feature = "sqlite-sessions")]
+            SessionBackendKind::Sqlite => {
+                Box::new(sqlite::SqliteBackend::open(&limits.sqlite_path)?)
+            }
+            #[allow(unreachable_patterns)]
+            other => bail!(
+                "Session backend `{:?}` is not compiled in; enable its cargo feature",
+                other
+            ),
+        };
+        Ok(Self::with_backend(limits, backend))
+    }
+
+    /// Whether MCP clients are given a session on `initialize`.
+    pub fn enabled(&self) -> bool {
+        self.limits.enabled
+    }
+
+    pub fn ttl(&self) -> Duration {
+        Duration::from_secs(self.limits.ttl_secs)
+    }
+
+    fn is_expired(&self, session: &Session, now: u64) -> bool {
+        now.saturating_sub(session.last_access_ms) > self.ttl().as_millis() as u64
+    }
+
+    pub fn create(&self) -> anyhow::Result<String> {
+        self.create_in(None, Environment::new())
+    }
+
+    /// A session owned by `tenant`, starting with the variables of `env`.
+    pub fn create_in(&self, tenant: Option<&str>, env: Environment) -> anyhow::Result<String> {
+        if self.backend.count()? >= self.limits.max_sessions {
+            self.evict_expired();
+        }
+        if self.backend.count()? >= self.limits.max_sessions {
+            self.metrics.rejected.fetch_add(1, Ordering::Relaxed);
+            bail!("Session limit reached ({})", self.limits.max_sessions);
+        }
+
+        let id = Uuid::new_v4().to_string();
+        let session = Session {
+            env,
+            last_access_ms: now_ms(),
+            tenant: tenant.map(str::to_string),
+            ..Session::default()
+        };
+        self.backend.save(&id, &session)?;
+        self.metrics.created.fetch_add(1, Ordering::Relaxed);
+        Ok(id)
+    }
+
+    pub fn remove(&self, id: &str) -> bool {
+        self.backend.remove(id).unwrap_or(false)
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.with_session(id, |_| ()).is_ok()
+    }
+
+    /// Whether `id` is a live session opened by `tenant`.
+    pub fn belongs_to(&self, id: &str, tenant: Option<&str>) -> bool {
+        self.with_session(id, |session| session.tenant.as_deref() == tenant)
+            .unwrap_or(false)
+    }
+
+    /// Run `f` against a live session, refreshing its TTL and persisting changes.
+    pub fn with_session<T>(
+        &self,
+        id: &str,
+        f: impl FnOnce(&mut Session) -> T,
+    ) -> anyhow::Result<T> {
+        let now = now_ms();
+        let mut session = match self.backend.load(id)? {
+            Some(session) if !self.is_expired(&session, now) => session,
+            Some(_) => {
+                self.backend.remove(id)?;
+                self.metrics.expired.fetch_add(1, Ordering::Relaxed);
+                bail!("Session expired: {}", id);
+            }
+            None => bail!("Unknown session: {}", id),
+        };
+
+        session.env.set_angle_mode(session.angle_mode);
+        session.env.set_data_units(session.data_units);
+        let result = f(&mut session);
+        session.last_access_ms = now;
+        self.backend.save(id, &session)?;
+        Ok(result)
+    }
+
+    /// Evaluate `input` in the session and record it in the history. Bindings
+    /// are only committed when they respect the variable-count and value-size limits.
+    pub fn evaluate(&self, id: &str, input: &str, ctx: &EvalContext) -> anyhow::Result<BigDecimal> {
+        Ok(self.evaluate_statements(id, input, ctx)?.value)
+    }
+
+    /// Like [`SessionStore::evaluate`], also reporting the bindings made by
+    /// `;`-separated statements. A failing statement commits none of them.
+    pub fn evaluate_statements(
+        &self,
+        id: &str,
+        input: &str,
+        ctx: &EvalContext,
+    ) -> anyhow::Result<Evaluation> {
+        self.with_session(id, |session| {
+            self.record(session, input, |session| {
+                let mut env = session.env.clone();
+                let evaluation = evaluator::eval_statements(input, &mut env, ctx)?;
+                self.check_limits(&session.env, &env)?;
+                session.env = env;
+                Ok(evaluation)
+            })
+        })?
+    }
+
+    fn record<T: fmt::Display>(
+        &self,
+        session: &mut Session,
+        expression: &str,
+        evaluate: impl FnOnce(&mut Session) -> anyhow::Result<T>,
+    ) -> anyhow::Result<T> {
+        let started = Instant::now();
+        let outcome = evaluate(session);
+        let entry = HistoryEntry {
+            index: 0,
+            expression: expression.to_string(),
+            result: outcome.as_ref().ok().map(ToString::to_string),
+            error: outcome.as_ref().err().map(ToString::to_string),
+            timestamp_ms: now_ms(),
+            duration_us: started.elapsed().as_micros() as u64,
+        };
+        session.history.record(entry, self.limits.max_history);
+        outcome
+    }
+
+    pub fn save_expression(
+        &self,
+        id: &str,
+        name: &str,
+        saved: SavedExpression,
+    ) -> anyhow::Result<()> {
+        validate_variable_name(name)?;
+        self.with_session(id, |session| {
+            if !session.saved.contains_key(name)
+                && session.saved.len() >= self.limits.max_saved_expressions
+            {
+                bail!(
+                    "Too many saved expressions in session (limit {})",
+                    self.limits.max_saved_expressions
+                );
+            }
+            session.saved.insert(name.to_string(), saved);
+            Ok(())
+        })?
+    }
+
+    pub fn saved_expressions(&self, id: &str) -> anyhow::Result<BTreeMap<String, SavedExpression>> {
+        self.with_session(id, |session| session.saved.clone())
+    }
+
+    /// Run a saved expression with positional `args` (each an expression evaluated
+    /// in the session). The result becomes `ans` and is recorded in the history.
+    pub fn run_saved(
+        &self,
+        id: &str,
+        name: &str,
+        args: &[String],
+        ctx: &EvalContext,
+    ) -> anyhow::Result<BigDecimal> {
+        self.with_session(id, |session| {
+            let call = format!("{}({})", name, args.join(", "));
+            self.record(session, &call, |session| {
+                let saved = session
+                    .saved
+                    .get(name)
+                    .ok_or_else(|| anyhow!("No saved expression named `{}`", name))?;
+                let values = args
+                    .iter()
+                    .map(|arg| evaluator::eval_with(arg, &session.env, ctx))
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                let env = saved.bind(&session.env, values)?;
+                let value = evaluator::eval_with(&saved.expression, &env, ctx)?;
+                session.env.set(ANS, value.clone())?;
+                Ok(value)
+            })
+        })?
+    }
+
+    /// Switch the session between radians and degrees for later evaluations.
+    pub fn set_angle_mode(&self, id: &str, mode: AngleMode) -> anyhow::Result<()> {
+        self.with_session(id, |session| session.angle_mode = mode)
+    }
+
+    /// Switch the session between decimal and binary `KB`, `MB`... for later evaluations.
+    pub fn set_data_units(&self, id: &str, units: DataUnits) -> anyhow::Result<()> {
+        self.with_session(id, |session| session.data_units = units)
+    }
+
+    pub fn history(&self, id: &str) -> anyhow::Result<Vec<HistoryEntry>> {
+        self.with_session(id, |session| session.history.entries().cloned().collect())
+    }
+
+    pub fn clear_history(&self, id: &str) -> anyhow::Result<usize> {
+        self.with_session(id, |session| session.history.clear())
+    }
+
+    fn check_limits(&self, before: &Environment, after: &Environment) -> anyhow::Result<()> {
+        let user_vars = after
+            .iter()
+            .filter(|(name, _)| name.as_str() != ANS)
+            .count();
+        if user_vars > self.limits.max_variables {
+            bail!(
+                "Too many variables in session (limit {})",
+                self.limits.max_variables
+            );
+        }
+        for (name, value) in after.iter() {
+            if before.get(name) != Some(value) && value.digits() > self.limits.max_value_digits {
+                bail!(
+                    "Value of `{}` has {} digits, above the session limit of {}",
+                    name,
+                    value.digits(),
+                    self.limits.max_value_digits
+                );
+            }
+        }
+        Ok(())
+    }
+
+    pub fn evict_expired(&self) -> usize {
+        let cutoff = now_ms().saturating_sub(self.ttl().as_millis() as u64);
+        let evicted = self.backend.evict_before(cutoff).unwrap_or(0);
+        self.metrics
+            .expired
+            .fetch_add(evicted as u64, Ordering::Relaxed);
+        evicted
+    }
+
+    pub fn metrics(&self) -> SessionMetricsSnapshot {
+        SessionMetricsSnapshot {
+            active: self.backend.count().unwrap_or(0) as u64,
+            created: self.metrics.created.load(Ordering::Relaxed),
+            expired: self.metrics.expired.load(Ordering::Relaxed),
+            rejected: self.metrics.rejected.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits() -> Sessions {
+        Sessions {
+            max_sessions: 2,
+            max_variables: 2,
+            max_value_digits: 10,
+            ttl_secs: 60,
+            sweep_interval_secs: 60,
+            max_history: 2,
+            max_saved_expressions: 1,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_variables_persist_within_session() {
+        let store = SessionStore::new(limits());
+        let id = store.create().unwrap();
+
+        store
+            .evaluate(&id, "x = 4", &EvalContext::default())
+            .unwrap();
+        assert_eq!(
+            store
+                .evaluate(&id, "x * ans", &EvalContext::default())
+                .unwrap(),
+            BigDecimal::from(16)
+        );
+
+        let other = store.create().unwrap();
+        assert!(
+            store
+                .evaluate(&other, "x", &EvalContext::default())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_angle_mode_persists_within_session() {
+        let store = SessionStore::new(limits());
+        let id = store.create().unwrap();
+        store.set_angle_mode(&id, AngleMode::Degrees).unwrap();
+        assert_eq!(
+            store
+                .evaluate(&id, "cos(180)", &EvalContext::default())
+                .unwrap(),
+            BigDecimal::from(-1)
+        );
+        assert_eq!(
+            store
+                .evaluate(&id, "polar(0, 2)", &EvalContext::default())
+                .unwrap(),
+            BigDecimal::from(2)
+        );
+        let theta = store
+            .evaluate_statements(&id, "polar(0, 2)", &EvalContext::default())
+            .unwrap();
+        assert_eq!(
+            theta.components.unwrap()[1],
+            ("theta", BigDecimal::from(90))
+        );
+
+        let other = store.create().unwrap();
+        assert_eq!(
+            store
+                .evaluate(&other, "cos(0)", &EvalContext::default())
+                .unwrap(),
+            BigDecimal::from(1)
+        );
+        assert_ne!(
+            store
+                .evaluate(&other, "intpart(cos(180))", &EvalContext::default())
+                .unwrap(),
+            BigDecimal::from(-1)
+        );
+    }
+
+    #[test]
+    fn test_data_units_persist_within_session() {
+        let store = SessionStore::new(limits());
+        let id = store.create().unwrap();
+        assert_eq!(
+            store
+                .evaluate(&id, "2 KB", &EvalContext::default())
+                .unwrap(),
+            BigDecimal::from(2000)
+        );
+        store.set_data_units(&id, DataUnits::Binary).unwrap();
+        assert_eq!(
+            store
+                .evaluate(&id, "2 KB", &EvalContext::default())
+                .unwrap(),
+            BigDecimal::from(2048)
+        );
+    }
+
+    #[test]
+    fn test_limits_are_enforced() {
+        let store = SessionStore::new(limits());
+        let id = store.create().unwrap();
+
+        store
+            .evaluate(&id, "a = 1", &EvalContext::default())
+            .unwrap();
+        store
+            .evaluate(&id, "b = 2", &EvalContext::default())
+            .unwrap();
+        assert!(
+            store
+                .evaluate(&id, "c = 3", &EvalContext::default())
+                .is_err()
+        );
+        assert!(
+            store
+                .evaluate(&id, "a = 10^20", &EvalContext::default())
+                .is_err()
+        );
+        assert_eq!(
+            store.evaluate(&id, "a", &EvalContext::default()).unwrap(),
+            BigDecimal::from(1)
+        );
+
+        store.create().unwrap();
+        assert!(store.create().is_err());
+        assert_eq!(store.metrics().rejected, 1);
+    }
+
+    #[test]
+    fn test_expired_sessions_are_evicted() {
+        let store = SessionStore::new(Sessions {
+            ttl_secs: 0,
+            ..limits()
+        });
+        let id = store.create().unwrap();
+        std::thread::sleep(Duration::from_millis(5));
+
+        assert_eq!(store.evict_expired(), 1);
+        assert!(store.evaluate(&id, "1", &EvalContext::default()).is_err());
+        assert_eq!(store.metrics().expired, 1);
+    }
+
+    #[test]
+    fn test_history_records_and_clears() {
+        let store = SessionStore::new(limits());
+        let id = store.create().unwrap();
+
+        store
+            .evaluate(&id, "1 + 1", &EvalContext::default())
+            .unwrap();
+        store
+            .evaluate(&id, "1 / 0", &EvalContext::default())
+            .unwrap_err();
+        store
+            .evaluate(&id, "ans * 3", &EvalContext::default())
+            .unwrap();
+
+        let history = store.history(&id).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].index, 1);
+        assert_eq!(history[0].error.as_deref(), Some("Division by zero"));
+        assert_eq!(history[1].index, 2);
+        assert_eq!(history[1].result.as_deref(), Some("6"));
+
+        assert_eq!(store.clear_history(&id).unwrap(), 2);
+        store.evaluate(&id, "5", &EvalContext::default()).unwrap();
+        assert_eq!(store.history(&id).unwrap()[0].index, 3);
+    }
+
+    #[test]
+    fn test_saved_expressions() {
+        let store = SessionStore::new(limits());
+        let id = store.create().unwrap();
+        store
+            .evaluate(&id, "k = 10", &EvalContext::default())
+            .unwrap();
+
+        let saved = SavedExpression::new("k * x + 1", Some(vec!["x".to_string()])).unwrap();
+        store.save_expression(&id, "line", saved.clone()).unwrap();
+        assert_eq!(
+            store
+                .run_saved(&id, "line", &["2 + 1".to_string()], &EvalContext::default())
+                .unwrap(),
+            BigDecimal::from(31)
+        );
+        assert_eq!(
+            store.evaluate(&id, "ans", &EvalContext::default()).unwrap(),
+            BigDecimal::from(31)
+        );
+
+        assert!(
+            store
+                .run_saved(&id, "missing", &[], &EvalContext::default())
+                .is_err()
+        );
+        assert!(store.save_expression(&id, "other", saved).is_err());
+    }
+}
@@ -0,0 +1,80 @@
+use super::backend::SessionBackend;
+use super::{Session, now_ms};
+use redis::Commands;
+use std::sync::Mutex;
+
+const KEY_PREFIX: &str = "calculator-mcp:session:";
+
+/// Sorted set of session ids scored by when their key expires, so sessions
+/// can be counted without scanning the keyspace.
+const INDEX_KEY: &str = "calculator-mcp:sessions";
+
+/// Sessions stored as JSON strings with a Redis TTL, so idle sessions expire
+/// server-side and every replica sees the same state. Redis does not announce
+/// expiry, so counting first drops the ids past their expiry time from
+/// [`INDEX_KEY`].
+pub struct RedisBackend {
+    connection: Mutex<redis::Connection>,
+    ttl_secs: u64,
+}
+
+impl RedisBackend {
+    pub fn connect(url: &str, ttl_secs: u64) -> anyhow::Result<Self> {
+        let client = redis::Client::open(url)?;
+        Ok(RedisBackend {
+            connection: Mutex::new(client.get_connection()?),
+            ttl_secs,
+        })
+    }
+
+    fn connection(&self) -> std::sync::MutexGuard<'_, redis::Connection> {
+        self.connection
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl SessionBackend for RedisBackend {
+    fn load(&self, id: &str) -> anyhow::Result<Option<Session>> {
+        let raw: Option<String> = self.connection().get(format!("{KEY_PREFIX}{id}"))?;
+        raw.map(|raw| serde_json::from_str(&raw).map_err(Into::into))
+            .transpose()
+    }
+
+    fn save(&self, id: &str, session: &Session) -> anyhow::Result<()> {
+        let raw = serde_json::to_string(session)?;
+        let ttl_secs = self.ttl_secs.max(1);
+        let expires_ms = now_ms().saturating_add(ttl_secs.saturating_mul(1000));
+        let _: () = redis::pipe()
+            .atomic()
+            .set_ex(format!("{KEY_PREFIX}{id}"), raw, ttl_secs)
+            .ignore()
+            .zadd(INDEX_KEY, id, expires_ms)
+            .ignore()
+            .query(&mut *self.connection())?;
+        Ok(())
+    }
+
+    fn remove(&self, id: &str) -> anyhow::Result<bool> {
+        let (removed, _): (usize, usize) = redis::pipe()
+            .atomic()
+            .del(format!("{KEY_PREFIX}{id}"))
+            .zrem(INDEX_KEY, id)
+            .query(&mut *self.connection())?;
+        Ok(removed > 0)
+    }
+
+    fn count(&self) -> anyhow::Result<usize> {
+        let (_, count): (usize, usize) = redis::pipe()
+            .atomic()
+            .zrembyscore(INDEX_KEY, "-inf", now_ms())
+            .zcard(INDEX_KEY)
+            .query(&mut *self.connection())?;
+        Ok(count)
+    }
+
+    fn evict_before(&self, _cutoff_ms: u64) -> anyhow::Result<usize> {
+        // Redis expires keys on its own.
+        Ok(0)
+    }
+}
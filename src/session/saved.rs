@@ -0,0 +1,80 @@
+use crate::evaluator::{self, Environment, validate_variable_name};
+use anyhow::bail;
+use serde::{Deserialize, Serialize};
+
+/// A reusable expression whose `params` are bound on each run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedExpression {
+    pub expression: String,
+    pub params: Vec<String>,
+}
+
+impl SavedExpression {
+    /// Validate `expression`; when `params` is `None` they are inferred from
+    /// the free variables in order of first use.
+    pub fn new(expression: &str, params: Option<Vec<String>>) -> anyhow::Result<Self> {
+        let free = evaluator::free_variables(expression)?;
+        let params = params.unwrap_or(free);
+        for (idx, param) in params.iter().enumerate() {
+            validate_variable_name(param)?;
+            if params[..idx].contains(param) {
+                bail!("Duplicate parameter: {}", param);
+            }
+        }
+        Ok(SavedExpression {
+            expression: expression.to_string(),
+            params,
+        })
+    }
+
+    /// Environment for one run: `base` plus the parameters bound positionally.
+    pub fn bind(
+        &self,
+        base: &Environment,
+        args: Vec<bigdecimal::BigDecimal>,
+    ) -> anyhow::Result<Environment> {
+        if args.len() != self.params.len() {
+            bail!(
+                "Expected {} argument(s) ({}), got {}",
+                self.params.len(),
+                self.params.join(", "),
+                args.len()
+            );
+        }
+        let mut env = base.clone();
+        for (param, value) in self.params.iter().zip(args) {
+            env.set(param, value)?;
+        }
+        Ok(env)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::EvalContext;
+    use bigdecimal::BigDecimal;
+
+    #[test]
+    fn test_infer_and_bind_params() {
+        let saved = SavedExpression::new("a * x + b", None).unwrap();
+        assert_eq!(saved.params, vec!["a", "x", "b"]);
+
+        let env = saved
+            .bind(
+                &Environment::new(),
+                vec![
+                    BigDecimal::from(2),
+                    BigDecimal::from(3),
+                    BigDecimal::from(1),
+                ],
+            )
+            .unwrap();
+        assert_eq!(
+            evaluator::eval_with(&saved.expression, &env, &EvalContext::default()).unwrap(),
+            BigDecimal::from(7)
+        );
+        assert!(saved.bind(&Environment::new(), vec![]).is_err());
+        assert!(SavedExpression::new("x + y", Some(vec!["x".into(), "x".into()])).is_err());
+    }
+}
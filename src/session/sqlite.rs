@@ -0,0 +1,107 @@
+use super::Session;
+use super::backend::SessionBackend;
+use rusqlite::{Connection, OptionalExtension, params};
+use std::sync::Mutex;
+
+/// Sessions persisted in a local SQLite file so they survive restarts.
+pub struct SqliteBackend {
+    connection: Mutex<Connection>,
+}
+
+impl SqliteBackend {
+    pub fn open(path: &str) -> anyhow::Result<Self> {
+        let connection = Connection::open(path)?;
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS sessions (
+                id TEXT PRIMARY KEY,
+                data TEXT NOT NULL,
+                last_access_ms INTEGER NOT NULL
+            )",
+        )?;
+        Ok(SqliteBackend {
+            connection: Mutex::new(connection),
+        })
+    }
+
+    fn connection(&self) -> std::sync::MutexGuard<'_, Connection> {
+        self.connection
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl SessionBackend for SqliteBackend {
+    fn load(&self, id: &str) -> anyhow::Result<Option<Session>> {
+        let raw: Option<String> = self
+            .connection()
+            .query_row("SELECT data FROM sessions WHERE id = ?1", [id], |row| {
+                row.get(0)
+            })
+            .optional()?;
+        raw.map(|raw| serde_json::from_str(&raw).map_err(Into::into))
+            .transpose()
+    }
+
+    fn save(&self, id: &str, session: &Session) -> anyhow::Result<()> {
+        let raw = serde_json::to_string(session)?;
+        self.connection().execute(
+            "INSERT INTO sessions (id, data, last_access_ms) VALUES (?1, ?2, ?3)
+             ON CONFLICT(id) DO UPDATE SET data = excluded.data, last_access_ms = excluded.last_access_ms",
+            params![id, raw, session.last_access_ms as i64],
+        )?;
+        Ok(())
+    }
+
+    fn remove(&self, id: &str) -> anyhow::Result<bool> {
+        Ok(self
+            .connection()
+            .execute("DELETE FROM sessions WHERE id = ?1", [id])?
+            > 0)
+    }
+
+    fn count(&self) -> anyhow::Result<usize> {
+        let count: i64 =
+            self.connection()
+                .query_row("SELECT COUNT(*) FROM sessions", [], |row| row.get(0))?;
+        Ok(count as usize)
+    }
+
+    fn evict_before(&self, cutoff_ms: u64) -> anyhow::Result<usize> {
+        Ok(self.connection().execute(
+            "DELETE FROM sessions WHERE last_access_ms < ?1",
+            [cutoff_ms as i64],
+        )?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::Environment;
+    use bigdecimal::BigDecimal;
+
+    #[test]
+    fn test_sessions_survive_reopen() {
+        let path = std::env::temp_dir().join(format!("calculator-mcp-{}.db", uuid::Uuid::new_v4()));
+        let path = path.to_str().unwrap();
+
+        let mut env = Environment::new();
+        env.set("x", BigDecimal::from(42)).unwrap();
+        let session = Session {
+            env,
+            last_access_ms: 1_000,
+            ..Session::default()
+        };
+        SqliteBackend::open(path)
+            .unwrap()
+            .save("abc", &session)
+            .unwrap();
+
+        let reopened = SqliteBackend::open(path).unwrap();
+        assert_eq!(reopened.load("abc").unwrap(), Some(session));
+        assert_eq!(reopened.evict_before(2_000).unwrap(), 1);
+        assert_eq!(reopened.count().unwrap(), 0);
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,479 @@
+//! Tenants sharing one deployment. Every request is attributed to a tenant,
+//! which owns the sessions it opens, is charged its quotas, gets its
+//! constants bound before evaluation and is counted separately in metrics.
+//! Per-tenant settings come from a [`TenantConfigStore`] and are reloaded
+//! periodically, so tenants can be managed without a redeploy.
+
+#[cfg(feature = "sqlite-tenants")]
+pub mod sqlite;
+pub mod store;
+
+pub use store::TenantConfigStore;
+
+use crate::app_config::{QuotaLimits, TenantSettings, Tenants};
+use crate::evaluator::{self, Environment, Function};
+use anyhow::{Context, bail};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Mutex, RwLock};
+
+/// Tenant of requests that neither present an assigned key nor name one.
+pub const DEFAULT_TENANT: &str = "default";
+pub const MAX_TENANT_LENGTH: usize = 64;
+
+/// Per-tenant counters behind the `tenant` label of `/metrics`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct TenantMetrics {
+    pub tool_calls: u64,
+    pub sessions_created: u64,
+}
+
+/// Settings of one tenant, checked and evaluated.
+#[derive(Debug, Clone, Default)]
+struct Tenant {
+    constants: Environment,
+    functions: Option<Vec<Function>>,
+    quotas: Option<QuotaLimits>,
+}
+
+impl Tenant {
+    fn new(name: &str, settings: &TenantSettings) -> anyhow::Result<Self> {
+        validate(name)?;
+        let mut constants = Environment::new();
+        for (constant, expression) in &settings.constants {
+            let value = evaluator::eval(expression)
+                .with_context(|| format!("Constant `{constant}` of tenant `{name}`"))?;
+            constants.set(constant, value)?;
+        }
+        let functions = settings
+            .functions
+            .as_ref()
+            .map(|functions| {
+                functions
+                    .iter()
+                    .map(|function| {
+                        Function::try_from(function.as_str())
+                            .with_context(|| format!("Function allowlist of tenant `{name}`"))
+                    })
+                    .collect::<anyhow::Result<Vec<_>>>()
+            })
+            .transpose()?;
+        Ok(Tenant {
+            constants,
+            functions,
+            quotas: settings.quotas,
+        })
+    }
+}
+
+pub struct Tenancy {
+    header: String,
+    keys: HashMap<String, String>,
+    store: Box<dyn TenantConfigStore>,
+    tenants: RwLock<HashMap<String, Tenant>>,
+    metrics: Mutex<BTreeMap<String, TenantMetrics>>,
+}
+
+impl Tenancy {
+    /// Validate the tenants keys are assigned to. Settings are empty until
+    /// the first [`Tenancy::reload`].
+    pub fn new(config: &Tenants, store: Box<dyn TenantConfigStore>) -> anyhow::Result<Self> {
+        for tenant in config.keys.values() {
+            validate(tenant)?;
+        }
+        Ok(Tenancy {
+            header: config.header.to_ascii_lowercase(),
+            keys: config.keys.clone(),
+            store,
+            tenants: RwLock::new(HashMap::new()),
+            metrics: Mutex::new(BTreeMap::new()),
+        })
+    }
+
+    /// Tenancy with the store selected by `config.store`, loaded once.
+    pub fn from_config(config: &Tenants) -> anyhow::Result<Option<Self>> {
+        if !config.enabled {
+            return Ok(None);
+        }
+        let tenancy = Tenancy::new(config, store::from_config(config)?)?;
+        tenancy.reload()?;
+        Ok(Some(tenancy))
+    }
+
+    /// Read the settings from the store again and return how many tenants
+    /// they cover. Settings that fail to load or check leave the current ones
+    /// in place. Blocks on the store.
+    pub fn reload(&self) -> anyhow::Result<usize> {
+        let tenants = self
+            .store
+            .load()?
+            .iter()
+            .map(|(name, settings)| Ok((name.clone(), Tenant::new(name, settings)?)))
+            .collect::<anyhow::Result<HashMap<_, _>>>()?;
+        let count = tenants.len();
+        *self
+            .tenants
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = tenants;
+        Ok(count)
+    }
+
+    /// Request header naming the tenant, lower-cased.
+    pub fn header(&self) -> &str {
+        &self.header
+    }
+
+    /// The tenant of a request from `caller` (an API key fingerprint) that
+    /// names `requested` in the tenant header. A key assigned to a tenant
+    /// always acts for it, whatever the header says. Other callers may only
+    /// name a tenant when `trust_header`, as when API keys are off; otherwise
+    /// they belong to the default tenant.
+    pub fn resolve(
+        &self,
+        caller: Option<&str>,
+        requested: Option<&str>,
+        trust_header: bool,
+    ) -> anyhow::Result<String> {
+        if let Some(tenant) = caller.and_then(|caller| self.keys.get(caller)) {
+            return Ok(tenant.clone());
+        }
+        match requested {
+            Some(tenant) if trust_header => {
+                validate(tenant)?;
+                Ok(tenant.to_string())
+            }
+            Some(tenant) if tenant != DEFAULT_TENANT => {
+                bail!("This API key is not assigned to tenant `{tenant}`")
+            }
+            _ => Ok(DEFAULT_TENANT.to_string()),
+        }
+    }
+
+    /// The identity quotas are charged to: the tenant for keys assigned to
+    /// it, so they share one allowance, else the caller's key. Naming a
+    /// tenant in the header never changes whose allowance is used.
+    pub fn quota_subject(&self, caller: Option<&str>) -> Option<String> {
+        let caller = caller?;
+        Some(match self.keys.get(caller) {
+            Some(tenant) => tenant_subject(tenant),
+            None => caller.to_string(),
+        })
+    }
+
+    /// Variables bound for the tenant before any of the caller's own.
+    pub fn constants(&self, tenant: &str) -> Option<Environment> {
+        self.read()
+            .get(tenant)
+            .map(|settings| settings.constants.clone())
+    }
+
+    /// Quota limits set by the store, keyed by quota subject.
+    pub fn quota_overrides(&self) -> HashMap<String, QuotaLimits> {
+        self.read()
+            .iter()
+            .filter_map(|(tenant, settings)| Some((tenant_subject(tenant), settings.quotas?)))
+            .collect()
+    }
+
+    /// Reject tool arguments calling a function outside the tenant's
+    /// allowlist. Every string argument is checked as expressions, statement
+    /// by statement; one that does not tokenize is rejected too, since what
+    /// it would call cannot be told.
+    pub fn check_functions(&self, tenant: &str, arguments: &Value) -> anyhow::Result<()> {
+        let tenants = self.read();
+        let Some(allowed) = tenants
+            .get(tenant)
+            .and_then(|settings| settings.functions.as_ref())
+        else {
+            return Ok(());
+        };
+        let mut pending = vec![arguments];
+        while let Some(value) = pending.pop() {
+            match value {
+                Value::String(input) => {
+                    let functions = functions_called(input).with_context(|| {
+                        format!("Cannot check arguments against the functions enabled for tenant `{tenant}`")
+                    })?;
+                    for function in functions {
+                        check_allowed(tenant, allowed, function)?;
+                    }
+                }
+                Value::Array(values) => pending.extend(values),
+                Value::Object(fields) => pending.extend(fields.values()),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Reject one function outside the tenant's allowlist, as met by a
+    /// tokenizer that never sees the whole expression.
+    pub fn check_function(&self, tenant: &str, function: Function) -> anyhow::Result<()> {
+        match self
+            .read()
+            .get(tenant)
+            .and_then(|settings| settings.functions.as_ref())
+        {
+            Some(allowed) => check_allowed(tenant, allowed, function),
+            None => Ok(()),
+        }
+    }
+
+    pub fn record_tool_call(&self, tenant: &str) {
+        self.lock()
+            .entry(tenant.to_string())
+            .or_default()
+            .tool_calls += 1;
+    }
+
+    pub fn record_session(&self, tenant: &str) {
+        self.lock()
+            .entry(tenant.to_string())
+            .or_default()
+            .sessions_created += 1;
+    }
+
+    pub fn metrics(&self) -> BTreeMap<String, TenantMetrics> {
+        self.lock().clone()
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, HashMap<String, Tenant>> {
+        self.tenants
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, TenantMetrics>> {
+        self.metrics
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// The identity quotas are charged to, by [`Tenancy::quota_subject`] when
+/// tenants are enabled, else the caller's key.
+pub fn quota_subject(tenancy: Option<&Tenancy>, caller: Option<&str>) -> Option<String> {
+    match tenancy {
+        Some(tenancy) => tenancy.quota_subject(caller),
+        None => caller.map(str::to_string),
+    }
+}
+
+/// Functions called anywhere in `input`: in each `;`-separated statement,
+/// after an assignment target, and on either side of a relation such as the
+/// `=` of an equation, none of which the tokenizer accepts itself.
+fn functions_called(input: &str) -> anyhow::Result<Vec<Function>> {
+    let input = evaluator::strip_comments(input)?;
+    let mut functions = Vec::new();
+    for statement in evaluator::split_statements(&input) {
+        let (_, expression) = evaluator::split_assignment(statement);
+        for side in expression.split(['=', '<', '>', '≤', '≥', '≠']) {
+            functions.extend(evaluator::functions_used(side)?);
+        }
+    }
+    Ok(functions)
+}
+
+fn check_allowed(tenant: &str, allowed: &[Function], function: Function) -> anyhow::Result<()> {
+    if !allowed.contains(&function) {
+        bail!("Function `{function}` is not enabled for tenant `{tenant}`");
+    }
+    Ok(())
+}
+
+fn tenant_subject(tenant: &str) -> String {
+    format!("tenant:{tenant}")
+}
+
+fn validate(tenant: &str) -> anyhow::Result<()> {
+    if tenant.is_empty()
+        || tenant.len() > MAX_TENANT_LENGTH
+        || !tenant
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    {
+        bail!(
+            "Invalid tenant `{tenant}`: use 1 to {MAX_TENANT_LENGTH} letters, digits, `-`, `_` or `.`"
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bigdecimal::BigDecimal;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_resolve_and_constants() {
+        let config = Tenants {
+            enabled: true,
+            keys: HashMap::from([("key:a".to_string(), "analytics".to_string())]),
+            settings: HashMap::from([(
+                "analytics".to_string(),
+                TenantSettings {
+                    constants: BTreeMap::from([("vat".to_string(), "1/5".to_string())]),
+                    quotas: Some(QuotaLimits {
+                        daily: Some(10),
+                        monthly: None,
+                    }),
+                    ..Default::default()
+                },
+            )]),
+            ..Default::default()
+        };
+        let tenancy = Tenancy::from_config(&config).unwrap().unwrap();
+
+        assert_eq!(
+            tenancy
+                .resolve(Some("key:a"), Some("billing"), false)
+                .unwrap(),
+            "analytics"
+        );
+        assert_eq!(
+            tenancy
+                .resolve(Some("key:b"), Some("billing"), true)
+                .unwrap(),
+            "billing"
+        );
+        assert_eq!(tenancy.resolve(None, None, false).unwrap(), DEFAULT_TENANT);
+        assert!(tenancy.resolve(None, Some("a b"), true).is_err());
+
+        let constants = tenancy.constants("analytics").unwrap();
+        assert_eq!(constants.get("vat"), Some(&BigDecimal::new(2.into(), 1)));
+        assert!(tenancy.constants("billing").is_none());
+        assert_eq!(
+            tenancy.quota_overrides()["tenant:analytics"].daily,
+            Some(10)
+        );
+
+        assert_eq!(
+            quota_subject(Some(&tenancy), Some("key:a")).as_deref(),
+            Some("tenant:analytics")
+        );
+        assert_eq!(quota_subject(None, Some("key:a")).as_deref(), Some("key:a"));
+    }
+
+    #[test]
+    fn test_header_cannot_choose_tenant_of_unassigned_key() {
+        let config = Tenants {
+            enabled: true,
+            keys: HashMap::from([("key:a".to_string(), "analytics".to_string())]),
+            ..Default::default()
+        };
+        let tenancy = Tenancy::from_config(&config).unwrap().unwrap();
+
+        // With API keys on, another key cannot act for a tenant or invent one
+        for tenant in ["analytics", "fresh-tenant-1"] {
+            let err = tenancy
+                .resolve(Some("key:b"), Some(tenant), false)
+                .unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!("This API key is not assigned to tenant `{tenant}`")
+            );
+        }
+        assert_eq!(
+            tenancy
+                .resolve(Some("key:b"), Some(DEFAULT_TENANT), false)
+                .unwrap(),
+            DEFAULT_TENANT
+        );
+
+        // Its quotas stay its own, so per-key overrides keep applying
+        assert_eq!(
+            tenancy.quota_subject(Some("key:b")).as_deref(),
+            Some("key:b")
+        );
+        assert_eq!(tenancy.quota_subject(None), None);
+    }
+
+    struct Shared(Arc<Mutex<anyhow::Result<store::TenantSettingsMap>>>);
+
+    impl TenantConfigStore for Shared {
+        fn load(&self) -> anyhow::Result<store::TenantSettingsMap> {
+            match &*self.0.lock().unwrap() {
+                Ok(settings) => Ok(settings.clone()),
+                Err(err) => bail!("{err}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_reload_and_function_allowlist() {
+        let settings = |functions: &[&str]| {
+            Ok(HashMap::from([(
+                "analytics".to_string(),
+                TenantSettings {
+                    functions: Some(functions.iter().map(|f| f.to_string()).collect()),
+                    ..Default::default()
+                },
+            )]))
+        };
+        let source = Arc::new(Mutex::new(settings(&["sqrt"])));
+        let tenancy = Tenancy::new(&Tenants::default(), Box::new(Shared(source.clone()))).unwrap();
+        assert!(tenancy.check_functions("analytics", &json!({})).is_ok());
+        assert_eq!(tenancy.reload().unwrap(), 1);
+
+        let call = |expression: &str| json!({ "expression": expression, "steps": [expression] });
+        assert!(
+            tenancy
+                .check_functions("analytics", &call("sqrt(16) + 1"))
+                .is_ok()
+        );
+        assert!(
+            tenancy
+                .check_functions("analytics", &call("sin(0)"))
+                .is_err()
+        );
+        assert!(tenancy.check_functions("billing", &call("sin(0)")).is_ok());
+        assert!(tenancy.check_functions("analytics", &call("sin(")).is_err());
+        for denied in [
+            "x = sin(0)",
+            "1; sin(0)",
+            "y = 2; sqrt(y) + sin(y)",
+            "sin(x) = 1",
+        ] {
+            let err = tenancy
+                .check_functions("analytics", &call(denied))
+                .unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                "Function `sin` is not enabled for tenant `analytics`"
+            );
+        }
+        assert!(
+            tenancy
+                .check_functions("analytics", &call("x = sqrt(4); x >= 1"))
+                .is_ok()
+        );
+        let err = tenancy
+            .check_functions("analytics", &call("1 $ 2"))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Cannot check arguments against the functions enabled for tenant `analytics`"
+        );
+
+        *source.lock().unwrap() = settings(&["sqrt", "sin"]);
+        tenancy.reload().unwrap();
+        assert!(
+            tenancy
+                .check_functions("analytics", &call("sin(0)"))
+                .is_ok()
+        );
+
+        *source.lock().unwrap() = settings(&["no_such_function"]);
+        assert!(tenancy.reload().is_err());
+        *source.lock().unwrap() = Err(anyhow::anyhow!("store unavailable"));
+        assert!(tenancy.reload().is_err());
+        assert!(
+            tenancy
+                .check_functions("analytics", &call("sin(0)"))
+                .is_ok()
+        );
+    }
+}
@@ -0,0 +1,64 @@
+use super::store::{TenantConfigStore, TenantSettingsMap};
+use rusqlite::Connection;
+use std::sync::Mutex;
+
+/// Tenant settings as JSON in a `tenants (name, settings)` table, managed by
+/// whatever tooling the platform team uses.
+pub struct SqliteStore {
+    connection: Mutex<Connection>,
+}
+
+impl SqliteStore {
+    pub fn open(path: &str) -> anyhow::Result<Self> {
+        let connection = Connection::open(path)?;
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS tenants (
+                name TEXT PRIMARY KEY,
+                settings TEXT NOT NULL
+            )",
+        )?;
+        Ok(SqliteStore {
+            connection: Mutex::new(connection),
+        })
+    }
+}
+
+impl TenantConfigStore for SqliteStore {
+    fn load(&self) -> anyhow::Result<TenantSettingsMap> {
+        let connection = self
+            .connection
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut statement = connection.prepare("SELECT name, settings FROM tenants")?;
+        let rows = statement.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+        let mut settings = TenantSettingsMap::new();
+        for row in rows {
+            let (name, raw) = row?;
+            settings.insert(name, serde_json::from_str(&raw)?);
+        }
+        Ok(settings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sqlite_store_lists_tenants() {
+        let store = SqliteStore::open(":memory:").unwrap();
+        store
+            .connection
+            .lock()
+            .unwrap()
+            .execute(
+                "INSERT INTO tenants (name, settings) VALUES ('analytics', ?1)",
+                [r#"{ "quotas": { "daily": 10 } }"#],
+            )
+            .unwrap();
+        let settings = store.load().unwrap();
+        assert_eq!(settings["analytics"].quotas.unwrap().daily, Some(10));
+    }
+}
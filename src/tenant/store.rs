@@ -0,0 +1,142 @@
+use crate::app_config::{TenantSettings, TenantStoreKind, Tenants};
+use anyhow::{Context, bail};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Settings by tenant.
+pub type TenantSettingsMap = HashMap<String, TenantSettings>;
+
+/// Source of per-tenant settings, read again every `tenants.refresh_secs`, so
+/// tenants can be added or changed without a redeploy. `load` may block.
+pub trait TenantConfigStore: Send + Sync {
+    fn load(&self) -> anyhow::Result<TenantSettingsMap>;
+}
+
+/// `tenants.settings` of the configuration file.
+pub struct ConfigStore {
+    settings: TenantSettingsMap,
+}
+
+impl ConfigStore {
+    pub fn new(settings: TenantSettingsMap) -> Self {
+        ConfigStore { settings }
+    }
+}
+
+impl TenantConfigStore for ConfigStore {
+    fn load(&self) -> anyhow::Result<TenantSettingsMap> {
+        Ok(self.settings.clone())
+    }
+}
+
+/// A JSON object of settings by tenant, read from disk on every load.
+pub struct FileStore {
+    path: String,
+}
+
+impl FileStore {
+    pub fn new(path: &str) -> Self {
+        FileStore {
+            path: path.to_string(),
+        }
+    }
+}
+
+impl TenantConfigStore for FileStore {
+    fn load(&self) -> anyhow::Result<TenantSettingsMap> {
+        let content = std::fs::read_to_string(&self.path)
+            .with_context(|| format!("Failed to read tenant settings from {}", self.path))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Invalid tenant settings in {}", self.path))
+    }
+}
+
+/// A JSON object of settings by tenant, fetched with GET from a platform
+/// service.
+pub struct HttpStore {
+    url: String,
+    timeout: Duration,
+}
+
+impl HttpStore {
+    pub fn new(url: &str) -> anyhow::Result<Self> {
+        if url.is_empty() {
+            bail!("The http tenant store needs `tenants.url`");
+        }
+        Ok(HttpStore {
+            url: url.to_string(),
+            timeout: Duration::from_secs(10),
+        })
+    }
+
+    async fn fetch(&self) -> anyhow::Result<TenantSettingsMap> {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let http = reqwest::Client::builder().timeout(self.timeout).build()?;
+        let response = http.get(&self.url).send().await?.error_for_status()?;
+        Ok(response.json().await?)
+    }
+}
+
+impl TenantConfigStore for HttpStore {
+    /// Runs the request on a thread of its own, so it may be called from
+    /// inside or outside an async runtime.
+    fn load(&self) -> anyhow::Result<TenantSettingsMap> {
+        std::thread::scope(|scope| {
+            scope
+                .spawn(|| {
+                    tokio::runtime::Builder::new_current_thread()
+                        .enable_all()
+                        .build()?
+                        .block_on(self.fetch())
+                })
+                .join()
+                .unwrap_or_else(|_| bail!("Tenant settings request panicked"))
+        })
+        .with_context(|| format!("Failed to fetch tenant settings from {}", self.url))
+    }
+}
+
+/// Store selected by `tenants.store`.
+pub fn from_config(config: &Tenants) -> anyhow::Result<Box<dyn TenantConfigStore>> {
+    Ok(match config.store {
+        TenantStoreKind::Config => Box::new(ConfigStore::new(config.settings.clone())),
+        TenantStoreKind::File => Box::new(FileStore::new(&config.path)),
+        TenantStoreKind::Http => Box::new(HttpStore::new(&config.url)?),
+        #[cfg(feature = "sqlite-tenants")]
+        TenantStoreKind::Sqlite => Box::new(super::sqlite::SqliteStore::open(&config.sqlite_path)?),
+        #[allow(unreachable_patterns)]
+        other => bail!(
+            "Tenant store `{:?}` is not compiled in; enable its cargo feature",
+            other
+        ),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_store_reads_on_every_load() {
+        let path = std::env::temp_dir().join(format!("tenants-{}.json", uuid::Uuid::new_v4()));
+        std::fs::write(
+            &path,
+            r#"{ "analytics": { "constants": { "vat": "0.2" } } }"#,
+        )
+        .unwrap();
+        let store = FileStore::new(&path.to_string_lossy());
+        assert_eq!(store.load().unwrap()["analytics"].constants["vat"], "0.2");
+
+        std::fs::write(&path, r#"{ "analytics": { "functions": ["sqrt"] } }"#).unwrap();
+        let settings = store.load().unwrap();
+        assert!(settings["analytics"].constants.is_empty());
+        assert_eq!(
+            settings["analytics"].functions,
+            Some(vec!["sqrt".to_string()])
+        );
+
+        std::fs::write(&path, "not json").unwrap();
+        assert!(store.load().is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+}
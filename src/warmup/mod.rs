@@ -0,0 +1,109 @@
+//! Startup self-test: evaluate the configured canaries and look up the
+//! configured constants before any traffic arrives, so a broken build or a
+//! limit tightened too far fails the deploy instead of the first requests.
+
+use crate::app_config::Warmup;
+use crate::evaluator::{self, Environment, EvalContext, Limits, MathConst, precision};
+use anyhow::{Context, bail};
+use bigdecimal::BigDecimal;
+use serde::Serialize;
+use std::time::Instant;
+
+/// Outcome of a passed warm-up, served by `/ready`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Report {
+    pub canaries: usize,
+    pub constants: usize,
+    pub elapsed_ms: u64,
+}
+
+/// Run every check under `limits`, failing on the first that does not pass.
+pub fn run(config: &Warmup, limits: Limits) -> anyhow::Result<Report> {
+    let started = Instant::now();
+    if !config.enabled {
+        return Ok(Report::default());
+    }
+    for canary in &config.canaries {
+        let context = || format!("Warm-up canary `{}`", canary.expression);
+        let ctx = EvalContext::new(limits);
+        let expected = evaluator::eval_with(&canary.expected, &Environment::new(), &ctx)
+            .with_context(context)?;
+        let actual = evaluator::eval_statements(&canary.expression, &mut Environment::new(), &ctx)
+            .with_context(context)?
+            .value;
+        if actual != expected {
+            bail!("{}: expected {expected}, got {actual}", context());
+        }
+    }
+    for name in &config.constants {
+        let constant = MathConst::try_from(name.as_str())
+            .with_context(|| format!("Warm-up constant `{name}`"))?;
+        let value = evaluator::eval_with(name, &Environment::new(), &EvalContext::new(limits))
+            .with_context(|| format!("Warm-up constant `{name}`"))?;
+        let expected = precision::round_to(BigDecimal::from(constant), limits.precision);
+        if value != expected {
+            bail!("Warm-up constant `{name}` evaluates to {value}");
+        }
+    }
+    Ok(Report {
+        canaries: config.canaries.len(),
+        constants: config.constants.len(),
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_config::Canary;
+
+    #[test]
+    fn test_default_warmup_passes() {
+        let report = run(&Warmup::default(), Limits::DEFAULT).unwrap();
+        assert_eq!((report.canaries, report.constants), (3, 3));
+    }
+
+    #[test]
+    fn test_failing_checks() {
+        let canary = |expression: &str, expected: &str| Warmup {
+            canaries: vec![Canary {
+                expression: expression.to_string(),
+                expected: expected.to_string(),
+            }],
+            constants: Vec::new(),
+            ..Default::default()
+        };
+        assert!(run(&canary("1/3 * 3", "1"), Limits::DEFAULT).is_ok());
+        let err = run(&canary("1 + 1", "3"), Limits::DEFAULT)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("expected 3, got 2"), "{err}");
+        assert!(run(&canary("1 +", "1"), Limits::DEFAULT).is_err());
+
+        let constants = Warmup {
+            canaries: Vec::new(),
+            constants: vec!["no_such_constant".to_string()],
+            ..Default::default()
+        };
+        assert!(run(&constants, Limits::DEFAULT).is_err());
+        assert!(
+            run(
+                &Warmup {
+                    enabled: false,
+                    ..constants
+                },
+                Limits::DEFAULT
+            )
+            .is_ok()
+        );
+
+        // Limits tightened too far fail the deploy
+        let power = canary("2 ^ 100", "1267650600228229401496703205376");
+        assert!(run(&power, Limits::DEFAULT).is_ok());
+        let tight = Limits {
+            max_digits: 10,
+            ..Limits::DEFAULT
+        };
+        assert!(run(&power, tight).is_err());
+    }
+}
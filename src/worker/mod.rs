@@ -0,0 +1,429 @@
+//! Evaluation in child processes. The server starts copies of its own binary
+//! with the hidden `worker` command and talks to each over its standard
+//! streams, one JSON message per line: first the evaluator settings, then one
+//! tool call per line, each answered with its result or error. A worker that
+//! crashes, overruns its deadline or loses its caller is killed, and a fresh
+//! one is started for a later call, so nothing a computation does takes the
+//! server down with it.
+
+use crate::app_config::{self, AppConfig, Sessions, Shadow};
+use crate::evaluator::{CancellationToken, EvalContext, Interrupted, ParseError};
+use crate::logging;
+use crate::mcp::shadow_candidate;
+use crate::mcp::tools::{self, ToolContext};
+use crate::session::SessionStore;
+use anyhow::{Context, anyhow, bail};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+mod sandbox;
+
+/// Command-line argument that starts a worker.
+pub const WORKER_COMMAND: &str = "worker";
+
+/// How often a waiting caller checks whether it was cancelled
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// What a worker reads first.
+#[derive(Debug, Serialize, Deserialize)]
+struct Settings {
+    evaluator: app_config::Evaluator,
+    cpu_secs: u64,
+    memory_mb: u64,
+    cgroup: Option<String>,
+    shadow: Shadow,
+    /// Directives for logging to stderr, which the server shares; `None` for
+    /// no logging
+    log_filter: Option<String>,
+}
+
+impl Settings {
+    /// Confine this process before it takes calls.
+    fn apply(&self) -> anyhow::Result<()> {
+        if let Some(cgroup) = &self.cgroup {
+            sandbox::join_cgroup(Path::new(cgroup))
+                .with_context(|| format!("Failed to join cgroup {cgroup}"))?;
+        }
+        if self.memory_mb > 0 {
+            sandbox::limit_memory(self.memory_mb.saturating_mul(1024 * 1024))
+                .context("Failed to limit worker memory")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Call {
+    tool: String,
+    arguments: Value,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Reply {
+    Ok(Value),
+    Err {
+        message: String,
+        /// Kept so the server can still report where the input is wrong
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        parse_error: Option<ParseError>,
+    },
+}
+
+impl Reply {
+    fn into_result(self) -> anyhow::Result<Value> {
+        match self {
+            Reply::Ok(value) => Ok(value),
+            Reply::Err {
+                message,
+                parse_error: Some(parse_error),
+            } => Err(anyhow::Error::new(parse_error).context(message)),
+            Reply::Err { message, .. } => Err(anyhow!(message)),
+        }
+    }
+}
+
+/// Answer tool calls read from stdin on stdout until stdin closes.
+pub fn serve() -> anyhow::Result<()> {
+    serve_on(std::io::stdin().lock(), std::io::stdout().lock())
+}
+
+fn serve_on(input: impl BufRead, mut output: impl Write) -> anyhow::Result<()> {
+    let mut lines = input.lines();
+    let Some(settings) = lines.next() else {
+        return Ok(());
+    };
+    let settings: Settings = serde_json::from_str(&settings?).context("Invalid worker settings")?;
+    settings.apply()?;
+    if let Some(filter) = &settings.log_filter {
+        logging::init_stderr(filter)?;
+    }
+    let limits = settings.evaluator.limits();
+    let tools = tools::default_tools();
+    // Calls never belong to a session; the store only completes the context
+    let sessions = SessionStore::new(Sessions::default());
+    let shadow = shadow_candidate(&settings.shadow);
+    for line in lines {
+        let call: Call = serde_json::from_str(&line?).context("Invalid worker call")?;
+        if settings.cpu_secs > 0 {
+            sandbox::limit_cpu(settings.cpu_secs).context("Failed to limit worker CPU time")?;
+        }
+        let result = match tools.iter().find(|tool| tool.name() == call.tool) {
+            Some(tool) => {
+                let eval = EvalContext::new(limits);
+                let ctx = ToolContext {
+                    sessions: &sessions,
+                    session_id: None,
+                    shadow: shadow.as_deref(),
+                    constants: None,
+                    eval: &eval,
+                };
+                eval.run(|| tool.call(&ctx, call.arguments))
+            }
+            None => Err(anyhow!("Unknown tool: {}", call.tool)),
+        };
+        let reply = match result {
+            Ok(value) => Reply::Ok(value),
+            Err(err) => Reply::Err {
+                message: err.to_string(),
+                parse_error: err.downcast_ref::<ParseError>().cloned(),
+            },
+        };
+        serde_json::to_writer(&mut output, &reply)?;
+        output.write_all(b"\n")?;
+        output.flush()?;
+    }
+    Ok(())
+}
+
+/// A running child; killed when dropped.
+struct Worker {
+    child: Child,
+    stdin: ChildStdin,
+    /// Lines of stdout, read on a thread of their own so waits can time out
+    replies: Receiver<String>,
+}
+
+impl Worker {
+    fn spawn(program: &Path, settings: &str) -> anyhow::Result<Worker> {
+        let mut child = Command::new(program)
+            .arg(WORKER_COMMAND)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .with_context(|| format!("Failed to start worker {}", program.display()))?;
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+        writeln!(stdin, "{settings}").context("Failed to configure worker")?;
+        let (sender, replies) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Worker {
+            child,
+            stdin,
+            replies,
+        })
+    }
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[derive(Default)]
+struct Idle {
+    workers: Vec<Worker>,
+    /// Workers alive, idle or busy
+    started: usize,
+}
+
+/// Up to `processes` workers, started on demand and reused across calls.
+pub struct WorkerPool {
+    program: PathBuf,
+    /// What each worker starts with
+    settings: Settings,
+    cpu_secs: u64,
+    memory_mb: u64,
+    processes: usize,
+    /// Longest wait for an answer; `None` without an evaluation deadline
+    deadline: Option<Duration>,
+    idle: Mutex<Idle>,
+    available: Condvar,
+    replaced: AtomicU64,
+}
+
+impl WorkerPool {
+    /// Workers running `program`, which must understand [`WORKER_COMMAND`].
+    pub fn new(
+        config: &app_config::Workers,
+        evaluator: &app_config::Evaluator,
+        program: PathBuf,
+    ) -> anyhow::Result<Self> {
+        let mut settings = Settings {
+            evaluator: evaluator.clone(),
+            cpu_secs: config.cpu_secs,
+            memory_mb: config.memory_mb,
+            cgroup: config.cgroup.clone(),
+            shadow: Shadow::default(),
+            log_filter: None,
+        };
+        if !sandbox::SUPPORTED
+            && (settings.cpu_secs > 0 || settings.memory_mb > 0 || settings.cgroup.is_some())
+        {
+            tracing::warn!("Worker CPU, memory and cgroup limits are only applied on Linux");
+            (settings.cpu_secs, settings.memory_mb, settings.cgroup) = (0, 0, None);
+        }
+        Ok(WorkerPool {
+            program,
+            cpu_secs: settings.cpu_secs,
+            memory_mb: settings.memory_mb,
+            settings,
+            processes: config.processes.max(1),
+            deadline: (evaluator.max_eval_ms > 0)
+                .then(|| Duration::from_millis(evaluator.max_eval_ms + config.grace_ms)),
+            idle: Mutex::new(Idle::default()),
+            available: Condvar::new(),
+            replaced: AtomicU64::new(0),
+        })
+    }
+
+    /// A pool running this very executable, when `workers.enabled`.
+    pub fn from_config(config: &AppConfig) -> anyhow::Result<Option<Self>> {
+        if !config.workers.enabled {
+            return Ok(None);
+        }
+        let program = std::env::current_exe().context("Failed to locate the worker executable")?;
+        Ok(Some(
+            Self::new(&config.workers, &config.evaluator, program)?
+                .with_shadow(&config.shadow)
+                .with_log_filter(logging::initial_directives(&config.logging)),
+        ))
+    }
+
+    /// Dry-run the configured candidate evaluator in the workers too.
+    pub fn with_shadow(mut self, shadow: &Shadow) -> Self {
+        self.settings.shadow = shadow.clone();
+        self
+    }
+
+    /// Have workers log to their stderr, e.g. disagreeing shadow evaluations.
+    pub fn with_log_filter(mut self, directives: String) -> Self {
+        self.settings.log_filter = Some(directives);
+        self
+    }
+
+    /// Workers killed after a crash, a deadline or a cancelled call.
+    pub fn replaced(&self) -> u64 {
+        self.replaced.load(Ordering::Relaxed)
+    }
+
+    /// Call `tool` in a worker, blocking until it answers. The tool's errors
+    /// come back as they would in process, parse errors still downcastable.
+    pub fn call(
+        &self,
+        tool: &str,
+        arguments: &Value,
+        cancellation: &CancellationToken,
+    ) -> anyhow::Result<Value> {
+        let mut worker = self.checkout(cancellation)?;
+        match self.exchange(&mut worker, tool, arguments, cancellation) {
+            Ok(reply) => {
+                self.lock().workers.push(worker);
+                self.available.notify_one();
+                reply.into_result()
+            }
+            Err(err) => {
+                drop(worker);
+                self.replaced.fetch_add(1, Ordering::Relaxed);
+                self.lock().started -= 1;
+                self.available.notify_one();
+                Err(err)
+            }
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Idle> {
+        self.idle
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// An idle worker, a new one while fewer than `processes` run, or else
+    /// the next one to finish.
+    fn checkout(&self, cancellation: &CancellationToken) -> anyhow::Result<Worker> {
+        let mut idle = self.lock();
+        loop {
+            if cancellation.is_cancelled() {
+                return Err(Interrupted::Cancelled.into());
+            }
+            if let Some(worker) = idle.workers.pop() {
+                return Ok(worker);
+            }
+            if idle.started < self.processes {
+                idle.started += 1;
+                drop(idle);
+                let settings = serde_json::to_string(&self.settings)?;
+                return Worker::spawn(&self.program, &settings).inspect_err(|_| {
+                    self.lock().started -= 1;
+                    self.available.notify_one();
+                });
+            }
+            idle = self
+                .available
+                .wait_timeout(idle, POLL_INTERVAL)
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .0;
+        }
+    }
+
+    /// Send one call and wait for its reply. Any error leaves the worker in
+    /// an unknown state, so the caller discards it.
+    fn exchange(
+        &self,
+        worker: &mut Worker,
+        tool: &str,
+        arguments: &Value,
+        cancellation: &CancellationToken,
+    ) -> anyhow::Result<Reply> {
+        let call = serde_json::to_string(&Call {
+            tool: tool.to_string(),
+            arguments: arguments.clone(),
+        })?;
+        writeln!(worker.stdin, "{call}").context("Evaluation worker is gone")?;
+        let started = Instant::now();
+        loop {
+            if cancellation.is_cancelled() {
+                return Err(Interrupted::Cancelled.into());
+            }
+            if let Some(deadline) = self.deadline
+                && started.elapsed() >= deadline
+            {
+                return Err(Interrupted::DeadlineExceeded {
+                    limit_ms: deadline.as_millis() as u64,
+                }
+                .into());
+            }
+            match worker.replies.recv_timeout(POLL_INTERVAL) {
+                Ok(line) => return serde_json::from_str(&line).context("Malformed worker reply"),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => {
+                    let status = worker.child.wait()?;
+                    if let Some(exceeded) =
+                        sandbox::exceeded_limit(status, self.cpu_secs, self.memory_mb)
+                    {
+                        bail!(exceeded);
+                    }
+                    bail!("Evaluation worker exited unexpectedly ({status})");
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn replies(calls: &[Value]) -> Vec<anyhow::Result<Value>> {
+        let settings = Settings {
+            evaluator: app_config::Evaluator::default(),
+            cpu_secs: 0,
+            memory_mb: 0,
+            cgroup: None,
+            shadow: Shadow {
+                enabled: true,
+                ..Shadow::default()
+            },
+            log_filter: None,
+        };
+        let mut input = serde_json::to_string(&settings).unwrap();
+        for call in calls {
+            input.push('\n');
+            input.push_str(&call.to_string());
+        }
+        let mut output = Vec::new();
+        serve_on(input.as_bytes(), &mut output).unwrap();
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str::<Reply>(line).unwrap().into_result())
+            .collect()
+    }
+
+    #[test]
+    fn test_worker_answers_calls_in_order() {
+        let call = |tool: &str, expression: &str| json!({ "tool": tool, "arguments": { "expression": expression } });
+        let replies = replies(&[
+            call("evaluate", "6 * 7"),
+            call("evaluate", "1.2.3"),
+            call("nope", "1"),
+        ]);
+        assert_eq!(replies.len(), 3);
+        assert_eq!(replies[0].as_ref().unwrap()["result"], "42");
+        let parse_error = replies[1].as_ref().unwrap_err();
+        assert!(parse_error.downcast_ref::<ParseError>().is_some());
+        assert_eq!(
+            replies[2].as_ref().unwrap_err().to_string(),
+            "Unknown tool: nope"
+        );
+    }
+}
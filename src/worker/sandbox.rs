@@ -0,0 +1,102 @@
+//! Hard limits on worker processes from the operating system, beyond the
+//! evaluator's cooperative checks: rlimits on CPU time and address space,
+//! and optionally a cgroup the operator has put CPU and memory controls on.
+//! Workers apply them to themselves before their first call. Only Linux is
+//! supported; elsewhere configured limits are ignored with a warning.
+
+use std::io;
+use std::path::Path;
+use std::process::ExitStatus;
+
+pub const SUPPORTED: bool = cfg!(target_os = "linux");
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+type Resource = libc::__rlimit_resource_t;
+#[cfg(all(target_os = "linux", not(target_env = "gnu")))]
+type Resource = libc::c_int;
+
+/// Lower the soft limit of `resource` to `value`, keeping the hard limit.
+#[cfg(target_os = "linux")]
+fn set_soft_limit(resource: Resource, value: u64) -> io::Result<()> {
+    let mut limit = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    // SAFETY: `limit` is a valid, writable rlimit
+    if unsafe { libc::getrlimit(resource, &mut limit) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    limit.rlim_cur = value.min(limit.rlim_max);
+    // SAFETY: `limit` is a valid rlimit with the soft limit within the hard one
+    if unsafe { libc::setrlimit(resource, &limit) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Cap the address space of this process, so allocating beyond `bytes`
+/// fails and ends the worker.
+#[cfg(target_os = "linux")]
+pub fn limit_memory(bytes: u64) -> io::Result<()> {
+    set_soft_limit(libc::RLIMIT_AS, bytes)
+}
+
+/// Allow this process `secs` more seconds of CPU time, after which the
+/// kernel ends it with `SIGXCPU`. Called before every call, as the limit
+/// counts the whole life of the process.
+#[cfg(target_os = "linux")]
+pub fn limit_cpu(secs: u64) -> io::Result<()> {
+    // SAFETY: an all-zero rusage is valid and getrusage only writes it
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    // SAFETY: `usage` is a valid, writable rusage
+    if unsafe { libc::getrusage(libc::RUSAGE_SELF, &mut usage) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let micros = |time: libc::timeval| time.tv_sec as u64 * 1_000_000 + time.tv_usec as u64;
+    let used = (micros(usage.ru_utime) + micros(usage.ru_stime)).div_ceil(1_000_000);
+    set_soft_limit(libc::RLIMIT_CPU, used + secs)
+}
+
+/// Move this process into the cgroup v2 directory `path`.
+#[cfg(target_os = "linux")]
+pub fn join_cgroup(path: &Path) -> io::Result<()> {
+    // Writing 0 moves the writing process
+    std::fs::write(path.join("cgroup.procs"), "0")
+}
+
+/// Why a worker that was killed by the kernel stopped, for the limits that
+/// cause it; `None` for other exits.
+#[cfg(target_os = "linux")]
+pub fn exceeded_limit(status: ExitStatus, cpu_secs: u64, memory_mb: u64) -> Option<String> {
+    use std::os::unix::process::ExitStatusExt;
+    match status.signal()? {
+        libc::SIGXCPU if cpu_secs > 0 => Some(format!(
+            "Evaluation exceeded the CPU time limit of {cpu_secs} s"
+        )),
+        // A failed allocation aborts the process
+        libc::SIGABRT if memory_mb > 0 => Some(format!(
+            "Evaluation exceeded the memory limit of {memory_mb} MiB"
+        )),
+        _ => None,
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn limit_memory(_bytes: u64) -> io::Result<()> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn limit_cpu(_secs: u64) -> io::Result<()> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn join_cgroup(_path: &Path) -> io::Result<()> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn exceeded_limit(_status: ExitStatus, _cpu_secs: u64, _memory_mb: u64) -> Option<String> {
+    None
+}
@@ -0,0 +1,174 @@
+//! Golden snapshots of MCP responses over HTTP. A server is started on a free
+//! port and taken through initialize, tools/list and a set of tools/call
+//! requests; the transcript is compared with `tests/snapshots/mcp.json`.
+//!
+//! After an intended change to the wire format, such as a new tool, refresh
+//! the snapshot with `UPDATE_SNAPSHOTS=1 cargo test --test mcp_snapshots` and
+//! review the diff.
+
+use calculator_mcp::app_config::AppConfig;
+use calculator_mcp::http_server::{AppState, mcp_router};
+use calculator_mcp::mcp::protocol::SESSION_ID_HEADER;
+use serde_json::{Value, json};
+use std::path::Path;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+
+const SNAPSHOT: &str = "tests/snapshots/mcp.json";
+
+/// Tools that only exist with optional crate features, left out of the
+/// snapshot so it holds for every feature set.
+const FEATURE_GATED_TOOLS: &[&str] = &["checksum"];
+
+async fn spawn_server() -> String {
+    let state = AppState::new(Arc::new(AppConfig::default())).unwrap();
+    let app = mcp_router(state);
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    format!("http://{addr}/mcp")
+}
+
+struct Transcript {
+    http: reqwest::Client,
+    url: String,
+    session_id: Option<String>,
+    next_id: u64,
+    entries: Vec<Value>,
+}
+
+impl Transcript {
+    /// Send one request and record it with the response body.
+    async fn request(&mut self, method: &str, params: Value) -> Value {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": self.next_id,
+            "method": method,
+            "params": params,
+        });
+        self.next_id += 1;
+        let mut post = self.http.post(&self.url).json(&request);
+        if let Some(session_id) = &self.session_id {
+            post = post.header(SESSION_ID_HEADER, session_id);
+        }
+        let response = post.send().await.unwrap();
+        assert!(
+            response.status().is_success(),
+            "{method}: {}",
+            response.status()
+        );
+        if let Some(session_id) = response.headers().get(SESSION_ID_HEADER) {
+            self.session_id = Some(session_id.to_str().unwrap().to_string());
+        }
+        let body: Value = response.json().await.unwrap();
+        self.entries
+            .push(json!({ "request": request, "response": body }));
+        body
+    }
+
+    async fn call(&mut self, name: &str, arguments: Value) -> Value {
+        self.request(
+            "tools/call",
+            json!({ "name": name, "arguments": arguments }),
+        )
+        .await
+    }
+}
+
+#[tokio::test]
+async fn test_mcp_responses_match_snapshot() {
+    let _ = rustls::crypto::ring::default_provider().install_default();
+    let mut transcript = Transcript {
+        http: reqwest::Client::new(),
+        url: spawn_server().await,
+        session_id: None,
+        next_id: 1,
+        entries: Vec::new(),
+    };
+
+    transcript.request("initialize", json!({})).await;
+    assert!(
+        transcript.session_id.is_some(),
+        "initialize opens a session"
+    );
+    transcript.request("ping", json!({})).await;
+    transcript.request("tools/list", json!({})).await;
+    if let Some(tools) =
+        transcript.entries.last_mut().unwrap()["response"]["result"]["tools"].as_array_mut()
+    {
+        tools.retain(|tool| !FEATURE_GATED_TOOLS.contains(&tool["name"].as_str().unwrap_or("")));
+    }
+
+    transcript
+        .call("evaluate", json!({ "expression": "2 + 3 * 4" }))
+        .await;
+    transcript
+        .call(
+            "evaluate",
+            json!({ "expression": "a = 2; b = 3; a ^ b + 1" }),
+        )
+        .await;
+    transcript
+        .call("evaluate", json!({ "expression": "divmod(17, 5)" }))
+        .await;
+    transcript
+        .call("evaluate", json!({ "expression": "2h 45m + 90s" }))
+        .await;
+    transcript
+        .call(
+            "evaluate",
+            json!({ "expression": "1 / 2", "format": "mathml" }),
+        )
+        .await;
+    transcript
+        .call("evaluate", json!({ "expression": "1.2.3" }))
+        .await;
+    transcript
+        .call("evaluate", json!({ "expression": "1 / 0" }))
+        .await;
+    transcript
+        .call("validate", json!({ "expression": "2 ^ 10" }))
+        .await;
+    transcript
+        .call(
+            "format_number",
+            json!({ "number": "1234567.891", "locale": "de" }),
+        )
+        .await;
+    transcript
+        .call("derivative", json!({ "expression": "x^2 * sin(x)" }))
+        .await;
+    transcript
+        .call("solve", json!({ "equation": "x^2 - 4 > 0" }))
+        .await;
+    transcript
+        .call(
+            "matrix",
+            json!({ "operation": "determinant", "matrix": [[1, 2], [3, 4]] }),
+        )
+        .await;
+    transcript.call("no_such_tool", json!({})).await;
+    transcript.request("no/such/method", json!({})).await;
+
+    let actual = serde_json::to_string_pretty(&transcript.entries).unwrap() + "\n";
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(SNAPSHOT);
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        std::fs::write(&path, &actual).unwrap();
+        return;
+    }
+    let expected = std::fs::read_to_string(&path).unwrap_or_default();
+    if actual != expected {
+        let line = actual
+            .lines()
+            .zip(expected.lines())
+            .position(|(a, b)| a != b)
+            .unwrap_or_else(|| actual.lines().count().min(expected.lines().count()));
+        panic!(
+            "MCP responses differ from {SNAPSHOT} from line {}:\n  expected: {}\n  actual:   {}\n\
+             If the change is intended, rerun with UPDATE_SNAPSHOTS=1 and review the diff.",
+            line + 1,
+            expected.lines().nth(line).unwrap_or("<end of file>"),
+            actual.lines().nth(line).unwrap_or("<end of file>"),
+        );
+    }
+}
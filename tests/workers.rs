@@ -0,0 +1,110 @@
+//! Tool calls evaluated in worker processes of the real binary.
+
+use calculator_mcp::app_config::{Evaluator, Shadow, Workers};
+use calculator_mcp::evaluator::CancellationToken;
+use calculator_mcp::mcp::protocol::JsonRpcRequest;
+use calculator_mcp::mcp::{McpServer, RequestMeta};
+use calculator_mcp::session::SessionStore;
+use calculator_mcp::worker::WorkerPool;
+use serde_json::json;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+fn pool(processes: usize) -> WorkerPool {
+    let workers = Workers {
+        enabled: true,
+        processes,
+        ..Workers::default()
+    };
+    let program = PathBuf::from(env!("CARGO_BIN_EXE_calculator-mcp"));
+    WorkerPool::new(&workers, &Evaluator::default(), program).unwrap()
+}
+
+#[test]
+fn test_calls_are_answered_by_workers() {
+    let pool = pool(2);
+    let token = CancellationToken::new();
+    let arguments = json!({ "expression": "6 * 7" });
+    for _ in 0..4 {
+        let result = pool.call("evaluate", &arguments, &token).unwrap();
+        assert_eq!(result["result"], "42");
+    }
+    assert_eq!(pool.replaced(), 0);
+
+    // A cancelled call costs its worker; the next call gets a fresh one
+    let cancelled = CancellationToken::new();
+    cancelled.cancel();
+    let err = pool.call("evaluate", &arguments, &cancelled).unwrap_err();
+    assert_eq!(err.to_string(), "Evaluation was cancelled");
+    let result = pool.call("evaluate", &arguments, &token).unwrap();
+    assert_eq!(result["result"], "42");
+}
+
+#[test]
+fn test_server_forwards_sessionless_calls() {
+    // Workers dry-run the shadow candidate as the server would
+    let shadow = Shadow {
+        enabled: true,
+        ..Shadow::default()
+    };
+    let server = McpServer::with_default_tools(Arc::new(SessionStore::new(Default::default())))
+        .with_workers(Some(Arc::new(pool(1).with_shadow(&shadow))));
+    let call = |expression: &str| {
+        let request = JsonRpcRequest::new(
+            1,
+            "tools/call",
+            Some(json!({ "name": "evaluate", "arguments": { "expression": expression } })),
+        );
+        let response = server
+            .handle(request, &RequestMeta::default())
+            .response
+            .unwrap();
+        serde_json::to_value(response).unwrap()["result"].clone()
+    };
+
+    assert_eq!(call("6 * 7")["structuredContent"]["result"], "42");
+    let malformed = call("1.2.3");
+    assert_eq!(malformed["isError"], true);
+    assert_eq!(
+        malformed["structuredContent"]["error"]["span"],
+        json!({ "start": 0, "end": 5 })
+    );
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_cpu_limit_ends_runaway_worker() {
+    let workers = Workers {
+        enabled: true,
+        processes: 1,
+        cpu_secs: 1,
+        ..Workers::default()
+    };
+    // Nothing cooperative stops this evaluation; only the kernel does
+    let evaluator = Evaluator {
+        precision: 100_000,
+        max_cost: u64::MAX,
+        max_eval_ms: 0,
+        max_memory_mb: 0,
+        ..Evaluator::default()
+    };
+    let program = PathBuf::from(env!("CARGO_BIN_EXE_calculator-mcp"));
+    let pool = WorkerPool::new(&workers, &evaluator, program).unwrap();
+    let token = CancellationToken::new();
+    let err = pool
+        .call(
+            "evaluate",
+            &json!({ "expression": "exp(1.5) + ln(3)" }),
+            &token,
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Evaluation exceeded the CPU time limit of 1 s"
+    );
+    assert_eq!(pool.replaced(), 1);
+    let result = pool
+        .call("evaluate", &json!({ "expression": "6 * 7" }), &token)
+        .unwrap();
+    assert_eq!(result["result"], "42");
+}